@@ -0,0 +1,50 @@
+use std::process::Command;
+
+fn main() {
+    emit_build_info();
+
+    // gRPC stubs are only generated when the `with-grpc` feature is enabled
+    #[cfg(feature = "with-grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/acropolis.proto");
+        tonic_build::compile_protos("proto/acropolis.proto")
+            .expect("Failed to compile gRPC protos");
+    }
+}
+
+/// Expose the git commit, build time and compiler version to `build_info`.
+/// Each falls back to "unknown" when it can't be determined, e.g. when
+/// building from a source tarball without git.
+fn emit_build_info() {
+    let git_commit = command_output("git", &["rev-parse", "HEAD"]);
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = command_output(&rustc, &["--version"]);
+    let build_timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs().to_string())
+        .unwrap_or_else(|_| "0".to_string());
+
+    println!("cargo:rustc-env=AEP_GIT_COMMIT={}", git_commit);
+    println!("cargo:rustc-env=AEP_RUSTC_VERSION={}", rustc_version);
+    println!("cargo:rustc-env=AEP_BUILD_TIMESTAMP={}", build_timestamp);
+
+    // Pick up new commits without rebuilding on every source change
+    for path in ["../.git/HEAD", "../.git/refs/heads"] {
+        if std::path::Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+}
+
+fn command_output(program: &str, args: &[&str]) -> String {
+    Command::new(program)
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|stdout| stdout.trim().to_string())
+        .filter(|stdout| !stdout.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
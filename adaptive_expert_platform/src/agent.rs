@@ -1,17 +1,22 @@
-use crate::{memory::Memory, settings::Settings};
+use crate::{memory::{Memory, ReadOnlyMemory}, settings::Settings};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::Arc;
 use tokio::process::Command;
-use tracing::{info, warn, error, instrument};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn, instrument};
 use blake3::Hasher;
 
 #[cfg(feature = "with-llama")]
 use llama_cpp::{standard_sampler, LlamaModel, LlamaParams, SessionParams};
+#[cfg(feature = "with-llama")]
+use crate::prompt::PromptTemplates;
 
 /// Enhanced Agent trait with better error handling and metadata
 #[async_trait]
@@ -21,6 +26,147 @@ pub trait Agent: Send + Sync {
     fn capabilities(&self) -> Vec<String>;
     async fn handle(&self, input: serde_json::Value, memory: Arc<Memory>) -> Result<String>;
     async fn health_check(&self) -> Result<AgentHealth>;
+
+    /// Run as a memory's embedding or reranker model. The memory is only
+    /// readable here, so a model cannot call back into it; agents that serve
+    /// as models override this, and the default refuses.
+    async fn handle_model(&self, _input: serde_json::Value, _memory: ReadOnlyMemory<'_>) -> Result<String> {
+        Err(anyhow!("Agent '{}' cannot serve as an embedding or rerank model", self.name()))
+    }
+
+    /// Handle a request that the caller may cancel.
+    ///
+    /// The default races `handle` against the token and drops the in-flight
+    /// work when cancelled (which kills any child spawned with `kill_on_drop`).
+    /// Agents with a cooperative cancellation point should override this.
+    async fn handle_cancellable(
+        &self,
+        input: serde_json::Value,
+        memory: Arc<Memory>,
+        cancel: CancellationToken,
+    ) -> Result<String> {
+        tokio::select! {
+            result = self.handle(input, memory) => result,
+            _ = cancel.cancelled() => Err(CancelledError.into()),
+        }
+    }
+
+    /// Produce output incrementally, sending each chunk to `chunks` as it is
+    /// ready. The default sends the whole `handle` result as a single chunk.
+    async fn handle_stream(
+        &self,
+        input: serde_json::Value,
+        memory: Arc<Memory>,
+        chunks: mpsc::Sender<String>,
+    ) -> Result<()> {
+        let output = self.handle(input, memory).await?;
+        // A closed receiver means the caller went away
+        chunks.send(output).await.map_err(|_| CancelledError)?;
+        Ok(())
+    }
+
+    /// Set up resources tied to registration, such as connection pools or
+    /// metrics. Called before the agent becomes callable under `ctx.name`;
+    /// an error fails the registration with [`AgentRegistrationError`] and
+    /// leaves any agent already registered under that name in place.
+    async fn on_register(&self, _ctx: &AgentContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Release what `on_register` set up. Called once the agent is replaced
+    /// by a new registration, or removed and its in-flight calls cancelled;
+    /// errors are logged.
+    async fn on_unregister(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Eagerly initialize expensive resources (models, connections) so the
+    /// first request does not pay for them. Called after registration.
+    async fn warm_up(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Whether identical inputs always produce the same output without side
+    /// effects, letting the orchestrator serve repeated calls from its result
+    /// cache. Only agents that are pure transforms should return true.
+    fn cacheable(&self) -> bool {
+        false
+    }
+
+    /// JSON Schema describing accepted input, published by `/agents/schema`
+    fn input_schema(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// JSON Schema that successful output, parsed as JSON, must match. The
+    /// orchestrator checks outputs against it and warns about or fails
+    /// mismatches as `orchestrator.output_validation` says; see
+    /// [`crate::output_schema`].
+    fn output_schema(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// What handling `input` is expected to cost, checked against the
+    /// caller's budget before the call is admitted. `None` means the agent
+    /// cannot tell in advance, and its calls are not metered.
+    fn cost_estimate(&self, _input: &serde_json::Value) -> Option<Cost> {
+        None
+    }
+
+    /// What handling `input` actually cost once it produced `output`. `None`
+    /// charges the estimate instead.
+    fn actual_cost(&self, _input: &serde_json::Value, _output: &str) -> Option<Cost> {
+        None
+    }
+
+    /// Load the model at `path` and swap it in, letting requests already
+    /// running finish on the previous model. Agents without a model reject
+    /// the call with [`InvalidInputError`].
+    async fn reload_model(&self, _path: &Path) -> Result<ModelReloadReport> {
+        Err(InvalidInputError(format!("Agent '{}' does not support model reload", self.name())).into())
+    }
+}
+
+/// Price of one agent call, as estimated up front or measured afterwards
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Cost {
+    /// Model tokens consumed, prompt and completion together
+    pub tokens: u64,
+    /// Units that user budgets are charged in
+    pub compute_units: f64,
+}
+
+impl Cost {
+    /// A cost charged at one compute unit per token
+    pub fn from_tokens(tokens: u64) -> Self {
+        Self { tokens, compute_units: tokens as f64 }
+    }
+}
+
+/// Rough token count for `text` when no tokenizer is at hand, at about four
+/// bytes per token for English prose
+pub fn approximate_tokens(text: &str) -> u64 {
+    (text.len() as u64).div_ceil(4)
+}
+
+/// Outcome of a successful [`Agent::reload_model`]
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelReloadReport {
+    pub previous_path: PathBuf,
+    pub path: PathBuf,
+    pub load_time_ms: u64,
+    /// Requests still running on the previous model when it was swapped out
+    pub draining_requests: usize,
+}
+
+/// Machine-readable description of a registered agent
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentSchema {
+    pub name: String,
+    pub agent_type: String,
+    pub capabilities: Vec<String>,
+    pub input_schema: Option<serde_json::Value>,
+    pub output_schema: Option<serde_json::Value>,
 }
 
 /// Agent health information
@@ -48,6 +194,162 @@ impl Default for AgentHealth {
     }
 }
 
+/// Extract a text input that may be given either as a bare JSON string or as
+/// an object carrying the text under `field_name`.
+///
+/// Agents accepting free-form text should use this so clients get the same
+/// error shape regardless of which agent they hit.
+pub fn extract_text_input(value: &serde_json::Value, field_name: &str) -> Result<String> {
+    let invalid_format = || InvalidInputError(format!(
+        "Invalid input format. Expected a string or an object with a '{}' field",
+        field_name
+    ));
+
+    match value {
+        serde_json::Value::String(s) => Ok(s.clone()),
+        serde_json::Value::Object(obj) => match obj.get(field_name) {
+            Some(serde_json::Value::String(s)) => Ok(s.clone()),
+            Some(_) => Err(InvalidInputError(format!("Field '{}' must be a string", field_name)).into()),
+            None => Err(invalid_format().into()),
+        },
+        _ => Err(invalid_format().into()),
+    }
+}
+
+/// Error for input an agent rejects as invalid. Retrying the same input on
+/// another agent would fail the same way, so fallback chains stop on it.
+#[derive(Debug)]
+pub struct InvalidInputError(pub String);
+
+impl std::fmt::Display for InvalidInputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for InvalidInputError {}
+
+tokio::task_local! {
+    static CALLER_TENANT: Option<String>;
+}
+
+/// Run an agent call on behalf of memory `tenant` (see [`caller_tenant`])
+pub async fn with_caller_tenant<F: std::future::Future>(tenant: Option<String>, call: F) -> F::Output {
+    CALLER_TENANT.scope(tenant, call).await
+}
+
+/// Memory tenant of the caller the current agent call is made for. `None`
+/// outside an orchestrator call, or when the caller may see every fragment.
+pub fn caller_tenant() -> Option<String> {
+    CALLER_TENANT.try_with(Clone::clone).ok().flatten()
+}
+
+/// Up to `top_k` fragments relevant to `query` that the current caller may
+/// see, for agents that add retrieved context to their prompt
+pub async fn caller_memory_context(memory: &Memory, query: &str, top_k: usize) -> Vec<String> {
+    let tenant = caller_tenant();
+    memory
+        .search_memory_with_tenant(
+            query,
+            top_k,
+            None,
+            crate::memory::SearchStrategy::Rerank,
+            memory.recency_half_life(),
+            tenant.as_deref(),
+        )
+        .await
+        .unwrap_or_default()
+}
+
+/// Shared resources handed to [`Agent::on_register`]
+#[derive(Clone)]
+pub struct AgentContext {
+    /// Name the agent is being registered under
+    pub name: String,
+    pub memory: Arc<Memory>,
+    pub monitoring: Arc<crate::monitoring::MonitoringSystem>,
+}
+
+/// Error returned when an agent's [`Agent::on_register`] hook fails
+#[derive(Debug)]
+pub struct AgentRegistrationError {
+    pub agent: String,
+    pub source: anyhow::Error,
+}
+
+impl std::fmt::Display for AgentRegistrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Agent '{}' failed to register: {:#}", self.agent, self.source)
+    }
+}
+
+impl std::error::Error for AgentRegistrationError {}
+
+/// Error returned when the caller cancelled a task before it completed
+#[derive(Debug)]
+pub struct CancelledError;
+
+impl std::fmt::Display for CancelledError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Task was cancelled")
+    }
+}
+
+impl std::error::Error for CancelledError {}
+
+/// Error returned when an agent does not finish within the task timeout
+#[derive(Debug)]
+pub struct AgentTimeoutError;
+
+impl std::fmt::Display for AgentTimeoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Agent execution timed out")
+    }
+}
+
+impl std::error::Error for AgentTimeoutError {}
+
+/// Error returned when no agent is registered under the requested name
+#[derive(Debug)]
+pub struct UnknownAgentError(pub String);
+
+impl std::fmt::Display for UnknownAgentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown agent '{}'", self.0)
+    }
+}
+
+impl std::error::Error for UnknownAgentError {}
+
+/// Error returned when registering under a name another agent already holds
+#[derive(Debug)]
+pub struct DuplicateAgentError(pub String);
+
+impl std::fmt::Display for DuplicateAgentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Agent '{}' is already registered", self.0)
+    }
+}
+
+impl std::error::Error for DuplicateAgentError {}
+
+/// Error returned when a registered agent has been disabled for maintenance
+#[derive(Debug)]
+pub struct AgentMaintenanceError(pub String);
+
+impl std::fmt::Display for AgentMaintenanceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Agent '{}' is in maintenance", self.0)
+    }
+}
+
+impl std::error::Error for AgentMaintenanceError {}
+
+/// Whether a failed agent call may succeed if retried on another agent
+pub fn is_retryable(error: &anyhow::Error) -> bool {
+    !crate::error::chain_any(error, |e| e.is::<InvalidInputError>() || e.is::<CancelledError>())
+}
+
 // --- Built-in Agents ---
 
 /// Simple echo agent for testing
@@ -123,13 +425,48 @@ impl HashEmbeddingAgent {
 impl Agent for HashEmbeddingAgent {
     fn name(&self) -> &str { "hash_embedding" }
 
+    fn cacheable(&self) -> bool { true }
+
     fn agent_type(&self) -> &str { "embedding" }
 
     fn capabilities(&self) -> Vec<String> {
         vec!["embedding".to_string()]
     }
 
+    fn input_schema(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!({
+            "type": "object",
+            "properties": { "text": { "type": "string" } },
+            "required": ["text"]
+        }))
+    }
+
     async fn handle(&self, input: serde_json::Value, _memory: Arc<Memory>) -> Result<String> {
+        self.embed(&input)
+    }
+
+    async fn handle_model(&self, input: serde_json::Value, _memory: ReadOnlyMemory<'_>) -> Result<String> {
+        self.embed(&input)
+    }
+
+    async fn health_check(&self) -> Result<AgentHealth> {
+        Ok(AgentHealth {
+            status: "healthy".to_string(),
+            details: None,
+            uptime_seconds: self.start_time.elapsed().as_secs(),
+            total_requests: self
+                .request_count
+                .load(std::sync::atomic::Ordering::Relaxed),
+            error_count: self
+                .error_count
+                .load(std::sync::atomic::Ordering::Relaxed),
+            average_response_time_ms: 1.0,
+        })
+    }
+}
+
+impl HashEmbeddingAgent {
+    fn embed(&self, input: &serde_json::Value) -> Result<String> {
         self.request_count
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
 
@@ -165,21 +502,6 @@ impl Agent for HashEmbeddingAgent {
 
         Ok(serde_json::to_string(&embedding)?)
     }
-
-    async fn health_check(&self) -> Result<AgentHealth> {
-        Ok(AgentHealth {
-            status: "healthy".to_string(),
-            details: None,
-            uptime_seconds: self.start_time.elapsed().as_secs(),
-            total_requests: self
-                .request_count
-                .load(std::sync::atomic::Ordering::Relaxed),
-            error_count: self
-                .error_count
-                .load(std::sync::atomic::Ordering::Relaxed),
-            average_response_time_ms: 1.0,
-        })
-    }
 }
 
 /// Simple reranking agent that orders candidates by length similarity to the query
@@ -203,13 +525,51 @@ impl LengthRerankAgent {
 impl Agent for LengthRerankAgent {
     fn name(&self) -> &str { "length_rerank" }
 
+    fn cacheable(&self) -> bool { true }
+
     fn agent_type(&self) -> &str { "rerank" }
 
     fn capabilities(&self) -> Vec<String> {
         vec!["rerank".to_string()]
     }
 
+    fn input_schema(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string" },
+                "candidates": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["query", "candidates"]
+        }))
+    }
+
     async fn handle(&self, input: serde_json::Value, _memory: Arc<Memory>) -> Result<String> {
+        self.rerank(&input)
+    }
+
+    async fn handle_model(&self, input: serde_json::Value, _memory: ReadOnlyMemory<'_>) -> Result<String> {
+        self.rerank(&input)
+    }
+
+    async fn health_check(&self) -> Result<AgentHealth> {
+        Ok(AgentHealth {
+            status: "healthy".to_string(),
+            details: None,
+            uptime_seconds: self.start_time.elapsed().as_secs(),
+            total_requests: self
+                .request_count
+                .load(std::sync::atomic::Ordering::Relaxed),
+            error_count: self
+                .error_count
+                .load(std::sync::atomic::Ordering::Relaxed),
+            average_response_time_ms: 1.0,
+        })
+    }
+}
+
+impl LengthRerankAgent {
+    fn rerank(&self, input: &serde_json::Value) -> Result<String> {
         self.request_count
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
 
@@ -243,21 +603,6 @@ impl Agent for LengthRerankAgent {
 
         Ok(serde_json::to_string(&cand_strings)?)
     }
-
-    async fn health_check(&self) -> Result<AgentHealth> {
-        Ok(AgentHealth {
-            status: "healthy".to_string(),
-            details: None,
-            uptime_seconds: self.start_time.elapsed().as_secs(),
-            total_requests: self
-                .request_count
-                .load(std::sync::atomic::Ordering::Relaxed),
-            error_count: self
-                .error_count
-                .load(std::sync::atomic::Ordering::Relaxed),
-            average_response_time_ms: 1.0,
-        })
-    }
 }
 
 /// Enhanced Python tool agent with better security
@@ -265,9 +610,56 @@ pub struct PythonToolAgent {
     request_count: std::sync::atomic::AtomicU64,
     error_count: std::sync::atomic::AtomicU64,
     start_time: std::time::Instant,
-    allowed_directories: Vec<String>,
+    allowed_directories: Vec<PathBuf>,
     script_allowlist_hashes: HashMap<String, String>,
     max_execution_time: std::time::Duration,
+    /// Fail instead of base64-encoding stdout that is not valid UTF-8
+    reject_non_utf8: bool,
+    /// Process-wide cap shared with every other subprocess-spawning agent
+    subprocesses: &'static crate::process::SubprocessLimiter,
+}
+
+/// Name prefix of per-task working directories created by batch runs
+pub const TASK_WORKDIR_PREFIX: &str = "acropolis-task-";
+
+/// Environment variable exposing the per-task working directory to scripts
+pub const TASK_WORKDIR_ENV: &str = "ACROPOLIS_TASK_DIR";
+
+/// Working directories of running tasks, keyed by the token handed to the task
+fn task_workdirs() -> &'static dashmap::DashMap<String, PathBuf> {
+    static WORKDIRS: std::sync::OnceLock<dashmap::DashMap<String, PathBuf>> = std::sync::OnceLock::new();
+    WORKDIRS.get_or_init(dashmap::DashMap::new)
+}
+
+/// Isolated working directory of one task attempt. Agents only accept it by
+/// its token, and only until this is dropped, which also removes the directory.
+pub struct TaskWorkdir {
+    dir: tempfile::TempDir,
+    token: String,
+}
+
+impl TaskWorkdir {
+    pub fn create() -> Result<Self> {
+        let dir = tempfile::Builder::new().prefix(TASK_WORKDIR_PREFIX).tempdir()?;
+        let token = uuid::Uuid::new_v4().to_string();
+        task_workdirs().insert(token.clone(), dir.path().canonicalize()?);
+        Ok(Self { dir, token })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Secret that lets an agent run in this directory
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+impl Drop for TaskWorkdir {
+    fn drop(&mut self) {
+        task_workdirs().remove(&self.token);
+    }
 }
 
 #[derive(Deserialize)]
@@ -275,6 +667,13 @@ struct PythonToolInput {
     script_path: String,
     args: Vec<String>,
     timeout_seconds: Option<u64>,
+    /// Token of the task's isolated working directory (see [`TaskWorkdir`])
+    #[serde(default)]
+    workdir_token: Option<String>,
+    /// Wrap stdout in the `{"format", "output"}` envelope instead of
+    /// returning it verbatim
+    #[serde(default)]
+    structured_output: bool,
 }
 
 impl PythonToolAgent {
@@ -283,35 +682,60 @@ impl PythonToolAgent {
             request_count: std::sync::atomic::AtomicU64::new(0),
             error_count: std::sync::atomic::AtomicU64::new(0),
             start_time: std::time::Instant::now(),
-            // Scripts must be in a dedicated, non-tmp directory
-            allowed_directories: vec!["./python_scripts".to_string()],
+            allowed_directories: Self::canonicalize_directories(&settings.security.python_script_directories),
             script_allowlist_hashes: settings.security.script_allowlist_hashes.clone(),
             max_execution_time: std::time::Duration::from_secs(300), // 5 minutes
+            reject_non_utf8: settings.security.python_non_utf8_output == "error",
+            subprocesses: crate::process::subprocess_limiter(settings),
         }
     }
 
-    fn validate_script_path(&self, path: &str) -> Result<()> {
-        let path = std::path::Path::new(path);
+    /// Resolve allowed directories to their canonical forms, dropping any that do not exist
+    fn canonicalize_directories(directories: &[PathBuf]) -> Vec<PathBuf> {
+        directories
+            .iter()
+            .filter_map(|dir| match dir.canonicalize() {
+                Ok(canonical) => Some(canonical),
+                Err(e) => {
+                    warn!("Ignoring Python script directory {:?}: {}", dir, e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Resolve `path` and check it lies within an allowed directory, returning the canonical path.
+    ///
+    /// Canonicalizing resolves `..` components and symlinks, so a script cannot
+    /// escape the allowlist via traversal or a link pointing outside it.
+    fn validate_script_path(&self, path: &str) -> Result<PathBuf> {
+        let canonical = Path::new(path)
+            .canonicalize()
+            .map_err(|_| anyhow!("Script file '{}' does not exist", path))?;
 
-        // Check if path is within allowed directories
+        // Component-wise comparison against canonical forms, not string prefixes
         let is_allowed = self.allowed_directories.iter().any(|allowed| {
-            path.starts_with(allowed)
+            canonical.starts_with(allowed)
         });
 
         if !is_allowed {
-            return Err(anyhow!("Script path '{}' is not in allowed directories", path.display()));
+            return Err(anyhow!("Script path '{}' is not in allowed directories", path));
         }
 
-        // Check if file exists and is readable
-        if !path.exists() {
-            return Err(anyhow!("Script file '{}' does not exist", path.display()));
+        if !canonical.is_file() {
+            return Err(anyhow!("Path '{}' is not a file", path));
         }
 
-        if !path.is_file() {
-            return Err(anyhow!("Path '{}' is not a file", path.display()));
-        }
+        Ok(canonical)
+    }
 
-        Ok(())
+    /// Resolve a working directory token to the directory of the running
+    /// task it was issued to
+    fn validate_working_dir(token: &str) -> Result<PathBuf> {
+        task_workdirs()
+            .get(token)
+            .map(|dir| dir.clone())
+            .ok_or_else(|| anyhow!("Working directory token does not belong to a running task"))
     }
 
     /// Validate command arguments to prevent shell injection and dangerous patterns
@@ -347,8 +771,71 @@ impl PythonToolAgent {
         Ok(())
     }
 
+    /// Validate the input and build the command that runs the script
+    fn prepare_command(&self, input: serde_json::Value) -> Result<PreparedScript> {
+        let parsed_input: PythonToolInput = serde_json::from_value(input)
+            .map_err(|e| {
+                self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                InvalidInputError(format!("Invalid Python tool input: {}", e))
+            })?;
+
+        // Validate script path and integrity
+        let script_path = self.validate_script_path(&parsed_input.script_path)?;
+        self.validate_script_integrity(&parsed_input.script_path, &script_path)?;
+
+        info!(
+            "Executing Python script: {} with args: {:?}",
+            parsed_input.script_path,
+            parsed_input.args
+        );
+
+        // Build command with security constraints and input validation
+        Self::validate_command_args(&parsed_input.args)?;
+        
+        let mut cmd = Command::new("python3");
+        cmd.arg(&script_path);
+        cmd.args(&parsed_input.args);
+
+        // Run in the isolated task directory if given, otherwise where the script lives
+        if let Some(token) = &parsed_input.workdir_token {
+            let working_dir = Self::validate_working_dir(token)?;
+            cmd.current_dir(&working_dir);
+            cmd.env(TASK_WORKDIR_ENV, &working_dir);
+            cmd.env("TMPDIR", &working_dir);
+        } else if let Some(script_dir) = script_path.parent() {
+            cmd.current_dir(script_dir);
+        }
+
+        // Don't leave the script running if the caller gives up on it
+        cmd.kill_on_drop(true);
+
+        // Set up I/O
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+
+        let timeout = parsed_input.timeout_seconds
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(self.max_execution_time);
+
+        Ok(PreparedScript { command: cmd, timeout, structured_output: parsed_input.structured_output })
+    }
+
+    /// Spawn once a subprocess slot is free; keep the permit until the
+    /// child has been reaped
+    async fn spawn(&self, command: &mut Command) -> Result<(tokio::process::Child, crate::process::SubprocessPermit)> {
+        let permit = self.subprocesses.acquire().await.map_err(|e| {
+            self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            e
+        })?;
+        let child = command.spawn().map_err(|e| {
+            self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            anyhow!("Failed to spawn Python process: {}", e)
+        })?;
+        Ok((child, permit))
+    }
+
     /// Validate the integrity of the script file by checking its hash
-    fn validate_script_integrity(&self, path: &str) -> Result<()> {
+    fn validate_script_integrity(&self, path: &str, resolved: &Path) -> Result<()> {
         if self.script_allowlist_hashes.is_empty() {
             warn!("Script allowlist is empty. Skipping integrity check for {}", path);
             return Ok(());
@@ -357,7 +844,7 @@ impl PythonToolAgent {
         let expected_hash = self.script_allowlist_hashes.get(path)
             .ok_or_else(|| anyhow!("Script '{}' is not in the allowlist", path))?;
 
-        let file_content = std::fs::read(path)?;
+        let file_content = std::fs::read(resolved)?;
         let mut hasher = Sha256::new();
         hasher.update(&file_content);
         let actual_hash = format!("{:x}", hasher.finalize());
@@ -389,80 +876,105 @@ impl Agent for PythonToolAgent {
     async fn handle(&self, input: serde_json::Value, _memory: Arc<Memory>) -> Result<String> {
         self.request_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
 
-        let parsed_input: PythonToolInput = serde_json::from_value(input)
-            .map_err(|e| {
-                self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                anyhow!("Invalid Python tool input: {}", e)
-            })?;
+        let PreparedScript { mut command, timeout, structured_output } = self.prepare_command(input)?;
 
-        // Validate script path and integrity
-        self.validate_script_path(&parsed_input.script_path)?;
-        self.validate_script_integrity(&parsed_input.script_path)?;
+        // Spawn child process for proper management
+        let (mut child, _permit) = self.spawn(&mut command).await?;
 
-        info!(
-            "Executing Python script: {} with args: {:?}",
-            parsed_input.script_path,
-            parsed_input.args
-        );
+        // Drain the pipes concurrently so a chatty script cannot block on a full buffer
+        let stdout_reader = tokio::spawn(read_pipe(child.stdout.take()));
+        let stderr_reader = tokio::spawn(read_pipe(child.stderr.take()));
 
-        // Build command with security constraints and input validation
-        Self::validate_command_args(&parsed_input.args)?;
-        
-        let mut cmd = Command::new("python3");
-        cmd.arg(&parsed_input.script_path);
-        cmd.args(&parsed_input.args);
+        // Wait for completion; on timeout the process is killed and reaped
+        let status = match crate::process::wait_or_kill(&mut child, timeout).await {
+            Ok(Some(status)) => status,
+            Ok(None) => {
+                self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                return Err(anyhow!("Python script execution timed out after {:?}", timeout));
+            }
+            Err(e) => {
+                self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                return Err(anyhow!("Failed to execute Python script: {}", e));
+            }
+        };
 
-        // The working directory is now fixed to where the script is located
-        if let Some(script_dir) = std::path::Path::new(&parsed_input.script_path).parent() {
-            cmd.current_dir(script_dir);
+        let output = std::process::Output {
+            status,
+            stdout: stdout_reader.await.unwrap_or_default(),
+            stderr: stderr_reader.await.unwrap_or_default(),
+        };
+
+        if output.status.success() {
+            info!("Python script executed successfully");
+            format_script_output(&output.stdout, structured_output, self.reject_non_utf8).map_err(|e| {
+                self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                e
+            })
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Err(anyhow!("Python script failed: {}", stderr))
         }
+    }
 
-        // Set up I/O
-        cmd.stdout(Stdio::piped());
-        cmd.stderr(Stdio::piped());
+    /// Stream stdout as it is printed, one `{"line": ...}` chunk per line,
+    /// followed by a final `{"exit_code": ...}` chunk (`null` when the script
+    /// was killed by a signal). Lines that are not valid UTF-8 are sent
+    /// base64-encoded with `"format": "base64"` unless the agent rejects them.
+    /// A non-zero exit still fails the call, after its exit chunk.
+    #[instrument(skip(self, _memory, chunks))]
+    async fn handle_stream(
+        &self,
+        input: serde_json::Value,
+        _memory: Arc<Memory>,
+        chunks: mpsc::Sender<String>,
+    ) -> Result<()> {
+        self.request_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
 
-        // Execute with proper timeout and cleanup
-        let timeout = parsed_input.timeout_seconds
-            .map(std::time::Duration::from_secs)
-            .unwrap_or(self.max_execution_time);
+        let PreparedScript { mut command, timeout, .. } = self.prepare_command(input)?;
+        // Python block-buffers piped stdout, which would hold progress back
+        command.env("PYTHONUNBUFFERED", "1");
 
-        // Spawn child process for proper management
-        let mut child = cmd.spawn()
-            .map_err(|e| {
-                self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                anyhow!("Failed to spawn Python process: {}", e)
-            })?;
+        let (mut child, _permit) = self.spawn(&mut command).await?;
+        let deadline = tokio::time::Instant::now() + timeout;
+        let stdout = child.stdout.take();
+        let stderr_reader = tokio::spawn(read_pipe(child.stderr.take()));
 
-        // Wait for completion with timeout and proper cleanup
-        let output = match tokio::time::timeout(timeout, async {
-            // Handle timeout case by checking if child is still running
-            let output_result = child.wait_with_output().await;
-            match output_result {
-                Ok(output) => Ok(output),
-                Err(e) => Err(anyhow!("Failed to execute Python script: {}", e))
+        let streamed = tokio::time::timeout_at(deadline, stream_lines(stdout, &chunks, self.reject_non_utf8)).await;
+        let status = match streamed {
+            Ok(Ok(())) => {
+                let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+                crate::process::wait_or_kill(&mut child, remaining).await
             }
-        }).await {
-            Ok(result) => result?,
-            Err(_) => {
-                // Timeout occurred - forcefully terminate the process  
-                warn!("Python script execution timed out, killing process");
-                if let Err(e) = child.start_kill() {
-                    error!("Failed to kill timed-out Python process: {}", e);
-                }
-                
+            Ok(Err(e)) => {
+                // Dropping the child on return kills it
+                self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                return Err(e);
+            }
+            Err(_) => crate::process::wait_or_kill(&mut child, std::time::Duration::ZERO).await,
+        };
+        let status = match status {
+            Ok(Some(status)) => status,
+            Ok(None) => {
                 self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
                 return Err(anyhow!("Python script execution timed out after {:?}", timeout));
             }
+            Err(e) => {
+                self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                return Err(anyhow!("Failed to execute Python script: {}", e));
+            }
         };
 
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
+        let exit = serde_json::json!({ "exit_code": status.code() }).to_string();
+        chunks.send(exit).await.map_err(|_| CancelledError)?;
+
+        if status.success() {
             info!("Python script executed successfully");
-            Ok(stdout.to_string())
+            Ok(())
         } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stderr = stderr_reader.await.unwrap_or_default();
             self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-            Err(anyhow!("Python script failed: {}", stderr))
+            Err(anyhow!("Python script failed: {}", String::from_utf8_lossy(&stderr)))
         }
     }
 
@@ -481,52 +993,395 @@ impl Agent for PythonToolAgent {
     }
 }
 
+/// Script stdout as returned to the caller: verbatim, or with `structured`
+/// set wrapped in a `{"format", "output"}` envelope.
+///
+/// In the envelope, stdout that parses cleanly as a single JSON document in
+/// its entirety is embedded as a value (`"format": "json"`) so downstream
+/// agents need not parse it again; anything else is embedded as a string
+/// (`"format": "text"`).
+///
+/// Stdout that is not valid UTF-8 is never decoded lossily: it fails when
+/// `reject_non_utf8` is set and is otherwise base64-encoded with
+/// `"format": "base64"`, even when not structured, since a bare string could
+/// not signal that it is binary.
+fn format_script_output(stdout: &[u8], structured: bool, reject_non_utf8: bool) -> Result<String> {
+    use base64::Engine;
+
+    let text = match std::str::from_utf8(stdout) {
+        Ok(text) => text,
+        Err(e) if reject_non_utf8 => {
+            return Err(anyhow!("Python script output is not valid UTF-8: {}", e));
+        }
+        Err(_) => {
+            let encoded = base64::engine::general_purpose::STANDARD.encode(stdout);
+            return Ok(serde_json::json!({ "format": "base64", "output": encoded }).to_string());
+        }
+    };
+    if !structured {
+        return Ok(text.to_string());
+    }
+
+    let envelope = match serde_json::from_str::<serde_json::Value>(text) {
+        Ok(value) => serde_json::json!({ "format": "json", "output": value }),
+        Err(_) => serde_json::json!({ "format": "text", "output": text }),
+    };
+    Ok(serde_json::to_string(&envelope)?)
+}
+
+/// A validated script invocation, ready to spawn
+struct PreparedScript {
+    command: Command,
+    timeout: std::time::Duration,
+    structured_output: bool,
+}
+
+/// Send each line of a child's stdout as a `{"line": ...}` chunk as soon as
+/// it is complete; a trailing line without a newline is sent at EOF
+async fn stream_lines<R>(pipe: Option<R>, chunks: &mpsc::Sender<String>, reject_non_utf8: bool) -> Result<()>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    use base64::Engine;
+    use tokio::io::AsyncBufReadExt;
+
+    let Some(pipe) = pipe else { return Ok(()) };
+    let mut reader = tokio::io::BufReader::new(pipe);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line).await? == 0 {
+            return Ok(());
+        }
+        if line.ends_with(b"\n") {
+            line.pop();
+            if line.ends_with(b"\r") {
+                line.pop();
+            }
+        }
+
+        let chunk = match std::str::from_utf8(&line) {
+            Ok(text) => serde_json::json!({ "line": text }),
+            Err(e) if reject_non_utf8 => {
+                return Err(anyhow!("Python script output is not valid UTF-8: {}", e));
+            }
+            Err(_) => serde_json::json!({
+                "format": "base64",
+                "line": base64::engine::general_purpose::STANDARD.encode(&line),
+            }),
+        };
+        // A closed receiver means the caller went away
+        chunks.send(chunk.to_string()).await.map_err(|_| CancelledError)?;
+    }
+}
+
+/// Read a child pipe to the end, returning an empty buffer if it is missing or fails
+async fn read_pipe<R>(pipe: Option<R>) -> Vec<u8>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    use tokio::io::AsyncReadExt;
+
+    let mut buf = Vec::new();
+    if let Some(mut pipe) = pipe {
+        if let Err(e) = pipe.read_to_end(&mut buf).await {
+            warn!("Failed to read child process output: {}", e);
+        }
+    }
+    buf
+}
+
+/// Sampling settings for one LLM generation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplingParams {
+    pub temperature: f32,
+    pub top_p: f32,
+    pub top_k: usize,
+    pub max_tokens: usize,
+    pub seed: u32,
+}
+
+impl Default for SamplingParams {
+    fn default() -> Self {
+        Self {
+            temperature: 0.7,
+            top_p: 0.9,
+            top_k: 40,
+            max_tokens: 512,
+            seed: 42,
+        }
+    }
+}
+
+impl SamplingParams {
+    /// Upper bound on `max_tokens` accepted from a request
+    pub const MAX_TOKENS_LIMIT: usize = 32_768;
+
+    /// Apply the optional `temperature`, `top_p`, `top_k`, `max_tokens` and
+    /// `seed` fields of a request over these defaults, rejecting values
+    /// outside sane ranges with [`InvalidInputError`]
+    pub fn with_overrides(mut self, input: &serde_json::Value) -> Result<Self> {
+        fn field<'a>(input: &'a serde_json::Value, name: &str) -> Option<&'a serde_json::Value> {
+            input.get(name).filter(|v| !v.is_null())
+        }
+        let invalid = |message: String| -> anyhow::Error { InvalidInputError(message).into() };
+
+        if let Some(value) = field(input, "temperature") {
+            let temperature = value.as_f64()
+                .filter(|t| (0.0..=2.0).contains(t))
+                .ok_or_else(|| invalid(format!("'temperature' must be a number in [0, 2], got {}", value)))?;
+            self.temperature = temperature as f32;
+        }
+        if let Some(value) = field(input, "top_p") {
+            let top_p = value.as_f64()
+                .filter(|p| *p > 0.0 && *p <= 1.0)
+                .ok_or_else(|| invalid(format!("'top_p' must be a number in (0, 1], got {}", value)))?;
+            self.top_p = top_p as f32;
+        }
+        if let Some(value) = field(input, "top_k") {
+            self.top_k = value.as_u64()
+                .filter(|k| *k >= 1)
+                .ok_or_else(|| invalid(format!("'top_k' must be a positive integer, got {}", value)))? as usize;
+        }
+        if let Some(value) = field(input, "max_tokens") {
+            self.max_tokens = value.as_u64()
+                .filter(|n| *n >= 1 && *n <= Self::MAX_TOKENS_LIMIT as u64)
+                .ok_or_else(|| invalid(format!("'max_tokens' must be an integer in [1, {}], got {}", Self::MAX_TOKENS_LIMIT, value)))? as usize;
+        }
+        if let Some(value) = field(input, "seed") {
+            self.seed = value.as_u64()
+                .and_then(|s| u32::try_from(s).ok())
+                .ok_or_else(|| invalid(format!("'seed' must be an integer in [0, {}], got {}", u32::MAX, value)))?;
+        }
+        Ok(self)
+    }
+}
+
+/// The model an `LlmAgent` is currently serving
+#[cfg(feature = "with-llama")]
+#[derive(Clone)]
+struct LoadedModel {
+    model: Arc<LlamaModel>,
+    path: PathBuf,
+}
+
 /// Enhanced LLM agent with better model management
 #[cfg(feature = "with-llama")]
 pub struct LlmAgent {
     name: String,
-    model: LlamaModel,
+    /// Each request clones the `Arc` it starts with, so a reload never tears
+    /// down a model mid-generation; the old one is freed by its last request
+    model: parking_lot::RwLock<LoadedModel>,
+    /// Serializes reloads so two swaps cannot interleave
+    reload_lock: tokio::sync::Mutex<()>,
+    /// Canonical directories reloaded models must live in
+    model_directories: Vec<PathBuf>,
     session_params: SessionParams,
     request_count: std::sync::atomic::AtomicU64,
     error_count: std::sync::atomic::AtomicU64,
     start_time: std::time::Instant,
-    max_tokens: usize,
-    temperature: f32,
+    /// Defaults for requests that don't override sampling
+    sampling: SamplingParams,
+    templates: Arc<PromptTemplates>,
+    /// Shape of the JSON the model is prompted to produce, if any
+    output_schema: Option<serde_json::Value>,
 }
 
 #[cfg(feature = "with-llama")]
 impl LlmAgent {
     pub fn new(name: &str, model_path: &str) -> Result<Self> {
-        let params = LlamaParams::default()
-            .with_model_path(model_path)
-            .with_n_ctx(2048)
-            .with_n_batch(512);
-
-        let model = LlamaModel::load(params)?;
-        let session_params = SessionParams::default()
-            .with_seed(42);
+        let model = Self::load_model(Path::new(model_path))?;
+        let session_params = SessionParams::default();
 
         Ok(Self {
             name: name.to_string(),
-            model,
+            model: parking_lot::RwLock::new(LoadedModel {
+                model: Arc::new(model),
+                path: PathBuf::from(model_path),
+            }),
+            reload_lock: tokio::sync::Mutex::new(()),
+            model_directories: Vec::new(),
             session_params,
             request_count: std::sync::atomic::AtomicU64::new(0),
             error_count: std::sync::atomic::AtomicU64::new(0),
             start_time: std::time::Instant::now(),
-            max_tokens: 512,
-            temperature: 0.7,
+            sampling: SamplingParams::default(),
+            templates: Arc::new(PromptTemplates::default()),
+            output_schema: None,
         })
     }
 
+    /// Declare the JSON Schema completions must match, for models prompted
+    /// to answer with structured output
+    pub fn with_output_schema(mut self, schema: Option<serde_json::Value>) -> Self {
+        self.output_schema = schema;
+        self
+    }
+
+    /// Templates requests can select with a `"template"` field
+    pub fn with_prompt_templates(mut self, templates: Arc<PromptTemplates>) -> Self {
+        self.templates = templates;
+        self
+    }
+
+    /// Build the prompt for a request.
+    ///
+    /// Without `"template"`, memory context (if any) is prepended to
+    /// `"prompt"`. With one, the named template is rendered from
+    /// `"variables"` plus `prompt` and, when the template uses it, `context`.
+    async fn build_prompt(&self, input: &serde_json::Value, memory: &Memory) -> Result<String> {
+        let prompt = input.get("prompt").and_then(|v| v.as_str());
+
+        let Some(template_name) = input.get("template").and_then(|v| v.as_str()) else {
+            let prompt = prompt.ok_or_else(|| anyhow!("Missing 'prompt' field in LLM input"))?;
+            let context = caller_memory_context(memory, prompt, 3).await;
+            return Ok(if context.is_empty() {
+                prompt.to_string()
+            } else {
+                format!("Context:\n{}\n\nQuestion: {}", context.join("\n"), prompt)
+            });
+        };
+
+        let template = self.templates.get(template_name)
+            .ok_or_else(|| InvalidInputError(format!("Unknown prompt template '{}'", template_name)))?;
+        let mut values: HashMap<String, String> = match input.get("variables") {
+            None | Some(serde_json::Value::Null) => HashMap::new(),
+            Some(serde_json::Value::Object(map)) => map.iter()
+                .map(|(name, value)| {
+                    let value = match value {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    (name.clone(), value)
+                })
+                .collect(),
+            Some(_) => return Err(InvalidInputError("'variables' must be an object".to_string()).into()),
+        };
+        if let Some(prompt) = prompt {
+            values.entry("prompt".to_string()).or_insert_with(|| prompt.to_string());
+            if template.variables().contains("context") && !values.contains_key("context") {
+                let context = caller_memory_context(memory, prompt, 3).await;
+                values.insert("context".to_string(), context.join("\n"));
+            }
+        }
+        template.render(&values)
+    }
+
     pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
-        self.max_tokens = max_tokens;
+        self.sampling.max_tokens = max_tokens;
         self
     }
 
     pub fn with_temperature(mut self, temperature: f32) -> Self {
-        self.temperature = temperature;
+        self.sampling.temperature = temperature;
+        self
+    }
+
+    /// Allow `reload_model` to load models from these directories; with
+    /// none configured, reloading is refused
+    pub fn with_model_directories(mut self, directories: &[PathBuf]) -> Self {
+        self.model_directories = directories
+            .iter()
+            .filter_map(|dir| match dir.canonicalize() {
+                Ok(canonical) => Some(canonical),
+                Err(e) => {
+                    warn!("Ignoring LLM model directory {:?}: {}", dir, e);
+                    None
+                }
+            })
+            .collect();
         self
     }
+
+    fn load_model(path: &Path) -> Result<LlamaModel> {
+        let params = LlamaParams::default()
+            .with_model_path(path)
+            .with_n_ctx(2048)
+            .with_n_batch(512);
+        Ok(LlamaModel::load(params)?)
+    }
+
+    /// The model new requests should run on
+    fn current_model(&self) -> Arc<LlamaModel> {
+        self.model.read().model.clone()
+    }
+
+    /// Resolve `path` and check it is a file inside an allowed model directory
+    fn validate_model_path(&self, path: &Path) -> Result<PathBuf> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|_| InvalidInputError(format!("Model file {:?} does not exist", path)))?;
+        if !self.model_directories.iter().any(|allowed| canonical.starts_with(allowed)) {
+            return Err(InvalidInputError(format!("Model path {:?} is not in allowed directories", path)).into());
+        }
+        if !canonical.is_file() {
+            return Err(InvalidInputError(format!("Model path {:?} is not a file", path)).into());
+        }
+        Ok(canonical)
+    }
+
+    /// Tokens in `text` according to the current model's tokenizer
+    fn count_tokens(&self, text: &str) -> u64 {
+        self.current_model()
+            .tokenize_bytes(text, false, false)
+            .map(|tokens| tokens.len() as u64)
+            .unwrap_or_else(|_| approximate_tokens(text))
+    }
+
+    /// Tokens in the prompt text the caller supplied. Retrieved memory
+    /// context and template boilerplate are not known before the call and
+    /// are left out.
+    fn prompt_tokens(&self, input: &serde_json::Value) -> u64 {
+        let mut text = input.get("prompt").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        if let Some(serde_json::Value::Object(variables)) = input.get("variables") {
+            for value in variables.values() {
+                match value {
+                    serde_json::Value::String(s) => text.push_str(s),
+                    other => text.push_str(&other.to_string()),
+                }
+            }
+        }
+        self.count_tokens(&text)
+    }
+
+    /// Default sampling settings; requests may override them individually
+    pub fn with_sampling(mut self, sampling: SamplingParams) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Run a completion with llama.cpp. CPU-bound, so callers run it on a
+    /// blocking thread.
+    fn generate(
+        model: &LlamaModel,
+        session_params: SessionParams,
+        sampling: SamplingParams,
+        prompt: &str,
+        cancel: &CancellationToken,
+    ) -> Result<String> {
+        let mut session = model.create_session(session_params)?;
+
+        let sampler = standard_sampler()
+            .with_temperature(sampling.temperature)
+            .with_top_p(sampling.top_p)
+            .with_top_k(sampling.top_k);
+
+        let completions = session
+            .advance_context(prompt)
+            .and_then(|_| session.start_completing_with(sampler, sampling.max_tokens))
+            .map_err(|e| anyhow!("LLM inference failed: {}", e))?;
+
+        // Check between tokens so an abandoned request stops generating
+        let mut response = String::new();
+        for token in completions.into_strings() {
+            if cancel.is_cancelled() {
+                info!("LLM generation cancelled");
+                return Err(CancelledError.into());
+            }
+            response.push_str(&token);
+        }
+        Ok(response)
+    }
 }
 
 #[cfg(feature = "with-llama")]
@@ -540,49 +1395,113 @@ impl Agent for LlmAgent {
         vec!["text_generation".to_string(), "completion".to_string(), "reasoning".to_string()]
     }
 
-    #[instrument(skip(self, memory))]
-    async fn handle(&self, input: serde_json::Value, memory: Arc<Memory>) -> Result<String> {
-        self.request_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    /// Prompt tokens plus the full completion allowance, so the estimate
+    /// never falls short of what generation can use
+    fn cost_estimate(&self, input: &serde_json::Value) -> Option<Cost> {
+        let sampling = self.sampling.with_overrides(input).ok()?;
+        Some(Cost::from_tokens(self.prompt_tokens(input) + sampling.max_tokens as u64))
+    }
 
-        let prompt = input.get("prompt")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| {
-                self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                anyhow!("Missing 'prompt' field in LLM input")
-            })?;
+    fn actual_cost(&self, input: &serde_json::Value, output: &str) -> Option<Cost> {
+        Some(Cost::from_tokens(self.prompt_tokens(input) + self.count_tokens(output)))
+    }
 
-        // Get relevant context from memory
-        let context = memory.search_memory(prompt, 3).await
-            .unwrap_or_else(|_| vec![]);
+    fn output_schema(&self) -> Option<serde_json::Value> {
+        self.output_schema.clone()
+    }
 
-        let enhanced_prompt = if context.is_empty() {
-            prompt.to_string()
-        } else {
-            format!("Context:\n{}\n\nQuestion: {}",
-                context.join("\n"), prompt)
-        };
+    async fn handle(&self, input: serde_json::Value, memory: Arc<Memory>) -> Result<String> {
+        self.handle_cancellable(input, memory, CancellationToken::new()).await
+    }
 
-        info!("Generating LLM response for prompt: {}", &enhanced_prompt[..enhanced_prompt.len().min(100)]);
+    #[instrument(skip(self, memory, cancel))]
+    async fn handle_cancellable(
+        &self,
+        input: serde_json::Value,
+        memory: Arc<Memory>,
+        cancel: CancellationToken,
+    ) -> Result<String> {
+        self.request_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
 
-        // Generate response using llama.cpp
-        let mut session = self.model.create_session(self.session_params.clone())?;
+        let count_error = |e: anyhow::Error| {
+            self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            e
+        };
+        let sampling = self.sampling.with_overrides(&input).map_err(count_error)?;
+        let enhanced_prompt = self.build_prompt(&input, &memory).await.map_err(count_error)?;
 
-        let sampler = standard_sampler()
-            .with_temperature(self.temperature)
-            .with_top_p(0.9)
-            .with_top_k(40);
+        info!("Generating LLM response for prompt: {}", &enhanced_prompt[..enhanced_prompt.len().min(100)]);
 
-        let response = session
-            .infer(enhanced_prompt, sampler, |_| {})
-            .map_err(|e| {
+        // Generate on a blocking thread, using the model current at the start
+        // of the request even if a reload swaps it out meanwhile. Generation
+        // also stops if this future is dropped.
+        let model = self.current_model();
+        let session_params = self.session_params.clone().with_seed(sampling.seed);
+        let generation_cancel = cancel.child_token();
+        let _stop_on_drop = generation_cancel.clone().drop_guard();
+        let response = tokio::task::spawn_blocking(move || {
+            Self::generate(&model, session_params, sampling, &enhanced_prompt, &generation_cancel)
+        })
+        .await
+        .map_err(|e| anyhow!("LLM generation task failed: {}", e))
+        .and_then(|result| result)
+        .map_err(|e| {
+            if !e.is::<CancelledError>() {
                 self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                anyhow!("LLM inference failed: {}", e)
-            })?;
+            }
+            e
+        })?;
 
         info!("LLM response generated successfully");
         Ok(response)
     }
 
+    async fn warm_up(&self) -> Result<()> {
+        // Allocate a context and evaluate a token so model weights are paged in
+        let model = self.current_model();
+        let session_params = self.session_params.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut session = model.create_session(session_params)?;
+            session
+                .advance_context(" ")
+                .map_err(|e| anyhow!("LLM warm-up failed: {}", e))
+        })
+        .await
+        .map_err(|e| anyhow!("LLM warm-up task failed: {}", e))?
+    }
+
+    #[instrument(skip(self))]
+    async fn reload_model(&self, path: &Path) -> Result<ModelReloadReport> {
+        let path = self.validate_model_path(path)?;
+        let _reloading = self.reload_lock.lock().await;
+
+        // Load fully before swapping, so a bad file leaves the old model serving
+        let start = std::time::Instant::now();
+        let load_path = path.clone();
+        let model = tokio::task::spawn_blocking(move || Self::load_model(&load_path))
+            .await
+            .map_err(|e| anyhow!("Model loading task failed: {}", e))?
+            .map_err(|e| anyhow!("Failed to load model {:?}: {}", path, e))?;
+        let load_time = start.elapsed();
+
+        let previous = std::mem::replace(
+            &mut *self.model.write(),
+            LoadedModel { model: Arc::new(model), path: path.clone() },
+        );
+        let draining_requests = Arc::strong_count(&previous.model) - 1;
+        info!(
+            "Agent '{}' now serving model {:?} (was {:?}, {} requests draining)",
+            self.name, path, previous.path, draining_requests
+        );
+
+        Ok(ModelReloadReport {
+            previous_path: previous.path,
+            path,
+            load_time_ms: load_time.as_millis() as u64,
+            draining_requests,
+        })
+    }
+
     async fn health_check(&self) -> Result<AgentHealth> {
         let uptime = self.start_time.elapsed().as_secs();
         let requests = self.request_count.load(std::sync::atomic::Ordering::Relaxed);
@@ -601,34 +1520,364 @@ impl Agent for LlmAgent {
 /// Agent factory for creating agents by type
 pub struct AgentFactory;
 
+/// Config for agent types that take no options
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NoAgentConfig {}
+
+/// Config for the `llm` agent type
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LlmAgentConfig {
+    name: String,
+    model_path: String,
+    /// JSON Schema completions are checked against
+    #[serde(default)]
+    output_schema: Option<serde_json::Value>,
+}
+
+impl LlmAgentConfig {
+    /// Checks beyond what deserializing already enforces
+    fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(anyhow!("'name' must not be empty"));
+        }
+        if self.model_path.trim().is_empty() {
+            return Err(anyhow!("'model_path' must not be empty"));
+        }
+        match &self.output_schema {
+            None | Some(serde_json::Value::Object(_)) | Some(serde_json::Value::Bool(_)) => Ok(()),
+            Some(_) => Err(anyhow!("'output_schema' must be a JSON Schema object or boolean")),
+        }
+    }
+}
+
 impl AgentFactory {
-    pub fn create_agent(agent_type: &str, config: serde_json::Value, settings: &Settings) -> Result<Box<dyn Agent>> {
+    /// Default config for an agent type; caller config is merged over it
+    pub fn default_config(agent_type: &str) -> Result<serde_json::Value> {
         match agent_type {
-            "echo" => Ok(Box::new(EchoAgent::new())),
-            "python" => Ok(Box::new(PythonToolAgent::new(settings))),
+            "echo" | "python" | "http_fetch" | "julia" | "zig" => Ok(serde_json::json!({})),
+            "llm" => Ok(serde_json::json!({ "name": "llm_agent" })),
+            _ => Err(anyhow!("Unknown agent type: {}", agent_type)),
+        }
+    }
+
+    /// Deep-merge `config` over the type's defaults and validate the result
+    pub fn resolve_config(agent_type: &str, config: serde_json::Value) -> Result<serde_json::Value> {
+        let mut merged = Self::default_config(agent_type)?;
+        match config {
+            serde_json::Value::Null => {}
+            serde_json::Value::Object(_) => merge_json(&mut merged, config),
+            _ => return Err(anyhow!("Config for agent type '{}' must be an object", agent_type)),
+        }
+
+        let validation = match agent_type {
+            "llm" => serde_json::from_value::<LlmAgentConfig>(merged.clone())
+                .map_err(anyhow::Error::from)
+                .and_then(|config| config.validate()),
+            _ => serde_json::from_value::<NoAgentConfig>(merged.clone())
+                .map(|_| ())
+                .map_err(anyhow::Error::from),
+        };
+        validation.map_err(|e| anyhow!("Invalid config for agent type '{}': {}", agent_type, e))?;
+
+        Ok(merged)
+    }
+
+    pub fn create_agent(agent_type: &str, config: serde_json::Value, settings: &Settings) -> Result<Box<dyn Agent>> {
+        let config = Self::resolve_config(agent_type, config)?;
+        match (agent_type, config) {
+            ("echo", _) => Ok(Box::new(EchoAgent::new())),
+            ("python", _) => Ok(Box::new(PythonToolAgent::new(settings))),
+            ("http_fetch", _) => Ok(Box::new(crate::http_agent::HttpFetchAgent::new(settings))),
             #[cfg(feature = "with-julia")]
-            "julia" => {
+            ("julia", _) => {
                 use crate::ffi_julia::JuliaAgent;
-                Ok(Box::new(JuliaAgent::new(settings)?))
+                Ok(Box::new(JuliaAgent::new(settings)))
             }
             #[cfg(feature = "with-zig")]
-            "zig" => {
+            ("zig", _) => {
                 use crate::ffi_zig::ZigAgent;
-                Ok(Box::new(ZigAgent::new()))
+                Ok(Box::new(ZigAgent::new(settings)))
             }
             #[cfg(feature = "with-llama")]
-            "llm" => {
-                let name = config.get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("llm_agent");
-                let model_path = config.get("model_path")
-                    .and_then(|v| v.as_str())
-                    .ok_or_else(|| anyhow!("Missing 'model_path' for LLM agent"))?;
-
-                let agent = LlmAgent::new(name, model_path)?;
+            ("llm", config) => {
+                let config: LlmAgentConfig = serde_json::from_value(config)?;
+                let templates = crate::prompt::PromptTemplates::from_config(&settings.llm)?;
+                let agent = LlmAgent::new(&config.name, &config.model_path)?
+                    .with_prompt_templates(Arc::new(templates))
+                    .with_model_directories(&settings.security.llm_model_directories)
+                    .with_output_schema(config.output_schema);
                 Ok(Box::new(agent))
             }
-            _ => Err(anyhow!("Unknown agent type: {}", agent_type)),
+            _ => Err(anyhow!("Agent type '{}' is not enabled in this build", agent_type)),
+        }
+    }
+}
+
+/// Recursively merge `overlay` into `base`; objects merge key by key, any other
+/// value in `overlay` replaces the one in `base`
+fn merge_json(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
         }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn python_agent_for(dir: &Path) -> PythonToolAgent {
+        let mut settings = Settings::default();
+        settings.security.python_script_directories = vec![dir.to_path_buf()];
+        PythonToolAgent::new(&settings)
+    }
+
+    #[test]
+    fn test_python_script_path_within_allowlist() {
+        let root = tempdir().unwrap();
+        let allowed = root.path().join("scripts");
+        std::fs::create_dir(&allowed).unwrap();
+        std::fs::write(allowed.join("tool.py"), "print('ok')").unwrap();
+
+        let agent = python_agent_for(&allowed);
+        let resolved = agent
+            .validate_script_path(allowed.join("tool.py").to_str().unwrap())
+            .unwrap();
+        assert_eq!(resolved, allowed.join("tool.py").canonicalize().unwrap());
+    }
+
+    #[test]
+    fn test_python_script_path_traversal_rejected() {
+        let root = tempdir().unwrap();
+        let allowed = root.path().join("scripts");
+        std::fs::create_dir(&allowed).unwrap();
+        std::fs::write(root.path().join("outside.py"), "print('escape')").unwrap();
+
+        let agent = python_agent_for(&allowed);
+        let traversal = format!("{}/../outside.py", allowed.display());
+        assert!(agent.validate_script_path(&traversal).is_err());
+
+        // A sibling sharing the allowed directory as a string prefix is not inside it
+        let sibling = root.path().join("scripts_evil");
+        std::fs::create_dir(&sibling).unwrap();
+        std::fs::write(sibling.join("tool.py"), "print('escape')").unwrap();
+        assert!(agent.validate_script_path(sibling.join("tool.py").to_str().unwrap()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_python_stream_delivers_lines_incrementally() {
+        let dir = tempdir().unwrap();
+        let script = dir.path().join("progress.py");
+        std::fs::write(&script, "import sys, time\nprint('step 1')\ntime.sleep(1)\nprint('step 2')\nsys.exit(3)\n").unwrap();
+        let agent = Arc::new(python_agent_for(dir.path()));
+        let memory = Arc::new(Memory::new(
+            Arc::new(HashEmbeddingAgent::new(8)),
+            Arc::new(LengthRerankAgent::new()),
+            Arc::new(crate::memory::redis_store::InMemoryEmbeddingCache::new()),
+        ));
+
+        let (tx, mut rx) = mpsc::channel(8);
+        let input = json!({ "script_path": script.to_str().unwrap(), "args": [] });
+        let run = tokio::spawn({
+            let agent = agent.clone();
+            async move { agent.handle_stream(input, memory, tx).await }
+        });
+
+        // The first line arrives while the script is still sleeping
+        let first = rx.recv().await.unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&first).unwrap(), json!({ "line": "step 1" }));
+        assert!(!run.is_finished());
+
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&rx.recv().await.unwrap()).unwrap(), json!({ "line": "step 2" }));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&rx.recv().await.unwrap()).unwrap(), json!({ "exit_code": 3 }));
+        assert!(run.await.unwrap().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_python_script_symlink_escape_rejected() {
+        let root = tempdir().unwrap();
+        let allowed = root.path().join("scripts");
+        std::fs::create_dir(&allowed).unwrap();
+        std::fs::write(root.path().join("outside.py"), "print('escape')").unwrap();
+        std::os::unix::fs::symlink(root.path().join("outside.py"), allowed.join("link.py")).unwrap();
+
+        let agent = python_agent_for(&allowed);
+        assert!(agent.validate_script_path(allowed.join("link.py").to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_extract_text_input_bare_string() {
+        assert_eq!(extract_text_input(&json!("hello"), "text").unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_extract_text_input_object_with_field() {
+        let input = json!({ "text": "hello", "other": 1 });
+        assert_eq!(extract_text_input(&input, "text").unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_extract_text_input_invalid() {
+        let missing = extract_text_input(&json!({ "code": "1 + 1" }), "text").unwrap_err();
+        assert!(missing.to_string().contains("'text'"));
+
+        let wrong_type = extract_text_input(&json!({ "text": 42 }), "text").unwrap_err();
+        assert!(wrong_type.to_string().contains("must be a string"));
+
+        assert!(extract_text_input(&json!(42), "text").is_err());
+        assert!(extract_text_input(&json!(["hello"]), "text").is_err());
+        assert!(extract_text_input(&serde_json::Value::Null, "text").is_err());
+    }
+
+    #[test]
+    fn test_agent_config_merge_precedence() {
+        let config = AgentFactory::resolve_config("llm", json!({ "model_path": "/models/a.gguf" })).unwrap();
+        assert_eq!(config, json!({ "name": "llm_agent", "model_path": "/models/a.gguf" }));
+
+        let config = AgentFactory::resolve_config(
+            "llm",
+            json!({ "name": "custom", "model_path": "/models/a.gguf" }),
+        ).unwrap();
+        assert_eq!(config["name"], "custom");
+
+        let mut base = json!({ "a": { "x": 1, "y": 2 }, "b": 1 });
+        merge_json(&mut base, json!({ "a": { "y": 3 }, "c": 4 }));
+        assert_eq!(base, json!({ "a": { "x": 1, "y": 3 }, "b": 1, "c": 4 }));
+    }
+
+    #[test]
+    fn test_agent_config_validation_errors() {
+        assert!(AgentFactory::resolve_config("echo", serde_json::Value::Null).is_ok());
+
+        let err = AgentFactory::resolve_config("echo", json!({ "bogus": true })).unwrap_err();
+        assert!(err.to_string().contains("unknown field `bogus`"), "{}", err);
+
+        let err = AgentFactory::resolve_config("llm", json!({})).unwrap_err();
+        assert!(err.to_string().contains("missing field `model_path`"), "{}", err);
+
+        let err = AgentFactory::resolve_config("llm", json!({ "model_path": 7 })).unwrap_err();
+        assert!(err.to_string().contains("invalid type"), "{}", err);
+
+        let err = AgentFactory::resolve_config("llm", json!({ "model_path": " " })).unwrap_err();
+        assert!(err.to_string().contains("'model_path' must not be empty"), "{}", err);
+
+        let err = AgentFactory::resolve_config("llm", json!({ "name": "", "model_path": "/models/a.gguf" })).unwrap_err();
+        assert!(err.to_string().contains("'name' must not be empty"), "{}", err);
+
+        let err = AgentFactory::resolve_config(
+            "llm",
+            json!({ "model_path": "/models/a.gguf", "output_schema": "object" }),
+        ).unwrap_err();
+        assert!(err.to_string().contains("'output_schema'"), "{}", err);
+
+        assert!(AgentFactory::resolve_config("echo", json!("text")).is_err());
+        assert!(AgentFactory::resolve_config("nope", json!({})).is_err());
+    }
+
+    #[test]
+    fn test_script_output_json_detection() {
+        let envelope = |stdout: &str| -> serde_json::Value {
+            serde_json::from_str(&format_script_output(stdout.as_bytes(), true, false).unwrap()).unwrap()
+        };
+
+        assert_eq!(
+            envelope("{\"rows\": [1, 2]}\n"),
+            json!({ "format": "json", "output": { "rows": [1, 2] } })
+        );
+        assert_eq!(envelope("42"), json!({ "format": "json", "output": 42 }));
+
+        // Only stdout that is JSON in its entirety counts
+        assert_eq!(envelope("hello world\n"), json!({ "format": "text", "output": "hello world\n" }));
+        assert_eq!(envelope("{\"a\": 1}\n{\"b\": 2}"), json!({ "format": "text", "output": "{\"a\": 1}\n{\"b\": 2}" }));
+        assert_eq!(envelope("Result: {\"a\": 1}")["format"], "text");
+        assert_eq!(envelope("")["format"], "text");
+
+        // Unless asked for the envelope, stdout is returned as printed
+        assert_eq!(format_script_output(b"{\"a\": 1}", false, false).unwrap(), "{\"a\": 1}");
+        assert_eq!(format_script_output(b"hello\n", false, false).unwrap(), "hello\n");
+    }
+
+    #[test]
+    fn test_script_output_non_utf8() {
+        let bytes = [0x89, b'P', b'N', b'G', 0xff, 0x00];
+        for structured in [false, true] {
+            let envelope: serde_json::Value =
+                serde_json::from_str(&format_script_output(&bytes, structured, false).unwrap()).unwrap();
+            assert_eq!(envelope, json!({ "format": "base64", "output": "iVBOR/8A" }));
+        }
+
+        let err = format_script_output(&bytes, false, true).unwrap_err();
+        assert!(err.to_string().contains("not valid UTF-8"), "{}", err);
+        assert!(format_script_output(b"plain", false, true).is_ok());
+    }
+
+    #[test]
+    fn test_sampling_overrides() {
+        let defaults = SamplingParams::default();
+        assert_eq!(defaults.with_overrides(&json!({"prompt": "hi"})).unwrap(), defaults);
+
+        let creative = defaults
+            .with_overrides(&json!({"temperature": 1.3, "top_p": 0.95, "top_k": 100, "max_tokens": 64, "seed": 7}))
+            .unwrap();
+        assert_eq!(creative, SamplingParams { temperature: 1.3, top_p: 0.95, top_k: 100, max_tokens: 64, seed: 7 });
+
+        let partial = defaults.with_overrides(&json!({"temperature": 0, "seed": null})).unwrap();
+        assert_eq!(partial, SamplingParams { temperature: 0.0, ..defaults });
+    }
+
+    #[test]
+    fn test_sampling_overrides_validated() {
+        for input in [
+            json!({"temperature": 2.5}),
+            json!({"temperature": "hot"}),
+            json!({"top_p": 0}),
+            json!({"top_k": 0}),
+            json!({"max_tokens": 1_000_000}),
+            json!({"seed": -1}),
+            json!({"seed": 5_000_000_000u64}),
+        ] {
+            let err = SamplingParams::default().with_overrides(&input).unwrap_err();
+            assert!(err.is::<InvalidInputError>(), "{} accepted", input);
+        }
+    }
+
+    #[test]
+    fn test_python_malformed_input_is_not_retryable() {
+        let dir = tempdir().unwrap();
+        let agent = python_agent_for(dir.path());
+        let err = agent.prepare_command(json!({"args": ["no script"]})).err().unwrap();
+        assert!(err.is::<InvalidInputError>());
+        assert!(!is_retryable(&err));
+    }
+
+    #[test]
+    fn test_python_working_dir_must_be_task_dir() {
+        let workdir = TaskWorkdir::create().unwrap();
+        let resolved = PythonToolAgent::validate_working_dir(workdir.token()).unwrap();
+        assert_eq!(resolved, workdir.path().canonicalize().unwrap());
+
+        // Someone else's task directory cannot be named by its path
+        assert!(PythonToolAgent::validate_working_dir(workdir.path().to_str().unwrap()).is_err());
+
+        // Nor used once its task is done
+        let token = workdir.token().to_string();
+        let path = workdir.path().to_path_buf();
+        drop(workdir);
+        assert!(!path.exists());
+        assert!(PythonToolAgent::validate_working_dir(&token).is_err());
     }
 }
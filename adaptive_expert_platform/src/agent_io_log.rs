@@ -0,0 +1,140 @@
+//! Sampled debug logging of agent inputs and outputs.
+//!
+//! Logging every payload floods the logs and copies user data into them.
+//! [`AgentIoLogger`] logs a fraction of calls per agent at DEBUG level, and
+//! only a short preview of each payload after secret fields and the
+//! configured redaction patterns have been scrubbed.
+
+use anyhow::Result;
+use dashmap::DashMap;
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tracing::{debug, Level};
+
+use crate::redaction::{is_secret_key, OutputRedactor, REDACTED};
+use crate::settings::Settings;
+
+/// Marker appended to previews cut at the configured length
+const ELLIPSIS: &str = "...";
+
+/// Logs redacted, length-capped previews of a sample of agent calls
+pub struct AgentIoLogger {
+    sample_rate: f64,
+    preview_chars: usize,
+    redactor: OutputRedactor,
+    calls: DashMap<String, AtomicU64>,
+}
+
+impl AgentIoLogger {
+    /// Build the logger from settings, or `None` when the sample rate is zero
+    pub fn from_settings(settings: &Settings) -> Result<Option<Self>> {
+        let config = &settings.logging;
+        if config.agent_io_sample_rate <= 0.0 {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            sample_rate: config.agent_io_sample_rate.min(1.0),
+            preview_chars: config.agent_io_preview_chars,
+            redactor: OutputRedactor::new(&settings.security)?,
+            calls: DashMap::new(),
+        }))
+    }
+
+    /// Whether this call to `agent` should be logged. Calls are sampled at
+    /// even intervals per agent, so a rarely used agent still shows up.
+    pub fn sample(&self, agent: &str) -> bool {
+        if !tracing::enabled!(Level::DEBUG) {
+            return false;
+        }
+        let n = match self.calls.get(agent) {
+            Some(calls) => calls.fetch_add(1, Ordering::Relaxed),
+            None => self.calls.entry(agent.to_string()).or_default().fetch_add(1, Ordering::Relaxed),
+        } as f64;
+        ((n + 1.0) * self.sample_rate).floor() > (n * self.sample_rate).floor()
+    }
+
+    /// Log a sampled call; `output` is `None` for streamed responses
+    pub fn log(
+        &self,
+        agent: &str,
+        input: &Value,
+        output: Option<std::result::Result<&str, &anyhow::Error>>,
+        duration: Duration,
+    ) {
+        let input = self.preview(&redact_secret_fields(input).to_string());
+        match output {
+            Some(Ok(output)) => debug!(
+                agent, input = %input, output = %self.preview(output),
+                duration_ms = duration.as_millis() as u64, "Agent call sample"
+            ),
+            Some(Err(e)) => debug!(
+                agent, input = %input, error = %self.preview(&e.to_string()),
+                duration_ms = duration.as_millis() as u64, "Agent call sample"
+            ),
+            None => debug!(
+                agent, input = %input,
+                duration_ms = duration.as_millis() as u64, "Agent streaming call sample"
+            ),
+        }
+    }
+
+    /// Redact, then cut to `preview_chars` characters
+    fn preview(&self, text: &str) -> String {
+        let redacted = self.redactor.redact(text);
+        match redacted.char_indices().nth(self.preview_chars) {
+            Some((end, _)) => format!("{}{}", &redacted[..end], ELLIPSIS),
+            None => redacted,
+        }
+    }
+}
+
+/// Copy of `value` with the values of secret-looking keys replaced
+fn redact_secret_fields(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    if is_secret_key(key) {
+                        (key.clone(), Value::String(REDACTED.to_string()))
+                    } else {
+                        (key.clone(), redact_secret_fields(value))
+                    }
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(redact_secret_fields).collect()),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn logger(sample_rate: f64, preview_chars: usize) -> AgentIoLogger {
+        let mut settings = Settings::default();
+        settings.logging.agent_io_sample_rate = sample_rate;
+        settings.logging.agent_io_preview_chars = preview_chars;
+        settings.security.output_redaction_patterns = vec![r"sk-[A-Za-z0-9]{8,}".to_string()];
+        AgentIoLogger::from_settings(&settings).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_previews_are_redacted_and_capped() {
+        let logger = logger(1.0, 40);
+        let input = json!({"query": "hi", "auth": {"API_KEY": "abc"}, "items": [{"password": "hunter2"}]});
+        let preview = logger.preview(&redact_secret_fields(&input).to_string());
+        assert!(!preview.contains("abc") && !preview.contains("hunter2"));
+        assert!(preview.ends_with(ELLIPSIS));
+        assert_eq!(preview.chars().count(), 40 + ELLIPSIS.len());
+
+        assert_eq!(logger.preview("key sk-abcdef123456"), format!("key {}", REDACTED));
+    }
+
+    #[test]
+    fn test_disabled_at_zero_sample_rate() {
+        assert!(AgentIoLogger::from_settings(&Settings::default()).unwrap().is_none());
+    }
+}
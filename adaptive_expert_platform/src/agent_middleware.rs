@@ -0,0 +1,231 @@
+//! Transformations applied around every agent call by the orchestrator.
+//!
+//! Unlike the HTTP middleware in [`crate::middleware`], these wrap the agent
+//! invocation itself, so they apply to every transport (HTTP, WebSocket, RPC,
+//! batch). Middlewares run in registration order: `before` hooks first to last,
+//! `after` hooks last to first, so the first one registered is the outermost.
+//! A middleware that must see across the chunks of a streamed output, such as
+//! redaction, keeps per-stream state through [`AgentMiddleware::stream_filter`].
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::debug;
+
+use crate::redaction::OutputRedactor;
+
+/// Hook pair run before and after an agent handles a request
+#[async_trait]
+pub trait AgentMiddleware: Send + Sync {
+    /// Transform the input before it reaches the agent; an error aborts the call
+    async fn before(&self, _agent_name: &str, input: Value) -> Result<Value> {
+        Ok(input)
+    }
+
+    /// Transform the agent's output. For streaming calls this runs per chunk
+    /// unless `stream_filter` returns a filter.
+    async fn after(&self, _agent_name: &str, output: String) -> Result<String> {
+        Ok(output)
+    }
+
+    /// State for transforming one streamed output in place of `after`
+    fn stream_filter(&self, _agent_name: &str) -> Option<Box<dyn StreamFilter>> {
+        None
+    }
+}
+
+/// Transforms the chunks of one streamed output, possibly holding text
+/// back until a later chunk arrives
+#[async_trait]
+pub trait StreamFilter: Send {
+    async fn chunk(&mut self, chunk: String) -> Result<String>;
+
+    /// Release whatever is still held back once the stream ends
+    async fn finish(&mut self) -> Result<String>;
+}
+
+/// Run `before` hooks in registration order
+pub(crate) async fn apply_before(
+    middlewares: &[Arc<dyn AgentMiddleware>],
+    agent_name: &str,
+    mut input: Value,
+) -> Result<Value> {
+    for middleware in middlewares {
+        input = middleware.before(agent_name, input).await?;
+    }
+    Ok(input)
+}
+
+/// Run `after` hooks in reverse registration order
+pub(crate) async fn apply_after(
+    middlewares: &[Arc<dyn AgentMiddleware>],
+    agent_name: &str,
+    mut output: String,
+) -> Result<String> {
+    for middleware in middlewares.iter().rev() {
+        output = middleware.after(agent_name, output).await?;
+    }
+    Ok(output)
+}
+
+/// `after` hooks of one streamed call, in the order they run
+pub(crate) struct StreamAfter {
+    agent_name: String,
+    stages: Vec<(Arc<dyn AgentMiddleware>, Option<Box<dyn StreamFilter>>)>,
+}
+
+impl StreamAfter {
+    pub(crate) fn new(middlewares: &[Arc<dyn AgentMiddleware>], agent_name: &str) -> Self {
+        let stages = middlewares.iter().rev()
+            .map(|middleware| (middleware.clone(), middleware.stream_filter(agent_name)))
+            .collect();
+        Self { agent_name: agent_name.to_string(), stages }
+    }
+
+    pub(crate) async fn chunk(&mut self, chunk: String) -> Result<String> {
+        self.run_from(0, chunk).await
+    }
+
+    /// Flush each stage, passing what it held back through the stages after it
+    pub(crate) async fn finish(&mut self) -> Result<String> {
+        let mut output = String::new();
+        for index in 0..self.stages.len() {
+            let held = match &mut self.stages[index].1 {
+                Some(filter) => filter.finish().await?,
+                None => continue,
+            };
+            if !held.is_empty() {
+                output.push_str(&self.run_from(index + 1, held).await?);
+            }
+        }
+        Ok(output)
+    }
+
+    async fn run_from(&mut self, first: usize, mut chunk: String) -> Result<String> {
+        for (middleware, filter) in &mut self.stages[first..] {
+            chunk = match filter {
+                Some(filter) => filter.chunk(chunk).await?,
+                None => middleware.after(&self.agent_name, chunk).await?,
+            };
+        }
+        Ok(chunk)
+    }
+}
+
+/// Logs the size of every agent input and output
+pub struct LoggingMiddleware;
+
+#[async_trait]
+impl AgentMiddleware for LoggingMiddleware {
+    async fn before(&self, agent_name: &str, input: Value) -> Result<Value> {
+        debug!("Agent '{}' input: {} bytes", agent_name, input.to_string().len());
+        Ok(input)
+    }
+
+    async fn after(&self, agent_name: &str, output: String) -> Result<String> {
+        debug!("Agent '{}' output: {} bytes", agent_name, output.len());
+        Ok(output)
+    }
+}
+
+/// Bytes of redacted stream output held back for rescanning with the next
+/// chunk; a secret split across chunks is caught when its prefix fits in it
+const STREAM_REDACTION_WINDOW: usize = 256;
+
+/// Redacts secrets from agent output with the configured [`OutputRedactor`]
+pub struct RedactionMiddleware {
+    redactor: Arc<OutputRedactor>,
+}
+
+impl RedactionMiddleware {
+    pub fn new(redactor: Arc<OutputRedactor>) -> Self {
+        Self { redactor }
+    }
+}
+
+#[async_trait]
+impl AgentMiddleware for RedactionMiddleware {
+    async fn after(&self, _agent_name: &str, output: String) -> Result<String> {
+        Ok(self.redactor.redact(&output))
+    }
+
+    fn stream_filter(&self, _agent_name: &str) -> Option<Box<dyn StreamFilter>> {
+        Some(Box::new(RedactingStream { redactor: self.redactor.clone(), held: String::new() }))
+    }
+}
+
+/// Redacts a stream over the chunk joined to the tail of the previous one
+struct RedactingStream {
+    redactor: Arc<OutputRedactor>,
+    held: String,
+}
+
+#[async_trait]
+impl StreamFilter for RedactingStream {
+    async fn chunk(&mut self, chunk: String) -> Result<String> {
+        self.held.push_str(&chunk);
+        let mut redacted = self.redactor.redact(&self.held);
+        let mut split = redacted.len().saturating_sub(STREAM_REDACTION_WINDOW);
+        while !redacted.is_char_boundary(split) {
+            split -= 1;
+        }
+        self.held = redacted.split_off(split);
+        Ok(redacted)
+    }
+
+    async fn finish(&mut self) -> Result<String> {
+        Ok(std::mem::take(&mut self.held))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Tag(&'static str);
+
+    #[async_trait]
+    impl AgentMiddleware for Tag {
+        async fn before(&self, _agent_name: &str, input: Value) -> Result<Value> {
+            Ok(Value::String(format!("{}{}", input.as_str().unwrap_or_default(), self.0)))
+        }
+
+        async fn after(&self, _agent_name: &str, output: String) -> Result<String> {
+            Ok(format!("{}{}", output, self.0))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_ordering() {
+        let chain: Vec<Arc<dyn AgentMiddleware>> = vec![Arc::new(Tag("a")), Arc::new(Tag("b"))];
+
+        let input = apply_before(&chain, "echo", Value::String(String::new())).await.unwrap();
+        assert_eq!(input, Value::String("ab".to_string()));
+
+        let output = apply_after(&chain, "echo", String::new()).await.unwrap();
+        assert_eq!(output, "ba");
+    }
+
+    #[tokio::test]
+    async fn test_stream_redaction_spans_chunks() {
+        let config = crate::settings::SecurityConfig {
+            enable_output_redaction: true,
+            output_redaction_patterns: vec!["sk-[a-z0-9]{8,}".to_string()],
+            ..crate::settings::SecurityConfig::default()
+        };
+        let redactor = Arc::new(OutputRedactor::new(&config).unwrap());
+        let chain: Vec<Arc<dyn AgentMiddleware>> = vec![Arc::new(Tag("!")), Arc::new(RedactionMiddleware::new(redactor))];
+        let mut stream = StreamAfter::new(&chain, "echo");
+
+        let long = "x".repeat(STREAM_REDACTION_WINDOW * 2);
+        let mut output = String::new();
+        for chunk in [long.as_str(), " key sk-abcd", "efgh1234 done"] {
+            output.push_str(&stream.chunk(chunk.to_string()).await.unwrap());
+        }
+        output.push_str(&stream.finish().await.unwrap());
+        // Held-back text still passes through the outer middleware
+        assert!(output.ends_with(" done!"));
+        assert_eq!(output.replace('!', ""), format!("{} key {} done", long, crate::redaction::REDACTED));
+    }
+}
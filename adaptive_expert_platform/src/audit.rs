@@ -0,0 +1,390 @@
+//! Append-only audit log of security-relevant actions.
+//!
+//! Entries are stored as JSON lines. Each entry carries the hash of the one
+//! before it, so editing or deleting a line breaks the chain and is detected by
+//! [`AuditLog::verify_chain`]. Writes are queued to a background task and never
+//! block the request that triggered them.
+//!
+//! A crash mid-write can leave a partial last line. Readers skip it with a
+//! warning, and [`AuditLog::open`] truncates it before appending, so the
+//! entries before it stay readable and verifiable.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{error, info, warn};
+
+/// Events queued beyond this are dropped (and logged) rather than blocking callers
+const QUEUE_CAPACITY: usize = 1024;
+
+/// Hash preceding the first entry of a log
+const GENESIS_HASH: &str = "0";
+
+/// A security-relevant action to record
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    pub actor: String,
+    pub action: String,
+    pub target: Option<String>,
+    pub source_ip: Option<String>,
+}
+
+/// A recorded audit entry
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditEntry {
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub actor: String,
+    pub action: String,
+    pub target: Option<String>,
+    pub source_ip: Option<String>,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditEntry {
+    /// Hash over every field except `hash` itself
+    fn compute_hash(&self) -> String {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(self.prev_hash.as_bytes());
+        hasher.update(&self.seq.to_le_bytes());
+        hasher.update(self.timestamp.to_rfc3339().as_bytes());
+        for field in [Some(&self.actor), Some(&self.action), self.target.as_ref(), self.source_ip.as_ref()] {
+            // Length-prefix fields so values cannot be shifted between them
+            let value = field.map(String::as_str).unwrap_or("");
+            hasher.update(&(value.len() as u64).to_le_bytes());
+            hasher.update(value.as_bytes());
+            hasher.update(&[field.is_some() as u8]);
+        }
+        hasher.finalize().to_hex().to_string()
+    }
+}
+
+/// Filter for querying the audit log
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditQuery {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.actor.as_ref().map_or(true, |actor| &entry.actor == actor)
+            && self.action.as_ref().map_or(true, |action| &entry.action == action)
+            && self.since.map_or(true, |since| entry.timestamp >= since)
+            && self.until.map_or(true, |until| entry.timestamp <= until)
+    }
+}
+
+enum Command {
+    Record(AuditEvent),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Handle to the audit log file and its background writer
+pub struct AuditLog {
+    path: PathBuf,
+    tx: mpsc::Sender<Command>,
+}
+
+impl AuditLog {
+    /// Open (or create) the log at `path` and start the writer task
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent).await
+                .with_context(|| format!("Failed to create audit log directory {:?}", parent))?;
+        }
+
+        let contents = read_log(&path).await?;
+        if let Err(e) = check_chain(&contents.entries) {
+            error!("Audit log {:?} failed verification: {}", path, e);
+        }
+        // Continue the chain from the last entry already on disk
+        let (next_seq, prev_hash) = match contents.entries.last() {
+            Some(last) => (last.seq + 1, last.hash.clone()),
+            None => (0, GENESIS_HASH.to_string()),
+        };
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .with_context(|| format!("Failed to open audit log {:?}", path))?;
+        match contents.end {
+            LogEnd::Complete => {}
+            LogEnd::Torn(offset) => {
+                warn!("Truncating partial audit entry at byte {} of {:?}", offset, path);
+                file.set_len(offset).await
+                    .with_context(|| format!("Failed to truncate audit log {:?}", path))?;
+            }
+            // The last entry lost only its newline; terminate it before appending
+            LogEnd::Unterminated => file.write_all(b"\n").await
+                .with_context(|| format!("Failed to repair audit log {:?}", path))?,
+        }
+
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(run_writer(file, rx, next_seq, prev_hash));
+
+        info!("Audit log opened at {:?}", path);
+        Ok(Self { path, tx })
+    }
+
+    /// Queue an event; never waits, drops the event if the writer is backed up
+    pub fn record(&self, event: AuditEvent) {
+        if let Err(e) = self.tx.try_send(Command::Record(event)) {
+            let event = match e {
+                mpsc::error::TrySendError::Full(Command::Record(event))
+                | mpsc::error::TrySendError::Closed(Command::Record(event)) => event,
+                _ => return,
+            };
+            warn!(
+                "Audit log unavailable, dropped event: actor={} action={} target={:?}",
+                event.actor, event.action, event.target
+            );
+        }
+    }
+
+    /// Wait until every event queued so far has been written
+    pub async fn flush(&self) -> Result<()> {
+        let (done_tx, done_rx) = oneshot::channel();
+        self.tx.send(Command::Flush(done_tx)).await
+            .map_err(|_| anyhow!("Audit log writer has stopped"))?;
+        done_rx.await.map_err(|_| anyhow!("Audit log writer has stopped"))
+    }
+
+    /// Return entries matching `query`, newest first
+    pub async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        let entries = read_entries(&self.path).await?;
+        Ok(entries
+            .into_iter()
+            .rev()
+            .filter(|entry| query.matches(entry))
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// Check the hash chain, returning the number of entries verified
+    pub async fn verify_chain(&self) -> Result<usize> {
+        let entries = read_entries(&self.path).await?;
+        check_chain(&entries)?;
+        Ok(entries.len())
+    }
+}
+
+fn check_chain(entries: &[AuditEntry]) -> Result<()> {
+    let mut prev_hash = GENESIS_HASH;
+    for (index, entry) in entries.iter().enumerate() {
+        if entry.seq != index as u64 || entry.prev_hash != prev_hash || entry.hash != entry.compute_hash() {
+            return Err(anyhow!("Audit log chain broken at entry {}", index));
+        }
+        prev_hash = &entry.hash;
+    }
+    Ok(())
+}
+
+async fn run_writer(
+    mut file: tokio::fs::File,
+    mut rx: mpsc::Receiver<Command>,
+    mut next_seq: u64,
+    mut prev_hash: String,
+) {
+    while let Some(command) = rx.recv().await {
+        let event = match command {
+            Command::Record(event) => event,
+            Command::Flush(done) => {
+                let _ = done.send(());
+                continue;
+            }
+        };
+
+        let mut entry = AuditEntry {
+            seq: next_seq,
+            timestamp: Utc::now(),
+            actor: event.actor,
+            action: event.action,
+            target: event.target,
+            source_ip: event.source_ip,
+            prev_hash: prev_hash.clone(),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+
+        let mut line = match serde_json::to_string(&entry) {
+            Ok(line) => line,
+            Err(e) => {
+                error!("Failed to serialize audit entry: {}", e);
+                continue;
+            }
+        };
+        line.push('\n');
+
+        // Only advance the chain once the entry is on disk
+        let written = async {
+            file.write_all(line.as_bytes()).await?;
+            file.sync_data().await
+        }.await;
+
+        match written {
+            Ok(()) => {
+                next_seq += 1;
+                prev_hash = entry.hash;
+            }
+            Err(e) => error!("Failed to write audit entry {}: {}", entry.seq, e),
+        }
+    }
+}
+
+/// How the log file ends
+#[derive(Debug, PartialEq)]
+enum LogEnd {
+    Complete,
+    /// The last line, starting at this byte offset, is a partial entry
+    Torn(u64),
+    /// The last entry is intact but missing its newline
+    Unterminated,
+}
+
+struct LogContents {
+    entries: Vec<AuditEntry>,
+    end: LogEnd,
+}
+
+async fn read_entries(path: &Path) -> Result<Vec<AuditEntry>> {
+    Ok(read_log(path).await?.entries)
+}
+
+async fn read_log(path: &Path) -> Result<LogContents> {
+    let contents = match tokio::fs::read(path).await {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(LogContents { entries: Vec::new(), end: LogEnd::Complete });
+        }
+        Err(e) => return Err(anyhow!("Failed to read audit log {:?}: {}", path, e)),
+    };
+    parse_log(&contents, path)
+}
+
+/// Parse JSON lines, tolerating a malformed final line (a torn write) but
+/// failing on malformed lines anywhere else
+fn parse_log(contents: &[u8], path: &Path) -> Result<LogContents> {
+    let mut entries = Vec::new();
+    let mut end = LogEnd::Complete;
+    let mut offset = 0;
+    let mut lines = contents.split_inclusive(|b| *b == b'\n').enumerate().peekable();
+    while let Some((index, line)) = lines.next() {
+        let start = offset;
+        offset += line.len();
+        let is_last = lines.peek().is_none();
+        if line.trim_ascii().is_empty() {
+            continue;
+        }
+        match serde_json::from_slice::<AuditEntry>(line) {
+            Ok(entry) => {
+                if is_last && !line.ends_with(b"\n") {
+                    end = LogEnd::Unterminated;
+                }
+                entries.push(entry);
+            }
+            Err(e) if is_last => {
+                warn!("Ignoring partial audit entry at line {} of {:?}: {}", index + 1, path, e);
+                end = LogEnd::Torn(start as u64);
+            }
+            Err(e) => return Err(anyhow!("Malformed audit entry at line {}: {}", index + 1, e)),
+        }
+    }
+    Ok(LogContents { entries, end })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn event(actor: &str, action: &str) -> AuditEvent {
+        AuditEvent {
+            actor: actor.to_string(),
+            action: action.to_string(),
+            target: Some("target".to_string()),
+            source_ip: Some("127.0.0.1".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_record_and_query() {
+        let dir = tempdir().unwrap();
+        let log = AuditLog::open(dir.path().join("audit.jsonl")).await.unwrap();
+
+        log.record(event("alice", "login"));
+        log.record(event("admin", "agent_register"));
+        log.record(event("alice", "password_change"));
+        log.flush().await.unwrap();
+
+        let all = log.query(&AuditQuery::default()).await.unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].action, "password_change");
+
+        let alice = log.query(&AuditQuery { actor: Some("alice".into()), ..Default::default() }).await.unwrap();
+        assert_eq!(alice.len(), 2);
+
+        let limited = log.query(&AuditQuery { limit: Some(1), ..Default::default() }).await.unwrap();
+        assert_eq!(limited.len(), 1);
+
+        let future = log.query(&AuditQuery { since: Some(Utc::now() + chrono::Duration::hours(1)), ..Default::default() }).await.unwrap();
+        assert!(future.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_chain_survives_reopen_and_detects_tampering() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+
+        let log = AuditLog::open(&path).await.unwrap();
+        log.record(event("alice", "login"));
+        log.flush().await.unwrap();
+        drop(log);
+
+        let log = AuditLog::open(&path).await.unwrap();
+        log.record(event("bob", "login"));
+        log.flush().await.unwrap();
+        assert_eq!(log.verify_chain().await.unwrap(), 2);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, contents.replace("\"bob\"", "\"mallory\"")).unwrap();
+        assert!(log.verify_chain().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_torn_trailing_entry_is_truncated_on_open() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+
+        let log = AuditLog::open(&path).await.unwrap();
+        log.record(event("alice", "login"));
+        log.flush().await.unwrap();
+        drop(log);
+
+        // Simulate a crash part-way through writing the next entry
+        let mut torn = std::fs::read(&path).unwrap();
+        torn.extend_from_slice(b"{\"seq\":1,\"timesta");
+        std::fs::write(&path, &torn).unwrap();
+
+        let log = AuditLog::open(&path).await.unwrap();
+        assert_eq!(log.verify_chain().await.unwrap(), 1);
+        log.record(event("bob", "login"));
+        log.flush().await.unwrap();
+        assert_eq!(log.verify_chain().await.unwrap(), 2);
+
+        // Only the last line may be malformed
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, format!("garbage\n{}", contents)).unwrap();
+        assert!(log.verify_chain().await.is_err());
+    }
+}
@@ -4,18 +4,24 @@ use anyhow::{anyhow, Result};
 use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
 use argon2::password_hash::{rand_core::OsRng, SaltString};
 use axum::{
-    extract::{Request, State},
-    http::{HeaderMap, StatusCode},
+    extract::{MatchedPath, Request, State},
+    http::{HeaderMap, Method, StatusCode},
     middleware::Next,
     response::Response,
 };
+use hmac::{Hmac, Mac};
 use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use lru::LruCache;
+use sha2::Sha256;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tracing::{error, info, warn};
 
 /// JWT claims structure
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,  // Subject (user ID)
     pub exp: usize,   // Expiration time
@@ -33,27 +39,198 @@ pub struct User {
     pub active: bool,
 }
 
+/// Error returned while the auth database is unavailable
+#[derive(Debug)]
+pub struct AuthUnavailableError;
+
+impl std::fmt::Display for AuthUnavailableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Authentication database is unavailable")
+    }
+}
+
+impl std::error::Error for AuthUnavailableError {}
+
+/// Hit/miss counters for the auth user cache
+#[derive(Debug, Clone, Serialize)]
+pub struct UserCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+/// Bounded LRU cache of users read from the database. Entries older than
+/// `ttl` are re-read so changes made outside this process are picked up.
+///
+/// The lock only guards the in-memory map; database reads and writes happen
+/// outside it. Every invalidation bumps `generation`, and a load only caches
+/// its row if no invalidation happened while it was reading, so a row read
+/// before a concurrent write can never overwrite the fresh state.
+struct UserCache {
+    entries: Mutex<LruCache<String, (User, Instant)>>,
+    generation: AtomicU64,
+    ttl: Duration,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl UserCache {
+    fn new(capacity: usize, ttl: Duration) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+            generation: AtomicU64::new(0),
+            ttl,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, LruCache<String, (User, Instant)>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Return the cached user, or `load` it without holding the lock and
+    /// cache the result unless the user was invalidated in the meantime
+    fn get_or_load(&self, username: &str, load: impl FnOnce() -> Result<User>) -> Result<User> {
+        let generation = {
+            let mut entries = self.entries();
+            match entries.get(username) {
+                Some((user, cached_at)) if cached_at.elapsed() < self.ttl => {
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    return Ok(user.clone());
+                }
+                Some(_) => {
+                    entries.pop(username);
+                }
+                None => {}
+            }
+            // Read under the lock so it orders against `invalidate`
+            self.generation.load(Ordering::Acquire)
+        };
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let user = load()?;
+        let mut entries = self.entries();
+        if self.generation.load(Ordering::Acquire) == generation {
+            entries.put(username.to_string(), (user.clone(), Instant::now()));
+        }
+        Ok(user)
+    }
+
+    /// Drop the cached entry after its row changed; loads already in flight
+    /// will not cache what they read
+    fn invalidate(&self, username: &str) {
+        let mut entries = self.entries();
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        entries.pop(username);
+    }
+
+    fn clear(&self) {
+        let mut entries = self.entries();
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        entries.clear();
+    }
+
+    fn stats(&self) -> UserCacheStats {
+        let entries = self.entries();
+        UserCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: entries.len(),
+            capacity: entries.cap().get(),
+        }
+    }
+}
+
+/// Default number of users kept in the cache
+const DEFAULT_USER_CACHE_SIZE: usize = 1024;
+
+/// Default age after which cached users are re-read from the database
+const DEFAULT_USER_CACHE_TTL: Duration = Duration::from_secs(60);
+
 /// Authentication manager using a persistent sled database
 #[derive(Clone)]
 pub struct AuthManager {
-    db: Arc<sled::Db>,
+    /// `None` while running degraded without a database
+    db: Arc<std::sync::RwLock<Option<Arc<sled::Db>>>>,
+    db_path: String,
     jwt_secret: String,
     jwt_expiry_hours: usize,
+    user_cache: Arc<UserCache>,
 }
 
 impl AuthManager {
     /// Creates a new AuthManager, opening or creating a sled database at the given path.
     pub fn new(jwt_secret: String, db_path: &str) -> Result<Self> {
-        let db = sled::open(db_path)
-            .map_err(|e| anyhow!("Failed to open auth database at '{}': {}", db_path, e))?;
+        let db = Self::open_db(db_path)?;
         info!("Authentication database opened at '{}'", db_path);
         Ok(Self {
-            db: Arc::new(db),
+            db: Arc::new(std::sync::RwLock::new(Some(Arc::new(db)))),
+            db_path: db_path.to_string(),
             jwt_secret,
             jwt_expiry_hours: 24, // 24 hour expiry
+            user_cache: Arc::new(UserCache::new(DEFAULT_USER_CACHE_SIZE, DEFAULT_USER_CACHE_TTL)),
         })
     }
 
+    /// Creates an AuthManager without a database. Every database operation
+    /// fails with [`AuthUnavailableError`] until `try_reconnect` succeeds.
+    pub fn new_degraded(jwt_secret: String, db_path: &str) -> Self {
+        Self {
+            db: Arc::new(std::sync::RwLock::new(None)),
+            db_path: db_path.to_string(),
+            jwt_secret,
+            jwt_expiry_hours: 24,
+            user_cache: Arc::new(UserCache::new(DEFAULT_USER_CACHE_SIZE, DEFAULT_USER_CACHE_TTL)),
+        }
+    }
+
+    /// Cache up to `capacity` users, re-reading each from the database once
+    /// it is older than `ttl`
+    pub fn with_user_cache(mut self, capacity: usize, ttl: Duration) -> Self {
+        self.user_cache = Arc::new(UserCache::new(capacity, ttl));
+        self
+    }
+
+    /// Hit/miss counters and occupancy of the user cache
+    pub fn user_cache_stats(&self) -> UserCacheStats {
+        self.user_cache.stats()
+    }
+
+    /// Open the database and bring its schema up to date
+    fn open_db(db_path: &str) -> Result<sled::Db> {
+        let db = sled::open(db_path)
+            .map_err(|e| anyhow!("Failed to open auth database at '{}': {}", db_path, e))?;
+        crate::auth_migrations::migrate(&db)?;
+        Ok(db)
+    }
+
+    /// Whether the auth database is connected
+    pub fn is_available(&self) -> bool {
+        self.db.read().unwrap_or_else(|e| e.into_inner()).is_some()
+    }
+
+    /// Try to open the database if it is unavailable; returns whether it is now available
+    pub fn try_reconnect(&self) -> Result<bool> {
+        if self.is_available() {
+            return Ok(true);
+        }
+        let db = Self::open_db(&self.db_path)?;
+        *self.db.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(db));
+        self.user_cache.clear();
+        info!("Authentication database reconnected at '{}'", self.db_path);
+        Ok(true)
+    }
+
+    fn db(&self) -> Result<Arc<sled::Db>> {
+        self.db.read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .ok_or_else(|| AuthUnavailableError.into())
+    }
+
     /// Initialize the first admin user during setup
     pub fn initialize_admin(&self, username: String, password: &str) -> Result<()> {
         if self.has_admin()? {
@@ -70,15 +247,15 @@ impl AuthManager {
         };
 
         let user_bytes = bincode::serialize(&user)?;
-        self.db.insert(username.as_bytes(), user_bytes)?;
-        self.db.flush()?; // Ensure data is written to disk
+        self.db()?.insert(username.as_bytes(), user_bytes)?;
+        self.db()?.flush()?; // Ensure data is written to disk
 
         Ok(())
     }
 
     /// Check if an admin user exists in the database
     pub fn has_admin(&self) -> Result<bool> {
-        for item in self.db.iter() {
+        for item in self.db()?.iter() {
             let (_, user_bytes) = item?;
             let user: User = bincode::deserialize(&user_bytes)?;
             if user.roles.contains(&"admin".to_string()) {
@@ -110,10 +287,7 @@ impl AuthManager {
 
     /// Authenticate user and return JWT token
     pub fn authenticate(&self, username: &str, password: &str) -> Result<String> {
-        let user_bytes = self.db.get(username.as_bytes())?
-            .ok_or_else(|| anyhow!("User not found"))?;
-
-        let user: User = bincode::deserialize(&user_bytes)?;
+        let user = self.get_user(username)?;
 
         if !user.active {
             return Err(anyhow!("User account is disabled"));
@@ -158,6 +332,42 @@ impl AuthManager {
         Ok(token_data.claims)
     }
 
+    /// Sign `path` so it can be fetched without a token until `expires`
+    /// (Unix seconds). Returns the hex-encoded HMAC-SHA256 signature.
+    pub fn sign_url(&self, path: &str, expires: u64) -> String {
+        let mut mac = self.url_mac(path, expires);
+        let tag = mac.finalize_reset().into_bytes();
+        tag.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Verify a signature produced by `sign_url`.
+    ///
+    /// Fails if the URL has expired or the signature does not match `path`
+    /// and `expires`; the comparison is constant-time.
+    pub fn verify_signed_url(&self, path: &str, expires: u64, signature: &str) -> Result<()> {
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        if now > expires {
+            return Err(anyhow!("Signed URL has expired"));
+        }
+
+        let signature = decode_hex(signature)
+            .ok_or_else(|| anyhow!("Malformed URL signature"))?;
+        self.url_mac(path, expires)
+            .verify_slice(&signature)
+            .map_err(|_| anyhow!("Invalid URL signature"))
+    }
+
+    fn url_mac(&self, path: &str, expires: u64) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.jwt_secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        // Domain-separate from JWT signatures made with the same secret
+        mac.update(b"signed-url\n");
+        mac.update(path.as_bytes());
+        mac.update(b"\n");
+        mac.update(expires.to_string().as_bytes());
+        mac
+    }
+
     /// Check if user has required role
     pub fn has_role(&self, claims: &Claims, required_role: &str) -> bool {
         claims.roles.contains(&required_role.to_string())
@@ -165,7 +375,8 @@ impl AuthManager {
 
     /// Add new user (admin only)
     pub fn add_user(&self, username: String, password: &str, roles: Vec<String>) -> Result<()> {
-        if self.db.contains_key(username.as_bytes())? {
+        let db = self.db()?;
+        if db.contains_key(username.as_bytes())? {
             return Err(anyhow!("User already exists"));
         }
 
@@ -178,37 +389,74 @@ impl AuthManager {
             active: true,
         };
 
+        // Insert only if the name is still free after hashing the password
         let user_bytes = bincode::serialize(&user)?;
-        self.db.insert(username.as_bytes(), user_bytes)?;
-        self.db.flush()?;
+        if db.compare_and_swap(username.as_bytes(), None as Option<&[u8]>, Some(user_bytes))?.is_err() {
+            return Err(anyhow!("User already exists"));
+        }
+        db.flush()?;
+        self.user_cache.invalidate(&username);
         Ok(())
     }
 
     /// Update user password
     pub fn update_password(&self, username: &str, new_password: &str) -> Result<()> {
-        let mut user = self.get_user(username)?;
-        user.password_hash = Self::hash_password(new_password)?;
-        self.update_user(&user)
+        let password_hash = Self::hash_password(new_password)?;
+        self.update_user(username, |user| user.password_hash = password_hash.clone())
+    }
+
+    /// Replace a user's roles; tokens already issued keep their old roles
+    /// until they expire
+    pub fn set_roles(&self, username: &str, roles: Vec<String>) -> Result<()> {
+        self.update_user(username, |user| user.roles = roles.clone())
     }
 
     /// Disable user account
     pub fn disable_user(&self, username: &str) -> Result<()> {
-        let mut user = self.get_user(username)?;
-        user.active = false;
-        self.update_user(&user)
+        self.update_user(username, |user| user.active = false)
     }
 
     fn get_user(&self, username: &str) -> Result<User> {
-        let user_bytes = self.db.get(username)?.ok_or_else(|| anyhow!("User not found"))?;
-        let user: User = bincode::deserialize(&user_bytes)?;
-        Ok(user)
+        self.user_cache.get_or_load(username, || {
+            let user_bytes = self.db()?.get(username)?.ok_or_else(|| anyhow!("User not found"))?;
+            Ok(bincode::deserialize(&user_bytes)?)
+        })
     }
 
-    fn update_user(&self, user: &User) -> Result<()> {
-        let user_bytes = bincode::serialize(user)?;
-        self.db.insert(user.username.as_bytes(), user_bytes)?;
-        self.db.flush()?;
-        Ok(())
+    /// Apply `change` to the stored row atomically. sled retries the
+    /// read-modify-write if another writer got in first, so concurrent updates
+    /// to different fields are never lost. The cached entry is dropped
+    /// afterwards whether or not the write succeeded.
+    fn update_user(&self, username: &str, change: impl Fn(&mut User)) -> Result<()> {
+        let db = self.db()?;
+        let mut failure = None;
+        let updated = db.update_and_fetch(username.as_bytes(), |current| {
+            failure = None;
+            let bytes = current?;
+            let mut user: User = match bincode::deserialize(bytes) {
+                Ok(user) => user,
+                Err(e) => {
+                    failure = Some(anyhow!("Corrupt user record for '{}': {}", username, e));
+                    return Some(bytes.to_vec());
+                }
+            };
+            change(&mut user);
+            match bincode::serialize(&user) {
+                Ok(bytes) => Some(bytes),
+                Err(e) => {
+                    failure = Some(e.into());
+                    Some(bytes.to_vec())
+                }
+            }
+        });
+        let result = match (updated, failure) {
+            (Err(e), _) => Err(e.into()),
+            (Ok(_), Some(e)) => Err(e),
+            (Ok(None), None) => Err(anyhow!("User not found")),
+            (Ok(Some(_)), None) => db.flush().map(|_| ()).map_err(Into::into),
+        };
+        self.user_cache.invalidate(username);
+        result
     }
 }
 
@@ -237,6 +485,11 @@ pub async fn auth_middleware(
         return Ok(next.run(request).await);
     }
 
+    if !auth_manager.is_available() {
+        warn!("Rejecting request to {}: authentication database unavailable", request.uri().path());
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+
     let token = match extract_token(request.headers()) {
         Ok(token) => token,
         Err(_) => {
@@ -275,6 +528,192 @@ pub fn require_role(required_role: &'static str) -> impl Fn(Request, Next) -> st
     }
 }
 
+/// A request being authorized: HTTP method, matched route template and the
+/// resource named by the route's path parameter, if any
+#[derive(Debug, Clone)]
+pub struct Action {
+    pub method: Method,
+    /// Route template, e.g. `/agents/:name`
+    pub route: String,
+    /// Value of the route's path parameter, e.g. the agent name
+    pub resource: Option<String>,
+}
+
+impl Action {
+    /// Build an action from a route template and the concrete request path
+    pub fn new(method: Method, route: &str, path: &str) -> Self {
+        let resource = route
+            .split('/')
+            .zip(path.split('/'))
+            .find(|(template, _)| template.starts_with(':') || template.starts_with('*'))
+            .map(|(_, value)| value.to_string());
+
+        Self {
+            method,
+            route: route.to_string(),
+            resource,
+        }
+    }
+
+    /// Whether the action only reads state
+    pub fn is_read_only(&self) -> bool {
+        matches!(self.method, Method::GET | Method::HEAD | Method::OPTIONS)
+    }
+}
+
+/// Authorization policy evaluated for every authenticated request
+pub trait Authorizer: Send + Sync {
+    fn authorize(&self, claims: &Claims, action: &Action) -> bool;
+}
+
+/// Role-based policy: listed (method, route) pairs require a role, every other
+/// route is open to any authenticated user
+pub struct RoleAuthorizer {
+    rules: Vec<(Method, String, String)>,
+}
+
+impl RoleAuthorizer {
+    pub fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Require `role` for `method` on `route`
+    pub fn require(mut self, method: Method, route: &str, role: &str) -> Self {
+        self.rules.push((method, route.to_string(), role.to_string()));
+        self
+    }
+}
+
+impl Default for RoleAuthorizer {
+    /// Admin-only agent and model management, user creation and quotas, audit and trace log access,
+    /// deployment scaling, WebSocket session listing, plugin management,
+    /// configuration reloads and metrics snapshots
+    fn default() -> Self {
+        Self::new()
+            .require(Method::POST, "/agents", "admin")
+            .require(Method::DELETE, "/agents/:name", "admin")
+            .require(Method::POST, "/agents/:name/model", "admin")
+            .require(Method::POST, "/agents/:name/disable", "admin")
+            .require(Method::POST, "/agents/:name/enable", "admin")
+            .require(Method::POST, "/auth/users", "admin")
+            .require(Method::PUT, "/auth/users/:name/quota", "admin")
+            .require(Method::DELETE, "/auth/users/:name/quota", "admin")
+            .require(Method::GET, "/audit", "admin")
+            .require(Method::GET, "/trace", "admin")
+            .require(Method::POST, "/trace/:id/replay", "admin")
+            .require(Method::POST, "/deployments/:name/scale", "admin")
+            .require(Method::GET, "/ws/connections", "admin")
+            .require(Method::GET, "/ws/stats", "admin")
+            .require(Method::GET, "/plugins", "admin")
+            .require(Method::POST, "/plugins/reload", "admin")
+            .require(Method::POST, "/memory/repair", "admin")
+            .require(Method::POST, "/memory/prefetch", "admin")
+            .require(Method::POST, "/admin/reload-config", "admin")
+            .require(Method::POST, "/admin/metrics/snapshot", "admin")
+            .require(Method::POST, "/admin/julia/gc", "admin")
+    }
+}
+
+impl Authorizer for RoleAuthorizer {
+    fn authorize(&self, claims: &Claims, action: &Action) -> bool {
+        self.rules
+            .iter()
+            .filter(|(method, route, _)| *method == action.method && *route == action.route)
+            .all(|(_, _, role)| claims.roles.contains(role))
+    }
+}
+
+/// Example attribute-based policy layered over role rules:
+///
+/// * users with the `readonly` role may only perform read-only requests;
+/// * a user holding `owner:<resource>` may use the owner routes on that
+///   resource (e.g. `POST /agents/my_agent/disable`) even where the role rules
+///   would require admin. Destructive routes such as deleting an agent or
+///   reloading its model stay admin-only.
+pub struct AttributeAuthorizer {
+    roles: RoleAuthorizer,
+    /// (method, route) pairs an owner of the named resource may use
+    owner_routes: Vec<(Method, String)>,
+}
+
+impl AttributeAuthorizer {
+    /// Owners may take their agents in and out of maintenance
+    pub fn new(roles: RoleAuthorizer) -> Self {
+        Self { roles, owner_routes: Vec::new() }
+            .allow_owner(Method::POST, "/agents/:name/disable")
+            .allow_owner(Method::POST, "/agents/:name/enable")
+    }
+
+    /// Let holders of `owner:<resource>` use `method` on `route` for that resource
+    pub fn allow_owner(mut self, method: Method, route: &str) -> Self {
+        self.owner_routes.push((method, route.to_string()));
+        self
+    }
+
+    fn is_owner_route(&self, action: &Action) -> bool {
+        self.owner_routes.iter().any(|(method, route)| *method == action.method && *route == action.route)
+    }
+}
+
+impl Authorizer for AttributeAuthorizer {
+    fn authorize(&self, claims: &Claims, action: &Action) -> bool {
+        if claims.roles.iter().any(|r| r == "readonly") && !action.is_read_only() {
+            return false;
+        }
+
+        if let Some(resource) = action.resource.as_ref().filter(|_| self.is_owner_route(action)) {
+            let owner_role = format!("owner:{}", resource);
+            if claims.roles.contains(&owner_role) {
+                return true;
+            }
+        }
+
+        self.roles.authorize(claims, action)
+    }
+}
+
+/// Build the authorizer named by `SecurityConfig::authorization_policy`
+pub fn create_authorizer(policy: &str) -> Result<Arc<dyn Authorizer>> {
+    match policy {
+        "role" => Ok(Arc::new(RoleAuthorizer::default())),
+        "attribute" => Ok(Arc::new(AttributeAuthorizer::new(RoleAuthorizer::default()))),
+        other => Err(anyhow!("Unknown authorization policy: {}", other)),
+    }
+}
+
+/// Authorization middleware; must run after `auth_middleware` and be applied
+/// with `route_layer` so the matched route is known
+pub async fn authorize_middleware(
+    State(authorizer): State<Arc<dyn Authorizer>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let claims = request.extensions().get::<Claims>()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let route = request.extensions().get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let action = Action::new(request.method().clone(), &route, request.uri().path());
+
+    if !authorizer.authorize(claims, &action) {
+        warn!("User {} not authorized for {} {}", claims.sub, action.method, request.uri().path());
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(next.run(request).await)
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 || !s.is_ascii() {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
 /// Login request structure
 #[derive(Deserialize)]
 pub struct LoginRequest {
@@ -342,4 +781,161 @@ mod tests {
         // Should fail to initialize again
         assert!(auth_manager.initialize_admin("admin2".to_string(), "admin_password2").is_err());
     }
+
+    #[test]
+    fn test_degraded_manager_recovers_on_reconnect() {
+        let dir = tempdir().unwrap();
+        let auth_manager = AuthManager::new_degraded("test_secret".to_string(), dir.path().to_str().unwrap());
+        assert!(!auth_manager.is_available());
+        let err = auth_manager.has_admin().unwrap_err();
+        assert!(err.is::<AuthUnavailableError>());
+
+        assert!(auth_manager.try_reconnect().unwrap());
+        assert!(auth_manager.is_available());
+        auth_manager.initialize_admin("admin".to_string(), "admin_password").unwrap();
+        assert!(auth_manager.has_admin().unwrap());
+    }
+
+    #[test]
+    fn test_user_cache_bound_and_invalidation() {
+        let dir = tempdir().unwrap();
+        let auth_manager = AuthManager::new("test_secret".to_string(), dir.path().to_str().unwrap())
+            .unwrap()
+            .with_user_cache(2, Duration::from_secs(60));
+        for name in ["a", "b", "c"] {
+            auth_manager.add_user(name.to_string(), "password", vec!["user".to_string()]).unwrap();
+            auth_manager.authenticate(name, "password").unwrap();
+        }
+        let stats = auth_manager.user_cache_stats();
+        assert_eq!((stats.entries, stats.capacity, stats.misses), (2, 2, 3));
+
+        auth_manager.authenticate("c", "password").unwrap();
+        assert_eq!(auth_manager.user_cache_stats().hits, 1);
+
+        auth_manager.set_roles("c", vec!["admin".to_string()]).unwrap();
+        let token = auth_manager.authenticate("c", "password").unwrap();
+        assert_eq!(auth_manager.validate_token(&token).unwrap().roles, vec!["admin"]);
+
+        // Entries expire so changes made by other processes are picked up
+        let expiring = auth_manager.clone().with_user_cache(2, Duration::ZERO);
+        expiring.authenticate("a", "password").unwrap();
+        expiring.authenticate("a", "password").unwrap();
+        assert_eq!(expiring.user_cache_stats().hits, 0);
+    }
+
+    #[test]
+    fn test_cached_user_changes_take_effect_immediately() {
+        let dir = tempdir().unwrap();
+        let auth_manager = AuthManager::new("test_secret".to_string(), dir.path().to_str().unwrap()).unwrap();
+        auth_manager.add_user("cached".to_string(), "old_password", vec!["user".to_string()]).unwrap();
+        // Populate the cache
+        auth_manager.authenticate("cached", "old_password").unwrap();
+
+        auth_manager.update_password("cached", "new_password").unwrap();
+        assert!(auth_manager.authenticate("cached", "old_password").is_err());
+        auth_manager.authenticate("cached", "new_password").unwrap();
+
+        auth_manager.disable_user("cached").unwrap();
+        let err = auth_manager.authenticate("cached", "new_password").unwrap_err();
+        assert_eq!(err.to_string(), "User account is disabled");
+        assert!(auth_manager.user_cache_stats().hits >= 2);
+    }
+
+    #[test]
+    fn test_concurrent_user_updates_are_not_lost() {
+        let dir = tempdir().unwrap();
+        let auth_manager = AuthManager::new("test_secret".to_string(), dir.path().to_str().unwrap()).unwrap();
+        auth_manager.add_user("racer".to_string(), "password", vec!["user".to_string()]).unwrap();
+        assert!(auth_manager.add_user("racer".to_string(), "other", vec![]).is_err());
+
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let auth_manager = &auth_manager;
+                scope.spawn(move || {
+                    if i == 0 {
+                        auth_manager.disable_user("racer").unwrap();
+                    } else {
+                        auth_manager.set_roles("racer", vec!["user".to_string(), "admin".to_string()]).unwrap();
+                    }
+                });
+            }
+        });
+
+        let user = auth_manager.get_user("racer").unwrap();
+        assert!(!user.active);
+        assert!(user.roles.contains(&"admin".to_string()));
+        assert!(auth_manager.set_roles("missing", vec![]).is_err());
+    }
+
+    fn claims_with_roles(roles: &[&str]) -> Claims {
+        Claims {
+            sub: "user".to_string(),
+            exp: 0,
+            iat: 0,
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_action_resource_extraction() {
+        let action = Action::new(Method::DELETE, "/agents/:name", "/agents/echo");
+        assert_eq!(action.resource.as_deref(), Some("echo"));
+
+        let action = Action::new(Method::GET, "/agents", "/agents");
+        assert_eq!(action.resource, None);
+    }
+
+    #[test]
+    fn test_role_authorizer_matches_admin_routes() {
+        let authorizer = RoleAuthorizer::default();
+        let user = claims_with_roles(&["user"]);
+        let admin = claims_with_roles(&["admin"]);
+
+        let register = Action::new(Method::POST, "/agents", "/agents");
+        let list = Action::new(Method::GET, "/agents", "/agents");
+        let remove = Action::new(Method::DELETE, "/agents/:name", "/agents/echo");
+
+        assert!(!authorizer.authorize(&user, &register));
+        assert!(!authorizer.authorize(&user, &remove));
+        assert!(authorizer.authorize(&user, &list));
+        assert!(authorizer.authorize(&admin, &register));
+        assert!(authorizer.authorize(&admin, &remove));
+    }
+
+    #[test]
+    fn test_attribute_authorizer_readonly_and_ownership() {
+        let authorizer = AttributeAuthorizer::new(RoleAuthorizer::default());
+
+        let readonly = claims_with_roles(&["readonly"]);
+        assert!(authorizer.authorize(&readonly, &Action::new(Method::GET, "/agents", "/agents")));
+        assert!(!authorizer.authorize(&readonly, &Action::new(Method::POST, "/execute", "/execute")));
+
+        let owner = claims_with_roles(&["user", "owner:my_agent"]);
+        let disable = |name: &str| Action::new(Method::POST, "/agents/:name/disable", &format!("/agents/{}/disable", name));
+        assert!(authorizer.authorize(&owner, &disable("my_agent")));
+        assert!(!authorizer.authorize(&owner, &disable("other")));
+
+        // Destructive routes, and routes naming something else, stay admin-only
+        assert!(!authorizer.authorize(&owner, &Action::new(Method::DELETE, "/agents/:name", "/agents/my_agent")));
+        assert!(!authorizer.authorize(&owner, &Action::new(Method::POST, "/agents/:name/model", "/agents/my_agent/model")));
+        assert!(!authorizer.authorize(&owner, &Action::new(Method::PUT, "/auth/users/:name/quota", "/auth/users/my_agent/quota")));
+    }
+
+    #[test]
+    fn test_signed_url_roundtrip_and_rejection() {
+        let auth = create_test_auth_manager();
+        let expires = chrono::Utc::now().timestamp() as u64 + 60;
+        let signature = auth.sign_url("/memory/export/download", expires);
+
+        assert!(auth.verify_signed_url("/memory/export/download", expires, &signature).is_ok());
+        // Tampered path, expiry or signature
+        assert!(auth.verify_signed_url("/memory/export", expires, &signature).is_err());
+        assert!(auth.verify_signed_url("/memory/export/download", expires + 1, &signature).is_err());
+        assert!(auth.verify_signed_url("/memory/export/download", expires, "zz").is_err());
+
+        // Expired
+        let past = chrono::Utc::now().timestamp() as u64 - 1;
+        let signature = auth.sign_url("/memory/export/download", past);
+        assert!(auth.verify_signed_url("/memory/export/download", past, &signature).is_err());
+    }
 }
@@ -0,0 +1,164 @@
+//! Versioned schema migrations for the sled auth database.
+//!
+//! The applied version is kept in a dedicated `schema` tree, apart from the
+//! user records in the default tree. Migrations run in order from the stored
+//! version up to [`LATEST_VERSION`], each recording its version once it has
+//! completed, so an interrupted run resumes at the step that did not finish.
+//! Every step must therefore be safe to apply again.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use tracing::info;
+
+use crate::auth::User;
+
+/// Tree holding schema bookkeeping
+const SCHEMA_TREE: &str = "schema";
+
+/// Key of the applied schema version in [`SCHEMA_TREE`]
+const VERSION_KEY: &[u8] = b"schema_version";
+
+/// One ordered step of the auth database schema
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    apply: fn(&sled::Db) -> Result<()>,
+}
+
+/// Every migration, in version order. Append new steps; never edit or
+/// reorder released ones.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Baseline: users keyed by username in the default tree",
+        apply: check_user_records,
+    },
+];
+
+/// Schema version this binary migrates databases to
+pub const LATEST_VERSION: u32 = 1;
+
+/// Outcome of a migration run
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub applied: Vec<u32>,
+}
+
+/// Schema version recorded in `db`; databases created before versioning
+/// report 0
+pub fn current_version(db: &sled::Db) -> Result<u32> {
+    let tree = db.open_tree(SCHEMA_TREE)?;
+    match tree.get(VERSION_KEY)? {
+        Some(bytes) => {
+            let bytes: [u8; 4] = bytes.as_ref().try_into()
+                .map_err(|_| anyhow!("Corrupt auth database schema version"))?;
+            Ok(u32::from_be_bytes(bytes))
+        }
+        None => Ok(0),
+    }
+}
+
+/// Bring `db` up to [`LATEST_VERSION`]
+pub fn migrate(db: &sled::Db) -> Result<MigrationReport> {
+    run_migrations(db, MIGRATIONS)
+}
+
+/// Open the auth database at `db_path` and migrate it
+pub fn migrate_path(db_path: &str) -> Result<MigrationReport> {
+    let db = sled::open(db_path)
+        .map_err(|e| anyhow!("Failed to open auth database at '{}': {}", db_path, e))?;
+    migrate(&db)
+}
+
+fn run_migrations(db: &sled::Db, migrations: &[Migration]) -> Result<MigrationReport> {
+    let from_version = current_version(db)?;
+    let latest = migrations.last().map_or(0, |m| m.version);
+    if from_version > latest {
+        return Err(anyhow!(
+            "Auth database schema version {} is newer than this binary supports ({}); refusing to run against it",
+            from_version, latest
+        ));
+    }
+
+    let tree = db.open_tree(SCHEMA_TREE)?;
+    let mut applied = Vec::new();
+    for migration in migrations.iter().filter(|m| m.version > from_version) {
+        info!("Applying auth database migration {}: {}", migration.version, migration.description);
+        (migration.apply)(db)
+            .map_err(|e| anyhow!("Auth database migration {} failed: {}", migration.version, e))?;
+        tree.insert(VERSION_KEY, &migration.version.to_be_bytes()[..])?;
+        db.flush()?;
+        applied.push(migration.version);
+    }
+
+    if applied.is_empty() {
+        info!("Auth database schema is up to date at version {}", from_version);
+    } else {
+        info!("Auth database schema migrated from version {} to {}", from_version, latest);
+    }
+    Ok(MigrationReport { from_version, to_version: latest, applied })
+}
+
+/// Refuse to adopt a database whose default tree holds anything but users
+fn check_user_records(db: &sled::Db) -> Result<()> {
+    for item in db.iter() {
+        let (key, bytes) = item?;
+        bincode::deserialize::<User>(&bytes).map_err(|e| {
+            anyhow!("Record '{}' is not a user: {}", String::from_utf8_lossy(&key), e)
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_db() -> sled::Db {
+        sled::Config::new().temporary(true).open().unwrap()
+    }
+
+    #[test]
+    fn test_migrations_are_ordered_and_latest() {
+        assert!(MIGRATIONS.windows(2).all(|w| w[0].version < w[1].version));
+        assert_eq!(MIGRATIONS.last().unwrap().version, LATEST_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_runs_once_and_resumes() {
+        fn add_marker(db: &sled::Db) -> Result<()> {
+            db.open_tree("markers")?.insert("v2", "done")?;
+            Ok(())
+        }
+        let steps = [
+            Migration { version: 1, description: "baseline", apply: check_user_records },
+            Migration { version: 2, description: "marker", apply: add_marker },
+        ];
+
+        let db = temp_db();
+        let report = run_migrations(&db, &steps[..1]).unwrap();
+        assert_eq!((report.from_version, report.applied.clone()), (0, vec![1]));
+
+        // A newer binary picks up where the old one stopped
+        let report = run_migrations(&db, &steps).unwrap();
+        assert_eq!((report.from_version, report.to_version, report.applied), (1, 2, vec![2]));
+        assert!(db.open_tree("markers").unwrap().contains_key("v2").unwrap());
+
+        let report = run_migrations(&db, &steps).unwrap();
+        assert!(report.applied.is_empty());
+        assert_eq!(current_version(&db).unwrap(), 2);
+
+        // ...and an older one refuses to touch the upgraded database
+        assert!(run_migrations(&db, &steps[..1]).is_err());
+    }
+
+    #[test]
+    fn test_baseline_rejects_foreign_records() {
+        let db = temp_db();
+        db.insert("not-a-user", &[0xff, 0x01][..]).unwrap();
+        assert!(migrate(&db).is_err());
+        assert_eq!(current_version(&db).unwrap(), 0);
+    }
+}
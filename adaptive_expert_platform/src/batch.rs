@@ -5,15 +5,17 @@
 //! result aggregation.
 
 use crate::{
-    orchestrator::Orchestrator,
+    mesh::TaskPriority,
+    orchestrator::{with_progress, Caller, Orchestrator},
     settings::Settings,
     memory::{Memory, redis_store::InMemoryEmbeddingCache},
-    agent::{EchoAgent, PythonToolAgent},
+    agent::{EchoAgent, PythonToolAgent, TaskWorkdir},
 };
 use anyhow::{Result, anyhow, Context};
 use serde::{Deserialize, Serialize};
-use std::{path::PathBuf, sync::Arc, time::Instant};
+use std::{path::{Path, PathBuf}, sync::Arc, time::Instant};
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 use tracing::{info, warn, error, instrument};
 use serde_json::{json, Value};
 
@@ -62,9 +64,10 @@ pub struct TaskConfig {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskSettings {
-    /// Maximum execution time in seconds
-    #[serde(default = "default_task_timeout")]
-    pub timeout_seconds: u64,
+    /// Maximum execution time in seconds; defaults to the orchestrator's
+    /// `task_timeout_seconds`
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
 
     /// Number of retries on failure
     #[serde(default)]
@@ -77,15 +80,21 @@ pub struct TaskSettings {
     /// Whether to continue on failure
     #[serde(default = "default_continue_on_error")]
     pub continue_on_error: bool,
+
+    /// Run each attempt in a fresh temp directory, removed afterwards. A
+    /// token for it is injected into the (object) input as `workdir_token`.
+    #[serde(default)]
+    pub isolated_workdir: bool,
 }
 
 impl Default for TaskSettings {
     fn default() -> Self {
         Self {
-            timeout_seconds: default_task_timeout(),
+            timeout_seconds: None,
             retries: 0,
             critical: false,
             continue_on_error: default_continue_on_error(),
+            isolated_workdir: false,
         }
     }
 }
@@ -106,6 +115,17 @@ pub struct BatchSettings {
     /// Whether to fail fast on first error
     #[serde(default)]
     pub fail_fast: bool,
+
+    /// Where to persist progress so an interrupted run can be resumed
+    pub checkpoint_file: Option<PathBuf>,
+
+    /// Write the checkpoint after this many newly completed tasks
+    #[serde(default = "default_checkpoint_interval")]
+    pub checkpoint_interval: usize,
+
+    /// Charge the tasks to this user's quota
+    #[serde(default)]
+    pub user: Option<String>,
 }
 
 impl Default for BatchSettings {
@@ -115,6 +135,9 @@ impl Default for BatchSettings {
             timeout_seconds: default_batch_timeout(),
             output_file: None,
             fail_fast: false,
+            checkpoint_file: None,
+            checkpoint_interval: default_checkpoint_interval(),
+            user: None,
         }
     }
 }
@@ -129,8 +152,25 @@ pub struct TaskResult {
     pub error: Option<String>,
     pub duration_ms: u64,
     pub retries_used: u32,
+    /// How the final attempt stopped, when it timed out
+    pub interruption: Option<InterruptionReport>,
 }
 
+/// Details of an attempt cut short by its timeout, to tell a task that needs
+/// more time from one that is stuck
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InterruptionReport {
+    /// 1-based attempt that timed out
+    pub attempt: u32,
+    /// Whether the agent wound down within the grace period after cancellation
+    pub stopped_cleanly: bool,
+    /// Output streamed by the agent before it was cancelled
+    pub partial_output: Option<String>,
+}
+
+/// How long a cancelled attempt may take to stop before it is abandoned
+const CANCEL_GRACE_PERIOD: std::time::Duration = std::time::Duration::from_secs(5);
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum TaskStatus {
@@ -164,17 +204,38 @@ pub enum BatchStatus {
     Failed,
 }
 
-/// Execute a batch job from configuration file
+/// Completed tasks of a batch job, persisted so an interrupted run can resume.
+///
+/// Only successful results are recorded; failed and unfinished tasks run again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchCheckpoint {
+    pub job_name: String,
+    pub completed: Vec<TaskResult>,
+}
+
+/// Execute a batch job from configuration file, optionally resuming from a
+/// checkpoint written by an earlier run of the same job
 #[instrument(skip(settings))]
-pub async fn run(config_path: PathBuf, settings: Settings) -> Result<()> {
+pub async fn run(config_path: PathBuf, resume: Option<PathBuf>, settings: Settings) -> Result<()> {
     info!("Starting batch execution from config: {:?}", config_path);
 
     // Load batch configuration
-    let config = load_batch_config(&config_path)
+    let mut config = load_batch_config(&config_path)
         .context("Failed to load batch configuration")?;
 
     info!("Loaded batch job: {} (version: {})", config.job.name, config.job.version);
 
+    let checkpoint = match &resume {
+        Some(path) => {
+            let checkpoint = load_checkpoint(path)?;
+            info!("Resuming from checkpoint {:?} ({} tasks already completed)", path, checkpoint.completed.len());
+            // Keep checkpointing to the file being resumed unless the config names one
+            config.settings.checkpoint_file.get_or_insert_with(|| path.clone());
+            Some(checkpoint)
+        }
+        None => None,
+    };
+
     // Store output file path before moving config
     let output_file = config.settings.output_file.clone();
 
@@ -184,7 +245,7 @@ pub async fn run(config_path: PathBuf, settings: Settings) -> Result<()> {
 
     // Execute batch job
     let start_time = Instant::now();
-    let result = execute_batch(orchestrator, config).await
+    let result = execute_batch(orchestrator, config, checkpoint).await
         .context("Batch execution failed")?;
 
     let total_duration = start_time.elapsed();
@@ -251,6 +312,87 @@ fn validate_batch_config(config: &BatchConfig) -> Result<()> {
         }
     }
 
+    // Reject cycles up front rather than stalling mid-run
+    dependency_stages(&config.tasks)?;
+
+    Ok(())
+}
+
+/// Group tasks into the stages they become ready in: the first holds tasks
+/// without dependencies, each later one the tasks whose dependencies all
+/// lie in earlier stages. Tasks keep their config order within a stage.
+///
+/// Fails naming the tasks left over when the dependencies form a cycle.
+fn dependency_stages(tasks: &[TaskConfig]) -> Result<Vec<Vec<&TaskConfig>>> {
+    let mut placed = std::collections::HashSet::new();
+    let mut remaining: Vec<&TaskConfig> = tasks.iter().collect();
+    let mut stages = Vec::new();
+
+    while !remaining.is_empty() {
+        let (ready, blocked): (Vec<&TaskConfig>, Vec<&TaskConfig>) = remaining
+            .into_iter()
+            .partition(|task| task.depends_on.iter().all(|dep| placed.contains(dep.as_str())));
+        if ready.is_empty() {
+            let ids: Vec<&str> = blocked.iter().map(|task| task.id.as_str()).collect();
+            return Err(anyhow!("Circular dependency among tasks: {}", ids.join(", ")));
+        }
+        placed.extend(ready.iter().map(|task| task.id.as_str()));
+        stages.push(ready);
+        remaining = blocked;
+    }
+
+    Ok(stages)
+}
+
+/// Render the task dependency graph in Graphviz DOT format.
+///
+/// Nodes are tasks labeled with their agent, critical ones filled red; an
+/// edge runs from each dependency to the task waiting on it. Tasks that
+/// become ready together share a rank, so the layout reads in execution order.
+pub fn task_graph_dot(config: &BatchConfig) -> Result<String> {
+    validate_batch_config(config)?;
+    let stages = dependency_stages(&config.tasks)?;
+
+    let mut dot = format!("digraph {} {{\n", dot_id(&config.job.name));
+    dot.push_str("    rankdir=LR;\n");
+    dot.push_str("    node [shape=box, style=\"rounded,filled\", fillcolor=\"white\"];\n");
+    for stage in &stages {
+        let ids: Vec<String> = stage.iter().map(|task| dot_id(&task.id)).collect();
+        dot.push_str(&format!("    {{ rank=same; {}; }}\n", ids.join("; ")));
+    }
+    for task in stages.iter().flatten() {
+        let fill = if task.settings.critical { "\"#f4a6a6\"" } else { "\"#d9e8f5\"" };
+        dot.push_str(&format!(
+            "    {} [label={}, fillcolor={}];\n",
+            dot_id(&task.id),
+            dot_id(&format!("{}\n({})", task.id, task.agent)),
+            fill,
+        ));
+    }
+    for task in stages.iter().flatten() {
+        for dep in &task.depends_on {
+            dot.push_str(&format!("    {} -> {};\n", dot_id(dep), dot_id(&task.id)));
+        }
+    }
+    dot.push_str("}\n");
+    Ok(dot)
+}
+
+/// Quote `text` as a DOT identifier; newlines become centered line breaks
+fn dot_id(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+/// Write the dependency graph of the batch job at `config_path` to `output`
+/// as DOT, without running anything
+pub fn export_graph(config_path: &PathBuf, output: &Path) -> Result<()> {
+    let config = load_batch_config(config_path)
+        .context("Failed to load batch configuration")?;
+    let dot = task_graph_dot(&config)?;
+    std::fs::write(output, dot)
+        .with_context(|| format!("Failed to write task graph: {:?}", output))?;
+    println!("Wrote the task graph of '{}' ({} tasks) to {}", config.job.name, config.tasks.len(), output.display());
     Ok(())
 }
 
@@ -268,68 +410,133 @@ async fn initialize_orchestrator(settings: &Settings) -> Result<Orchestrator> {
 
     // Register built-in agents
     orchestrator.register_agent("echo".to_string(), echo_agent).await?;
-    orchestrator.register_agent("python_tool".to_string(), Arc::new(PythonToolAgent::new())).await?;
+    orchestrator.register_agent("python_tool".to_string(), Arc::new(PythonToolAgent::new(settings))).await?;
 
     info!("Orchestrator initialized with built-in agents");
     Ok(orchestrator)
 }
 
-/// Execute batch job with dependency resolution and concurrency control
-async fn execute_batch(orchestrator: Arc<Orchestrator>, config: BatchConfig) -> Result<BatchResult> {
+/// Execute batch job with dependency resolution and concurrency control.
+///
+/// Tasks run in the stages [`dependency_stages`] groups them into. A task
+/// whose dependencies did not all succeed is skipped.
+///
+/// Tasks recorded in `resume_from` are not run again; their saved results are
+/// reported and satisfy dependencies as if they had just completed.
+async fn execute_batch(
+    orchestrator: Arc<Orchestrator>,
+    config: BatchConfig,
+    resume_from: Option<BatchCheckpoint>,
+) -> Result<BatchResult> {
     let start_time = Instant::now();
     let total_tasks = config.tasks.len();
 
+    // Run stage by stage, so the order matches the graph `task_graph_dot` draws
+    let stages: Vec<Vec<TaskConfig>> = dependency_stages(&config.tasks)?
+        .into_iter()
+        .map(|stage| stage.into_iter().cloned().collect())
+        .collect();
+
     let mut task_results = Vec::new();
     let mut completed_tasks = std::collections::HashSet::new();
-    let mut remaining_tasks: std::collections::HashMap<String, TaskConfig> =
-        config.tasks.into_iter().map(|t| (t.id.clone(), t)).collect();
-
-    // Execute tasks in dependency order
-    while !remaining_tasks.is_empty() {
-        // Find tasks that can be executed (all dependencies completed)
-        let ready_tasks: Vec<_> = remaining_tasks
-            .values()
-            .filter(|task| {
-                task.depends_on.iter().all(|dep| completed_tasks.contains(dep))
-            })
-            .cloned()
+
+    let mut checkpoint = BatchCheckpoint {
+        job_name: config.job.name.clone(),
+        completed: Vec::new(),
+    };
+    if let Some(resumed) = resume_from {
+        if resumed.job_name != config.job.name {
+            return Err(anyhow!(
+                "Checkpoint is for job '{}', not '{}'", resumed.job_name, config.job.name
+            ));
+        }
+        for result in resumed.completed {
+            if !config.tasks.iter().any(|task| task.id == result.task_id) {
+                return Err(anyhow!("Checkpoint contains unknown task: {}", result.task_id));
+            }
+            completed_tasks.insert(result.task_id.clone());
+            task_results.push(result.clone());
+            checkpoint.completed.push(result);
+        }
+    }
+    let checkpoint_path = config.settings.checkpoint_file.as_deref();
+    let checkpoint_interval = config.settings.checkpoint_interval.max(1);
+    let mut unsaved = 0;
+    let caller = Caller { user: config.settings.user.clone(), tenant: None };
+
+    for (stage_index, stage) in stages.into_iter().enumerate() {
+        let pending: Vec<TaskConfig> = stage
+            .into_iter()
+            .filter(|task| !completed_tasks.contains(&task.id))
             .collect();
+        if pending.is_empty() {
+            continue;
+        }
 
-        if ready_tasks.is_empty() {
-            return Err(anyhow!("Circular dependency detected or missing dependencies"));
+        // Tasks whose dependencies did not all succeed are skipped
+        let (ready_tasks, blocked): (Vec<TaskConfig>, Vec<TaskConfig>) = pending
+            .into_iter()
+            .partition(|task| task.depends_on.iter().all(|dep| completed_tasks.contains(dep)));
+        for task in blocked {
+            let failed_deps: Vec<&str> = task.depends_on.iter()
+                .filter(|dep| !completed_tasks.contains(*dep))
+                .map(String::as_str)
+                .collect();
+            warn!("Skipping task {}: dependencies did not succeed: {}", task.id, failed_deps.join(", "));
+            let error = format!("Dependencies did not succeed: {}", failed_deps.join(", "));
+            task_results.push(TaskResult {
+                task_id: task.id,
+                agent: task.agent,
+                status: TaskStatus::Skipped,
+                output: None,
+                error: Some(error),
+                duration_ms: 0,
+                retries_used: 0,
+                interruption: None,
+            });
         }
+        info!("Running stage {} with {} tasks", stage_index + 1, ready_tasks.len());
 
-        // Execute ready tasks with concurrency limit
+        // Execute ready tasks with concurrency limit. Permits are taken inside the
+        // spawned tasks so results can be collected while later tasks still wait.
         let semaphore = Arc::new(tokio::sync::Semaphore::new(config.settings.max_concurrent_tasks));
-        let mut handles = Vec::new();
+        let mut running = tokio::task::JoinSet::new();
 
         for task in ready_tasks {
-            let permit = semaphore.clone().acquire_owned().await?;
-            let task_clone = task.clone();
+            let semaphore = semaphore.clone();
             let orchestrator_clone = orchestrator.clone();
-
-            let handle = tokio::spawn(async move {
-                let _permit = permit; // Keep permit until task completes
-                execute_single_task(orchestrator_clone.as_ref(), task_clone).await
+            let caller = caller.clone();
+
+            running.spawn(async move {
+                let task_id = task.id.clone();
+                let result = match semaphore.acquire_owned().await {
+                    // Keep permit until task completes
+                    Ok(_permit) => execute_single_task(orchestrator_clone.as_ref(), &caller, task).await,
+                    Err(e) => Err(e.into()),
+                };
+                (task_id, result)
             });
-
-            handles.push((task.id.clone(), handle));
         }
 
-        // Wait for all tasks in this batch to complete
-        for (task_id, handle) in handles {
-            let result = handle.await??;
+        // Handle results in completion order; tasks still running are aborted
+        // if we return early
+        while let Some(joined) = running.join_next().await {
+            let (task_id, result) = joined?;
+            let result = result?;
 
             // Check if we should fail fast
             if config.settings.fail_fast && result.status == TaskStatus::Failed {
                 error!("Failing fast due to task failure: {}", task_id);
+                if let Some(path) = checkpoint_path {
+                    save_checkpoint_or_warn(&checkpoint, path);
+                }
                 return Ok(BatchResult {
                     job_name: config.job.name,
                     status: BatchStatus::Failed,
                     total_tasks,
                     successful_tasks: task_results.iter().filter(|r: &&TaskResult| r.status == TaskStatus::Success).count(),
                     failed_tasks: task_results.iter().filter(|r| r.status == TaskStatus::Failed).count() + 1,
-                    skipped_tasks: remaining_tasks.len() - 1,
+                    skipped_tasks: total_tasks - task_results.len() - 1,
                     total_duration_ms: start_time.elapsed().as_millis() as u64,
                     task_results,
                     error: Some(format!("Failed fast on task: {}", task_id)),
@@ -338,10 +545,18 @@ async fn execute_batch(orchestrator: Arc<Orchestrator>, config: BatchConfig) ->
 
             if result.status == TaskStatus::Success {
                 completed_tasks.insert(task_id.clone());
+                checkpoint.completed.push(result.clone());
+                unsaved += 1;
             }
 
             task_results.push(result);
-            remaining_tasks.remove(&task_id);
+
+            if let Some(path) = checkpoint_path {
+                if unsaved >= checkpoint_interval || (task_results.len() == total_tasks && unsaved > 0) {
+                    save_checkpoint_or_warn(&checkpoint, path);
+                    unsaved = 0;
+                }
+            }
         }
     }
 
@@ -372,92 +587,184 @@ async fn execute_batch(orchestrator: Arc<Orchestrator>, config: BatchConfig) ->
 }
 
 /// Execute a single task with retry logic
-async fn execute_single_task(orchestrator: &Orchestrator, task: TaskConfig) -> Result<TaskResult> {
+async fn execute_single_task(orchestrator: &Orchestrator, caller: &Caller, task: TaskConfig) -> Result<TaskResult> {
     let start_time = Instant::now();
     let mut retries_used = 0;
 
     loop {
         info!("Executing task: {} (attempt {})", task.id, retries_used + 1);
 
-                let (tx, mut rx) = mpsc::channel(1);
-        let task_tuple = (task.agent.clone(), task.input.clone(), tx);
+        // Held for the whole attempt; the directory is removed on drop, including
+        // after failures and timeouts
+        let (_workdir, input) = if task.settings.isolated_workdir {
+            let workdir = TaskWorkdir::create()
+                .context("Failed to create task working directory")?;
+            let input = match with_working_dir(&task.input, &workdir) {
+                Ok(input) => input,
+                Err(e) => {
+                    error!("Task {} cannot run isolated: {}", task.id, e);
+                    return Ok(TaskResult {
+                        task_id: task.id,
+                        agent: task.agent,
+                        status: TaskStatus::Failed,
+                        output: None,
+                        error: Some(e.to_string()),
+                        duration_ms: start_time.elapsed().as_millis() as u64,
+                        retries_used,
+                        interruption: None,
+                    });
+                }
+            };
+            (Some(workdir), input)
+        } else {
+            (None, task.input.clone())
+        };
 
-        // Execute with timeout
-        let execution_result = tokio::time::timeout(
-            std::time::Duration::from_secs(task.settings.timeout_seconds),
-            async {
-                orchestrator.dispatch(task_tuple).await?;
-                rx.recv().await.ok_or_else(|| anyhow!("No response received"))
+        // Dispatched like any other task (cache, traces, quotas, output
+        // schema); progress is collected so a timeout can report how far it got
+        let (tx, mut rx) = mpsc::channel(16);
+        let cancel = CancellationToken::new();
+        let mut output = String::new();
+        let run = async {
+            let (resp_tx, mut resp_rx) = mpsc::channel(1);
+            let task = (task.agent.clone(), input, resp_tx);
+            with_progress(tx, orchestrator.dispatch_as(caller, task, TaskPriority::Normal, cancel.clone())).await?;
+            match resp_rx.recv().await {
+                Some(response) => response.map_err(|e| e.into_source_error()),
+                None => Err(anyhow!("Task response channel closed unexpectedly")),
             }
-        ).await;
-
-        match execution_result {
-            Ok(Ok(Ok(output))) => {
-                info!("Task {} completed successfully", task.id);
-                return Ok(TaskResult {
-                    task_id: task.id,
-                    agent: task.agent,
-                    status: TaskStatus::Success,
-                    output: Some(output),
-                    error: None,
-                    duration_ms: start_time.elapsed().as_millis() as u64,
-                    retries_used,
-                });
+        };
+        tokio::pin!(run);
+        let timeout = task.settings.timeout_seconds
+            .map(std::time::Duration::from_secs)
+            .unwrap_or_else(|| orchestrator.task_timeout());
+        let deadline = tokio::time::sleep(timeout);
+        tokio::pin!(deadline);
+
+        let finished = loop {
+            tokio::select! {
+                Some(chunk) = rx.recv() => output.push_str(&chunk),
+                result = &mut run => break Some(result),
+                _ = &mut deadline => break None,
             }
-            Ok(Ok(Err(e))) => {
-                warn!("Task {} failed: {}", task.id, e);
+        };
+
+        let execution_result = match finished {
+            Some(result) => result,
+            None => {
+                cancel.cancel();
+                let stopped_cleanly = tokio::time::timeout(CANCEL_GRACE_PERIOD, &mut run).await.is_ok();
+                while let Ok(chunk) = rx.try_recv() {
+                    output.push_str(&chunk);
+                }
+                let interruption = InterruptionReport {
+                    attempt: retries_used + 1,
+                    stopped_cleanly,
+                    partial_output: (!output.is_empty()).then_some(output),
+                };
+                warn!("Task {} timed out: {:?}", task.id, interruption);
 
                 if retries_used < task.settings.retries {
                     retries_used += 1;
-                    warn!("Retrying task {} (attempt {})", task.id, retries_used + 1);
+                    warn!("Retrying task {} after timeout (attempt {})", task.id, retries_used + 1);
                     continue;
                 }
 
                 return Ok(TaskResult {
                     task_id: task.id,
                     agent: task.agent,
-                    status: TaskStatus::Failed,
+                    status: TaskStatus::Timeout,
                     output: None,
-                    error: Some(e.to_string()),
+                    error: Some("Task execution timed out".to_string()),
                     duration_ms: start_time.elapsed().as_millis() as u64,
                     retries_used,
+                    interruption: Some(interruption),
                 });
             }
-            Ok(Err(e)) => {
-                error!("Task {} dispatch failed: {}", task.id, e);
+        };
+
+        match execution_result {
+            Ok(output) => {
+                info!("Task {} completed successfully", task.id);
                 return Ok(TaskResult {
                     task_id: task.id,
                     agent: task.agent,
-                    status: TaskStatus::Failed,
-                    output: None,
-                    error: Some(e.to_string()),
+                    status: TaskStatus::Success,
+                    output: Some(output),
+                    error: None,
                     duration_ms: start_time.elapsed().as_millis() as u64,
                     retries_used,
+                    interruption: None,
                 });
             }
-            Err(_) => {
-                warn!("Task {} timed out", task.id);
+            Err(e) => {
+                warn!("Task {} failed: {}", task.id, e);
 
-                if retries_used < task.settings.retries {
+                // Rejected inputs fail the same way on every attempt
+                let rejected = e.is::<crate::input_guard::InputLimitError>();
+                if !rejected && retries_used < task.settings.retries {
                     retries_used += 1;
-                    warn!("Retrying task {} after timeout (attempt {})", task.id, retries_used + 1);
+                    warn!("Retrying task {} (attempt {})", task.id, retries_used + 1);
                     continue;
                 }
 
                 return Ok(TaskResult {
                     task_id: task.id,
                     agent: task.agent,
-                    status: TaskStatus::Timeout,
+                    status: TaskStatus::Failed,
                     output: None,
-                    error: Some("Task execution timed out".to_string()),
+                    error: Some(e.to_string()),
                     duration_ms: start_time.elapsed().as_millis() as u64,
                     retries_used,
+                    interruption: None,
                 });
             }
         }
     }
 }
 
+/// Inject an isolated working directory into a task's input
+fn with_working_dir(input: &Value, workdir: &TaskWorkdir) -> Result<Value> {
+    let mut input = input.clone();
+    let fields = input.as_object_mut()
+        .ok_or_else(|| anyhow!("Tasks with an isolated working directory need an object input"))?;
+    fields.insert("workdir_token".to_string(), json!(workdir.token()));
+    Ok(input)
+}
+
+/// Load a checkpoint written by `save_checkpoint`
+fn load_checkpoint(path: &Path) -> Result<BatchCheckpoint> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read checkpoint: {:?}", path))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse checkpoint: {:?}", path))
+}
+
+/// Write a checkpoint atomically, so a crash mid-write leaves the previous one intact
+fn save_checkpoint(checkpoint: &BatchCheckpoint, path: &Path) -> Result<()> {
+    use std::io::Write;
+
+    let json = serde_json::to_vec_pretty(checkpoint)
+        .context("Failed to serialize batch checkpoint")?;
+
+    let tmp_path = path.with_extension("tmp");
+    let mut file = std::fs::File::create(&tmp_path)
+        .with_context(|| format!("Failed to create checkpoint: {:?}", tmp_path))?;
+    file.write_all(&json)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp_path, path)
+        .with_context(|| format!("Failed to replace checkpoint: {:?}", path))?;
+    Ok(())
+}
+
+/// Checkpointing is best effort; a failed write must not fail the batch itself
+fn save_checkpoint_or_warn(checkpoint: &BatchCheckpoint, path: &Path) {
+    match save_checkpoint(checkpoint, path) {
+        Ok(()) => info!("Checkpoint saved: {} tasks completed", checkpoint.completed.len()),
+        Err(e) => warn!("Failed to save batch checkpoint: {:#}", e),
+    }
+}
+
 /// Print batch execution summary
 fn print_batch_summary(result: &BatchResult) {
     println!("\n=== Batch Execution Summary ===");
@@ -469,14 +776,22 @@ fn print_batch_summary(result: &BatchResult) {
     println!("Skipped: {}", result.skipped_tasks);
     println!("Duration: {}ms", result.total_duration_ms);
 
-    if result.failed_tasks > 0 {
+    let failures: Vec<&TaskResult> = result.task_results.iter()
+        .filter(|task| task.status == TaskStatus::Failed || task.status == TaskStatus::Timeout)
+        .collect();
+    if !failures.is_empty() {
         println!("\nFailed Tasks:");
-        for task in &result.task_results {
-            if task.status == TaskStatus::Failed || task.status == TaskStatus::Timeout {
-                println!("  - {} ({}): {}",
-                    task.task_id,
-                    task.agent,
-                    task.error.as_deref().unwrap_or("Unknown error")
+        for task in failures {
+            println!("  - {} ({}): {}",
+                task.task_id,
+                task.agent,
+                task.error.as_deref().unwrap_or("Unknown error")
+            );
+            if let Some(interruption) = &task.interruption {
+                println!("      attempt {} {}, {} bytes of partial output",
+                    interruption.attempt,
+                    if interruption.stopped_cleanly { "stopped cleanly" } else { "did not stop after cancellation" },
+                    interruption.partial_output.as_ref().map_or(0, |o| o.len()),
                 );
             }
         }
@@ -497,10 +812,10 @@ fn save_batch_results(result: &BatchResult, output_file: &PathBuf) -> Result<()>
 }
 
 // Default value functions
-fn default_task_timeout() -> u64 { 30 }
 fn default_continue_on_error() -> bool { false }
 fn default_max_concurrent() -> usize { 4 }
 fn default_batch_timeout() -> u64 { 3600 }
+fn default_checkpoint_interval() -> usize { 10 }
 
 #[cfg(test)]
 mod tests {
@@ -578,4 +893,302 @@ retries = 1
         assert_eq!(config.tasks[0].id, "echo_task");
         assert_eq!(config.settings.max_concurrent_tasks, 2);
     }
+
+    #[test]
+    fn test_isolated_workdir_injected_into_input() {
+        let workdir = TaskWorkdir::create().unwrap();
+        let input = with_working_dir(&json!({ "script_path": "tool.py", "args": [] }), &workdir).unwrap();
+        assert_eq!(input["workdir_token"], workdir.token());
+        assert_eq!(input["script_path"], "tool.py");
+
+        assert!(with_working_dir(&json!("text"), &workdir).is_err());
+    }
+
+    /// Echoes its input and counts how many times it ran
+    struct CountingAgent {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl crate::agent::Agent for CountingAgent {
+        fn name(&self) -> &str { "counting" }
+        fn agent_type(&self) -> &str { "test" }
+        fn capabilities(&self) -> Vec<String> { vec![] }
+        async fn handle(&self, input: Value, _memory: Arc<Memory>) -> Result<String> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(input.to_string())
+        }
+        async fn health_check(&self) -> Result<crate::agent::AgentHealth> {
+            Ok(crate::agent::AgentHealth::default())
+        }
+    }
+
+    /// Streams one chunk, then never finishes
+    struct StallingAgent;
+
+    #[async_trait::async_trait]
+    impl crate::agent::Agent for StallingAgent {
+        fn name(&self) -> &str { "stalling" }
+        fn agent_type(&self) -> &str { "test" }
+        fn capabilities(&self) -> Vec<String> { vec![] }
+        async fn handle(&self, _input: Value, _memory: Arc<Memory>) -> Result<String> {
+            std::future::pending().await
+        }
+        async fn handle_stream(&self, _input: Value, _memory: Arc<Memory>, chunks: mpsc::Sender<String>) -> Result<()> {
+            chunks.send("step 1 done".to_string()).await?;
+            std::future::pending().await
+        }
+        async fn health_check(&self) -> Result<crate::agent::AgentHealth> {
+            Ok(crate::agent::AgentHealth::default())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timeout_reports_partial_output() {
+        let orchestrator = initialize_orchestrator(&Settings::default()).await.unwrap();
+        orchestrator.register_agent("stalling".to_string(), Arc::new(StallingAgent)).await.unwrap();
+
+        let mut stalled = task("stalled", "stalling", &[]);
+        stalled.settings.timeout_seconds = Some(1);
+        stalled.settings.retries = 1;
+        let result = execute_single_task(&orchestrator, &Caller::default(), stalled).await.unwrap();
+
+        assert_eq!(result.status, TaskStatus::Timeout);
+        assert_eq!(result.retries_used, 1);
+        assert_eq!(result.interruption, Some(InterruptionReport {
+            attempt: 2,
+            stopped_cleanly: true,
+            partial_output: Some("step 1 done".to_string()),
+        }));
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["interruption"]["partial_output"], "step 1 done");
+    }
+
+    /// Echoes its input but promises an object
+    struct ObjectAgent;
+
+    #[async_trait::async_trait]
+    impl crate::agent::Agent for ObjectAgent {
+        fn name(&self) -> &str { "object" }
+        fn agent_type(&self) -> &str { "test" }
+        fn capabilities(&self) -> Vec<String> { vec![] }
+        async fn handle(&self, input: Value, _memory: Arc<Memory>) -> Result<String> {
+            Ok(input.to_string())
+        }
+        async fn health_check(&self) -> Result<crate::agent::AgentHealth> {
+            Ok(crate::agent::AgentHealth::default())
+        }
+        fn output_schema(&self) -> Option<Value> {
+            Some(json!({"type": "object"}))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_task_outputs_are_checked_against_the_agent_schema() {
+        let mut settings = Settings::default();
+        settings.orchestrator.output_validation = "fail".to_string();
+        let orchestrator = initialize_orchestrator(&settings).await.unwrap();
+        orchestrator.register_agent("object".to_string(), Arc::new(ObjectAgent)).await.unwrap();
+
+        let result = execute_single_task(&orchestrator, &Caller::default(), task("plain", "object", &[])).await.unwrap();
+        assert_eq!(result.status, TaskStatus::Failed);
+        assert!(result.error.unwrap().contains("does not match its schema"));
+    }
+
+    fn task(id: &str, agent: &str, depends_on: &[&str]) -> TaskConfig {
+        TaskConfig {
+            id: id.to_string(),
+            agent: agent.to_string(),
+            input: json!(id),
+            settings: TaskSettings::default(),
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_task_graph_dot() {
+        let mut extract = task("extract", "python_tool", &[]);
+        extract.settings.critical = true;
+        let config = BatchConfig {
+            job: JobMetadata {
+                name: "etl".to_string(),
+                description: None,
+                version: "1.0".to_string(),
+                tags: vec![],
+            },
+            tasks: vec![
+                task("report", "echo", &["transform", "audit"]),
+                extract,
+                task("transform", "echo", &["extract"]),
+                task("audit", "echo", &["extract"]),
+            ],
+            settings: BatchSettings::default(),
+        };
+
+        let dot = task_graph_dot(&config).unwrap();
+        assert!(dot.starts_with("digraph \"etl\" {"));
+        assert!(dot.contains(r##""extract" [label="extract\n(python_tool)", fillcolor="#f4a6a6"];"##));
+        assert!(dot.contains(r##""report" [label="report\n(echo)", fillcolor="#d9e8f5"];"##));
+        for edge in [
+            r#""extract" -> "transform";"#,
+            r#""extract" -> "audit";"#,
+            r#""transform" -> "report";"#,
+            r#""audit" -> "report";"#,
+        ] {
+            assert!(dot.contains(edge), "missing {} in\n{}", edge, dot);
+        }
+        assert_eq!(dot.matches("->").count(), 4);
+        assert!(dot.contains(r#"{ rank=same; "transform"; "audit"; }"#));
+
+        // Cycles are rejected before anything is rendered
+        let mut cyclic = config.clone();
+        cyclic.tasks[1].depends_on = vec!["report".to_string()];
+        let err = task_graph_dot(&cyclic).unwrap_err();
+        assert!(err.to_string().starts_with("Circular dependency among tasks"));
+    }
+
+    #[tokio::test]
+    async fn test_dependents_of_failed_tasks_are_skipped() {
+        let config = BatchConfig {
+            job: JobMetadata {
+                name: "partial".to_string(),
+                description: None,
+                version: "1.0".to_string(),
+                tags: vec![],
+            },
+            tasks: vec![
+                task("broken", "missing", &[]),
+                task("after_broken", "echo", &["broken"]),
+                task("independent", "echo", &[]),
+                task("downstream", "echo", &["after_broken", "independent"]),
+            ],
+            settings: BatchSettings { fail_fast: false, ..BatchSettings::default() },
+        };
+
+        let orchestrator = Arc::new(initialize_orchestrator(&Settings::default()).await.unwrap());
+        let result = execute_batch(orchestrator, config, None).await.unwrap();
+
+        assert_eq!(result.status, BatchStatus::PartialSuccess);
+        assert_eq!((result.successful_tasks, result.failed_tasks, result.skipped_tasks), (1, 1, 2));
+        let status = |id: &str| result.task_results.iter().find(|r| r.task_id == id).unwrap().status.clone();
+        assert_eq!(status("after_broken"), TaskStatus::Skipped);
+        assert_eq!(status("downstream"), TaskStatus::Skipped);
+        let skipped = result.task_results.iter().find(|r| r.task_id == "downstream").unwrap();
+        assert_eq!(skipped.error.as_deref(), Some("Dependencies did not succeed: after_broken"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_dependencies_are_rejected_before_running() {
+        let config = BatchConfig {
+            job: JobMetadata {
+                name: "dangling".to_string(),
+                description: None,
+                version: "1.0".to_string(),
+                tags: vec![],
+            },
+            tasks: vec![
+                task("counted", "counting", &[]),
+                task("orphan", "counting", &["nowhere"]),
+            ],
+            settings: BatchSettings::default(),
+        };
+
+        let orchestrator = Arc::new(initialize_orchestrator(&Settings::default()).await.unwrap());
+        let counting = Arc::new(CountingAgent { calls: Default::default() });
+        orchestrator.register_agent("counting".to_string(), counting.clone()).await.unwrap();
+
+        assert!(execute_batch(orchestrator, config, None).await.is_err());
+        assert_eq!(counting.calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_batch_resume_from_checkpoint() {
+        let dir = tempdir().unwrap();
+        let checkpoint_path = dir.path().join("job.checkpoint.json");
+        let config = BatchConfig {
+            job: JobMetadata {
+                name: "resumable".to_string(),
+                description: None,
+                version: "1.0".to_string(),
+                tags: vec![],
+            },
+            tasks: vec![
+                task("first", "counting", &[]),
+                task("second", "counting", &["first"]),
+                task("third", "late", &["second"]),
+                task("fourth", "counting", &["third"]),
+            ],
+            settings: BatchSettings {
+                fail_fast: true,
+                checkpoint_file: Some(checkpoint_path.clone()),
+                checkpoint_interval: 1,
+                ..BatchSettings::default()
+            },
+        };
+
+        let settings = Settings::default();
+        let orchestrator = Arc::new(initialize_orchestrator(&settings).await.unwrap());
+        let counting = Arc::new(CountingAgent { calls: Default::default() });
+        orchestrator.register_agent("counting".to_string(), counting.clone()).await.unwrap();
+
+        // "late" is not registered yet, so the run is interrupted at the third task
+        let interrupted = execute_batch(orchestrator.clone(), config.clone(), None).await.unwrap();
+        assert_eq!(interrupted.status, BatchStatus::Failed);
+        assert_eq!(counting.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        let checkpoint = load_checkpoint(&checkpoint_path).unwrap();
+        let done: Vec<&str> = checkpoint.completed.iter().map(|r| r.task_id.as_str()).collect();
+        assert_eq!(done, vec!["first", "second"]);
+        assert!(!checkpoint_path.with_extension("tmp").exists());
+
+        orchestrator.register_agent("late".to_string(), Arc::new(EchoAgent::new())).await.unwrap();
+        let resumed = execute_batch(orchestrator, config, Some(checkpoint)).await.unwrap();
+        assert_eq!(resumed.status, BatchStatus::Success);
+        assert_eq!(resumed.successful_tasks, 4);
+        // Only "fourth" ran on the counting agent during the resumed run
+        assert_eq!(counting.calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+        assert_eq!(load_checkpoint(&checkpoint_path).unwrap().completed.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_saved_while_stage_still_running() {
+        let dir = tempdir().unwrap();
+        let checkpoint_path = dir.path().join("job.checkpoint.json");
+        let config = BatchConfig {
+            job: JobMetadata {
+                name: "slow-stage".to_string(),
+                description: None,
+                version: "1.0".to_string(),
+                tags: vec![],
+            },
+            tasks: vec![
+                task("stuck", "stalling", &[]),
+                task("quick", "counting", &[]),
+            ],
+            settings: BatchSettings {
+                checkpoint_file: Some(checkpoint_path.clone()),
+                checkpoint_interval: 1,
+                ..BatchSettings::default()
+            },
+        };
+
+        let orchestrator = Arc::new(initialize_orchestrator(&Settings::default()).await.unwrap());
+        orchestrator.register_agent("stalling".to_string(), Arc::new(StallingAgent)).await.unwrap();
+        orchestrator.register_agent("counting".to_string(), Arc::new(CountingAgent { calls: Default::default() })).await.unwrap();
+
+        let run = tokio::spawn(execute_batch(orchestrator, config, None));
+
+        // "quick" is checkpointed even though "stuck" in the same stage never finishes
+        let mut done = Vec::new();
+        for _ in 0..50 {
+            if let Ok(checkpoint) = load_checkpoint(&checkpoint_path) {
+                done = checkpoint.completed.into_iter().map(|r| r.task_id).collect();
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert!(!run.is_finished());
+        run.abort();
+        assert_eq!(done, vec!["quick".to_string()]);
+    }
 }
@@ -0,0 +1,69 @@
+//! Provenance of the running binary, captured at compile time by `build.rs`.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// What was built, from which commit, when and with which compiler
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub git_commit: &'static str,
+    /// RFC 3339 build time, or `None` if the build script could not read the clock
+    pub build_timestamp: Option<String>,
+    pub rustc_version: &'static str,
+    /// Cargo features compiled in, e.g. `with-redis`
+    pub features: Vec<&'static str>,
+}
+
+impl BuildInfo {
+    pub fn current() -> Self {
+        let build_timestamp = env!("AEP_BUILD_TIMESTAMP")
+            .parse::<i64>()
+            .ok()
+            .filter(|&seconds| seconds > 0)
+            .and_then(|seconds| DateTime::<Utc>::from_timestamp(seconds, 0))
+            .map(|time| time.to_rfc3339());
+
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            git_commit: env!("AEP_GIT_COMMIT"),
+            build_timestamp,
+            rustc_version: env!("AEP_RUSTC_VERSION"),
+            features: enabled_features(),
+        }
+    }
+}
+
+/// Optional features this binary was compiled with
+pub fn enabled_features() -> Vec<&'static str> {
+    [
+        ("with-llama", cfg!(feature = "with-llama")),
+        ("with-wasm", cfg!(feature = "with-wasm")),
+        ("with-redis", cfg!(feature = "with-redis")),
+        ("with-julia", cfg!(feature = "with-julia")),
+        ("with-zig", cfg!(feature = "with-zig")),
+        ("with-observability", cfg!(feature = "with-observability")),
+        ("with-vector-search", cfg!(feature = "with-vector-search")),
+        ("with-faiss", cfg!(feature = "with-faiss")),
+        ("with-metrics", cfg!(feature = "with-metrics")),
+        ("with-distributed", cfg!(feature = "with-distributed")),
+        ("with-grpc", cfg!(feature = "with-grpc")),
+    ]
+    .into_iter()
+    .filter_map(|(name, enabled)| enabled.then_some(name))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_info() {
+        let info = BuildInfo::current();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert!(!info.git_commit.is_empty());
+        assert!(!info.rustc_version.is_empty());
+        assert_eq!(info.features.contains(&"with-grpc"), cfg!(feature = "with-grpc"));
+    }
+}
@@ -22,6 +22,8 @@ pub struct CacheEntry<T> {
     pub value: T,
     pub created_at: SystemTime,
     pub expires_at: Option<SystemTime>,
+    /// Whether `expires_at` came from the caller rather than a tier's `default_ttl`
+    pub explicit_ttl: bool,
     pub access_count: u64,
     pub last_accessed: SystemTime,
     pub size_bytes: usize,
@@ -39,6 +41,7 @@ impl<T> CacheEntry<T> {
             value,
             created_at: now,
             expires_at,
+            explicit_ttl: ttl.is_some(),
             access_count: 0,
             last_accessed: now,
             size_bytes: 0, // Would be calculated based on serialized size
@@ -57,6 +60,13 @@ impl<T> CacheEntry<T> {
         self
     }
 
+    /// Apply the storing tier's default TTL, counted from now, unless the caller set one
+    pub fn apply_tier_ttl(&mut self, default_ttl: Option<Duration>) {
+        if !self.explicit_ttl {
+            self.expires_at = default_ttl.map(|ttl| SystemTime::now() + ttl);
+        }
+    }
+
     pub fn is_expired(&self) -> bool {
         if let Some(expires_at) = self.expires_at {
             SystemTime::now() > expires_at
@@ -115,6 +125,9 @@ pub struct MultiTierCacheConfig {
     pub bloom_filter_capacity: usize,
     pub bloom_filter_error_rate: f64,
     pub stats_collection_interval: Duration,
+    /// How often cold entries are demoted; also the window access counts are measured over.
+    /// Zero disables background demotion.
+    pub demotion_interval: Duration,
 }
 
 impl Default for MultiTierCacheConfig {
@@ -150,6 +163,7 @@ impl Default for MultiTierCacheConfig {
             bloom_filter_capacity: 100_000,
             bloom_filter_error_rate: 0.01,
             stats_collection_interval: Duration::from_secs(60),
+            demotion_interval: Duration::from_secs(300),
         }
     }
 }
@@ -174,17 +188,19 @@ pub struct CacheStats {
 pub struct MultiTierCache {
     config: MultiTierCacheConfig,
     tiers: Vec<Arc<dyn CacheTier>>,
-    bloom_filter: Option<Arc<Mutex<BloomFilter>>>,
+    bloom_filter: Option<Arc<Mutex<ScalableBloom>>>,
     stats: Arc<DashMap<String, CacheStats>>,
     global_stats: Arc<RwLock<GlobalCacheStats>>,
 }
 
-#[derive(Debug, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct GlobalCacheStats {
     pub total_requests: u64,
     pub total_hits: u64,
     pub total_misses: u64,
     pub overall_hit_rate: f64,
+    pub average_miss_time_ms: f64,
+    pub bloom_filter_rebuilds: u64,
     pub tier_performance: HashMap<String, f64>,
 }
 
@@ -214,14 +230,10 @@ impl MultiTierCache {
 
         // Initialize bloom filter if enabled
         let bloom_filter = if config.enable_bloom_filter {
-            let capacity = config.bloom_filter_capacity;
-            let error_rate = config.bloom_filter_error_rate;
-            let bitmap_size = BloomFilter::compute_bitmap_size(capacity, error_rate);
-            let optimal_hashes = BloomFilter::compute_optimal_hashes(bitmap_size, capacity);
-            
-            Some(Arc::new(Mutex::new(
-                BloomFilter::with_rate(error_rate, capacity)
-            )))
+            Some(Arc::new(Mutex::new(ScalableBloom::new(
+                config.bloom_filter_capacity,
+                config.bloom_filter_error_rate,
+            ))))
         } else {
             None
         };
@@ -269,9 +281,9 @@ impl MultiTierCache {
         // Check bloom filter first
         if let Some(ref bloom_filter) = self.bloom_filter {
             let bf = bloom_filter.lock().await;
-            if !bf.contains(&key) {
+            if !bf.contains(key) {
                 debug!("Bloom filter miss for key: {}", key);
-                self.record_miss(None).await;
+                self.record_miss(start_time.elapsed()).await;
                 return Ok(None);
             }
         }
@@ -289,40 +301,90 @@ impl MultiTierCache {
                     
                     return Ok(Some(entry.value));
                 }
-                Ok(None) => continue,
+                Ok(None) => {
+                    self.record_tier_miss(&tier.name());
+                    continue;
+                }
                 Err(e) => {
                     error!("Error accessing tier {}: {}", tier.name(), e);
+                    self.record_tier_miss(&tier.name());
                     continue;
                 }
             }
         }
 
-        self.record_miss(Some(start_time.elapsed())).await;
+        self.record_miss(start_time.elapsed()).await;
         Ok(None)
     }
 
-    /// Set value in cache
-    #[instrument(skip(self, value))]
+    /// Set value in cache.
+    ///
+    /// With `ttl: None` the entry expires per the first tier's `default_ttl`.
     pub async fn set<T>(&self, key: &str, value: T, ttl: Option<Duration>) -> Result<()>
     where
         T: Serialize + Clone + Send + Sync,
     {
-        let entry = CacheEntry::new(key.to_string(), value, ttl);
+        self.set_tagged(key, value, ttl, Vec::new()).await
+    }
+
+    /// Set value in cache under `tags`, so it can be dropped later with
+    /// [`MultiTierCache::invalidate_by_tag`]
+    #[instrument(skip(self, value))]
+    pub async fn set_tagged<T>(&self, key: &str, value: T, ttl: Option<Duration>, tags: Vec<String>) -> Result<()>
+    where
+        T: Serialize + Clone + Send + Sync,
+    {
+        let mut entry = CacheEntry::new(key.to_string(), value, ttl).with_tags(tags);
         
-        // Add to bloom filter
+        // Add to bloom filter; the key stays pending until the tier write lands
         if let Some(ref bloom_filter) = self.bloom_filter {
-            let mut bf = bloom_filter.lock().await;
-            bf.insert(&key);
+            bloom_filter.lock().await.insert(key);
         }
 
         // Store in first tier (L1)
-        if let Some(first_tier) = self.tiers.first() {
-            first_tier.set(key, entry).await?;
+        let result = if let Some(first_tier) = self.tiers.first() {
+            entry.apply_tier_ttl(first_tier.default_ttl());
+            first_tier.set(key, entry).await
         } else {
-            return Err(anyhow!("No cache tiers configured"));
+            Err(anyhow!("No cache tiers configured"))
+        };
+
+        if let Some(ref bloom_filter) = self.bloom_filter {
+            let mut bf = bloom_filter.lock().await;
+            bf.complete_insert(key);
+            if bf.needs_rebuild() {
+                self.rebuild_bloom_filter(&mut bf).await;
+            }
         }
 
-        Ok(())
+        result
+    }
+
+    /// Rebuild the bloom filter from the keys currently held in the tiers.
+    ///
+    /// Collapses any growth layers into one filter sized for the live key set
+    /// and drops stale positives left behind by deletes. Callers hold the
+    /// filter lock, so no insert can slip in between enumeration and the swap.
+    async fn rebuild_bloom_filter(&self, bf: &mut ScalableBloom) {
+        let mut keys: Vec<String> = bf.pending_keys();
+        for tier in &self.tiers {
+            match tier.keys().await {
+                Ok(Some(tier_keys)) => keys.extend(tier_keys),
+                Ok(None) => {
+                    debug!("Tier {} cannot enumerate keys, keeping scalable bloom filter", tier.name());
+                    bf.mark_rebuild_unsupported();
+                    return;
+                }
+                Err(e) => {
+                    warn!("Failed to enumerate keys in tier {} for bloom rebuild: {}", tier.name(), e);
+                    return;
+                }
+            }
+        }
+
+        bf.rebuild(&keys);
+        self.global_stats.write().await.bloom_filter_rebuilds += 1;
+        debug!("Rebuilt bloom filter with {} keys", keys.len());
     }
 
     /// Delete value from all tiers
@@ -335,6 +397,17 @@ impl MultiTierCache {
                 deleted = true;
             }
         }
+
+        // Deleted keys still test positive; rebuild once too many have piled up
+        if deleted {
+            if let Some(ref bloom_filter) = self.bloom_filter {
+                let mut bf = bloom_filter.lock().await;
+                bf.record_delete();
+                if bf.needs_rebuild() {
+                    self.rebuild_bloom_filter(&mut bf).await;
+                }
+            }
+        }
         
         Ok(deleted)
     }
@@ -347,11 +420,7 @@ impl MultiTierCache {
 
         // Clear bloom filter
         if let Some(ref bloom_filter) = self.bloom_filter {
-            let mut bf = bloom_filter.lock().await;
-            *bf = BloomFilter::with_rate(
-                self.config.bloom_filter_error_rate,
-                self.config.bloom_filter_capacity,
-            );
+            bloom_filter.lock().await.rebuild(&[]);
         }
 
         Ok(())
@@ -387,7 +456,9 @@ impl MultiTierCache {
     {
         if current_tier > 0 {
             let target_tier = &self.tiers[current_tier - 1];
-            if let Err(e) = target_tier.set(key, entry.clone()).await {
+            let mut promoted = entry.clone();
+            promoted.apply_tier_ttl(target_tier.default_ttl());
+            if let Err(e) = target_tier.set(key, promoted).await {
                 error!("Failed to promote entry to tier {}: {}", target_tier.name(), e);
             } else {
                 // Update stats
@@ -398,13 +469,28 @@ impl MultiTierCache {
         }
     }
 
+    /// Demote entries that were accessed fewer than `demotion_threshold` times
+    /// during the last `demotion_interval` to the next lower tier.
+    ///
+    /// Runs periodically in the background; returns the number of entries moved.
+    pub async fn demote_cold_entries(&self) -> u64 {
+        demote_cold_entries(
+            &self.tiers,
+            &self.stats,
+            self.config.demotion_threshold,
+            self.config.demotion_interval,
+        )
+        .await
+    }
+
     /// Record cache hit
     async fn record_hit(&self, tier_name: &str, access_time: Duration) {
         if let Some(mut stats) = self.stats.get_mut(tier_name) {
             stats.hit_count += 1;
             stats.hit_rate = stats.hit_count as f64 / (stats.hit_count + stats.miss_count) as f64;
-            stats.average_access_time_ms = 
-                (stats.average_access_time_ms + access_time.as_millis() as f64) / 2.0;
+            // Running mean over all hits in this tier
+            stats.average_access_time_ms +=
+                (duration_ms(access_time) - stats.average_access_time_ms) / stats.hit_count as f64;
         }
 
         let mut global_stats = self.global_stats.write().await;
@@ -414,13 +500,23 @@ impl MultiTierCache {
             global_stats.total_hits as f64 / global_stats.total_requests as f64;
     }
 
-    /// Record cache miss
-    async fn record_miss(&self, access_time: Option<Duration>) {
+    /// Record a lookup that fell through a tier without finding the key
+    fn record_tier_miss(&self, tier_name: &str) {
+        if let Some(mut stats) = self.stats.get_mut(tier_name) {
+            stats.miss_count += 1;
+            stats.hit_rate = stats.hit_count as f64 / (stats.hit_count + stats.miss_count) as f64;
+        }
+    }
+
+    /// Record cache miss across all tiers, tracking how long the miss took to determine
+    async fn record_miss(&self, access_time: Duration) {
         let mut global_stats = self.global_stats.write().await;
         global_stats.total_requests += 1;
         global_stats.total_misses += 1;
         global_stats.overall_hit_rate = 
             global_stats.total_hits as f64 / global_stats.total_requests as f64;
+        global_stats.average_miss_time_ms +=
+            (duration_ms(access_time) - global_stats.average_miss_time_ms) / global_stats.total_misses as f64;
     }
 
     /// Start background maintenance tasks
@@ -444,6 +540,26 @@ impl MultiTierCache {
             }
         });
 
+        // Demotion task: move entries that went cold to lower tiers
+        let tiers = self.tiers.clone();
+        let stats = self.stats.clone();
+        let demotion_threshold = self.config.demotion_threshold;
+        let demotion_interval = self.config.demotion_interval;
+        if !demotion_interval.is_zero() {
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(demotion_interval);
+                interval.tick().await; // First tick completes immediately
+
+                loop {
+                    interval.tick().await;
+                    let demoted = demote_cold_entries(&tiers, &stats, demotion_threshold, demotion_interval).await;
+                    if demoted > 0 {
+                        debug!("Demoted {} cold cache entries", demoted);
+                    }
+                }
+            });
+        }
+
         // Cleanup expired entries task
         let tiers = self.tiers.clone();
         tokio::spawn(async move {
@@ -466,6 +582,8 @@ impl MultiTierCache {
 #[async_trait::async_trait]
 pub trait CacheTier: Send + Sync {
     fn name(&self) -> String;
+    /// TTL applied to entries stored without an explicit one
+    fn default_ttl(&self) -> Option<Duration>;
     async fn get<T>(&self, key: &str) -> Result<Option<CacheEntry<T>>> where T: DeserializeOwned + Send;
     async fn set<T>(&self, key: &str, entry: CacheEntry<T>) -> Result<()> where T: Serialize + Send;
     async fn delete(&self, key: &str) -> Result<bool>;
@@ -474,6 +592,167 @@ pub trait CacheTier: Send + Sync {
     async fn cleanup_expired(&self) -> Result<u64>;
     async fn get_size(&self) -> Result<usize>;
     async fn get_entry_count(&self) -> Result<usize>;
+    /// Keys accessed fewer than `threshold` times in the current window and older
+    /// than `min_age`; starts a new window by resetting every entry's access count
+    async fn take_cold_keys(&self, threshold: u64, min_age: Duration) -> Result<Vec<String>>;
+    /// Remove an entry and return its serialized form and metadata, for moving between tiers
+    async fn export_entry(&self, key: &str) -> Result<Option<(Vec<u8>, CacheEntry<()>)>>;
+    /// Store an entry previously returned by `export_entry`
+    async fn import_entry(&self, key: &str, data: Vec<u8>, meta: CacheEntry<()>) -> Result<()>;
+    /// All keys held by the tier, or `None` if the backend cannot enumerate them
+    async fn keys(&self) -> Result<Option<Vec<String>>>;
+}
+
+/// Bloom filter that grows with the working set instead of saturating.
+///
+/// When the newest layer reaches its capacity a layer twice as large (with a
+/// tighter error rate) is added, bounding the compound false-positive rate.
+/// Deletes cannot be removed from a bloom filter, so they are counted as stale
+/// positives; these only cost an extra tier lookup. Whenever there is more than
+/// one layer, or stale positives exceed half the capacity, the owning cache
+/// rebuilds a single filter from the live keys.
+struct ScalableBloom {
+    layers: Vec<BloomFilter>,
+    /// Capacity of the newest layer
+    layer_capacity: usize,
+    /// Inserts into the newest layer
+    layer_inserts: usize,
+    /// Capacity of all layers combined
+    total_capacity: usize,
+    initial_capacity: usize,
+    error_rate: f64,
+    stale_positives: usize,
+    /// Keys inserted whose tier write has not completed yet, with in-flight counts
+    pending: HashMap<String, usize>,
+    rebuild_supported: bool,
+}
+
+impl ScalableBloom {
+    fn new(capacity: usize, error_rate: f64) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            layers: vec![Self::layer(capacity, error_rate)],
+            layer_capacity: capacity,
+            layer_inserts: 0,
+            total_capacity: capacity,
+            initial_capacity: capacity,
+            error_rate,
+            stale_positives: 0,
+            pending: HashMap::new(),
+            rebuild_supported: true,
+        }
+    }
+
+    fn layer(capacity: usize, error_rate: f64) -> BloomFilter {
+        BloomFilter::with_rate(error_rate as f32, capacity.min(u32::MAX as usize) as u32)
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.layers.iter().any(|layer| layer.contains(&key))
+    }
+
+    fn insert(&mut self, key: &str) {
+        if self.layer_inserts >= self.layer_capacity {
+            // Each new layer halves its error rate so the sum stays bounded
+            self.layer_capacity *= 2;
+            let rate = self.error_rate / 2f64.powi(self.layers.len() as i32);
+            self.layers.push(Self::layer(self.layer_capacity, rate));
+            self.total_capacity += self.layer_capacity;
+            self.layer_inserts = 0;
+        }
+        if let Some(layer) = self.layers.last_mut() {
+            layer.insert(&key);
+        }
+        self.layer_inserts += 1;
+        *self.pending.entry(key.to_string()).or_insert(0) += 1;
+    }
+
+    fn complete_insert(&mut self, key: &str) {
+        if let Some(count) = self.pending.get_mut(key) {
+            *count -= 1;
+            if *count == 0 {
+                self.pending.remove(key);
+            }
+        }
+    }
+
+    fn record_delete(&mut self) {
+        self.stale_positives += 1;
+    }
+
+    fn needs_rebuild(&self) -> bool {
+        self.rebuild_supported
+            && (self.layers.len() > 1 || self.stale_positives > self.total_capacity / 2)
+    }
+
+    fn mark_rebuild_unsupported(&mut self) {
+        self.rebuild_supported = false;
+    }
+
+    fn pending_keys(&self) -> Vec<String> {
+        self.pending.keys().cloned().collect()
+    }
+
+    /// Replace all layers with one filter holding `keys`, with headroom to grow
+    fn rebuild(&mut self, keys: &[String]) {
+        let capacity = (keys.len() * 2).max(self.initial_capacity);
+        let mut layer = Self::layer(capacity, self.error_rate);
+        for key in keys {
+            layer.insert(key);
+        }
+        self.layers = vec![layer];
+        self.layer_capacity = capacity;
+        self.layer_inserts = keys.len();
+        self.total_capacity = capacity;
+        self.stale_positives = 0;
+    }
+}
+
+/// Move cold entries from each tier to the tier below it, updating demotion stats
+async fn demote_cold_entries(
+    tiers: &[Arc<dyn CacheTier>],
+    stats: &DashMap<String, CacheStats>,
+    threshold: u64,
+    window: Duration,
+) -> u64 {
+    let mut demoted = 0;
+
+    // The last tier has nowhere to demote to
+    for pair in tiers.windows(2) {
+        let (source, target) = (&pair[0], &pair[1]);
+        let cold_keys = match source.take_cold_keys(threshold, window).await {
+            Ok(keys) => keys,
+            Err(e) => {
+                error!("Failed to scan tier {} for cold entries: {}", source.name(), e);
+                continue;
+            }
+        };
+
+        for key in cold_keys {
+            let (data, meta) = match source.export_entry(&key).await {
+                Ok(Some(exported)) => exported,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to export entry from tier {}: {}", source.name(), e);
+                    continue;
+                }
+            };
+            if let Err(e) = target.import_entry(&key, data, meta).await {
+                error!("Failed to demote entry to tier {}: {}", target.name(), e);
+                continue;
+            }
+            if let Some(mut tier_stats) = stats.get_mut(&target.name()) {
+                tier_stats.demotion_count += 1;
+            }
+            demoted += 1;
+        }
+    }
+
+    demoted
+}
+
+fn duration_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
 }
 
 /// In-memory cache tier implementation
@@ -504,6 +783,10 @@ impl CacheTier for MemoryCacheTier {
         self.config.name.clone()
     }
 
+    fn default_ttl(&self) -> Option<Duration> {
+        self.config.default_ttl
+    }
+
     async fn get<T>(&self, key: &str) -> Result<Option<CacheEntry<T>>> 
     where 
         T: DeserializeOwned + Send 
@@ -514,17 +797,27 @@ impl CacheTier for MemoryCacheTier {
         };
 
         if let Some(data) = data {
-            let entry: CacheEntry<T> = bincode::deserialize(&data)?;
-            if entry.is_expired() {
+            let mut entry: CacheEntry<T> = bincode::deserialize(&data)?;
+
+            // Bookkeeping lives in the metadata, which is updated when entries
+            // move between tiers; the serialized entry is never rewritten
+            let expired = match self.metadata.get_mut(key) {
+                Some(mut meta) if !meta.is_expired() => {
+                    meta.access();
+                    entry.expires_at = meta.expires_at;
+                    entry.explicit_ttl = meta.explicit_ttl;
+                    entry.access_count = meta.access_count;
+                    entry.last_accessed = meta.last_accessed;
+                    false
+                }
+                Some(_) => true,
+                None => entry.is_expired(),
+            };
+            if expired {
                 self.delete(key).await?;
                 return Ok(None);
             }
             
-            // Update metadata
-            if let Some(mut meta) = self.metadata.get_mut(key) {
-                meta.access();
-            }
-            
             Ok(Some(entry))
         } else {
             Ok(None)
@@ -548,6 +841,7 @@ impl CacheTier for MemoryCacheTier {
             value: (),
             created_at: entry.created_at,
             expires_at: entry.expires_at,
+            explicit_ttl: entry.explicit_ttl,
             access_count: entry.access_count,
             last_accessed: entry.last_accessed,
             size_bytes: entry.size_bytes,
@@ -627,6 +921,42 @@ impl CacheTier for MemoryCacheTier {
     async fn get_entry_count(&self) -> Result<usize> {
         Ok(self.metadata.len())
     }
+
+    async fn keys(&self) -> Result<Option<Vec<String>>> {
+        Ok(Some(self.metadata.iter().map(|entry| entry.key().clone()).collect()))
+    }
+
+    async fn take_cold_keys(&self, threshold: u64, min_age: Duration) -> Result<Vec<String>> {
+        let now = SystemTime::now();
+        let mut cold = Vec::new();
+
+        for mut entry in self.metadata.iter_mut() {
+            let old_enough = now
+                .duration_since(entry.created_at)
+                .map(|age| age >= min_age)
+                .unwrap_or(false);
+            if old_enough && entry.access_count < threshold {
+                cold.push(entry.key().clone());
+            }
+            entry.access_count = 0;
+        }
+
+        Ok(cold)
+    }
+
+    async fn export_entry(&self, key: &str) -> Result<Option<(Vec<u8>, CacheEntry<()>)>> {
+        let data = self.cache.write().pop(key);
+        let meta = self.metadata.remove(key).map(|(_, meta)| meta);
+        Ok(data.zip(meta))
+    }
+
+    async fn import_entry(&self, key: &str, data: Vec<u8>, mut meta: CacheEntry<()>) -> Result<()> {
+        meta.access_count = 0;
+        meta.apply_tier_ttl(self.config.default_ttl);
+        self.cache.write().put(key.to_string(), data);
+        self.metadata.insert(key.to_string(), meta);
+        Ok(())
+    }
 }
 
 /// Redis cache tier implementation (placeholder)
@@ -648,6 +978,7 @@ impl RedisCacheTier {
 #[async_trait::async_trait]
 impl CacheTier for RedisCacheTier {
     fn name(&self) -> String { self.config.name.clone() }
+    fn default_ttl(&self) -> Option<Duration> { self.config.default_ttl }
     async fn get<T>(&self, _key: &str) -> Result<Option<CacheEntry<T>>> where T: DeserializeOwned + Send { Ok(None) }
     async fn set<T>(&self, _key: &str, _entry: CacheEntry<T>) -> Result<()> where T: Serialize + Send { Ok(()) }
     async fn delete(&self, _key: &str) -> Result<bool> { Ok(false) }
@@ -656,6 +987,10 @@ impl CacheTier for RedisCacheTier {
     async fn cleanup_expired(&self) -> Result<u64> { Ok(0) }
     async fn get_size(&self) -> Result<usize> { Ok(0) }
     async fn get_entry_count(&self) -> Result<usize> { Ok(0) }
+    async fn take_cold_keys(&self, _threshold: u64, _min_age: Duration) -> Result<Vec<String>> { Ok(Vec::new()) }
+    async fn export_entry(&self, _key: &str) -> Result<Option<(Vec<u8>, CacheEntry<()>)>> { Ok(None) }
+    async fn import_entry(&self, _key: &str, _data: Vec<u8>, _meta: CacheEntry<()>) -> Result<()> { Ok(()) }
+    async fn keys(&self) -> Result<Option<Vec<String>>> { Ok(None) }
 }
 
 /// Disk cache tier implementation (placeholder)
@@ -676,6 +1011,7 @@ impl DiskCacheTier {
 #[async_trait::async_trait]
 impl CacheTier for DiskCacheTier {
     fn name(&self) -> String { self.config.name.clone() }
+    fn default_ttl(&self) -> Option<Duration> { self.config.default_ttl }
     async fn get<T>(&self, _key: &str) -> Result<Option<CacheEntry<T>>> where T: DeserializeOwned + Send { Ok(None) }
     async fn set<T>(&self, _key: &str, _entry: CacheEntry<T>) -> Result<()> where T: Serialize + Send { Ok(()) }
     async fn delete(&self, _key: &str) -> Result<bool> { Ok(false) }
@@ -684,6 +1020,10 @@ impl CacheTier for DiskCacheTier {
     async fn cleanup_expired(&self) -> Result<u64> { Ok(0) }
     async fn get_size(&self) -> Result<usize> { Ok(0) }
     async fn get_entry_count(&self) -> Result<usize> { Ok(0) }
+    async fn take_cold_keys(&self, _threshold: u64, _min_age: Duration) -> Result<Vec<String>> { Ok(Vec::new()) }
+    async fn export_entry(&self, _key: &str) -> Result<Option<(Vec<u8>, CacheEntry<()>)>> { Ok(None) }
+    async fn import_entry(&self, _key: &str, _data: Vec<u8>, _meta: CacheEntry<()>) -> Result<()> { Ok(()) }
+    async fn keys(&self) -> Result<Option<Vec<String>>> { Ok(None) }
 }
 
 /// Distributed cache tier implementation (placeholder)
@@ -700,6 +1040,7 @@ impl DistributedCacheTier {
 #[async_trait::async_trait]
 impl CacheTier for DistributedCacheTier {
     fn name(&self) -> String { self.config.name.clone() }
+    fn default_ttl(&self) -> Option<Duration> { self.config.default_ttl }
     async fn get<T>(&self, _key: &str) -> Result<Option<CacheEntry<T>>> where T: DeserializeOwned + Send { Ok(None) }
     async fn set<T>(&self, _key: &str, _entry: CacheEntry<T>) -> Result<()> where T: Serialize + Send { Ok(()) }
     async fn delete(&self, _key: &str) -> Result<bool> { Ok(false) }
@@ -708,10 +1049,153 @@ impl CacheTier for DistributedCacheTier {
     async fn cleanup_expired(&self) -> Result<u64> { Ok(0) }
     async fn get_size(&self) -> Result<usize> { Ok(0) }
     async fn get_entry_count(&self) -> Result<usize> { Ok(0) }
+    async fn take_cold_keys(&self, _threshold: u64, _min_age: Duration) -> Result<Vec<String>> { Ok(Vec::new()) }
+    async fn export_entry(&self, _key: &str) -> Result<Option<(Vec<u8>, CacheEntry<()>)>> { Ok(None) }
+    async fn import_entry(&self, _key: &str, _data: Vec<u8>, _meta: CacheEntry<()>) -> Result<()> { Ok(()) }
+    async fn keys(&self) -> Result<Option<Vec<String>>> { Ok(None) }
 }
 
 // Helper function for tier memory usage (would be implemented properly)
 fn get_tier_memory_usage(_tier_name: &str) -> usize {
     // Placeholder implementation
     1024 * 1024 // 1MB
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn two_tier_cache() -> MultiTierCache {
+        let mut config = MultiTierCacheConfig::default();
+        config.enable_bloom_filter = false;
+        config.promotion_threshold = 2;
+        config.demotion_threshold = 1;
+        config.demotion_interval = Duration::ZERO;
+        MultiTierCache::new(config).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_cold_entries_are_demoted() {
+        let cache = two_tier_cache().await;
+        cache.set("hot", 1u32, None).await.unwrap();
+        cache.set("cold", 2u32, None).await.unwrap();
+        assert_eq!(cache.get::<u32>("hot").await.unwrap(), Some(1));
+
+        assert_eq!(cache.demote_cold_entries().await, 1);
+        assert_eq!(cache.tiers[0].get_entry_count().await.unwrap(), 1);
+        assert_eq!(cache.tiers[1].get_entry_count().await.unwrap(), 1);
+
+        // Demoted entries are still served from the lower tier
+        assert_eq!(cache.get::<u32>("cold").await.unwrap(), Some(2));
+
+        let stats = cache.get_stats().await;
+        assert_eq!(stats["L2"].demotion_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_access_promotes_demoted_entry() {
+        let cache = two_tier_cache().await;
+        cache.set("key", 7u32, None).await.unwrap();
+        cache.demote_cold_entries().await;
+
+        cache.get::<u32>("key").await.unwrap();
+        cache.get::<u32>("key").await.unwrap();
+
+        let stats = cache.get_stats().await;
+        assert_eq!(stats["L1"].promotion_count, 1);
+        assert_eq!(stats["L1"].miss_count, 2);
+        assert_eq!(stats["L2"].hit_count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_set_without_ttl_uses_tier_default() {
+        let cache = two_tier_cache().await;
+        cache.set("default", 1u32, None).await.unwrap();
+        cache.set("explicit", 2u32, Some(Duration::from_secs(30))).await.unwrap();
+
+        // L1's configured default is 5 minutes
+        let meta = cache.tiers[0].export_entry("default").await.unwrap().unwrap().1;
+        let ttl = meta.expires_at.unwrap().duration_since(meta.created_at).unwrap();
+        assert!(ttl >= Duration::from_secs(300) && ttl < Duration::from_secs(301));
+        assert!(!meta.explicit_ttl);
+
+        let meta = cache.tiers[0].export_entry("explicit").await.unwrap().unwrap().1;
+        let ttl = meta.expires_at.unwrap().duration_since(meta.created_at).unwrap();
+        assert!(ttl < Duration::from_secs(31));
+        assert!(meta.explicit_ttl);
+    }
+
+    #[tokio::test]
+    async fn test_entry_expires_after_tier_default_ttl() {
+        let mut config = MultiTierCacheConfig::default();
+        config.enable_bloom_filter = false;
+        config.tiers[0].default_ttl = Some(Duration::from_millis(50));
+        let cache = MultiTierCache::new(config).await.unwrap();
+
+        cache.set("key", 1u32, None).await.unwrap();
+        assert_eq!(cache.get::<u32>("key").await.unwrap(), Some(1));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(cache.get::<u32>("key").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_demoted_entry_gets_target_tier_ttl() {
+        let cache = two_tier_cache().await;
+        cache.set("key", 1u32, None).await.unwrap();
+        cache.demote_cold_entries().await;
+
+        // L2's configured default is 1 hour
+        let meta = cache.tiers[1].export_entry("key").await.unwrap().unwrap().1;
+        let remaining = meta.expires_at.unwrap().duration_since(SystemTime::now()).unwrap();
+        assert!(remaining > Duration::from_secs(3590));
+    }
+
+    #[test]
+    fn test_scalable_bloom_grows_and_rebuilds() {
+        let mut bloom = ScalableBloom::new(4, 0.01);
+        let keys: Vec<String> = (0..10).map(|i| format!("key-{}", i)).collect();
+        for key in &keys {
+            bloom.insert(key);
+            bloom.complete_insert(key);
+        }
+
+        // Exceeding capacity adds layers instead of saturating the first
+        assert!(bloom.layers.len() > 1);
+        assert!(bloom.needs_rebuild());
+        assert!(keys.iter().all(|k| bloom.contains(k)));
+
+        bloom.rebuild(&keys[..5]);
+        assert_eq!(bloom.layers.len(), 1);
+        assert!(!bloom.needs_rebuild());
+        assert!(keys[..5].iter().all(|k| bloom.contains(k)));
+    }
+
+    #[tokio::test]
+    async fn test_bloom_filter_rebuilt_as_working_set_grows() {
+        let mut config = MultiTierCacheConfig::default();
+        config.bloom_filter_capacity = 8;
+        let cache = MultiTierCache::new(config).await.unwrap();
+
+        for i in 0..50u32 {
+            cache.set(&format!("key-{}", i), i, None).await.unwrap();
+        }
+
+        // Every live key is still found after the filter was rebuilt
+        for i in 0..50u32 {
+            assert_eq!(cache.get::<u32>(&format!("key-{}", i)).await.unwrap(), Some(i));
+        }
+        assert!(cache.get_global_stats().await.bloom_filter_rebuilds > 0);
+        assert_eq!(cache.bloom_filter.as_ref().unwrap().lock().await.layers.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_miss_latency_tracked() {
+        let cache = two_tier_cache().await;
+        assert_eq!(cache.get::<u32>("missing").await.unwrap(), None);
+
+        let global = cache.get_global_stats().await;
+        assert_eq!(global.total_misses, 1);
+        assert!(global.average_miss_time_ms >= 0.0);
+    }
+}
@@ -181,3 +181,39 @@ fn get_tier_memory_usage(_tier_name: &str) -> usize {
     1024 * 1024 // 1MB
 }
 
+/// Command line interface
+#[derive(clap::Parser, Debug)]
+#[command(name = "adaptive_expert_platform", version, about = "Adaptive Expert Platform")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Commands,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum Commands {
+    /// Start the HTTP server
+    Serve {
+        #[arg(long)]
+        addr: Option<String>,
+    },
+    /// Run a batch job from a TOML config
+    Run {
+        config: std::path::PathBuf,
+        /// Resume from a checkpoint written by an interrupted run of the same job
+        #[arg(long)]
+        resume: Option<std::path::PathBuf>,
+        /// Write the task dependency graph to this file as Graphviz DOT
+        /// instead of running the job
+        #[arg(long, value_name = "OUT.dot", conflicts_with = "resume")]
+        graph: Option<std::path::PathBuf>,
+    },
+    /// Create the first admin user
+    InitAdmin {
+        #[arg(long)]
+        username: String,
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Apply pending auth database schema migrations
+    Migrate,
+}
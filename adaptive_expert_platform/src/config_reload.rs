@@ -0,0 +1,271 @@
+//! Applying configuration changes to a running server.
+//!
+//! On SIGHUP (or `POST /admin/reload-config`) the server re-reads
+//! [`Settings`] and applies the settings listed in [`HOT_RELOADABLE`] in
+//! place. Everything else sizes or wires up a subsystem at startup (the bind
+//! address, TLS files, the auth database path, the memory backend, ...);
+//! changes to those are logged and take effect on the next restart.
+
+use std::collections::BTreeMap;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::{Mutex, RwLock};
+use tracing::{error, info, warn};
+
+use crate::memory::{validate_similarity_threshold, Memory};
+use crate::middleware::SharedRateLimiter;
+use crate::orchestrator::Orchestrator;
+use crate::settings::Settings;
+
+/// Settings applied without a restart
+pub const HOT_RELOADABLE: &[&str] = &[
+    "logging.level",
+    "memory.embedding_timeout_seconds",
+    "memory.similarity_threshold",
+    "orchestrator.max_concurrent_tasks",
+    "orchestrator.task_timeout_seconds",
+    "security.rate_limit_per_minute",
+];
+
+/// Outcome of applying reloaded settings, by dotted setting name
+#[derive(Debug, Default, Serialize)]
+pub struct ReloadReport {
+    /// Changed settings now in effect
+    pub applied: Vec<String>,
+    /// Changed settings that only take effect on restart
+    pub restart_required: Vec<String>,
+    /// Hot-reloadable settings whose new value was rejected; the old one stays
+    pub failed: Vec<String>,
+}
+
+/// Dotted names of the settings whose values differ, in name order.
+///
+/// Maps such as `orchestrator.user_budgets` are compared per entry; lists
+/// are compared whole.
+pub fn changed_settings(current: &Settings, new: &Settings) -> Result<Vec<String>> {
+    let mut current_values = BTreeMap::new();
+    flatten("", serde_json::to_value(current)?, &mut current_values);
+    let mut new_values = BTreeMap::new();
+    flatten("", serde_json::to_value(new)?, &mut new_values);
+
+    let mut changed: Vec<String> = current_values.iter()
+        .filter(|(key, value)| new_values.get(*key) != Some(*value))
+        .map(|(key, _)| key.clone())
+        .collect();
+    changed.extend(new_values.keys().filter(|key| !current_values.contains_key(*key)).cloned());
+    changed.sort();
+    Ok(changed)
+}
+
+fn flatten(prefix: &str, value: Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                let path = if prefix.is_empty() { key } else { format!("{}.{}", prefix, key) };
+                flatten(&path, value, out);
+            }
+        }
+        value => {
+            out.insert(prefix.to_string(), value);
+        }
+    }
+}
+
+/// Holds the settings in effect and the runtime handles they are applied to
+pub struct ConfigReloader {
+    /// Startup settings with every successfully reloaded value applied
+    current: Mutex<Settings>,
+    rate_limiter: Arc<SharedRateLimiter>,
+    orchestrator: Arc<RwLock<Orchestrator>>,
+    memory: Arc<Memory>,
+}
+
+impl ConfigReloader {
+    pub fn new(
+        settings: Settings,
+        rate_limiter: Arc<SharedRateLimiter>,
+        orchestrator: Arc<RwLock<Orchestrator>>,
+        memory: Arc<Memory>,
+    ) -> Self {
+        Self { current: Mutex::new(settings), rate_limiter, orchestrator, memory }
+    }
+
+    /// Apply the hot-reloadable settings that differ from those in effect.
+    ///
+    /// Other changes are reported and logged but not recorded as in effect,
+    /// so every later reload keeps warning about them until a restart.
+    pub async fn apply(&self, new: &Settings) -> Result<ReloadReport> {
+        let mut current = self.current.lock().await;
+        let mut report = ReloadReport::default();
+        for key in changed_settings(&current, new)? {
+            if !HOT_RELOADABLE.contains(&key.as_str()) {
+                report.restart_required.push(key);
+                continue;
+            }
+            match self.apply_setting(&key, new, &mut current).await {
+                Ok(()) => report.applied.push(key),
+                Err(e) => {
+                    warn!("Keeping the current {}: {}", key, e);
+                    report.failed.push(key);
+                }
+            }
+        }
+
+        if !report.applied.is_empty() {
+            info!("Applied configuration changes to {}", report.applied.join(", "));
+        }
+        if !report.restart_required.is_empty() {
+            warn!(
+                "Ignoring configuration changes to {} until the server restarts",
+                report.restart_required.join(", ")
+            );
+        }
+        Ok(report)
+    }
+
+    async fn apply_setting(&self, key: &str, new: &Settings, current: &mut Settings) -> Result<()> {
+        match key {
+            "logging.level" => {
+                crate::telemetry::set_log_level(&new.logging.level)?;
+                current.logging.level = new.logging.level.clone();
+            }
+            "memory.embedding_timeout_seconds" => {
+                self.memory.set_embedding_timeout(Duration::from_secs(new.memory.embedding_timeout_seconds));
+                current.memory.embedding_timeout_seconds = new.memory.embedding_timeout_seconds;
+            }
+            "memory.similarity_threshold" => {
+                validate_similarity_threshold(new.memory.similarity_threshold)?;
+                self.memory.set_similarity_threshold(new.memory.similarity_threshold);
+                current.memory.similarity_threshold = new.memory.similarity_threshold;
+            }
+            "orchestrator.max_concurrent_tasks" => {
+                if new.orchestrator.max_concurrent_tasks == 0 {
+                    return Err(anyhow!("Max concurrent tasks cannot be 0"));
+                }
+                self.orchestrator.read().await.set_max_concurrent_tasks(new.orchestrator.max_concurrent_tasks);
+                current.orchestrator.max_concurrent_tasks = new.orchestrator.max_concurrent_tasks;
+            }
+            "orchestrator.task_timeout_seconds" => {
+                if new.orchestrator.task_timeout_seconds == 0 {
+                    return Err(anyhow!("Task timeout cannot be 0"));
+                }
+                self.orchestrator.read().await
+                    .set_task_timeout(Duration::from_secs(new.orchestrator.task_timeout_seconds));
+                current.orchestrator.task_timeout_seconds = new.orchestrator.task_timeout_seconds;
+            }
+            // The new limiter starts with a full burst; see `SharedRateLimiter::set_per_minute`
+            "security.rate_limit_per_minute" => {
+                let per_minute = NonZeroU32::new(new.security.rate_limit_per_minute)
+                    .ok_or_else(|| anyhow!("Rate limit cannot be 0"))?;
+                self.rate_limiter.set_per_minute(per_minute);
+                current.security.rate_limit_per_minute = new.security.rate_limit_per_minute;
+            }
+            _ => return Err(anyhow!("{} is not hot-reloadable", key)),
+        }
+        Ok(())
+    }
+}
+
+/// Reload and apply the configuration whenever the process receives SIGHUP.
+///
+/// A configuration that fails to load or validate is logged and changes nothing.
+#[cfg(unix)]
+pub fn spawn_sighup_listener(reloader: Arc<ConfigReloader>) -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = signal(SignalKind::hangup())?;
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            info!("Received SIGHUP, reloading configuration");
+            let settings = match tokio::task::spawn_blocking(Settings::load).await {
+                Ok(Ok(settings)) => settings,
+                Ok(Err(e)) => {
+                    warn!("Rejected configuration reload: {:#}", e);
+                    continue;
+                }
+                Err(e) => {
+                    error!("Configuration reload task failed: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = reloader.apply(&settings).await {
+                error!("Configuration reload failed: {}", e);
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::redis_store::InMemoryEmbeddingCache;
+    use crate::agent::{HashEmbeddingAgent, LengthRerankAgent};
+    use crate::middleware::create_rate_limiter;
+
+    #[test]
+    fn test_changed_settings_names_leaf_values() {
+        let current = Settings::default();
+        let mut new = current.clone();
+        new.server.port = 9000;
+        new.orchestrator.user_budgets.insert("alice".to_string(), 10.0);
+        new.security.rate_limit_per_minute = 5;
+
+        assert_eq!(
+            changed_settings(&current, &new).unwrap(),
+            vec!["orchestrator.user_budgets.alice", "security.rate_limit_per_minute", "server.port"]
+        );
+        assert!(changed_settings(&current, &current).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_apply_hot_settings_and_report_the_rest() {
+        let settings = Settings::default();
+        let memory = Arc::new(
+            Memory::new(
+                Arc::new(HashEmbeddingAgent::new(384)),
+                Arc::new(LengthRerankAgent::new()),
+                Arc::new(InMemoryEmbeddingCache::new()),
+            )
+            .with_similarity_threshold(settings.memory.similarity_threshold),
+        );
+        let orchestrator = Arc::new(RwLock::new(Orchestrator::new(&settings, memory.clone()).await.unwrap()));
+        let rate_limiter = create_rate_limiter(&settings.security);
+        let reloader = ConfigReloader::new(settings.clone(), rate_limiter.clone(), orchestrator, memory.clone());
+
+        let mut new = settings.clone();
+        new.memory.similarity_threshold = 0.5;
+        new.orchestrator.max_concurrent_tasks = 2;
+        new.orchestrator.task_timeout_seconds = 5;
+        new.security.rate_limit_per_minute = 1;
+        new.db_path = Some("/elsewhere/auth".to_string());
+
+        let report = reloader.apply(&new).await.unwrap();
+        assert_eq!(report.applied, vec![
+            "memory.similarity_threshold",
+            "orchestrator.max_concurrent_tasks",
+            "orchestrator.task_timeout_seconds",
+            "security.rate_limit_per_minute",
+        ]);
+        assert_eq!(report.restart_required, vec!["db_path"]);
+        assert_eq!(memory.similarity_threshold(), 0.5);
+        assert!(rate_limiter.check());
+        assert!(!rate_limiter.check());
+
+        // Applied values are not reported again; restart-only ones are
+        let report = reloader.apply(&new).await.unwrap();
+        assert!(report.applied.is_empty());
+        assert_eq!(report.restart_required, vec!["db_path"]);
+
+        // A rejected value leaves the current one in place
+        new.memory.similarity_threshold = 3.0;
+        let report = reloader.apply(&new).await.unwrap();
+        assert_eq!(report.failed, vec!["memory.similarity_threshold"]);
+        assert_eq!(memory.similarity_threshold(), 0.5);
+    }
+}
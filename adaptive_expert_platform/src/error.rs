@@ -0,0 +1,168 @@
+//! Structured errors for tasks run through the orchestrator.
+//!
+//! Results cross the dispatch channel as [`OrchestratorError`] rather than a
+//! bare `anyhow::Error`, so the receiving side can match on what went wrong
+//! while the original error, with its full context chain, travels along.
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::agent::{AgentMaintenanceError, AgentTimeoutError, CancelledError, InvalidInputError, UnknownAgentError};
+use crate::input_guard::InputLimitError;
+use crate::process::SubprocessLimitError;
+use crate::quota::QuotaExceededError;
+use crate::scheduler::QueueFullError;
+
+/// Why a dispatched task failed. Every variant keeps the underlying error;
+/// format it with `{:#}` to include its causes.
+#[derive(Debug)]
+pub enum OrchestratorError {
+    /// The input was rejected by the input guard or by the agent itself
+    InvalidInput(anyhow::Error),
+    /// No agent is registered under the requested name
+    UnknownAgent(anyhow::Error),
+    /// The agent is registered but disabled for maintenance
+    Maintenance(anyhow::Error),
+    /// The task queue, or the subprocess limit, stayed at capacity
+    QueueFull(anyhow::Error),
+    /// The call would take the caller over their request or cost quota
+    QuotaExceeded(anyhow::Error),
+    /// The caller cancelled the task
+    Cancelled(anyhow::Error),
+    /// The agent did not finish within the task timeout
+    Timeout(anyhow::Error),
+    /// The agent, or a middleware around it, failed
+    Agent(anyhow::Error),
+}
+
+impl OrchestratorError {
+    /// The underlying error, including its context chain
+    pub fn source_error(&self) -> &anyhow::Error {
+        match self {
+            OrchestratorError::InvalidInput(e)
+            | OrchestratorError::UnknownAgent(e)
+            | OrchestratorError::Maintenance(e)
+            | OrchestratorError::QueueFull(e)
+            | OrchestratorError::QuotaExceeded(e)
+            | OrchestratorError::Cancelled(e)
+            | OrchestratorError::Timeout(e)
+            | OrchestratorError::Agent(e) => e,
+        }
+    }
+
+    /// Unwrap to the underlying error, e.g. to add context and propagate with `?`
+    pub fn into_source_error(self) -> anyhow::Error {
+        match self {
+            OrchestratorError::InvalidInput(e)
+            | OrchestratorError::UnknownAgent(e)
+            | OrchestratorError::Maintenance(e)
+            | OrchestratorError::QueueFull(e)
+            | OrchestratorError::QuotaExceeded(e)
+            | OrchestratorError::Cancelled(e)
+            | OrchestratorError::Timeout(e)
+            | OrchestratorError::Agent(e) => e,
+        }
+    }
+
+    /// Short machine-readable name of the variant
+    pub fn kind(&self) -> &'static str {
+        match self {
+            OrchestratorError::InvalidInput(_) => "invalid_input",
+            OrchestratorError::UnknownAgent(_) => "unknown_agent",
+            OrchestratorError::Maintenance(_) => "maintenance",
+            OrchestratorError::QueueFull(_) => "queue_full",
+            OrchestratorError::QuotaExceeded(_) => "quota_exceeded",
+            OrchestratorError::Cancelled(_) => "cancelled",
+            OrchestratorError::Timeout(_) => "timeout",
+            OrchestratorError::Agent(_) => "agent",
+        }
+    }
+}
+
+/// A failure shared by identical calls coalesced onto one execution.
+///
+/// Displays and chains exactly like the original error; use [`chain_any`]
+/// rather than `chain()` to look for marker types through it.
+#[derive(Debug, Clone)]
+pub struct SharedError(pub Arc<anyhow::Error>);
+
+impl fmt::Display for SharedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for SharedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+/// Whether any error in `e`'s chain passes `check`, looking through
+/// [`SharedError`] to the original error
+pub fn chain_any(e: &anyhow::Error, check: impl Fn(&(dyn std::error::Error + 'static)) -> bool) -> bool {
+    match e.downcast_ref::<SharedError>() {
+        Some(shared) => shared.0.chain().any(check),
+        None => e.chain().any(check),
+    }
+}
+
+/// Classify by the marker error types anywhere in the chain
+impl From<anyhow::Error> for OrchestratorError {
+    fn from(e: anyhow::Error) -> Self {
+        let has = |check: fn(&(dyn std::error::Error + 'static)) -> bool| chain_any(&e, check);
+        if has(|c| c.is::<CancelledError>()) {
+            OrchestratorError::Cancelled(e)
+        } else if has(|c| c.is::<InvalidInputError>() || c.is::<InputLimitError>()) {
+            OrchestratorError::InvalidInput(e)
+        } else if has(|c| c.is::<UnknownAgentError>()) {
+            OrchestratorError::UnknownAgent(e)
+        } else if has(|c| c.is::<AgentMaintenanceError>()) {
+            OrchestratorError::Maintenance(e)
+        } else if has(|c| c.is::<QueueFullError>() || c.is::<SubprocessLimitError>()) {
+            OrchestratorError::QueueFull(e)
+        } else if has(|c| c.is::<QuotaExceededError>()) {
+            OrchestratorError::QuotaExceeded(e)
+        } else if has(|c| c.is::<AgentTimeoutError>()) {
+            OrchestratorError::Timeout(e)
+        } else {
+            OrchestratorError::Agent(e)
+        }
+    }
+}
+
+/// Displays the underlying error; the alternate form (`{:#}`) includes its causes
+impl fmt::Display for OrchestratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "{:#}", self.source_error())
+        } else {
+            write!(f, "{}", self.source_error())
+        }
+    }
+}
+
+impl std::error::Error for OrchestratorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source_error().source()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_classification_keeps_context() {
+        let nested: anyhow::Result<()> = Err(anyhow::Error::new(InvalidInputError("bad field".into())));
+        let err = OrchestratorError::from(nested.context("parsing request").unwrap_err());
+        assert!(matches!(err, OrchestratorError::InvalidInput(_)));
+        assert_eq!(err.to_string(), "parsing request");
+        assert_eq!(format!("{:#}", err), "parsing request: bad field");
+
+        let err = OrchestratorError::from(anyhow::anyhow!("disk full"));
+        assert_eq!(err.kind(), "agent");
+        assert!(err.into_source_error().to_string().contains("disk full"));
+    }
+}
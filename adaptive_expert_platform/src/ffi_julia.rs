@@ -2,16 +2,21 @@
 
 #[cfg(feature = "with-julia")]
 mod julia_impl {
-    use crate::{agent::Agent, memory::Memory, settings::Settings};
+    use crate::{
+        agent::Agent, memory::Memory, mesh::TaskPriority, metrics::platform,
+        scheduler::PriorityScheduler, settings::Settings,
+    };
     use anyhow::{anyhow, Result};
     use async_trait::async_trait;
     use jlrs::prelude::*;
     use serde_json::Value;
     use sha2::{Digest, Sha256};
     use std::collections::HashMap;
-    use std::sync::Arc;
-    use tokio::sync::{mpsc::Sender, oneshot::channel};
-    use tracing::{info, error, warn};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, OnceLock};
+    use std::time::Duration;
+    use tokio::sync::{mpsc::{Receiver, Sender}, oneshot};
+    use tracing::{debug, info, error, warn};
 
     pub struct JuliaTask {
         pub function_name: String,
@@ -19,6 +24,38 @@ mod julia_impl {
         pub response: oneshot::Sender<Result<String>>,
     }
 
+    /// Work handed to the runtime thread
+    pub enum JuliaRequest {
+        Task(JuliaTask),
+        /// Run a full garbage collection and report the outcome
+        Gc(oneshot::Sender<Result<()>>),
+    }
+
+    /// What the runtime thread should do next
+    enum Received {
+        Request(JuliaRequest),
+        /// Nothing arrived within the idle period
+        Idle,
+        /// Every sender is gone
+        Closed,
+    }
+
+    /// Wait for the next request, giving up after the idle period when one
+    /// is passed
+    fn next_request(
+        rx: &mut Receiver<JuliaRequest>,
+        idle: Option<&(tokio::runtime::Runtime, Duration)>,
+    ) -> Received {
+        let request = match idle {
+            Some((waiter, period)) => match waiter.block_on(tokio::time::timeout(*period, rx.recv())) {
+                Ok(request) => request,
+                Err(_) => return Received::Idle,
+            },
+            None => rx.blocking_recv(),
+        };
+        request.map_or(Received::Closed, Received::Request)
+    }
+
     /// Validate the integrity of the model file by checking its hash
     fn validate_model_integrity(path: &str, allowlist: &HashMap<String, String>) -> Result<()> {
         if allowlist.is_empty() {
@@ -46,13 +83,84 @@ mod julia_impl {
         Ok(())
     }
 
+    /// Julia can only be initialized once per process, so every agent shares
+    /// this runtime. Settings read at init (e.g. `julia.threads`) therefore
+    /// only change on restart.
+    ///
+    /// For the same reason the runtime is never torn down when idle; instead
+    /// its heap is reclaimed by a full GC after `julia.idle_gc_seconds`
+    /// without work, after every task with `julia.gc_after_task`, or on
+    /// demand through [`collect_julia_garbage`].
+    static JULIA_RUNTIME: OnceLock<std::result::Result<Sender<JuliaRequest>, String>> = OnceLock::new();
+
+    /// Get the shared runtime, starting it on first use.
+    ///
+    /// A failed start is remembered too: Julia cannot be re-initialized in the
+    /// same process, so retrying would only fail in more confusing ways.
+    fn get_julia(settings: &Settings) -> Result<Sender<JuliaRequest>> {
+        JULIA_RUNTIME
+            .get_or_init(|| init_julia(settings.clone()).map_err(|e| e.to_string()))
+            .clone()
+            .map_err(|reason| anyhow!(unavailable_message(&reason)))
+    }
+
+    fn unavailable_message(reason: &str) -> String {
+        format!(
+            "Julia runtime unavailable: {}; ensure Julia is installed and JULIA_DIR is set",
+            reason
+        )
+    }
+
+    /// Run a full Julia garbage collection now. Fails if the runtime was
+    /// never started or could not start.
+    pub async fn collect_julia_garbage() -> Result<()> {
+        let sender = match JULIA_RUNTIME.get() {
+            Some(Ok(sender)) => sender.clone(),
+            Some(Err(reason)) => return Err(anyhow!(unavailable_message(reason))),
+            None => return Err(anyhow!("Julia runtime has not been started")),
+        };
+
+        let (done_tx, done_rx) = oneshot::channel();
+        sender.send(JuliaRequest::Gc(done_tx)).await
+            .map_err(|_| anyhow!("Julia runtime channel closed"))?;
+        done_rx.await
+            .map_err(|_| anyhow!("Julia runtime dropped the GC request"))?
+    }
+
+    /// Start the runtime eagerly and log whether it came up, so a missing
+    /// Julia install shows at startup rather than on the first request
+    pub fn probe_julia_runtime(settings: &Settings) -> Result<()> {
+        match get_julia(settings) {
+            Ok(_) => {
+                info!("Julia runtime probe succeeded");
+                Ok(())
+            }
+            Err(e) => {
+                warn!("Julia runtime probe failed: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Clamp the configured thread count to `1..=available cores`
+    fn effective_threads(requested: usize) -> usize {
+        let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        if requested > cores {
+            warn!("Julia threads ({}) exceeds available cores ({}), using {}", requested, cores, cores);
+        }
+        requested.clamp(1, cores)
+    }
+
     /// Initialize Julia runtime in a dedicated thread with bounded queue for concurrency control
-    fn init_julia(settings: Settings) -> Result<Sender<JuliaTask>> {
-        let (tx, mut rx) = tokio::sync::mpsc::channel::<JuliaTask>(100);
+    fn init_julia(settings: Settings) -> Result<Sender<JuliaRequest>> {
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<JuliaRequest>(100);
         let (init_tx, init_rx) = std::sync::mpsc::channel::<Result<()>>();
+        let threads = effective_threads(settings.julia.threads);
 
-        std::thread::spawn(move || {
-            let julia_res = RuntimeBuilder::new().start();
+        std::thread::Builder::new().name("julia-runtime".to_string()).spawn(move || {
+            // Passed to the runtime rather than through JULIA_NUM_THREADS:
+            // mutating the environment here would race other threads
+            let julia_res = RuntimeBuilder::new().n_threads(threads).start();
             let mut julia = match julia_res {
                 Ok(julia) => {
                     if init_tx.send(Ok(())).is_err() {
@@ -69,7 +177,7 @@ mod julia_impl {
             };
             let mut frame = StackFrame::new();
 
-            info!("Julia runtime initialized in dedicated thread");
+            info!("Julia runtime initialized in dedicated thread with {} threads", threads);
 
             // Load common Julia modules
             if let Err(e) = julia.scope(|mut frame| {
@@ -91,25 +199,67 @@ mod julia_impl {
                 error!("Failed to load Julia models: {}", e);
             }
 
+            // Waiting with a deadline needs a timer; without one the idle
+            // collection is skipped and the thread just blocks
+            let idle_waiter = match settings.julia.idle_gc_seconds {
+                0 => None,
+                seconds => match tokio::runtime::Builder::new_current_thread().enable_time().build() {
+                    Ok(waiter) => Some((waiter, Duration::from_secs(seconds))),
+                    Err(e) => {
+                        warn!("Julia idle garbage collection disabled: {}", e);
+                        None
+                    }
+                },
+            };
+            // Nothing worth collecting until a task has run
+            let mut collected = true;
+
             // Main loop for processing Julia tasks
-            while let Some(task) = rx.blocking_recv() {
-                let result = julia.scope(|mut frame| {
-                    let func = Module::main(&mut frame).function(&mut frame, &task.function_name)?;
-                    let config_val = Value::new(&mut frame, task.json_config);
-                    let result = func.call1(&mut frame, config_val)?;
-                    Ok(result.display_string(&mut frame)?)
-                });
-
-                let response = match result {
-                    Ok(output) => Ok(output),
-                    Err(e) => Err(anyhow!("Julia execution error: {}", e)),
+            loop {
+                let idle = idle_waiter.as_ref().filter(|_| !collected);
+                let (reply, reason) = match next_request(&mut rx, idle) {
+                    Received::Closed => break,
+                    Received::Idle => (None, "idle"),
+                    Received::Request(JuliaRequest::Gc(done)) => (Some(done), "requested"),
+                    Received::Request(JuliaRequest::Task(task)) => {
+                        let result = julia.scope(|mut frame| {
+                            let func = Module::main(&mut frame).function(&mut frame, &task.function_name)?;
+                            let config_val = Value::new(&mut frame, task.json_config);
+                            let result = func.call1(&mut frame, config_val)?;
+                            Ok(result.display_string(&mut frame)?)
+                        });
+
+                        let response = match result {
+                            Ok(output) => Ok(output),
+                            Err(e) => Err(anyhow!("Julia execution error: {}", e)),
+                        };
+
+                        if let Err(_) = task.response.send(response) {
+                            error!("Failed to send Julia task response");
+                        }
+                        collected = false;
+                        if !settings.julia.gc_after_task {
+                            continue;
+                        }
+                        (None, "after task")
+                    }
                 };
 
-                if let Err(_) = task.response.send(response) {
-                    error!("Failed to send Julia task response");
+                let result = julia.scope(|mut frame| {
+                    let gc = Module::base(&mut frame).submodule(&mut frame, "GC")?;
+                    gc.function(&mut frame, "gc")?.call0(&mut frame)?;
+                    Ok(())
+                }).map_err(|e| anyhow!("Julia garbage collection failed: {}", e));
+                match &result {
+                    Ok(()) => debug!("Julia garbage collection ({}) completed", reason),
+                    Err(e) => warn!("{}", e),
                 }
+                if let Some(done) = reply {
+                    let _ = done.send(result);
+                }
+                collected = true;
             }
-        });
+        }).map_err(|e| anyhow!("Failed to spawn Julia runtime thread: {}", e))?;
 
         let init_timeout = std::time::Duration::from_secs(30);
         match init_rx.recv_timeout(init_timeout) {
@@ -119,6 +269,40 @@ mod julia_impl {
         }
     }
 
+    /// Admission to the runtime, shared like the runtime itself: at most
+    /// `julia.max_concurrent` calls are handed over at once and up to
+    /// `julia.max_queued` more wait, served strictly in arrival order. Sized
+    /// from the settings seen first, so changes need a restart.
+    static JULIA_GATE: OnceLock<PriorityScheduler> = OnceLock::new();
+
+    /// Calls currently waiting at the gate
+    static JULIA_WAITING: AtomicUsize = AtomicUsize::new(0);
+
+    fn julia_gate(settings: &Settings) -> &'static PriorityScheduler {
+        JULIA_GATE.get_or_init(|| {
+            PriorityScheduler::new(settings.julia.max_concurrent.max(1), settings.julia.max_queued)
+        })
+    }
+
+    /// Counts a caller waiting at the gate, publishing the queue depth as it
+    /// changes. Dropped when the caller is admitted, rejected or gives up.
+    struct QueuedCall;
+
+    impl QueuedCall {
+        fn enter() -> Self {
+            let depth = JULIA_WAITING.fetch_add(1, Ordering::AcqRel) + 1;
+            platform().set_julia_queue_depth(depth);
+            Self
+        }
+    }
+
+    impl Drop for QueuedCall {
+        fn drop(&mut self) {
+            let depth = JULIA_WAITING.fetch_sub(1, Ordering::AcqRel) - 1;
+            platform().set_julia_queue_depth(depth);
+        }
+    }
+
     /// Allowed Julia function names for security
     const ALLOWED_JULIA_FUNCTIONS: &[&str] = &[
         "main",
@@ -131,15 +315,22 @@ mod julia_impl {
         "process_data",
     ];
 
-    /// Julia agent that processes tasks via the runtime
+    /// Julia agent that processes tasks via the runtime.
+    ///
+    /// Constructed even when the runtime failed to start, so it stays visible
+    /// as unhealthy with the reason instead of silently missing.
     pub struct JuliaAgent {
-        sender: Sender<JuliaTask>,
+        runtime: std::result::Result<Sender<JuliaRequest>, String>,
+        gate: &'static PriorityScheduler,
     }
 
     impl JuliaAgent {
-        pub fn new(settings: &Settings) -> Result<Self> {
-            let sender = init_julia(settings.clone())?;
-            Ok(Self { sender })
+        pub fn new(settings: &Settings) -> Self {
+            let runtime = get_julia(settings).map_err(|e| e.to_string());
+            if let Err(e) = &runtime {
+                error!("{}", e);
+            }
+            Self { runtime, gate: julia_gate(settings) }
         }
     }
 
@@ -158,16 +349,23 @@ mod julia_impl {
         }
 
         async fn health_check(&self) -> Result<crate::agent::AgentHealth> {
-            if self.sender.is_closed() {
+            let problem = match &self.runtime {
+                Err(reason) => Some(reason.clone()),
+                Ok(sender) if sender.is_closed() => Some("Julia runtime thread has terminated.".to_string()),
+                Ok(_) => None,
+            };
+            if let Some(details) = problem {
                 let mut health = crate::agent::AgentHealth::default();
                 health.status = "unhealthy".to_string();
-                health.details = Some("Julia runtime thread has terminated.".to_string());
+                health.details = Some(details);
                 return Ok(health);
             }
             Ok(crate::agent::AgentHealth::default())
         }
 
         async fn handle(&self, input: Value, _memory: Arc<Memory>) -> Result<String> {
+            let sender = self.runtime.as_ref().map_err(|reason| anyhow!(reason.clone()))?;
+
             let function_name = input.get("function")
                 .and_then(|v| v.as_str())
                 .unwrap_or("main")
@@ -186,6 +384,14 @@ mod julia_impl {
                 .cloned()
                 .unwrap_or(input);
 
+            // Every caller gets the same priority, so the gate is plain FIFO
+            let _slot = {
+                let _queued = QueuedCall::enter();
+                self.gate.acquire(TaskPriority::Normal).await.map_err(|e| {
+                    anyhow::Error::new(e).context("Julia busy: too many calls are already waiting for the runtime")
+                })?
+            };
+
             let (response_tx, response_rx) = oneshot::channel();
             let task = JuliaTask {
                 function_name,
@@ -196,7 +402,7 @@ mod julia_impl {
             // Send with timeout to handle backpressure gracefully
             match tokio::time::timeout(
                 std::time::Duration::from_secs(5),
-                self.sender.send(task)
+                sender.send(JuliaRequest::Task(task))
             ).await {
                 Ok(Ok(())) => {},
                 Ok(Err(_)) => return Err(anyhow!("Julia runtime channel closed")),
@@ -207,10 +413,58 @@ mod julia_impl {
                 .map_err(|_| anyhow!("Julia task response channel closed"))?
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_effective_threads_is_clamped() {
+            let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+            assert_eq!(effective_threads(0), 1);
+            assert_eq!(effective_threads(1), 1);
+            assert_eq!(effective_threads(cores + 8), cores);
+        }
+
+        #[test]
+        fn test_next_request_reports_idle_and_closed() {
+            let waiter = (
+                tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap(),
+                Duration::from_millis(10),
+            );
+            let (tx, mut rx) = tokio::sync::mpsc::channel::<JuliaRequest>(1);
+
+            assert!(matches!(next_request(&mut rx, Some(&waiter)), Received::Idle));
+
+            let (done, _) = oneshot::channel();
+            tx.try_send(JuliaRequest::Gc(done)).unwrap();
+            assert!(matches!(next_request(&mut rx, Some(&waiter)), Received::Request(JuliaRequest::Gc(_))));
+
+            drop(tx);
+            assert!(matches!(next_request(&mut rx, None), Received::Closed));
+        }
+
+        #[test]
+        fn test_queued_calls_publish_depth() {
+            let before = platform().snapshot().julia_queue_depth;
+            let first = QueuedCall::enter();
+            let second = QueuedCall::enter();
+            assert!(platform().snapshot().julia_queue_depth >= before + 2);
+            drop((first, second));
+            assert_eq!(JULIA_WAITING.load(Ordering::Acquire) as u64, platform().snapshot().julia_queue_depth);
+        }
+
+        #[test]
+        fn test_unavailable_message_is_actionable() {
+            let message = unavailable_message("libjulia not found");
+            assert!(message.starts_with("Julia runtime unavailable: libjulia not found"));
+            assert!(message.contains("JULIA_DIR"));
+        }
+    }
 }
 
 #[cfg(feature = "with-julia")]
-pub use julia_impl::JuliaAgent;
+pub use julia_impl::{collect_julia_garbage, probe_julia_runtime, JuliaAgent};
 
 #[cfg(not(feature = "with-julia"))]
 pub struct JuliaAgent;
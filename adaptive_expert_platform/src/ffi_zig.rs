@@ -1,105 +1,228 @@
-//! FFI bindings and helpers for calling Zig functions from Rust.
+//! Zig agent calling functions exported from Zig shared libraries.
 //!
-//! When the `zig` feature is enabled this module exposes a sample
-//! [`Agent`](crate::agent::Agent) that demonstrates how to call into a
-//! function defined in a Zig dynamic library.  Zig has excellent C
-//! interop, so the recommended way to build Zig code for Rust is to
-//! compile it as a C-compatible dynamic library and declare the
-//! exported functions using the `extern` keyword in Rust.  See the
-//! following example from the Zig manual for details:
+//! Zig has excellent C interop, so libraries are built as C-ABI dynamic
+//! libraries and loaded at call time with `libloading`. A library must live in
+//! one of `security.zig_library_directories` and have its SHA-256 listed in
+//! `security.script_allowlist_hashes`. Unlike Python scripts, which run in a
+//! child process, native code runs inside this process, so a library is never
+//! loaded without an allowlist entry.
+//!
+//! Callable functions take a NUL-terminated JSON string and return a newly
+//! allocated NUL-terminated JSON string (or null on failure), which is handed
+//! back to the library's exported `acropolis_free`. Only symbols prefixed with
+//! `acropolis_` may be called, so arbitrary exports with other signatures are
+//! never invoked:
 //!
 //! ```zig
 //! const std = @import("std");
+//! const allocator = std.heap.c_allocator;
 //!
-//! // Export an addition function so it can be called from C/Rust
-//! export fn zig_add(a: i32, b: i32) i32 {
-//!     return a + b;
+//! export fn acropolis_echo(input: [*:0]const u8) ?[*:0]u8 {
+//!     return allocator.dupeZ(u8, std.mem.span(input)) catch null;
 //! }
-//! ```
-//!
-//! The companion Rust declaration looks like this:
 //!
-//! ```rust
-//! extern "C" {
-//!     fn zig_add(a: i32, b: i32) -> i32;
+//! export fn acropolis_free(ptr: [*:0]u8) void {
+//!     allocator.free(std.mem.span(ptr));
 //! }
 //! ```
 //!
-//! You must build the Zig library as a shared object (e.g. using
-//! `zig build-lib mylib.zig -dynamic`) and ensure that it is on your
-//! library search path at runtime.  The Rust compiler will link
-//! against it if the appropriate linker flags are provided.  See the
-//! [DEV Community article on using Zig in Rust] for an example of
-//! building and linking a Zig library from Rust【486881172987055†L52-L86】.
+//! Build with `zig build-lib mylib.zig -dynamic -lc`.
 //!
-//! This module defines a [`ZigAgent`] which parses its input as two
-//! integers separated by a comma and returns their sum using the Zig
-//! function.  If parsing fails an error is returned.
+//! The library is read once, hashed, and the verified bytes are copied into
+//! a private directory that is what actually gets loaded, so the file can't
+//! be swapped between the integrity check and `dlopen`. Calls that time out
+//! keep their blocking thread until the native code returns; at most
+//! `security.zig_max_running_calls` may be running at once.
 
-use crate::agent::Agent;
+use crate::agent::{Agent, AgentHealth, InvalidInputError};
 use crate::memory::Memory;
+use crate::settings::Settings;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use libloading::{Library, Symbol};
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::ffi::{c_char, CStr, CString};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use tempfile::TempDir;
+use tokio::sync::Semaphore;
 use tracing::{instrument, warn};
 
-/// Maximum safe integer value for FFI operations to prevent overflow
-const MAX_SAFE_INTEGER: i32 = 1_000_000;
-/// Minimum safe integer value for FFI operations
-const MIN_SAFE_INTEGER: i32 = -1_000_000;
+/// Only exported symbols with this prefix may be called
+const FUNCTION_PREFIX: &str = "acropolis_";
+
+/// Symbol the library must export to release strings it returned
+const FREE_SYMBOL: &[u8] = b"acropolis_free\0";
+
+type ZigCallFn = unsafe extern "C" fn(*const c_char) -> *mut c_char;
+type ZigFreeFn = unsafe extern "C" fn(*mut c_char);
 
-/// Input validation structure for Zig operations
 #[derive(Deserialize)]
-struct ZigAddInput {
-    a: i32,
-    b: i32,
+struct ZigCallInput {
+    library: String,
+    function: String,
+    #[serde(default)]
+    input: serde_json::Value,
 }
 
-/// Foreign function imported from the Zig library.  This signature
-/// must match the exported function in Zig exactly.  The function
-/// performs integer addition and returns the result.
-#[cfg(feature = "zig")]
-extern "C" {
-    fn zig_add(a: i32, b: i32) -> i32;
+/// Agent that calls JSON-in/JSON-out functions in allowlisted Zig libraries
+pub struct ZigAgent {
+    request_count: AtomicU64,
+    error_count: AtomicU64,
+    start_time: std::time::Instant,
+    allowed_directories: Vec<PathBuf>,
+    library_allowlist_hashes: HashMap<String, String>,
+    max_execution_time: std::time::Duration,
+    /// Held by each call until its blocking thread finishes, even after a timeout
+    running_calls: Arc<Semaphore>,
 }
 
-/// Validates integer inputs for safe FFI operations
-fn validate_ffi_input(a: i32, b: i32) -> Result<()> {
-    if a < MIN_SAFE_INTEGER || a > MAX_SAFE_INTEGER {
-        return Err(anyhow!("Integer 'a' ({}) outside safe range [{}, {}]", a, MIN_SAFE_INTEGER, MAX_SAFE_INTEGER));
+/// A private copy of a library whose contents matched its allowlisted hash
+struct VerifiedLibrary {
+    path: PathBuf,
+    _dir: TempDir,
+}
+
+impl ZigAgent {
+    pub fn new(settings: &Settings) -> Self {
+        let allowed_directories = settings.security.zig_library_directories
+            .iter()
+            .filter_map(|dir| match dir.canonicalize() {
+                Ok(canonical) => Some(canonical),
+                Err(e) => {
+                    warn!("Ignoring Zig library directory {:?}: {}", dir, e);
+                    None
+                }
+            })
+            .collect();
+
+        Self {
+            request_count: AtomicU64::new(0),
+            error_count: AtomicU64::new(0),
+            start_time: std::time::Instant::now(),
+            allowed_directories,
+            library_allowlist_hashes: settings.security.script_allowlist_hashes.clone(),
+            max_execution_time: std::time::Duration::from_secs(settings.security.max_execution_time_seconds),
+            running_calls: Arc::new(Semaphore::new(settings.security.zig_max_running_calls.max(1))),
+        }
     }
-    if b < MIN_SAFE_INTEGER || b > MAX_SAFE_INTEGER {
-        return Err(anyhow!("Integer 'b' ({}) outside safe range [{}, {}]", b, MIN_SAFE_INTEGER, MAX_SAFE_INTEGER));
+
+    /// Only `acropolis_*` identifiers, never the free function itself
+    fn validate_function_name(name: &str) -> Result<()> {
+        let valid = name.starts_with(FUNCTION_PREFIX)
+            && name.len() > FUNCTION_PREFIX.len()
+            && name.as_bytes() != &FREE_SYMBOL[..FREE_SYMBOL.len() - 1]
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(anyhow!(
+                "Zig function '{}' is not callable; exported names must start with '{}'",
+                name, FUNCTION_PREFIX
+            ));
+        }
+        Ok(())
     }
 
-    // Check for potential overflow in addition
-    if (a > 0 && b > i32::MAX - a) || (a < 0 && b < i32::MIN - a) {
-        return Err(anyhow!("Addition of {} and {} would cause integer overflow", a, b));
+    /// Resolve the library, check both the directory and hash allowlists, and
+    /// copy the exact bytes that were hashed somewhere only this process can write
+    fn validate_library(&self, path: &str) -> Result<VerifiedLibrary> {
+        let canonical = Path::new(path)
+            .canonicalize()
+            .map_err(|_| anyhow!("Zig library '{}' does not exist", path))?;
+
+        if !self.allowed_directories.iter().any(|allowed| canonical.starts_with(allowed)) {
+            return Err(anyhow!("Zig library '{}' is not in allowed directories", path));
+        }
+        if !canonical.is_file() {
+            return Err(anyhow!("Path '{}' is not a file", path));
+        }
+
+        let expected_hash = self.library_allowlist_hashes.get(path)
+            .ok_or_else(|| anyhow!("Zig library '{}' is not in the allowlist", path))?;
+
+        let contents = std::fs::read(&canonical)?;
+        let mut hasher = Sha256::new();
+        hasher.update(&contents);
+        let actual_hash = format!("{:x}", hasher.finalize());
+
+        if actual_hash != *expected_hash {
+            return Err(anyhow!(
+                "Zig library integrity check failed for '{}'. Expected hash: {}, Actual hash: {}",
+                path, expected_hash, actual_hash
+            ));
+        }
+
+        // tempfile creates the directory with mode 0700
+        let dir = tempfile::Builder::new().prefix("acropolis-zig-").tempdir()?;
+        let file_name = canonical.file_name().ok_or_else(|| anyhow!("Path '{}' is not a file", path))?;
+        let private = dir.path().join(file_name);
+        std::fs::write(&private, &contents)?;
+        Ok(VerifiedLibrary { path: private, _dir: dir })
     }
 
-    Ok(())
-}
+    async fn call(&self, input: serde_json::Value) -> Result<String> {
+        let call: ZigCallInput = serde_json::from_value(input).map_err(|e| InvalidInputError(format!(
+            "Invalid input for ZigAgent: {}. Expected {{\"library\": path, \"function\": name, \"input\": json}}", e
+        )))?;
+        Self::validate_function_name(&call.function)?;
+        let permit = self.running_calls.clone().try_acquire_owned().map_err(|_| anyhow!(
+            "Too many Zig calls still running; retry once earlier calls have returned"
+        ))?;
+        let library = self.validate_library(&call.library)?;
+        let input_json = call.input.to_string();
 
-/// An agent that delegates addition to a Zig function via FFI.
-///
-/// The `ZigAgent` expects an input JSON in the form {"a": 123, "b": 456} where
-/// `a` and `b` are 32-bit integers within safe bounds.  It validates input,
-/// performs bounds checking, and then calls the `zig_add` function.  The result
-/// is returned to the caller.  All parsing and FFI calls include comprehensive
-/// error handling and safety checks.
-#[cfg(feature = "zig")]
-pub struct ZigAgent;
-
-#[cfg(feature = "zig")]
-impl ZigAgent {
-    /// Create a new instance of the agent.  No state is stored.
-    pub fn new() -> Self {
-        Self
+        // FFI calls cannot be interrupted; on timeout the blocking thread is
+        // abandoned but keeps its permit (and the library copy) until it returns
+        let function = call.function;
+        let result = tokio::time::timeout(
+            self.max_execution_time,
+            tokio::task::spawn_blocking(move || {
+                let _permit = permit;
+                call_library(&library.path, &function, input_json)
+            }),
+        ).await;
+
+        match result {
+            Ok(Ok(output)) => output,
+            Ok(Err(e)) => Err(anyhow!("Zig call panicked: {}", e)),
+            Err(_) => Err(anyhow!("Zig call timed out after {:?}", self.max_execution_time)),
+        }
     }
 }
 
-#[cfg(feature = "zig")]
+/// Load `library` and call `function` with a JSON string, returning its JSON output
+fn call_library(library: &Path, function: &str, input_json: String) -> Result<String> {
+    let input = CString::new(input_json).map_err(|_| anyhow!("Zig input contains a NUL byte"))?;
+    let symbol = CString::new(function)?;
+
+    // SAFETY: the library passed the directory and integrity allowlists, so its
+    // initializers are trusted code. Callable symbols are restricted to the
+    // documented `acropolis_*` signature, and the returned string is released
+    // by the library's own allocator exactly once.
+    let output = unsafe {
+        let library = Library::new(library)
+            .map_err(|e| anyhow!("Failed to load Zig library {:?}: {}", library, e))?;
+        let call: Symbol<ZigCallFn> = library.get(symbol.as_bytes_with_nul())
+            .map_err(|e| anyhow!("Zig function '{}' not found: {}", function, e))?;
+        let free: Symbol<ZigFreeFn> = library.get(FREE_SYMBOL)
+            .map_err(|e| anyhow!("Zig library does not export acropolis_free: {}", e))?;
+
+        let raw = call(input.as_ptr());
+        if raw.is_null() {
+            return Err(anyhow!("Zig function '{}' returned null", function));
+        }
+        let output = CStr::from_ptr(raw).to_str().map(str::to_owned);
+        free(raw);
+        output.map_err(|_| anyhow!("Zig function '{}' returned non-UTF-8 output", function))?
+    };
+
+    serde_json::from_str::<serde_json::Value>(&output)
+        .map_err(|e| anyhow!("Zig function '{}' returned invalid JSON: {}", function, e))?;
+    Ok(output)
+}
+
 #[async_trait]
 impl Agent for ZigAgent {
     fn name(&self) -> &str {
@@ -111,67 +234,104 @@ impl Agent for ZigAgent {
     }
 
     fn capabilities(&self) -> Vec<String> {
-        vec!["addition".to_string()]
+        vec!["zig_execute".to_string()]
     }
 
-    async fn health_check(&self) -> Result<crate::agent::AgentHealth> {
-        // Zig FFI is synchronous and stateless, so it's always healthy if the library is loaded.
-        Ok(crate::agent::AgentHealth::default())
+    async fn health_check(&self) -> Result<AgentHealth> {
+        let total_requests = self.request_count.load(Ordering::Relaxed);
+        let error_count = self.error_count.load(Ordering::Relaxed);
+        let (status, details) = if self.allowed_directories.is_empty() {
+            ("degraded", Some("No Zig library directories are configured".to_string()))
+        } else {
+            ("healthy", None)
+        };
+
+        Ok(AgentHealth {
+            status: status.to_string(),
+            details,
+            uptime_seconds: self.start_time.elapsed().as_secs(),
+            total_requests,
+            error_count,
+            ..AgentHealth::default()
+        })
     }
 
-    #[instrument(skip(self, memory), fields(agent = "zig"))]
+    #[instrument(skip(self, input, _memory), fields(agent = "zig"))]
     async fn handle(&self, input: serde_json::Value, _memory: Arc<Memory>) -> Result<String> {
-        // Parse and validate input JSON structure
-        let parsed_input: ZigAddInput = serde_json::from_value(input)
-            .map_err(|e| anyhow!("Invalid JSON input for ZigAgent: {}. Expected {{\"a\": number, \"b\": number}}", e))?;
-
-        let a = parsed_input.a;
-        let b = parsed_input.b;
-
-        // Comprehensive input validation
-        validate_ffi_input(a, b)?;
-
-        // SAFETY: We have validated that:
-        // 1. Both inputs are within safe integer bounds
-        // 2. The addition will not overflow
-        // 3. The Zig function signature matches our declaration
-        // 4. We trust the Zig library to adhere to the C ABI
-        let result = unsafe {
-            tracing::debug!("Calling zig_add({}, {})", a, b);
-            zig_add(a, b)
-        };
-
-        // Validate result is within expected bounds
-        let expected = a.saturating_add(b);
-        if result != expected {
-            warn!("Zig FFI result mismatch: expected {}, got {}", expected, result);
-            return Err(anyhow!("FFI result validation failed: expected {}, got {}", expected, result));
+        self.request_count.fetch_add(1, Ordering::Relaxed);
+        let result = self.call(input).await;
+        if result.is_err() {
+            self.error_count.fetch_add(1, Ordering::Relaxed);
         }
-
-        Ok(result.to_string())
+        result
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::tempdir;
 
-    #[test]
-    fn test_validate_ffi_input_valid() {
-        assert!(validate_ffi_input(100, 200).is_ok());
-        assert!(validate_ffi_input(-100, 200).is_ok());
-        assert!(validate_ffi_input(0, 0).is_ok());
+    fn agent(dir: &Path, allowlist: HashMap<String, String>) -> ZigAgent {
+        let mut settings = Settings::default();
+        settings.security.zig_library_directories = vec![dir.to_path_buf()];
+        settings.security.script_allowlist_hashes = allowlist;
+        ZigAgent::new(&settings)
     }
 
     #[test]
-    fn test_validate_ffi_input_overflow() {
-        assert!(validate_ffi_input(i32::MAX, 1).is_err());
-        assert!(validate_ffi_input(i32::MIN, -1).is_err());
+    fn test_function_name_validation() {
+        assert!(ZigAgent::validate_function_name("acropolis_echo").is_ok());
+        assert!(ZigAgent::validate_function_name("acropolis_").is_err());
+        assert!(ZigAgent::validate_function_name("acropolis_free").is_err());
+        assert!(ZigAgent::validate_function_name("system").is_err());
+        assert!(ZigAgent::validate_function_name("acropolis_x; rm").is_err());
     }
 
     #[test]
-    fn test_validate_ffi_input_out_of_bounds() {
-        assert!(validate_ffi_input(MAX_SAFE_INTEGER + 1, 0).is_err());
-        assert!(validate_ffi_input(MIN_SAFE_INTEGER - 1, 0).is_err());
+    fn test_library_requires_allowlisted_hash() {
+        let dir = tempdir().unwrap();
+        let lib = dir.path().join("libtool.so");
+        std::fs::write(&lib, b"not really a library").unwrap();
+        let lib_path = lib.to_string_lossy().to_string();
+
+        // Inside the directory but not allowlisted
+        let unlisted = agent(dir.path(), HashMap::new());
+        assert!(unlisted.validate_library(&lib_path).unwrap_err().to_string().contains("not in the allowlist"));
+
+        let wrong = agent(dir.path(), HashMap::from([(lib_path.clone(), "00".repeat(32))]));
+        assert!(wrong.validate_library(&lib_path).unwrap_err().to_string().contains("integrity check failed"));
+
+        let hash = format!("{:x}", Sha256::digest(b"not really a library"));
+        let listed = agent(dir.path(), HashMap::from([(lib_path.clone(), hash)]));
+        let verified = listed.validate_library(&lib_path).unwrap();
+        assert_ne!(verified.path, lib);
+        assert_eq!(std::fs::read(&verified.path).unwrap(), b"not really a library");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(verified.path.parent().unwrap()).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+
+        // Allowlisted hash but outside the library directories
+        let other = tempdir().unwrap();
+        let elsewhere = agent(other.path(), HashMap::new());
+        assert!(elsewhere.validate_library(&lib_path).unwrap_err().to_string().contains("not in allowed directories"));
+    }
+
+    #[tokio::test]
+    async fn test_calls_refused_while_running_limit_is_held() {
+        let dir = tempdir().unwrap();
+        let mut settings = Settings::default();
+        settings.security.zig_library_directories = vec![dir.path().to_path_buf()];
+        settings.security.zig_max_running_calls = 1;
+        let agent = ZigAgent::new(&settings);
+
+        // Stands in for a timed-out call whose thread has not returned
+        let _stuck = agent.running_calls.clone().try_acquire_owned().unwrap();
+        let input = serde_json::json!({"library": "libtool.so", "function": "acropolis_echo"});
+        let err = agent.call(input).await.unwrap_err();
+        assert!(err.to_string().contains("Too many Zig calls"));
     }
 }
@@ -0,0 +1,368 @@
+//! gRPC transport for agent execution, enabled by the `with-grpc` feature.
+//!
+//! Serves `acropolis.v1.AgentService` (see `proto/acropolis.proto`) on its own
+//! port alongside the HTTP API, sharing the same orchestrator and JWT
+//! authentication. Clients send `authorization: Bearer <token>` metadata;
+//! calls are charged to the token's subject and scoped to its tenant exactly
+//! as over HTTP. When the HTTP server terminates TLS, so does this port, with
+//! the same (hot-reloaded) certificate.
+
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use anyhow::{anyhow, Result};
+use axum_server::accept::Accept;
+use axum_server::tls_rustls::{RustlsAcceptor, RustlsConfig};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, RwLock};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::CancellationToken;
+use tonic::transport::server::Connected;
+use tonic::{Request, Response, Status};
+use tracing::{debug, error, info, instrument, warn};
+
+use crate::{
+    agent::{AgentMaintenanceError, CancelledError, InvalidInputError},
+    auth::{AuthManager, Claims},
+    error::chain_any,
+    input_guard::InputLimitError,
+    mesh::TaskPriority,
+    orchestrator::{Caller, Orchestrator},
+    redaction::OutputRedactor,
+    server::task_caller,
+    settings::Settings,
+};
+
+/// Generated protobuf types and service stubs
+pub mod proto {
+    tonic::include_proto!("acropolis.v1");
+}
+
+use proto::agent_service_server::{AgentService, AgentServiceServer};
+use proto::{
+    AgentInfo, ExecuteChunk, ExecuteRequest, ExecuteResponse, ListAgentsRequest, ListAgentsResponse,
+};
+
+/// Chunks buffered per stream before the agent is made to wait on the client
+const STREAM_BUFFER: usize = 32;
+
+/// Accepted connections queued for tonic while TLS handshakes complete
+const ACCEPT_BUFFER: usize = 64;
+
+/// `AgentService` implementation backed by the shared orchestrator
+pub struct GrpcAgentService {
+    orchestrator: Arc<RwLock<Orchestrator>>,
+    auth_manager: Arc<AuthManager>,
+    output_redactor: Option<Arc<OutputRedactor>>,
+    settings: Settings,
+}
+
+impl GrpcAgentService {
+    pub fn new(
+        orchestrator: Arc<RwLock<Orchestrator>>,
+        auth_manager: Arc<AuthManager>,
+        output_redactor: Option<Arc<OutputRedactor>>,
+        settings: Settings,
+    ) -> Self {
+        Self { orchestrator, auth_manager, output_redactor, settings }
+    }
+
+    /// Validate the bearer token carried in request metadata
+    fn authenticate<T>(&self, request: &Request<T>) -> Result<Claims, Status> {
+        let token = request.metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| Status::unauthenticated("Missing bearer token"))?;
+
+        self.auth_manager.validate_token(token).map_err(|e| {
+            warn!("gRPC token validation failed: {}", e);
+            Status::unauthenticated("Invalid token")
+        })
+    }
+
+    /// Quota and memory identity for calls made with `claims`
+    fn caller(&self, claims: &Claims) -> Caller {
+        task_caller(&self.settings, Some(claims))
+    }
+
+    fn redact(&self, output: String) -> String {
+        match &self.output_redactor {
+            Some(redactor) => redactor.redact(&output),
+            None => output,
+        }
+    }
+
+    /// Parse the request and make sure the target agent exists
+    async fn prepare(&self, request: &ExecuteRequest) -> Result<serde_json::Value, Status> {
+        let input = serde_json::from_str(&request.input_json)
+            .map_err(|e| Status::invalid_argument(format!("input_json is not valid JSON: {}", e)))?;
+
+        if !self.orchestrator.read().await.can_dispatch(&request.agent_name).await {
+            return Err(Status::not_found(format!("Unknown agent '{}'", request.agent_name)));
+        }
+        Ok(input)
+    }
+}
+
+/// Inputs refused by the input guard are the caller's fault, like a 400 on `/execute`
+fn dispatch_status(e: anyhow::Error) -> Status {
+    if e.is::<InputLimitError>() {
+        warn!("{}", e);
+        return Status::invalid_argument(e.to_string());
+    }
+    error!("Failed to dispatch gRPC task: {}", e);
+    Status::internal("Failed to dispatch task")
+}
+
+/// Map an agent failure onto the closest gRPC status
+fn agent_status(e: &anyhow::Error, message: String) -> Status {
+    if chain_any(e, |cause| cause.is::<InvalidInputError>() || cause.is::<InputLimitError>()) {
+        Status::invalid_argument(message)
+    } else if chain_any(e, |cause| cause.is::<CancelledError>()) {
+        Status::cancelled(message)
+    } else if chain_any(e, |cause| cause.is::<AgentMaintenanceError>()) {
+        Status::unavailable(message)
+    } else {
+        Status::internal(message)
+    }
+}
+
+#[tonic::async_trait]
+impl AgentService for GrpcAgentService {
+    type StreamExecuteStream = ReceiverStream<Result<ExecuteChunk, Status>>;
+
+    #[instrument(skip(self, request))]
+    async fn execute(&self, request: Request<ExecuteRequest>) -> Result<Response<ExecuteResponse>, Status> {
+        let claims = self.authenticate(&request)?;
+        let request = request.into_inner();
+        let input = self.prepare(&request).await?;
+        info!("gRPC execute of agent '{}' by {}", request.agent_name, claims.sub);
+
+        let start = std::time::Instant::now();
+        let (resp_tx, mut resp_rx) = mpsc::channel(1);
+        self.orchestrator.read().await
+            .dispatch_as(
+                &self.caller(&claims),
+                (request.agent_name.clone(), input, resp_tx),
+                TaskPriority::Normal,
+                CancellationToken::new(),
+            )
+            .await
+            .map_err(dispatch_status)?;
+
+        match resp_rx.recv().await {
+            Some(Ok(output)) => {
+                let output = match output {
+                    serde_json::Value::String(output) => output,
+                    other => other.to_string(),
+                };
+                Ok(Response::new(ExecuteResponse {
+                    output: self.redact(output),
+                    execution_time_ms: start.elapsed().as_millis() as u64,
+                }))
+            }
+            Some(Err(e)) => Err(agent_status(e.source_error(), self.redact(e.to_string()))),
+            None => Err(Status::internal("Task response channel closed unexpectedly")),
+        }
+    }
+
+    #[instrument(skip(self, request))]
+    async fn stream_execute(
+        &self,
+        request: Request<ExecuteRequest>,
+    ) -> Result<Response<Self::StreamExecuteStream>, Status> {
+        let claims = self.authenticate(&request)?;
+        let request = request.into_inner();
+        let input = self.prepare(&request).await?;
+        info!("gRPC stream execute of agent '{}' by {}", request.agent_name, claims.sub);
+
+        let (out_tx, out_rx) = mpsc::channel(STREAM_BUFFER);
+        let (chunk_tx, mut chunk_rx) = mpsc::channel::<String>(STREAM_BUFFER);
+        let cancel = CancellationToken::new();
+
+        let orchestrator = self.orchestrator.clone();
+        let caller = self.caller(&claims);
+        let agent_name = request.agent_name;
+        let agent_cancel = cancel.clone();
+        let agent_task = tokio::spawn(async move {
+            orchestrator.read().await
+                .dispatch_stream_as(&caller, &agent_name, input, chunk_tx, TaskPriority::Normal, agent_cancel)
+                .await
+        });
+
+        let redactor = self.output_redactor.clone();
+        tokio::spawn(async move {
+            let redact = |data: String| match &redactor {
+                Some(redactor) => redactor.redact(&data),
+                None => data,
+            };
+
+            while let Some(data) = chunk_rx.recv().await {
+                if out_tx.send(Ok(ExecuteChunk { data: redact(data), done: false })).await.is_err() {
+                    // Client went away; stop the agent rather than run it to completion
+                    cancel.cancel();
+                    return;
+                }
+            }
+
+            let last = match agent_task.await {
+                Ok(Ok(())) => Ok(ExecuteChunk { data: String::new(), done: true }),
+                Ok(Err(e)) => Err(agent_status(&e, redact(e.to_string()))),
+                Err(e) => {
+                    error!("gRPC streaming task panicked: {}", e);
+                    Err(Status::internal("Streaming task failed"))
+                }
+            };
+            let _ = out_tx.send(last).await;
+        });
+
+        Ok(Response::new(ReceiverStream::new(out_rx)))
+    }
+
+    #[instrument(skip(self, request))]
+    async fn list_agents(&self, request: Request<ListAgentsRequest>) -> Result<Response<ListAgentsResponse>, Status> {
+        self.authenticate(&request)?;
+        let agents = self.orchestrator.read().await
+            .list_agent_capabilities()
+            .await
+            .into_iter()
+            .map(|(name, agent_type, capabilities)| AgentInfo { name, agent_type, capabilities })
+            .collect();
+        Ok(Response::new(ListAgentsResponse { agents }))
+    }
+}
+
+/// Bind the gRPC port, resolving `host` so names like `localhost` work
+pub async fn bind(host: &str, port: u16) -> Result<TcpListener> {
+    let addr = tokio::net::lookup_host((host, port)).await
+        .map_err(|e| anyhow!("Invalid gRPC address {}:{}: {}", host, port, e))?
+        .next()
+        .ok_or_else(|| anyhow!("gRPC host '{}' did not resolve to an address", host))?;
+    TcpListener::bind(addr).await
+        .map_err(|e| anyhow!("Failed to bind gRPC server to {}: {}", addr, e))
+}
+
+/// Serve the gRPC API on `listener` until `shutdown` is cancelled, then wait
+/// for in-flight calls to finish. With `tls` set, connections complete a TLS
+/// handshake before they reach tonic.
+pub async fn serve(
+    listener: TcpListener,
+    service: GrpcAgentService,
+    tls: Option<RustlsConfig>,
+    shutdown: CancellationToken,
+) -> Result<()> {
+    let addr = listener.local_addr()?;
+    let (conn_tx, conn_rx) = mpsc::channel(ACCEPT_BUFFER);
+    let acceptor = tls.map(RustlsAcceptor::new);
+    info!("gRPC server listening on {}{}", addr, if acceptor.is_some() { " (TLS)" } else { "" });
+
+    let accepting = shutdown.clone();
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer) = tokio::select! {
+                _ = accepting.cancelled() => return,
+                accepted = listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        warn!("Failed to accept gRPC connection: {}", e);
+                        continue;
+                    }
+                },
+            };
+            let conn_tx = conn_tx.clone();
+            match &acceptor {
+                // Handshake off the accept loop so one slow client can't stall the rest
+                Some(acceptor) => {
+                    let acceptor = acceptor.clone();
+                    tokio::spawn(async move {
+                        match acceptor.accept(stream, ()).await {
+                            Ok((stream, ())) => {
+                                let _ = conn_tx.send(Ok(GrpcConnection(Box::new(stream)))).await;
+                            }
+                            Err(e) => debug!("gRPC TLS handshake with {} failed: {}", peer, e),
+                        }
+                    });
+                }
+                None => {
+                    let _ = conn_tx.send(Ok::<_, io::Error>(GrpcConnection(Box::new(stream)))).await;
+                }
+            }
+        }
+    });
+
+    tonic::transport::Server::builder()
+        .add_service(AgentServiceServer::new(service))
+        .serve_with_incoming_shutdown(ReceiverStream::new(conn_rx), shutdown.cancelled_owned())
+        .await
+        .map_err(|e| anyhow!("gRPC server error: {}", e))?;
+    info!("gRPC server drained");
+    Ok(())
+}
+
+trait Io: AsyncRead + AsyncWrite + Send + Unpin + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin + 'static> Io for T {}
+
+/// A plain or TLS connection handed to tonic
+struct GrpcConnection(Box<dyn Io>);
+
+impl Connected for GrpcConnection {
+    type ConnectInfo = ();
+
+    fn connect_info(&self) -> Self::ConnectInfo {}
+}
+
+impl AsyncRead for GrpcConnection {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for GrpcConnection {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut *self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.0).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agent_status_mapping() {
+        let invalid = anyhow::Error::new(InvalidInputError("bad".into()));
+        assert_eq!(agent_status(&invalid, "bad".into()).code(), tonic::Code::InvalidArgument);
+
+        let cancelled = anyhow::Error::new(CancelledError);
+        assert_eq!(agent_status(&cancelled, "stop".into()).code(), tonic::Code::Cancelled);
+
+        let failed = anyhow::anyhow!("boom");
+        assert_eq!(agent_status(&failed, "boom".into()).code(), tonic::Code::Internal);
+    }
+
+    #[test]
+    fn test_dispatch_status_mapping() {
+        let rejected = dispatch_status(InputLimitError {
+            agent: "echo".to_string(),
+            reason: "too deep".to_string(),
+        }.into());
+        assert_eq!(rejected.code(), tonic::Code::InvalidArgument);
+        assert_eq!(rejected.message(), "Input for agent 'echo' rejected: too deep");
+
+        let failed = dispatch_status(anyhow::anyhow!("scheduler closed"));
+        assert_eq!(failed.code(), tonic::Code::Internal);
+        assert_eq!(failed.message(), "Failed to dispatch task");
+    }
+}
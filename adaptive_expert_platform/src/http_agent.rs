@@ -0,0 +1,495 @@
+//! Built-in agent that performs outbound HTTP requests with SSRF protection.
+//!
+//! Accepts JSON like:
+//! ```json
+//! { "url": "https://example.com/data", "method": "GET", "headers": {"Accept": "application/json"}, "body": null }
+//! ```
+//! and returns `{"status", "headers", "body"}` as a JSON string.
+//!
+//! Every hop (including redirects) is resolved up front and rejected if any
+//! address is private, loopback, link-local or otherwise internal, unless the
+//! host is explicitly allowlisted in `SecurityConfig::http_fetch_allowed_hosts`.
+//! The vetted address is pinned for the connection so a second DNS lookup
+//! cannot rebind the host to an internal address, and system proxies are
+//! ignored since a proxy would resolve the host again itself. Credential
+//! headers are dropped once a redirect leaves the original origin.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::{header::LOCATION, redirect::Policy, Client, Method, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, instrument, warn};
+use url::{Host, Url};
+
+use crate::{
+    agent::{Agent, AgentHealth, InvalidInputError},
+    memory::Memory,
+    settings::Settings,
+};
+
+#[derive(Deserialize)]
+struct HttpFetchInput {
+    url: String,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    body: Option<Value>,
+}
+
+/// Agent that fetches a URL and returns the response as JSON
+pub struct HttpFetchAgent {
+    request_count: std::sync::atomic::AtomicU64,
+    error_count: std::sync::atomic::AtomicU64,
+    start_time: std::time::Instant,
+    allowed_hosts: HashSet<String>,
+    max_response_bytes: usize,
+    max_redirects: usize,
+    timeout: Duration,
+}
+
+impl HttpFetchAgent {
+    pub fn new(settings: &Settings) -> Self {
+        Self {
+            request_count: std::sync::atomic::AtomicU64::new(0),
+            error_count: std::sync::atomic::AtomicU64::new(0),
+            start_time: std::time::Instant::now(),
+            allowed_hosts: settings
+                .security
+                .http_fetch_allowed_hosts
+                .iter()
+                .map(|h| h.to_ascii_lowercase())
+                .collect(),
+            max_response_bytes: settings.security.http_fetch_max_response_bytes,
+            max_redirects: settings.security.http_fetch_max_redirects,
+            timeout: Duration::from_secs(settings.security.max_execution_time_seconds),
+        }
+    }
+
+    /// Validate the URL and resolve it to a single address that is safe to connect to
+    async fn resolve_target(&self, url: &Url) -> Result<SocketAddr> {
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Err(anyhow!("Unsupported URL scheme '{}'", url.scheme()));
+        }
+
+        let port = url
+            .port_or_known_default()
+            .ok_or_else(|| anyhow!("URL '{}' has no port", url))?;
+
+        let addrs: Vec<SocketAddr> = match url.host() {
+            Some(Host::Ipv4(ip)) => vec![SocketAddr::new(IpAddr::V4(ip), port)],
+            Some(Host::Ipv6(ip)) => vec![SocketAddr::new(IpAddr::V6(ip), port)],
+            Some(Host::Domain(domain)) => tokio::net::lookup_host((domain, port))
+                .await
+                .map_err(|e| anyhow!("Failed to resolve host '{}': {}", domain, e))?
+                .collect(),
+            None => return Err(anyhow!("URL '{}' has no host", url)),
+        };
+
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let allowlisted = self.allowed_hosts.contains(&host);
+
+        // Reject the host if *any* resolved address is internal
+        if !allowlisted {
+            if let Some(blocked) = addrs.iter().find(|addr| is_blocked_ip(addr.ip())) {
+                return Err(anyhow!(
+                    "Host '{}' resolves to a non-public address ({}) and is not allowlisted",
+                    host,
+                    blocked.ip()
+                ));
+            }
+        }
+
+        addrs
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("Host '{}' did not resolve to any address", host))
+    }
+
+    /// Build a client that connects to `addr` for `url`, never following redirects itself
+    fn client_for(&self, url: &Url, addr: SocketAddr) -> Result<Client> {
+        let mut builder = Client::builder()
+            .redirect(Policy::none())
+            .no_proxy()
+            .timeout(self.timeout);
+        if let Some(Host::Domain(domain)) = url.host() {
+            builder = builder.resolve(domain, addr);
+        }
+        Ok(builder.build()?)
+    }
+
+    async fn fetch(&self, input: HttpFetchInput) -> Result<Value> {
+        let mut url = Url::parse(&input.url)
+            .map_err(|e| InvalidInputError(format!("Invalid URL '{}': {}", input.url, e)))?;
+        let mut method = parse_method(input.method.as_deref())?;
+
+        if input.headers.keys().any(|k| k.eq_ignore_ascii_case("host")) {
+            return Err(InvalidInputError("Overriding the 'Host' header is not allowed".to_string()).into());
+        }
+
+        let mut body = match input.body {
+            None | Some(Value::Null) => None,
+            Some(Value::String(s)) => Some(s),
+            Some(other) => Some(serde_json::to_string(&other)?),
+        };
+
+        let origin = url.origin();
+        // Stays set once any hop leaves the original origin
+        let mut cross_origin = false;
+        let mut redirects = 0;
+        loop {
+            let addr = self.resolve_target(&url).await?;
+            let client = self.client_for(&url, addr)?;
+
+            let mut request = client.request(method.clone(), url.clone());
+            cross_origin |= url.origin() != origin;
+            for (name, value) in &input.headers {
+                if cross_origin && is_credential_header(name) {
+                    continue;
+                }
+                request = request.header(name, value);
+            }
+            if let Some(body) = &body {
+                request = request.body(body.clone());
+            }
+
+            let mut response = request
+                .send()
+                .await
+                .map_err(|e| anyhow!("HTTP request to '{}' failed: {}", url, e))?;
+            let status = response.status();
+
+            if status.is_redirection() {
+                if let Some(location) = response.headers().get(LOCATION) {
+                    if redirects >= self.max_redirects {
+                        return Err(anyhow!("Too many redirects (max: {})", self.max_redirects));
+                    }
+                    redirects += 1;
+
+                    let location = location
+                        .to_str()
+                        .map_err(|_| anyhow!("Redirect location is not valid UTF-8"))?;
+                    url = url
+                        .join(location)
+                        .map_err(|e| anyhow!("Invalid redirect location '{}': {}", location, e))?;
+
+                    // Mirror browser semantics: 303, and 301/302 after POST, become a bodiless GET
+                    if status == StatusCode::SEE_OTHER
+                        || (method == Method::POST
+                            && (status == StatusCode::MOVED_PERMANENTLY || status == StatusCode::FOUND))
+                    {
+                        method = Method::GET;
+                        body = None;
+                    }
+                    continue;
+                }
+            }
+
+            let headers: HashMap<String, String> = response
+                .headers()
+                .iter()
+                .map(|(k, v)| (k.to_string(), String::from_utf8_lossy(v.as_bytes()).into_owned()))
+                .collect();
+
+            if let Some(length) = response.content_length() {
+                if length as usize > self.max_response_bytes {
+                    return Err(anyhow!(
+                        "Response body of {} bytes exceeds limit of {} bytes",
+                        length,
+                        self.max_response_bytes
+                    ));
+                }
+            }
+
+            // Stream the body so an unannounced large response is cut off early
+            let mut bytes = Vec::new();
+            while let Some(chunk) = response.chunk().await? {
+                if bytes.len() + chunk.len() > self.max_response_bytes {
+                    return Err(anyhow!(
+                        "Response body exceeds limit of {} bytes",
+                        self.max_response_bytes
+                    ));
+                }
+                bytes.extend_from_slice(&chunk);
+            }
+
+            return Ok(json!({
+                "status": status.as_u16(),
+                "headers": headers,
+                "body": String::from_utf8_lossy(&bytes),
+            }));
+        }
+    }
+}
+
+/// Headers not forwarded to a redirect target on another scheme, host or port
+fn is_credential_header(name: &str) -> bool {
+    ["authorization", "cookie", "proxy-authorization"]
+        .iter()
+        .any(|credential| name.eq_ignore_ascii_case(credential))
+}
+
+fn parse_method(method: Option<&str>) -> Result<Method> {
+    match method.map(|m| m.to_ascii_uppercase()).as_deref() {
+        None | Some("GET") => Ok(Method::GET),
+        Some("HEAD") => Ok(Method::HEAD),
+        Some("POST") => Ok(Method::POST),
+        Some("PUT") => Ok(Method::PUT),
+        Some("PATCH") => Ok(Method::PATCH),
+        Some("DELETE") => Ok(Method::DELETE),
+        Some("OPTIONS") => Ok(Method::OPTIONS),
+        Some(other) => Err(InvalidInputError(format!("Unsupported HTTP method '{}'", other)).into()),
+    }
+}
+
+/// Whether an address is internal (private, loopback, link-local, ...) and must not be fetched
+pub fn is_blocked_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_blocked_ipv4(ip),
+        IpAddr::V6(ip) => is_blocked_ipv6(ip),
+    }
+}
+
+fn is_blocked_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || a == 0 // "this network"
+        || (a == 100 && (64..128).contains(&b)) // shared address space (CGNAT)
+        || (a == 192 && b == 0 && c == 0) // IETF protocol assignments
+        || (a == 198 && (b == 18 || b == 19)) // benchmarking
+        || a >= 240 // reserved
+}
+
+fn is_blocked_ipv6(ip: Ipv6Addr) -> bool {
+    // IPv4-mapped, NAT64, 6to4 and Teredo addresses are judged by the
+    // embedded IPv4 addresses, which is where the packets end up
+    if let Some(v4) = ip.to_ipv4_mapped() {
+        return is_blocked_ipv4(v4);
+    }
+    let segments = ip.segments();
+    let embedded = |high: u16, low: u16| Ipv4Addr::from((u32::from(high) << 16) | u32::from(low));
+    if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        return is_blocked_ipv4(embedded(segments[6], segments[7]));
+    }
+    if segments[0] == 0x2002 {
+        return is_blocked_ipv4(embedded(segments[1], segments[2]));
+    }
+    if segments[0] == 0x2001 && segments[1] == 0 {
+        // Teredo: server address in the prefix, client address stored inverted at the end
+        return is_blocked_ipv4(embedded(segments[2], segments[3]))
+            || is_blocked_ipv4(embedded(!segments[6], !segments[7]));
+    }
+
+    ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        || (segments[0] & 0xfe00) == 0xfc00 // unique local
+        || (segments[0] & 0xffc0) == 0xfe80 // link-local
+        || (segments[0] & 0xffc0) == 0xfec0 // site-local (deprecated)
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8) // documentation
+}
+
+#[async_trait]
+impl Agent for HttpFetchAgent {
+    fn name(&self) -> &str { "http_fetch" }
+
+    fn agent_type(&self) -> &str { "network" }
+
+    fn capabilities(&self) -> Vec<String> {
+        vec!["http_fetch".to_string()]
+    }
+
+    #[instrument(skip(self, input, _memory))]
+    async fn handle(&self, input: Value, _memory: Arc<Memory>) -> Result<String> {
+        self.request_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+        let parsed_input: HttpFetchInput = serde_json::from_value(input).map_err(|e| {
+            self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            InvalidInputError(format!("Invalid HTTP fetch input: {}", e))
+        })?;
+
+        info!("Fetching URL: {}", parsed_input.url);
+
+        match self.fetch(parsed_input).await {
+            Ok(result) => Ok(result.to_string()),
+            Err(e) => {
+                self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                warn!("HTTP fetch failed: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    async fn health_check(&self) -> Result<AgentHealth> {
+        Ok(AgentHealth {
+            status: "healthy".to_string(),
+            details: None,
+            uptime_seconds: self.start_time.elapsed().as_secs(),
+            total_requests: self.request_count.load(std::sync::atomic::Ordering::Relaxed),
+            error_count: self.error_count.load(std::sync::atomic::Ordering::Relaxed),
+            average_response_time_ms: 0.0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{response::Redirect, routing::get, Router};
+
+    fn agent_with(allowed_hosts: &[&str]) -> HttpFetchAgent {
+        let mut settings = Settings::default();
+        settings.security.http_fetch_allowed_hosts = allowed_hosts.iter().map(|h| h.to_string()).collect();
+        settings.security.http_fetch_max_response_bytes = 1024;
+        settings.security.http_fetch_max_redirects = 2;
+        HttpFetchAgent::new(&settings)
+    }
+
+    async fn spawn_test_server() -> SocketAddr {
+        let app = Router::new()
+            .route("/ok", get(|| async { "hello" }))
+            .route("/big", get(|| async { "x".repeat(4096) }))
+            .route("/hop", get(|| async { Redirect::temporary("/ok") }))
+            .route("/loop", get(|| async { Redirect::temporary("/loop") }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        addr
+    }
+
+    fn input(url: String) -> HttpFetchInput {
+        HttpFetchInput { url, method: None, headers: HashMap::new(), body: None }
+    }
+
+    #[test]
+    fn test_blocked_ips() {
+        for ip in [
+            "127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254",
+            "0.0.0.0", "100.64.0.1", "::1", "::", "fc00::1", "fe80::1",
+            "::ffff:127.0.0.1", "64:ff9b::a9fe:a9fe",
+            // 6to4 wrapping 127.0.0.1 and 10.0.0.1
+            "2002:7f00:1::", "2002:a00:1::1",
+            // Teredo with client 127.0.0.1, and with server 10.0.0.1
+            "2001:0:4136:e378:8000:63bf:80ff:fffe", "2001:0:a00:1:8000:63bf:f7f7:f7f7",
+        ] {
+            assert!(is_blocked_ip(ip.parse().unwrap()), "{} should be blocked", ip);
+        }
+        for ip in [
+            "93.184.216.34", "8.8.8.8", "2606:4700::1111",
+            // 6to4 wrapping 93.184.216.34, Teredo with client 8.8.8.8
+            "2002:5db8:d822::1", "2001:0:4136:e378:8000:63bf:f7f7:f7f7",
+        ] {
+            assert!(!is_blocked_ip(ip.parse().unwrap()), "{} should be allowed", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_malformed_input_is_not_retryable() {
+        let agent = agent_with(&[]);
+        let echo = Arc::new(crate::agent::EchoAgent::new());
+        let memory = Arc::new(Memory::new(
+            echo.clone(),
+            echo,
+            Arc::new(crate::memory::redis_store::InMemoryEmbeddingCache::new()),
+        ));
+        for input in [
+            json!({"method": "GET"}),
+            json!({"url": "not a url"}),
+            json!({"url": "http://example.com/", "method": "TRACE"}),
+            json!({"url": "http://example.com/", "headers": {"Host": "internal"}}),
+        ] {
+            let err = agent.handle(input.clone(), memory.clone()).await.unwrap_err();
+            assert!(err.is::<InvalidInputError>(), "{}", input);
+            assert!(!crate::agent::is_retryable(&err), "{}", input);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejects_internal_targets() {
+        let agent = agent_with(&[]);
+        for url in [
+            "http://127.0.0.1/",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/",
+            "http://[::ffff:10.0.0.1]/",
+            "file:///etc/passwd",
+        ] {
+            assert!(agent.resolve_target(&Url::parse(url).unwrap()).await.is_err(), "{}", url);
+        }
+        assert!(agent.resolve_target(&Url::parse("http://93.184.216.34/").unwrap()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_allowlisted_host_fetch_and_limits() {
+        let addr = spawn_test_server().await;
+        let base = format!("http://{}", addr);
+
+        // Blocked unless allowlisted
+        assert!(agent_with(&[]).fetch(input(format!("{}/ok", base))).await.is_err());
+
+        let agent = agent_with(&["127.0.0.1"]);
+        let result = agent.fetch(input(format!("{}/ok", base))).await.unwrap();
+        assert_eq!(result["status"], 200);
+        assert_eq!(result["body"], "hello");
+
+        let redirected = agent.fetch(input(format!("{}/hop", base))).await.unwrap();
+        assert_eq!(redirected["body"], "hello");
+
+        let too_many = agent.fetch(input(format!("{}/loop", base))).await.unwrap_err();
+        assert!(too_many.to_string().contains("Too many redirects"));
+
+        let too_big = agent.fetch(input(format!("{}/big", base))).await.unwrap_err();
+        assert!(too_big.to_string().contains("exceeds limit"));
+    }
+
+    #[tokio::test]
+    async fn test_credentials_dropped_on_cross_origin_redirect() {
+        async fn whoami(headers: axum::http::HeaderMap) -> String {
+            let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or("none").to_string();
+            format!("{} {} {}", header("authorization"), header("cookie"), header("accept"))
+        }
+        let target = Router::new()
+            .route("/whoami", get(whoami))
+            .route("/self", get(|| async { Redirect::temporary("/whoami") }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target_addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, target).await.unwrap() });
+
+        let away = format!("http://{}/whoami", target_addr);
+        let origin = Router::new().route("/away", get(move || async move { Redirect::temporary(&away) }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let origin_addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, origin).await.unwrap() });
+
+        let agent = agent_with(&["127.0.0.1"]);
+        let with_credentials = |url: String| HttpFetchInput {
+            url,
+            method: None,
+            headers: HashMap::from([
+                ("Authorization".to_string(), "Bearer secret".to_string()),
+                ("Cookie".to_string(), "session=1".to_string()),
+                ("Accept".to_string(), "text/plain".to_string()),
+            ]),
+            body: None,
+        };
+
+        let same = agent.fetch(with_credentials(format!("http://{}/self", target_addr))).await.unwrap();
+        assert_eq!(same["body"], "Bearer secret session=1 text/plain");
+
+        // Only the port differs, which already makes it another origin
+        let cross = agent.fetch(with_credentials(format!("http://{}/away", origin_addr))).await.unwrap();
+        assert_eq!(cross["body"], "none none text/plain");
+    }
+}
@@ -0,0 +1,165 @@
+//! Idempotency-key support so clients can safely retry non-idempotent requests.
+//!
+//! Completed responses are stored in the shared `MultiTierCache` under the
+//! client-supplied key for a TTL. A per-key lock makes concurrent duplicates
+//! wait for the first request instead of executing twice.
+
+use anyhow::{anyhow, Result};
+use dashmap::DashMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use tracing::warn;
+
+use crate::cache::MultiTierCache;
+
+/// Maximum accepted length of an idempotency key
+pub const MAX_KEY_LENGTH: usize = 255;
+
+/// Cached response together with a fingerprint of the request that produced it
+#[derive(Clone, Serialize, Deserialize)]
+struct StoredResponse<T> {
+    fingerprint: String,
+    response: T,
+}
+
+/// Stores completed responses by idempotency key
+pub struct IdempotencyStore {
+    cache: Arc<MultiTierCache>,
+    locks: Arc<DashMap<String, Arc<Mutex<()>>>>,
+    ttl: Duration,
+}
+
+/// Holds the per-key lock; released (and cleaned up if uncontended) on drop
+pub struct IdempotencyGuard {
+    key: String,
+    guard: Option<OwnedMutexGuard<()>>,
+    locks: Arc<DashMap<String, Arc<Mutex<()>>>>,
+}
+
+impl Drop for IdempotencyGuard {
+    fn drop(&mut self) {
+        // Release the lock before checking whether anyone else still waits on it
+        self.guard.take();
+        self.locks.remove_if(&self.key, |_, lock| Arc::strong_count(lock) == 1);
+    }
+}
+
+impl IdempotencyStore {
+    pub fn new(cache: Arc<MultiTierCache>, ttl: Duration) -> Self {
+        Self {
+            cache,
+            locks: Arc::new(DashMap::new()),
+            ttl,
+        }
+    }
+
+    /// Validate a client-supplied key
+    pub fn validate_key(key: &str) -> Result<()> {
+        if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+            return Err(anyhow!("Idempotency key must be 1-{} characters", MAX_KEY_LENGTH));
+        }
+        if !key.chars().all(|c| c.is_ascii_graphic()) {
+            return Err(anyhow!("Idempotency key must contain only visible ASCII characters"));
+        }
+        Ok(())
+    }
+
+    /// Acquire the lock for `key`, waiting for any in-flight request with the same key
+    pub async fn lock(&self, key: &str) -> IdempotencyGuard {
+        let lock = self
+            .locks
+            .entry(key.to_string())
+            .or_insert_with(|| Arc::new(Mutex::new(())))
+            .clone();
+        IdempotencyGuard {
+            key: key.to_string(),
+            guard: Some(lock.lock_owned().await),
+            locks: self.locks.clone(),
+        }
+    }
+
+    /// Look up a completed response for `key`.
+    ///
+    /// Fails if the key was previously used for a different request.
+    pub async fn get<T>(&self, key: &str, fingerprint: &str) -> Result<Option<T>>
+    where
+        T: Serialize + DeserializeOwned + Clone + Send + Sync,
+    {
+        let stored = match self.cache.get::<StoredResponse<T>>(&Self::cache_key(key)).await {
+            Ok(stored) => stored,
+            Err(e) => {
+                warn!("Idempotency cache lookup failed, treating as miss: {}", e);
+                None
+            }
+        };
+
+        match stored {
+            Some(stored) if stored.fingerprint != fingerprint => {
+                Err(anyhow!("Idempotency key was already used for a different request"))
+            }
+            Some(stored) => Ok(Some(stored.response)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store the completed response for `key`
+    pub async fn put<T>(&self, key: &str, fingerprint: &str, response: T) -> Result<()>
+    where
+        T: Serialize + DeserializeOwned + Clone + Send + Sync,
+    {
+        let stored = StoredResponse {
+            fingerprint: fingerprint.to_string(),
+            response,
+        };
+        self.cache.set(&Self::cache_key(key), stored, Some(self.ttl)).await
+    }
+
+    /// Fingerprint a request so a reused key with a different payload can be detected
+    pub fn fingerprint(request: &impl Serialize) -> Result<String> {
+        let bytes = serde_json::to_vec(request)?;
+        Ok(blake3::hash(&bytes).to_hex().to_string())
+    }
+
+    fn cache_key(key: &str) -> String {
+        format!("idempotency:{}", key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::MultiTierCacheConfig;
+
+    async fn store() -> Arc<IdempotencyStore> {
+        let cache = Arc::new(MultiTierCache::new(MultiTierCacheConfig::default()).await.unwrap());
+        Arc::new(IdempotencyStore::new(cache, Duration::from_secs(60)))
+    }
+
+    #[tokio::test]
+    async fn test_lock_removed_with_last_guard() {
+        let store = store().await;
+        let guard = store.lock("key-1").await;
+        assert_eq!(store.locks.len(), 1);
+        drop(guard);
+        assert!(store.locks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reused_key_with_different_request_rejected() {
+        let store = store().await;
+        store.put("key-2", "fp-a", 1u32).await.unwrap();
+
+        assert_eq!(store.get::<u32>("key-2", "fp-a").await.unwrap(), Some(1));
+        assert!(store.get::<u32>("key-2", "fp-b").await.is_err());
+    }
+
+    #[test]
+    fn test_validate_key() {
+        assert!(IdempotencyStore::validate_key("9f1c-retry-1").is_ok());
+        assert!(IdempotencyStore::validate_key("").is_err());
+        assert!(IdempotencyStore::validate_key("has space").is_err());
+        assert!(IdempotencyStore::validate_key(&"k".repeat(MAX_KEY_LENGTH + 1)).is_err());
+    }
+}
@@ -0,0 +1,132 @@
+//! Structural limits on task inputs.
+//!
+//! Deeply nested or enormous JSON values can exhaust the stack or memory of
+//! agents that walk them recursively. [`InputGuard`] bounds nesting depth and
+//! total node count before a task is dispatched; the walk itself stops as soon
+//! as either limit is exceeded, so a hostile input never costs more than the
+//! limits allow.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::settings::Settings;
+
+/// Maximum nesting depth and node count accepted for one input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputLimits {
+    pub max_depth: usize,
+    pub max_nodes: usize,
+}
+
+/// Returned when an input exceeds its [`InputLimits`]
+#[derive(Debug)]
+pub struct InputLimitError {
+    pub agent: String,
+    pub reason: String,
+}
+
+impl fmt::Display for InputLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Input for agent '{}' rejected: {}", self.agent, self.reason)
+    }
+}
+
+impl std::error::Error for InputLimitError {}
+
+impl InputLimits {
+    /// Check `value` against these limits without recursing past `max_depth`
+    pub fn check(&self, value: &Value) -> std::result::Result<(), String> {
+        let mut nodes = 0;
+        self.walk(value, 1, &mut nodes)
+    }
+
+    fn walk(&self, value: &Value, depth: usize, nodes: &mut usize) -> std::result::Result<(), String> {
+        if depth > self.max_depth {
+            return Err(format!("nesting depth exceeds {}", self.max_depth));
+        }
+        *nodes += 1;
+        if *nodes > self.max_nodes {
+            return Err(format!("more than {} JSON nodes", self.max_nodes));
+        }
+
+        match value {
+            Value::Array(items) => items.iter().try_for_each(|item| self.walk(item, depth + 1, nodes)),
+            Value::Object(map) => map.values().try_for_each(|item| self.walk(item, depth + 1, nodes)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Input limits with optional per-agent overrides
+#[derive(Debug, Clone)]
+pub struct InputGuard {
+    default: InputLimits,
+    agents: HashMap<String, InputLimits>,
+}
+
+impl InputGuard {
+    pub fn new(default: InputLimits) -> Self {
+        Self { default, agents: HashMap::new() }
+    }
+
+    pub fn from_settings(settings: &Settings) -> Self {
+        let security = &settings.security;
+        Self {
+            default: InputLimits {
+                max_depth: security.max_input_depth,
+                max_nodes: security.max_input_nodes,
+            },
+            agents: security.agent_input_limits.clone(),
+        }
+    }
+
+    /// Override the limits for one agent
+    pub fn with_agent_limits(mut self, agent: impl Into<String>, limits: InputLimits) -> Self {
+        self.agents.insert(agent.into(), limits);
+        self
+    }
+
+    pub fn limits_for(&self, agent: &str) -> InputLimits {
+        self.agents.get(agent).copied().unwrap_or(self.default)
+    }
+
+    pub fn check(&self, agent: &str, input: &Value) -> std::result::Result<(), InputLimitError> {
+        self.limits_for(agent).check(input).map_err(|reason| InputLimitError {
+            agent: agent.to_string(),
+            reason,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn nested(depth: usize) -> Value {
+        (0..depth).fold(json!(0), |inner, _| json!([inner]))
+    }
+
+    #[test]
+    fn test_depth_and_node_limits() {
+        let guard = InputGuard::new(InputLimits { max_depth: 8, max_nodes: 10 });
+        assert!(guard.check("echo", &json!({"a": [1, 2, {"b": "c"}]})).is_ok());
+        assert!(guard.check("echo", &nested(7)).is_ok());
+
+        let err = guard.check("echo", &nested(8)).unwrap_err();
+        assert!(err.reason.contains("depth"));
+        let err = guard.check("echo", &json!([0, 1, 2, 3, 4, 5, 6, 7, 8, 9])).unwrap_err();
+        assert!(err.reason.contains("nodes"));
+    }
+
+    #[test]
+    fn test_pathological_nesting_is_rejected_without_overflow() {
+        let guard = InputGuard::new(InputLimits { max_depth: 64, max_nodes: 100_000 })
+            .with_agent_limits("trusted", InputLimits { max_depth: 2_000, max_nodes: 100_000 });
+        let hostile = nested(1_000);
+        assert!(guard.check("echo", &hostile).is_err());
+        assert!(guard.check("trusted", &hostile).is_ok());
+    }
+}
@@ -3,21 +3,43 @@
 //! A secure, polyglot AI orchestration platform built in Rust.
 
 pub mod agent;
+pub mod agent_io_log;
+pub mod agent_middleware;
+pub mod audit;
 pub mod auth;
+pub mod auth_migrations;
 pub mod batch;
+pub mod build_info;
 pub mod cache;
 pub mod cli;
+pub mod config_reload;
+pub mod error;
+pub mod http_agent;
+pub mod idempotency;
+pub mod input_guard;
 pub mod lifecycle;
 pub mod memory;
 pub mod mesh;
+pub mod metric_exporter;
 pub mod metrics;
 pub mod middleware;
 pub mod monitoring;
+pub mod openapi;
 pub mod orchestrator;
+pub mod output_schema;
 pub mod plugin;
+pub mod process;
+pub mod prompt;
+pub mod quota;
+pub mod redaction;
+pub mod rpc;
+pub mod scheduler;
 pub mod server;
 pub mod settings;
+pub mod shutdown;
 pub mod telemetry;
+pub mod tls;
+pub mod trace;
 pub mod websocket;
 
 #[cfg(feature = "with-wasm")]
@@ -29,4 +51,7 @@ pub mod ffi_julia;
 #[cfg(feature = "with-zig")]
 pub mod ffi_zig;
 
+#[cfg(feature = "with-grpc")]
+pub mod grpc;
+
 pub use agent::Agent;
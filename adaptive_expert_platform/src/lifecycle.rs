@@ -10,17 +10,22 @@ use serde::{Deserialize, Serialize};
 use dashmap::DashMap;
 use tokio::process::Command;
 use tracing::{info, warn, error, instrument, debug};
-use reqwest::Client;  // ← newly added
+use reqwest::Client;
 
 use crate::agent::{Agent, AgentHealth};
+use crate::monitoring::HealthStatus;
 
 /// Agent lifecycle states
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum AgentState {
+    Deploying,
     Initializing,
     Running,
     Updating,
     Scaling,
+    Stopping,
+    Stopped,
+    Failed,
     Terminated,
 }
 
@@ -34,9 +39,181 @@ pub struct AgentDeploymentConfig {
     pub min_replicas: u32,
     pub max_replicas: u32,
     pub resource_limits: ResourceLimits,
+    #[serde(default)]
+    pub health_check: HealthCheckConfig,
+    #[serde(default)]
+    pub auto_scaling: AutoScalingConfig,
 }
 
-// ... (all other code in this file remains unchanged) ...
+/// Resource limits applied to each instance of a deployment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    pub max_cpu_percent: f64,
+    pub max_memory_mb: u64,
+    pub max_disk_mb: u64,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            max_cpu_percent: 100.0,
+            max_memory_mb: 1024,
+            max_disk_mb: 1024,
+        }
+    }
+}
+
+/// Resources an instance is currently using
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    pub cpu_percent: f64,
+    pub memory_mb: u64,
+    pub disk_mb: u64,
+    pub network_in_mbps: f64,
+    pub network_out_mbps: f64,
+}
+
+/// How instances of a deployment are health checked; an `endpoint` is
+/// fetched over HTTP, a `command` is run, and without either the instance
+/// state alone decides
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheckConfig {
+    pub enabled: bool,
+    pub endpoint: Option<String>,
+    pub command: Option<Vec<String>>,
+    pub timeout_secs: u64,
+    pub failure_threshold: u32,
+    pub success_threshold: u32,
+}
+
+impl Default for HealthCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            endpoint: None,
+            command: None,
+            timeout_secs: 5,
+            failure_threshold: 3,
+            success_threshold: 1,
+        }
+    }
+}
+
+/// Auto-scaling bounds and targets of a deployment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoScalingConfig {
+    pub enabled: bool,
+    pub target_cpu_percent: f64,
+    pub scale_up_cooldown_secs: u64,
+    pub scale_down_cooldown_secs: u64,
+}
+
+impl Default for AutoScalingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target_cpu_percent: 70.0,
+            scale_up_cooldown_secs: 60,
+            scale_down_cooldown_secs: 300,
+        }
+    }
+}
+
+/// A running (or stopped) replica of a deployment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentInstance {
+    pub id: Uuid,
+    pub deployment_name: String,
+    pub state: AgentState,
+    pub started_at: SystemTime,
+    pub last_health_check: Option<SystemTime>,
+    pub health_status: HealthStatus,
+    pub restart_count: u32,
+    pub resource_usage: ResourceUsage,
+    pub version: String,
+    pub endpoint: Option<String>,
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+/// Deployment history entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeploymentEvent {
+    pub id: Uuid,
+    pub deployment_name: String,
+    pub instance_id: Option<Uuid>,
+    pub event_type: DeploymentEventType,
+    pub timestamp: SystemTime,
+    pub message: String,
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum DeploymentEventType {
+    DeploymentStarted,
+    DeploymentCompleted,
+    DeploymentFailed,
+    InstanceStarted,
+    InstanceStopped,
+    InstanceFailed,
+    ScalingUp,
+    ScalingDown,
+}
+
+/// Running health check tally of one instance
+#[derive(Debug, Clone)]
+struct HealthCheckState {
+    consecutive_failures: u32,
+    consecutive_successes: u32,
+    last_check: SystemTime,
+    /// Set while a worker is checking the instance, so workers don't overlap
+    checking: bool,
+}
+
+/// Last auto-scaling decision for a deployment
+#[derive(Debug, Clone)]
+pub struct ScalingDecision {
+    pub from_replicas: u32,
+    pub to_replicas: u32,
+    pub reason: String,
+    pub decided_at: SystemTime,
+}
+
+/// Lifecycle manager configuration
+#[derive(Debug, Clone)]
+pub struct LifecycleConfig {
+    pub max_concurrent_deployments: usize,
+    pub health_check_worker_count: usize,
+    pub resource_monitoring_interval_secs: u64,
+    pub auto_scaling_interval_secs: u64,
+    pub event_retention_hours: u64,
+}
+
+impl Default for LifecycleConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_deployments: 5,
+            health_check_worker_count: 2,
+            resource_monitoring_interval_secs: 30,
+            auto_scaling_interval_secs: 60,
+            event_retention_hours: 24,
+        }
+    }
+}
+
+/// Deploys, scales, health checks and stops agent instances
+pub struct LifecycleManager {
+    deployments: Arc<DashMap<String, AgentDeploymentConfig>>,
+    instances: Arc<DashMap<Uuid, AgentInstance>>,
+    agents: Arc<DashMap<Uuid, Arc<dyn Agent>>>,
+    events: Arc<RwLock<Vec<DeploymentEvent>>>,
+    health_checks: Arc<DashMap<Uuid, HealthCheckState>>,
+    scaling_decisions: Arc<DashMap<String, ScalingDecision>>,
+    resource_monitor: Arc<ResourceMonitor>,
+    deployment_semaphore: Arc<Semaphore>,
+    /// Shared by every HTTP health check, see [`Self::with_http_client`]
+    http_client: Client,
+    config: LifecycleConfig,
+}
 
 impl LifecycleManager {
     /// Create a new lifecycle manager
@@ -50,10 +227,18 @@ impl LifecycleManager {
             scaling_decisions: Arc::new(DashMap::new()),
             resource_monitor: Arc::new(ResourceMonitor::new()),
             deployment_semaphore: Arc::new(Semaphore::new(config.max_concurrent_deployments)),
+            http_client: Client::new(),
             config,
         }
     }
 
+    /// Run HTTP health checks through `client`, reusing its pooled
+    /// connections instead of opening new ones for every check
+    pub fn with_http_client(mut self, client: Client) -> Self {
+        self.http_client = client;
+        self
+    }
+
     /// Start the lifecycle management system
     #[instrument(skip(self))]
     pub async fn start(&self) -> Result<()> {
@@ -359,9 +544,11 @@ impl LifecycleManager {
         Ok(())
     }
 
-    /// Scale a deployment to the specified number of replicas
-    #[instrument(skip(self))]
-    pub async fn scale_deployment(&self, deployment_name: &str, target_replicas: u32) -> Result<()> {
+    /// Compute the effect of scaling a deployment without changing anything.
+    ///
+    /// Scale-downs stop the newest instances first; `scale_deployment` applies
+    /// exactly the plan returned here.
+    pub async fn plan_scale(&self, deployment_name: &str, target_replicas: u32) -> Result<ScalePlan> {
         let config = self.deployments.get(deployment_name)
             .ok_or_else(|| anyhow!("Deployment '{}' not found", deployment_name))?
             .clone();
@@ -373,57 +560,86 @@ impl LifecycleManager {
             ));
         }
 
-        let current_instances: Vec<_> = self.instances
+        let mut current_instances: Vec<(SystemTime, Uuid)> = self.instances
             .iter()
             .filter(|entry| {
                 entry.value().deployment_name == deployment_name &&
                 entry.value().state != AgentState::Stopped &&
                 entry.value().state != AgentState::Failed
             })
+            .map(|entry| (entry.value().started_at, entry.value().id))
             .collect();
+        current_instances.sort_by(|a, b| b.cmp(a));
 
         let current_replicas = current_instances.len() as u32;
+        let (action, instances_to_stop) = if target_replicas > current_replicas {
+            (ScaleAction::ScaleUp(target_replicas - current_replicas), Vec::new())
+        } else if target_replicas < current_replicas {
+            let count = current_replicas - target_replicas;
+            let stop = current_instances.iter().take(count as usize).map(|(_, id)| *id).collect();
+            (ScaleAction::ScaleDown(count), stop)
+        } else {
+            (ScaleAction::None, Vec::new())
+        };
+
+        Ok(ScalePlan {
+            deployment_name: deployment_name.to_string(),
+            current_replicas,
+            target_replicas,
+            action,
+            instances_to_stop,
+        })
+    }
+
+    /// Scale a deployment to the specified number of replicas, returning the
+    /// plan that was applied
+    #[instrument(skip(self))]
+    pub async fn scale_deployment(&self, deployment_name: &str, target_replicas: u32) -> Result<ScalePlan> {
+        let plan = self.plan_scale(deployment_name, target_replicas).await?;
+        let config = self.deployments.get(deployment_name)
+            .ok_or_else(|| anyhow!("Deployment '{}' not found", deployment_name))?
+            .clone();
+        let current_replicas = plan.current_replicas;
 
         info!("Scaling deployment '{}' from {} to {} replicas", 
               deployment_name, current_replicas, target_replicas);
 
-        if target_replicas > current_replicas {
-            // Scale up
-            self.record_event(DeploymentEvent {
-                id: Uuid::new_v4(),
-                deployment_name: deployment_name.to_string(),
-                instance_id: None,
-                event_type: DeploymentEventType::ScalingUp,
-                timestamp: SystemTime::now(),
-                message: format!("Scaling up from {} to {} replicas", current_replicas, target_replicas),
-                metadata: HashMap::new(),
-            }).await;
+        match plan.action {
+            ScaleAction::ScaleUp(_) => {
+                self.record_event(DeploymentEvent {
+                    id: Uuid::new_v4(),
+                    deployment_name: deployment_name.to_string(),
+                    instance_id: None,
+                    event_type: DeploymentEventType::ScalingUp,
+                    timestamp: SystemTime::now(),
+                    message: format!("Scaling up from {} to {} replicas", current_replicas, target_replicas),
+                    metadata: HashMap::new(),
+                }).await;
 
-            for replica_index in current_replicas..target_replicas {
-                if let Err(e) = self.deploy_instance(&config, replica_index).await {
-                    error!("Failed to deploy additional replica: {}", e);
+                for replica_index in current_replicas..target_replicas {
+                    if let Err(e) = self.deploy_instance(&config, replica_index).await {
+                        error!("Failed to deploy additional replica: {}", e);
+                    }
                 }
             }
-        } else if target_replicas < current_replicas {
-            // Scale down
-            self.record_event(DeploymentEvent {
-                id: Uuid::new_v4(),
-                deployment_name: deployment_name.to_string(),
-                instance_id: None,
-                event_type: DeploymentEventType::ScalingDown,
-                timestamp: SystemTime::now(),
-                message: format!("Scaling down from {} to {} replicas", current_replicas, target_replicas),
-                metadata: HashMap::new(),
-            }).await;
+            ScaleAction::ScaleDown(_) => {
+                self.record_event(DeploymentEvent {
+                    id: Uuid::new_v4(),
+                    deployment_name: deployment_name.to_string(),
+                    instance_id: None,
+                    event_type: DeploymentEventType::ScalingDown,
+                    timestamp: SystemTime::now(),
+                    message: format!("Scaling down from {} to {} replicas", current_replicas, target_replicas),
+                    metadata: HashMap::new(),
+                }).await;
 
-            let instances_to_stop = current_replicas - target_replicas;
-            for (i, entry) in current_instances.iter().enumerate() {
-                if i < instances_to_stop as usize {
-                    if let Err(e) = self.stop_instance(entry.value().id).await {
+                for instance_id in &plan.instances_to_stop {
+                    if let Err(e) = self.stop_instance(*instance_id).await {
                         error!("Failed to stop instance during scale down: {}", e);
                     }
                 }
             }
+            ScaleAction::None => {}
         }
 
         // Update deployment configuration
@@ -432,7 +648,7 @@ impl LifecycleManager {
         }
 
         info!("Scaling completed for deployment '{}'", deployment_name);
-        Ok(())
+        Ok(plan)
     }
 
     /// Get deployment status
@@ -503,6 +719,7 @@ impl LifecycleManager {
             let instances = self.instances.clone();
             let health_checks = self.health_checks.clone();
             let deployments = self.deployments.clone();
+            let http_client = self.http_client.clone();
             
             tokio::spawn(async move {
                 info!("Starting health check worker {}", worker_id);
@@ -517,7 +734,7 @@ impl LifecycleManager {
                         
                         if let Some(config) = deployments.get(&instance.deployment_name) {
                             if config.health_check.enabled {
-                                Self::perform_health_check(*instance_id, &instance, &config.health_check, &health_checks).await;
+                                Self::perform_health_check(*instance_id, &instance, &config.health_check, &health_checks, &http_client).await;
                             }
                         }
                     }
@@ -532,6 +749,7 @@ impl LifecycleManager {
         instance: &AgentInstance,
         config: &HealthCheckConfig,
         health_checks: &DashMap<Uuid, HealthCheckState>,
+        http_client: &Client,
     ) {
         if let Some(mut state) = health_checks.get_mut(&instance_id) {
             if state.checking {
@@ -541,7 +759,7 @@ impl LifecycleManager {
         }
 
         let health_result = if let Some(ref endpoint) = config.endpoint {
-            Self::http_health_check(endpoint, config.timeout_secs).await
+            Self::http_health_check(http_client, endpoint, config.timeout_secs).await
         } else if let Some(ref command) = config.command {
             Self::command_health_check(command, config.timeout_secs).await
         } else {
@@ -570,12 +788,9 @@ impl LifecycleManager {
     }
 
     /// Perform HTTP health check
-    async fn http_health_check(endpoint: &str, timeout_secs: u64) -> Result<bool> {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(timeout_secs))
-            .build()?;
-
-        match client.get(endpoint).send().await {
+    async fn http_health_check(client: &Client, endpoint: &str, timeout_secs: u64) -> Result<bool> {
+        let request = client.get(endpoint).timeout(Duration::from_secs(timeout_secs));
+        match request.send().await {
             Ok(response) => Ok(response.status().is_success()),
             Err(_) => Ok(false),
         }
@@ -591,9 +806,20 @@ impl LifecycleManager {
         if command.len() > 1 {
             cmd.args(&command[1..]);
         }
+        cmd.stdout(std::process::Stdio::null());
+        cmd.stderr(std::process::Stdio::null());
 
-        match tokio::time::timeout(Duration::from_secs(timeout_secs), cmd.output()).await {
-            Ok(Ok(output)) => Ok(output.status.success()),
+        let mut child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                warn!("Failed to spawn health check command {:?}: {}", command, e);
+                return Ok(false);
+            }
+        };
+
+        // Kill and reap the child on timeout instead of leaking it
+        match crate::process::wait_or_kill(&mut child, Duration::from_secs(timeout_secs)).await {
+            Ok(Some(status)) => Ok(status.success()),
             _ => Ok(false),
         }
     }
@@ -718,6 +944,7 @@ impl Clone for LifecycleManager {
             scaling_decisions: self.scaling_decisions.clone(),
             resource_monitor: self.resource_monitor.clone(),
             deployment_semaphore: self.deployment_semaphore.clone(),
+            http_client: self.http_client.clone(),
             config: self.config.clone(),
         }
     }
@@ -733,7 +960,30 @@ impl ResourceMonitor {
         Self {}
     }
 
-    // ... rest of file follows ...
+    /// Current resource usage of an instance (simulated)
+    pub async fn get_instance_usage(&self, _instance_id: Uuid) -> Result<ResourceUsage> {
+        Ok(ResourceUsage::default())
+    }
+}
+
+/// Replica change computed by [`LifecycleManager::plan_scale`]
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "action", content = "count", rename_all = "snake_case")]
+pub enum ScaleAction {
+    None,
+    ScaleUp(u32),
+    ScaleDown(u32),
+}
+
+/// Structured preview of a scaling operation
+#[derive(Debug, Clone, Serialize)]
+pub struct ScalePlan {
+    pub deployment_name: String,
+    pub current_replicas: u32,
+    pub target_replicas: u32,
+    pub action: ScaleAction,
+    /// Instances a scale-down would stop, newest first
+    pub instances_to_stop: Vec<Uuid>,
 }
 
 /// Deployment status information
@@ -747,3 +997,87 @@ pub struct DeploymentStatus {
     pub instances: Vec<AgentInstance>,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deployment(name: &str) -> AgentDeploymentConfig {
+        AgentDeploymentConfig {
+            name: name.to_string(),
+            agent_type: "mock".to_string(),
+            version: "1.0".to_string(),
+            replicas: 3,
+            min_replicas: 1,
+            max_replicas: 5,
+            resource_limits: ResourceLimits::default(),
+            health_check: HealthCheckConfig::default(),
+            auto_scaling: AutoScalingConfig::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plan_scale_is_dry_run() {
+        let manager = LifecycleManager::new(LifecycleConfig::default());
+        manager.deployments.insert("web".to_string(), deployment("web"));
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            ids.push(manager.register_agent_instance("web").await.unwrap());
+        }
+        let states = |m: &LifecycleManager| -> Vec<AgentState> {
+            ids.iter().map(|id| m.instances.get(id).unwrap().state.clone()).collect()
+        };
+        let before = states(&manager);
+
+        let down = manager.plan_scale("web", 1).await.unwrap();
+        assert_eq!(down.action, ScaleAction::ScaleDown(2));
+        assert_eq!(down.instances_to_stop.len(), 2);
+        assert!(down.instances_to_stop.iter().all(|id| ids.contains(id)));
+
+        let up = manager.plan_scale("web", 5).await.unwrap();
+        assert_eq!(up.action, ScaleAction::ScaleUp(2));
+        assert_eq!(manager.plan_scale("web", 3).await.unwrap().action, ScaleAction::None);
+        assert!(manager.plan_scale("web", 9).await.is_err());
+
+        // Nothing was started or stopped
+        assert_eq!(manager.instances.len(), 3);
+        assert_eq!(states(&manager), before);
+        assert_eq!(manager.deployments.get("web").unwrap().replicas, 3);
+    }
+
+    #[tokio::test]
+    async fn test_http_health_checks_share_pooled_client() {
+        use axum::{http::StatusCode, routing::get, Router};
+
+        let app = Router::new()
+            .route("/healthz", get(|| async { "ok" }))
+            .route("/down", get(|| async { StatusCode::SERVICE_UNAVAILABLE }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = crate::monitoring::pooled_http_client(&crate::monitoring::HttpPoolConfig::default()).unwrap();
+        let manager = LifecycleManager::new(LifecycleConfig::default()).with_http_client(client);
+        let healthy = format!("http://{}/healthz", addr);
+        for _ in 0..5 {
+            assert!(LifecycleManager::http_health_check(&manager.http_client, &healthy, 5).await.unwrap());
+        }
+        let down = format!("http://{}/down", addr);
+        assert!(!LifecycleManager::http_health_check(&manager.http_client, &down, 5).await.unwrap());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_health_check() {
+        let command = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+        assert!(LifecycleManager::command_health_check(&command(&["true"]), 5).await.unwrap());
+        assert!(!LifecycleManager::command_health_check(&command(&["false"]), 5).await.unwrap());
+        assert!(!LifecycleManager::command_health_check(&command(&["/nonexistent/probe"]), 5).await.unwrap());
+        assert!(!LifecycleManager::command_health_check(&[], 5).await.unwrap());
+
+        // A hung probe counts as unhealthy once the timeout passes
+        let start = std::time::Instant::now();
+        assert!(!LifecycleManager::command_health_check(&command(&["sleep", "30"]), 1).await.unwrap());
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
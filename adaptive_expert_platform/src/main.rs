@@ -2,7 +2,7 @@
 
 use adaptive_expert_platform::{
     batch, cli, server, settings::Settings, telemetry,
-    auth::AuthManager,
+    auth::AuthManager, auth_migrations,
 };
 use anyhow::Result;
 use clap::Parser;
@@ -17,22 +17,43 @@ async fn main() -> Result<()> {
     let settings = Settings::load()?;
 
     // Initialize telemetry
-    telemetry::init(settings.otlp_endpoint.as_deref())?;
+    telemetry::init(&settings.logging.level, settings.otlp_endpoint.as_deref())?;
 
     // Execute the requested command
     match args.command {
         cli::Commands::Serve { addr: _ } => {
             server::serve(&settings).await
         }
-        cli::Commands::Run { config } => {
-            batch::run(config, settings).await
+        cli::Commands::Run { config, graph: Some(output), .. } => {
+            batch::export_graph(&config, &output)
+        }
+        cli::Commands::Run { config, resume, graph: None } => {
+            batch::run(config, resume, settings).await
         }
         cli::Commands::InitAdmin { username, password } => {
             init_admin(username, password, &settings).await
         }
+        cli::Commands::Migrate => {
+            migrate(&settings)
+        }
     }
 }
 
+/// Apply pending auth database migrations
+fn migrate(settings: &Settings) -> Result<()> {
+    let db_path = settings.db_path.clone().unwrap_or_else(|| "./acropolis_db/auth".to_string());
+    let report = auth_migrations::migrate_path(&db_path)?;
+    if report.applied.is_empty() {
+        println!("Auth database is up to date (schema version {})", report.to_version);
+    } else {
+        println!(
+            "Auth database migrated from schema version {} to {}",
+            report.from_version, report.to_version
+        );
+    }
+    Ok(())
+}
+
 /// Initialize the first admin user
 async fn init_admin(username: String, password: Option<String>, settings: &Settings) -> Result<()> {
     // Validate JWT secret before proceeding
@@ -0,0 +1,301 @@
+//! Splitting of ingested documents into overlapping fragments.
+//!
+//! [`Chunker`] implementations split a whole document at once and are picked
+//! per content type; [`StreamingChunker`] splits uploads as they arrive.
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+
+/// Names accepted by [`chunker_by_name`]
+pub const CHUNKER_NAMES: &[&str] = &["fixed", "sentence"];
+
+/// A piece of a document. Offsets are in characters, `end` exclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Splits a document into the pieces that become memory fragments
+pub trait Chunker: Send + Sync {
+    fn chunk(&self, text: &str) -> Vec<Chunk>;
+}
+
+/// Build a built-in chunker: `"fixed"` windows of `max_chars` overlapping by
+/// `overlap`, or `"sentence"` runs of whole sentences up to `max_chars`
+pub fn chunker_by_name(name: &str, max_chars: usize, overlap: usize) -> Result<Arc<dyn Chunker>> {
+    match name {
+        "fixed" => Ok(Arc::new(FixedSizeChunker::new(max_chars, overlap)?)),
+        "sentence" => Ok(Arc::new(SentenceChunker::new(max_chars)?)),
+        other => Err(anyhow!("Unknown chunker '{}', expected one of: {}", other, CHUNKER_NAMES.join(", "))),
+    }
+}
+
+/// Byte offset of every character, plus the text length
+fn char_boundaries(text: &str) -> Vec<usize> {
+    text.char_indices().map(|(i, _)| i).chain(std::iter::once(text.len())).collect()
+}
+
+/// Overlapping windows of a fixed number of characters, matching the
+/// windows [`StreamingChunker`] produces for the same text
+#[derive(Debug, Clone)]
+pub struct FixedSizeChunker {
+    size: usize,
+    overlap: usize,
+}
+
+impl FixedSizeChunker {
+    /// `size` and `overlap` are in characters; overlap must be smaller
+    pub fn new(size: usize, overlap: usize) -> Result<Self> {
+        validate_window(size, overlap)?;
+        Ok(Self { size, overlap })
+    }
+}
+
+impl Chunker for FixedSizeChunker {
+    fn chunk(&self, text: &str) -> Vec<Chunk> {
+        let bounds = char_boundaries(text);
+        let len = bounds.len() - 1;
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        while start < len {
+            let end = (start + self.size).min(len);
+            chunks.push(Chunk { text: text[bounds[start]..bounds[end]].to_string(), start, end });
+            if end == len {
+                break;
+            }
+            start += self.size - self.overlap;
+        }
+        chunks
+    }
+}
+
+/// Packs whole sentences into chunks of at most `max_chars` characters.
+///
+/// A sentence ends at `.`, `!` or `?` followed by whitespace, or at a line
+/// break. Sentences longer than `max_chars` are split at that length.
+/// Whitespace around each chunk is trimmed, so offsets point at its first
+/// and past its last non-whitespace character.
+#[derive(Debug, Clone)]
+pub struct SentenceChunker {
+    max_chars: usize,
+}
+
+impl SentenceChunker {
+    pub fn new(max_chars: usize) -> Result<Self> {
+        if max_chars == 0 {
+            return Err(anyhow!("Chunk size must be greater than 0"));
+        }
+        Ok(Self { max_chars })
+    }
+}
+
+impl Chunker for SentenceChunker {
+    fn chunk(&self, text: &str) -> Vec<Chunk> {
+        let chars: Vec<char> = text.chars().collect();
+        let bounds = char_boundaries(text);
+
+        let mut sentences = Vec::new();
+        let mut start = 0;
+        for (i, c) in chars.iter().enumerate() {
+            let at_break = chars.get(i + 1).map_or(true, |next| next.is_whitespace());
+            if *c == '\n' || (matches!(c, '.' | '!' | '?') && at_break) {
+                sentences.push((start, i + 1));
+                start = i + 1;
+            }
+        }
+        if start < chars.len() {
+            sentences.push((start, chars.len()));
+        }
+
+        let mut chunks = Vec::new();
+        let mut push = |mut start: usize, mut end: usize| {
+            while start < end && chars[start].is_whitespace() {
+                start += 1;
+            }
+            while end > start && chars[end - 1].is_whitespace() {
+                end -= 1;
+            }
+            if start < end {
+                chunks.push(Chunk { text: text[bounds[start]..bounds[end]].to_string(), start, end });
+            }
+        };
+
+        let mut current: Option<(usize, usize)> = None;
+        for (start, end) in sentences {
+            if let Some((current_start, current_end)) = current {
+                if end - current_start <= self.max_chars {
+                    current = Some((current_start, end));
+                    continue;
+                }
+                push(current_start, current_end);
+            }
+            if end - start <= self.max_chars {
+                current = Some((start, end));
+            } else {
+                current = None;
+                for piece in (start..end).step_by(self.max_chars) {
+                    push(piece, (piece + self.max_chars).min(end));
+                }
+            }
+        }
+        if let Some((start, end)) = current {
+            push(start, end);
+        }
+        chunks
+    }
+}
+
+fn validate_window(chunk_size: usize, overlap: usize) -> Result<()> {
+    if chunk_size == 0 {
+        return Err(anyhow!("Chunk size must be greater than 0"));
+    }
+    if overlap >= chunk_size {
+        return Err(anyhow!("Chunk overlap ({}) must be smaller than chunk size ({})", overlap, chunk_size));
+    }
+    Ok(())
+}
+
+/// Incrementally splits a byte stream into overlapping character windows.
+///
+/// Bytes may arrive split at arbitrary points, including inside a UTF-8
+/// sequence, so only complete characters are buffered. Between pushes at most
+/// one window plus one partial character is held in memory.
+pub struct StreamingChunker {
+    chunk_size: usize,
+    overlap: usize,
+    buffer: String,
+    pending: Vec<u8>,
+}
+
+impl StreamingChunker {
+    /// `chunk_size` and `overlap` are in characters; overlap must be smaller
+    pub fn new(chunk_size: usize, overlap: usize) -> Result<Self> {
+        validate_window(chunk_size, overlap)?;
+        Ok(Self {
+            chunk_size,
+            overlap,
+            buffer: String::new(),
+            pending: Vec::new(),
+        })
+    }
+
+    /// Feed more bytes, returning every chunk that is now complete
+    pub fn push(&mut self, bytes: &[u8]) -> Result<Vec<String>> {
+        self.pending.extend_from_slice(bytes);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text.len(),
+            // An incomplete trailing sequence is kept until the next push
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => return Err(anyhow!("Upload is not valid UTF-8 text")),
+        };
+        let decoded: Vec<u8> = self.pending.drain(..valid).collect();
+        // Validated above
+        self.buffer.push_str(std::str::from_utf8(&decoded)?);
+
+        let mut chunks = Vec::new();
+        while let Some((end, _)) = self.buffer.char_indices().nth(self.chunk_size) {
+            chunks.push(self.buffer[..end].to_string());
+            let step = self.buffer.char_indices()
+                .nth(self.chunk_size - self.overlap)
+                .map(|(i, _)| i)
+                .unwrap_or(end);
+            self.buffer.drain(..step);
+        }
+        Ok(chunks)
+    }
+
+    /// Flush the final window.
+    ///
+    /// A window is only emitted once a character beyond it arrives, so the
+    /// remainder always holds text not yet emitted.
+    pub fn finish(self) -> Result<Option<String>> {
+        if !self.pending.is_empty() {
+            return Err(anyhow!("Upload ends with an incomplete UTF-8 sequence"));
+        }
+        if !self.buffer.trim().is_empty() {
+            Ok(Some(self.buffer))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk_all(text: &str, size: usize, overlap: usize, piece: usize) -> Vec<String> {
+        let mut chunker = StreamingChunker::new(size, overlap).unwrap();
+        let mut chunks = Vec::new();
+        for bytes in text.as_bytes().chunks(piece) {
+            chunks.extend(chunker.push(bytes).unwrap());
+        }
+        chunks.extend(chunker.finish().unwrap());
+        chunks
+    }
+
+    #[test]
+    fn test_chunks_overlap_and_flush_tail() {
+        assert_eq!(chunk_all("abcdefghij", 4, 1, 3), vec!["abcd", "defg", "ghij"]);
+        assert_eq!(chunk_all("abcdefghij", 4, 2, 100), vec!["abcd", "cdef", "efgh", "ghij"]);
+        assert_eq!(chunk_all("abc", 4, 1, 1), vec!["abc"]);
+        assert!(chunk_all("", 4, 1, 1).is_empty());
+    }
+
+    #[test]
+    fn test_multibyte_characters_split_across_pushes() {
+        // One-byte pushes split every multibyte sequence
+        let text = "héllo wörld 🦀🦀";
+        let chunks = chunk_all(text, 5, 0, 1);
+        assert_eq!(chunks.concat(), text);
+        assert!(chunks.iter().all(|c| c.chars().count() <= 5));
+
+        let mut chunker = StreamingChunker::new(5, 0).unwrap();
+        assert!(chunker.push(&[0xff]).is_err());
+        let mut truncated = StreamingChunker::new(5, 0).unwrap();
+        truncated.push(&"🦀".as_bytes()[..2]).unwrap();
+        assert!(truncated.finish().is_err());
+        assert!(StreamingChunker::new(4, 4).is_err());
+    }
+
+    fn texts(chunks: &[Chunk]) -> Vec<&str> {
+        chunks.iter().map(|c| c.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_fixed_size_chunker_matches_streaming_windows() {
+        for (text, size, overlap) in [("abcdefghij", 4, 1), ("abcdefghij", 4, 2), ("abcdefgh", 4, 1), ("abc", 4, 1)] {
+            let chunks = FixedSizeChunker::new(size, overlap).unwrap().chunk(text);
+            assert_eq!(texts(&chunks), chunk_all(text, size, overlap, 3));
+        }
+
+        // Offsets count characters, not bytes
+        let chunks = FixedSizeChunker::new(3, 1).unwrap().chunk("héllo🦀");
+        assert_eq!(texts(&chunks), vec!["hél", "llo", "o🦀"]);
+        assert_eq!((chunks[2].start, chunks[2].end), (4, 6));
+        assert!(FixedSizeChunker::new(2, 0).unwrap().chunk("").is_empty());
+        assert!(FixedSizeChunker::new(2, 2).is_err());
+    }
+
+    #[test]
+    fn test_sentence_chunker_keeps_sentences_whole() {
+        let text = "One two. Three four! Five?\nSix v1.2 seven.";
+        let chunks = SentenceChunker::new(20).unwrap().chunk(text);
+        assert_eq!(texts(&chunks), vec!["One two. Three four!", "Five?", "Six v1.2 seven."]);
+        for chunk in &chunks {
+            let slice: String = text.chars().skip(chunk.start).take(chunk.end - chunk.start).collect();
+            assert_eq!(slice, chunk.text);
+        }
+
+        // An overlong sentence is split at the limit, and a trailing
+        // fragment without a terminator still counts
+        let chunks = SentenceChunker::new(4).unwrap().chunk("abcdefghij. ok");
+        assert_eq!(texts(&chunks), vec!["abcd", "efgh", "ij.", "ok"]);
+        assert!(SentenceChunker::new(10).unwrap().chunk("  \n ").is_empty());
+        assert!(SentenceChunker::new(0).is_err());
+        assert!(chunker_by_name("paragraph", 10, 0).is_err());
+    }
+}
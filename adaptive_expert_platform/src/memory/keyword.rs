@@ -0,0 +1,167 @@
+/*!
+In-memory inverted index over fragment content, scoring with Okapi BM25.
+
+Backs the keyword half of [`SearchStrategy::Hybrid`](super::SearchStrategy),
+catching exact terms (names, codes, ids) that embedding similarity
+underweights. `Memory` keeps it in step with the fragments it adds and
+deletes, and with those a store reports evicting to make room; entries for
+fragments a store drops without reporting are pruned when a search turns
+them up.
+*/
+use std::collections::{HashMap, HashSet};
+
+use super::tenant_visible;
+
+/// Term frequency saturation
+const K1: f32 = 1.2;
+/// Document length normalization
+const B: f32 = 0.75;
+
+/// Lowercased alphanumeric runs of `text`; "ZX-4471" yields "zx" and "4471"
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+struct IndexedFragment {
+    /// Distinct terms, to find the postings on removal
+    terms: Vec<String>,
+    len: usize,
+    tenant: Option<String>,
+}
+
+/// Term postings of every indexed fragment
+#[derive(Default)]
+pub struct KeywordIndex {
+    /// term -> fragment id -> occurrences
+    postings: HashMap<String, HashMap<String, u32>>,
+    fragments: HashMap<String, IndexedFragment>,
+    total_len: usize,
+}
+
+impl KeywordIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index `content` under `id`, replacing anything indexed under it before
+    pub fn insert(&mut self, id: &str, content: &str, tenant: Option<&str>) {
+        self.remove(id);
+        let tokens = tokenize(content);
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for token in &tokens {
+            *counts.entry(token.clone()).or_insert(0) += 1;
+        }
+        for (term, count) in &counts {
+            self.postings.entry(term.clone()).or_default().insert(id.to_string(), *count);
+        }
+        self.total_len += tokens.len();
+        self.fragments.insert(id.to_string(), IndexedFragment {
+            terms: counts.into_keys().collect(),
+            len: tokens.len(),
+            tenant: tenant.map(str::to_string),
+        });
+    }
+
+    /// Drop `id` from the index, returning whether it was indexed
+    pub fn remove(&mut self, id: &str) -> bool {
+        let Some(fragment) = self.fragments.remove(id) else {
+            return false;
+        };
+        for term in &fragment.terms {
+            if let Some(posting) = self.postings.get_mut(term) {
+                posting.remove(id);
+                if posting.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+        self.total_len -= fragment.len;
+        true
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn len(&self) -> usize {
+        self.fragments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fragments.is_empty()
+    }
+
+    /// Up to `top_k` `(id, score)` pairs matching any query term, best first.
+    ///
+    /// With a `tenant`, fragments of other tenants are skipped before scoring,
+    /// as in vector search; term rarity is still measured over every fragment.
+    pub fn search(&self, query: &str, top_k: usize, tenant: Option<&str>) -> Vec<(String, f32)> {
+        if self.fragments.is_empty() {
+            return Vec::new();
+        }
+        let n = self.fragments.len() as f32;
+        let avg_len = (self.total_len as f32 / n).max(1.0);
+
+        let terms: HashSet<String> = tokenize(query).into_iter().collect();
+        let mut scores: HashMap<&str, f32> = HashMap::new();
+        for term in &terms {
+            let Some(posting) = self.postings.get(term) else { continue };
+            let df = posting.len() as f32;
+            let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+            for (id, &tf) in posting {
+                let fragment = &self.fragments[id];
+                if !tenant_visible(tenant, fragment.tenant.as_deref()) {
+                    continue;
+                }
+                let tf = tf as f32;
+                let norm = K1 * (1.0 - B + B * fragment.len as f32 / avg_len);
+                *scores.entry(id.as_str()).or_insert(0.0) += idf * tf * (K1 + 1.0) / (tf + norm);
+            }
+        }
+
+        let mut ranked: Vec<(String, f32)> = scores.into_iter()
+            .map(|(id, score)| (id.to_string(), score))
+            .collect();
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then_with(|| a.0.cmp(&b.0)));
+        ranked.truncate(top_k);
+        ranked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rare_terms_rank_first() {
+        let mut index = KeywordIndex::new();
+        index.insert("a", "the order shipped", None);
+        index.insert("b", "the order for ZX-4471 shipped", None);
+        index.insert("c", "the invoice was paid", None);
+
+        let results = index.search("status of zx-4471", 10, None);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, "b");
+
+        let results = index.search("order shipped", 10, None);
+        assert_eq!(results.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_remove_and_tenants() {
+        let mut index = KeywordIndex::new();
+        index.insert("a", "acme roadmap", Some("acme"));
+        index.insert("b", "globex roadmap", Some("globex"));
+
+        assert_eq!(index.search("roadmap", 10, Some("acme")).len(), 1);
+        assert_eq!(index.search("roadmap", 10, None).len(), 2);
+
+        assert!(index.remove("a"));
+        assert!(!index.remove("a"));
+        assert!(index.search("acme", 10, None).is_empty());
+        assert_eq!(index.len(), 1);
+    }
+}
@@ -3,17 +3,26 @@
 use anyhow::{anyhow, Result};
 use blake3::Hasher;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use tracing::{debug, warn, instrument};
 
 use crate::agent::Agent;
 use crate::memory::redis_store::{EmbeddingCache, CacheStats};
 
+/// Metadata key naming the tenant that owns a fragment
+pub const TENANT_METADATA_KEY: &str = "tenant";
+
 /// Memory fragment with enhanced metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryFragment {
+    /// Exports from before fragments had ids get a fresh one on import
+    #[serde(default = "new_fragment_id")]
+    pub id: String,
     pub content: String,
     pub embedding: Vec<f32>,
     pub metadata: HashMap<String, serde_json::Value>,
@@ -25,13 +34,11 @@ pub struct MemoryFragment {
 impl MemoryFragment {
     pub fn new(content: String, embedding: Vec<f32>) -> Self {
         Self {
+            id: new_fragment_id(),
             content,
             embedding,
             metadata: HashMap::new(),
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs(),
+            timestamp: unix_now(),
             source: "manual".to_string(),
             tags: Vec::new(),
         }
@@ -51,18 +58,138 @@ impl MemoryFragment {
         self.tags = tags;
         self
     }
+
+    /// Restrict the fragment to searches made on behalf of `tenant`
+    pub fn with_tenant(mut self, tenant: &str) -> Self {
+        self.metadata.insert(TENANT_METADATA_KEY.to_string(), serde_json::json!(tenant));
+        self
+    }
+
+    /// Tenant owning the fragment; `None` for fragments shared by all tenants
+    pub fn tenant(&self) -> Option<&str> {
+        self.metadata.get(TENANT_METADATA_KEY).and_then(|v| v.as_str())
+    }
+
+    /// Whether a caller acting for `tenant` may see this fragment. Callers
+    /// without a tenant see everything, and shared fragments are visible to all.
+    pub fn visible_to(&self, tenant: Option<&str>) -> bool {
+        tenant_visible(tenant, self.tenant())
+    }
+}
+
+/// Visibility rule behind [`MemoryFragment::visible_to`], for indexes that
+/// keep the owning tenant apart from the fragment
+pub(crate) fn tenant_visible(tenant: Option<&str>, owner: Option<&str>) -> bool {
+    match (tenant, owner) {
+        (Some(tenant), Some(owner)) => tenant == owner,
+        _ => true,
+    }
 }
 
-/// Enhanced memory system with real embeddings and improved performance
+/// How `search_memory_with_strategy` orders the first-pass candidates
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SearchStrategy {
+    /// Top-k by embedding similarity alone
+    Similarity,
+    /// Reorder candidates with the reranker agent
+    #[default]
+    Rerank,
+    /// Maximal marginal relevance: `lambda` in `[0, 1]` weighs similarity to
+    /// the query against similarity to results already selected, so lower
+    /// values favour diverse results over near-duplicates
+    Mmr { lambda: f32 },
+    /// Merge BM25 keyword matches with the similarity candidates before
+    /// reranking; `keyword_weight` in `[0, 1]` is the keyword score's share
+    /// of the combined score, each side scaled by its best match
+    Hybrid { keyword_weight: f32 },
+}
+
+/// Keyword share of the combined score when a hybrid search doesn't set one
+pub const DEFAULT_KEYWORD_WEIGHT: f32 = 0.3;
+
+/// Candidates fetched per requested result when selecting with MMR
+const MMR_POOL_FACTOR: usize = 4;
+
+/// Embeddings computed concurrently per batch by [`Memory::prefetch_embeddings`]
+const PREFETCH_BATCH_SIZE: usize = 16;
+
+/// Default limit on a single embedding or rerank call
+pub const DEFAULT_EMBEDDING_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Returned when the embedding or reranker agent does not answer in time
+#[derive(Debug)]
+pub struct EmbeddingTimeoutError {
+    /// `"embedding"` or `"rerank"`
+    pub task: &'static str,
+    pub timeout: Duration,
+}
+
+impl fmt::Display for EmbeddingTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Memory {} request timed out after {:?}", self.task, self.timeout)
+    }
+}
+
+impl std::error::Error for EmbeddingTimeoutError {}
+
+/// View of a memory given to its embedding and reranker agents.
+///
+/// It offers reads only: a model has no way to embed, search or store
+/// through it, so it cannot recurse into the memory that called it.
+#[derive(Clone, Copy)]
+pub struct ReadOnlyMemory<'a> {
+    memory: &'a Memory,
+}
+
+impl ReadOnlyMemory<'_> {
+    pub async fn get_kv(&self, key: &str) -> Result<Option<serde_json::Value>> {
+        self.memory.get_kv(key).await
+    }
+
+    pub async fn get_fragment_count(&self) -> usize {
+        self.memory.get_fragment_count().await
+    }
+
+    pub fn fragment_version(&self) -> u64 {
+        self.memory.fragment_version()
+    }
+
+    pub fn embedding_dim(&self) -> usize {
+        self.memory.embedding_dim
+    }
+
+    pub fn similarity_threshold(&self) -> f32 {
+        self.memory.similarity_threshold()
+    }
+}
+
+/// Enhanced memory system with real embeddings and improved performance.
+///
+/// The embedding and reranker agents are called through
+/// [`Agent::handle_model`] with a [`ReadOnlyMemory`] of this memory.
 pub struct Memory {
     embedding_agent: Arc<dyn Agent>,
     reranker_agent: Arc<dyn Agent>,
     cache: Arc<dyn EmbeddingCache>,
-    fragments: RwLock<Vec<MemoryFragment>>,
+    store: Box<dyn VectorStore>,
     kv_store: RwLock<HashMap<String, serde_json::Value>>,
+    /// Keyword postings of the fragments added through this handle
+    keywords: parking_lot::RwLock<KeywordIndex>,
     max_fragments: usize,
     embedding_dim: usize,
-    similarity_threshold: f32,
+    /// Default search threshold as `f32` bits; changeable at runtime
+    similarity_threshold: AtomicU32,
+    normalize_embeddings: bool,
+    /// Limit in milliseconds on each embedding and rerank call; changeable at runtime
+    embedding_timeout_ms: AtomicU64,
+    /// Splits added content into several fragments; `None` stores it whole
+    chunker: Option<Arc<dyn Chunker>>,
+    /// Default half-life of the search score's time decay; `None` disables it
+    recency_half_life: Option<Duration>,
+    /// Default time limit of a search; `None` searches exhaustively
+    search_budget: Option<Duration>,
+    /// Bumped whenever the fragment set changes
+    version: AtomicU64,
 }
 
 impl Memory {
@@ -75,16 +202,34 @@ impl Memory {
             embedding_agent,
             reranker_agent,
             cache,
-            fragments: RwLock::new(Vec::new()),
+            store: Box::new(InMemoryVectorStore::new(10_000)),
             kv_store: RwLock::new(HashMap::new()),
+            keywords: parking_lot::RwLock::new(KeywordIndex::new()),
             max_fragments: 10_000,
             embedding_dim: 384, // Default embedding dimension
-            similarity_threshold: 0.1,
+            similarity_threshold: AtomicU32::new(0.1f32.to_bits()),
+            normalize_embeddings: false,
+            embedding_timeout_ms: AtomicU64::new(DEFAULT_EMBEDDING_TIMEOUT.as_millis() as u64),
+            chunker: None,
+            recency_half_life: None,
+            search_budget: None,
+            version: AtomicU64::new(0),
         }
     }
 
+    /// Set the capacity of the vector store. External stores that manage
+    /// their own capacity keep it.
     pub fn with_max_fragments(mut self, max_fragments: usize) -> Self {
         self.max_fragments = max_fragments;
+        if !self.store.set_capacity(max_fragments) {
+            debug!("Vector store manages its own capacity, ignoring max_fragments");
+        }
+        self
+    }
+
+    /// Use an external vector store backend instead of the in-memory default
+    pub fn with_vector_store(mut self, store: Box<dyn VectorStore>) -> Self {
+        self.store = store;
         self
     }
 
@@ -93,14 +238,233 @@ impl Memory {
         self
     }
 
-    pub fn with_similarity_threshold(mut self, threshold: f32) -> Self {
-        self.similarity_threshold = threshold;
+    pub fn with_similarity_threshold(self, threshold: f32) -> Self {
+        self.set_similarity_threshold(threshold);
+        self
+    }
+
+    /// Fail embedding and rerank calls that take longer than `timeout` with
+    /// [`EmbeddingTimeoutError`], so a hung model cannot block memory operations
+    pub fn with_embedding_timeout(self, timeout: Duration) -> Self {
+        self.set_embedding_timeout(timeout);
         self
     }
 
-    /// Adds a fragment with real embedding generation
+    /// Threshold applied to searches that don't pass their own
+    pub fn similarity_threshold(&self) -> f32 {
+        f32::from_bits(self.similarity_threshold.load(Ordering::Relaxed))
+    }
+
+    /// Change the default search threshold; searches already running keep the old one
+    pub fn set_similarity_threshold(&self, threshold: f32) {
+        self.similarity_threshold.store(threshold.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn embedding_timeout(&self) -> Duration {
+        Duration::from_millis(self.embedding_timeout_ms.load(Ordering::Relaxed))
+    }
+
+    /// Change the embedding and rerank call limit for calls started from now on
+    pub fn set_embedding_timeout(&self, timeout: Duration) {
+        self.embedding_timeout_ms.store(timeout.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// L2-normalize embeddings before storing and searching, so the built-in
+    /// store can score with a dot product instead of full cosine similarity.
+    ///
+    /// Mixing normalized and unnormalized fragments in one store is
+    /// unsupported, so enable this before any fragments are added.
+    pub fn with_normalize_embeddings(mut self, normalize: bool) -> Self {
+        self.normalize_embeddings = normalize;
+        self.store.set_normalized(normalize);
+        self
+    }
+
+    /// Split added content into fragments with `chunker`.
+    ///
+    /// Chunks of one input share a `document_id` metadata entry so search can
+    /// return the relevant part of a long document. Content the chunker
+    /// leaves in one piece is stored as a single fragment.
+    pub fn with_chunker(mut self, chunker: Arc<dyn Chunker>) -> Self {
+        self.chunker = Some(chunker);
+        self
+    }
+
+    /// Split content longer than `max_chars` characters into fragments of at
+    /// most that size, with `overlap` characters repeated between neighbours.
+    /// `overlap` must be smaller than `max_chars`.
+    pub fn with_auto_chunking(self, max_chars: usize, overlap: usize) -> Result<Self> {
+        Ok(self.with_chunker(Arc::new(FixedSizeChunker::new(max_chars, overlap)?)))
+    }
+
+    /// Decay search scores by fragment age, halving them every `half_life`, so
+    /// newer fragments outrank older ones of equal similarity. `None` or a
+    /// zero half-life disables decay.
+    pub fn with_recency_half_life(mut self, half_life: Option<Duration>) -> Self {
+        self.recency_half_life = half_life.filter(|half_life| !half_life.is_zero());
+        self
+    }
+
+    /// Default half-life used by searches that don't override it
+    pub fn recency_half_life(&self) -> Option<Duration> {
+        self.recency_half_life
+    }
+
+    /// Bound how long a search may take, trading completeness for latency.
+    ///
+    /// Once the budget runs out during the similarity pass, the best matches
+    /// scanned so far are returned without reranking; see
+    /// [`search_memory_with_budget`](Self::search_memory_with_budget).
+    /// `None` or zero searches exhaustively.
+    pub fn with_search_budget(mut self, budget: Option<Duration>) -> Self {
+        self.search_budget = budget.filter(|budget| !budget.is_zero());
+        self
+    }
+
+    /// Default time limit used by searches that don't override it
+    pub fn search_budget(&self) -> Option<Duration> {
+        self.search_budget
+    }
+
+    /// Store every input as a single fragment, for callers that pre-chunk
+    pub fn without_auto_chunking(mut self) -> Self {
+        self.chunker = None;
+        self
+    }
+
+    /// Adds a fragment with real embedding generation, chunking long content
+    /// when auto-chunking is enabled
     #[instrument(skip(self))]
     pub async fn add_memory(&self, content: &str) -> Result<()> {
+        self.add_content(content, None, None).await.map(|_| ())
+    }
+
+    /// Adds a fragment tagged with where its content came from, e.g. an uploaded file name
+    #[instrument(skip(self, content))]
+    pub async fn add_memory_from_source(&self, content: &str, source: &str) -> Result<()> {
+        self.add_content(content, Some(source), None).await.map(|_| ())
+    }
+
+    /// Adds content owned by `tenant`, so only that tenant's searches and
+    /// unscoped ones find it; `None` adds it shared. `chunker` overrides the
+    /// configured chunker. Returns the ids of the fragments created.
+    #[instrument(skip(self, content, chunker))]
+    pub async fn add_tenant_memory(
+        &self,
+        content: &str,
+        source: Option<&str>,
+        tenant: Option<&str>,
+        chunker: Option<&dyn Chunker>,
+    ) -> Result<Vec<String>> {
+        match chunker {
+            Some(chunker) => self.add_chunks(content, source, tenant, chunker.chunk(content)).await,
+            None => self.add_content(content, source, tenant).await,
+        }
+    }
+
+    /// Adds content split by `chunker` instead of the configured chunker,
+    /// returning how many fragments were created
+    #[instrument(skip(self, content, chunker))]
+    pub async fn add_memory_with_chunker(
+        &self,
+        content: &str,
+        source: Option<&str>,
+        chunker: &dyn Chunker,
+    ) -> Result<usize> {
+        self.add_chunks(content, source, None, chunker.chunk(content)).await.map(|ids| ids.len())
+    }
+
+    async fn add_content(&self, content: &str, source: Option<&str>, tenant: Option<&str>) -> Result<Vec<String>> {
+        let chunks = match &self.chunker {
+            Some(chunker) => chunker.chunk(content),
+            None => Vec::new(),
+        };
+        self.add_chunks(content, source, tenant, chunks).await
+    }
+
+    async fn add_chunks(
+        &self,
+        content: &str,
+        source: Option<&str>,
+        tenant: Option<&str>,
+        mut chunks: Vec<Chunk>,
+    ) -> Result<Vec<String>> {
+        // Refused before any embedding is computed for it
+        if self.max_fragments == 0 {
+            return Err(anyhow!("Memory has no capacity for fragments (max_fragments is 0)"));
+        }
+        chunks.retain(|chunk| !chunk.text.trim().is_empty());
+        let finish = |mut fragment: MemoryFragment| {
+            if let Some(source) = source {
+                fragment = fragment.with_source(source.to_owned());
+            }
+            if let Some(tenant) = tenant {
+                fragment = fragment.with_tenant(tenant);
+            }
+            fragment
+        };
+        if chunks.len() <= 1 {
+            let embedding = self.embed_content(content).await?;
+            let id = self.insert_fragment(finish(MemoryFragment::new(content.to_owned(), embedding))).await?;
+            return Ok(vec![id]);
+        }
+
+        let document_id = uuid::Uuid::new_v4().to_string();
+        debug!("Splitting content into {} chunks for document {}", chunks.len(), document_id);
+        let mut ids = Vec::with_capacity(chunks.len());
+        for (index, chunk) in chunks.iter().enumerate() {
+            let embedding = match self.embed_content(&chunk.text).await {
+                Ok(embedding) => embedding,
+                Err(e) => {
+                    self.discard_fragments(&ids).await;
+                    return Err(e);
+                }
+            };
+            let metadata = HashMap::from([
+                ("document_id".to_string(), serde_json::json!(document_id)),
+                ("chunk_index".to_string(), serde_json::json!(index)),
+                ("char_start".to_string(), serde_json::json!(chunk.start)),
+                ("char_end".to_string(), serde_json::json!(chunk.end)),
+            ]);
+            let fragment = MemoryFragment::new(chunk.text.clone(), embedding).with_metadata(metadata);
+            match self.insert_fragment(finish(fragment)).await {
+                Ok(id) => ids.push(id),
+                Err(e) => {
+                    self.discard_fragments(&ids).await;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Delete the fragments of a partly added document, so a failed add
+    /// leaves no part of it searchable
+    pub async fn discard_fragments(&self, ids: &[String]) {
+        for id in ids {
+            if let Err(e) = self.delete_memory(id).await {
+                warn!("Failed to remove fragment {} of an incomplete add: {}", id, e);
+            }
+        }
+    }
+
+    /// Store a fragment and index its content for keyword search, dropping
+    /// fragments the store evicted to make room from the index
+    async fn insert_fragment(&self, fragment: MemoryFragment) -> Result<String> {
+        let (content, tenant) = (fragment.content.clone(), fragment.tenant().map(str::to_string));
+        let added = self.store.add(fragment).await?;
+        let mut keywords = self.keywords.write();
+        for id in &added.evicted {
+            keywords.remove(id);
+        }
+        keywords.insert(&added.id, &content, tenant.as_deref());
+        drop(keywords);
+        self.bump_version();
+        Ok(added.id)
+    }
+
+    /// Embed content for storage, using the cache when possible
+    async fn embed_content(&self, content: &str) -> Result<Vec<f32>> {
         if content.trim().is_empty() {
             return Err(anyhow!("Cannot add empty content to memory"));
         }
@@ -111,50 +475,313 @@ impl Memory {
             vec
         } else {
             debug!("Computing new embedding for content");
+            let vec = self.compute_embedding(content).await?;
+            self.cache.set(&key, &vec).await?;
+            vec
+        };
 
-            // Generate real embedding using the embedding agent
-            let embedding_input = serde_json::json!({
-                "text": content,
-                "task": "embedding"
-            });
+        Ok(self.stored_embedding(embedding))
+    }
 
-            let embedding_result = self.embedding_agent
-                .handle(embedding_input, Arc::new(self.clone_dummy_memory()))
-                .await?;
+    /// Raw embedding of content from the embedding agent, bypassing the cache
+    async fn compute_embedding(&self, content: &str) -> Result<Vec<f32>> {
+        let embedding_input = serde_json::json!({
+            "text": content,
+            "task": "embedding"
+        });
 
-            let vec: Vec<f32> = serde_json::from_str(&embedding_result)
-                .map_err(|e| anyhow!("Failed to parse embedding JSON: {}", e))?;
+        let embedding_result = self.call_model(&self.embedding_agent, embedding_input, "embedding").await?;
+
+        let vec: Vec<f32> = serde_json::from_str(&embedding_result)
+            .map_err(|e| anyhow!("Failed to parse embedding JSON: {}", e))?;
 
-            if vec.is_empty() {
-                return Err(anyhow!("Embedding agent returned empty vector"));
+        if vec.is_empty() {
+            return Err(anyhow!("Embedding agent returned empty vector"));
+        }
+
+        if vec.len() != self.embedding_dim {
+            warn!("Embedding dimension mismatch: expected {}, got {}", self.embedding_dim, vec.len());
+        }
+
+        Ok(vec)
+    }
+
+    /// The form of a raw embedding kept on fragments. The cache holds raw
+    /// embeddings; normalization is applied on use.
+    fn stored_embedding(&self, mut embedding: Vec<f32>) -> Vec<f32> {
+        if self.normalize_embeddings {
+            l2_normalize(&mut embedding);
+        }
+        embedding
+    }
+
+    /// Call the embedding or reranker agent, bounded by the embedding timeout
+    async fn call_model(&self, agent: &Arc<dyn Agent>, input: serde_json::Value, task: &'static str) -> Result<String> {
+        let call = agent.handle_model(input, ReadOnlyMemory { memory: self });
+        let timeout = self.embedding_timeout();
+        match tokio::time::timeout(timeout, call).await {
+            Ok(result) => result,
+            Err(_) => {
+                warn!("Memory {} call to '{}' timed out after {:?}", task, agent.name(), timeout);
+                Err(EmbeddingTimeoutError { task, timeout }.into())
             }
+        }
+    }
 
-            if vec.len() != self.embedding_dim {
-                warn!("Embedding dimension mismatch: expected {}, got {}", self.embedding_dim, vec.len());
+    /// Delete a fragment by id, returning whether it existed
+    pub async fn delete_memory(&self, id: &str) -> Result<bool> {
+        let deleted = self.store.delete(id).await?;
+        self.keywords.write().remove(id);
+        if deleted {
+            self.bump_version();
+        }
+        Ok(deleted)
+    }
+
+    /// Version of the fragment set, changing on every add, delete and clear.
+    ///
+    /// Search results for a given query are stable while this is unchanged,
+    /// so it can be used to validate cached results.
+    pub fn fragment_version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    fn bump_version(&self) {
+        self.version.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Check every fragment's embedding against the embedding cache and fix
+    /// whatever has drifted apart.
+    ///
+    /// A fragment whose content has no cached embedding, or whose stored
+    /// embedding disagrees with the cached one, is re-embedded by the
+    /// embedding agent; the result is written to both the cache and the
+    /// fragment. Cache entries that belong to no fragment are dropped, which
+    /// includes cached query embeddings. Fragments that fail to re-embed are
+    /// left as they are and listed in the report.
+    ///
+    /// Safe to run while memory is in use: it works from a snapshot, takes no
+    /// lock across model calls, and skips fragments deleted in the meantime.
+    /// A cache entry written by an add that races the snapshot may be dropped;
+    /// that only costs a recomputation on the next use.
+    pub async fn verify_and_repair(&self) -> Result<RepairReport> {
+        // Keys are listed before fragments, so every fragment added since has
+        // its cache entry in neither snapshot or in both
+        let cached_keys = self.cache.keys().await?;
+        let fragments = self.store.list().await?;
+
+        let mut report = RepairReport::default();
+        let mut live_keys = HashSet::with_capacity(fragments.len());
+        for fragment in fragments {
+            report.fragments_checked += 1;
+            let key = cache_key(&fragment.content);
+            live_keys.insert(key.clone());
+
+            let cached = self.cache.get(&key).await?;
+            let consistent = cached.as_ref().map_or(false, |raw| {
+                embeddings_match(&self.stored_embedding(raw.clone()), &fragment.embedding)
+            });
+            if consistent {
+                continue;
             }
 
-            self.cache.set(&key, &vec).await?;
-            vec
-        };
+            let raw = match self.compute_embedding(&fragment.content).await {
+                Ok(raw) => raw,
+                Err(e) => {
+                    warn!("Could not re-embed memory fragment {}: {}", fragment.id, e);
+                    report.failed_fragments.push(fragment.id);
+                    continue;
+                }
+            };
+            self.cache.set(&key, &raw).await?;
+            if cached.is_some() {
+                report.mismatched_embeddings_recomputed += 1;
+            } else {
+                report.missing_embeddings_recomputed += 1;
+            }
 
-        let mut fragments = self.fragments.write().await;
+            let embedding = self.stored_embedding(raw);
+            if !embeddings_match(&embedding, &fragment.embedding)
+                && self.store.update_embedding(&fragment.id, embedding).await?
+            {
+                report.fragments_updated += 1;
+                self.bump_version();
+            }
+        }
 
-        // Enforce max fragments limit with LRU eviction
-        if fragments.len() >= self.max_fragments {
-            debug!("Memory at capacity, removing oldest fragment");
-            fragments.remove(0); // Remove oldest
+        for key in cached_keys {
+            // Only entries this memory writes; a shared cache may hold others
+            if key.starts_with(CACHE_KEY_PREFIX) && !live_keys.contains(&key) {
+                self.cache.delete(&key).await?;
+                report.orphaned_cache_entries_removed += 1;
+            }
         }
 
-        fragments.push(MemoryFragment::new(content.to_owned(), embedding));
-        debug!("Added memory fragment, total fragments: {}", fragments.len());
-        Ok(())
+        debug!("Memory repair finished: {:?}", report);
+        Ok(report)
+    }
+
+    /// Compute and cache embeddings for anticipated queries or content, so
+    /// later searches and adds for the same text skip the embedding agent.
+    ///
+    /// Duplicates and blank texts are ignored. Texts missing from the cache
+    /// are embedded in batches of concurrent calls, in input order; the first
+    /// failure aborts the run, keeping whatever was cached before it.
+    #[instrument(skip(self, texts), fields(texts = texts.len()))]
+    pub async fn prefetch_embeddings(&self, texts: &[String]) -> Result<PrefetchReport> {
+        let mut seen = HashSet::new();
+        let mut report = PrefetchReport::default();
+        let mut missing = Vec::new();
+        for text in texts {
+            if text.trim().is_empty() || !seen.insert(text.as_str()) {
+                continue;
+            }
+            report.requested += 1;
+            if self.cache.get(&cache_key(text)).await?.is_some() {
+                report.already_cached += 1;
+            } else {
+                missing.push(text.as_str());
+            }
+        }
+
+        for batch in missing.chunks(PREFETCH_BATCH_SIZE) {
+            let embeddings = futures::future::join_all(batch.iter().map(|text| self.compute_embedding(text))).await;
+            for (text, embedding) in batch.iter().zip(embeddings) {
+                self.cache.set(&cache_key(text), &embedding?).await?;
+                report.computed += 1;
+            }
+        }
+
+        debug!("Embedding prefetch finished: {:?}", report);
+        Ok(report)
+    }
+
+    /// Export every stored fragment, oldest first
+    pub async fn export_fragments(&self) -> Result<Vec<MemoryFragment>> {
+        self.store.list().await
+    }
+
+    /// Export the fragments a caller acting for `tenant` may see, oldest first
+    pub async fn export_tenant_fragments(&self, tenant: Option<&str>) -> Result<Vec<MemoryFragment>> {
+        let mut fragments = self.store.list().await?;
+        fragments.retain(|fragment| fragment.visible_to(tenant));
+        Ok(fragments)
     }
 
     /// Enhanced memory search with reranking
-    #[instrument(skip(self))]
     pub async fn search_memory(&self, query: &str, top_k: usize) -> Result<Vec<String>> {
+        self.search_memory_with_threshold(query, top_k, None).await
+    }
+
+    /// Search with a similarity threshold overriding the configured default
+    /// for this call only; the override must lie in `[-1.0, 1.0]`
+    pub async fn search_memory_with_threshold(
+        &self,
+        query: &str,
+        top_k: usize,
+        threshold: Option<f32>,
+    ) -> Result<Vec<String>> {
+        self.search_memory_with_strategy(query, top_k, threshold, SearchStrategy::Rerank).await
+    }
+
+    /// Search with an explicit candidate ordering strategy
+    pub async fn search_memory_with_strategy(
+        &self,
+        query: &str,
+        top_k: usize,
+        threshold: Option<f32>,
+        strategy: SearchStrategy,
+    ) -> Result<Vec<String>> {
+        self.search_memory_with_recency(query, top_k, threshold, strategy, self.recency_half_life).await
+    }
+
+    /// Search with an explicit time-decay half-life instead of the configured
+    /// one; `None` or zero ranks by relevance alone.
+    ///
+    /// Similarity and MMR scores are multiplied by `0.5^(age / half_life)`.
+    /// Reranked results, which carry no score, are decayed by rank position.
+    pub async fn search_memory_with_recency(
+        &self,
+        query: &str,
+        top_k: usize,
+        threshold: Option<f32>,
+        strategy: SearchStrategy,
+        half_life: Option<Duration>,
+    ) -> Result<Vec<String>> {
+        self.search_memory_with_tenant(query, top_k, threshold, strategy, half_life, None).await
+    }
+
+    /// Search only the fragments a caller acting for `tenant` may see (see
+    /// [`MemoryFragment::visible_to`]). Others are excluded before similarity
+    /// scoring, so they neither appear in nor displace results.
+    pub async fn search_memory_with_tenant(
+        &self,
+        query: &str,
+        top_k: usize,
+        threshold: Option<f32>,
+        strategy: SearchStrategy,
+        half_life: Option<Duration>,
+        tenant: Option<&str>,
+    ) -> Result<Vec<String>> {
+        let search = self.search_memory_with_budget(query, top_k, threshold, strategy, half_life, tenant, self.search_budget).await?;
+        Ok(search.results)
+    }
+
+    /// Search with an explicit time limit instead of the configured one;
+    /// `None` or zero searches exhaustively.
+    ///
+    /// The deadline counts from the start of the call and is checked
+    /// periodically while the store scans. Once it passes, the best matches
+    /// found so far are returned by similarity, without reranking, and
+    /// flagged `truncated`. Stores that cannot stop early scan in full but
+    /// still skip the reranker when the deadline has passed.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn search_memory_with_budget(
+        &self,
+        query: &str,
+        top_k: usize,
+        threshold: Option<f32>,
+        strategy: SearchStrategy,
+        half_life: Option<Duration>,
+        tenant: Option<&str>,
+        budget: Option<Duration>,
+    ) -> Result<SearchResults> {
+        let explanation = self.explain_search(query, top_k, threshold, strategy, half_life, tenant, budget).await?;
+        Ok(SearchResults {
+            results: explanation.results.into_iter().map(|result| result.content).collect(),
+            truncated: explanation.truncated,
+        })
+    }
+
+    /// Run a search like [`search_memory_with_budget`](Self::search_memory_with_budget),
+    /// returning each result's fragment and how it was scored and filtered
+    /// instead of just its content.
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip(self))]
+    pub async fn explain_search(
+        &self,
+        query: &str,
+        top_k: usize,
+        threshold: Option<f32>,
+        strategy: SearchStrategy,
+        half_life: Option<Duration>,
+        tenant: Option<&str>,
+        budget: Option<Duration>,
+    ) -> Result<SearchExplanation> {
+        let deadline = budget.filter(|budget| !budget.is_zero()).map(|budget| Instant::now() + budget);
+        let half_life = half_life.filter(|half_life| !half_life.is_zero());
+        if let Some(threshold) = threshold {
+            validate_similarity_threshold(threshold)?;
+        }
+        match strategy {
+            SearchStrategy::Mmr { lambda } => validate_mmr_lambda(lambda)?,
+            SearchStrategy::Hybrid { keyword_weight } => validate_keyword_weight(keyword_weight)?,
+            _ => {}
+        }
+        let threshold = threshold.unwrap_or_else(|| self.similarity_threshold());
+        let mut explanation = SearchExplanation { threshold, candidates: 0, truncated: false, results: Vec::new() };
         if query.trim().is_empty() {
-            return Ok(vec![]);
+            return Ok(explanation);
         }
 
         let key = cache_key(query);
@@ -167,9 +794,7 @@ impl Memory {
                 "task": "embedding"
             });
 
-            let embedding_result = self.embedding_agent
-                .handle(query_input, Arc::new(self.clone_dummy_memory()))
-                .await?;
+            let embedding_result = self.call_model(&self.embedding_agent, query_input, "embedding").await?;
 
             let vec: Vec<f32> = serde_json::from_str(&embedding_result)
                 .map_err(|e| anyhow!("Failed to parse query embedding JSON: {}", e))?;
@@ -178,59 +803,206 @@ impl Memory {
             vec
         };
 
-        let frags = self.fragments.read().await;
-        if frags.is_empty() {
-            debug!("No fragments in memory for search");
-            return Ok(vec![]);
+        let mut q_emb = q_emb;
+        if self.normalize_embeddings {
+            l2_normalize(&mut q_emb);
         }
 
-        // First pass: vector similarity search
-        let mut scored: Vec<(f32, &MemoryFragment)> = frags
-            .iter()
-            .map(|f| (cosine(&q_emb, &f.embedding), f))
-            .filter(|(score, _)| *score > self.similarity_threshold)
+        // First pass: vector similarity search, fetching extra candidates for
+        // the second pass, and for slightly less similar but newer fragments
+        let pool_size = match strategy {
+            SearchStrategy::Similarity if half_life.is_some() => top_k * 2,
+            SearchStrategy::Similarity => top_k,
+            SearchStrategy::Rerank | SearchStrategy::Hybrid { .. } => top_k * 2,
+            SearchStrategy::Mmr { .. } => top_k * MMR_POOL_FACTOR,
+        };
+        let mut scored = match deadline {
+            Some(deadline) => {
+                let (scored, truncated) = self.store.search_until(&q_emb, pool_size, threshold, tenant, deadline).await?;
+                // A store that scanned in full may still have used up the budget
+                explanation.truncated = truncated || Instant::now() >= deadline;
+                scored
+            }
+            None => self.store.search(&q_emb, pool_size, threshold, tenant).await?,
+        };
+        if explanation.truncated {
+            debug!("Memory search ran out of its time budget; skipping the reranker");
+        }
+        let mut breakdowns: HashMap<String, ScoreBreakdown> = scored.iter()
+            .map(|s| (s.fragment.id.clone(), ScoreBreakdown { similarity: Some(s.score), ..Default::default() }))
             .collect();
+        if let SearchStrategy::Hybrid { keyword_weight } = strategy {
+            scored = self.merge_keyword_matches(query, scored, pool_size, keyword_weight, tenant, &mut breakdowns).await?;
+        }
+        explanation.candidates = scored.len();
+        // Reranked strategies carry no score past the reranker; out of time,
+        // they keep the similarity order instead
+        let reranked = matches!(strategy, SearchStrategy::Rerank | SearchStrategy::Hybrid { .. })
+            && !explanation.truncated;
+
+        if scored.is_empty() {
+            debug!("No fragments matched memory search");
+            return Ok(explanation);
+        }
+
+        let now = unix_now();
+        if let Some(half_life) = half_life.filter(|_| !reranked) {
+            for candidate in &mut scored {
+                let decay = recency_decay(candidate.fragment.timestamp, now, half_life);
+                candidate.score *= decay;
+                breakdowns.entry(candidate.fragment.id.clone()).or_default().recency_factor = Some(decay);
+            }
+            scored.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        }
 
-        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        let (selected, selection_filter) = match strategy {
+            SearchStrategy::Mmr { lambda } => (mmr_select(scored, top_k, lambda), Some("mmr_diversity")),
+            _ if reranked => {
+                let ranked = self.rerank(query, scored, half_life, now, &mut breakdowns).await?;
+                (ranked.into_iter().take(top_k).collect(), Some("rerank"))
+            }
+            _ => (scored.into_iter().take(top_k).collect(), None),
+        };
 
-        // Take top candidates for reranking
-        let candidates: Vec<String> = scored
-            .into_iter()
-            .take(top_k * 2) // Get more candidates for reranking
-            .map(|(_, fragment)| fragment.content.clone())
+        explanation.results = selected.into_iter().enumerate()
+            .map(|(rank, s)| {
+                let mut scores = breakdowns.remove(&s.fragment.id).unwrap_or_default();
+                scores.final_score = Some(s.score);
+                let filters_passed = [
+                    tenant.map(|_| "tenant"),
+                    scores.similarity.map(|_| "similarity_threshold"),
+                    scores.keyword_score.map(|_| "keyword_match"),
+                    selection_filter,
+                    Some("top_k"),
+                ].into_iter().flatten().map(str::to_string).collect();
+                let tenant = s.fragment.tenant().map(str::to_string);
+                ExplainedResult {
+                    rank,
+                    id: s.fragment.id,
+                    content: s.fragment.content,
+                    content_truncated: false,
+                    source: s.fragment.source,
+                    tags: s.fragment.tags,
+                    timestamp: s.fragment.timestamp,
+                    tenant,
+                    scores,
+                    filters_passed,
+                }
+            })
             .collect();
+        debug!("Memory search returned {} results", explanation.results.len());
+        Ok(explanation)
+    }
 
-        if candidates.is_empty() {
-            return Ok(vec![]);
-        }
-
-        // Second pass: rerank using reranker agent
+    /// Second pass: order `candidates` with the reranker agent.
+    ///
+    /// Each is scored by rank, 1.0 for the first down to 1/n for the last,
+    /// then decayed by age if `half_life` is set. Entries the reranker
+    /// returns that are not candidate contents are dropped.
+    async fn rerank(
+        &self,
+        query: &str,
+        candidates: Vec<ScoredFragment>,
+        half_life: Option<Duration>,
+        now: u64,
+        breakdowns: &mut HashMap<String, ScoreBreakdown>,
+    ) -> Result<Vec<ScoredFragment>> {
+        let contents: Vec<&str> = candidates.iter().map(|s| s.fragment.content.as_str()).collect();
         let rerank_input = serde_json::json!({
             "query": query,
-            "candidates": candidates,
+            "candidates": contents,
             "task": "rerank"
         });
 
-        let rerank_result = self.reranker_agent
-            .handle(rerank_input, Arc::new(self.clone_dummy_memory()))
-            .await?;
+        let rerank_result = self.call_model(&self.reranker_agent, rerank_input, "rerank").await?;
 
         // Parse reranked results
-        let reranked: Vec<String> = serde_json::from_str(&rerank_result)
+        let order: Vec<String> = serde_json::from_str(&rerank_result)
             .map_err(|e| anyhow!("Failed to parse rerank result: {}", e))?;
 
-        let final_results = reranked.into_iter().take(top_k).collect();
-        debug!("Memory search returned {} results", final_results.len());
-        Ok(final_results)
+        // Fragments may share content, so match each entry to a candidate not yet taken
+        let mut pool: Vec<Option<ScoredFragment>> = candidates.into_iter().map(Some).collect();
+        let n = order.len() as f32;
+        let mut ranked = Vec::with_capacity(order.len());
+        for (position, content) in order.into_iter().enumerate() {
+            let Some(mut candidate) = pool.iter_mut()
+                .find(|slot| slot.as_ref().is_some_and(|s| s.fragment.content == content))
+                .and_then(Option::take)
+            else {
+                debug!("Ignoring reranker output that matches no candidate");
+                continue;
+            };
+            let breakdown = breakdowns.entry(candidate.fragment.id.clone()).or_default();
+            let rank_score = (n - position as f32) / n;
+            breakdown.rerank_position = Some(position);
+            breakdown.rerank_score = Some(rank_score);
+            candidate.score = rank_score;
+            if let Some(half_life) = half_life {
+                let decay = recency_decay(candidate.fragment.timestamp, now, half_life);
+                breakdown.recency_factor = Some(decay);
+                candidate.score *= decay;
+            }
+            ranked.push(candidate);
+        }
+        if half_life.is_some() {
+            ranked.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        }
+        Ok(ranked)
+    }
+
+    /// Combine similarity candidates with the best keyword matches, scaling
+    /// each side by its top score and weighting the keyword side by
+    /// `keyword_weight`. Matches the store no longer holds are unindexed.
+    async fn merge_keyword_matches(
+        &self,
+        query: &str,
+        similar: Vec<ScoredFragment>,
+        pool_size: usize,
+        keyword_weight: f32,
+        tenant: Option<&str>,
+        breakdowns: &mut HashMap<String, ScoreBreakdown>,
+    ) -> Result<Vec<ScoredFragment>> {
+        let matches = self.keywords.read().search(query, pool_size, tenant);
+        let best_similarity = similar.iter().map(|s| s.score).fold(0.0_f32, f32::max);
+        let best_keyword = matches.first().map_or(0.0, |(_, score)| *score);
+
+        let mut merged: HashMap<String, ScoredFragment> = similar.into_iter()
+            .map(|mut s| {
+                s.score = if best_similarity > 0.0 { (1.0 - keyword_weight) * s.score / best_similarity } else { 0.0 };
+                (s.fragment.id.clone(), s)
+            })
+            .collect();
+        for (id, bm25) in matches {
+            let score = keyword_weight * bm25 / best_keyword;
+            if let Some(candidate) = merged.get_mut(&id) {
+                candidate.score += score;
+            } else if let Some(fragment) = self.store.get(&id).await? {
+                merged.insert(id.clone(), ScoredFragment { score, fragment });
+            } else {
+                // Evicted by the store
+                self.keywords.write().remove(&id);
+                continue;
+            }
+            breakdowns.entry(id).or_default().keyword_score = Some(bm25);
+        }
+        for candidate in merged.values() {
+            breakdowns.entry(candidate.fragment.id.clone()).or_default().hybrid_score = Some(candidate.score);
+        }
+
+        let mut merged: Vec<ScoredFragment> = merged.into_values().collect();
+        merged.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        merged.truncate(pool_size);
+        Ok(merged)
     }
 
     /// Get memory statistics
     pub async fn stats(&self) -> MemoryStats {
-        let fragments = self.fragments.read().await;
+        let total_fragments = self.store.count().await.unwrap_or(0);
         let cache_stats = self.cache.stats().await;
 
         MemoryStats {
-            total_fragments: fragments.len(),
+            total_fragments,
+            max_fragments: self.max_fragments,
             cache_hits: cache_stats.hits,
             cache_misses: cache_stats.misses,
             cache_hit_rate: if cache_stats.hits + cache_stats.misses > 0 {
@@ -238,16 +1010,18 @@ impl Memory {
             } else {
                 0.0
             },
-            memory_usage_mb: (fragments.len() * self.embedding_dim * 4) as f64 / (1024.0 * 1024.0),
+            memory_usage_mb: (total_fragments * self.embedding_dim * 4) as f64 / (1024.0 * 1024.0),
             embedding_dim: self.embedding_dim,
-            similarity_threshold: self.similarity_threshold,
+            similarity_threshold: self.similarity_threshold(),
+            normalize_embeddings: self.normalize_embeddings,
         }
     }
 
     /// Clear all memory
     pub async fn clear(&self) -> Result<()> {
-        let mut fragments = self.fragments.write().await;
-        fragments.clear();
+        self.store.clear().await?;
+        self.keywords.write().clear();
+        self.bump_version();
 
         let mut kv_store = self.kv_store.write().await;
         kv_store.clear();
@@ -270,23 +1044,9 @@ impl Memory {
         Ok(kv_store.get(key).cloned())
     }
 
-    /// Create a dummy memory for embedding calls to avoid circular dependency
-    fn clone_dummy_memory(&self) -> Self {
-        Self {
-            embedding_agent: self.embedding_agent.clone(),
-            reranker_agent: self.reranker_agent.clone(),
-            cache: self.cache.clone(),
-            fragments: RwLock::new(Vec::new()),
-            kv_store: RwLock::new(HashMap::new()),
-            max_fragments: 0, // Empty for dummy
-            embedding_dim: self.embedding_dim,
-            similarity_threshold: self.similarity_threshold,
-        }
-    }
-
     /// Get the number of memory fragments
     pub async fn get_fragment_count(&self) -> usize {
-        self.fragments.read().await.len()
+        self.store.count().await.unwrap_or(0)
     }
 }
 
@@ -294,19 +1054,143 @@ impl Memory {
 #[derive(Debug, Clone, Serialize)]
 pub struct MemoryStats {
     pub total_fragments: usize,
+    pub max_fragments: usize,
     pub cache_hits: u64,
     pub cache_misses: u64,
     pub cache_hit_rate: f64,
     pub memory_usage_mb: f64,
     pub embedding_dim: usize,
     pub similarity_threshold: f32,
+    pub normalize_embeddings: bool,
+}
+
+/// Outcome of [`Memory::verify_and_repair`]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RepairReport {
+    pub fragments_checked: usize,
+    /// Fragments whose content had no cached embedding
+    pub missing_embeddings_recomputed: usize,
+    /// Fragments whose stored embedding disagreed with the cached one
+    pub mismatched_embeddings_recomputed: usize,
+    /// Fragments whose stored embedding was replaced
+    pub fragments_updated: usize,
+    pub orphaned_cache_entries_removed: usize,
+    /// Ids of fragments that could not be re-embedded
+    pub failed_fragments: Vec<String>,
+}
+
+/// Outcome of [`Memory::prefetch_embeddings`]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PrefetchReport {
+    /// Distinct non-blank texts submitted
+    pub requested: usize,
+    /// Texts embedded and cached by this run
+    pub computed: usize,
+    /// Texts whose embedding was cached already
+    pub already_cached: usize,
+}
+
+/// Outcome of [`Memory::search_memory_with_budget`]
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResults {
+    /// Contents of the matching fragments, best first
+    pub results: Vec<String>,
+    /// Set when the time budget ran out before the search finished
+    pub truncated: bool,
+}
+
+/// Outcome of [`Memory::explain_search`]
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchExplanation {
+    /// Similarity threshold the search applied
+    pub threshold: f32,
+    /// Candidates scored after the first pass, before selecting results
+    pub candidates: usize,
+    /// Set when the time budget ran out: results are the best similarity
+    /// matches scanned so far, not reranked
+    pub truncated: bool,
+    pub results: Vec<ExplainedResult>,
+}
+
+/// A search result with the fragment details and scores that placed it
+#[derive(Debug, Clone, Serialize)]
+pub struct ExplainedResult {
+    /// Position in the results, from 0
+    pub rank: usize,
+    pub id: String,
+    pub content: String,
+    /// Set when `content` was shortened for display
+    pub content_truncated: bool,
+    pub source: String,
+    pub tags: Vec<String>,
+    pub timestamp: u64,
+    pub tenant: Option<String>,
+    pub scores: ScoreBreakdown,
+    /// Stages the fragment got through, in order: "tenant",
+    /// "similarity_threshold", "keyword_match", "mmr_diversity" or
+    /// "rerank", and "top_k"
+    pub filters_passed: Vec<String>,
+}
+
+impl ExplainedResult {
+    /// Cut `content` to at most `max_chars` characters
+    pub fn truncate_content(&mut self, max_chars: usize) {
+        if let Some((cut, _)) = self.content.char_indices().nth(max_chars) {
+            self.content.truncate(cut);
+            self.content_truncated = true;
+        }
+    }
 }
 
+/// The scores behind a search result; stages a strategy skips are `None`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScoreBreakdown {
+    /// Similarity to the query embedding; `None` for hybrid keyword-only matches
+    pub similarity: Option<f32>,
+    /// BM25 score of the query terms, for hybrid searches
+    pub keyword_score: Option<f32>,
+    /// Weighted sum of the scaled similarity and keyword scores
+    pub hybrid_score: Option<f32>,
+    /// Position the reranker put the fragment at, from 0
+    pub rerank_position: Option<usize>,
+    /// 1.0 for the reranker's first pick down to 1/n for its last
+    pub rerank_score: Option<f32>,
+    /// Time decay multiplier, when a recency half-life applied
+    pub recency_factor: Option<f32>,
+    /// Score results were ordered by; for MMR, relevance before the diversity penalty
+    pub final_score: Option<f32>,
+}
+
+fn new_fragment_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Score multiplier in `(0, 1]` that halves every `half_life` of age.
+/// Fragments stamped in the future count as brand new.
+fn recency_decay(timestamp: u64, now: u64, half_life: Duration) -> f32 {
+    let age = now.saturating_sub(timestamp) as f64;
+    0.5f64.powf(age / half_life.as_secs_f64()) as f32
+}
+
+const CACHE_KEY_PREFIX: &str = "embedding:";
+
 /// Create a Blake3 hash key for content.
 fn cache_key(content: &str) -> String {
     let mut hasher = Hasher::new();
     hasher.update(content.as_bytes());
-    format!("embedding:{}", hasher.finalize().to_hex())
+    format!("{}{}", CACHE_KEY_PREFIX, hasher.finalize().to_hex())
+}
+
+/// Whether two embeddings are equal up to float rounding
+fn embeddings_match(a: &[f32], b: &[f32]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(x, y)| (x - y).abs() <= 1e-5)
 }
 
 /// Compute cosine similarity between two vectors.
@@ -326,10 +1210,83 @@ fn cosine(a: &[f32], b: &[f32]) -> f32 {
     }
 }
 
+/// Similarity scores are cosines, so thresholds outside `[-1, 1]` are meaningless
+pub fn validate_similarity_threshold(threshold: f32) -> Result<()> {
+    if !(-1.0..=1.0).contains(&threshold) {
+        return Err(anyhow!("Similarity threshold must be in [-1.0, 1.0], got {}", threshold));
+    }
+    Ok(())
+}
+
+/// MMR weights outside `[0, 1]` would reward redundancy or penalise relevance
+pub fn validate_mmr_lambda(lambda: f32) -> Result<()> {
+    if !(0.0..=1.0).contains(&lambda) {
+        return Err(anyhow!("MMR lambda must be in [0.0, 1.0], got {}", lambda));
+    }
+    Ok(())
+}
+
+/// A keyword weight outside `[0, 1]` would invert one side of a hybrid score
+pub fn validate_keyword_weight(keyword_weight: f32) -> Result<()> {
+    if !(0.0..=1.0).contains(&keyword_weight) {
+        return Err(anyhow!("Keyword weight must be in [0.0, 1.0], got {}", keyword_weight));
+    }
+    Ok(())
+}
+
+/// Greedily pick `top_k` candidates maximising
+/// `lambda * relevance - (1 - lambda) * max similarity to those already picked`
+fn mmr_select(mut candidates: Vec<ScoredFragment>, top_k: usize, lambda: f32) -> Vec<ScoredFragment> {
+    let mut selected: Vec<ScoredFragment> = Vec::with_capacity(top_k.min(candidates.len()));
+    while selected.len() < top_k && !candidates.is_empty() {
+        let mut best = 0;
+        let mut best_score = f32::NEG_INFINITY;
+        for (i, candidate) in candidates.iter().enumerate() {
+            let redundancy = selected
+                .iter()
+                .map(|s| cosine(&candidate.fragment.embedding, &s.fragment.embedding))
+                .fold(None, |max: Option<f32>, sim| Some(max.map_or(sim, |m| m.max(sim))))
+                .unwrap_or(0.0);
+            let score = lambda * candidate.score - (1.0 - lambda) * redundancy;
+            if score > best_score {
+                best = i;
+                best_score = score;
+            }
+        }
+        selected.push(candidates.remove(best));
+    }
+    selected
+}
+
+/// Dot product of two vectors; equals cosine similarity for unit vectors.
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+/// Scale a vector to unit length in place; zero vectors are left unchanged.
+pub(crate) fn l2_normalize(v: &mut [f32]) {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        v.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
 // Re-export the redis store module and core traits
 pub mod redis_store;
 pub use redis_store::{EmbeddingCache, CacheStats};
 
+pub mod chunking;
+pub use chunking::{chunker_by_name, Chunk, Chunker, FixedSizeChunker, SentenceChunker, StreamingChunker};
+
+pub mod keyword;
+pub use keyword::KeywordIndex;
+
+pub mod store;
+pub use store::{AddedFragment, InMemoryVectorStore, ScoredFragment, VectorStore};
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -365,6 +1322,566 @@ mod tests {
         assert_eq!(cosine(&a, &e), 0.0);
     }
 
+    #[test]
+    fn test_l2_normalize() {
+        let mut v = vec![3.0, 4.0];
+        l2_normalize(&mut v);
+        assert!((v[0] - 0.6).abs() < 1e-6);
+        assert!((v[1] - 0.8).abs() < 1e-6);
+        assert!((dot(&v, &v) - 1.0).abs() < 1e-6);
+
+        let mut zero = vec![0.0, 0.0];
+        l2_normalize(&mut zero);
+        assert_eq!(zero, vec![0.0, 0.0]);
+    }
+
+    #[tokio::test]
+    async fn test_memory_normalizes_stored_embeddings() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let embed = Arc::new(HashEmbeddingAgent::new(384));
+        let rerank = Arc::new(LengthRerankAgent::new());
+        let memory = Memory::new(embed, rerank, cache)
+            .with_normalize_embeddings(true);
+
+        memory.add_memory("normalize me").await.unwrap();
+        let fragments = memory.export_fragments().await.unwrap();
+        let norm: f32 = fragments[0].embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+    }
+
+    #[tokio::test]
+    async fn test_search_threshold_override() {
+        let memory = Memory::new(
+            Arc::new(HashEmbeddingAgent::new(384)),
+            Arc::new(LengthRerankAgent::new()),
+            Arc::new(InMemoryEmbeddingCache::new()),
+        );
+        memory.add_memory("the quick brown fox").await.unwrap();
+        memory.add_memory("an unrelated sentence").await.unwrap();
+
+        let strict = memory.search_memory_with_threshold("the quick brown fox", 10, Some(0.99)).await.unwrap();
+        assert_eq!(strict, vec!["the quick brown fox"]);
+        let broad = memory.search_memory_with_threshold("the quick brown fox", 10, Some(-1.0)).await.unwrap();
+        assert_eq!(broad.len(), 2);
+
+        assert!(memory.search_memory_with_threshold("fox", 10, Some(1.5)).await.is_err());
+        assert!(memory.search_memory_with_threshold("fox", 10, Some(f32::NAN)).await.is_err());
+    }
+
+    #[test]
+    fn test_mmr_skips_near_duplicates() {
+        let scored = |content: &str, score: f32, embedding: Vec<f32>| ScoredFragment {
+            score,
+            fragment: MemoryFragment::new(content.to_string(), embedding),
+        };
+        let candidates = vec![
+            scored("rust async runtime", 0.95, vec![1.0, 0.0, 0.1]),
+            scored("rust async runtimes", 0.94, vec![0.99, 0.0, 0.12]),
+            scored("memory safety", 0.70, vec![0.2, 1.0, 0.0]),
+        ];
+        let contents = |picked: Vec<ScoredFragment>| -> Vec<String> {
+            picked.into_iter().map(|s| s.fragment.content).collect()
+        };
+
+        assert_eq!(
+            contents(mmr_select(candidates.clone(), 2, 1.0)),
+            vec!["rust async runtime", "rust async runtimes"]
+        );
+        assert_eq!(
+            contents(mmr_select(candidates, 2, 0.5)),
+            vec!["rust async runtime", "memory safety"]
+        );
+        assert!(validate_mmr_lambda(1.5).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_search_strategies() {
+        let memory = Memory::new(
+            Arc::new(HashEmbeddingAgent::new(384)),
+            Arc::new(LengthRerankAgent::new()),
+            Arc::new(InMemoryEmbeddingCache::new()),
+        );
+        memory.add_memory("the quick brown fox").await.unwrap();
+        memory.add_memory("an unrelated sentence").await.unwrap();
+
+        for strategy in [SearchStrategy::Similarity, SearchStrategy::Mmr { lambda: 0.7 }] {
+            let results = memory
+                .search_memory_with_strategy("the quick brown fox", 1, Some(-1.0), strategy)
+                .await
+                .unwrap();
+            assert_eq!(results, vec!["the quick brown fox"]);
+        }
+        assert!(memory
+            .search_memory_with_strategy("fox", 1, None, SearchStrategy::Mmr { lambda: -0.1 })
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_recency_decay_prefers_newer_fragments() {
+        let memory = Memory::new(
+            Arc::new(HashEmbeddingAgent::new(384)),
+            Arc::new(LengthRerankAgent::new()),
+            Arc::new(InMemoryEmbeddingCache::new()),
+        );
+        // Identical embeddings, so only age separates the two
+        let embedding = memory.embed_content("release notes").await.unwrap();
+        let now = unix_now();
+        for (content, age) in [("older notes", 30 * 86_400), ("newer notes", 86_400)] {
+            let mut fragment = MemoryFragment::new(content.to_string(), embedding.clone());
+            fragment.timestamp = now - age;
+            memory.store.add(fragment).await.unwrap();
+        }
+
+        let week = Some(Duration::from_secs(7 * 86_400));
+        for strategy in [SearchStrategy::Similarity, SearchStrategy::Mmr { lambda: 0.7 }] {
+            let results = memory
+                .search_memory_with_recency("release notes", 1, Some(-1.0), strategy, week)
+                .await
+                .unwrap();
+            assert_eq!(results, vec!["newer notes"]);
+        }
+        assert_eq!(recency_decay(now, now, Duration::from_secs(60)), 1.0);
+        assert_eq!(recency_decay(now - 60, now, Duration::from_secs(60)), 0.5);
+    }
+
+    struct HungAgent;
+
+    #[async_trait::async_trait]
+    impl Agent for HungAgent {
+        fn name(&self) -> &str { "hung" }
+        fn agent_type(&self) -> &str { "test" }
+        fn capabilities(&self) -> Vec<String> { vec![] }
+        async fn handle(&self, _input: serde_json::Value, _memory: Arc<Memory>) -> Result<String> {
+            std::future::pending().await
+        }
+        async fn handle_model(&self, _input: serde_json::Value, _memory: ReadOnlyMemory<'_>) -> Result<String> {
+            std::future::pending().await
+        }
+        async fn health_check(&self) -> Result<crate::agent::AgentHealth> {
+            Ok(crate::agent::AgentHealth::default())
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_adds_respect_max_fragments() {
+        let memory = Arc::new(Memory::new(
+            Arc::new(HashEmbeddingAgent::new(384)),
+            Arc::new(LengthRerankAgent::new()),
+            Arc::new(InMemoryEmbeddingCache::new()),
+        ).with_max_fragments(10));
+
+        // Sample the count while writers race each other
+        let stop = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let watcher = {
+            let (memory, stop) = (memory.clone(), stop.clone());
+            tokio::spawn(async move {
+                let mut peak = 0;
+                while !stop.load(Ordering::Acquire) {
+                    peak = peak.max(memory.get_fragment_count().await);
+                    tokio::task::yield_now().await;
+                }
+                peak
+            })
+        };
+
+        let writers: Vec<_> = (0..200)
+            .map(|i| {
+                let memory = memory.clone();
+                tokio::spawn(async move { memory.add_memory(&format!("fragment number {}", i)).await })
+            })
+            .collect();
+        for writer in writers {
+            writer.await.unwrap().unwrap();
+        }
+        stop.store(true, Ordering::Release);
+
+        assert!(watcher.await.unwrap() <= 10);
+        assert_eq!(memory.get_fragment_count().await, 10);
+        assert_eq!(memory.fragment_version(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_verify_and_repair_fixes_drift() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let memory = Memory::new(
+            Arc::new(HashEmbeddingAgent::new(384)),
+            Arc::new(LengthRerankAgent::new()),
+            cache.clone(),
+        );
+        for content in ["alpha", "beta", "gamma"] {
+            memory.add_memory(content).await.unwrap();
+        }
+        let fragments = memory.export_fragments().await.unwrap();
+        let original = fragments[2].embedding.clone();
+
+        // Lost cache entry, stale cache entry, corrupted fragment, orphans
+        cache.delete(&cache_key("alpha")).await.unwrap();
+        cache.set(&cache_key("beta"), &[1.0; 384]).await.unwrap();
+        memory.store.update_embedding(&fragments[2].id, vec![0.0; 384]).await.unwrap();
+        cache.set(&cache_key("deleted long ago"), &[1.0; 384]).await.unwrap();
+        cache.set("foreign:entry", &[1.0]).await.unwrap();
+        let version = memory.fragment_version();
+
+        let report = memory.verify_and_repair().await.unwrap();
+        assert_eq!(report.fragments_checked, 3);
+        assert_eq!(report.missing_embeddings_recomputed, 1);
+        assert_eq!(report.mismatched_embeddings_recomputed, 2);
+        assert_eq!(report.fragments_updated, 1);
+        assert_eq!(report.orphaned_cache_entries_removed, 1);
+        assert!(report.failed_fragments.is_empty());
+        assert_eq!(memory.fragment_version(), version + 1);
+
+        let repaired = memory.export_fragments().await.unwrap();
+        assert_eq!(repaired[2].embedding, original);
+        assert_eq!(cache.get(&cache_key("beta")).await.unwrap().unwrap(), repaired[1].embedding);
+        assert!(cache.get("foreign:entry").await.unwrap().is_some());
+
+        // A consistent memory needs no model calls and no fixes
+        let report = memory.verify_and_repair().await.unwrap();
+        assert_eq!(report.fragments_checked, 3);
+        assert_eq!(report.missing_embeddings_recomputed + report.mismatched_embeddings_recomputed, 0);
+        assert_eq!(report.orphaned_cache_entries_removed, 0);
+    }
+
+    /// Embedding agent whose embedding is the fragment count it reads
+    struct CountingEmbeddingAgent;
+
+    #[async_trait::async_trait]
+    impl Agent for CountingEmbeddingAgent {
+        fn name(&self) -> &str { "counting" }
+        fn agent_type(&self) -> &str { "test" }
+        fn capabilities(&self) -> Vec<String> { vec![] }
+        async fn handle(&self, _input: serde_json::Value, _memory: Arc<Memory>) -> Result<String> {
+            unreachable!("memory calls models through handle_model")
+        }
+        async fn handle_model(&self, _input: serde_json::Value, memory: ReadOnlyMemory<'_>) -> Result<String> {
+            let count = memory.get_fragment_count().await as f32;
+            Ok(serde_json::to_string(&vec![count + 1.0; memory.embedding_dim()])?)
+        }
+        async fn health_check(&self) -> Result<crate::agent::AgentHealth> {
+            Ok(crate::agent::AgentHealth::default())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_model_agents_read_through_read_only_memory() {
+        let memory = Memory::new(
+            Arc::new(CountingEmbeddingAgent),
+            Arc::new(LengthRerankAgent::new()),
+            Arc::new(InMemoryEmbeddingCache::new()),
+        )
+        .with_embedding_dim(2);
+        memory.add_memory("first").await.unwrap();
+        memory.add_memory("second").await.unwrap();
+
+        let mut embeddings: Vec<Vec<f32>> = memory.export_fragments().await.unwrap()
+            .into_iter()
+            .map(|fragment| fragment.embedding)
+            .collect();
+        embeddings.sort_by(|a, b| a[0].total_cmp(&b[0]));
+        assert_eq!(embeddings, vec![vec![1.0, 1.0], vec![2.0, 2.0]]);
+    }
+
+    #[tokio::test]
+    async fn test_hung_models_time_out() {
+        let timeout = Duration::from_millis(50);
+        let hung_embedder = Memory::new(
+            Arc::new(HungAgent),
+            Arc::new(LengthRerankAgent::new()),
+            Arc::new(InMemoryEmbeddingCache::new()),
+        )
+        .with_embedding_timeout(timeout);
+        let err = hung_embedder.add_memory("some content").await.unwrap_err();
+        assert!(err.is::<EmbeddingTimeoutError>());
+        let err = hung_embedder.search_memory("query", 5).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<EmbeddingTimeoutError>().unwrap().task, "embedding");
+
+        let hung_reranker = Memory::new(
+            Arc::new(HashEmbeddingAgent::new(384)),
+            Arc::new(HungAgent),
+            Arc::new(InMemoryEmbeddingCache::new()),
+        )
+        .with_embedding_timeout(timeout);
+        // Nothing stored yet: an empty result, not a timeout
+        assert!(hung_reranker.search_memory("query", 5).await.unwrap().is_empty());
+        hung_reranker.add_memory("query").await.unwrap();
+        let err = hung_reranker.search_memory_with_threshold("query", 5, Some(-1.0)).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<EmbeddingTimeoutError>().unwrap().task, "rerank");
+    }
+
+    #[tokio::test]
+    async fn test_memory_auto_chunks_long_content() {
+        let memory = || Memory::new(
+            Arc::new(HashEmbeddingAgent::new(384)),
+            Arc::new(LengthRerankAgent::new()),
+            Arc::new(InMemoryEmbeddingCache::new()),
+        );
+        let long = "abcdefghij".repeat(3);
+
+        let chunked = memory().with_auto_chunking(12, 2).unwrap();
+        chunked.add_memory(&long).await.unwrap();
+        chunked.add_memory("short").await.unwrap();
+        let fragments = chunked.export_fragments().await.unwrap();
+        assert_eq!(fragments.len(), 4);
+        assert!(fragments.iter().all(|f| f.content.chars().count() <= 12));
+        let document_id = &fragments[0].metadata["document_id"];
+        assert!(fragments[..3].iter().all(|f| &f.metadata["document_id"] == document_id));
+        assert_eq!(fragments[2].metadata["chunk_index"], 2);
+        assert!(fragments[3].metadata.is_empty());
+
+        assert_eq!(fragments[1].metadata["char_start"], 10);
+
+        let whole = memory().with_auto_chunking(12, 2).unwrap().without_auto_chunking();
+        whole.add_memory(&long).await.unwrap();
+        assert_eq!(whole.export_fragments().await.unwrap().len(), 1);
+
+        // A per-call chunker overrides the configured one
+        let sentences = SentenceChunker::new(25).unwrap();
+        let created = whole
+            .add_memory_with_chunker("First sentence here. Second sentence here.", None, &sentences)
+            .await
+            .unwrap();
+        assert_eq!(created, 2);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_tags_restrict_search() {
+        let memory = Memory::new(
+            Arc::new(HashEmbeddingAgent::new(384)),
+            Arc::new(LengthRerankAgent::new()),
+            Arc::new(InMemoryEmbeddingCache::new()),
+        );
+        memory.add_tenant_memory("acme roadmap", None, Some("acme"), None).await.unwrap();
+        memory.add_tenant_memory("globex roadmap", None, Some("globex"), None).await.unwrap();
+        memory.add_memory("shared roadmap").await.unwrap();
+
+        let search = |tenant| memory.search_memory_with_tenant("roadmap", 10, Some(-1.0), SearchStrategy::Similarity, None, tenant);
+        let mut acme = search(Some("acme")).await.unwrap();
+        acme.sort();
+        assert_eq!(acme, vec!["acme roadmap", "shared roadmap"]);
+        assert_eq!(search(None).await.unwrap().len(), 3);
+
+        let exported = memory.export_tenant_fragments(Some("globex")).await.unwrap();
+        assert_eq!(exported.len(), 2);
+        assert!(exported.iter().all(|f| f.tenant() != Some("acme")));
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_embeddings_fills_cache() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let memory = Memory::new(
+            Arc::new(HashEmbeddingAgent::new(384)),
+            Arc::new(LengthRerankAgent::new()),
+            cache.clone(),
+        );
+        let texts: Vec<String> = ["alpha", "beta", "alpha", "  "].iter().map(|t| t.to_string()).collect();
+
+        let report = memory.prefetch_embeddings(&texts).await.unwrap();
+        assert_eq!((report.requested, report.computed, report.already_cached), (2, 2, 0));
+        assert!(cache.get(&cache_key("beta")).await.unwrap().is_some());
+
+        let texts = vec!["beta".to_string(), "gamma".to_string()];
+        let report = memory.prefetch_embeddings(&texts).await.unwrap();
+        assert_eq!((report.requested, report.computed, report.already_cached), (2, 1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_search_surfaces_exact_keyword_matches() {
+        let memory = Memory::new(
+            Arc::new(HashEmbeddingAgent::new(384)),
+            Arc::new(LengthRerankAgent::new()),
+            Arc::new(InMemoryEmbeddingCache::new()),
+        );
+        memory.add_memory("Order ZX-4471 shipped on Tuesday").await.unwrap();
+        memory.add_memory("The weather is mild this week").await.unwrap();
+        memory.add_memory("Quarterly revenue grew again").await.unwrap();
+
+        // Hash embeddings of unrelated texts never clear a strict threshold
+        let vector = memory
+            .search_memory_with_strategy("zx-4471", 3, Some(0.99), SearchStrategy::Similarity)
+            .await
+            .unwrap();
+        assert!(vector.is_empty());
+
+        let hybrid = SearchStrategy::Hybrid { keyword_weight: 0.5 };
+        let results = memory.search_memory_with_strategy("zx-4471", 3, Some(0.99), hybrid).await.unwrap();
+        assert_eq!(results, vec!["Order ZX-4471 shipped on Tuesday"]);
+
+        // Deleted fragments leave the keyword index too
+        let id = memory.export_fragments().await.unwrap()[0].id.clone();
+        memory.delete_memory(&id).await.unwrap();
+        assert!(memory.search_memory_with_strategy("zx-4471", 3, Some(0.99), hybrid).await.unwrap().is_empty());
+
+        let invalid = SearchStrategy::Hybrid { keyword_weight: 1.5 };
+        assert!(memory.search_memory_with_strategy("zx-4471", 3, None, invalid).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_evicted_fragments_leave_keyword_index() {
+        let memory = Memory::new(
+            Arc::new(HashEmbeddingAgent::new(384)),
+            Arc::new(LengthRerankAgent::new()),
+            Arc::new(InMemoryEmbeddingCache::new()),
+        )
+        .with_max_fragments(2);
+        memory.add_memory("Order ZX-4471 shipped on Tuesday").await.unwrap();
+        memory.add_memory("The weather is mild this week").await.unwrap();
+        memory.add_memory("Quarterly revenue grew again").await.unwrap();
+
+        assert_eq!(memory.keywords.read().len(), 2);
+        assert!(memory.keywords.read().search("zx", 3, None).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_zero_max_fragments_stores_nothing() {
+        let memory = Memory::new(
+            Arc::new(HashEmbeddingAgent::new(384)),
+            Arc::new(LengthRerankAgent::new()),
+            Arc::new(InMemoryEmbeddingCache::new()),
+        )
+        .with_max_fragments(0);
+        assert!(memory.add_memory("anything").await.is_err());
+        assert_eq!(memory.get_fragment_count().await, 0);
+        assert_eq!(memory.keywords.read().len(), 0);
+        assert_eq!(memory.stats().await.cache_misses, 0);
+    }
+
+    #[tokio::test]
+    async fn test_max_fragments_resizes_configured_store() {
+        let store = InMemoryVectorStore::new(1);
+        store.add(MemoryFragment::new("kept".to_string(), vec![1.0; 384])).await.unwrap();
+        let memory = Memory::new(
+            Arc::new(HashEmbeddingAgent::new(384)),
+            Arc::new(LengthRerankAgent::new()),
+            Arc::new(InMemoryEmbeddingCache::new()),
+        )
+        .with_vector_store(Box::new(store))
+        .with_max_fragments(2);
+        assert_eq!(memory.get_fragment_count().await, 1);
+
+        memory.add_memory("first").await.unwrap();
+        memory.add_memory("second").await.unwrap();
+        assert_eq!(memory.get_fragment_count().await, 2);
+    }
+
+    #[test]
+    fn test_fragments_exported_without_ids_get_one() {
+        let fragment: MemoryFragment = serde_json::from_value(serde_json::json!({
+            "content": "old export",
+            "embedding": [1.0],
+            "metadata": {},
+            "timestamp": 0,
+            "source": "manual",
+            "tags": [],
+        })).unwrap();
+        assert!(!fragment.id.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_explain_search_breaks_down_scores() {
+        let memory = Memory::new(
+            Arc::new(HashEmbeddingAgent::new(384)),
+            Arc::new(LengthRerankAgent::new()),
+            Arc::new(InMemoryEmbeddingCache::new()),
+        );
+        memory.add_memory("Order ZX-4471 shipped on Tuesday").await.unwrap();
+        memory.add_memory("The weather is mild this week").await.unwrap();
+
+        // Hash embeddings carry no meaning, so only the breakdown is checked
+        let explanation = memory
+            .explain_search("weather this week", 5, Some(-1.0), SearchStrategy::Similarity, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(explanation.candidates, 2);
+        assert_eq!(explanation.results.len(), 2);
+        for (rank, result) in explanation.results.iter().enumerate() {
+            assert_eq!(result.rank, rank);
+            assert!(result.scores.similarity.is_some());
+            assert_eq!(result.scores.final_score, result.scores.similarity);
+            assert_eq!(result.filters_passed, vec!["similarity_threshold", "top_k"]);
+        }
+
+        // A keyword-only hybrid match carries no similarity and goes through the reranker
+        let hybrid = SearchStrategy::Hybrid { keyword_weight: 0.5 };
+        let explanation = memory.explain_search("zx-4471", 5, Some(0.99), hybrid, None, None, None).await.unwrap();
+        let [result] = explanation.results.as_slice() else { panic!("expected one result") };
+        assert!(result.scores.similarity.is_none());
+        assert!(result.scores.keyword_score.unwrap() > 0.0);
+        assert_eq!(result.scores.rerank_position, Some(0));
+        assert_eq!(result.filters_passed, vec!["keyword_match", "rerank", "top_k"]);
+
+        let mut result = result.clone();
+        result.truncate_content(5);
+        assert_eq!((result.content.as_str(), result.content_truncated), ("Order", true));
+    }
+
+    #[tokio::test]
+    async fn test_search_budget_cuts_large_scans_short() {
+        const FRAGMENTS: usize = 200_000;
+        let store = InMemoryVectorStore::new(FRAGMENTS);
+        for i in 0..FRAGMENTS {
+            let embedding = (0..32).map(|j| ((i * 31 + j * 17) % 97) as f32 - 48.0).collect();
+            store.add(MemoryFragment::new(format!("fragment {}", i), embedding)).await.unwrap();
+        }
+        let memory = Memory::new(
+            Arc::new(HashEmbeddingAgent::new(32)),
+            Arc::new(LengthRerankAgent::new()),
+            Arc::new(InMemoryEmbeddingCache::new()),
+        )
+        .with_embedding_dim(32)
+        .with_vector_store(Box::new(store));
+
+        let started = Instant::now();
+        let bounded = memory
+            .search_memory_with_budget("query", 5, Some(-1.0), SearchStrategy::Rerank, None, None, Some(Duration::from_millis(1)))
+            .await
+            .unwrap();
+        let bounded_elapsed = started.elapsed();
+        assert!(bounded.truncated);
+        assert_eq!(bounded.results.len(), 5);
+
+        let started = Instant::now();
+        let full = memory
+            .search_memory_with_budget("query", 5, Some(-1.0), SearchStrategy::Rerank, None, None, None)
+            .await
+            .unwrap();
+        assert!(!full.truncated);
+        // Past the deadline only the current batch of fragments is scored
+        assert!(
+            bounded_elapsed < started.elapsed(),
+            "budgeted search took {:?}, exhaustive search {:?}",
+            bounded_elapsed,
+            started.elapsed()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fragment_version_changes_on_mutation() {
+        let memory = Memory::new(
+            Arc::new(HashEmbeddingAgent::new(384)),
+            Arc::new(LengthRerankAgent::new()),
+            Arc::new(InMemoryEmbeddingCache::new()),
+        );
+        let initial = memory.fragment_version();
+
+        memory.add_memory("versioned").await.unwrap();
+        let added = memory.fragment_version();
+        assert_ne!(added, initial);
+
+        memory.search_memory("versioned", 5).await.unwrap();
+        assert!(!memory.delete_memory("missing").await.unwrap());
+        assert_eq!(memory.fragment_version(), added);
+
+        let id = memory.export_fragments().await.unwrap()[0].id.clone();
+        assert!(memory.delete_memory(&id).await.unwrap());
+        let deleted = memory.fragment_version();
+        assert_ne!(deleted, added);
+
+        memory.clear().await.unwrap();
+        assert_ne!(memory.fragment_version(), deleted);
+    }
+
     #[tokio::test]
     async fn test_memory_kv_operations() {
         let cache = Arc::new(InMemoryEmbeddingCache::new());
@@ -34,6 +34,9 @@ pub trait EmbeddingCache: Send + Sync + std::fmt::Debug {
     /// Clear all embeddings (for testing)
     async fn clear(&self) -> Result<()>;
 
+    /// Every key currently cached
+    async fn keys(&self) -> Result<Vec<String>>;
+
     /// Get cache statistics
     async fn stats(&self) -> Result<CacheStats>;
 }
@@ -157,6 +160,13 @@ impl EmbeddingCache for InMemoryEmbeddingCache {
         Ok(())
     }
 
+    async fn keys(&self) -> Result<Vec<String>> {
+        let storage = self.storage.read()
+            .map_err(|e| anyhow!("Failed to acquire read lock: {}", e))?;
+
+        Ok(storage.keys().cloned().collect())
+    }
+
     async fn stats(&self) -> Result<CacheStats> {
         let stats = self.stats.read()
             .map_err(|e| anyhow!("Failed to acquire stats read lock: {}", e))?;
@@ -243,6 +253,13 @@ impl EmbeddingCache for LruCache {
         Ok(())
     }
 
+    async fn keys(&self) -> Result<Vec<String>> {
+        let cache = self.inner.read()
+            .map_err(|e| anyhow!("Failed to acquire read lock: {}", e))?;
+
+        Ok(cache.iter().map(|(key, _)| key.clone()).collect())
+    }
+
     async fn clear(&self) -> Result<()> {
         let mut cache = self.inner.write()
             .map_err(|e| anyhow!("Failed to acquire write lock: {}", e))?;
@@ -394,6 +411,33 @@ impl EmbeddingCache for RedisCache {
         Ok(())
     }
 
+    async fn keys(&self) -> Result<Vec<String>> {
+        let mut conn = self.pool.get().await
+            .map_err(|e| anyhow!("Failed to get Redis connection: {}", e))?;
+
+        let pattern = format!("{}*", self.key_prefix);
+        let mut keys = Vec::new();
+        let mut cursor: u64 = 0;
+        loop {
+            let (next, batch): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(1000)
+                .query_async(&mut *conn)
+                .await
+                .map_err(|e| anyhow!("Failed to scan Redis keys: {}", e))?;
+            keys.extend(batch.iter().filter_map(|key| key.strip_prefix(&self.key_prefix)).map(str::to_string));
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+
+        Ok(keys)
+    }
+
     async fn stats(&self) -> Result<CacheStats> {
         let stats = self.stats.read()
             .map_err(|e| anyhow!("Failed to acquire stats read lock: {}", e))?;
@@ -0,0 +1,355 @@
+/*!
+Vector storage back-ends for memory fragments:
+
+* **InMemoryVectorStore** – in-process brute-force store (default, single instance).
+
+External stores (e.g. Qdrant, pgvector) implement [`VectorStore`] behind their
+own feature flags and are installed with `Memory::with_vector_store`.
+*/
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Instant;
+use tokio::sync::RwLock;
+use tracing::debug;
+
+use super::{cosine, dot, MemoryFragment};
+
+/// Fragments scored between checks of a search deadline
+const DEADLINE_CHECK_INTERVAL: usize = 256;
+
+/// Fragment returned from a similarity search with its score
+#[derive(Debug, Clone)]
+pub struct ScoredFragment {
+    pub score: f32,
+    pub fragment: MemoryFragment,
+}
+
+/// Outcome of [`VectorStore::add`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddedFragment {
+    /// Id the fragment was stored under
+    pub id: String,
+    /// Ids of fragments the store dropped to make room
+    pub evicted: Vec<String>,
+}
+
+/// Storage and similarity search for memory fragments.
+///
+/// `Memory` owns embedding and reranking; a store only persists fragments and
+/// answers nearest-neighbour queries over their embeddings.
+#[async_trait]
+pub trait VectorStore: Send + Sync {
+    /// Store a fragment, returning its id and any fragments evicted for it
+    async fn add(&self, fragment: MemoryFragment) -> Result<AddedFragment>;
+
+    /// Return up to `top_k` fragments scoring above `threshold`, best first,
+    /// considering only those visible to `tenant` when one is given
+    async fn search(&self, query: &[f32], top_k: usize, threshold: f32, tenant: Option<&str>) -> Result<Vec<ScoredFragment>>;
+
+    /// Like [`search`](Self::search), but stop scanning once `deadline` has
+    /// passed and return the best matches seen so far, with `true` when the
+    /// scan was cut short. Stores that cannot stop early search in full.
+    async fn search_until(
+        &self,
+        query: &[f32],
+        top_k: usize,
+        threshold: f32,
+        tenant: Option<&str>,
+        _deadline: Instant,
+    ) -> Result<(Vec<ScoredFragment>, bool)> {
+        Ok((self.search(query, top_k, threshold, tenant).await?, false))
+    }
+
+    /// Delete a fragment by id, returning whether it existed
+    async fn delete(&self, id: &str) -> Result<bool>;
+
+    /// The fragment stored under `id`, if any
+    async fn get(&self, id: &str) -> Result<Option<MemoryFragment>> {
+        Ok(self.list().await?.into_iter().find(|f| f.id == id))
+    }
+
+    /// Replace a fragment's embedding in place, returning whether it existed
+    async fn update_embedding(&self, id: &str, embedding: Vec<f32>) -> Result<bool>;
+
+    /// All stored fragments, oldest first
+    async fn list(&self) -> Result<Vec<MemoryFragment>>;
+
+    /// Number of stored fragments
+    async fn count(&self) -> Result<usize>;
+
+    /// Remove all fragments
+    async fn clear(&self) -> Result<()>;
+
+    /// Change how many fragments are kept, returning `false` if the store
+    /// manages its own capacity
+    fn set_capacity(&self, _max_fragments: usize) -> bool {
+        false
+    }
+
+    /// Score by dot product, for embeddings known to be unit length;
+    /// returns `false` if the store chooses its own metric
+    fn set_normalized(&self, _normalized: bool) -> bool {
+        false
+    }
+}
+
+/// In-process store that keeps fragments in insertion order and evicts the
+/// oldest once `max_fragments` is reached. Lowering the capacity evicts
+/// down to it on the next add.
+///
+/// The capacity check, eviction and insert happen under one write lock, so
+/// concurrent writers never push the count past `max_fragments`.
+pub struct InMemoryVectorStore {
+    fragments: RwLock<Vec<MemoryFragment>>,
+    max_fragments: AtomicUsize,
+    normalized: AtomicBool,
+}
+
+impl InMemoryVectorStore {
+    pub fn new(max_fragments: usize) -> Self {
+        Self {
+            fragments: RwLock::new(Vec::new()),
+            max_fragments: AtomicUsize::new(max_fragments),
+            normalized: AtomicBool::new(false),
+        }
+    }
+
+    /// Score with a plain dot product instead of cosine similarity.
+    ///
+    /// Only valid when every stored and query embedding is unit length.
+    pub fn with_normalized_embeddings(self, normalized: bool) -> Self {
+        self.set_normalized(normalized);
+        self
+    }
+
+    /// Score fragments oldest first, checking `deadline` every
+    /// [`DEADLINE_CHECK_INTERVAL`] fragments
+    async fn scan(
+        &self,
+        query: &[f32],
+        top_k: usize,
+        threshold: f32,
+        tenant: Option<&str>,
+        deadline: Option<Instant>,
+    ) -> (Vec<ScoredFragment>, bool) {
+        let fragments = self.fragments.read().await;
+
+        let similarity = if self.normalized.load(Ordering::Relaxed) { dot } else { cosine };
+        let mut scored: Vec<(f32, &MemoryFragment)> = Vec::new();
+        let mut truncated = false;
+        for (i, fragment) in fragments.iter().enumerate() {
+            if i > 0 && i % DEADLINE_CHECK_INTERVAL == 0 && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                debug!("Search deadline passed after scanning {} of {} fragments", i, fragments.len());
+                truncated = true;
+                break;
+            }
+            if !fragment.visible_to(tenant) {
+                continue;
+            }
+            let score = similarity(query, &fragment.embedding);
+            if score > threshold {
+                scored.push((score, fragment));
+            }
+        }
+
+        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        let scored = scored
+            .into_iter()
+            .take(top_k)
+            .map(|(score, fragment)| ScoredFragment { score, fragment: fragment.clone() })
+            .collect();
+        (scored, truncated)
+    }
+}
+
+#[async_trait]
+impl VectorStore for InMemoryVectorStore {
+    async fn add(&self, fragment: MemoryFragment) -> Result<AddedFragment> {
+        let max_fragments = self.max_fragments.load(Ordering::Relaxed);
+        if max_fragments == 0 {
+            return Err(anyhow!("Vector store has no capacity for fragments"));
+        }
+
+        let id = fragment.id.clone();
+        let mut fragments = self.fragments.write().await;
+
+        // Enforce max fragments limit by evicting the oldest, making room
+        // for exactly one more
+        let excess = (fragments.len() + 1).saturating_sub(max_fragments);
+        let mut evicted = Vec::new();
+        if excess > 0 {
+            debug!("Memory at capacity, removing {} oldest fragments", excess);
+            let excess = excess.min(fragments.len());
+            evicted.extend(fragments.drain(..excess).map(|f| f.id));
+        }
+
+        fragments.push(fragment);
+        debug!("Added memory fragment, total fragments: {}", fragments.len());
+        Ok(AddedFragment { id, evicted })
+    }
+
+    async fn search(&self, query: &[f32], top_k: usize, threshold: f32, tenant: Option<&str>) -> Result<Vec<ScoredFragment>> {
+        Ok(self.scan(query, top_k, threshold, tenant, None).await.0)
+    }
+
+    async fn search_until(
+        &self,
+        query: &[f32],
+        top_k: usize,
+        threshold: f32,
+        tenant: Option<&str>,
+        deadline: Instant,
+    ) -> Result<(Vec<ScoredFragment>, bool)> {
+        Ok(self.scan(query, top_k, threshold, tenant, Some(deadline)).await)
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool> {
+        let mut fragments = self.fragments.write().await;
+        let before = fragments.len();
+        fragments.retain(|f| f.id != id);
+        Ok(fragments.len() != before)
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<MemoryFragment>> {
+        Ok(self.fragments.read().await.iter().find(|f| f.id == id).cloned())
+    }
+
+    async fn update_embedding(&self, id: &str, embedding: Vec<f32>) -> Result<bool> {
+        let mut fragments = self.fragments.write().await;
+        match fragments.iter_mut().find(|f| f.id == id) {
+            Some(fragment) => {
+                fragment.embedding = embedding;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<MemoryFragment>> {
+        Ok(self.fragments.read().await.clone())
+    }
+
+    async fn count(&self) -> Result<usize> {
+        Ok(self.fragments.read().await.len())
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.fragments.write().await.clear();
+        Ok(())
+    }
+
+    fn set_capacity(&self, max_fragments: usize) -> bool {
+        self.max_fragments.store(max_fragments, Ordering::Relaxed);
+        true
+    }
+
+    fn set_normalized(&self, normalized: bool) -> bool {
+        self.normalized.store(normalized, Ordering::Relaxed);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fragment(content: &str, embedding: Vec<f32>) -> MemoryFragment {
+        MemoryFragment::new(content.to_string(), embedding)
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_search_and_delete() {
+        let store = InMemoryVectorStore::new(10);
+        let x = store.add(fragment("x", vec![1.0, 0.0])).await.unwrap().id;
+        store.add(fragment("xy", vec![1.0, 1.0])).await.unwrap();
+        store.add(fragment("y", vec![0.0, 1.0])).await.unwrap();
+        assert_eq!(store.count().await.unwrap(), 3);
+
+        let results = store.search(&[1.0, 0.0], 2, 0.1, None).await.unwrap();
+        let contents: Vec<&str> = results.iter().map(|r| r.fragment.content.as_str()).collect();
+        assert_eq!(contents, vec!["x", "xy"]);
+        assert!(results[0].score > results[1].score);
+
+        assert!(store.delete(&x).await.unwrap());
+        assert!(!store.delete(&x).await.unwrap());
+        assert_eq!(store.count().await.unwrap(), 2);
+        let listed: Vec<String> = store.list().await.unwrap().into_iter().map(|f| f.content).collect();
+        assert_eq!(listed, vec!["xy", "y"]);
+
+        store.clear().await.unwrap();
+        assert_eq!(store.count().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_evicts_oldest() {
+        let store = InMemoryVectorStore::new(2);
+        let first = store.add(fragment("first", vec![1.0])).await.unwrap();
+        assert!(first.evicted.is_empty());
+        store.add(fragment("second", vec![1.0])).await.unwrap();
+        let third = store.add(fragment("third", vec![1.0])).await.unwrap();
+        assert_eq!(third.evicted, vec![first.id]);
+
+        assert_eq!(store.count().await.unwrap(), 2);
+        let results = store.search(&[1.0], 10, 0.0, None).await.unwrap();
+        assert!(results.iter().all(|r| r.fragment.content != "first"));
+
+        assert!(InMemoryVectorStore::new(0).add(fragment("none", vec![1.0])).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_ranking_with_normalization() {
+        // "aligned" points the same way as the query; "large" is off-axis but long
+        let raw = [("aligned", vec![1.0, 0.1]), ("large", vec![10.0, 10.0])];
+        let query = [2.0, 0.0];
+
+        let cosine_store = InMemoryVectorStore::new(10);
+        let skewed_store = InMemoryVectorStore::new(10).with_normalized_embeddings(true);
+        let normalized_store = InMemoryVectorStore::new(10).with_normalized_embeddings(true);
+        for (content, embedding) in &raw {
+            cosine_store.add(fragment(content, embedding.clone())).await.unwrap();
+            skewed_store.add(fragment(content, embedding.clone())).await.unwrap();
+            let mut unit = embedding.clone();
+            crate::memory::l2_normalize(&mut unit);
+            normalized_store.add(fragment(content, unit)).await.unwrap();
+        }
+
+        let ranking = |results: Vec<ScoredFragment>| -> Vec<String> {
+            results.into_iter().map(|r| r.fragment.content).collect()
+        };
+
+        // Dot product over unnormalized vectors favours magnitude over direction
+        let skewed = skewed_store.search(&query, 2, 0.0, None).await.unwrap();
+        assert_eq!(ranking(skewed), vec!["large", "aligned"]);
+
+        let mut unit_query = query.to_vec();
+        crate::memory::l2_normalize(&mut unit_query);
+        let expected = cosine_store.search(&query, 2, 0.0, None).await.unwrap();
+        let normalized = normalized_store.search(&unit_query, 2, 0.0, None).await.unwrap();
+        for (a, b) in expected.iter().zip(&normalized) {
+            assert!((a.score - b.score).abs() < 1e-5);
+        }
+        assert_eq!(ranking(normalized), ranking(expected));
+        assert_eq!(ranking(cosine_store.search(&query, 2, 0.0, None).await.unwrap()), vec!["aligned", "large"]);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_stops_at_deadline() {
+        let store = InMemoryVectorStore::new(10_000);
+        for i in 0..2_000 {
+            store.add(fragment(&i.to_string(), vec![1.0, i as f32])).await.unwrap();
+        }
+
+        // A passed deadline stops the scan at the first check
+        let (results, truncated) = store.search_until(&[1.0, 0.0], 5, -1.0, None, Instant::now()).await.unwrap();
+        assert!(truncated);
+        assert_eq!(results.len(), 5);
+        assert!(results.iter().all(|r| r.fragment.content.parse::<usize>().unwrap() < DEADLINE_CHECK_INTERVAL));
+
+        let later = Instant::now() + std::time::Duration::from_secs(60);
+        let (results, truncated) = store.search_until(&[0.0, 1.0], 1, -1.0, None, later).await.unwrap();
+        assert!(!truncated);
+        assert_eq!(results[0].fragment.content, "1999");
+    }
+}
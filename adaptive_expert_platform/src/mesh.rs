@@ -1,17 +1,26 @@
 //! Distributed agent mesh networking for horizontal scaling
 
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
 use anyhow::{anyhow, Result};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::{RwLock, mpsc, oneshot};
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 use serde::{Deserialize, Serialize};
 use dashmap::DashMap;
-use tracing::{info, warn, error, instrument};
+use tracing::{info, warn, error, info_span, instrument, Instrument};
 
 use crate::agent::Agent;
 
+/// Metadata key under which a node advertises its per-capability
+/// proficiency, a map of capability to a non-negative score
+pub const PROFICIENCY_METADATA_KEY: &str = "proficiency";
+
 /// Node information in the mesh network
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MeshNode {
@@ -24,6 +33,16 @@ pub struct MeshNode {
     pub metadata: HashMap<String, serde_json::Value>,
 }
 
+impl MeshNode {
+    /// Advertised proficiency for `capability`, if any
+    pub fn proficiency(&self, capability: &str) -> Option<f64> {
+        self.metadata.get(PROFICIENCY_METADATA_KEY)?
+            .get(capability)?
+            .as_f64()
+            .filter(|score| score.is_finite() && *score >= 0.0)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum NodeStatus {
     Healthy,
@@ -45,7 +64,7 @@ pub struct TaskRoute {
     pub routing_hints: HashMap<String, String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum TaskPriority {
     Low = 1,
     Normal = 2,
@@ -90,6 +109,17 @@ pub enum MeshMessage {
     },
 }
 
+/// A message as sent on the wire. The sender's sequence number travels with
+/// the message, authenticated along with it, so receivers can refuse frames
+/// they have already seen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope {
+    pub sender: Uuid,
+    /// Strictly increasing per sender
+    pub sequence: u64,
+    pub message: MeshMessage,
+}
+
 /// Load balancing strategies
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum LoadBalancingStrategy {
@@ -97,9 +127,47 @@ pub enum LoadBalancingStrategy {
     LeastConnections,
     WeightedRoundRobin,
     ConsistentHashing,
+    /// Highest weighted score of proficiency, load and task priority
+    /// (see [`ScoringWeights`])
     Capability,
 }
 
+/// Weights of the [`LoadBalancingStrategy::Capability`] selection score:
+///
+/// `proficiency * (proficiency_weight + priority_weight * levels) - load_weight * load`
+///
+/// where `levels` counts priority levels above `Low`, so more urgent tasks
+/// lean harder towards strong nodes while background work fills idle ones.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringWeights {
+    pub proficiency_weight: f64,
+    pub load_weight: f64,
+    pub priority_weight: f64,
+    /// Proficiency assumed for nodes that advertise none for a capability
+    pub default_proficiency: f64,
+}
+
+impl Default for ScoringWeights {
+    fn default() -> Self {
+        Self {
+            proficiency_weight: 1.0,
+            load_weight: 1.0,
+            priority_weight: 0.5,
+            default_proficiency: 0.5,
+        }
+    }
+}
+
+impl ScoringWeights {
+    /// Selection score of `node` for `task`; higher is better
+    pub fn score(&self, node: &MeshNode, task: &TaskRoute) -> f64 {
+        let proficiency = node.proficiency(&task.agent_type).unwrap_or(self.default_proficiency);
+        let levels = (task.priority as u8 - TaskPriority::Low as u8) as f64;
+        proficiency * (self.proficiency_weight + self.priority_weight * levels) - self.load_weight * node.load
+    }
+}
+
 /// Mesh network configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MeshConfig {
@@ -111,7 +179,18 @@ pub struct MeshConfig {
     pub max_task_retries: u32,
     pub load_balancing_strategy: LoadBalancingStrategy,
     pub enable_encryption: bool,
+    /// Shared secret every node in the cluster must hold; required when
+    /// `enable_encryption` is set
+    #[serde(default, skip_serializing)]
+    pub cluster_secret: Option<String>,
     pub max_concurrent_tasks: usize,
+    /// Weights used by the `Capability` load balancing strategy
+    #[serde(default)]
+    pub scoring: ScoringWeights,
+    /// Proficiency this node advertises per capability, e.g. a higher score
+    /// for LLM agents on a GPU node
+    #[serde(default)]
+    pub proficiency: HashMap<String, f64>,
 }
 
 impl Default for MeshConfig {
@@ -125,7 +204,10 @@ impl Default for MeshConfig {
             max_task_retries: 3,
             load_balancing_strategy: LoadBalancingStrategy::LeastConnections,
             enable_encryption: true,
+            cluster_secret: None,
             max_concurrent_tasks: 100,
+            scoring: ScoringWeights::default(),
+            proficiency: HashMap::new(),
         }
     }
 }
@@ -140,11 +222,17 @@ pub struct AgentMesh {
     load_balancer: Arc<LoadBalancer>,
     network_transport: Arc<NetworkTransport>,
     task_executor: Arc<TaskExecutor>,
+    /// Stops the heartbeat and message loops
+    shutdown: CancellationToken,
 }
 
 impl AgentMesh {
     /// Create a new agent mesh
     pub async fn new(config: MeshConfig) -> Result<Self> {
+        let mut metadata = HashMap::new();
+        if !config.proficiency.is_empty() {
+            metadata.insert(PROFICIENCY_METADATA_KEY.to_string(), serde_json::json!(config.proficiency));
+        }
         let local_node = MeshNode {
             id: config.node_id,
             address: config.bind_address,
@@ -152,7 +240,7 @@ impl AgentMesh {
             load: 0.0,
             status: NodeStatus::Joining,
             last_seen: chrono::Utc::now(),
-            metadata: HashMap::new(),
+            metadata,
         };
 
         let remote_nodes = Arc::new(DashMap::new());
@@ -172,6 +260,7 @@ impl AgentMesh {
             load_balancer,
             network_transport,
             task_executor,
+            shutdown: CancellationToken::new(),
         })
     }
 
@@ -219,19 +308,52 @@ impl AgentMesh {
         Ok(())
     }
 
-    /// Execute a task on the mesh network
-    #[instrument(skip(self, task))]
+    /// Execute a task on the mesh network.
+    ///
+    /// When delegation fails (node offline, timeout) the task is re-routed to
+    /// the next-best capable node it has not tried yet, up to `max_retries`
+    /// times. Failures reported by the agent itself are returned as-is.
+    #[instrument(skip(self, task), fields(task_id = %task.task_id))]
     pub async fn execute_task(&self, task: TaskRoute) -> Result<TaskResult> {
-        // Find best node for execution
-        let target_node = self.task_router.route_task(&task, &self.remote_nodes).await?;
+        let mut tried = HashSet::new();
+        let mut last_error: Option<anyhow::Error> = None;
+
+        for attempt in 1..=task.max_retries + 1 {
+            // Find best node for execution among those not yet tried
+            let target_node = match self.task_router.route_task(&task, &self.remote_nodes, &tried).await {
+                Ok(node) => node,
+                Err(e) => {
+                    return Err(match last_error {
+                        Some(last) => anyhow!("{} (last delegation error: {})", e, last),
+                        None => e,
+                    });
+                }
+            };
+
+            if target_node == self.local_node.id {
+                // Execute locally
+                return self.execute_local_task(task).await;
+            }
 
-        if target_node == self.local_node.id {
-            // Execute locally
-            self.execute_local_task(task).await
-        } else {
             // Delegate to remote node
-            self.delegate_task(task, target_node).await
+            let span = info_span!("mesh_delegation", attempt, node_id = %target_node);
+            match self.delegate_task(task.clone(), target_node).instrument(span).await {
+                Ok(result) => return Ok(result),
+                Err(e) => {
+                    warn!("Delegation of task {} to node {} failed (attempt {}): {}",
+                          task.task_id, target_node, attempt, e);
+                    tried.insert(target_node);
+                    last_error = Some(e);
+                }
+            }
         }
+
+        Err(anyhow!(
+            "Task {} failed after {} delegation attempts: {}",
+            task.task_id,
+            tried.len(),
+            last_error.map(|e| e.to_string()).unwrap_or_default()
+        ))
     }
 
     /// Execute task on local node
@@ -305,6 +427,7 @@ impl AgentMesh {
         let node_id = self.local_node.id;
         let transport = self.network_transport.clone();
         let interval = self.config.heartbeat_interval_secs;
+        let shutdown = self.shutdown.clone();
 
         tokio::spawn(async move {
             let mut heartbeat_interval = tokio::time::interval(
@@ -312,7 +435,10 @@ impl AgentMesh {
             );
 
             loop {
-                heartbeat_interval.tick().await;
+                tokio::select! {
+                    _ = heartbeat_interval.tick() => {}
+                    _ = shutdown.cancelled() => return,
+                }
                 
                 // Calculate current load
                 let load = calculate_system_load();
@@ -346,11 +472,17 @@ impl AgentMesh {
         let transport = self.network_transport.clone();
         let executor = self.task_executor.clone();
         let local_agents = self.local_agents.clone();
+        let remote_nodes = self.remote_nodes.clone();
+        let local_id = self.local_node.id;
+        let shutdown = self.shutdown.clone();
 
         tokio::spawn(async move {
             let mut message_receiver = transport.get_message_receiver().await;
             
-            while let Some(message) = message_receiver.recv().await {
+            while let Some(message) = tokio::select! {
+                message = message_receiver.recv() => message,
+                _ = shutdown.cancelled() => None,
+            } {
                 match message {
                     MeshMessage::TaskDelegation(task) => {
                         // Process delegated task
@@ -367,13 +499,23 @@ impl AgentMesh {
                             }
                         });
                     }
-                    MeshMessage::NodeAnnouncement(node) => {
-                        info!("New node joined: {}", node.id);
-                        // Handle node announcement
+                    MeshMessage::NodeAnnouncement(mut node) => {
+                        // Announcements carry capabilities and metadata such
+                        // as proficiency; the latest one replaces the entry
+                        if node.id != local_id {
+                            info!("Node announced: {}", node.id);
+                            node.status = NodeStatus::Healthy;
+                            node.last_seen = chrono::Utc::now();
+                            remote_nodes.insert(node.id, node);
+                        }
                     }
                     MeshMessage::Heartbeat { node_id, load } => {
                         // Update node load information
                         info!("Heartbeat from {}: load={}", node_id, load);
+                        if let Some(mut node) = remote_nodes.get_mut(&node_id) {
+                            node.load = load;
+                            node.last_seen = chrono::Utc::now();
+                        }
                     }
                     _ => {
                         // Handle other message types
@@ -383,6 +525,20 @@ impl AgentMesh {
         });
     }
 
+    /// Tell peers this node is leaving and stop the background loops.
+    /// Tasks already delegated to this node run to completion.
+    pub async fn shutdown(&self) -> Result<()> {
+        if self.shutdown.is_cancelled() {
+            return Ok(());
+        }
+        let mut leaving = self.local_node.clone();
+        leaving.status = NodeStatus::Leaving;
+        let announced = self.network_transport.broadcast(MeshMessage::NodeAnnouncement(leaving)).await;
+        self.shutdown.cancel();
+        info!("Agent mesh node {} left the mesh", self.local_node.id);
+        announced
+    }
+
     /// Announce capabilities to the network
     async fn announce_capabilities(&self) -> Result<()> {
         let announcement = MeshMessage::NodeAnnouncement(self.local_node.clone());
@@ -411,11 +567,12 @@ impl TaskRouter {
         Self { config }
     }
 
-    /// Route task to the best available node
+    /// Route task to the best available node, skipping any in `exclude`
     pub async fn route_task(
         &self,
         task: &TaskRoute,
         nodes: &DashMap<Uuid, MeshNode>,
+        exclude: &HashSet<Uuid>,
     ) -> Result<Uuid> {
         // Find nodes with required capability
         let capable_nodes: Vec<_> = nodes
@@ -423,7 +580,8 @@ impl TaskRouter {
             .filter(|entry| {
                 let node = entry.value();
                 node.status == NodeStatus::Healthy &&
-                node.capabilities.contains(&task.agent_type)
+                node.capabilities.contains(&task.agent_type) &&
+                !exclude.contains(&node.id)
             })
             .collect();
 
@@ -446,6 +604,19 @@ impl TaskRouter {
                 let index = (task.task_id.as_u128() % capable_nodes.len() as u128) as usize;
                 capable_nodes[index].value().id
             }
+            LoadBalancingStrategy::Capability => {
+                let scoring = &self.config.scoring;
+                capable_nodes
+                    .iter()
+                    .max_by(|a, b| {
+                        scoring.score(a.value(), task)
+                            .partial_cmp(&scoring.score(b.value(), task))
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                    })
+                    .unwrap()
+                    .value()
+                    .id
+            }
             _ => capable_nodes[0].value().id, // Default to first available
         };
 
@@ -464,22 +635,219 @@ impl LoadBalancer {
     }
 }
 
+/// Length of the random nonce prefixed to every encrypted frame
+const NONCE_LEN: usize = 12;
+
+/// Length of the random challenge a node sends to a connecting peer
+const CHALLENGE_LEN: usize = 32;
+
+/// Associated data binding ciphertexts to their purpose, so a sealed message
+/// can never be replayed as a handshake response or vice versa
+const MESSAGE_AAD: &[u8] = b"acropolis-mesh/message";
+const HANDSHAKE_AAD: &[u8] = b"acropolis-mesh/handshake";
+
+/// AES-256-GCM cipher keyed from the cluster secret
+pub struct MeshCipher {
+    cipher: Aes256Gcm,
+}
+
+impl MeshCipher {
+    pub fn from_secret(secret: &str) -> Result<Self> {
+        if secret.is_empty() {
+            return Err(anyhow!("Mesh cluster secret cannot be empty"));
+        }
+        let key = blake3::derive_key("acropolis mesh v1 cluster key", secret.as_bytes());
+        Ok(Self { cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)) })
+    }
+
+    /// Encrypt `plaintext` into `nonce || ciphertext`
+    fn seal(&self, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher
+            .encrypt(&nonce, Payload { msg: plaintext, aad })
+            .map_err(|_| anyhow!("Failed to encrypt mesh frame"))?;
+        let mut frame = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        frame.extend_from_slice(&nonce);
+        frame.extend_from_slice(&ciphertext);
+        Ok(frame)
+    }
+
+    /// Decrypt a frame produced by `seal`; fails on a wrong key or tampering
+    fn open(&self, frame: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        if frame.len() < NONCE_LEN {
+            return Err(anyhow!("Mesh frame too short"));
+        }
+        let (nonce, ciphertext) = frame.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+            .map_err(|_| anyhow!("Failed to decrypt mesh frame (wrong cluster secret or corrupted data)"))
+    }
+}
+
+/// Wire encoding of mesh messages, encrypted unless disabled in `MeshConfig`
+pub enum MessageCodec {
+    Plaintext,
+    Encrypted(MeshCipher),
+}
+
+impl MessageCodec {
+    pub fn from_config(config: &MeshConfig) -> Result<Self> {
+        if !config.enable_encryption {
+            warn!("Mesh encryption is disabled; inter-node traffic is sent in the clear");
+            return Ok(Self::Plaintext);
+        }
+        let secret = config.cluster_secret.as_deref()
+            .ok_or_else(|| anyhow!("Mesh encryption is enabled but no cluster secret is configured"))?;
+        Ok(Self::Encrypted(MeshCipher::from_secret(secret)?))
+    }
+
+    pub fn encode(&self, envelope: &Envelope) -> Result<Vec<u8>> {
+        let bytes = serde_json::to_vec(envelope)?;
+        match self {
+            Self::Plaintext => Ok(bytes),
+            Self::Encrypted(cipher) => cipher.seal(&bytes, MESSAGE_AAD),
+        }
+    }
+
+    pub fn decode(&self, frame: &[u8]) -> Result<Envelope> {
+        let bytes = match self {
+            Self::Plaintext => frame.to_vec(),
+            Self::Encrypted(cipher) => cipher.open(frame, MESSAGE_AAD)?,
+        };
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Random challenge sent to a connecting peer
+    pub fn handshake_challenge(&self) -> Vec<u8> {
+        let mut challenge = vec![0u8; CHALLENGE_LEN];
+        OsRng.fill_bytes(&mut challenge);
+        challenge
+    }
+
+    /// Prove possession of the cluster key by sealing the peer's challenge.
+    /// The responder's id is bound in so a challenge cannot be reflected back.
+    pub fn answer_handshake(&self, challenge: &[u8], responder: Uuid) -> Result<Vec<u8>> {
+        match self {
+            Self::Plaintext => Ok(Vec::new()),
+            Self::Encrypted(cipher) => cipher.seal(challenge, &handshake_aad(responder)),
+        }
+    }
+
+    /// Reject peers that cannot answer our challenge with the cluster key
+    pub fn verify_handshake(&self, challenge: &[u8], response: &[u8], peer: Uuid) -> Result<()> {
+        match self {
+            Self::Plaintext => Ok(()),
+            Self::Encrypted(cipher) => {
+                let answered = cipher.open(response, &handshake_aad(peer))
+                    .map_err(|_| anyhow!("Mesh handshake rejected for node {}: wrong cluster secret", peer))?;
+                if answered != challenge {
+                    return Err(anyhow!("Mesh handshake rejected for node {}: challenge mismatch", peer));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn handshake_aad(node_id: Uuid) -> Vec<u8> {
+    let mut aad = HANDSHAKE_AAD.to_vec();
+    aad.extend_from_slice(node_id.as_bytes());
+    aad
+}
+
 /// Network transport layer
 pub struct NetworkTransport {
     config: MeshConfig,
+    codec: MessageCodec,
     message_sender: Option<mpsc::Sender<MeshMessage>>,
     task_results: Arc<DashMap<Uuid, oneshot::Sender<TaskResult>>>,
+    /// Sequence number of the next frame sent. Starts from the clock, so a
+    /// restarted node carries on above the numbers peers have already seen.
+    next_sequence: std::sync::atomic::AtomicU64,
+    /// Highest sequence number accepted from each peer
+    peer_sequences: DashMap<Uuid, u64>,
+    /// Challenges sent to peers that have not answered yet
+    pending_challenges: DashMap<Uuid, Vec<u8>>,
+    /// Peers that answered a challenge with the cluster key
+    verified_peers: dashmap::DashSet<Uuid>,
 }
 
 impl NetworkTransport {
     pub async fn new(config: MeshConfig) -> Result<Self> {
+        let codec = MessageCodec::from_config(&config)?;
+        let now_micros = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_micros() as u64);
         Ok(Self {
             config,
+            codec,
             message_sender: None,
             task_results: Arc::new(DashMap::new()),
+            next_sequence: std::sync::atomic::AtomicU64::new(now_micros),
+            peer_sequences: DashMap::new(),
+            pending_challenges: DashMap::new(),
+            verified_peers: dashmap::DashSet::new(),
         })
     }
 
+    /// Codec used for every frame and handshake on this transport
+    pub fn codec(&self) -> &MessageCodec {
+        &self.codec
+    }
+
+    /// Encode `message` as the next frame from this node
+    pub fn frame(&self, message: MeshMessage) -> Result<Vec<u8>> {
+        let sequence = self.next_sequence.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.codec.encode(&Envelope { sender: self.config.node_id, sequence, message })
+    }
+
+    /// Start a handshake with `peer`, returning the challenge to send it
+    pub fn challenge_peer(&self, peer: Uuid) -> Vec<u8> {
+        let challenge = self.codec.handshake_challenge();
+        self.pending_challenges.insert(peer, challenge.clone());
+        challenge
+    }
+
+    /// Answer a challenge a peer sent to this node
+    pub fn answer_challenge(&self, challenge: &[u8]) -> Result<Vec<u8>> {
+        self.codec.answer_handshake(challenge, self.config.node_id)
+    }
+
+    /// Check `peer`'s answer to our challenge; only verified peers' frames
+    /// are accepted when encryption is enabled
+    pub fn complete_handshake(&self, peer: Uuid, response: &[u8]) -> Result<()> {
+        let (_, challenge) = self.pending_challenges.remove(&peer)
+            .ok_or_else(|| anyhow!("No mesh handshake in progress with node {}", peer))?;
+        self.codec.verify_handshake(&challenge, response, peer)?;
+        self.verified_peers.insert(peer);
+        info!("Mesh handshake with node {} succeeded", peer);
+        Ok(())
+    }
+
+    /// Decode a received frame, rejecting frames from peers that have not
+    /// completed a handshake and frames already seen from a peer
+    pub fn receive(&self, frame: &[u8]) -> Result<MeshMessage> {
+        let envelope = self.codec.decode(frame)?;
+        if matches!(self.codec, MessageCodec::Encrypted(_)) && !self.verified_peers.contains(&envelope.sender) {
+            return Err(anyhow!("Rejected mesh frame from unverified node {}", envelope.sender));
+        }
+        match self.peer_sequences.entry(envelope.sender) {
+            dashmap::mapref::entry::Entry::Occupied(mut last) => {
+                if envelope.sequence <= *last.get() {
+                    return Err(anyhow!(
+                        "Rejected replayed mesh frame {} from node {}",
+                        envelope.sequence, envelope.sender
+                    ));
+                }
+                last.insert(envelope.sequence);
+            }
+            dashmap::mapref::entry::Entry::Vacant(entry) => {
+                entry.insert(envelope.sequence);
+            }
+        }
+        Ok(envelope.message)
+    }
+
     pub async fn start(&mut self) -> Result<()> {
         // In a real implementation, this would start TCP/UDP listeners
         // For now, we'll simulate with channels
@@ -488,18 +856,21 @@ impl NetworkTransport {
         Ok(())
     }
 
-    pub async fn broadcast(&self, _message: MeshMessage) -> Result<()> {
-        // Simulate network broadcast
+    pub async fn broadcast(&self, message: MeshMessage) -> Result<()> {
+        // Simulate network broadcast of the encoded frame
+        let _frame = self.frame(message)?;
         Ok(())
     }
 
-    pub async fn send_to_node(&self, _node_id: Uuid, _message: MeshMessage) -> Result<()> {
-        // Simulate sending to specific node
+    pub async fn send_to_node(&self, _node_id: Uuid, message: MeshMessage) -> Result<()> {
+        // Simulate sending the encoded frame to specific node
+        let _frame = self.frame(message)?;
         Ok(())
     }
 
-    pub async fn send_to_address(&self, _addr: SocketAddr, _message: MeshMessage) -> Result<()> {
-        // Simulate sending to specific address
+    pub async fn send_to_address(&self, _addr: SocketAddr, message: MeshMessage) -> Result<()> {
+        // Simulate sending the encoded frame to specific address
+        let _frame = self.frame(message)?;
         Ok(())
     }
 
@@ -571,4 +942,192 @@ fn calculate_system_load() -> f64 {
     // This would use actual system metrics in production
     // For now, return a simulated value
     0.5
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(load: f64, status: NodeStatus) -> MeshNode {
+        MeshNode {
+            id: Uuid::new_v4(),
+            address: "127.0.0.1:7001".parse().unwrap(),
+            capabilities: vec!["echo".to_string()],
+            load,
+            status,
+            last_seen: chrono::Utc::now(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    fn encrypted_codec(secret: &str) -> MessageCodec {
+        MessageCodec::from_config(&MeshConfig {
+            cluster_secret: Some(secret.to_string()),
+            ..MeshConfig::default()
+        }).unwrap()
+    }
+
+    fn envelope(message: MeshMessage) -> Envelope {
+        Envelope { sender: Uuid::new_v4(), sequence: 1, message }
+    }
+
+    #[test]
+    fn test_encrypted_codec_round_trip_and_wrong_key() {
+        let codec = encrypted_codec("cluster-secret");
+        let message = envelope(MeshMessage::Heartbeat { node_id: Uuid::new_v4(), load: 0.25 });
+
+        let frame = codec.encode(&message).unwrap();
+        assert!(!String::from_utf8_lossy(&frame).contains("Heartbeat"));
+        assert!(matches!(codec.decode(&frame).unwrap().message, MeshMessage::Heartbeat { load, .. } if load == 0.25));
+
+        assert!(encrypted_codec("other-secret").decode(&frame).is_err());
+
+        let mut tampered = frame.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(codec.decode(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_handshake_rejects_wrong_key_and_reflection() {
+        let local = encrypted_codec("cluster-secret");
+        let peer_id = Uuid::new_v4();
+        let challenge = local.handshake_challenge();
+
+        let good = encrypted_codec("cluster-secret").answer_handshake(&challenge, peer_id).unwrap();
+        assert!(local.verify_handshake(&challenge, &good, peer_id).is_ok());
+
+        let bad = encrypted_codec("wrong-secret").answer_handshake(&challenge, peer_id).unwrap();
+        assert!(local.verify_handshake(&challenge, &bad, peer_id).is_err());
+
+        // An answer produced by another node cannot be passed off as the peer's
+        let reflected = local.answer_handshake(&challenge, Uuid::new_v4()).unwrap();
+        assert!(local.verify_handshake(&challenge, &reflected, peer_id).is_err());
+    }
+
+    #[test]
+    fn test_codec_requires_secret_unless_disabled() {
+        assert!(MessageCodec::from_config(&MeshConfig::default()).is_err());
+
+        let plain = MessageCodec::from_config(&MeshConfig {
+            enable_encryption: false,
+            ..MeshConfig::default()
+        }).unwrap();
+        let frame = plain.encode(&envelope(MeshMessage::CapabilityQuery { requested_capability: "echo".into() })).unwrap();
+        assert!(String::from_utf8_lossy(&frame).contains("echo"));
+    }
+
+    async fn transport(secret: &str) -> NetworkTransport {
+        NetworkTransport::new(MeshConfig {
+            node_id: Uuid::new_v4(),
+            cluster_secret: Some(secret.to_string()),
+            ..MeshConfig::default()
+        }).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_transport_requires_handshake_and_rejects_replays() {
+        let local = transport("cluster-secret").await;
+        let peer = transport("cluster-secret").await;
+        let peer_id = peer.config.node_id;
+        let heartbeat = || MeshMessage::Heartbeat { node_id: peer_id, load: 0.5 };
+
+        let early = peer.frame(heartbeat()).unwrap();
+        assert!(local.receive(&early).is_err());
+
+        let challenge = local.challenge_peer(peer_id);
+        let answer = peer.answer_challenge(&challenge).unwrap();
+        local.complete_handshake(peer_id, &answer).unwrap();
+        assert!(local.complete_handshake(peer_id, &answer).is_err());
+
+        let first = peer.frame(heartbeat()).unwrap();
+        let second = peer.frame(heartbeat()).unwrap();
+        assert!(local.receive(&first).is_ok());
+        assert!(local.receive(&first).is_err());
+        assert!(local.receive(&second).is_ok());
+        // Frames older than the last one accepted count as replays too
+        assert!(local.receive(&early).is_err());
+
+        let impostor = transport("wrong-secret").await;
+        let challenge = local.challenge_peer(impostor.config.node_id);
+        let answer = impostor.answer_challenge(&challenge).unwrap();
+        assert!(local.complete_handshake(impostor.config.node_id, &answer).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_background_loops_once() {
+        let mut mesh = AgentMesh::new(MeshConfig {
+            cluster_secret: Some("cluster-secret".to_string()),
+            ..MeshConfig::default()
+        }).await.unwrap();
+        mesh.start().await.unwrap();
+        mesh.shutdown().await.unwrap();
+        assert!(mesh.shutdown.is_cancelled());
+        mesh.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_route_task_skips_tried_and_unhealthy_nodes() {
+        let router = TaskRouter::new(MeshConfig::default());
+        let nodes = DashMap::new();
+        let idle = node(0.1, NodeStatus::Healthy);
+        let busy = node(0.8, NodeStatus::Healthy);
+        let offline = node(0.0, NodeStatus::Offline);
+        for n in [&idle, &busy, &offline] {
+            nodes.insert(n.id, n.clone());
+        }
+
+        let task = TaskRoute {
+            task_id: Uuid::new_v4(),
+            agent_type: "echo".to_string(),
+            payload: serde_json::json!("hi"),
+            priority: TaskPriority::Normal,
+            max_retries: 2,
+            timeout_seconds: 5,
+            routing_hints: HashMap::new(),
+        };
+
+        let mut tried = HashSet::new();
+        assert_eq!(router.route_task(&task, &nodes, &tried).await.unwrap(), idle.id);
+
+        tried.insert(idle.id);
+        assert_eq!(router.route_task(&task, &nodes, &tried).await.unwrap(), busy.id);
+
+        tried.insert(busy.id);
+        assert!(router.route_task(&task, &nodes, &tried).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_capability_scoring_sends_heavy_tasks_to_strong_nodes() {
+        let router = TaskRouter::new(MeshConfig {
+            load_balancing_strategy: LoadBalancingStrategy::Capability,
+            ..MeshConfig::default()
+        });
+        let mut strong = node(0.9, NodeStatus::Healthy);
+        strong.metadata.insert(PROFICIENCY_METADATA_KEY.to_string(), serde_json::json!({ "echo": 0.9 }));
+        let mut weak = node(0.1, NodeStatus::Healthy);
+        weak.metadata.insert(PROFICIENCY_METADATA_KEY.to_string(), serde_json::json!({ "echo": 0.2 }));
+        let nodes = DashMap::new();
+        for n in [&strong, &weak] {
+            nodes.insert(n.id, n.clone());
+        }
+
+        let task = |priority| TaskRoute {
+            task_id: Uuid::new_v4(),
+            agent_type: "echo".to_string(),
+            payload: serde_json::json!("hi"),
+            priority,
+            max_retries: 0,
+            timeout_seconds: 5,
+            routing_hints: HashMap::new(),
+        };
+
+        // Urgent work outweighs the strong node's load; background work
+        // goes to the idle node
+        let none = HashSet::new();
+        assert_eq!(router.route_task(&task(TaskPriority::High), &nodes, &none).await.unwrap(), strong.id);
+        assert_eq!(router.route_task(&task(TaskPriority::Low), &nodes, &none).await.unwrap(), weak.id);
+
+        // Nodes without a score for the capability get the default
+        assert_eq!(strong.proficiency("echo"), Some(0.9));
+        assert_eq!(strong.proficiency("llm"), None);
+    }
+}
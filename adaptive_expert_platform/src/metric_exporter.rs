@@ -0,0 +1,146 @@
+//! Pluggable sinks for periodic metrics snapshots.
+//!
+//! The monitoring system builds a [`MetricsSnapshot`] on every collection tick
+//! and hands it to each registered [`MetricExporter`]. Prometheus is one such
+//! exporter; StatsD, CloudWatch or custom sinks implement the same trait and
+//! are installed with `MonitoringSystem::add_exporter`.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use tracing::info;
+
+use crate::monitoring::{AgentMetrics, SystemMetrics};
+
+/// Point-in-time view of system and per-agent metrics
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsSnapshot {
+    pub timestamp: DateTime<Utc>,
+    pub system: SystemMetrics,
+    pub agents: HashMap<String, AgentMetrics>,
+}
+
+/// Destination for metrics snapshots.
+///
+/// Exports run on the collection loop, so a slow exporter delays the next
+/// tick; exporters talking to remote services should buffer or spawn.
+#[async_trait]
+pub trait MetricExporter: Send + Sync {
+    /// Short name used in logs
+    fn name(&self) -> &str;
+
+    async fn export(&self, snapshot: &MetricsSnapshot);
+}
+
+/// Discards every snapshot
+pub struct NoopExporter;
+
+#[async_trait]
+impl MetricExporter for NoopExporter {
+    fn name(&self) -> &str {
+        "noop"
+    }
+
+    async fn export(&self, _snapshot: &MetricsSnapshot) {}
+}
+
+/// Logs a one-line summary of every snapshot
+pub struct LoggingExporter;
+
+#[async_trait]
+impl MetricExporter for LoggingExporter {
+    fn name(&self) -> &str {
+        "logging"
+    }
+
+    async fn export(&self, snapshot: &MetricsSnapshot) {
+        let total_requests: u64 = snapshot.agents.values().map(|m| m.total_requests).sum();
+        let failed_requests: u64 = snapshot.agents.values().map(|m| m.failed_requests).sum();
+        info!(
+            "Metrics: uptime={}s cpu={:.1}% memory={}B agents={} requests={} failed={}",
+            snapshot.system.uptime_seconds,
+            snapshot.system.cpu_usage_percent,
+            snapshot.system.used_memory_bytes,
+            snapshot.agents.len(),
+            total_requests,
+            failed_requests,
+        );
+    }
+}
+
+/// Publishes snapshot gauges through the `metrics` facade scraped by the
+/// Prometheus endpoint
+#[cfg(feature = "with-metrics")]
+pub struct PrometheusExporter;
+
+#[cfg(feature = "with-metrics")]
+#[async_trait]
+impl MetricExporter for PrometheusExporter {
+    fn name(&self) -> &str {
+        "prometheus"
+    }
+
+    async fn export(&self, snapshot: &MetricsSnapshot) {
+        use metrics::gauge;
+
+        gauge!("system_memory_used_bytes").set(snapshot.system.used_memory_bytes as f64);
+        gauge!("system_cpu_usage_percent").set(snapshot.system.cpu_usage_percent);
+        gauge!("system_active_connections").set(snapshot.system.active_connections as f64);
+
+        for (agent, metrics) in &snapshot.agents {
+            gauge!("agent_memory_usage_bytes", "agent" => agent.clone())
+                .set(metrics.memory_usage_bytes as f64);
+            gauge!("agent_cpu_usage_percent", "agent" => agent.clone())
+                .set(metrics.cpu_usage_percent);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitoring::{MonitoringConfig, MonitoringSystem};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingExporter {
+        snapshots: Mutex<Vec<MetricsSnapshot>>,
+    }
+
+    #[async_trait]
+    impl MetricExporter for RecordingExporter {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        async fn export(&self, snapshot: &MetricsSnapshot) {
+            self.snapshots.lock().await.push(snapshot.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_fans_out_to_exporters() {
+        let monitoring = MonitoringSystem::new(MonitoringConfig::default());
+        let first = Arc::new(RecordingExporter::default());
+        let second = Arc::new(RecordingExporter::default());
+        monitoring.add_exporter(first.clone()).await;
+        monitoring.add_exporter(second.clone()).await;
+        monitoring.add_exporter(Arc::new(LoggingExporter)).await;
+        monitoring.add_exporter(Arc::new(NoopExporter)).await;
+
+        monitoring.record_agent_request("echo", true, Duration::from_millis(5)).await;
+        monitoring.record_agent_request("echo", false, Duration::from_millis(5)).await;
+        monitoring.export_snapshot().await;
+
+        for exporter in [&first, &second] {
+            let snapshots = exporter.snapshots.lock().await;
+            assert_eq!(snapshots.len(), 1);
+            let echo = &snapshots[0].agents["echo"];
+            assert_eq!(echo.total_requests, 2);
+            assert_eq!(echo.failed_requests, 1);
+        }
+    }
+}
@@ -1,5 +1,9 @@
 //! Metrics and tracing utilities with conditional OpenTelemetry support.
 
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Serialize;
+
 #[cfg(feature = "with-observability")]
 mod metrics_impl {
     use anyhow::Result;
@@ -34,3 +38,207 @@ pub fn init_metrics(_otlp_endpoint: Option<&str>) -> anyhow::Result<()> {
     // No-op when observability features are disabled
     Ok(())
 }
+
+/// Platform health metric names, exported through the `metrics` facade when
+/// the `with-metrics` feature is enabled:
+///
+/// | Name                          | Kind    | Meaning                                                          |
+/// |-------------------------------|---------|------------------------------------------------------------------|
+/// | `plugins_loaded_total`        | counter | Native plugin libraries loaded                                   |
+/// | `plugins_failed_total`        | counter | Native plugin load attempts that failed                          |
+/// | `agents_registered`           | gauge   | Agents currently registered                                      |
+/// | `agents_in_maintenance`       | gauge   | Registered agents disabled by an admin                           |
+/// | `julia_queue_depth`           | gauge   | Julia calls waiting for a runtime slot                           |
+/// | `subprocesses_running`        | gauge   | Child processes agents are running, across all agents            |
+/// | `dispatch_total`              | counter | Tasks dispatched, streaming included                             |
+/// | `dispatch_errors_total`       | counter | Dispatched tasks that ended in an error                          |
+/// | `cost_estimated_tokens_total` | counter | Tokens agents estimated for calls that also reported actual cost |
+/// | `cost_actual_tokens_total`    | counter | Tokens those same calls actually consumed                        |
+/// | `output_schema_errors_total`  | counter | Agent outputs that did not match the agent's output schema       |
+pub mod names {
+    pub const PLUGINS_LOADED_TOTAL: &str = "plugins_loaded_total";
+    pub const PLUGINS_FAILED_TOTAL: &str = "plugins_failed_total";
+    pub const AGENTS_REGISTERED: &str = "agents_registered";
+    pub const AGENTS_IN_MAINTENANCE: &str = "agents_in_maintenance";
+    pub const JULIA_QUEUE_DEPTH: &str = "julia_queue_depth";
+    pub const SUBPROCESSES_RUNNING: &str = "subprocesses_running";
+    pub const DISPATCH_TOTAL: &str = "dispatch_total";
+    pub const DISPATCH_ERRORS_TOTAL: &str = "dispatch_errors_total";
+    pub const COST_ESTIMATED_TOKENS_TOTAL: &str = "cost_estimated_tokens_total";
+    pub const COST_ACTUAL_TOKENS_TOTAL: &str = "cost_actual_tokens_total";
+    pub const OUTPUT_SCHEMA_ERRORS_TOTAL: &str = "output_schema_errors_total";
+}
+
+/// Process-wide health counters, independent of per-agent metrics.
+///
+/// Values are always kept in process for `/metrics`; publishing them through
+/// the `metrics` facade is a no-op without the `with-metrics` feature.
+pub struct PlatformMetrics {
+    plugins_loaded: AtomicU64,
+    plugins_failed: AtomicU64,
+    agents_registered: AtomicU64,
+    agents_in_maintenance: AtomicU64,
+    julia_queue_depth: AtomicU64,
+    subprocesses_running: AtomicU64,
+    dispatches: AtomicU64,
+    dispatch_errors: AtomicU64,
+    cost_estimated_tokens: AtomicU64,
+    cost_actual_tokens: AtomicU64,
+    output_schema_errors: AtomicU64,
+}
+
+/// Point-in-time copy of [`PlatformMetrics`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PlatformMetricsSnapshot {
+    pub plugins_loaded_total: u64,
+    pub plugins_failed_total: u64,
+    pub agents_registered: u64,
+    pub agents_in_maintenance: u64,
+    pub julia_queue_depth: u64,
+    pub subprocesses_running: u64,
+    pub dispatch_total: u64,
+    pub dispatch_errors_total: u64,
+    pub cost_estimated_tokens_total: u64,
+    pub cost_actual_tokens_total: u64,
+    pub output_schema_errors_total: u64,
+}
+
+static PLATFORM: PlatformMetrics = PlatformMetrics {
+    plugins_loaded: AtomicU64::new(0),
+    plugins_failed: AtomicU64::new(0),
+    agents_registered: AtomicU64::new(0),
+    agents_in_maintenance: AtomicU64::new(0),
+    julia_queue_depth: AtomicU64::new(0),
+    subprocesses_running: AtomicU64::new(0),
+    dispatches: AtomicU64::new(0),
+    dispatch_errors: AtomicU64::new(0),
+    cost_estimated_tokens: AtomicU64::new(0),
+    cost_actual_tokens: AtomicU64::new(0),
+    output_schema_errors: AtomicU64::new(0),
+};
+
+/// The process-wide platform counters
+pub fn platform() -> &'static PlatformMetrics {
+    &PLATFORM
+}
+
+impl PlatformMetrics {
+    /// Record the outcome of loading one plugin library
+    pub fn record_plugin_load(&self, ok: bool) {
+        let (counter, name) = if ok {
+            (&self.plugins_loaded, names::PLUGINS_LOADED_TOTAL)
+        } else {
+            (&self.plugins_failed, names::PLUGINS_FAILED_TOTAL)
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        increment(name);
+    }
+
+    /// Record the number of agents now registered
+    pub fn set_agents_registered(&self, count: usize) {
+        self.agents_registered.store(count as u64, Ordering::Relaxed);
+        #[cfg(feature = "with-metrics")]
+        ::metrics::gauge!(names::AGENTS_REGISTERED).set(count as f64);
+    }
+
+    /// Record the number of registered agents now in maintenance
+    pub fn set_agents_in_maintenance(&self, count: usize) {
+        self.agents_in_maintenance.store(count as u64, Ordering::Relaxed);
+        #[cfg(feature = "with-metrics")]
+        ::metrics::gauge!(names::AGENTS_IN_MAINTENANCE).set(count as f64);
+    }
+
+    /// Record the number of Julia calls waiting for a runtime slot
+    pub fn set_julia_queue_depth(&self, depth: usize) {
+        self.julia_queue_depth.store(depth as u64, Ordering::Relaxed);
+        #[cfg(feature = "with-metrics")]
+        ::metrics::gauge!(names::JULIA_QUEUE_DEPTH).set(depth as f64);
+    }
+
+    /// Record a subprocess slot being taken (`true`) or released (`false`)
+    pub fn record_subprocess(&self, started: bool) {
+        let running = if started {
+            self.subprocesses_running.fetch_add(1, Ordering::Relaxed) + 1
+        } else {
+            self.subprocesses_running.fetch_sub(1, Ordering::Relaxed) - 1
+        };
+        #[cfg(feature = "with-metrics")]
+        ::metrics::gauge!(names::SUBPROCESSES_RUNNING).set(running as f64);
+        #[cfg(not(feature = "with-metrics"))]
+        let _ = running;
+    }
+
+    /// Record a finished dispatch and whether it failed
+    pub fn record_dispatch(&self, ok: bool) {
+        self.dispatches.fetch_add(1, Ordering::Relaxed);
+        increment(names::DISPATCH_TOTAL);
+        if !ok {
+            self.dispatch_errors.fetch_add(1, Ordering::Relaxed);
+            increment(names::DISPATCH_ERRORS_TOTAL);
+        }
+    }
+
+    /// Record a call's estimated and actual token cost, for calls whose agent
+    /// reported both
+    pub fn record_cost(&self, estimated_tokens: u64, actual_tokens: u64) {
+        self.cost_estimated_tokens.fetch_add(estimated_tokens, Ordering::Relaxed);
+        self.cost_actual_tokens.fetch_add(actual_tokens, Ordering::Relaxed);
+        #[cfg(feature = "with-metrics")]
+        {
+            ::metrics::counter!(names::COST_ESTIMATED_TOKENS_TOTAL).increment(estimated_tokens);
+            ::metrics::counter!(names::COST_ACTUAL_TOKENS_TOTAL).increment(actual_tokens);
+        }
+    }
+
+    /// Record an agent output that failed its schema check, whether or not
+    /// the call was failed for it
+    pub fn record_output_schema_violation(&self) {
+        self.output_schema_errors.fetch_add(1, Ordering::Relaxed);
+        increment(names::OUTPUT_SCHEMA_ERRORS_TOTAL);
+    }
+
+    pub fn snapshot(&self) -> PlatformMetricsSnapshot {
+        PlatformMetricsSnapshot {
+            plugins_loaded_total: self.plugins_loaded.load(Ordering::Relaxed),
+            plugins_failed_total: self.plugins_failed.load(Ordering::Relaxed),
+            agents_registered: self.agents_registered.load(Ordering::Relaxed),
+            agents_in_maintenance: self.agents_in_maintenance.load(Ordering::Relaxed),
+            julia_queue_depth: self.julia_queue_depth.load(Ordering::Relaxed),
+            subprocesses_running: self.subprocesses_running.load(Ordering::Relaxed),
+            dispatch_total: self.dispatches.load(Ordering::Relaxed),
+            dispatch_errors_total: self.dispatch_errors.load(Ordering::Relaxed),
+            cost_estimated_tokens_total: self.cost_estimated_tokens.load(Ordering::Relaxed),
+            cost_actual_tokens_total: self.cost_actual_tokens.load(Ordering::Relaxed),
+            output_schema_errors_total: self.output_schema_errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(feature = "with-metrics")]
+fn increment(name: &'static str) {
+    ::metrics::counter!(name).increment(1);
+}
+
+#[cfg(not(feature = "with-metrics"))]
+fn increment(_name: &'static str) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_platform_counters() {
+        // Counters are process-wide, so compare against a baseline
+        let before = platform().snapshot();
+        platform().record_plugin_load(true);
+        platform().record_plugin_load(false);
+        platform().record_dispatch(true);
+        platform().record_dispatch(false);
+
+        let after = platform().snapshot();
+        assert!(after.plugins_loaded_total > before.plugins_loaded_total);
+        assert!(after.plugins_failed_total > before.plugins_failed_total);
+        assert!(after.dispatch_total >= before.dispatch_total + 2);
+        assert!(after.dispatch_errors_total > before.dispatch_errors_total);
+    }
+}
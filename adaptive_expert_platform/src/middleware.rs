@@ -3,9 +3,9 @@
 use axum::{
     body::Body,
     extract::{Request, State},
-    http::{HeaderMap, HeaderValue, Method, StatusCode},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
 use governor::{
     clock::DefaultClock,
@@ -24,24 +24,44 @@ use crate::settings::SecurityConfig;
 /// Rate limiter type
 pub type AppRateLimiter = RateLimiter<NotKeyed, InMemoryState, DefaultClock>;
 
+/// Rate limiter whose quota can be replaced while the server runs
+pub struct SharedRateLimiter {
+    limiter: parking_lot::RwLock<Arc<AppRateLimiter>>,
+}
+
+impl SharedRateLimiter {
+    pub fn new(per_minute: NonZeroU32) -> Self {
+        Self { limiter: parking_lot::RwLock::new(Arc::new(RateLimiter::direct(Quota::per_minute(per_minute)))) }
+    }
+
+    /// Whether a request may proceed under the current quota
+    pub fn check(&self) -> bool {
+        self.limiter.read().check().is_ok()
+    }
+
+    /// Switch to a new quota. The replacement starts with a full burst, so
+    /// requests already counted against the old quota are forgotten.
+    pub fn set_per_minute(&self, per_minute: NonZeroU32) {
+        *self.limiter.write() = Arc::new(RateLimiter::direct(Quota::per_minute(per_minute)));
+    }
+}
+
 /// Create rate limiter from configuration
-pub fn create_rate_limiter(config: &SecurityConfig) -> Arc<AppRateLimiter> {
-    let quota = Quota::per_minute(NonZeroU32::new(config.rate_limit_per_minute).unwrap());
-    Arc::new(RateLimiter::direct(quota))
+pub fn create_rate_limiter(config: &SecurityConfig) -> Arc<SharedRateLimiter> {
+    Arc::new(SharedRateLimiter::new(NonZeroU32::new(config.rate_limit_per_minute).unwrap()))
 }
 
 /// Rate limiting middleware
 pub async fn rate_limit_middleware(
-    State(rate_limiter): State<Arc<AppRateLimiter>>,
+    State(rate_limiter): State<Arc<SharedRateLimiter>>,
     request: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    match rate_limiter.check() {
-        Ok(_) => Ok(next.run(request).await),
-        Err(_) => {
-            warn!("Rate limit exceeded for request to {}", request.uri().path());
-            Err(StatusCode::TOO_MANY_REQUESTS)
-        }
+    if rate_limiter.check() {
+        Ok(next.run(request).await)
+    } else {
+        warn!("Rate limit exceeded for request to {}", request.uri().path());
+        Err(StatusCode::TOO_MANY_REQUESTS)
     }
 }
 
@@ -75,6 +95,117 @@ pub async fn security_headers_middleware(
     Ok(response)
 }
 
+/// Media type a client sends in `Accept` to get enveloped responses
+pub const ENVELOPE_MEDIA_TYPE: &str = "application/vnd.acropolis+json";
+
+/// Correlation data attached to enveloped responses
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct EnvelopeMeta {
+    pub request_id: String,
+    pub duration_ms: u64,
+    pub trace_id: String,
+}
+
+/// Wrap JSON responses as `{"data": ..., "meta": {...}}` and failures as
+/// `{"error": {...}, "meta": {...}}` for clients that accept
+/// [`ENVELOPE_MEDIA_TYPE`]. Other clients, and non-JSON successes such as
+/// event streams, get the handler's response unchanged.
+///
+/// The request id comes from `X-Request-Id` and the trace id from a W3C
+/// `traceparent` header when the client sends them; otherwise fresh ids are
+/// generated. The request id is echoed back in `X-Request-Id`.
+pub async fn response_envelope_middleware(
+    State(enabled): State<bool>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !enabled || !accepts_envelope(request.headers()) {
+        return next.run(request).await;
+    }
+
+    let start = std::time::Instant::now();
+    let request_id = request.headers().get("x-request-id")
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= 128)
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let trace_id = trace_id_from(request.headers())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+
+    let response = next.run(request).await;
+    let status = response.status();
+    let is_json = response.headers().get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |content_type| content_type.starts_with("application/json"));
+    if !is_json && !status.is_client_error() && !status.is_server_error() {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            error!("Failed to read response body for envelope: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let meta = EnvelopeMeta {
+        request_id,
+        duration_ms: start.elapsed().as_millis() as u64,
+        trace_id,
+    };
+    let envelope = envelope(status, &bytes, &meta);
+
+    parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(ENVELOPE_MEDIA_TYPE));
+    parts.headers.remove(header::CONTENT_LENGTH);
+    if let Ok(value) = HeaderValue::from_str(&meta.request_id) {
+        parts.headers.insert("x-request-id", value);
+    }
+    Response::from_parts(parts, Body::from(envelope.to_string()))
+}
+
+fn accepts_envelope(headers: &HeaderMap) -> bool {
+    headers.get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media| media.split(';').next().unwrap_or("").trim().eq_ignore_ascii_case(ENVELOPE_MEDIA_TYPE))
+}
+
+/// Trace id of a well-formed `traceparent` (`version-traceid-parentid-flags`)
+fn trace_id_from(headers: &HeaderMap) -> Option<String> {
+    let traceparent = headers.get("traceparent")?.to_str().ok()?;
+    let trace_id = traceparent.split('-').nth(1)?;
+    let valid = trace_id.len() == 32
+        && trace_id.chars().all(|c| c.is_ascii_hexdigit())
+        && trace_id.chars().any(|c| c != '0');
+    valid.then(|| trace_id.to_ascii_lowercase())
+}
+
+/// Build the envelope for a handler's response body. Bodies that are not
+/// JSON are carried as a string; empty bodies as `null`.
+fn envelope(status: StatusCode, body: &[u8], meta: &EnvelopeMeta) -> serde_json::Value {
+    let payload = if body.is_empty() {
+        serde_json::Value::Null
+    } else {
+        serde_json::from_slice(body)
+            .unwrap_or_else(|_| serde_json::Value::String(String::from_utf8_lossy(body).into_owned()))
+    };
+
+    if status.is_client_error() || status.is_server_error() {
+        serde_json::json!({
+            "error": {
+                "status": status.as_u16(),
+                "message": status.canonical_reason().unwrap_or("Error"),
+                "details": payload,
+            },
+            "meta": meta,
+        })
+    } else {
+        serde_json::json!({ "data": payload, "meta": meta })
+    }
+}
+
 /// Create CORS layer from security configuration
 pub fn create_cors_layer(config: &SecurityConfig) -> CorsLayer {
     if config.enable_cors {
@@ -178,11 +309,52 @@ mod tests {
         let rate_limiter = create_rate_limiter(&config);
 
         // First two requests should succeed
-        assert!(rate_limiter.check().is_ok());
-        assert!(rate_limiter.check().is_ok());
+        assert!(rate_limiter.check());
+        assert!(rate_limiter.check());
 
         // Third request should be rate limited
-        assert!(rate_limiter.check().is_err());
+        assert!(!rate_limiter.check());
+
+        // A new quota applies immediately
+        rate_limiter.set_per_minute(NonZeroU32::new(5).unwrap());
+        assert!(rate_limiter.check());
+    }
+
+    #[test]
+    fn test_envelope_shapes() {
+        let meta = EnvelopeMeta {
+            request_id: "req-1".to_string(),
+            duration_ms: 12,
+            trace_id: "4bf92f3577b34da6a3ce929d0e0e4736".to_string(),
+        };
+
+        let ok = envelope(StatusCode::OK, br#"{"agents":3}"#, &meta);
+        assert_eq!(ok["data"]["agents"], 3);
+        assert_eq!(ok["meta"]["request_id"], "req-1");
+        assert_eq!(ok["meta"]["duration_ms"], 12);
+
+        let not_found = envelope(StatusCode::NOT_FOUND, b"", &meta);
+        assert!(not_found.get("data").is_none());
+        assert_eq!(not_found["error"]["status"], 404);
+        assert_eq!(not_found["error"]["message"], "Not Found");
+        assert!(not_found["error"]["details"].is_null());
+        assert_eq!(not_found["meta"]["trace_id"], meta.trace_id);
+
+        let text = envelope(StatusCode::BAD_REQUEST, b"bad field", &meta);
+        assert_eq!(text["error"]["details"], "bad field");
+    }
+
+    #[test]
+    fn test_envelope_negotiation_headers() {
+        let mut headers = HeaderMap::new();
+        assert!(!accepts_envelope(&headers));
+        headers.insert(header::ACCEPT, HeaderValue::from_static("text/html, application/vnd.acropolis+json;q=0.9"));
+        assert!(accepts_envelope(&headers));
+
+        headers.insert("traceparent", HeaderValue::from_static("00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01"));
+        assert_eq!(trace_id_from(&headers).as_deref(), Some("4bf92f3577b34da6a3ce929d0e0e4736"));
+        headers.insert("traceparent", HeaderValue::from_static("00-00000000000000000000000000000000-00f067aa0ba902b7-01"));
+        assert!(trace_id_from(&headers).is_none());
     }
 
     #[test]
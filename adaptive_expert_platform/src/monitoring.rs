@@ -1,7 +1,9 @@
 //! Comprehensive monitoring and metrics system
 
-use anyhow::{anyhow, Result};
-use std::collections::HashMap;
+use anyhow::{anyhow, Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::{RwLock, mpsc};
@@ -11,10 +13,12 @@ use dashmap::DashMap;
 use parking_lot::Mutex;
 use tracing::{info, warn, error, instrument};
 
+use crate::metric_exporter::{MetricExporter, MetricsSnapshot};
+
 #[cfg(feature = "with-metrics")]
 use {
     prometheus::{Registry, Counter, Histogram, Gauge, Opts},
-    metrics::{counter, histogram, gauge},
+    metrics::{counter, histogram},
 };
 
 /// System health status
@@ -66,6 +70,15 @@ pub struct AgentMetrics {
     pub max_concurrent_requests: u32,
     pub memory_usage_bytes: u64,
     pub cpu_usage_percent: f64,
+    /// Requests this agent served after the requested agent failed
+    pub fallback_requests_served: u64,
+    /// Calls answered from the orchestrator's result cache
+    pub result_cache_hits: u64,
+    pub result_cache_misses: u64,
+    /// Mirrored calls this agent ran as a shadow, and how they compared
+    pub shadow_matches: u64,
+    pub shadow_mismatches: u64,
+    pub shadow_failures: u64,
     pub last_updated: SystemTime,
 }
 
@@ -84,11 +97,36 @@ impl Default for AgentMetrics {
             max_concurrent_requests: 0,
             memory_usage_bytes: 0,
             cpu_usage_percent: 0.0,
+            fallback_requests_served: 0,
+            result_cache_hits: 0,
+            result_cache_misses: 0,
+            shadow_matches: 0,
+            shadow_mismatches: 0,
+            shadow_failures: 0,
             last_updated: SystemTime::now(),
         }
     }
 }
 
+/// How a shadow agent's output compared to the primary agent's
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShadowOutcome {
+    Match,
+    Mismatch,
+    /// The shadow errored or timed out
+    Failed,
+}
+
+impl ShadowOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShadowOutcome::Match => "match",
+            ShadowOutcome::Mismatch => "mismatch",
+            ShadowOutcome::Failed => "failed",
+        }
+    }
+}
+
 /// System-wide metrics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemMetrics {
@@ -155,6 +193,19 @@ pub enum AlertCondition {
     LessThanOrEqual,
 }
 
+impl AlertCondition {
+    pub fn holds(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            Self::GreaterThan => value > threshold,
+            Self::LessThan => value < threshold,
+            Self::Equal => value == threshold,
+            Self::NotEqual => value != threshold,
+            Self::GreaterThanOrEqual => value >= threshold,
+            Self::LessThanOrEqual => value <= threshold,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum AlertSeverity {
     Info,
@@ -203,6 +254,9 @@ pub struct MonitoringSystem {
     // Runtime state
     system_start_time: Instant,
     agent_metrics: Arc<DashMap<String, AgentMetrics>>,
+    exporters: Arc<RwLock<Vec<Arc<dyn MetricExporter>>>>,
+    /// Cancelled by [`MonitoringSystem::stop`] to end the background loops
+    stop_token: tokio_util::sync::CancellationToken,
     
     // Prometheus integration
     #[cfg(feature = "with-metrics")]
@@ -236,15 +290,199 @@ impl Default for MonitoringConfig {
     }
 }
 
+/// Connection pool settings of the HTTP client shared by health checks and
+/// webhook alerts
+#[derive(Debug, Clone)]
+pub struct HttpPoolConfig {
+    pub max_idle_per_host: usize,
+    pub idle_timeout: Duration,
+    pub connect_timeout: Duration,
+    pub request_timeout: Duration,
+}
+
+impl Default for HttpPoolConfig {
+    fn default() -> Self {
+        Self::from_settings(&crate::settings::ObservabilityConfig::default())
+    }
+}
+
+impl HttpPoolConfig {
+    pub fn from_settings(config: &crate::settings::ObservabilityConfig) -> Self {
+        Self {
+            max_idle_per_host: config.http_pool_max_idle_per_host,
+            idle_timeout: Duration::from_secs(config.http_pool_idle_timeout_seconds),
+            connect_timeout: Duration::from_secs(config.http_connect_timeout_seconds),
+            request_timeout: Duration::from_secs(config.http_request_timeout_seconds),
+        }
+    }
+}
+
+/// Build the pooled client; clones share its connections and TLS sessions,
+/// so build it once and hand clones to each user
+pub fn pooled_http_client(config: &HttpPoolConfig) -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .pool_max_idle_per_host(config.max_idle_per_host)
+        .pool_idle_timeout(config.idle_timeout)
+        .connect_timeout(config.connect_timeout)
+        .timeout(config.request_timeout)
+        .build()
+        .context("Failed to build the HTTP client")
+}
+
+/// Time series points at most this old are included in snapshot files
+const SNAPSHOT_SERIES_WINDOW: Duration = Duration::from_secs(3600);
+
+/// File format of [`MonitoringSystem::snapshot_to_file`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotFormat {
+    /// One document holding every section
+    Json,
+    /// One `section,name,metric,value,timestamp,labels` row per value
+    Csv,
+}
+
+impl SnapshotFormat {
+    pub fn parse(format: &str) -> Result<Self> {
+        match format {
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            other => Err(anyhow!("Unknown metrics snapshot format '{}'", other)),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+/// Point-in-time copy of every metric, written by [`MonitoringSystem::snapshot_to_file`]
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsFileSnapshot {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub system: SystemMetrics,
+    pub agents: BTreeMap<String, AgentMetrics>,
+    /// Series with their points from the last hour, in name order
+    pub time_series: Vec<TimeSeries>,
+}
+
+impl MetricsFileSnapshot {
+    /// Render as CSV; system and agent rows carry the snapshot time, series
+    /// rows their point's time and labels as sorted `key=value` pairs
+    pub fn to_csv(&self) -> Result<String> {
+        let timestamp = self.timestamp.timestamp().to_string();
+        let mut csv = String::from("section,name,metric,value,timestamp,labels\n");
+        let mut row = |fields: [&str; 6]| {
+            let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        };
+
+        for (metric, value) in numeric_fields(&self.system)? {
+            row(["system", "", metric.as_str(), value.as_str(), timestamp.as_str(), ""]);
+        }
+        for (agent, metrics) in &self.agents {
+            for (metric, value) in numeric_fields(metrics)? {
+                row(["agent", agent.as_str(), metric.as_str(), value.as_str(), timestamp.as_str(), ""]);
+            }
+        }
+        for series in &self.time_series {
+            for point in &series.points {
+                let labels: BTreeMap<_, _> = point.labels.iter().collect();
+                let labels: Vec<String> = labels.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+                row([
+                    "series",
+                    series.name.as_str(),
+                    "value",
+                    point.value.to_string().as_str(),
+                    point.timestamp.to_string().as_str(),
+                    labels.join(";").as_str(),
+                ]);
+            }
+        }
+        Ok(csv)
+    }
+}
+
+/// The numeric fields of a metrics struct, in field name order
+fn numeric_fields<T: Serialize>(metrics: &T) -> Result<Vec<(String, String)>> {
+    let serde_json::Value::Object(fields) = serde_json::to_value(metrics)? else {
+        return Err(anyhow!("Metrics did not serialize to an object"));
+    };
+    let mut fields: Vec<(String, String)> = fields.into_iter()
+        .filter(|(_, value)| value.is_number())
+        .map(|(name, value)| (name, value.to_string()))
+        .collect();
+    fields.sort();
+    Ok(fields)
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Write `snapshot` to a temporary file next to `path` and rename it into
+/// place, so readers never see a partial file
+/// Delete all but the newest `keep` archived snapshot files in `dir`.
+/// Archive names start with a sortable timestamp, so name order is age order.
+fn prune_snapshots(dir: &Path, keep: usize) -> Result<()> {
+    let mut snapshots: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to list metrics snapshot directory: {:?}", dir))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+            name.starts_with("metrics-") && (name.ends_with(".json") || name.ends_with(".csv"))
+        })
+        .collect();
+    snapshots.sort();
+    let excess = snapshots.len().saturating_sub(keep);
+    for path in &snapshots[..excess] {
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to delete old metrics snapshot: {:?}", path))?;
+    }
+    Ok(())
+}
+
+fn write_snapshot(snapshot: &MetricsFileSnapshot, path: &Path, format: SnapshotFormat) -> Result<()> {
+    let contents = match format {
+        SnapshotFormat::Json => serde_json::to_vec_pretty(snapshot)?,
+        SnapshotFormat::Csv => snapshot.to_csv()?.into_bytes(),
+    };
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut file = tempfile::NamedTempFile::new_in(dir)
+        .with_context(|| format!("Failed to create metrics snapshot in {:?}", dir))?;
+    file.write_all(&contents)?;
+    file.as_file().sync_all()?;
+    file.persist(path)
+        .map_err(|e| e.error)
+        .with_context(|| format!("Failed to write metrics snapshot: {:?}", path))?;
+    Ok(())
+}
+
 impl MonitoringSystem {
     /// Create a new monitoring system
     pub fn new(config: MonitoringConfig) -> Self {
         let metrics_store = Arc::new(MetricsStore::new());
         let health_checker = Arc::new(HealthChecker::new());
-        let alert_manager = Arc::new(AlertManager::new());
+        let alert_manager = Arc::new(AlertManager::new(reqwest::Client::new()));
         
         #[cfg(feature = "with-metrics")]
         let prometheus_registry = Arc::new(Registry::new());
+
+        let mut exporters: Vec<Arc<dyn MetricExporter>> = Vec::new();
+        #[cfg(feature = "with-metrics")]
+        if config.enable_prometheus {
+            exporters.push(Arc::new(crate::metric_exporter::PrometheusExporter));
+        }
         
         Self {
             metrics_store,
@@ -253,12 +491,20 @@ impl MonitoringSystem {
             config,
             system_start_time: Instant::now(),
             agent_metrics: Arc::new(DashMap::new()),
+            exporters: Arc::new(RwLock::new(exporters)),
+            stop_token: tokio_util::sync::CancellationToken::new(),
             
             #[cfg(feature = "with-metrics")]
             prometheus_registry,
         }
     }
 
+    /// Send webhook alerts through `client` instead of a client of its own
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.alert_manager = Arc::new(AlertManager::new(client));
+        self
+    }
+
     /// Start the monitoring system
     #[instrument(skip(self))]
     pub async fn start(&self) -> Result<()> {
@@ -333,6 +579,97 @@ impl MonitoringSystem {
         }
     }
 
+    /// Record that `served_by` handled a request after `primary` failed
+    pub async fn record_fallback(&self, primary: &str, served_by: &str) {
+        if let Some(mut metrics) = self.agent_metrics.get_mut(served_by) {
+            metrics.fallback_requests_served += 1;
+        }
+
+        let labels = HashMap::from([
+            ("primary".to_string(), primary.to_string()),
+            ("served_by".to_string(), served_by.to_string()),
+        ]);
+        self.metrics_store
+            .record_metric("agent_fallback_served".to_string(), 1.0, labels)
+            .await;
+
+        #[cfg(feature = "with-metrics")]
+        counter!(
+            "agent_fallback_served_total",
+            "primary" => primary.to_string(),
+            "served_by" => served_by.to_string()
+        )
+        .increment(1);
+    }
+
+    /// Record how a shadow agent's output compared to the primary's
+    pub async fn record_shadow_comparison(
+        &self,
+        primary: &str,
+        shadow: &str,
+        outcome: ShadowOutcome,
+        duration: Duration,
+    ) {
+        {
+            let mut metrics = self.agent_metrics
+                .entry(shadow.to_string())
+                .or_insert_with(|| {
+                    let mut m = AgentMetrics::default();
+                    m.agent_name = shadow.to_string();
+                    m
+                });
+            match outcome {
+                ShadowOutcome::Match => metrics.shadow_matches += 1,
+                ShadowOutcome::Mismatch => metrics.shadow_mismatches += 1,
+                ShadowOutcome::Failed => metrics.shadow_failures += 1,
+            }
+        }
+
+        let labels = HashMap::from([
+            ("primary".to_string(), primary.to_string()),
+            ("shadow".to_string(), shadow.to_string()),
+            ("outcome".to_string(), outcome.as_str().to_string()),
+        ]);
+        self.metrics_store
+            .record_metric("agent_shadow_duration_ms".to_string(), duration.as_millis() as f64, labels)
+            .await;
+
+        #[cfg(feature = "with-metrics")]
+        {
+            counter!(
+                "agent_shadow_comparisons_total",
+                "primary" => primary.to_string(),
+                "shadow" => shadow.to_string(),
+                "outcome" => outcome.as_str()
+            )
+            .increment(1);
+            histogram!("agent_shadow_duration_seconds", "shadow" => shadow.to_string())
+                .record(duration.as_secs_f64());
+        }
+    }
+
+    /// Record a result cache lookup for a cacheable agent
+    pub async fn record_result_cache(&self, agent_name: &str, hit: bool) {
+        let mut metrics = self.agent_metrics
+            .entry(agent_name.to_string())
+            .or_insert_with(|| {
+                let mut m = AgentMetrics::default();
+                m.agent_name = agent_name.to_string();
+                m
+            });
+        if hit {
+            metrics.result_cache_hits += 1;
+        } else {
+            metrics.result_cache_misses += 1;
+        }
+
+        #[cfg(feature = "with-metrics")]
+        {
+            let name = if hit { "agent_result_cache_hits_total" } else { "agent_result_cache_misses_total" };
+            counter!(name, "agent" => agent_name.to_string()).increment(1);
+        }
+    }
+
     /// Get agent metrics
     pub async fn get_agent_metrics(&self, agent_name: &str) -> Option<AgentMetrics> {
         self.agent_metrics.get(agent_name).map(|entry| entry.clone())
@@ -346,10 +683,25 @@ impl MonitoringSystem {
             .collect()
     }
 
+    /// Register an exporter to receive a snapshot on every collection tick
+    pub async fn add_exporter(&self, exporter: Arc<dyn MetricExporter>) {
+        info!("Registered metric exporter '{}'", exporter.name());
+        self.exporters.write().await.push(exporter);
+    }
+
+    /// Build a snapshot now and send it to every exporter
+    pub async fn export_snapshot(&self) {
+        Self::export_to(&self.exporters, &self.agent_metrics, self.system_start_time).await;
+    }
+
     /// Get system metrics
     pub async fn get_system_metrics(&self) -> SystemMetrics {
+        Self::system_metrics(self.system_start_time)
+    }
+
+    fn system_metrics(system_start_time: Instant) -> SystemMetrics {
         SystemMetrics {
-            uptime_seconds: self.system_start_time.elapsed().as_secs(),
+            uptime_seconds: system_start_time.elapsed().as_secs(),
             total_memory_bytes: get_total_memory(),
             used_memory_bytes: get_used_memory(),
             cpu_cores: num_cpus::get() as u32,
@@ -390,10 +742,137 @@ impl MonitoringSystem {
         self.alert_manager.get_active_alerts().await
     }
 
+    /// Stop the collection, health check and alert loops
+    pub fn stop(&self) {
+        self.stop_token.cancel();
+    }
+
+    /// Push a final snapshot to every exporter
+    pub async fn flush(&self) {
+        Self::export_to(&self.exporters, &self.agent_metrics, self.system_start_time).await;
+    }
+
+    /// Copy system and agent metrics and the last hour of time series
+    pub async fn file_snapshot(&self) -> MetricsFileSnapshot {
+        Self::capture(&self.metrics_store, &self.agent_metrics, self.system_start_time).await
+    }
+
+    /// Atomically write every metric to `path` as JSON or CSV.
+    ///
+    /// Metrics are copied under brief read locks and serialized and written
+    /// afterwards on a blocking thread, so recording carries on meanwhile.
+    pub async fn snapshot_to_file(&self, path: &Path, format: SnapshotFormat) -> Result<()> {
+        let snapshot = self.file_snapshot().await;
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || write_snapshot(&snapshot, &path, format))
+            .await
+            .map_err(|e| anyhow!("Metrics snapshot task failed: {}", e))?
+    }
+
+    /// Write a snapshot into `dir`, named by the current time, and return its
+    /// path. Only the newest `retention` snapshot files are kept (0 keeps all).
+    pub async fn archive_snapshot(&self, dir: &Path, format: SnapshotFormat, retention: usize) -> Result<PathBuf> {
+        Self::archive_to(&self.metrics_store, &self.agent_metrics, self.system_start_time, dir, format, retention).await
+    }
+
+    /// Archive a snapshot into `dir` every `interval` until [`MonitoringSystem::stop`],
+    /// keeping the newest `retention` files like [`MonitoringSystem::archive_snapshot`]
+    pub fn start_snapshot_archival(&self, dir: PathBuf, format: SnapshotFormat, interval: Duration, retention: usize) {
+        let metrics_store = self.metrics_store.clone();
+        let agent_metrics = self.agent_metrics.clone();
+        let system_start_time = self.system_start_time;
+        let stop = self.stop_token.clone();
+
+        tokio::spawn(async move {
+            let mut archive_interval = tokio::time::interval(interval);
+            // The first tick completes immediately; skip it so the first
+            // file is a full interval in
+            archive_interval.tick().await;
+
+            loop {
+                tokio::select! {
+                    _ = archive_interval.tick() => {}
+                    _ = stop.cancelled() => break,
+                }
+                match Self::archive_to(&metrics_store, &agent_metrics, system_start_time, &dir, format, retention).await {
+                    Ok(path) => info!("Archived metrics snapshot to {:?}", path),
+                    Err(e) => warn!("Failed to archive metrics snapshot: {:#}", e),
+                }
+            }
+        });
+        info!("Archiving metrics snapshots to {:?} every {}s", dir, interval.as_secs());
+    }
+
+    async fn archive_to(
+        metrics_store: &MetricsStore,
+        agent_metrics: &DashMap<String, AgentMetrics>,
+        system_start_time: Instant,
+        dir: &Path,
+        format: SnapshotFormat,
+        retention: usize,
+    ) -> Result<PathBuf> {
+        let snapshot = Self::capture(metrics_store, agent_metrics, system_start_time).await;
+        let path = dir.join(format!(
+            "metrics-{}.{}",
+            snapshot.timestamp.format("%Y%m%dT%H%M%S%.3fZ"),
+            format.extension()
+        ));
+        let written = path.clone();
+        let dir = dir.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            std::fs::create_dir_all(&dir)
+                .with_context(|| format!("Failed to create metrics snapshot directory: {:?}", dir))?;
+            write_snapshot(&snapshot, &path, format)?;
+            if retention > 0 {
+                prune_snapshots(&dir, retention)?;
+            }
+            Ok::<(), anyhow::Error>(())
+        })
+        .await
+        .map_err(|e| anyhow!("Metrics snapshot task failed: {}", e))??;
+        Ok(written)
+    }
+
+    /// Copy every metric while holding the time series read lock, so agent
+    /// counters and the series points they are recorded alongside agree
+    async fn capture(
+        metrics_store: &MetricsStore,
+        agent_metrics: &DashMap<String, AgentMetrics>,
+        system_start_time: Instant,
+    ) -> MetricsFileSnapshot {
+        let store = metrics_store.time_series.read().await;
+        let timestamp = chrono::Utc::now();
+        let cutoff = (timestamp.timestamp().max(0) as u64).saturating_sub(SNAPSHOT_SERIES_WINDOW.as_secs());
+        let mut time_series: Vec<TimeSeries> = store.values()
+            .map(|series| TimeSeries {
+                name: series.name.clone(),
+                metric_type: series.metric_type.clone(),
+                points: series.points.iter().filter(|point| point.timestamp >= cutoff).cloned().collect(),
+                retention_duration: series.retention_duration,
+            })
+            .collect();
+        let agents = agent_metrics
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        drop(store);
+
+        time_series.sort_by(|a, b| a.name.cmp(&b.name));
+        MetricsFileSnapshot {
+            timestamp,
+            system: Self::system_metrics(system_start_time),
+            agents,
+            time_series,
+        }
+    }
+
     /// Start metrics collection loop
     async fn start_metrics_collection(&self) {
         let interval = self.config.metrics_collection_interval_seconds;
         let agent_metrics = self.agent_metrics.clone();
+        let exporters = self.exporters.clone();
+        let system_start_time = self.system_start_time;
+        let stop = self.stop_token.clone();
         
         tokio::spawn(async move {
             let mut collection_interval = tokio::time::interval(
@@ -401,24 +880,20 @@ impl MonitoringSystem {
             );
             
             loop {
-                collection_interval.tick().await;
+                tokio::select! {
+                    _ = collection_interval.tick() => {}
+                    _ = stop.cancelled() => break,
+                }
                 
                 // Collect system metrics
                 Self::collect_system_metrics(&agent_metrics).await;
+                Self::export_to(&exporters, &agent_metrics, system_start_time).await;
             }
         });
     }
 
     /// Collect system-wide metrics
     async fn collect_system_metrics(agent_metrics: &DashMap<String, AgentMetrics>) {
-        // Update system-level metrics
-        #[cfg(feature = "with-metrics")]
-        {
-            gauge!("system_memory_used_bytes").set(get_used_memory() as f64);
-            gauge!("system_cpu_usage_percent").set(get_cpu_usage());
-            gauge!("system_active_connections").set(get_active_connections() as f64);
-        }
-
         // Update agent-specific metrics
         for mut entry in agent_metrics.iter_mut() {
             let metrics = entry.value_mut();
@@ -427,10 +902,33 @@ impl MonitoringSystem {
         }
     }
 
+    /// Fan a fresh snapshot out to all exporters concurrently
+    async fn export_to(
+        exporters: &RwLock<Vec<Arc<dyn MetricExporter>>>,
+        agent_metrics: &DashMap<String, AgentMetrics>,
+        system_start_time: Instant,
+    ) {
+        let exporters = exporters.read().await.clone();
+        if exporters.is_empty() {
+            return;
+        }
+
+        let snapshot = MetricsSnapshot {
+            timestamp: chrono::Utc::now(),
+            system: Self::system_metrics(system_start_time),
+            agents: agent_metrics
+                .iter()
+                .map(|entry| (entry.key().clone(), entry.value().clone()))
+                .collect(),
+        };
+        futures::future::join_all(exporters.iter().map(|exporter| exporter.export(&snapshot))).await;
+    }
+
     /// Start health check loop
     async fn start_health_checks(&self) {
         let health_checker = self.health_checker.clone();
         let interval = self.config.health_check_interval_seconds;
+        let stop = self.stop_token.clone();
         
         tokio::spawn(async move {
             let mut check_interval = tokio::time::interval(
@@ -438,7 +936,10 @@ impl MonitoringSystem {
             );
             
             loop {
-                check_interval.tick().await;
+                tokio::select! {
+                    _ = check_interval.tick() => {}
+                    _ = stop.cancelled() => break,
+                }
                 health_checker.run_all_checks().await;
             }
         });
@@ -449,6 +950,7 @@ impl MonitoringSystem {
         let alert_manager = self.alert_manager.clone();
         let metrics_store = self.metrics_store.clone();
         let interval = self.config.alert_evaluation_interval_seconds;
+        let stop = self.stop_token.clone();
         
         tokio::spawn(async move {
             let mut eval_interval = tokio::time::interval(
@@ -456,7 +958,10 @@ impl MonitoringSystem {
             );
             
             loop {
-                eval_interval.tick().await;
+                tokio::select! {
+                    _ = eval_interval.tick() => {}
+                    _ = stop.cancelled() => break,
+                }
                 alert_manager.evaluate_alerts(&metrics_store).await;
             }
         });
@@ -553,6 +1058,24 @@ impl MetricsStore {
         let cutoff = timestamp - series.retention_duration.as_secs();
         series.points.retain(|point| point.timestamp > cutoff);
     }
+
+    /// Values of `name` recorded within the last `window`, oldest first;
+    /// only the latest value for a zero window
+    pub async fn recent_values(&self, name: &str, window: Duration) -> Vec<f64> {
+        let store = self.time_series.read().await;
+        let Some(series) = store.get(name) else {
+            return Vec::new();
+        };
+        if window.is_zero() {
+            return series.points.last().map(|point| vec![point.value]).unwrap_or_default();
+        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let cutoff = now.saturating_sub(window.as_secs());
+        series.points.iter()
+            .filter(|point| point.timestamp >= cutoff)
+            .map(|point| point.value)
+            .collect()
+    }
 }
 
 /// Health checking system
@@ -632,13 +1155,16 @@ impl HealthChecker {
 pub struct AlertManager {
     configs: Arc<RwLock<HashMap<String, AlertConfig>>>,
     active_alerts: Arc<RwLock<HashMap<Uuid, Alert>>>,
+    /// Delivers webhook alerts, see [`pooled_http_client`]
+    http_client: reqwest::Client,
 }
 
 impl AlertManager {
-    pub fn new() -> Self {
+    pub fn new(http_client: reqwest::Client) -> Self {
         Self {
             configs: Arc::new(RwLock::new(HashMap::new())),
             active_alerts: Arc::new(RwLock::new(HashMap::new())),
+            http_client,
         }
     }
 
@@ -648,15 +1174,81 @@ impl AlertManager {
         Ok(())
     }
 
-    pub async fn evaluate_alerts(&self, _metrics_store: &MetricsStore) {
-        // Alert evaluation logic would go here
-        // For now, this is a placeholder
+    /// Fire each enabled alert whose condition held for every value recorded
+    /// over its `duration_seconds`, and resolve firing alerts whose condition
+    /// no longer holds. Both transitions are sent to the alert's channels.
+    pub async fn evaluate_alerts(&self, metrics_store: &MetricsStore) {
+        let configs: Vec<AlertConfig> = self.configs.read().await.values()
+            .filter(|config| config.enabled)
+            .cloned()
+            .collect();
+
+        for config in configs {
+            let window = Duration::from_secs(config.duration_seconds);
+            let values = metrics_store.recent_values(&config.metric_name, window).await;
+            let Some(&current_value) = values.last() else {
+                continue;
+            };
+            let breached = values.iter().all(|value| config.condition.holds(*value, config.threshold));
+            let firing = self.active_alerts.read().await.values()
+                .find(|alert| alert.config.name == config.name)
+                .map(|alert| alert.id);
+
+            match (breached, firing) {
+                (true, None) => {
+                    let alert = Alert {
+                        id: Uuid::new_v4(),
+                        message: format!("{} is {} (threshold {})", config.metric_name, current_value, config.threshold),
+                        config,
+                        triggered_at: SystemTime::now(),
+                        resolved_at: None,
+                        current_value,
+                        status: AlertStatus::Firing,
+                    };
+                    warn!("Alert '{}' firing: {}", alert.config.name, alert.message);
+                    self.active_alerts.write().await.insert(alert.id, alert.clone());
+                    self.notify(&alert).await;
+                }
+                (false, Some(id)) => {
+                    let resolved = self.active_alerts.write().await.remove(&id);
+                    if let Some(mut alert) = resolved {
+                        alert.status = AlertStatus::Resolved;
+                        alert.resolved_at = Some(SystemTime::now());
+                        alert.current_value = current_value;
+                        info!("Alert '{}' resolved", alert.config.name);
+                        self.notify(&alert).await;
+                    }
+                }
+                _ => {}
+            }
+        }
     }
 
     pub async fn get_active_alerts(&self) -> Vec<Alert> {
         let alerts = self.active_alerts.read().await;
         alerts.values().cloned().collect()
     }
+
+    /// Deliver `alert` to its webhook channels; other channels are not wired up yet
+    pub async fn notify(&self, alert: &Alert) {
+        for channel in &alert.config.channels {
+            if let AlertChannel::Webhook(url) = channel {
+                if let Err(e) = self.send_webhook(url, alert).await {
+                    warn!("Failed to deliver alert '{}' to {}: {}", alert.config.name, url, e);
+                }
+            }
+        }
+    }
+
+    async fn send_webhook(&self, url: &str, alert: &Alert) -> Result<()> {
+        self.http_client.post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(alert)?)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
 }
 
 // System metric collection functions (platform-specific)
@@ -672,4 +1264,121 @@ fn get_heap_size() -> u64 { 100_000_000 }
 fn get_gc_count() -> u64 { 25 }
 fn get_last_gc_duration() -> f64 { 5.2 }
 fn get_agent_memory_usage(_agent_name: &str) -> u64 { 50_000_000 }
-fn get_agent_cpu_usage(_agent_name: &str) -> f64 { 15.0 }
\ No newline at end of file
+fn get_agent_cpu_usage(_agent_name: &str) -> f64 { 15.0 }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn monitoring_with_traffic() -> MonitoringSystem {
+        let monitoring = MonitoringSystem::new(MonitoringConfig::default());
+        monitoring.record_agent_request("echo", true, Duration::from_millis(5)).await;
+        monitoring.record_agent_request("echo", false, Duration::from_millis(5)).await;
+        monitoring.record_agent_request("backup", true, Duration::from_millis(5)).await;
+        monitoring.record_fallback("echo", "backup").await;
+        monitoring
+    }
+
+    #[tokio::test]
+    async fn test_json_snapshot_file() {
+        let monitoring = monitoring_with_traffic().await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metrics.json");
+        monitoring.snapshot_to_file(&path, SnapshotFormat::Json).await.unwrap();
+
+        let snapshot: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert!(snapshot["timestamp"].is_string());
+        assert!(snapshot["system"]["uptime_seconds"].is_u64());
+        assert_eq!(snapshot["agents"]["echo"]["total_requests"], 2);
+        assert_eq!(snapshot["agents"]["echo"]["failed_requests"], 1);
+        assert_eq!(snapshot["agents"]["backup"]["fallback_requests_served"], 1);
+        let series = &snapshot["time_series"][0];
+        assert_eq!(series["name"], "agent_fallback_served");
+        assert_eq!(series["points"][0]["labels"]["served_by"], "backup");
+
+        // Only the renamed file is left behind
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_csv_snapshot_file() {
+        let monitoring = monitoring_with_traffic().await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = monitoring.archive_snapshot(&dir.path().join("archive"), SnapshotFormat::Csv, 0).await.unwrap();
+        assert_eq!(path.extension().unwrap(), "csv");
+
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let rows: Vec<Vec<&str>> = csv.lines().map(|line| line.split(',').collect()).collect();
+        assert_eq!(rows[0], vec!["section", "name", "metric", "value", "timestamp", "labels"]);
+        assert!(rows.iter().all(|row| row.len() == 6));
+        assert!(rows.iter().any(|row| row[0] == "system" && row[2] == "cpu_cores"));
+        assert!(rows.iter().any(|row| row[..4] == ["agent", "echo", "total_requests", "2"]));
+        let series = rows.iter().find(|row| row[0] == "series").unwrap();
+        assert_eq!(series[1], "agent_fallback_served");
+        assert_eq!(series[5], "primary=echo;served_by=backup");
+    }
+
+    #[tokio::test]
+    async fn test_archived_snapshots_are_pruned_to_retention() {
+        let monitoring = monitoring_with_traffic().await;
+        let dir = tempfile::tempdir().unwrap();
+        let unrelated = dir.path().join("notes.txt");
+        std::fs::write(&unrelated, "keep me").unwrap();
+
+        let mut written = Vec::new();
+        for _ in 0..3 {
+            written.push(monitoring.archive_snapshot(dir.path(), SnapshotFormat::Json, 2).await.unwrap());
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert!(!written[0].exists());
+        assert!(written[1].exists() && written[2].exists());
+        assert!(unrelated.exists());
+    }
+
+    #[tokio::test]
+    async fn test_alerts_fire_and_resolve_through_webhook() {
+        use axum::{routing::post, Router};
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+        let app = Router::new().route("/hook", post(move |body: String| {
+            let tx = tx.clone();
+            async move {
+                tx.send(body).unwrap();
+                "ok"
+            }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = pooled_http_client(&HttpPoolConfig::default()).unwrap();
+        let alerts = AlertManager::new(client);
+        alerts.add_alert(AlertConfig {
+            name: "high_cpu".to_string(),
+            enabled: true,
+            metric_name: "cpu".to_string(),
+            condition: AlertCondition::GreaterThan,
+            threshold: 90.0,
+            duration_seconds: 0,
+            severity: AlertSeverity::Critical,
+            channels: vec![AlertChannel::Webhook(format!("http://{}/hook", addr))],
+        }).await.unwrap();
+        let store = MetricsStore::new();
+
+        store.record_metric("cpu".to_string(), 95.0, HashMap::new()).await;
+        alerts.evaluate_alerts(&store).await;
+        alerts.evaluate_alerts(&store).await;
+        assert_eq!(alerts.get_active_alerts().await.len(), 1);
+        let fired: serde_json::Value = serde_json::from_str(&rx.recv().await.unwrap()).unwrap();
+        assert_eq!(fired["status"], "Firing");
+        assert_eq!(fired["current_value"], 95.0);
+
+        store.record_metric("cpu".to_string(), 40.0, HashMap::new()).await;
+        alerts.evaluate_alerts(&store).await;
+        assert!(alerts.get_active_alerts().await.is_empty());
+        let resolved: serde_json::Value = serde_json::from_str(&rx.recv().await.unwrap()).unwrap();
+        assert_eq!(resolved["status"], "Resolved");
+
+        // A still-firing alert is sent only once
+        assert!(rx.try_recv().is_err());
+    }
+}
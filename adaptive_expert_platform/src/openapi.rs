@@ -0,0 +1,146 @@
+//! OpenAPI 3 description of the REST API for client generation.
+//!
+//! Routes are listed in [`ROUTES`] alongside the router in `server.rs`; keep
+//! the two in sync when adding endpoints.
+
+use serde_json::{json, Map, Value};
+
+/// An HTTP route as exposed in the OpenAPI document
+struct RouteDoc {
+    method: &'static str,
+    /// Axum-style path, with `:param` segments
+    path: &'static str,
+    summary: &'static str,
+    tag: &'static str,
+    authenticated: bool,
+}
+
+const fn route(method: &'static str, path: &'static str, summary: &'static str, tag: &'static str, authenticated: bool) -> RouteDoc {
+    RouteDoc { method, path, summary, tag, authenticated }
+}
+
+const ROUTES: &[RouteDoc] = &[
+    route("get", "/health", "Service health", "system", false),
+    route("get", "/readyz", "Readiness of required agents; 503 until they have warmed up", "system", false),
+    route("get", "/version", "Version, commit, build time and compiled features", "system", false),
+    route("get", "/openapi.json", "This OpenAPI document", "system", false),
+    route("post", "/auth/login", "Exchange credentials for a JWT", "auth", false),
+    route("post", "/auth/users", "Create a user (admin)", "auth", true),
+    route("post", "/auth/password", "Change the caller's password", "auth", true),
+    route("get", "/auth/usage", "The caller's request and cost quota and usage in the current window", "auth", true),
+    route("put", "/auth/users/:name/quota", "Override a user's quota (admin)", "auth", true),
+    route("delete", "/auth/users/:name/quota", "Return a user to their configured quota (admin)", "auth", true),
+    route("get", "/agents", "List registered agents", "agents", true),
+    route("post", "/agents", "Register an agent (admin)", "agents", true),
+    route("delete", "/agents/:name", "Remove an agent (admin)", "agents", true),
+    route("post", "/agents/:name/model", "Hot-reload the model behind an LLM agent (admin)", "agents", true),
+    route("post", "/agents/:name/disable", "Put an agent in maintenance, refusing calls with 503 (admin)", "agents", true),
+    route("post", "/agents/:name/enable", "Bring an agent out of maintenance (admin)", "agents", true),
+    route("get", "/agents/schema", "Agent capabilities and input schemas", "agents", true),
+    route("get", "/capabilities", "Capabilities with the agents providing them", "agents", true),
+    route("post", "/execute", "Run a task on an agent, streaming output as SSE for Accept: text/event-stream", "tasks", true),
+    route("post", "/rpc", "JSON-RPC 2.0 endpoint", "tasks", true),
+    route("get", "/memory/stats", "Memory statistics", "memory", true),
+    route("post", "/memory/search", "Semantic memory search", "memory", true),
+    route("post", "/memory/search/explain", "Explain memory search scores and filters", "memory", true),
+    route("post", "/memory/add", "Add content to memory", "memory", true),
+    route("post", "/memory/upload", "Ingest an uploaded text file", "memory", true),
+    route("get", "/memory/export", "Export every memory fragment", "memory", true),
+    route("post", "/memory/export/url", "Create a signed export download URL", "memory", true),
+    route("get", "/memory/export/download", "Download an export via a signed URL", "memory", false),
+    route("post", "/memory/repair", "Re-embed drifted fragments and drop orphaned cache entries (admin)", "memory", true),
+    route("post", "/memory/prefetch", "Precompute and cache embeddings for anticipated queries (admin)", "memory", true),
+    route("get", "/metrics", "Agent and system metrics", "system", true),
+    route("get", "/audit", "Query the audit log (admin)", "admin", true),
+    route("get", "/trace", "Query the execution trace log (admin)", "admin", true),
+    route("post", "/trace/:id/replay", "Replay a traced execution (admin)", "admin", true),
+    route("post", "/deployments/:name/scale", "Scale a deployment, optionally as a dry run (admin)", "admin", true),
+    route("get", "/ws/connections", "Active WebSocket sessions (admin)", "admin", true),
+    route("get", "/ws/stats", "WebSocket server statistics (admin)", "admin", true),
+    route("get", "/plugins", "Loaded plugins and load failures (admin)", "admin", true),
+    route("post", "/plugins/reload", "Load new libraries from the plugin directory (admin)", "admin", true),
+    route("post", "/admin/reload-config", "Re-read the configuration, apply hot-reloadable settings and reload plugin agents (admin)", "admin", true),
+    route("post", "/admin/metrics/snapshot", "Write all metrics to a JSON or CSV file in the snapshot directory (admin)", "admin", true),
+    route("post", "/admin/julia/gc", "Run a full Julia garbage collection; builds with Julia support only (admin)", "admin", true),
+];
+
+/// Build the OpenAPI 3 document for the REST API
+pub fn openapi_document() -> Value {
+    let mut paths = Map::new();
+    for route in ROUTES {
+        let (path, parameters) = openapi_path(route.path);
+        let mut operation = json!({
+            "summary": route.summary,
+            "tags": [route.tag],
+            "responses": {
+                "200": { "description": "Success" },
+                "400": { "description": "Invalid request" },
+            },
+        });
+        if !parameters.is_empty() {
+            operation["parameters"] = Value::Array(parameters);
+        }
+        if route.authenticated {
+            operation["security"] = json!([{ "bearerAuth": [] }]);
+            operation["responses"]["401"] = json!({ "description": "Missing or invalid token" });
+            operation["responses"]["403"] = json!({ "description": "Insufficient permissions" });
+        }
+
+        let item = paths.entry(path).or_insert_with(|| json!({}));
+        item[route.method] = operation;
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Adaptive Expert Platform API",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "securitySchemes": {
+                "bearerAuth": { "type": "http", "scheme": "bearer", "bearerFormat": "JWT" },
+            },
+        },
+    })
+}
+
+/// Convert `:param` segments to `{param}` and describe each parameter
+fn openapi_path(path: &str) -> (String, Vec<Value>) {
+    let mut parameters = Vec::new();
+    let segments: Vec<String> = path
+        .split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(name) => {
+                parameters.push(json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" },
+                }));
+                format!("{{{}}}", name)
+            }
+            None => segment.to_string(),
+        })
+        .collect();
+    (segments.join("/"), parameters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openapi_document_paths() {
+        let doc = openapi_document();
+        assert_eq!(doc["openapi"], "3.0.3");
+
+        let scale = &doc["paths"]["/deployments/{name}/scale"]["post"];
+        assert_eq!(scale["parameters"][0]["name"], "name");
+        assert!(scale["security"].is_array());
+
+        let agents = &doc["paths"]["/agents"];
+        assert!(agents["get"].is_object() && agents["post"].is_object());
+        assert!(doc["paths"]["/health"]["get"].get("security").is_none());
+    }
+}
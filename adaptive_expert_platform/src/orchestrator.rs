@@ -1,34 +1,255 @@
 //! Core coordinator that routes tasks to agents (built-in or from plugins).
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 use anyhow::Result;
+use serde::Serialize;
 use serde_json::Value;
-use tokio::sync::{mpsc, Mutex, Semaphore};
-use tracing::{info, warn, error, instrument};
+use tokio::sync::{mpsc, Mutex};
+use dashmap::DashMap;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use tokio_util::sync::{CancellationToken, DropGuard};
+use tracing::{debug, info, warn, error, instrument};
 use uuid::Uuid;
 
 use crate::{
-    agent::Agent,
-    plugin::{self, PluginEvent, PluginSecurityConfig},
+    agent::{with_caller_tenant, Agent, AgentContext, AgentRegistrationError, Cost, DuplicateAgentError},
+    agent_io_log::AgentIoLogger,
+    quota::{Quota, QuotaLedger, UsageReport},
+    agent_middleware::{apply_after, apply_before, AgentMiddleware, LoggingMiddleware, RedactionMiddleware, StreamAfter},
+    error::{OrchestratorError, SharedError},
+    input_guard::InputGuard,
+    output_schema::{OutputValidationMode, OutputValidator},
+    plugin::{self, PluginEvent, PluginFailure, PluginLoadReport, PluginManager, PluginSecurityConfig},
+    redaction::OutputRedactor,
+    scheduler::PriorityScheduler,
     settings::Settings,
     memory::Memory,
     lifecycle::{LifecycleManager, LifecycleConfig},
-    monitoring::{MonitoringSystem, MonitoringConfig},
+    monitoring::{pooled_http_client, HttpPoolConfig, MonitoringSystem, MonitoringConfig, ShadowOutcome},
     cache::{MultiTierCache, MultiTierCacheConfig},
-    websocket::{WebSocketServer, WebSocketConfig},
-    mesh::{AgentMesh, MeshConfig},
+    websocket::{AgentRequestHandler, WebSocketServer},
+    mesh::{AgentMesh, MeshConfig, TaskPriority},
+    trace::TraceLog,
 };
 
-type Task = (String, Value, mpsc::Sender<Result<Value>>);
+type Task = (String, Value, mpsc::Sender<Result<Value, OrchestratorError>>);
+
+/// Who a call is made for
+#[derive(Debug, Clone, Default)]
+pub struct Caller {
+    /// Charged against their quota
+    pub user: Option<String>,
+    /// Memory tenant agents retrieve context for (see
+    /// [`caller_tenant`](crate::agent::caller_tenant)); `None` sees every fragment
+    pub tenant: Option<String>,
+}
+
+tokio::task_local! {
+    static PROGRESS: mpsc::Sender<String>;
+}
+
+/// Run a dispatch with the agent's output also streamed to `progress` chunk
+/// by chunk as it is produced, ahead of the `after` middleware. The call
+/// still returns the assembled output through the usual pipeline. Cached
+/// results, and calls coalesced onto another caller's execution, send nothing.
+pub async fn with_progress<F: std::future::Future>(progress: mpsc::Sender<String>, call: F) -> F::Output {
+    PROGRESS.scope(progress, call).await
+}
+
+/// Run `agent` through `handle_stream`, forwarding chunks to `progress` and
+/// returning them joined
+async fn collect_stream(
+    agent: &dyn Agent,
+    input: Value,
+    memory: Arc<Memory>,
+    progress: mpsc::Sender<String>,
+    cancel: &CancellationToken,
+) -> Result<String> {
+    let (chunk_tx, mut chunk_rx) = mpsc::channel(progress.max_capacity());
+    let mut output = String::new();
+    let collect = async {
+        while let Some(chunk) = chunk_rx.recv().await {
+            // A caller that stopped watching still gets the full result
+            let _ = progress.send(chunk.clone()).await;
+            output.push_str(&chunk);
+        }
+    };
+    let run = async {
+        let (handled, ()) = tokio::join!(agent.handle_stream(input, memory, chunk_tx), collect);
+        handled
+    };
+    let result = tokio::select! {
+        result = run => result,
+        _ = cancel.cancelled() => Err(crate::agent::CancelledError.into()),
+    };
+    result.map(|()| output)
+}
+
+/// Outcome of warming up a single agent
+#[derive(Debug)]
+pub struct WarmUpReport {
+    pub agent_name: String,
+    pub duration: std::time::Duration,
+    pub result: Result<()>,
+}
+
+/// Whether an agent has finished warming up
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AgentReadiness {
+    /// Registered but not yet warmed up
+    Initializing,
+    Ready,
+    Failed { error: String },
+}
+
+/// Readiness of the agents, as reported by `/readyz`
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessReport {
+    /// Every required agent is registered and warmed up
+    pub ready: bool,
+    pub required: Vec<String>,
+    pub agents: BTreeMap<String, AgentReadiness>,
+}
+
+/// How long outputs of cacheable agents are kept in the result cache
+struct ResultCachePolicy {
+    enabled: bool,
+    default_ttl: std::time::Duration,
+    agent_ttls: HashMap<String, std::time::Duration>,
+}
+
+impl ResultCachePolicy {
+    fn from_settings(settings: &Settings) -> Self {
+        let config = &settings.orchestrator;
+        Self {
+            enabled: config.enable_result_cache,
+            default_ttl: std::time::Duration::from_secs(config.result_cache_ttl_seconds),
+            agent_ttls: config.result_cache_agent_ttls.iter()
+                .map(|(agent, ttl)| (agent.clone(), std::time::Duration::from_secs(*ttl)))
+                .collect(),
+        }
+    }
+
+    fn ttl(&self, agent: &str) -> std::time::Duration {
+        self.agent_ttls.get(agent).copied().unwrap_or(self.default_ttl)
+    }
+
+    /// Key by agent and its registration generation, the caller's memory
+    /// tenant and a hash of the input, so output built from one tenant's
+    /// memory is never served to another, nor output of a replaced agent
+    fn key(agent: &str, generation: u64, tenant: Option<&str>, input: &Value) -> Result<String> {
+        let mut hasher = blake3::Hasher::new();
+        if let Some(tenant) = tenant {
+            // Serialized JSON never contains a NUL, so this can't collide with an input
+            hasher.update(tenant.as_bytes());
+            hasher.update(&[0]);
+        }
+        hasher.update(&serde_json::to_vec(input)?);
+        Ok(format!("agent_result:{}:{}:{}", agent, generation, hasher.finalize().to_hex()))
+    }
+
+    /// Tag carried by every cached result of `agent`
+    fn tag(agent: &str) -> String {
+        format!("agent_result:{}", agent)
+    }
+}
+
+/// Registration generation of each agent name, advanced whenever an agent
+/// is registered under it
+#[derive(Default)]
+struct Generations {
+    next: std::sync::atomic::AtomicU64,
+    current: parking_lot::Mutex<HashMap<String, u64>>,
+}
+
+impl Generations {
+    fn advance(&self, name: &str) {
+        let generation = self.next.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+        self.current.lock().insert(name.to_string(), generation);
+    }
+
+    fn get(&self, name: &str) -> u64 {
+        self.current.lock().get(name).copied().unwrap_or(0)
+    }
+}
+
+/// Outcome of an execution shared with identical concurrent calls; `None`
+/// when the executing call was cancelled and waiters must run it themselves
+type SharedOutcome = Option<Result<Value, Arc<anyhow::Error>>>;
+
+/// An execution of a cacheable agent that identical calls wait on
+struct InFlight {
+    id: u64,
+    outcome: Shared<BoxFuture<'static, SharedOutcome>>,
+}
+
+/// Removes an [`InFlight`] entry when its executing call finishes or is dropped
+struct InFlightGuard<'a> {
+    in_flight: &'a DashMap<String, InFlight>,
+    key: &'a str,
+    id: u64,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.in_flight.remove_if(self.key, |_, flight| flight.id == self.id);
+    }
+}
+
+/// A candidate agent receiving a sample of another agent's traffic
+struct ShadowRoute {
+    shadow: String,
+    sample_rate: f64,
+    calls: std::sync::atomic::AtomicU64,
+}
+
+impl ShadowRoute {
+    fn from_settings(settings: &Settings) -> HashMap<String, ShadowRoute> {
+        settings.orchestrator.shadow_agents.iter()
+            .map(|config| (config.primary.clone(), ShadowRoute {
+                shadow: config.shadow.clone(),
+                sample_rate: config.sample_rate.clamp(0.0, 1.0),
+                calls: std::sync::atomic::AtomicU64::new(0),
+            }))
+            .collect()
+    }
+
+    /// Evenly spaced sampling: mirror call `n` when it crosses the next
+    /// multiple of `1 / sample_rate`
+    fn sample(&self) -> bool {
+        let n = self.calls.fetch_add(1, std::sync::atomic::Ordering::Relaxed) as f64;
+        ((n + 1.0) * self.sample_rate).floor() > (n * self.sample_rate).floor()
+    }
+}
 
 pub struct Orchestrator {
     agents: Arc<Mutex<HashMap<String, Arc<dyn Agent>>>>,
+    middlewares: Mutex<Vec<Arc<dyn AgentMiddleware>>>,
     agent_instances: Arc<Mutex<HashMap<String, Uuid>>>,
+    /// Cancelled when the agent is unregistered, aborting its in-flight calls
+    registrations: parking_lot::Mutex<HashMap<String, CancellationToken>>,
+    /// Registered agents taken offline by an admin; calls to them are refused
+    maintenance: parking_lot::Mutex<HashSet<String>>,
     memory: Arc<Memory>,
     plugin_security_config: PluginSecurityConfig,
-    task_semaphore: Arc<Semaphore>,
-    max_concurrent_tasks: usize,
+    scheduler: Arc<PriorityScheduler>,
+    /// Seconds an agent call may run; hot-reloadable
+    task_timeout_seconds: std::sync::atomic::AtomicU64,
+    input_guard: InputGuard,
+    /// Checks outputs of agents that declare an output schema
+    output_validator: OutputValidator,
+    /// Per-user request and cost quotas, and the usage counted against them
+    quotas: QuotaLedger,
+    /// Agent standing in for names with no registered agent
+    default_agent: Option<String>,
+    /// Warm-up state of each registered agent
+    readiness: parking_lot::Mutex<HashMap<String, AgentReadiness>>,
+    /// Agents that must be ready before the orchestrator reports ready
+    required_agents: Vec<String>,
+    /// Plugins loaded and agents warmed up at once
+    startup_concurrency: usize,
     _bus: mpsc::Sender<PluginEvent>,
     
     // Advanced systems
@@ -37,6 +258,16 @@ pub struct Orchestrator {
     cache_system: Arc<MultiTierCache>,
     websocket_server: Arc<WebSocketServer>,
     agent_mesh: Option<Arc<AgentMesh>>,
+    trace_log: Arc<TraceLog>,
+    result_cache: ResultCachePolicy,
+    /// Part of result cache keys, so a re-registered agent starts afresh
+    generations: Arc<Generations>,
+    /// Executions of cacheable agents keyed like the result cache
+    in_flight: DashMap<String, InFlight>,
+    next_flight_id: std::sync::atomic::AtomicU64,
+    shadows: HashMap<String, ShadowRoute>,
+    io_logger: Option<AgentIoLogger>,
+    plugin_manager: Arc<PluginManager>,
 }
 
 impl Orchestrator {
@@ -49,22 +280,34 @@ impl Orchestrator {
         // Initialize plugin security configuration from settings
         let plugin_security_config = PluginSecurityConfig::from_security_config(&settings.security);
         
-        // Initialize task throttling semaphore
+        // Tasks beyond the concurrency limit queue by priority
         let max_concurrent_tasks = settings.orchestrator.max_concurrent_tasks;
-        let task_semaphore = Arc::new(Semaphore::new(max_concurrent_tasks));
+        let scheduler = Arc::new(PriorityScheduler::new(
+            max_concurrent_tasks,
+            settings.orchestrator.task_queue_capacity,
+        ));
         
         info!("Orchestrator configured with max {} concurrent tasks", max_concurrent_tasks);
 
         // Initialize advanced systems
-        let lifecycle_manager = Arc::new(LifecycleManager::new(LifecycleConfig::default()));
-        let monitoring_system = Arc::new(MonitoringSystem::new(MonitoringConfig::default()));
+        // Health checks and webhook alerts share one connection pool
+        let http_client = pooled_http_client(&HttpPoolConfig::from_settings(&settings.observability))?;
+        let lifecycle_manager = Arc::new(
+            LifecycleManager::new(LifecycleConfig::default()).with_http_client(http_client.clone()),
+        );
+        let monitoring_system = Arc::new(MonitoringSystem::new(MonitoringConfig::default()).with_http_client(http_client));
         let cache_system = Arc::new(MultiTierCache::new(MultiTierCacheConfig::default()).await?);
-        let websocket_server = Arc::new(WebSocketServer::new(WebSocketConfig::default()));
+        let websocket_server = Arc::new(WebSocketServer::new(settings.websocket.clone()));
         
         // Initialize agent mesh if enabled (optional)
-        let agent_mesh = if settings.orchestrator.enable_mesh_networking.unwrap_or(false) {
+        let agent_mesh = if settings.mesh.enabled {
             let mesh_config = MeshConfig {
-                bind_address: settings.server.bind_address.parse()?,
+                bind_address: settings.mesh.bind_address.parse()?,
+                load_balancing_strategy: settings.mesh.load_balancing_strategy.clone(),
+                scoring: settings.mesh.scoring.clone(),
+                proficiency: settings.mesh.proficiency.clone(),
+                enable_encryption: settings.security.mesh_encryption,
+                cluster_secret: settings.security.mesh_cluster_secret.clone(),
                 ..MeshConfig::default()
             };
             Some(Arc::new(AgentMesh::new(mesh_config).await?))
@@ -72,6 +315,18 @@ impl Orchestrator {
             None
         };
 
+        let redactor = OutputRedactor::from_config(&settings.security)?.map(Arc::new);
+        let trace_log = Arc::new(
+            TraceLog::new(settings.orchestrator.trace_log_capacity, settings.orchestrator.enable_trace_log)
+                .with_redactor(redactor.clone()),
+        );
+        // Redaction is outermost, so no other middleware's output escapes it
+        let mut middlewares: Vec<Arc<dyn AgentMiddleware>> = Vec::new();
+        if let Some(redactor) = redactor {
+            middlewares.push(Arc::new(RedactionMiddleware::new(redactor)));
+        }
+        middlewares.push(Arc::new(LoggingMiddleware));
+
         // Start all systems
         lifecycle_manager.start().await?;
         monitoring_system.start().await?;
@@ -84,9 +339,18 @@ impl Orchestrator {
 
         info!("All orchestrator subsystems initialized successfully");
 
+        let plugin_manager = Arc::new(PluginManager::new(
+            settings.plugin_dir.clone(),
+            plugin_security_config.clone(),
+        ));
+
         // ---------- secure hot-reload loop ----------
         let agents_reload = agents.clone();
-        let security_config_clone = plugin_security_config.clone();
+        let generations = Arc::new(Generations::default());
+        let generations_reload = generations.clone();
+        let manager_reload = plugin_manager.clone();
+        let memory_reload = memory.clone();
+        let monitoring_reload = monitoring_system.clone();
 
         tokio::spawn(async move {
             while let Some(evt) = bus_rx.recv().await {
@@ -94,26 +358,39 @@ impl Orchestrator {
                     PluginEvent::Reload(path) => {
                         info!("Processing plugin reload: {:?}", path);
 
-                        match unsafe { plugin::Plugin::load(&path, &security_config_clone) } {
-                            Ok(lib) => {
-                                match unsafe { lib.instantiate() } {
-                                    Ok(agent) => {
-                                        let name = agent.name().to_string();
-                                        let metadata = lib.metadata();
-
-                                        agents_reload.lock().await.insert(name.clone(), Arc::from(agent));
-                                        info!(
-                                            "Successfully reloaded plugin '{}' from {:?} (hash: {})",
-                                            name, path, &metadata.hash[..16]
-                                        );
-                                    }
-                                    Err(e) => {
-                                        error!("Failed to instantiate plugin agent from {:?}: {}", path, e);
-                                    }
+                        let manager = manager_reload.clone();
+                        match tokio::task::spawn_blocking(move || manager.open(&path)).await {
+                            Ok(Ok(candidate)) => {
+                                let agent = candidate.agent.clone();
+                                let name = agent.name().to_string();
+                                let ctx = AgentContext {
+                                    name: name.clone(),
+                                    memory: memory_reload.clone(),
+                                    monitoring: monitoring_reload.clone(),
+                                };
+                                if let Err(e) = agent.on_register(&ctx).await {
+                                    let e = anyhow::Error::new(AgentRegistrationError { agent: name, source: e });
+                                    error!("{}", e);
+                                    manager_reload.reject(candidate, &e);
+                                    continue;
                                 }
+                                let info = candidate.info.clone();
+                                manager_reload.commit(candidate);
+                                let replaced = agents_reload.lock().await.insert(name.clone(), agent.clone());
+                                generations_reload.advance(&name);
+                                if let Some(replaced) = replaced.filter(|old| !Arc::ptr_eq(old, &agent)) {
+                                    Self::run_unregister_hook(&name, replaced).await;
+                                }
+                                info!(
+                                    "Successfully reloaded plugin '{}' from {:?} (hash: {})",
+                                    name, info.path, &info.hash[..16]
+                                );
+                            }
+                            Ok(Err(e)) => {
+                                error!("Failed to reload plugin: {}", e);
                             }
                             Err(e) => {
-                                error!("Failed to load plugin from {:?}: {}", path, e);
+                                error!("Plugin reload task failed: {}", e);
                             }
                         }
                     }
@@ -142,94 +419,636 @@ impl Orchestrator {
 
         Ok(Self {
             agents,
+            middlewares: Mutex::new(middlewares),
             agent_instances,
+            registrations: parking_lot::Mutex::new(HashMap::new()),
+            maintenance: parking_lot::Mutex::new(HashSet::new()),
             memory,
             plugin_security_config,
-            task_semaphore,
-            max_concurrent_tasks,
+            scheduler,
+            task_timeout_seconds: std::sync::atomic::AtomicU64::new(settings.orchestrator.task_timeout_seconds),
+            startup_concurrency: settings.orchestrator.startup_concurrency.max(1),
             _bus: bus_tx,
             lifecycle_manager,
             monitoring_system,
             cache_system,
             websocket_server,
             agent_mesh,
+            trace_log,
+            result_cache: ResultCachePolicy::from_settings(settings),
+            generations,
+            in_flight: DashMap::new(),
+            next_flight_id: std::sync::atomic::AtomicU64::new(0),
+            shadows: ShadowRoute::from_settings(settings),
+            io_logger: AgentIoLogger::from_settings(settings)?,
+            input_guard: InputGuard::from_settings(settings),
+            output_validator: OutputValidator::from_settings(settings),
+            quotas: QuotaLedger::from_settings(settings)?,
+            default_agent: settings.orchestrator.default_agent.clone(),
+            readiness: parking_lot::Mutex::new(HashMap::new()),
+            required_agents: settings.orchestrator.required_agents.clone(),
+            plugin_manager,
         })
     }
 
     /// Dispatch a task `(agent_name, json_in)`; send result via `resp_tx`.
-    #[instrument(skip(self, task), fields(agent_name))]
+    ///
+    /// Failures are sent as an [`OrchestratorError`] wrapping the original
+    /// error, so receivers can tell e.g. a timeout from an agent failure.
     pub async fn dispatch(&self, task: Task) -> Result<()> {
+        self.dispatch_with_cancel(task, CancellationToken::new()).await
+    }
+
+    /// Dispatch a task that stops when `cancel` is triggered. A cancelled task
+    /// reports `CancelledError` instead of whatever the agent produced.
+    pub async fn dispatch_with_cancel(&self, task: Task, cancel: CancellationToken) -> Result<()> {
+        self.dispatch_with_priority(task, TaskPriority::Normal, cancel).await
+    }
+
+    /// Dispatch a task at `priority`. When `max_concurrent_tasks` are already
+    /// running the task waits in the queue, and higher priorities are admitted
+    /// first as slots free up.
+    pub async fn dispatch_with_priority(
+        &self,
+        task: Task,
+        priority: TaskPriority,
+        cancel: CancellationToken,
+    ) -> Result<()> {
+        self.dispatch_as(&Caller::default(), task, priority, cancel).await
+    }
+
+    /// Dispatch a task at `priority` on behalf of `caller`, counting it and
+    /// its cost against the caller's quota (see [`run_agent`](Self::run_agent)).
+    /// The agent retrieves memory context for the caller's tenant only.
+    #[instrument(skip(self, task, cancel), fields(agent_name))]
+    pub async fn dispatch_as(
+        &self,
+        caller: &Caller,
+        task: Task,
+        priority: TaskPriority,
+        cancel: CancellationToken,
+    ) -> Result<()> {
         let (name, input, resp_tx) = task;
         tracing::Span::current().record("agent_name", &name);
+        self.input_guard.check(&name, &input)?;
 
-        // Acquire semaphore permit to limit concurrent tasks
-        let permit = match self.task_semaphore.try_acquire() {
-            Ok(permit) => permit,
-            Err(_) => {
-                warn!("Task queue full ({} concurrent tasks), rejecting task for agent '{}'", 
-                      self.max_concurrent_tasks, name);
-                let error = anyhow::anyhow!("Task queue full - too many concurrent tasks");
-                let _ = resp_tx.send(Err(error)).await;
+        let permit = tokio::select! {
+            permit = self.scheduler.acquire(priority) => match permit {
+                Ok(permit) => permit,
+                Err(e) => {
+                    warn!("Task queue full ({} concurrent tasks), rejecting task for agent '{}'",
+                          self.scheduler.permits(), name);
+                    crate::metrics::platform().record_dispatch(false);
+                    let _ = resp_tx.send(Err(OrchestratorError::QueueFull(e.into()))).await;
+                    return Ok(());
+                }
+            },
+            _ = cancel.cancelled() => {
+                crate::metrics::platform().record_dispatch(false);
+                let _ = resp_tx.send(Err(OrchestratorError::Cancelled(crate::agent::CancelledError.into()))).await;
                 return Ok(());
             }
         };
 
-        let agent = {
-            let map = self.agents.lock().await;
-            match map.get(&name) {
-                Some(agent) => agent.clone(),
-                None => {
-                    let error = anyhow::anyhow!("Unknown agent '{}'", name);
-                    let _ = resp_tx.send(Err(error)).await;
-                    return Ok(());
+        let shadow = self.shadows.get(&name)
+            .filter(|route| route.sample())
+            .map(|route| (route.shadow.clone(), input.clone()));
+
+        // The input guard already ran before the task was queued
+        let response = self.run_admitted(caller, &name, input, cancel).await;
+
+        // Release permit automatically when it goes out of scope
+        drop(permit);
+
+        if let (Some((shadow, input)), Ok(Value::String(output))) = (shadow, &response) {
+            self.spawn_shadow(caller, &name, shadow, input, output.clone()).await;
+        }
+
+        crate::metrics::platform().record_dispatch(response.is_ok());
+        let _ = resp_tx.send(response.map_err(OrchestratorError::from)).await;
+        Ok(())
+    }
+
+    /// Run `shadow` on a copy of the primary's input in the background and
+    /// record whether it produced the same output.
+    ///
+    /// Shadow calls only use a free concurrency slot, never queue, and their
+    /// result and any failure stay out of the primary's response.
+    async fn spawn_shadow(&self, caller: &Caller, primary: &str, shadow: String, input: Value, primary_output: String) {
+        let Some(permit) = self.scheduler.try_acquire() else {
+            debug!("Skipping shadow call to '{}': no free task slot", shadow);
+            return;
+        };
+        let Some(agent) = self.agents.lock().await.get(&shadow).cloned() else {
+            warn!("Shadow agent '{}' for '{}' is not registered", shadow, primary);
+            return;
+        };
+        let middlewares = self.middlewares.lock().await.clone();
+        let memory = self.memory.clone();
+        let monitoring = self.monitoring_system.clone();
+        let primary = primary.to_string();
+        let tenant = caller.tenant.clone();
+        let timeout = self.task_timeout();
+
+        tokio::spawn(async move {
+            let start = std::time::Instant::now();
+            let result = tokio::time::timeout(timeout, async {
+                let input = apply_before(&middlewares, &shadow, input).await?;
+                let output = with_caller_tenant(tenant, agent.handle(input, memory)).await?;
+                apply_after(&middlewares, &shadow, output).await
+            }).await;
+            drop(permit);
+
+            let outcome = match result {
+                Ok(Ok(output)) if output == primary_output => ShadowOutcome::Match,
+                Ok(Ok(_)) => ShadowOutcome::Mismatch,
+                Ok(Err(e)) => {
+                    debug!("Shadow agent '{}' failed: {}", shadow, e);
+                    ShadowOutcome::Failed
+                }
+                Err(_) => ShadowOutcome::Failed,
+            };
+            monitoring.record_shadow_comparison(&primary, &shadow, outcome, start.elapsed()).await;
+        });
+    }
+
+    /// Quota and usage of `user` in the current window
+    pub fn usage(&self, user: &str) -> UsageReport {
+        self.quotas.report(user)
+    }
+
+    /// Hold `user` to `quota` instead of the configured one, or with `None`
+    /// return them to it
+    pub fn set_user_quota(&self, user: &str, quota: Option<Quota>) {
+        self.quotas.set_override(user, quota);
+    }
+
+    /// The agent serving calls to `name` and its estimated cost for `input`,
+    /// when it gives one
+    async fn estimate_cost(&self, name: &str, input: &Value) -> Option<(Arc<dyn Agent>, Cost)> {
+        let (_, agent) = self.resolve_agent(name).await.ok()?;
+        let cost = agent.cost_estimate(input)?;
+        Some((agent, cost))
+    }
+
+    /// Run an agent in streaming mode, forwarding output chunks to `chunks` as
+    /// they are produced. Stops early when `cancel` is triggered or the
+    /// receiver is dropped.
+    pub async fn dispatch_stream(
+        &self,
+        name: &str,
+        input: Value,
+        chunks: mpsc::Sender<String>,
+        cancel: CancellationToken,
+    ) -> Result<()> {
+        self.dispatch_stream_as(&Caller::default(), name, input, chunks, TaskPriority::Normal, cancel).await
+    }
+
+    /// Stream an agent's output on behalf of `caller`, admitted at `priority`
+    /// and counted against the caller's quota as [`dispatch_as`](Self::dispatch_as)
+    /// does. Streamed output is not retained, so a completed stream is charged
+    /// its estimate.
+    ///
+    /// If the agent declares an output schema, the assembled output is checked
+    /// once the stream ends. Chunks have already been sent by then, so in
+    /// "fail" mode a mismatch fails the call after the fact.
+    #[instrument(skip(self, input, chunks, cancel))]
+    pub async fn dispatch_stream_as(
+        &self,
+        caller: &Caller,
+        name: &str,
+        input: Value,
+        chunks: mpsc::Sender<String>,
+        priority: TaskPriority,
+        cancel: CancellationToken,
+    ) -> Result<()> {
+        self.input_guard.check(name, &input)?;
+        let _permit = self.scheduler.acquire(priority).await?;
+        let (name, agent, input) = self.route(name, input).await?;
+        let name = name.as_str();
+        self.check_not_in_maintenance(name)?;
+        let units = agent.cost_estimate(&input).map_or(0.0, |cost| cost.compute_units);
+        let reservation = caller.user.as_deref().map(|user| self.quotas.reserve(user, name, units)).transpose()?;
+        let (cancel, _unlink) = self.link_registration(name, cancel);
+
+        let middlewares = self.middlewares.lock().await.clone();
+        let sampled_input = self.sample_io(name, &input);
+        let output_schema = agent.output_schema()
+            .filter(|_| self.output_validator.mode_for(name) != OutputValidationMode::Off);
+        let start = std::time::Instant::now();
+        let result = tokio::time::timeout(self.task_timeout(), async {
+            let input = apply_before(&middlewares, name, input).await?;
+
+            // Agent output passes through the `after` hooks chunk by chunk;
+            // text they hold back is sent once the agent finishes
+            let (agent_tx, mut agent_rx) = mpsc::channel(chunks.max_capacity());
+            let forward = async {
+                let mut after = StreamAfter::new(&middlewares, name);
+                let mut assembled = String::new();
+                while let Some(chunk) = agent_rx.recv().await {
+                    let chunk = after.chunk(chunk).await?;
+                    if !chunk.is_empty() {
+                        if output_schema.is_some() {
+                            assembled.push_str(&chunk);
+                        }
+                        chunks.send(chunk).await.map_err(|_| crate::agent::CancelledError)?;
+                    }
+                }
+                let rest = after.finish().await?;
+                if !rest.is_empty() {
+                    if output_schema.is_some() {
+                        assembled.push_str(&rest);
+                    }
+                    chunks.send(rest).await.map_err(|_| crate::agent::CancelledError)?;
+                }
+                if let Some(schema) = &output_schema {
+                    self.output_validator.check(name, schema, &assembled)?;
+                }
+                Ok::<(), anyhow::Error>(())
+            };
+            let run = async {
+                let (handled, forwarded) = tokio::join!(
+                    with_caller_tenant(caller.tenant.clone(), agent.handle_stream(input, self.memory.clone(), agent_tx)),
+                    forward,
+                );
+                handled.and(forwarded)
+            };
+
+            tokio::select! {
+                result = run => result,
+                _ = cancel.cancelled() => Err(crate::agent::CancelledError.into()),
+            }
+        }).await;
+
+        let response = match result {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => {
+                error!("Agent '{}' streaming execution failed: {}", name, e);
+                Err(e)
+            }
+            Err(_) => {
+                error!("Agent '{}' streaming execution timed out", name);
+                Err(crate::agent::AgentTimeoutError.into())
+            }
+        };
+
+        self.monitoring_system
+            .record_agent_request(name, response.is_ok(), start.elapsed())
+            .await;
+        crate::metrics::platform().record_dispatch(response.is_ok());
+        if let (Some(logger), Some(input)) = (&self.io_logger, sampled_input) {
+            logger.log(name, &input, None, start.elapsed());
+        }
+        if let (Ok(()), Some(reservation)) = (&response, reservation) {
+            reservation.settle(units);
+        }
+
+        response
+    }
+
+    /// Copy of `input` when this call is sampled for debug I/O logging
+    fn sample_io(&self, name: &str, input: &Value) -> Option<Value> {
+        self.io_logger.as_ref()
+            .filter(|logger| logger.sample(name))
+            .map(|_| input.clone())
+    }
+
+    /// Call `primary`, falling back to each of `fallbacks` in order when a call
+    /// fails with a retryable error (timeouts, agent failures, unknown agents).
+    ///
+    /// Returns the first success or the last error; invalid-input errors are
+    /// returned immediately since every agent would reject them.
+    /// Each agent tried is charged to `caller` as a dispatched task would be.
+    #[instrument(skip(self, caller, input))]
+    pub async fn call_with_fallback(&self, caller: &Caller, primary: &str, fallbacks: &[&str], input: Value) -> Result<Value> {
+        let _permit = self.scheduler.acquire(TaskPriority::Normal).await?;
+
+        let mut last_error = None;
+        for name in std::iter::once(primary).chain(fallbacks.iter().copied()) {
+            match self.run_agent(caller, name, input.clone(), CancellationToken::new()).await {
+                Ok(output) => {
+                    if name != primary {
+                        info!("Agent '{}' served request after '{}' failed", name, primary);
+                        self.monitoring_system.record_fallback(primary, name).await;
+                    }
+                    return Ok(output);
+                }
+                Err(e) if !crate::agent::is_retryable(&e) => return Err(e),
+                Err(e) => {
+                    warn!("Agent '{}' failed, trying next fallback: {}", name, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("No agents to call")))
+    }
+
+    /// Run a single agent for `caller`, counting the call against their quota.
+    ///
+    /// The request and the agent's cost estimate are reserved first; a call
+    /// that would exceed the quota fails with
+    /// [`QuotaExceededError`](crate::quota::QuotaExceededError). Once it
+    /// succeeds, the actual cost is charged if the agent reports one. Every
+    /// way of calling an agent goes through here, so the quota and the input
+    /// guard hold for fallbacks and replays as well as dispatched tasks.
+    async fn run_agent(&self, caller: &Caller, name: &str, input: Value, cancel: CancellationToken) -> Result<Value> {
+        self.input_guard.check(name, &input)?;
+        self.run_admitted(caller, name, input, cancel).await
+    }
+
+    /// [`run_agent`](Self::run_agent) for input that already passed the input guard
+    async fn run_admitted(&self, caller: &Caller, name: &str, input: Value, cancel: CancellationToken) -> Result<Value> {
+        let estimate = self.estimate_cost(name, &input).await;
+        let units = estimate.as_ref().map_or(0.0, |(_, cost)| cost.compute_units);
+        let reservation = match caller.user.as_deref() {
+            Some(user) => Some(self.quotas.reserve(user, name, units).inspect_err(|e| warn!("{}", e))?),
+            None => None,
+        };
+        // Kept to price the call once its output is known
+        let cost_input = estimate.as_ref().map(|_| input.clone());
+
+        let response = self.run_coalesced(caller, name, input, cancel).await;
+
+        let charge = match (estimate, cost_input, &response) {
+            (Some((agent, estimated)), Some(input), Ok(Value::String(output))) => {
+                let actual = agent.actual_cost(&input, output);
+                if let Some(actual) = actual {
+                    debug!("Agent '{}' cost {} tokens against an estimate of {}", name, actual.tokens, estimated.tokens);
+                    crate::metrics::platform().record_cost(estimated.tokens, actual.tokens);
+                }
+                actual.unwrap_or(estimated).compute_units
+            }
+            _ => units,
+        };
+        // Failed calls are not charged; dropping the reservation refunds them
+        if let (Some(reservation), Ok(_)) = (reservation, &response) {
+            reservation.settle(charge);
+        }
+        response
+    }
+
+    /// Run a single agent with the task timeout, recording metrics.
+    ///
+    /// Identical concurrent calls to a cacheable agent share one execution:
+    /// later callers wait for the first one's result instead of running the
+    /// agent again. If the executing call is cancelled, a waiter takes over.
+    async fn run_coalesced(&self, caller: &Caller, name: &str, input: Value, cancel: CancellationToken) -> Result<Value> {
+        let (name, agent, input) = self.route(name, input).await?;
+        let name = name.as_str();
+        self.check_not_in_maintenance(name)?;
+        if !agent.cacheable() {
+            return self.execute_agent(caller, name, agent, input, cancel).await;
+        }
+
+        let key = ResultCachePolicy::key(name, self.generations.get(name), caller.tenant.as_deref(), &input)?;
+        let (id, outcome_tx) = loop {
+            let waiting = match self.in_flight.entry(key.clone()) {
+                dashmap::mapref::entry::Entry::Occupied(flight) => flight.get().outcome.clone(),
+                dashmap::mapref::entry::Entry::Vacant(slot) => {
+                    let id = self.next_flight_id.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    let (outcome_tx, outcome_rx) = tokio::sync::oneshot::channel::<SharedOutcome>();
+                    let outcome = async move { outcome_rx.await.ok().flatten() }.boxed().shared();
+                    slot.insert(InFlight { id, outcome });
+                    break (id, outcome_tx);
+                }
+            };
+
+            debug!("Coalescing call to '{}' with an identical in-flight call", name);
+            tokio::select! {
+                outcome = waiting => match outcome {
+                    Some(Ok(output)) => return Ok(output),
+                    Some(Err(e)) => return Err(SharedError(e).into()),
+                    None => continue,
+                },
+                _ = cancel.cancelled() => return Err(crate::agent::CancelledError.into()),
+            }
+        };
+
+        let _leave = InFlightGuard { in_flight: &self.in_flight, key: &key, id };
+        match self.execute_agent(caller, name, agent, input, cancel).await {
+            Ok(output) => {
+                let _ = outcome_tx.send(Some(Ok(output.clone())));
+                Ok(output)
+            }
+            Err(e) if crate::error::chain_any(&e, |c| c.is::<crate::agent::CancelledError>()) => {
+                let _ = outcome_tx.send(None);
+                Err(e)
+            }
+            Err(e) => {
+                let e = Arc::new(e);
+                let _ = outcome_tx.send(Some(Err(e.clone())));
+                Err(SharedError(e).into())
+            }
+        }
+    }
+
+    /// The name and agent serving calls to `name`: the agent registered under
+    /// it, or else the default agent if one is configured and registered
+    async fn resolve_agent(&self, name: &str) -> Result<(String, Arc<dyn Agent>)> {
+        let agents = self.agents.lock().await;
+        if let Some(agent) = agents.get(name) {
+            return Ok((name.to_string(), agent.clone()));
+        }
+        match self.default_agent.as_ref().and_then(|default| Some((default, agents.get(default)?))) {
+            Some((default, agent)) => Ok((default.clone(), agent.clone())),
+            None => Err(crate::agent::UnknownAgentError(name.to_string()).into()),
+        }
+    }
+
+    /// Resolve `name` and the input to give its agent. A call that lands on
+    /// the default agent carries the requested name as `requested_agent`:
+    /// added to an object input, or wrapping any other input as `input`.
+    async fn route(&self, name: &str, input: Value) -> Result<(String, Arc<dyn Agent>, Value)> {
+        let (resolved, agent) = self.resolve_agent(name).await?;
+        if resolved == name {
+            return Ok((resolved, agent, input));
+        }
+
+        info!("No agent named '{}', routing the call to default agent '{}'", name, resolved);
+        let input = match input {
+            Value::Object(mut fields) => {
+                fields.insert("requested_agent".to_string(), Value::String(name.to_string()));
+                Value::Object(fields)
+            }
+            other => serde_json::json!({ "requested_agent": name, "input": other }),
+        };
+        Ok((resolved, agent, input))
+    }
+
+    /// Run `agent` once under the task timeout, recording metrics, traces
+    /// and cached results
+    async fn execute_agent(
+        &self,
+        caller: &Caller,
+        name: &str,
+        agent: Arc<dyn Agent>,
+        input: Value,
+        cancel: CancellationToken,
+    ) -> Result<Value> {
+        let (cancel, _unlink) = self.link_registration(name, cancel);
+
+        let cache_key = if self.result_cache.enabled && agent.cacheable() {
+            let key = ResultCachePolicy::key(name, self.generations.get(name), caller.tenant.as_deref(), &input)?;
+            match self.cache_system.get::<String>(&key).await {
+                Ok(Some(output)) => {
+                    self.monitoring_system.record_result_cache(name, true).await;
+                    let output = Ok(Value::String(output));
+                    if self.trace_log.is_enabled() {
+                        self.trace_log.record(name, &input, &output, std::time::Duration::ZERO, true).await;
+                    }
+                    return output;
                 }
+                Ok(None) => {}
+                Err(e) => warn!("Result cache lookup for '{}' failed, treating as miss: {}", name, e),
             }
-        }; // Release lock before awaiting
+            self.monitoring_system.record_result_cache(name, false).await;
+            Some(key)
+        } else {
+            None
+        };
 
         // Execute agent with timeout and error handling
         let memory_clone = self.memory.clone();
+        let middlewares = self.middlewares.lock().await.clone();
+        // Traces keep the caller's input so replays take the same middleware path
+        let traced_input = self.trace_log.is_enabled().then(|| input.clone());
+        let sampled_input = self.sample_io(name, &input);
+        let output_schema = agent.output_schema();
         let start = std::time::Instant::now();
         let result = tokio::time::timeout(
-            std::time::Duration::from_secs(30), // 30 second timeout
-            agent.handle(input, memory_clone)
+            self.task_timeout(),
+            async {
+                let input = apply_before(&middlewares, name, input).await?;
+                let output = match PROGRESS.try_with(Clone::clone) {
+                    Ok(progress) => with_caller_tenant(
+                        caller.tenant.clone(),
+                        collect_stream(agent.as_ref(), input, memory_clone, progress, &cancel),
+                    ).await?,
+                    Err(_) => with_caller_tenant(
+                        caller.tenant.clone(),
+                        agent.handle_cancellable(input, memory_clone, cancel.clone()),
+                    ).await?,
+                };
+                let output = apply_after(&middlewares, name, output).await?;
+                // Checked before caching, so only conforming results are replayed
+                if let Some(schema) = &output_schema {
+                    self.output_validator.check(name, schema, &output)?;
+                }
+                Ok(output)
+            }
         ).await;
 
         let response = match result {
+            // Agents that finish despite cancellation don't get their result delivered
+            _ if cancel.is_cancelled() => {
+                info!("Agent '{}' task cancelled", name);
+                Err(crate::agent::CancelledError.into())
+            }
             Ok(Ok(output)) => Ok(Value::String(output)),
             Ok(Err(e)) => {
                 error!("Agent '{}' execution failed: {}", name, e);
-                self.monitoring_system
-                    .record_agent_request(&name, false, start.elapsed())
-                    .await;
                 Err(e)
             }
             Err(_) => {
                 error!("Agent '{}' execution timed out", name);
-                self.monitoring_system
-                    .record_agent_request(&name, false, start.elapsed())
-                    .await;
-                Err(anyhow::anyhow!("Agent execution timed out"))
+                Err(crate::agent::AgentTimeoutError.into())
             }
         };
 
-        if response.is_ok() {
-            self.monitoring_system
-                .record_agent_request(&name, true, start.elapsed())
-                .await;
+        self.monitoring_system
+            .record_agent_request(name, response.is_ok(), start.elapsed())
+            .await;
+        if let Some(input) = traced_input {
+            self.trace_log.record(name, &input, &response, start.elapsed(), false).await;
+        }
+        if let (Some(logger), Some(input)) = (&self.io_logger, sampled_input) {
+            let output = response.as_ref().map(|output| output.as_str().unwrap_or_default());
+            logger.log(name, &input, Some(output), start.elapsed());
+        }
+        if let (Some(key), Ok(Value::String(output))) = (cache_key, &response) {
+            let tags = vec![ResultCachePolicy::tag(name)];
+            if let Err(e) = self.cache_system.set_tagged(&key, output.clone(), Some(self.result_cache.ttl(name)), tags).await {
+                warn!("Failed to cache result of '{}': {}", name, e);
+            }
         }
 
-        // Release permit automatically when it goes out of scope
-        drop(permit);
+        response
+    }
 
-        let _ = resp_tx.send(response).await;
-        Ok(())
+    /// Re-run the input recorded in a trace entry against the same agent,
+    /// charged to `caller`
+    #[instrument(skip(self, caller))]
+    pub async fn replay_trace(&self, caller: &Caller, id: Uuid) -> Result<Value> {
+        let entry = self.trace_log.get(id).await
+            .ok_or_else(|| anyhow::anyhow!("Trace entry {} not found", id))?;
+        let _permit = self.scheduler.acquire(TaskPriority::Normal).await?;
+
+        info!("Replaying trace {} against agent '{}'", id, entry.agent);
+        self.run_agent(caller, &entry.agent, entry.input, CancellationToken::new()).await
+    }
+
+    /// Tasks currently waiting for a free slot, per priority
+    pub fn task_queue_depths(&self) -> HashMap<TaskPriority, usize> {
+        self.scheduler.queue_depths()
+    }
+
+    /// Append a middleware to the chain wrapping every agent call
+    pub async fn add_middleware(&self, middleware: Arc<dyn AgentMiddleware>) {
+        self.middlewares.lock().await.push(middleware);
     }
 
-    /// Register a built-in agent
+    /// Register a built-in agent under a name no other agent holds.
+    ///
+    /// The agent's [`Agent::on_register`] hook runs first; if it fails the
+    /// registration fails with [`AgentRegistrationError`]. A taken name fails
+    /// with [`DuplicateAgentError`]; use [`Orchestrator::replace_agent`] to
+    /// swap out a registered agent.
     #[instrument(skip(self, agent))]
     pub async fn register_agent(&self, name: String, agent: Arc<dyn Agent>) -> Result<()> {
+        self.register(name, agent, false).await
+    }
+
+    /// Register a built-in agent, replacing any agent of the same name.
+    ///
+    /// A replaced agent's [`Agent::on_unregister`] hook runs once the new one
+    /// is in place, unless it is the same instance registered again.
+    #[instrument(skip(self, agent))]
+    pub async fn replace_agent(&self, name: String, agent: Arc<dyn Agent>) -> Result<()> {
+        self.register(name, agent, true).await
+    }
+
+    async fn register(&self, name: String, agent: Arc<dyn Agent>, replace: bool) -> Result<()> {
         info!("Registering built-in agent: {}", name);
-        self.agents.lock().await.insert(name.clone(), agent);
+        if !replace && self.agents.lock().await.contains_key(&name) {
+            return Err(DuplicateAgentError(name).into());
+        }
+        let ctx = AgentContext {
+            name: name.clone(),
+            memory: self.memory.clone(),
+            monitoring: self.monitoring_system.clone(),
+        };
+        agent.on_register(&ctx).await
+            .map_err(|source| AgentRegistrationError { agent: name.clone(), source })?;
+
+        let replaced = {
+            let mut agents = self.agents.lock().await;
+            // Another registration may have taken the name while the hook ran
+            if !replace && agents.contains_key(&name) {
+                drop(agents);
+                Self::run_unregister_hook(&name, agent).await;
+                return Err(DuplicateAgentError(name).into());
+            }
+            let replaced = agents.insert(name.clone(), agent.clone());
+            crate::metrics::platform().set_agents_registered(agents.len());
+            replaced
+        };
+        self.generations.advance(&name);
+        if let Some(replaced) = replaced.filter(|old| !Arc::ptr_eq(old, &agent)) {
+            Self::run_unregister_hook(&name, replaced).await;
+        }
+        self.registrations.lock().entry(name.clone()).or_insert_with(CancellationToken::new);
+        self.readiness.lock().insert(name.clone(), AgentReadiness::Initializing);
         let instance_id = self
             .lifecycle_manager
             .register_agent_instance(&name)
@@ -238,51 +1057,473 @@ impl Orchestrator {
         Ok(())
     }
 
-    /// Get list of registered agents with their types
-    pub async fn list_agents(&self) -> Vec<(String, String)> {
-        let agents_map = self.agents.lock().await;
-        agents_map.iter()
-            .map(|(name, agent)| (name.clone(), agent.agent_type().to_string()))
+    /// Re-scan the plugin directory and register agents from newly added libraries
+    ///
+    /// Up to `startup_concurrency` libraries load at once. A failing library is
+    /// reported without stopping the others, and agents are registered in path
+    /// order so the result doesn't depend on which load finished first.
+    /// Each newly registered agent is then warmed up, so readiness reflects
+    /// it; a warm-up failure leaves the agent registered but not ready.
+    #[instrument(skip(self))]
+    pub async fn reload_plugins(&self) -> Result<PluginLoadReport> {
+        use futures::StreamExt;
+
+        let report = self.register_plugins().await?;
+        let warmed: Vec<(String, Result<std::time::Duration>)> = futures::stream::iter(
+            report.loaded.iter().flat_map(|info| info.agents.iter().cloned()),
+        )
+            .map(|name| async move {
+                let result = self.warm_up_agent(&name).await;
+                (name, result)
+            })
+            .buffer_unordered(self.startup_concurrency)
             .collect()
+            .await;
+        for (name, result) in warmed {
+            match result {
+                Ok(duration) => info!("Plugin agent '{}' warmed up in {}ms", name, duration.as_millis()),
+                Err(e) => warn!("Plugin agent '{}' failed to warm up: {}", name, e),
+            }
+        }
+        Ok(report)
     }
 
-    /// Remove a registered agent
-    #[instrument(skip(self))]
-    pub async fn remove_agent(&self, name: &str) -> Result<()> {
-        info!("Removing agent: {}", name);
-        if self.agents.lock().await.remove(name).is_some() {
-            if let Some(id) = self.agent_instances.lock().await.remove(name) {
-                let _ = self.lifecycle_manager.shutdown_agent(id).await;
+    /// Load and register agents from newly added libraries like
+    /// [`Orchestrator::reload_plugins`], without warming them up. For startup,
+    /// where every agent is warmed up together afterwards.
+    pub async fn register_plugins(&self) -> Result<PluginLoadReport> {
+        use futures::StreamExt;
+
+        let start = std::time::Instant::now();
+        let manager = self.plugin_manager.clone();
+        let (paths, skipped) = tokio::task::spawn_blocking(move || manager.pending()).await??;
+
+        let results: Vec<_> = futures::stream::iter(paths)
+            .map(|path| {
+                let manager = self.plugin_manager.clone();
+                async move {
+                    let load_path = path.clone();
+                    let result = tokio::task::spawn_blocking(move || manager.open(&load_path))
+                        .await
+                        .map_err(|e| anyhow::anyhow!("Plugin load task failed: {}", e))
+                        .and_then(|result| result);
+                    (path, result)
+                }
+            })
+            .buffered(self.startup_concurrency)
+            .collect()
+            .await;
+
+        let mut report = PluginLoadReport { skipped, ..Default::default() };
+        for (path, result) in results {
+            match result {
+                Ok(candidate) => {
+                    let agent = candidate.agent.clone();
+                    match self.replace_agent(agent.name().to_string(), agent).await {
+                        Ok(()) => {
+                            report.loaded.push(candidate.info.clone());
+                            self.plugin_manager.commit(candidate);
+                        }
+                        Err(e) => {
+                            report.failed.push(PluginFailure { path, error: format!("{:#}", e) });
+                            self.plugin_manager.reject(candidate, &e);
+                        }
+                    }
+                }
+                Err(e) => report.failed.push(PluginFailure { path, error: format!("{:#}", e) }),
             }
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Agent '{}' not found", name))
         }
+
+        info!(
+            "Plugin reload: {} loaded, {} failed, {} already loaded in {}ms",
+            report.loaded.len(), report.failed.len(), report.skipped.len(), start.elapsed().as_millis()
+        );
+        for failure in &report.failed {
+            warn!("Plugin {:?} not loaded: {}", failure.path, failure.error);
+        }
+        Ok(report)
     }
 
-    /// Get plugin security configuration
-    pub fn plugin_security_config(&self) -> &PluginSecurityConfig {
-        &self.plugin_security_config
+    /// Warm up a registered agent, returning how long it took
+    pub async fn warm_up_agent(&self, name: &str) -> Result<std::time::Duration> {
+        let agent = self.agents.lock().await.get(name).cloned()
+            .ok_or_else(|| crate::agent::UnknownAgentError(name.to_string()))?;
+        let start = std::time::Instant::now();
+        let result = agent.warm_up().await;
+        self.record_warm_up(name, &result);
+        result.map(|_| start.elapsed())
     }
 
-    /// Update plugin security configuration
-    pub fn update_plugin_security_config(&mut self, config: PluginSecurityConfig) {
-        self.plugin_security_config = config;
+    /// Warm up every registered agent, up to `startup_concurrency` at a time
+    /// if `parallel` is set. Reports keep the order agents were listed in.
+    pub async fn warm_up_agents(&self, parallel: bool) -> Vec<WarmUpReport> {
+        use futures::StreamExt;
+
+        // Snapshot so slow warm-ups don't hold the agents lock
+        let agents: Vec<(String, Arc<dyn Agent>)> = self.agents.lock().await
+            .iter()
+            .map(|(name, agent)| (name.clone(), agent.clone()))
+            .collect();
+
+        let warm_up = |(name, agent): (String, Arc<dyn Agent>)| async move {
+            let start = std::time::Instant::now();
+            let result = agent.warm_up().await;
+            self.record_warm_up(&name, &result);
+            WarmUpReport { agent_name: name, duration: start.elapsed(), result }
+        };
+
+        if parallel {
+            futures::stream::iter(agents)
+                .map(warm_up)
+                .buffered(self.startup_concurrency)
+                .collect()
+                .await
+        } else {
+            let mut reports = Vec::with_capacity(agents.len());
+            for entry in agents {
+                reports.push(warm_up(entry).await);
+            }
+            reports
+        }
     }
 
-    /// Get a clone of the memory system
-    pub fn memory(&self) -> Arc<Memory> {
-        self.memory.clone()
+    /// Mark a still-registered agent ready or failed after warming up
+    fn record_warm_up(&self, name: &str, result: &Result<()>) {
+        if let Some(state) = self.readiness.lock().get_mut(name) {
+            *state = match result {
+                Ok(()) => AgentReadiness::Ready,
+                Err(e) => AgentReadiness::Failed { error: e.to_string() },
+            };
+        }
     }
 
-    /// Get monitoring system handle
-    pub fn monitoring(&self) -> Arc<MonitoringSystem> {
-        self.monitoring_system.clone()
+    /// Warm-up state of every registered agent. The orchestrator is ready
+    /// once each agent in `orchestrator.required_agents` is registered and
+    /// warmed up; other agents never hold readiness back.
+    pub fn readiness(&self) -> ReadinessReport {
+        let agents: BTreeMap<String, AgentReadiness> = self.readiness.lock()
+            .iter()
+            .map(|(name, state)| (name.clone(), state.clone()))
+            .collect();
+        let ready = self.required_agents.iter()
+            .all(|name| agents.get(name) == Some(&AgentReadiness::Ready));
+        ReadinessReport { ready, required: self.required_agents.clone(), agents }
     }
 
-    /// Gracefully shutdown all running agents
-    pub async fn shutdown(&self) -> Result<()> {
-        self.lifecycle_manager.shutdown_all().await
+    /// Change how many tasks run at once. Running tasks are never cut short;
+    /// a lower limit takes effect as they finish.
+    pub fn set_max_concurrent_tasks(&self, max_concurrent_tasks: usize) {
+        self.scheduler.resize(max_concurrent_tasks);
+        info!("Orchestrator now runs at most {} concurrent tasks", max_concurrent_tasks);
+    }
+
+    /// Change how long an agent call may run. Calls already running keep
+    /// the timeout they started with.
+    pub fn set_task_timeout(&self, timeout: std::time::Duration) {
+        self.task_timeout_seconds.store(timeout.as_secs(), std::sync::atomic::Ordering::Relaxed);
+        info!("Agent calls now time out after {}s", timeout.as_secs());
+    }
+
+    /// How long an agent call may run
+    pub fn task_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.task_timeout_seconds.load(std::sync::atomic::Ordering::Relaxed))
+    }
+
+    /// Whether an agent is registered under `name`
+    pub async fn has_agent(&self, name: &str) -> bool {
+        self.agents.lock().await.contains_key(name)
+    }
+
+    /// Whether calls to `name` reach an agent, either one registered under it
+    /// or the default agent
+    pub async fn can_dispatch(&self, name: &str) -> bool {
+        self.resolve_agent(name).await.is_ok()
+    }
+
+    /// Take a registered agent offline, or bring it back, without
+    /// unregistering it; its state, metrics and listing are kept.
+    ///
+    /// While in maintenance new calls fail with `AgentMaintenanceError`;
+    /// calls already running finish normally. Returns whether the state changed.
+    #[instrument(skip(self))]
+    pub async fn set_agent_maintenance(&self, name: &str, in_maintenance: bool) -> Result<bool> {
+        // Held across the update so a concurrent removal can't leave a stale entry
+        let agents = self.agents.lock().await;
+        if !agents.contains_key(name) {
+            return Err(crate::agent::UnknownAgentError(name.to_string()).into());
+        }
+        let mut maintenance = self.maintenance.lock();
+        let changed = if in_maintenance {
+            maintenance.insert(name.to_string())
+        } else {
+            maintenance.remove(name)
+        };
+        crate::metrics::platform().set_agents_in_maintenance(maintenance.len());
+        if changed {
+            info!("Agent '{}' {} maintenance", name, if in_maintenance { "entered" } else { "left" });
+        }
+        Ok(changed)
+    }
+
+    /// Whether `name` has been taken offline with [`Orchestrator::set_agent_maintenance`]
+    pub fn is_in_maintenance(&self, name: &str) -> bool {
+        self.maintenance.lock().contains(name)
+    }
+
+    fn check_not_in_maintenance(&self, name: &str) -> Result<()> {
+        if self.is_in_maintenance(name) {
+            return Err(crate::agent::AgentMaintenanceError(name.to_string()).into());
+        }
+        Ok(())
+    }
+
+    /// Get list of registered agents with their types
+    pub async fn list_agents(&self) -> Vec<(String, String)> {
+        let agents_map = self.agents.lock().await;
+        agents_map.iter()
+            .map(|(name, agent)| (name.clone(), agent.agent_type().to_string()))
+            .collect()
+    }
+
+    /// Get list of registered agents with their types and capabilities
+    pub async fn list_agent_capabilities(&self) -> Vec<(String, String, Vec<String>)> {
+        let agents_map = self.agents.lock().await;
+        agents_map.iter()
+            .map(|(name, agent)| (name.clone(), agent.agent_type().to_string(), agent.capabilities()))
+            .collect()
+    }
+
+    /// Describe every registered agent, sorted by name
+    pub async fn agent_schemas(&self) -> Vec<crate::agent::AgentSchema> {
+        let mut schemas: Vec<_> = self.agents.lock().await
+            .iter()
+            .map(|(name, agent)| crate::agent::AgentSchema {
+                name: name.clone(),
+                agent_type: agent.agent_type().to_string(),
+                capabilities: agent.capabilities(),
+                input_schema: agent.input_schema(),
+                output_schema: agent.output_schema(),
+            })
+            .collect();
+        schemas.sort_by(|a, b| a.name.cmp(&b.name));
+        schemas
+    }
+
+    /// Build a reverse index of capability -> names of agents providing it
+    pub async fn capability_index(&self) -> BTreeMap<String, Vec<String>> {
+        let mut index: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (name, _, capabilities) in self.list_agent_capabilities().await {
+            for capability in capabilities {
+                index.entry(capability).or_default().push(name.clone());
+            }
+        }
+        for agents in index.values_mut() {
+            agents.sort();
+            agents.dedup();
+        }
+        index
+    }
+
+    /// Remove a registered agent
+    #[instrument(skip(self))]
+    pub async fn remove_agent(&self, name: &str) -> Result<()> {
+        info!("Removing agent: {}", name);
+        let removed = {
+            let mut agents = self.agents.lock().await;
+            let removed = agents.remove(name);
+            crate::metrics::platform().set_agents_registered(agents.len());
+            removed
+        };
+        match removed {
+            Some(agent) => {
+                self.forget_agent(name).await;
+                Self::run_unregister_hook(name, agent).await;
+                Ok(())
+            }
+            None => Err(anyhow::anyhow!("Agent '{}' not found", name)),
+        }
+    }
+
+    /// Remove every registered agent except those named in `preserve`,
+    /// returning the removed names in order.
+    ///
+    /// Calls already running on a removed agent are cancelled and report
+    /// `CancelledError`; later calls fail as unknown agents. Loaded plugins
+    /// are forgotten too, so [`Orchestrator::reload_plugins`] registers them
+    /// again. Safe to call while requests are being served.
+    #[instrument(skip(self))]
+    pub async fn unregister_all(&self, preserve: &[&str]) -> Vec<String> {
+        self.unregister_matching(|name, _| !preserve.contains(&name)).await
+    }
+
+    /// Remove the agents created by loaded plugins, leaving agents registered
+    /// through the API in place, and return the removed names in order.
+    /// An API registration that replaced a plugin's agent under the same name
+    /// is kept. Otherwise behaves like [`Orchestrator::unregister_all`].
+    #[instrument(skip(self))]
+    pub async fn unregister_plugin_agents(&self) -> Vec<String> {
+        let from_plugins = self.plugin_manager.loaded_agents();
+        self.unregister_matching(|name, agent| {
+            from_plugins.iter().any(|(plugin_name, plugin_agent)| {
+                plugin_name == name
+                    && std::ptr::addr_eq(Arc::as_ptr(plugin_agent), Arc::as_ptr(agent))
+            })
+        }).await
+    }
+
+    async fn unregister_matching(&self, remove: impl Fn(&str, &Arc<dyn Agent>) -> bool) -> Vec<String> {
+        let mut removed: Vec<(String, Arc<dyn Agent>)> = {
+            let mut agents = self.agents.lock().await;
+            let names = agents.iter()
+                .filter(|(name, agent)| remove(name, agent))
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>();
+            let removed = names.into_iter()
+                .filter_map(|name| agents.remove(&name).map(|agent| (name, agent)))
+                .collect();
+            crate::metrics::platform().set_agents_registered(agents.len());
+            removed
+        };
+        removed.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (name, _) in &removed {
+            self.forget_agent(name).await;
+        }
+        for (name, agent) in &removed {
+            Self::run_unregister_hook(name, agent.clone()).await;
+        }
+        let retired = self.plugin_manager.retire_all();
+        info!("Unregistered {} agents ({} plugin libraries retired)", removed.len(), retired);
+        removed.into_iter().map(|(name, _)| name).collect()
+    }
+
+    /// Run a removed agent's `on_unregister` hook, logging failure
+    async fn run_unregister_hook(name: &str, agent: Arc<dyn Agent>) {
+        if let Err(e) = agent.on_unregister().await {
+            warn!("Agent '{}' failed to unregister cleanly: {:#}", name, e);
+        }
+    }
+
+    /// Drop the bookkeeping kept for an unregistered agent and cancel its
+    /// in-flight calls
+    async fn forget_agent(&self, name: &str) {
+        if let Some(registration) = self.registrations.lock().remove(name) {
+            registration.cancel();
+        }
+        self.readiness.lock().remove(name);
+        {
+            let mut maintenance = self.maintenance.lock();
+            if maintenance.remove(name) {
+                crate::metrics::platform().set_agents_in_maintenance(maintenance.len());
+            }
+        }
+        if let Some(id) = self.agent_instances.lock().await.remove(name) {
+            let _ = self.lifecycle_manager.shutdown_agent(id).await;
+        }
+        self.invalidate_results(name).await;
+    }
+
+    /// Drop every cached result of `name`, so a later agent registered under
+    /// the same name never serves them
+    async fn invalidate_results(&self, name: &str) {
+        match self.cache_system.invalidate_by_tag(&ResultCachePolicy::tag(name)).await {
+            Ok(0) => {}
+            Ok(count) => debug!("Dropped {} cached results of '{}'", count, name),
+            Err(e) => warn!("Failed to drop cached results of '{}': {}", name, e),
+        }
+    }
+
+    /// Token cancelled by `cancel` or by unregistering `name`, whichever comes
+    /// first. Dropping the guard stops watching `cancel`.
+    fn link_registration(&self, name: &str, cancel: CancellationToken) -> (CancellationToken, DropGuard) {
+        let linked = match self.registrations.lock().get(name) {
+            Some(registration) => registration.child_token(),
+            None => CancellationToken::new(),
+        };
+        let watcher = linked.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = cancel.cancelled() => watcher.cancel(),
+                _ = watcher.cancelled() => {}
+            }
+        });
+        (linked.clone(), linked.drop_guard())
+    }
+
+    /// Swap the model behind a registered agent without unregistering it;
+    /// requests already running finish on the previous model. Results cached
+    /// from the previous model are dropped, and those still running are not
+    /// cached for the new one.
+    #[instrument(skip(self))]
+    pub async fn reload_agent_model(&self, name: &str, path: &std::path::Path) -> Result<crate::agent::ModelReloadReport> {
+        let agent = self.agents.lock().await.get(name).cloned()
+            .ok_or_else(|| crate::agent::UnknownAgentError(name.to_string()))?;
+        let report = agent.reload_model(path).await?;
+        self.generations.advance(name);
+        self.invalidate_results(name).await;
+        Ok(report)
+    }
+
+    /// Get plugin security configuration
+    pub fn plugin_security_config(&self) -> &PluginSecurityConfig {
+        &self.plugin_security_config
+    }
+
+    /// Update plugin security configuration
+    pub fn update_plugin_security_config(&mut self, config: PluginSecurityConfig) {
+        self.plugin_security_config = config;
+    }
+
+    /// Get a clone of the memory system
+    pub fn memory(&self) -> Arc<Memory> {
+        self.memory.clone()
+    }
+
+    /// Get shared multi-tier cache handle
+    pub fn cache(&self) -> Arc<MultiTierCache> {
+        self.cache_system.clone()
+    }
+
+    /// Get the agent lifecycle manager
+    pub fn lifecycle(&self) -> Arc<LifecycleManager> {
+        self.lifecycle_manager.clone()
+    }
+
+    /// Get the agent execution trace log
+    pub fn trace_log(&self) -> Arc<TraceLog> {
+        self.trace_log.clone()
+    }
+
+    /// Get monitoring system handle
+    pub fn monitoring(&self) -> Arc<MonitoringSystem> {
+        self.monitoring_system.clone()
+    }
+
+    /// The agent mesh, when mesh networking is enabled
+    pub fn mesh(&self) -> Option<Arc<AgentMesh>> {
+        self.agent_mesh.clone()
+    }
+
+    /// Get the native plugin manager
+    pub fn plugin_manager(&self) -> Arc<PluginManager> {
+        self.plugin_manager.clone()
+    }
+
+    /// Get the real-time WebSocket server
+    pub fn websocket(&self) -> Arc<WebSocketServer> {
+        self.websocket_server.clone()
+    }
+
+    /// Wait until no task is running or queued, checking every `poll`
+    pub async fn drain(&self, poll: std::time::Duration) {
+        while !self.scheduler.is_idle() {
+            tokio::time::sleep(poll).await;
+        }
+    }
+
+    /// Gracefully shutdown all running agents
+    pub async fn shutdown(&self) -> Result<()> {
+        self.lifecycle_manager.shutdown_all().await
     }
 
     /// Get the number of memory fragments
@@ -291,6 +1532,58 @@ impl Orchestrator {
     }
 }
 
+/// Serves agent requests made over WebSocket connections by dispatching them
+/// like any other task, for the caller the connection authenticated as.
+/// Holds the orchestrator weakly, since the orchestrator owns the WebSocket
+/// server.
+pub struct WebSocketAgentHandler {
+    orchestrator: std::sync::Weak<tokio::sync::RwLock<Orchestrator>>,
+    auth_manager: Arc<crate::auth::AuthManager>,
+    output_redactor: Option<Arc<OutputRedactor>>,
+    settings: Settings,
+}
+
+impl WebSocketAgentHandler {
+    pub fn new(
+        orchestrator: &Arc<tokio::sync::RwLock<Orchestrator>>,
+        auth_manager: Arc<crate::auth::AuthManager>,
+        output_redactor: Option<Arc<OutputRedactor>>,
+        settings: Settings,
+    ) -> Self {
+        Self { orchestrator: Arc::downgrade(orchestrator), auth_manager, output_redactor, settings }
+    }
+}
+
+#[async_trait::async_trait]
+impl AgentRequestHandler for WebSocketAgentHandler {
+    fn authenticate(&self, token: &str) -> Result<Caller> {
+        let claims = self.auth_manager.validate_token(token)?;
+        Ok(crate::server::task_caller(&self.settings, Some(&claims)))
+    }
+
+    async fn run(&self, caller: &Caller, agent: &str, input: Value, cancel: CancellationToken) -> Result<Value> {
+        let orchestrator = self.orchestrator.upgrade()
+            .ok_or_else(|| anyhow::anyhow!("The orchestrator has shut down"))?;
+        let orchestrator = orchestrator.read().await;
+        let (resp_tx, mut resp_rx) = mpsc::channel(1);
+        orchestrator.dispatch_as(
+            caller,
+            (agent.to_string(), input, resp_tx),
+            TaskPriority::High,
+            cancel,
+        ).await?;
+        let output = match resp_rx.recv().await {
+            Some(result) => result.map_err(OrchestratorError::into_source_error)?,
+            None => return Err(anyhow::anyhow!("Agent '{}' finished without a result", agent)),
+        };
+        Ok(match (&self.output_redactor, output) {
+            (Some(redactor), Value::String(text)) => Value::String(redactor.redact(&text)),
+            (Some(redactor), other) => Value::String(redactor.redact(&other.to_string())),
+            (None, output) => output,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -321,6 +1614,471 @@ mod tests {
         assert!(agents.contains(&"test_echo".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_orchestrator_capability_index() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(
+            echo_agent.clone(),
+            echo_agent.clone(),
+            cache,
+        ));
+
+        let settings = crate::settings::Settings::default();
+        let orchestrator = Orchestrator::new(&settings, memory).await.unwrap();
+
+        orchestrator.register_agent("echo_a".to_string(), Arc::new(EchoAgent::new())).await.unwrap();
+        orchestrator.register_agent("echo_b".to_string(), Arc::new(EchoAgent::new())).await.unwrap();
+        orchestrator
+            .register_agent("embedder".to_string(), Arc::new(crate::agent::HashEmbeddingAgent::new(8)))
+            .await
+            .unwrap();
+
+        let agents = orchestrator.list_agent_capabilities().await;
+        let (_, _, capabilities) = agents.iter().find(|(name, _, _)| name == "embedder").unwrap();
+        assert_eq!(capabilities, &vec!["embedding".to_string()]);
+
+        let index = orchestrator.capability_index().await;
+        assert_eq!(index["embedding"], vec!["embedder".to_string()]);
+        assert_eq!(index["text_echo"], vec!["echo_a".to_string(), "echo_b".to_string()]);
+    }
+
+    struct FailingWarmUpAgent;
+
+    #[async_trait::async_trait]
+    impl Agent for FailingWarmUpAgent {
+        fn name(&self) -> &str { "failing" }
+        fn agent_type(&self) -> &str { "test" }
+        fn capabilities(&self) -> Vec<String> { vec![] }
+        async fn handle(&self, _input: Value, _memory: Arc<Memory>) -> Result<String> {
+            Ok(String::new())
+        }
+        async fn health_check(&self) -> Result<crate::agent::AgentHealth> {
+            Ok(crate::agent::AgentHealth::default())
+        }
+        async fn warm_up(&self) -> Result<()> {
+            Err(anyhow::anyhow!("model missing"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_orchestrator_warm_up_reports() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(
+            echo_agent.clone(),
+            echo_agent.clone(),
+            cache,
+        ));
+
+        let settings = crate::settings::Settings::default();
+        let orchestrator = Orchestrator::new(&settings, memory).await.unwrap();
+        orchestrator.register_agent("echo".to_string(), Arc::new(EchoAgent::new())).await.unwrap();
+        orchestrator.register_agent("failing".to_string(), Arc::new(FailingWarmUpAgent)).await.unwrap();
+
+        for parallel in [true, false] {
+            let reports = orchestrator.warm_up_agents(parallel).await;
+            assert_eq!(reports.len(), 2);
+            for report in reports {
+                assert_eq!(report.result.is_ok(), report.agent_name == "echo");
+            }
+        }
+
+        assert!(orchestrator.warm_up_agent("echo").await.is_ok());
+        assert!(orchestrator.warm_up_agent("missing").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_readiness_waits_for_required_agents() {
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(echo_agent.clone(), echo_agent, Arc::new(InMemoryEmbeddingCache::new())));
+        let mut settings = crate::settings::Settings::default();
+        settings.orchestrator.required_agents = vec!["echo".to_string()];
+        let orchestrator = Orchestrator::new(&settings, memory).await.unwrap();
+
+        // Required agent not registered yet
+        assert!(!orchestrator.readiness().ready);
+
+        orchestrator.register_agent("echo".to_string(), Arc::new(EchoAgent::new())).await.unwrap();
+        orchestrator.register_agent("failing".to_string(), Arc::new(FailingWarmUpAgent)).await.unwrap();
+        let report = orchestrator.readiness();
+        assert!(!report.ready);
+        assert_eq!(report.agents["echo"], AgentReadiness::Initializing);
+
+        orchestrator.warm_up_agents(true).await;
+        let report = orchestrator.readiness();
+        assert!(report.ready, "a failed optional agent must not block readiness");
+        assert_eq!(report.agents["echo"], AgentReadiness::Ready);
+        assert!(matches!(report.agents["failing"], AgentReadiness::Failed { .. }));
+
+        orchestrator.remove_agent("echo").await.unwrap();
+        assert!(!orchestrator.readiness().ready);
+    }
+
+    /// Agent whose calls always fail, optionally with an invalid-input error
+    struct ErroringAgent {
+        invalid_input: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for ErroringAgent {
+        fn name(&self) -> &str { "erroring" }
+        fn agent_type(&self) -> &str { "test" }
+        fn capabilities(&self) -> Vec<String> { vec![] }
+        async fn handle(&self, _input: Value, _memory: Arc<Memory>) -> Result<String> {
+            if self.invalid_input {
+                Err(crate::agent::InvalidInputError("bad input".to_string()).into())
+            } else {
+                Err(anyhow::anyhow!("backend unavailable"))
+            }
+        }
+        async fn health_check(&self) -> Result<crate::agent::AgentHealth> {
+            Ok(crate::agent::AgentHealth::default())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_orchestrator_call_with_fallback() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(
+            echo_agent.clone(),
+            echo_agent.clone(),
+            cache,
+        ));
+
+        let settings = crate::settings::Settings::default();
+        let orchestrator = Orchestrator::new(&settings, memory).await.unwrap();
+        orchestrator.register_agent("echo".to_string(), Arc::new(EchoAgent::new())).await.unwrap();
+        orchestrator
+            .register_agent("down".to_string(), Arc::new(ErroringAgent { invalid_input: false }))
+            .await
+            .unwrap();
+        orchestrator
+            .register_agent("strict".to_string(), Arc::new(ErroringAgent { invalid_input: true }))
+            .await
+            .unwrap();
+
+        // Retryable failures (and unknown agents) fall through to the next agent
+        let output = orchestrator
+            .call_with_fallback(&Caller::default(), "down", &["missing", "echo"], serde_json::json!("hi"))
+            .await
+            .unwrap();
+        assert_eq!(output, Value::String("Echo: \"hi\"".to_string()));
+        let metrics = orchestrator.monitoring().get_agent_metrics("echo").await.unwrap();
+        assert_eq!(metrics.fallback_requests_served, 1);
+
+        // Invalid input is not retried
+        let err = orchestrator
+            .call_with_fallback(&Caller::default(), "strict", &["echo"], serde_json::json!("hi"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "bad input");
+
+        // The last error is returned when every agent fails
+        let err = orchestrator
+            .call_with_fallback(&Caller::default(), "down", &["missing"], serde_json::json!("hi"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Unknown agent 'missing'"));
+    }
+
+    /// Pure agent counting how often it actually runs
+    struct CountingAgent {
+        calls: std::sync::atomic::AtomicUsize,
+        cacheable: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for CountingAgent {
+        fn name(&self) -> &str { "counting" }
+        fn agent_type(&self) -> &str { "test" }
+        fn capabilities(&self) -> Vec<String> { vec![] }
+        async fn handle(&self, input: Value, _memory: Arc<Memory>) -> Result<String> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(input.to_string())
+        }
+        async fn health_check(&self) -> Result<crate::agent::AgentHealth> {
+            Ok(crate::agent::AgentHealth::default())
+        }
+        fn cacheable(&self) -> bool { self.cacheable }
+        async fn reload_model(&self, path: &std::path::Path) -> Result<crate::agent::ModelReloadReport> {
+            Ok(crate::agent::ModelReloadReport {
+                previous_path: std::path::PathBuf::new(),
+                path: path.to_path_buf(),
+                load_time_ms: 0,
+                draining_requests: 0,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_orchestrator_result_cache() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(
+            echo_agent.clone(),
+            echo_agent.clone(),
+            cache,
+        ));
+
+        let mut settings = crate::settings::Settings::default();
+        settings.orchestrator.enable_result_cache = true;
+        settings.orchestrator.enable_trace_log = true;
+        let orchestrator = Orchestrator::new(&settings, memory).await.unwrap();
+        let pure = Arc::new(CountingAgent { calls: Default::default(), cacheable: true });
+        let impure = Arc::new(CountingAgent { calls: Default::default(), cacheable: false });
+        orchestrator.register_agent("pure".to_string(), pure.clone()).await.unwrap();
+        orchestrator.register_agent("impure".to_string(), impure.clone()).await.unwrap();
+
+        for input in ["a", "a", "b"] {
+            for name in ["pure", "impure"] {
+                let output = orchestrator.call_with_fallback(&Caller::default(), name, &[], serde_json::json!(input)).await.unwrap();
+                assert_eq!(output, Value::String(format!("\"{}\"", input)));
+            }
+        }
+
+        assert_eq!(pure.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert_eq!(impure.calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+        let metrics = orchestrator.monitoring().get_agent_metrics("pure").await.unwrap();
+        assert_eq!((metrics.result_cache_hits, metrics.result_cache_misses), (1, 2));
+        let query = crate::trace::TraceQuery { agent: Some("pure".to_string()), limit: None };
+        let cached: Vec<bool> = orchestrator.trace_log().query(&query).await.iter().map(|e| e.cached).collect();
+        assert_eq!(cached.iter().filter(|cached| **cached).count(), 1);
+        assert_eq!(cached.len(), 3);
+
+        // A replacement registered under the same name starts with no cached results
+        orchestrator.remove_agent("pure").await.unwrap();
+        let replacement = Arc::new(CountingAgent { calls: Default::default(), cacheable: true });
+        orchestrator.register_agent("pure".to_string(), replacement.clone()).await.unwrap();
+        orchestrator.call_with_fallback(&Caller::default(), "pure", &[], serde_json::json!("a")).await.unwrap();
+        assert_eq!(replacement.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // So does the same agent once its model is reloaded
+        orchestrator.reload_agent_model("pure", std::path::Path::new("models/next.gguf")).await.unwrap();
+        orchestrator.call_with_fallback(&Caller::default(), "pure", &[], serde_json::json!("a")).await.unwrap();
+        assert_eq!(replacement.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        // Config reloads only rebuild plugin agents; these came from the API
+        assert!(orchestrator.unregister_plugin_agents().await.is_empty());
+        assert!(orchestrator.can_dispatch("impure").await);
+    }
+
+    #[tokio::test]
+    async fn test_shadow_agents_are_compared_off_the_response_path() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(
+            echo_agent.clone(),
+            echo_agent.clone(),
+            cache,
+        ));
+
+        let mut settings = crate::settings::Settings::default();
+        settings.orchestrator.shadow_agents = vec![
+            crate::settings::ShadowAgentConfig { primary: "primary".into(), shadow: "twin".into(), sample_rate: 0.5 },
+            crate::settings::ShadowAgentConfig { primary: "other".into(), shadow: "echo".into(), sample_rate: 1.0 },
+        ];
+        let orchestrator = Orchestrator::new(&settings, memory).await.unwrap();
+        let twin = Arc::new(CountingAgent { calls: Default::default(), cacheable: false });
+        orchestrator.register_agent("primary".to_string(), Arc::new(CountingAgent { calls: Default::default(), cacheable: false })).await.unwrap();
+        orchestrator.register_agent("twin".to_string(), twin.clone()).await.unwrap();
+        orchestrator.register_agent("other".to_string(), Arc::new(CountingAgent { calls: Default::default(), cacheable: false })).await.unwrap();
+        orchestrator.register_agent("echo".to_string(), echo_agent.clone()).await.unwrap();
+
+        for name in ["primary", "primary", "primary", "primary", "other"] {
+            let (tx, mut rx) = mpsc::channel(1);
+            orchestrator.dispatch((name.to_string(), serde_json::json!("x"), tx)).await.unwrap();
+            // Callers always get the primary's output
+            assert_eq!(rx.recv().await.unwrap().unwrap(), Value::String("\"x\"".to_string()));
+        }
+
+        let monitoring = orchestrator.monitoring();
+        for _ in 0..100 {
+            let twin_done = monitoring.get_agent_metrics("twin").await.is_some_and(|m| m.shadow_matches == 2);
+            let echo_done = monitoring.get_agent_metrics("echo").await.is_some_and(|m| m.shadow_mismatches == 1);
+            if twin_done && echo_done {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(twin.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert_eq!(monitoring.get_agent_metrics("twin").await.unwrap().shadow_matches, 2);
+        assert_eq!(monitoring.get_agent_metrics("echo").await.unwrap().shadow_mismatches, 1);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_input_rejected_before_dispatch() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(
+            echo_agent.clone(),
+            echo_agent.clone(),
+            cache,
+        ));
+
+        let orchestrator = Orchestrator::new(&crate::settings::Settings::default(), memory).await.unwrap();
+        let agent = Arc::new(CountingAgent { calls: Default::default(), cacheable: false });
+        orchestrator.register_agent("counting".to_string(), agent.clone()).await.unwrap();
+
+        let hostile = (0..1_000).fold(serde_json::json!(0), |inner, _| serde_json::json!([inner]));
+        let (tx, _rx) = mpsc::channel(1);
+        let err = orchestrator.dispatch(("counting".to_string(), hostile.clone(), tx)).await.unwrap_err();
+        assert!(err.is::<crate::input_guard::InputLimitError>());
+        // Fallback chains are guarded as well
+        let err = orchestrator.call_with_fallback(&Caller::default(), "counting", &["counting"], hostile).await.unwrap_err();
+        assert!(err.is::<crate::input_guard::InputLimitError>());
+        assert_eq!(agent.calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_orchestrator_dispatch_cancelled() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(
+            echo_agent.clone(),
+            echo_agent.clone(),
+            cache,
+        ));
+
+        let settings = crate::settings::Settings::default();
+        let orchestrator = Orchestrator::new(&settings, memory).await.unwrap();
+        orchestrator.register_agent("echo".to_string(), Arc::new(EchoAgent::new())).await.unwrap();
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        let (tx, mut rx) = mpsc::channel(1);
+        orchestrator
+            .dispatch_with_cancel(("echo".to_string(), serde_json::json!("hi"), tx), cancel)
+            .await
+            .unwrap();
+
+        let err = rx.recv().await.unwrap().unwrap_err();
+        assert!(matches!(err, OrchestratorError::Cancelled(_)));
+        assert!(err.source_error().is::<crate::agent::CancelledError>());
+    }
+
+    #[tokio::test]
+    async fn test_orchestrator_dispatch_stream() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(
+            echo_agent.clone(),
+            echo_agent.clone(),
+            cache,
+        ));
+
+        let settings = crate::settings::Settings::default();
+        let orchestrator = Orchestrator::new(&settings, memory).await.unwrap();
+        orchestrator.register_agent("echo".to_string(), Arc::new(EchoAgent::new())).await.unwrap();
+
+        let (tx, mut rx) = mpsc::channel(4);
+        orchestrator
+            .dispatch_stream("echo", serde_json::json!("hi"), tx, CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(rx.recv().await.unwrap(), "hi");
+        assert!(rx.recv().await.is_none());
+
+        let (tx, _rx) = mpsc::channel(1);
+        assert!(orchestrator
+            .dispatch_stream("missing", serde_json::json!("hi"), tx, CancellationToken::new())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_stream_queues_at_its_priority() {
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(echo_agent.clone(), echo_agent.clone(), Arc::new(InMemoryEmbeddingCache::new())));
+        let orchestrator = Arc::new(Orchestrator::new(&crate::settings::Settings::default(), memory).await.unwrap());
+        orchestrator.register_agent("echo".to_string(), echo_agent).await.unwrap();
+
+        // No free slots, so the stream waits in the scheduler queue
+        orchestrator.set_max_concurrent_tasks(0);
+        let (tx, mut rx) = mpsc::channel(4);
+        let stream = tokio::spawn({
+            let orchestrator = orchestrator.clone();
+            async move {
+                orchestrator
+                    .dispatch_stream_as(&Caller::default(), "echo", serde_json::json!("hi"), tx, TaskPriority::High, CancellationToken::new())
+                    .await
+            }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert_eq!(orchestrator.task_queue_depths()[&TaskPriority::High], 1);
+
+        orchestrator.set_max_concurrent_tasks(1);
+        stream.await.unwrap().unwrap();
+        assert_eq!(rx.recv().await.unwrap(), "hi");
+    }
+
+    #[tokio::test]
+    async fn test_orchestrator_trace_and_replay() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(
+            echo_agent.clone(),
+            echo_agent.clone(),
+            cache,
+        ));
+
+        let mut settings = crate::settings::Settings::default();
+        settings.orchestrator.enable_trace_log = true;
+        let orchestrator = Orchestrator::new(&settings, memory).await.unwrap();
+        orchestrator.register_agent("echo".to_string(), Arc::new(EchoAgent::new())).await.unwrap();
+
+        let (tx, mut rx) = mpsc::channel(1);
+        orchestrator.dispatch(("echo".to_string(), serde_json::json!("hi"), tx)).await.unwrap();
+        let original = rx.recv().await.unwrap().unwrap();
+
+        let traces = orchestrator.trace_log().query(&crate::trace::TraceQuery::default()).await;
+        assert_eq!(traces.len(), 1);
+        assert_eq!(traces[0].input, serde_json::json!("hi"));
+        assert_eq!(traces[0].output.as_deref(), original.as_str());
+
+        let replayed = orchestrator.replay_trace(&Caller::default(), traces[0].id).await.unwrap();
+        assert_eq!(replayed, original);
+        assert!(orchestrator.replay_trace(&Caller::default(), Uuid::new_v4()).await.is_err());
+    }
+
+    struct UppercaseMiddleware;
+
+    #[async_trait::async_trait]
+    impl AgentMiddleware for UppercaseMiddleware {
+        async fn after(&self, _agent_name: &str, output: String) -> Result<String> {
+            Ok(output.to_uppercase())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_orchestrator_applies_middleware() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(
+            echo_agent.clone(),
+            echo_agent.clone(),
+            cache,
+        ));
+
+        let settings = crate::settings::Settings::default();
+        let orchestrator = Orchestrator::new(&settings, memory).await.unwrap();
+        orchestrator.register_agent("echo".to_string(), Arc::new(EchoAgent::new())).await.unwrap();
+        orchestrator.add_middleware(Arc::new(UppercaseMiddleware)).await;
+
+        let (tx, mut rx) = mpsc::channel(1);
+        orchestrator.dispatch(("echo".to_string(), serde_json::json!("hi"), tx)).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap().unwrap(), Value::String("HI".to_string()));
+
+        let (tx, mut rx) = mpsc::channel(4);
+        orchestrator
+            .dispatch_stream("echo", serde_json::json!("hi"), tx, CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(rx.recv().await.unwrap(), "HI");
+    }
+
     #[tokio::test]
     async fn test_orchestrator_dispatch_timeout() {
         let cache = Arc::new(InMemoryEmbeddingCache::new());
@@ -340,6 +2098,510 @@ mod tests {
 
         orchestrator.dispatch(task).await.unwrap();
         let result = rx.recv().await.unwrap();
-        assert!(result.is_err());
+        assert!(matches!(result, Err(OrchestratorError::UnknownAgent(_))));
+    }
+
+    #[tokio::test]
+    async fn test_model_reload_requires_model_backed_agent() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(
+            echo_agent.clone(),
+            echo_agent.clone(),
+            cache,
+        ));
+
+        let orchestrator = Orchestrator::new(&crate::settings::Settings::default(), memory).await.unwrap();
+        orchestrator.register_agent("echo".to_string(), echo_agent).await.unwrap();
+
+        let path = std::path::Path::new("models/other.gguf");
+        let err = orchestrator.reload_agent_model("echo", path).await.unwrap_err();
+        assert!(err.is::<crate::agent::InvalidInputError>());
+        let err = orchestrator.reload_agent_model("missing", path).await.unwrap_err();
+        assert!(err.is::<crate::agent::UnknownAgentError>());
+        // A failed reload leaves the agent registered
+        assert!(orchestrator.has_agent("echo").await);
+    }
+
+    struct NestedFailureAgent;
+
+    #[async_trait::async_trait]
+    impl Agent for NestedFailureAgent {
+        fn name(&self) -> &str { "nested_failure" }
+        fn agent_type(&self) -> &str { "test" }
+        fn capabilities(&self) -> Vec<String> { vec![] }
+        async fn handle(&self, _input: Value, _memory: Arc<Memory>) -> Result<String> {
+            use anyhow::Context;
+            let io = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "index is read-only");
+            Err(io).context("writing index").context("building embeddings")
+        }
+        async fn health_check(&self) -> Result<crate::agent::AgentHealth> {
+            Ok(crate::agent::AgentHealth::default())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_error_context_survives_channel() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(
+            echo_agent.clone(),
+            echo_agent.clone(),
+            cache,
+        ));
+
+        let orchestrator = Orchestrator::new(&crate::settings::Settings::default(), memory).await.unwrap();
+        orchestrator.register_agent("nested".to_string(), Arc::new(NestedFailureAgent)).await.unwrap();
+
+        let (tx, mut rx) = mpsc::channel(1);
+        orchestrator.dispatch(("nested".to_string(), serde_json::json!("hi"), tx)).await.unwrap();
+        let err = rx.recv().await.unwrap().unwrap_err();
+
+        assert!(matches!(err, OrchestratorError::Agent(_)));
+        assert_eq!(format!("{:#}", err), "building embeddings: writing index: index is read-only");
+        let io = err.source_error().root_cause().downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(io.kind(), std::io::ErrorKind::PermissionDenied);
+    }
+
+    struct HangingAgent;
+
+    #[async_trait::async_trait]
+    impl Agent for HangingAgent {
+        fn name(&self) -> &str { "hanging" }
+        fn agent_type(&self) -> &str { "test" }
+        fn capabilities(&self) -> Vec<String> { vec![] }
+        async fn handle(&self, _input: Value, _memory: Arc<Memory>) -> Result<String> {
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            Ok("too late".to_string())
+        }
+        async fn health_check(&self) -> Result<crate::agent::AgentHealth> {
+            Ok(crate::agent::AgentHealth::default())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unregister_all_cancels_in_flight_calls() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(echo_agent.clone(), echo_agent.clone(), cache));
+
+        let orchestrator = Arc::new(Orchestrator::new(&crate::settings::Settings::default(), memory).await.unwrap());
+        orchestrator.register_agent("hanging".to_string(), Arc::new(HangingAgent)).await.unwrap();
+        orchestrator.register_agent("embedder".to_string(), echo_agent.clone()).await.unwrap();
+        orchestrator.register_agent("echo".to_string(), Arc::new(EchoAgent::new())).await.unwrap();
+
+        let (tx, mut rx) = mpsc::channel(1);
+        let running = orchestrator.clone();
+        tokio::spawn(async move {
+            running.dispatch(("hanging".to_string(), serde_json::json!("hi"), tx)).await.unwrap();
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        let removed = orchestrator.unregister_all(&["embedder"]).await;
+        assert_eq!(removed, vec!["echo".to_string(), "hanging".to_string()]);
+        assert!(orchestrator.has_agent("embedder").await);
+        assert_eq!(orchestrator.list_agents().await.len(), 1);
+
+        let err = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+            .await
+            .expect("in-flight call should be cancelled")
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(err, OrchestratorError::Cancelled(_)));
+
+        // Re-registering under a removed name works normally
+        orchestrator.register_agent("echo".to_string(), Arc::new(EchoAgent::new())).await.unwrap();
+        let (tx, mut rx) = mpsc::channel(1);
+        orchestrator.dispatch(("echo".to_string(), serde_json::json!("hi"), tx)).await.unwrap();
+        assert!(rx.recv().await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_agent_maintenance_refuses_calls() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(echo_agent.clone(), echo_agent.clone(), cache));
+
+        let orchestrator = Orchestrator::new(&crate::settings::Settings::default(), memory).await.unwrap();
+        let counting = Arc::new(CountingAgent { calls: Default::default(), cacheable: false });
+        orchestrator.register_agent("counting".to_string(), counting.clone()).await.unwrap();
+        orchestrator.register_agent("echo".to_string(), Arc::new(EchoAgent::new())).await.unwrap();
+
+        assert!(orchestrator.set_agent_maintenance("counting", true).await.unwrap());
+        assert!(!orchestrator.set_agent_maintenance("counting", true).await.unwrap());
+        assert!(orchestrator.is_in_maintenance("counting"));
+        assert!(orchestrator.has_agent("counting").await);
+
+        let (tx, mut rx) = mpsc::channel(1);
+        orchestrator.dispatch(("counting".to_string(), serde_json::json!("hi"), tx)).await.unwrap();
+        let err = rx.recv().await.unwrap().unwrap_err();
+        assert!(matches!(err, OrchestratorError::Maintenance(_)));
+        assert_eq!(counting.calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+        // Fallbacks skip an agent in maintenance
+        let output = orchestrator.call_with_fallback(&Caller::default(), "counting", &["echo"], serde_json::json!("hi")).await.unwrap();
+        assert!(output.as_str().is_some());
+
+        assert!(orchestrator.set_agent_maintenance("counting", false).await.unwrap());
+        let (tx, mut rx) = mpsc::channel(1);
+        orchestrator.dispatch(("counting".to_string(), serde_json::json!("hi"), tx)).await.unwrap();
+        assert!(rx.recv().await.unwrap().is_ok());
+        assert!(orchestrator.set_agent_maintenance("missing", true).await.is_err());
+    }
+
+    struct SlowPureAgent {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for SlowPureAgent {
+        fn name(&self) -> &str { "slow_pure" }
+        fn agent_type(&self) -> &str { "test" }
+        fn capabilities(&self) -> Vec<String> { vec![] }
+        async fn handle(&self, input: Value, _memory: Arc<Memory>) -> Result<String> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            Ok(input.to_string())
+        }
+        async fn health_check(&self) -> Result<crate::agent::AgentHealth> {
+            Ok(crate::agent::AgentHealth::default())
+        }
+        fn cacheable(&self) -> bool { true }
+    }
+
+    #[tokio::test]
+    async fn test_identical_concurrent_calls_are_coalesced() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(echo_agent.clone(), echo_agent.clone(), cache));
+
+        let orchestrator = Orchestrator::new(&crate::settings::Settings::default(), memory).await.unwrap();
+        let agent = Arc::new(SlowPureAgent { calls: Default::default() });
+        orchestrator.register_agent("slow_pure".to_string(), agent.clone()).await.unwrap();
+
+        let calls = (0..8).map(|_| async {
+            let (tx, mut rx) = mpsc::channel(1);
+            orchestrator.dispatch(("slow_pure".to_string(), serde_json::json!("same"), tx)).await.unwrap();
+            rx.recv().await.unwrap().unwrap()
+        });
+        let outputs = futures::future::join_all(calls).await;
+
+        assert!(outputs.iter().all(|output| output == &Value::String("\"same\"".to_string())));
+        assert_eq!(agent.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(orchestrator.in_flight.is_empty());
+
+        // Once finished, the next identical call runs the agent again
+        let (tx, mut rx) = mpsc::channel(1);
+        orchestrator.dispatch(("slow_pure".to_string(), serde_json::json!("same"), tx)).await.unwrap();
+        rx.recv().await.unwrap().unwrap();
+        assert_eq!(agent.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_agents_route_to_default_agent() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(echo_agent.clone(), echo_agent.clone(), cache));
+
+        async fn call(orchestrator: &Orchestrator, input: Value) -> Result<Value, OrchestratorError> {
+            let (tx, mut rx) = mpsc::channel(1);
+            orchestrator.dispatch(("summarise".to_string(), input, tx)).await.unwrap();
+            rx.recv().await.unwrap()
+        }
+
+        // Without a default a misspelled name still fails
+        let orchestrator = Orchestrator::new(&crate::settings::Settings::default(), memory.clone()).await.unwrap();
+        orchestrator.register_agent("counting".to_string(), Arc::new(CountingAgent { calls: Default::default(), cacheable: false })).await.unwrap();
+        let err = call(&orchestrator, serde_json::json!({"text": "hi"})).await.unwrap_err();
+        assert!(matches!(err, OrchestratorError::UnknownAgent(_)));
+        assert!(!orchestrator.can_dispatch("summarise").await);
+
+        let mut settings = crate::settings::Settings::default();
+        settings.orchestrator.default_agent = Some("counting".to_string());
+        let orchestrator = Orchestrator::new(&settings, memory).await.unwrap();
+        let counting = Arc::new(CountingAgent { calls: Default::default(), cacheable: false });
+        orchestrator.register_agent("counting".to_string(), counting.clone()).await.unwrap();
+        assert!(orchestrator.can_dispatch("summarise").await);
+
+        let output = call(&orchestrator, serde_json::json!({"text": "hi"})).await.unwrap();
+        let seen: Value = serde_json::from_str(output.as_str().unwrap()).unwrap();
+        assert_eq!(seen, serde_json::json!({"text": "hi", "requested_agent": "summarise"}));
+
+        let output = call(&orchestrator, serde_json::json!("hi")).await.unwrap();
+        let seen: Value = serde_json::from_str(output.as_str().unwrap()).unwrap();
+        assert_eq!(seen, serde_json::json!({"requested_agent": "summarise", "input": "hi"}));
+        assert_eq!(counting.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    /// Estimates the completion allowance in the input and charges one
+    /// token per output byte
+    struct PricedAgent {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for PricedAgent {
+        fn name(&self) -> &str { "priced" }
+        fn agent_type(&self) -> &str { "test" }
+        fn capabilities(&self) -> Vec<String> { vec![] }
+        async fn handle(&self, _input: Value, _memory: Arc<Memory>) -> Result<String> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok("ok".to_string())
+        }
+        async fn health_check(&self) -> Result<crate::agent::AgentHealth> {
+            Ok(crate::agent::AgentHealth::default())
+        }
+        fn cost_estimate(&self, input: &Value) -> Option<Cost> {
+            input.get("max_tokens").and_then(Value::as_u64).map(Cost::from_tokens)
+        }
+        fn actual_cost(&self, _input: &Value, output: &str) -> Option<Cost> {
+            Some(Cost::from_tokens(output.len() as u64))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_enforces_user_budgets() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(echo_agent.clone(), echo_agent.clone(), cache));
+
+        let mut settings = crate::settings::Settings::default();
+        settings.orchestrator.user_budgets = HashMap::from([("alice".to_string(), 10.0)]);
+        let orchestrator = Orchestrator::new(&settings, memory).await.unwrap();
+        let agent = Arc::new(PricedAgent { calls: Default::default() });
+        orchestrator.register_agent("priced".to_string(), agent.clone()).await.unwrap();
+
+        async fn dispatch(orchestrator: &Orchestrator, user: &str, max_tokens: u64) -> Result<Value, OrchestratorError> {
+            let (tx, mut rx) = mpsc::channel(1);
+            let task = ("priced".to_string(), serde_json::json!({"max_tokens": max_tokens}), tx);
+            let caller = Caller { user: Some(user.to_string()), tenant: None };
+            orchestrator.dispatch_as(&caller, task, TaskPriority::Normal, CancellationToken::new()).await.unwrap();
+            rx.recv().await.unwrap()
+        }
+
+        // Admitted on its estimate of 8, then charged the 2 it actually cost
+        dispatch(&orchestrator, "alice", 8).await.unwrap();
+        assert_eq!(orchestrator.quotas.remaining("alice"), Some(8.0));
+
+        let err = dispatch(&orchestrator, "alice", 9).await.unwrap_err();
+        assert!(matches!(err, OrchestratorError::QuotaExceeded(_)));
+        assert_eq!(agent.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(orchestrator.quotas.remaining("alice"), Some(8.0));
+
+        // Users without a budget are not metered
+        dispatch(&orchestrator, "bob", 1_000).await.unwrap();
+        assert_eq!(agent.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        // Calls that bypass the task queue are charged all the same
+        let alice = Caller { user: Some("alice".to_string()), tenant: None };
+        orchestrator.call_with_fallback(&alice, "priced", &[], serde_json::json!({"max_tokens": 4})).await.unwrap();
+        assert_eq!(orchestrator.quotas.remaining("alice"), Some(6.0));
+        let err = orchestrator
+            .call_with_fallback(&alice, "priced", &[], serde_json::json!({"max_tokens": 7}))
+            .await
+            .unwrap_err();
+        assert!(err.is::<crate::quota::QuotaExceededError>());
+        assert_eq!(agent.calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    /// Returns its input as output and promises an object with a "label"
+    struct LabelAgent;
+
+    #[async_trait::async_trait]
+    impl Agent for LabelAgent {
+        fn name(&self) -> &str { "label" }
+        fn agent_type(&self) -> &str { "test" }
+        fn capabilities(&self) -> Vec<String> { vec![] }
+        async fn handle(&self, input: Value, _memory: Arc<Memory>) -> Result<String> {
+            Ok(input.to_string())
+        }
+        async fn health_check(&self) -> Result<crate::agent::AgentHealth> {
+            Ok(crate::agent::AgentHealth::default())
+        }
+        fn output_schema(&self) -> Option<Value> {
+            Some(serde_json::json!({
+                "type": "object",
+                "properties": { "label": { "type": "string" } },
+                "required": ["label"]
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_outputs_are_checked_against_the_agent_schema() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(echo_agent.clone(), echo_agent.clone(), cache));
+
+        let mut settings = crate::settings::Settings::default();
+        settings.orchestrator.agent_output_validation = HashMap::from([("strict".to_string(), "fail".to_string())]);
+        let orchestrator = Orchestrator::new(&settings, memory).await.unwrap();
+        orchestrator.register_agent("lenient".to_string(), Arc::new(LabelAgent)).await.unwrap();
+        orchestrator.register_agent("strict".to_string(), Arc::new(LabelAgent)).await.unwrap();
+
+        async fn call(orchestrator: &Orchestrator, agent: &str, input: Value) -> Result<Value, OrchestratorError> {
+            let (tx, mut rx) = mpsc::channel(1);
+            orchestrator.dispatch((agent.to_string(), input, tx)).await.unwrap();
+            rx.recv().await.unwrap()
+        }
+
+        let conforming = serde_json::json!({"label": "spam"});
+        assert!(call(&orchestrator, "strict", conforming.clone()).await.is_ok());
+        assert!(call(&orchestrator, "lenient", conforming).await.is_ok());
+
+        // The default mode passes a mismatch through with a warning
+        let malformed = serde_json::json!({"label": 3});
+        let output = call(&orchestrator, "lenient", malformed.clone()).await.unwrap();
+        assert_eq!(output, Value::String(malformed.to_string()));
+
+        let before = crate::metrics::platform().snapshot().output_schema_errors_total;
+        let err = call(&orchestrator, "strict", malformed).await.unwrap_err();
+        let schema_error = err.source_error().chain()
+            .find_map(|e| e.downcast_ref::<crate::output_schema::OutputSchemaError>())
+            .unwrap();
+        assert_eq!(schema_error.violations, vec!["$.label: expected string, got number"]);
+        assert!(crate::metrics::platform().snapshot().output_schema_errors_total > before);
+
+        // Streamed output is checked once assembled
+        let (tx, _rx) = mpsc::channel(4);
+        let err = orchestrator
+            .dispatch_stream("strict", serde_json::json!({"label": 3}), tx, CancellationToken::new())
+            .await
+            .unwrap_err();
+        assert!(err.chain().any(|e| e.is::<crate::output_schema::OutputSchemaError>()));
+        let (tx, _rx) = mpsc::channel(4);
+        orchestrator
+            .dispatch_stream("strict", serde_json::json!({"label": "spam"}), tx, CancellationToken::new())
+            .await
+            .unwrap();
+    }
+
+    /// Appends its lifecycle events to a shared log; fails `on_register` if asked to
+    struct HookAgent {
+        id: &'static str,
+        events: Arc<parking_lot::Mutex<Vec<String>>>,
+        fail_register: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for HookAgent {
+        fn name(&self) -> &str { self.id }
+        fn agent_type(&self) -> &str { "test" }
+        fn capabilities(&self) -> Vec<String> { vec![] }
+        async fn handle(&self, _input: Value, _memory: Arc<Memory>) -> Result<String> {
+            self.events.lock().push(format!("{}:handle", self.id));
+            Ok(self.id.to_string())
+        }
+        async fn health_check(&self) -> Result<crate::agent::AgentHealth> {
+            Ok(crate::agent::AgentHealth::default())
+        }
+        async fn on_register(&self, ctx: &AgentContext) -> Result<()> {
+            self.events.lock().push(format!("{}:register as {}", self.id, ctx.name));
+            if self.fail_register {
+                return Err(anyhow::anyhow!("connection refused"));
+            }
+            Ok(())
+        }
+        async fn on_unregister(&self) -> Result<()> {
+            self.events.lock().push(format!("{}:unregister", self.id));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_hooks_run_in_order() {
+        let cache = Arc::new(InMemoryEmbeddingCache::new());
+        let echo_agent = Arc::new(EchoAgent::new());
+        let memory = Arc::new(Memory::new(echo_agent.clone(), echo_agent.clone(), cache));
+        let orchestrator = Orchestrator::new(&crate::settings::Settings::default(), memory).await.unwrap();
+
+        let events = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let agent = |id, fail_register| Arc::new(HookAgent { id, events: events.clone(), fail_register });
+
+        orchestrator.register_agent("worker".to_string(), agent("v1", false)).await.unwrap();
+        let (tx, mut rx) = mpsc::channel(1);
+        orchestrator.dispatch(("worker".to_string(), serde_json::json!({"text": "hi"}), tx)).await.unwrap();
+        rx.recv().await.unwrap().unwrap();
+
+        // A failing hook rejects the newcomer and keeps the current agent
+        let err = orchestrator.replace_agent("worker".to_string(), agent("broken", true)).await.unwrap_err();
+        let err = err.downcast_ref::<AgentRegistrationError>().unwrap();
+        assert_eq!(err.agent, "worker");
+        assert_eq!(err.to_string(), "Agent 'worker' failed to register: connection refused");
+
+        // A taken name is refused before any hook runs
+        let err = orchestrator.register_agent("worker".to_string(), agent("dup", false)).await.unwrap_err();
+        assert!(err.downcast_ref::<DuplicateAgentError>().is_some());
+
+        let v2 = agent("v2", false);
+        orchestrator.replace_agent("worker".to_string(), v2.clone()).await.unwrap();
+        // Registering the live instance again must not tear it down
+        orchestrator.replace_agent("worker".to_string(), v2).await.unwrap();
+        orchestrator.register_agent("helper".to_string(), agent("v3", false)).await.unwrap();
+        orchestrator.remove_agent("worker").await.unwrap();
+        assert_eq!(orchestrator.unregister_all(&[]).await, vec!["helper"]);
+
+        assert_eq!(*events.lock(), vec![
+            "v1:register as worker",
+            "v1:handle",
+            "broken:register as worker",
+            "v2:register as worker",
+            "v1:unregister",
+            "v2:register as worker",
+            "v3:register as helper",
+            "v2:unregister",
+            "v3:unregister",
+        ]);
+    }
+
+    /// Answers with the memory context it would put in front of a prompt
+    struct RagAgent;
+
+    #[async_trait::async_trait]
+    impl Agent for RagAgent {
+        fn name(&self) -> &str { "rag" }
+        fn agent_type(&self) -> &str { "test" }
+        fn capabilities(&self) -> Vec<String> { vec![] }
+        async fn handle(&self, input: Value, memory: Arc<Memory>) -> Result<String> {
+            let mut context = crate::agent::caller_memory_context(&memory, input.as_str().unwrap_or_default(), 10).await;
+            context.sort();
+            Ok(context.join("|"))
+        }
+        async fn health_check(&self) -> Result<crate::agent::AgentHealth> {
+            Ok(crate::agent::AgentHealth::default())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_context_is_scoped_to_caller_tenant() {
+        let memory = Arc::new(
+            Memory::new(
+                Arc::new(crate::agent::HashEmbeddingAgent::new(64)),
+                Arc::new(crate::agent::LengthRerankAgent::new()),
+                Arc::new(InMemoryEmbeddingCache::new()),
+            )
+            .with_similarity_threshold(-1.0),
+        );
+        memory.add_tenant_memory("acme launch plan", None, Some("acme"), None).await.unwrap();
+        memory.add_tenant_memory("globex launch plan", None, Some("globex"), None).await.unwrap();
+        let orchestrator = Orchestrator::new(&crate::settings::Settings::default(), memory).await.unwrap();
+        orchestrator.register_agent("rag".to_string(), Arc::new(RagAgent)).await.unwrap();
+
+        let ask = |tenant: &str| {
+            let caller = Caller { user: None, tenant: Some(tenant.to_string()) };
+            let orchestrator = &orchestrator;
+            async move {
+                let (tx, mut rx) = mpsc::channel(1);
+                let task = ("rag".to_string(), serde_json::json!("launch plan"), tx);
+                orchestrator.dispatch_as(&caller, task, TaskPriority::Normal, CancellationToken::new()).await.unwrap();
+                rx.recv().await.unwrap().unwrap()
+            }
+        };
+        assert_eq!(ask("acme").await, "acme launch plan");
+        assert_eq!(ask("globex").await, "globex launch plan");
+
+        let (tx, mut rx) = mpsc::channel(1);
+        orchestrator.dispatch(("rag".to_string(), serde_json::json!("launch plan"), tx)).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap().unwrap(), "acme launch plan|globex launch plan");
     }
 }
@@ -0,0 +1,321 @@
+//! Checking agent outputs against the JSON Schema the agent declares.
+//!
+//! Agents that promise structured output (typically LLMs prompted to answer
+//! in JSON) publish an [`Agent::output_schema`](crate::agent::Agent::output_schema).
+//! The orchestrator checks each completed output against it before the
+//! result is cached or returned, so malformed output never reaches clients
+//! unnoticed. Per agent, a mismatch is either logged and passed through
+//! ("warn") or fails the call ("fail").
+//!
+//! Only the commonly used core of JSON Schema is supported: `type`, `enum`,
+//! `const`, `properties`, `required`, `additionalProperties: false`,
+//! `items`, `minItems`/`maxItems`, `minLength`/`maxLength` and
+//! `minimum`/`maximum`. Other keywords are ignored. Streamed outputs are
+//! checked once the stream ends, after their chunks have been sent.
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use tracing::warn;
+
+use crate::settings::Settings;
+
+/// Violations reported per output; the rest are summarized by count
+const MAX_REPORTED_VIOLATIONS: usize = 10;
+
+/// What to do with an output that does not match the agent's schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputValidationMode {
+    /// Don't check outputs
+    Off,
+    /// Log the mismatch and return the output anyway
+    Warn,
+    /// Fail the call with [`OutputSchemaError`]
+    Fail,
+}
+
+impl OutputValidationMode {
+    pub fn parse(mode: &str) -> Result<Self> {
+        match mode {
+            "off" => Ok(Self::Off),
+            "warn" => Ok(Self::Warn),
+            "fail" => Ok(Self::Fail),
+            other => Err(anyhow!("Unknown output validation mode '{}'", other)),
+        }
+    }
+}
+
+/// Returned when an agent's output does not match its declared schema
+#[derive(Debug)]
+pub struct OutputSchemaError {
+    pub agent: String,
+    pub violations: Vec<String>,
+}
+
+impl fmt::Display for OutputSchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Output of agent '{}' does not match its schema: {}", self.agent, self.violations.join("; "))
+    }
+}
+
+impl std::error::Error for OutputSchemaError {}
+
+/// Output validation mode with per-agent overrides
+#[derive(Debug, Clone)]
+pub struct OutputValidator {
+    default: OutputValidationMode,
+    agents: HashMap<String, OutputValidationMode>,
+}
+
+impl OutputValidator {
+    pub fn new(default: OutputValidationMode) -> Self {
+        Self { default, agents: HashMap::new() }
+    }
+
+    /// Modes come from `orchestrator.output_validation` and
+    /// `orchestrator.agent_output_validation`; unknown modes are rejected
+    /// by settings validation and fall back to "warn" here
+    pub fn from_settings(settings: &Settings) -> Self {
+        let parse = |mode: &str| OutputValidationMode::parse(mode).unwrap_or(OutputValidationMode::Warn);
+        let config = &settings.orchestrator;
+        Self {
+            default: parse(&config.output_validation),
+            agents: config.agent_output_validation.iter()
+                .map(|(agent, mode)| (agent.clone(), parse(mode)))
+                .collect(),
+        }
+    }
+
+    pub fn with_agent_mode(mut self, agent: &str, mode: OutputValidationMode) -> Self {
+        self.agents.insert(agent.to_string(), mode);
+        self
+    }
+
+    pub fn mode_for(&self, agent: &str) -> OutputValidationMode {
+        self.agents.get(agent).copied().unwrap_or(self.default)
+    }
+
+    /// Check `output` of `agent` against `schema` according to the agent's mode
+    pub fn check(&self, agent: &str, schema: &Value, output: &str) -> Result<()> {
+        let mode = self.mode_for(agent);
+        if mode == OutputValidationMode::Off {
+            return Ok(());
+        }
+        let Err(violations) = validate_output(schema, output) else {
+            return Ok(());
+        };
+        crate::metrics::platform().record_output_schema_violation();
+        let error = OutputSchemaError { agent: agent.to_string(), violations };
+        match mode {
+            OutputValidationMode::Fail => Err(error.into()),
+            _ => {
+                warn!("{}", error);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Parse `output` as JSON and check it against `schema`, returning every
+/// violation (up to a limit) as "`path`: problem"
+pub fn validate_output(schema: &Value, output: &str) -> std::result::Result<(), Vec<String>> {
+    let value: Value = serde_json::from_str(output)
+        .map_err(|e| vec![format!("output is not valid JSON: {}", e)])?;
+    let mut violations = Vec::new();
+    check_value(schema, &value, "$", &mut violations);
+    if violations.is_empty() {
+        return Ok(());
+    }
+    if violations.len() > MAX_REPORTED_VIOLATIONS {
+        let more = violations.len() - MAX_REPORTED_VIOLATIONS;
+        violations.truncate(MAX_REPORTED_VIOLATIONS);
+        violations.push(format!("and {} more", more));
+    }
+    Err(violations)
+}
+
+fn check_value(schema: &Value, value: &Value, path: &str, violations: &mut Vec<String>) {
+    // `true`, `{}` and non-schema values accept anything; `false` accepts nothing
+    let schema = match schema {
+        Value::Object(schema) => schema,
+        Value::Bool(false) => {
+            violations.push(format!("{}: not allowed", path));
+            return;
+        }
+        _ => return,
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|name| has_type(value, name)) {
+            violations.push(format!("{}: expected {}, got {}", path, allowed.join(" or "), type_name(value)));
+            // Further keywords would only repeat the mismatch
+            return;
+        }
+    }
+    if let Some(Value::Array(options)) = schema.get("enum") {
+        if !options.contains(value) {
+            violations.push(format!("{}: {} is not one of the allowed values", path, value));
+        }
+    }
+    if let Some(constant) = schema.get("const") {
+        if constant != value {
+            violations.push(format!("{}: expected {}", path, constant));
+        }
+    }
+
+    match value {
+        Value::Object(fields) => {
+            if let Some(Value::Array(required)) = schema.get("required") {
+                for name in required.iter().filter_map(Value::as_str) {
+                    if !fields.contains_key(name) {
+                        violations.push(format!("{}: missing required property '{}'", path, name));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (name, field) in fields {
+                match properties.and_then(|properties| properties.get(name)) {
+                    Some(field_schema) => check_value(field_schema, field, &format!("{}.{}", path, name), violations),
+                    None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                        violations.push(format!("{}: unexpected property '{}'", path, name));
+                    }
+                    None => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+                if (items.len() as u64) < min {
+                    violations.push(format!("{}: expected at least {} items, got {}", path, min, items.len()));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+                if items.len() as u64 > max {
+                    violations.push(format!("{}: expected at most {} items, got {}", path, max, items.len()));
+                }
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check_value(item_schema, item, &format!("{}[{}]", path, i), violations);
+                }
+            }
+        }
+        Value::String(text) => {
+            let len = text.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if len < min {
+                    violations.push(format!("{}: shorter than {} characters", path, min));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+                if len > max {
+                    violations.push(format!("{}: longer than {} characters", path, max));
+                }
+            }
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or(f64::NAN);
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+                if number < min {
+                    violations.push(format!("{}: {} is below the minimum {}", path, number, min));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+                if number > max {
+                    violations.push(format!("{}: {} is above the maximum {}", path, number, max));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        // Integral floats such as 2.0 count as integers, as in JSON Schema
+        "integer" => value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sentiment_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "label": { "enum": ["positive", "negative", "neutral"] },
+                "score": { "type": "number", "minimum": 0, "maximum": 1 },
+                "keywords": { "type": "array", "items": { "type": "string" }, "maxItems": 3 }
+            },
+            "required": ["label", "score"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn test_conforming_output_passes() {
+        let output = r#"{"label": "positive", "score": 0.9, "keywords": ["great"]}"#;
+        assert!(validate_output(&sentiment_schema(), output).is_ok());
+    }
+
+    #[test]
+    fn test_violations_name_their_paths() {
+        let output = r#"{"label": "ecstatic", "score": 1.5, "keywords": ["a", 2], "extra": true}"#;
+        let violations = validate_output(&sentiment_schema(), output).unwrap_err();
+        assert_eq!(violations, vec![
+            "$: unexpected property 'extra'",
+            "$.keywords[1]: expected string, got number",
+            "$.label: \"ecstatic\" is not one of the allowed values",
+            "$.score: 1.5 is above the maximum 1",
+        ]);
+
+        let violations = validate_output(&sentiment_schema(), r#"{"label": "neutral"}"#).unwrap_err();
+        assert_eq!(violations, vec!["$: missing required property 'score'"]);
+
+        let violations = validate_output(&sentiment_schema(), "Sure! Here is the JSON:").unwrap_err();
+        assert!(violations[0].starts_with("output is not valid JSON"));
+    }
+
+    #[test]
+    fn test_modes_warn_or_fail() {
+        let validator = OutputValidator::new(OutputValidationMode::Warn)
+            .with_agent_mode("strict", OutputValidationMode::Fail)
+            .with_agent_mode("lenient", OutputValidationMode::Off);
+        let bad = r#"{"label": "positive"}"#;
+
+        assert!(validator.check("classifier", &sentiment_schema(), bad).is_ok());
+        assert!(validator.check("lenient", &sentiment_schema(), bad).is_ok());
+        let err = validator.check("strict", &sentiment_schema(), bad).unwrap_err();
+        let err = err.downcast_ref::<OutputSchemaError>().unwrap();
+        assert_eq!(err.agent, "strict");
+        assert_eq!(err.violations.len(), 1);
+        assert!(validator.check("strict", &sentiment_schema(), r#"{"label": "positive", "score": 1}"#).is_ok());
+    }
+}
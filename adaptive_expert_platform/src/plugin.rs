@@ -1,12 +1,13 @@
 //! Native / WASM plugin loader + hot-reload support with enhanced security.
 
-use std::{path::Path, sync::Arc};
+use std::{path::{Path, PathBuf}, sync::{Arc, Mutex, Weak}};
 use anyhow::{Context, Result, anyhow};
 use libloading::Library;
 use crate::agent::Agent;
 use sha2::{Sha256, Digest};
 use std::fs;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use serde::Serialize;
 use tracing::{info, warn, error, instrument};
 
 pub enum PluginEvent {
@@ -15,6 +16,7 @@ pub enum PluginEvent {
 }
 
 type FactoryFn = unsafe extern "C" fn() -> *mut dyn Agent;
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
 
 /// Plugin security configuration
 #[derive(Debug, Clone)]
@@ -70,6 +72,7 @@ pub struct Plugin {
     factory: FactoryFn,
     hash: String,
     path: std::path::PathBuf,
+    abi_version: Option<u32>,
 }
 
 impl Plugin {
@@ -135,6 +138,8 @@ impl Plugin {
             .with_context(|| format!("Plugin missing 'create_agent' symbol: {:?}", lib_path))?;
 
         let factory_fn = *factory;
+        // Optional; older plugins predate the version export
+        let abi_version = library.get::<AbiVersionFn>(b"plugin_abi_version").ok().map(|f| f());
         let lib = Arc::new(library);
 
         Ok(Self {
@@ -142,6 +147,7 @@ impl Plugin {
             factory: factory_fn,
             hash,
             path: lib_path.to_path_buf(),
+            abi_version,
         })
     }
 
@@ -180,6 +186,7 @@ impl Plugin {
         PluginMetadata {
             hash: self.hash.clone(),
             path: self.path.clone(),
+            abi_version: self.abi_version,
         }
     }
 
@@ -220,6 +227,265 @@ impl Plugin {
 pub struct PluginMetadata {
     pub hash: String,
     pub path: std::path::PathBuf,
+    pub abi_version: Option<u32>,
+}
+
+/// A plugin that loaded and the agent it contributed
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadedPluginInfo {
+    pub path: PathBuf,
+    pub hash: String,
+    pub abi_version: Option<u32>,
+    pub agents: Vec<String>,
+    /// Time spent loading and instantiating the library
+    pub load_time_ms: u64,
+}
+
+/// A plugin that could not be loaded or instantiated
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginFailure {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// Plugins currently loaded and those whose last load attempt failed
+#[derive(Debug, Default, Serialize)]
+pub struct PluginInventory {
+    pub loaded: Vec<LoadedPluginInfo>,
+    pub failed: Vec<PluginFailure>,
+}
+
+/// Outcome of scanning the plugin directory
+#[derive(Debug, Default, Serialize)]
+pub struct PluginLoadReport {
+    pub loaded: Vec<LoadedPluginInfo>,
+    pub failed: Vec<PluginFailure>,
+    /// Libraries already loaded by an earlier scan
+    pub skipped: Vec<PathBuf>,
+}
+
+/// Loads native plugins from a directory, keeping each library alive for as
+/// long as the agents it created
+pub struct PluginManager {
+    directory: PathBuf,
+    security_config: PluginSecurityConfig,
+    loaded: Mutex<HashMap<PathBuf, LoadedPlugin>>,
+    failed: Mutex<HashMap<PathBuf, PluginFailure>>,
+    /// Libraries replaced or dropped from `loaded`, kept mapped while agents
+    /// created from them may still be running
+    retired: Mutex<Vec<RetiredPlugin>>,
+}
+
+struct LoadedPlugin {
+    plugin: Plugin,
+    info: LoadedPluginInfo,
+    agent: Weak<dyn Agent>,
+}
+
+/// A library opened by [`PluginManager::open`], not yet recorded as loaded.
+/// Pass it to [`PluginManager::commit`] once its agent is registered, or to
+/// [`PluginManager::reject`] if registration fails.
+pub struct PluginCandidate {
+    pub info: LoadedPluginInfo,
+    pub agent: Arc<dyn Agent>,
+    plugin: Plugin,
+}
+
+/// A library that stays mapped until the agent it created has been dropped
+struct RetiredPlugin {
+    agent: Weak<dyn Agent>,
+    _plugin: Plugin,
+}
+
+impl From<LoadedPlugin> for RetiredPlugin {
+    fn from(loaded: LoadedPlugin) -> Self {
+        Self { agent: loaded.agent, _plugin: loaded.plugin }
+    }
+}
+
+impl PluginManager {
+    pub fn new(directory: PathBuf, security_config: PluginSecurityConfig) -> Self {
+        Self {
+            directory,
+            security_config,
+            loaded: Mutex::new(HashMap::new()),
+            failed: Mutex::new(HashMap::new()),
+            retired: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Load (or reload) a single library, replacing any earlier copy and
+    /// recording the outcome. Blocks like [`PluginManager::scan`].
+    pub fn load(&self, path: &Path) -> Result<(LoadedPluginInfo, Arc<dyn Agent>)> {
+        let candidate = self.open(path)?;
+        let loaded = (candidate.info.clone(), candidate.agent.clone());
+        self.commit(candidate);
+        Ok(loaded)
+    }
+
+    /// Load and instantiate a single library without recording it as
+    /// loaded; a failure is recorded. Blocks like [`PluginManager::scan`].
+    pub fn open(&self, path: &Path) -> Result<PluginCandidate> {
+        let start = std::time::Instant::now();
+        let result = unsafe {
+            Plugin::load(path, &self.security_config)
+                .and_then(|plugin| plugin.instantiate().map(|agent| (plugin, agent)))
+        };
+        let load_time = start.elapsed();
+        crate::metrics::platform().record_plugin_load(result.is_ok());
+        match result {
+            Ok((plugin, agent)) => {
+                let agent: Arc<dyn Agent> = Arc::from(agent);
+                let metadata = plugin.metadata();
+                let info = LoadedPluginInfo {
+                    path: metadata.path,
+                    hash: metadata.hash,
+                    abi_version: metadata.abi_version,
+                    agents: vec![agent.name().to_string()],
+                    load_time_ms: load_time.as_millis() as u64,
+                };
+                info!("Loaded plugin {:?} providing {:?} in {}ms", path, info.agents, info.load_time_ms);
+                Ok(PluginCandidate { info, agent, plugin })
+            }
+            Err(e) => {
+                warn!("Failed to load plugin {:?} after {}ms: {:#}", path, load_time.as_millis(), e);
+                self.record_failure(path, &e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Record an opened library as loaded, replacing any earlier copy
+    pub fn commit(&self, candidate: PluginCandidate) {
+        let PluginCandidate { info, agent, plugin } = candidate;
+        let path = info.path.clone();
+        self.failed.lock().unwrap_or_else(|e| e.into_inner()).remove(&path);
+        let loaded = LoadedPlugin { plugin, info, agent: Arc::downgrade(&agent) };
+        let replaced = self.loaded
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path, loaded);
+        if let Some(replaced) = replaced {
+            self.retire(std::iter::once(replaced));
+        }
+    }
+
+    /// Record an opened library whose agent could not be registered as
+    /// failed, so the next scan tries it again. The library stays mapped
+    /// while its agent is alive.
+    pub fn reject(&self, candidate: PluginCandidate, error: &anyhow::Error) {
+        let PluginCandidate { info, agent, plugin } = candidate;
+        self.record_failure(&info.path, error);
+        self.retire(std::iter::once(LoadedPlugin { plugin, info, agent: Arc::downgrade(&agent) }));
+    }
+
+    fn record_failure(&self, path: &Path, error: &anyhow::Error) {
+        self.failed.lock().unwrap_or_else(|e| e.into_inner()).insert(
+            path.to_path_buf(),
+            PluginFailure { path: path.to_path_buf(), error: format!("{:#}", error) },
+        );
+    }
+
+    /// Forget every loaded library so the next scan loads it again and
+    /// produces fresh agents, returning how many were retired
+    pub fn retire_all(&self) -> usize {
+        let drained: Vec<LoadedPlugin> = self
+            .loaded
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .drain()
+            .map(|(_, loaded)| loaded)
+            .collect();
+        self.failed.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        let count = drained.len();
+        self.retire(drained);
+        count
+    }
+
+    /// Keep `plugins` mapped while their agents live, unmapping earlier
+    /// retirees whose agents have since been dropped
+    fn retire(&self, plugins: impl IntoIterator<Item = LoadedPlugin>) {
+        let mut retired = self.retired.lock().unwrap_or_else(|e| e.into_inner());
+        let before = retired.len();
+        retired.retain(|plugin| plugin.agent.strong_count() > 0);
+        if retired.len() < before {
+            info!("Unloaded {} retired plugin libraries", before - retired.len());
+        }
+        retired.extend(plugins.into_iter().map(RetiredPlugin::from));
+    }
+
+    /// Agents created by the currently loaded libraries that are still alive
+    pub fn loaded_agents(&self) -> Vec<(String, Arc<dyn Agent>)> {
+        self.loaded
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .filter_map(|loaded| {
+                let agent = loaded.agent.upgrade()?;
+                Some((agent.name().to_string(), agent))
+            })
+            .collect()
+    }
+
+    /// Loaded plugins and outstanding failures, ordered by path
+    pub fn inventory(&self) -> PluginInventory {
+        let mut loaded: Vec<LoadedPluginInfo> = self
+            .loaded
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .map(|loaded| loaded.info.clone())
+            .collect();
+        let mut failed: Vec<PluginFailure> = self
+            .failed
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect();
+        loaded.sort_by(|a, b| a.path.cmp(&b.path));
+        failed.sort_by(|a, b| a.path.cmp(&b.path));
+        PluginInventory { loaded, failed }
+    }
+
+    /// Load every library in the directory not loaded by a previous scan,
+    /// returning the report and the agents to register. Blocks on file I/O
+    /// and `dlopen`, so async callers should use `spawn_blocking`.
+    pub fn scan(&self) -> Result<(PluginLoadReport, Vec<Arc<dyn Agent>>)> {
+        let (paths, skipped) = self.pending()?;
+        let mut report = PluginLoadReport { skipped, ..Default::default() };
+        let mut agents = Vec::new();
+        for path in paths {
+            match self.load(&path) {
+                Ok((info, agent)) => {
+                    report.loaded.push(info);
+                    agents.push(agent);
+                }
+                Err(e) => report.failed.push(PluginFailure { path, error: format!("{:#}", e) }),
+            }
+        }
+        Ok((report, agents))
+    }
+
+    /// Libraries in the directory not yet loaded, and those already loaded,
+    /// each sorted by path. Blocks on directory I/O.
+    pub fn pending(&self) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(&self.directory)
+            .with_context(|| format!("Failed to read plugin directory: {:?}", self.directory))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && self.has_plugin_extension(path))
+            .collect();
+        paths.sort();
+
+        let loaded = self.loaded.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(paths.into_iter().partition(|path| !loaded.contains_key(path)))
+    }
+
+    fn has_plugin_extension(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .map_or(false, |e| self.security_config.allowed_extensions.contains(&format!(".{}", e)))
+    }
 }
 
 /* ------------ file-watcher hot-reload with security -------------- */
@@ -339,6 +605,45 @@ mod tests {
         assert!(hot_reload::validate_plugin_path(&bad_path, &config).is_err());
     }
 
+    #[test]
+    fn test_plugin_manager_reports_failures() {
+        let temp_dir = tempdir().unwrap();
+        File::create(temp_dir.path().join("broken.so")).unwrap().write_all(b"not a library").unwrap();
+        File::create(temp_dir.path().join("README.txt")).unwrap();
+
+        let config = PluginSecurityConfig {
+            require_signatures: false,
+            ..PluginSecurityConfig::default()
+        };
+        let manager = PluginManager::new(temp_dir.path().to_path_buf(), config);
+        let (report, agents) = manager.scan().unwrap();
+        assert!(agents.is_empty() && report.loaded.is_empty() && report.skipped.is_empty());
+        assert_eq!(report.failed.len(), 1);
+        assert!(report.failed[0].path.ends_with("broken.so"));
+        assert!(!report.failed[0].error.is_empty());
+
+        let inventory = manager.inventory();
+        assert!(inventory.loaded.is_empty());
+        assert_eq!(inventory.failed.len(), 1);
+        assert_eq!(inventory.failed[0].error, report.failed[0].error);
+
+        assert!(PluginManager::new(temp_dir.path().join("missing"), PluginSecurityConfig::default()).scan().is_err());
+    }
+
+    #[test]
+    fn test_pending_plugins_are_sorted() {
+        let temp_dir = tempdir().unwrap();
+        for name in ["c.so", "a.so", "b.so", "notes.txt"] {
+            File::create(temp_dir.path().join(name)).unwrap();
+        }
+
+        let manager = PluginManager::new(temp_dir.path().to_path_buf(), PluginSecurityConfig::default());
+        let (pending, loaded) = manager.pending().unwrap();
+        let names: Vec<_> = pending.iter().map(|p| p.file_name().unwrap().to_str().unwrap()).collect();
+        assert_eq!(names, vec!["a.so", "b.so", "c.so"]);
+        assert!(loaded.is_empty());
+    }
+
     #[test]
     fn test_file_hash_calculation() {
         let temp_dir = tempdir().unwrap();
@@ -0,0 +1,173 @@
+//! Helpers for supervising and capping child processes spawned by agents and health checks.
+
+use anyhow::{anyhow, Result};
+use std::fmt;
+use std::process::ExitStatus;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::process::Child;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::{error, warn};
+
+use crate::metrics::platform;
+use crate::settings::Settings;
+
+/// No subprocess slot freed up within the limiter's wait
+#[derive(Debug)]
+pub struct SubprocessLimitError {
+    pub limit: usize,
+    pub waited: Duration,
+}
+
+impl fmt::Display for SubprocessLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Too many subprocesses: all {} slots stayed busy for {:?}", self.limit, self.waited)
+    }
+}
+
+impl std::error::Error for SubprocessLimitError {}
+
+/// Caps how many child processes run at once. Spawners wait for a slot in
+/// arrival order, for at most `max_wait`.
+pub struct SubprocessLimiter {
+    slots: Arc<Semaphore>,
+    limit: usize,
+    max_wait: Duration,
+}
+
+/// A subprocess slot, held until the child has exited and been reaped
+pub struct SubprocessPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Drop for SubprocessPermit {
+    fn drop(&mut self) {
+        platform().record_subprocess(false);
+    }
+}
+
+impl SubprocessLimiter {
+    pub fn new(limit: usize, max_wait: Duration) -> Self {
+        let limit = limit.max(1);
+        Self { slots: Arc::new(Semaphore::new(limit)), limit, max_wait }
+    }
+
+    /// Wait for a free slot, failing with [`SubprocessLimitError`] after `max_wait`
+    pub async fn acquire(&self) -> Result<SubprocessPermit> {
+        match tokio::time::timeout(self.max_wait, self.slots.clone().acquire_owned()).await {
+            Ok(Ok(permit)) => {
+                platform().record_subprocess(true);
+                Ok(SubprocessPermit { _permit: permit })
+            }
+            Ok(Err(_)) => Err(anyhow!("Subprocess limiter closed")),
+            Err(_) => {
+                warn!("No subprocess slot freed up within {:?}", self.max_wait);
+                Err(SubprocessLimitError { limit: self.limit, waited: self.max_wait }.into())
+            }
+        }
+    }
+
+    /// Slots not currently held
+    pub fn available(&self) -> usize {
+        self.slots.available_permits()
+    }
+}
+
+/// The limiter shared by every subprocess-spawning agent, sized from
+/// `security.max_subprocesses` as first seen, so changes need a restart
+pub fn subprocess_limiter(settings: &Settings) -> &'static SubprocessLimiter {
+    static LIMITER: OnceLock<SubprocessLimiter> = OnceLock::new();
+    LIMITER.get_or_init(|| {
+        SubprocessLimiter::new(
+            settings.security.max_subprocesses,
+            Duration::from_secs(settings.security.subprocess_wait_seconds),
+        )
+    })
+}
+
+/// Wait for `child` to exit, killing and reaping it if `timeout` elapses first.
+///
+/// Returns `Ok(Some(status))` when the process exits on its own and `Ok(None)`
+/// when it had to be killed. Dropping a `wait()` future does not stop the OS
+/// process, so callers must use this instead of wrapping `output()` in a
+/// `tokio::time::timeout`, otherwise timed-out children are left orphaned.
+pub async fn wait_or_kill(child: &mut Child, timeout: Duration) -> Result<Option<ExitStatus>> {
+    match tokio::time::timeout(timeout, child.wait()).await {
+        Ok(status) => Ok(Some(status.map_err(|e| anyhow!("Failed to wait for child process: {}", e))?)),
+        Err(_) => {
+            warn!("Child process {:?} timed out after {:?}, killing it", child.id(), timeout);
+            if let Err(e) = child.start_kill() {
+                error!("Failed to kill timed-out child process: {}", e);
+            }
+            // Reap the process so it does not linger as a zombie
+            if let Err(e) = child.wait().await {
+                error!("Failed to reap killed child process: {}", e);
+            }
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::process::Stdio;
+    use tokio::process::Command;
+
+    #[tokio::test]
+    async fn test_wait_or_kill_terminates_timed_out_process() {
+        let mut child = Command::new("sleep")
+            .arg("10")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let pid = child.id().unwrap();
+
+        let status = wait_or_kill(&mut child, Duration::from_secs(1)).await.unwrap();
+        assert!(status.is_none());
+
+        // The process must be reaped, not left running or as a zombie
+        assert!(child.try_wait().unwrap().is_some());
+        if cfg!(target_os = "linux") {
+            assert!(!std::path::Path::new(&format!("/proc/{}", pid)).exists());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subprocess_limiter_queues_beyond_limit() {
+        let limiter = Arc::new(SubprocessLimiter::new(2, Duration::from_secs(5)));
+        let running = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let peak = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let spawns = (0..5).map(|_| {
+            let (limiter, running, peak) = (limiter.clone(), running.clone(), peak.clone());
+            tokio::spawn(async move {
+                let _permit = limiter.acquire().await.unwrap();
+                let now = running.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+                peak.fetch_max(now, std::sync::atomic::Ordering::SeqCst);
+                let mut child = Command::new("sleep").arg("0.2").spawn().unwrap();
+                wait_or_kill(&mut child, Duration::from_secs(5)).await.unwrap();
+                running.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+            })
+        });
+        for spawn in spawns.collect::<Vec<_>>() {
+            spawn.await.unwrap();
+        }
+        assert_eq!(peak.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert_eq!(limiter.available(), 2);
+
+        // Waiting is bounded
+        let limiter = SubprocessLimiter::new(1, Duration::from_millis(50));
+        let _held = limiter.acquire().await.unwrap();
+        let err = limiter.acquire().await.err().unwrap();
+        assert!(err.downcast_ref::<SubprocessLimitError>().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_wait_or_kill_returns_status_on_exit() {
+        let mut child = Command::new("true").spawn().unwrap();
+        let status = wait_or_kill(&mut child, Duration::from_secs(5)).await.unwrap();
+        assert!(status.unwrap().success());
+    }
+}
@@ -0,0 +1,206 @@
+//! Named prompt templates for LLM agents.
+//!
+//! Templates use `{{variable}}` placeholders (whitespace inside the braces is
+//! ignored) and `\{{` for a literal `{{`. Rendering is a single pass: values
+//! are inserted verbatim and never re-parsed, so caller-supplied text that
+//! happens to contain `{{...}}` cannot pull in other variables.
+
+use anyhow::{anyhow, Context, Result};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use crate::agent::InvalidInputError;
+use crate::settings::LlmConfig;
+
+/// File extension of templates loaded from `llm.prompt_template_dir`
+pub const TEMPLATE_EXTENSION: &str = "tmpl";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Variable(String),
+}
+
+/// A parsed prompt template
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    parts: Vec<Part>,
+}
+
+impl PromptTemplate {
+    /// Parse `source`, rejecting unterminated or malformed placeholders
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut rest = source;
+
+        while let Some(start) = rest.find("{{") {
+            if rest[..start].ends_with('\\') {
+                literal.push_str(&rest[..start - 1]);
+                literal.push_str("{{");
+                rest = &rest[start + 2..];
+                continue;
+            }
+            literal.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let end = after.find("}}")
+                .ok_or_else(|| anyhow!("Unterminated '{{{{' at byte {}", source.len() - rest.len() + start))?;
+            let name = after[..end].trim();
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(anyhow!("Invalid template variable '{}'; use letters, digits and '_'", name));
+            }
+            if !literal.is_empty() {
+                parts.push(Part::Literal(std::mem::take(&mut literal)));
+            }
+            parts.push(Part::Variable(name.to_string()));
+            rest = &after[end + 2..];
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// Names of the variables the template uses
+    pub fn variables(&self) -> BTreeSet<&str> {
+        self.parts.iter()
+            .filter_map(|part| match part {
+                Part::Variable(name) => Some(name.as_str()),
+                Part::Literal(_) => None,
+            })
+            .collect()
+    }
+
+    /// Substitute `values`, failing with [`InvalidInputError`] naming every
+    /// variable that was not provided
+    pub fn render(&self, values: &HashMap<String, String>) -> Result<String> {
+        let missing: Vec<&str> = self.variables().into_iter()
+            .filter(|name| !values.contains_key(*name))
+            .collect();
+        if !missing.is_empty() {
+            return Err(InvalidInputError(format!("Missing template variables: {}", missing.join(", "))).into());
+        }
+
+        let mut output = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => output.push_str(text),
+                Part::Variable(name) => output.push_str(&values[name]),
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// Templates selectable by name
+#[derive(Debug, Clone, Default)]
+pub struct PromptTemplates {
+    templates: HashMap<String, PromptTemplate>,
+}
+
+impl PromptTemplates {
+    /// Load inline templates from `llm.prompt_templates`, then every
+    /// `*.tmpl` file in `llm.prompt_template_dir` named by its file stem.
+    /// A file replaces an inline template of the same name.
+    pub fn from_config(config: &LlmConfig) -> Result<Self> {
+        let mut templates = Self::default();
+        for (name, source) in &config.prompt_templates {
+            templates.insert(name, source)?;
+        }
+        if let Some(dir) = &config.prompt_template_dir {
+            templates.load_dir(dir)?;
+        }
+        Ok(templates)
+    }
+
+    fn load_dir(&mut self, dir: &Path) -> Result<()> {
+        let entries = std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read prompt template directory {:?}", dir))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(TEMPLATE_EXTENSION) {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let source = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read prompt template {:?}", path))?;
+            self.insert(name, &source)?;
+        }
+        Ok(())
+    }
+
+    /// Add or replace a template
+    pub fn insert(&mut self, name: &str, source: &str) -> Result<()> {
+        let template = PromptTemplate::parse(source)
+            .with_context(|| format!("Invalid prompt template '{}'", name))?;
+        self.templates.insert(name.to_string(), template);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PromptTemplate> {
+        self.templates.get(name)
+    }
+
+    /// Render the named template, failing with [`InvalidInputError`] if it
+    /// does not exist or a variable is missing
+    pub fn render(&self, name: &str, values: &HashMap<String, String>) -> Result<String> {
+        self.get(name)
+            .ok_or_else(|| InvalidInputError(format!("Unknown prompt template '{}'", name)))?
+            .render(values)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.templates.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_render_substitutes_once() {
+        let template = PromptTemplate::parse("Summarize:\n{{ text }}\nin {{words}} words. \\{{literal}}").unwrap();
+        assert_eq!(template.variables().into_iter().collect::<Vec<_>>(), vec!["text", "words"]);
+
+        let rendered = template.render(&values(&[("text", "say {{words}}"), ("words", "ten")])).unwrap();
+        assert_eq!(rendered, "Summarize:\nsay {{words}}\nin ten words. {{literal}}");
+    }
+
+    #[test]
+    fn test_missing_variables_and_bad_syntax() {
+        let template = PromptTemplate::parse("{{a}} {{b}} {{c}}").unwrap();
+        let err = template.render(&values(&[("b", "x")])).unwrap_err();
+        assert!(err.is::<InvalidInputError>());
+        assert_eq!(err.to_string(), "Missing template variables: a, c");
+
+        assert!(PromptTemplate::parse("Hello {{name").is_err());
+        assert!(PromptTemplate::parse("Hello {{first name}}").is_err());
+    }
+
+    #[test]
+    fn test_templates_from_config_and_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("classify.tmpl"), "Label: {{text}}").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let mut config = LlmConfig::default();
+        config.prompt_templates.insert("chat".to_string(), "User: {{prompt}}".to_string());
+        config.prompt_template_dir = Some(dir.path().to_path_buf());
+        let templates = PromptTemplates::from_config(&config).unwrap();
+
+        assert_eq!(templates.names(), vec!["chat", "classify", "rag"]);
+        assert_eq!(templates.render("classify", &values(&[("text", "hi")])).unwrap(), "Label: hi");
+        assert!(templates.render("missing", &values(&[])).unwrap_err().is::<InvalidInputError>());
+    }
+}
@@ -0,0 +1,409 @@
+//! Per-user quotas enforced at dispatch.
+//!
+//! A quota caps the requests a user may make and the compute units they may
+//! spend per window. Agents that can price a call report a
+//! [`Cost`](crate::agent::Cost) estimate before it runs; the call and its
+//! estimate are reserved against the caller's quota up front, so concurrent
+//! calls cannot jointly overspend, and the estimate is replaced by the actual
+//! cost once the call completes. Calls that fail or are cancelled are
+//! refunded. Agents without an estimate only count as requests.
+//!
+//! Usage is kept in memory and, when a store path is configured, written
+//! through to a sled database so it survives restarts, along with the quota
+//! overrides admins set at runtime.
+
+use anyhow::{anyhow, Result};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use tracing::{info, warn};
+
+use crate::settings::Settings;
+
+/// Tree of per-user [`Usage`] in the usage store
+const USAGE_TREE: &str = "usage";
+
+/// Tree of admin-set [`Quota`] overrides in the usage store
+const OVERRIDES_TREE: &str = "quota_overrides";
+
+/// Limits on one user's usage per window; `None` leaves that limit off
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Quota {
+    pub max_requests: Option<u64>,
+    pub max_cost_units: Option<f64>,
+}
+
+/// Usage accumulated in a user's current window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    /// Unix time in seconds the window started
+    pub window_start: u64,
+    pub requests: u64,
+    pub cost_units: f64,
+}
+
+/// The part of a quota a call would exceed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuotaLimit {
+    Requests { limit: u64 },
+    CostUnits { required: f64, remaining: f64 },
+}
+
+/// Returned when a call would take its caller over quota
+#[derive(Debug)]
+pub struct QuotaExceededError {
+    pub user: String,
+    pub agent: String,
+    pub limit: QuotaLimit,
+}
+
+impl fmt::Display for QuotaExceededError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Quota exceeded for user '{}': ", self.user)?;
+        match self.limit {
+            QuotaLimit::Requests { limit } => {
+                write!(f, "all {} requests of the current window are used", limit)
+            }
+            QuotaLimit::CostUnits { required, remaining } => write!(
+                f,
+                "agent '{}' needs an estimated {:.1} compute units, {:.1} remain",
+                self.agent, required, remaining
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QuotaExceededError {}
+
+/// A user's quota and usage, as reported by `GET /auth/usage`
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageReport {
+    pub user: String,
+    pub quota: Quota,
+    /// Whether `quota` was set by an admin rather than configured
+    pub overridden: bool,
+    pub requests: u64,
+    pub cost_units: f64,
+    /// Unix time in seconds the current window ends; `None` when usage never resets
+    pub resets_at: Option<u64>,
+}
+
+/// Write-through persistence for usage and quota overrides
+#[derive(Debug)]
+struct UsageStore {
+    usage: sled::Tree,
+    overrides: sled::Tree,
+}
+
+impl UsageStore {
+    fn open(path: &str) -> Result<Self> {
+        let db = sled::open(path).map_err(|e| anyhow!("Failed to open usage store at '{}': {}", path, e))?;
+        Ok(Self {
+            usage: db.open_tree(USAGE_TREE)?,
+            overrides: db.open_tree(OVERRIDES_TREE)?,
+        })
+    }
+
+    fn load<T: serde::de::DeserializeOwned>(tree: &sled::Tree) -> Result<Vec<(String, T)>> {
+        tree.iter()
+            .map(|item| {
+                let (key, value) = item?;
+                Ok((String::from_utf8_lossy(&key).into_owned(), serde_json::from_slice(&value)?))
+            })
+            .collect()
+    }
+
+    fn save<T: Serialize>(tree: &sled::Tree, user: &str, value: &T) {
+        let result = serde_json::to_vec(value)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| Ok(tree.insert(user, bytes)?));
+        if let Err(e) = result {
+            warn!("Failed to persist usage for '{}': {}", user, e);
+        }
+    }
+}
+
+/// Per-user quotas and the usage counted against them
+#[derive(Debug, Default)]
+pub struct QuotaLedger {
+    default_quota: Quota,
+    /// Configured compute units per user, over `default_quota`
+    budgets: HashMap<String, f64>,
+    /// Configured requests per user, over `default_quota`
+    request_quotas: HashMap<String, u64>,
+    /// Quotas set by admins, replacing the configured ones
+    overrides: DashMap<String, Quota>,
+    usage: DashMap<String, Usage>,
+    /// Window length in seconds; `None` lets usage accumulate without resetting
+    window: Option<u64>,
+    store: Option<UsageStore>,
+}
+
+impl QuotaLedger {
+    /// An in-memory ledger charging compute units against `budgets`, without
+    /// request limits or windows
+    pub fn new(budgets: HashMap<String, f64>) -> Self {
+        Self { budgets, ..Self::default() }
+    }
+
+    /// Build the ledger from the orchestrator settings, reloading usage and
+    /// overrides from the usage store when one is configured
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let config = &settings.orchestrator;
+        let ledger = Self {
+            default_quota: Quota {
+                max_requests: config.default_request_quota,
+                max_cost_units: config.default_user_budget,
+            },
+            budgets: config.user_budgets.clone(),
+            request_quotas: config.user_request_quotas.clone(),
+            window: Some(config.quota_window_seconds).filter(|seconds| *seconds > 0),
+            store: config.usage_store_path.as_deref().map(UsageStore::open).transpose()?,
+            ..Self::default()
+        };
+
+        if let Some(store) = &ledger.store {
+            for (user, usage) in UsageStore::load(&store.usage)? {
+                ledger.usage.insert(user, usage);
+            }
+            for (user, quota) in UsageStore::load(&store.overrides)? {
+                ledger.overrides.insert(user, quota);
+            }
+            info!("Loaded usage for {} users and {} quota overrides", ledger.usage.len(), ledger.overrides.len());
+        }
+        Ok(ledger)
+    }
+
+    /// The quota `user` is held to: an admin override if set, otherwise the
+    /// configured per-user limits over the defaults
+    pub fn quota(&self, user: &str) -> Quota {
+        if let Some(quota) = self.overrides.get(user) {
+            return *quota;
+        }
+        Quota {
+            max_requests: self.request_quotas.get(user).copied().or(self.default_quota.max_requests),
+            max_cost_units: self.budgets.get(user).copied().or(self.default_quota.max_cost_units),
+        }
+    }
+
+    /// Replace the quota of `user`, or with `None` return them to the configured one
+    pub fn set_override(&self, user: &str, quota: Option<Quota>) {
+        match quota {
+            Some(quota) => {
+                self.overrides.insert(user.to_string(), quota);
+                if let Some(store) = &self.store {
+                    UsageStore::save(&store.overrides, user, &quota);
+                }
+            }
+            None => {
+                self.overrides.remove(user);
+                if let Some(Err(e)) = self.store.as_ref().map(|store| store.overrides.remove(user)) {
+                    warn!("Failed to remove quota override for '{}': {}", user, e);
+                }
+            }
+        }
+    }
+
+    /// Compute units `user` may still spend this window; `None` when unlimited
+    pub fn remaining(&self, user: &str) -> Option<f64> {
+        let max = self.quota(user).max_cost_units?;
+        Some((max - self.report(user).cost_units).max(0.0))
+    }
+
+    /// Quota and current usage of `user`
+    pub fn report(&self, user: &str) -> UsageReport {
+        self.report_at(user, unix_now())
+    }
+
+    fn report_at(&self, user: &str, now: u64) -> UsageReport {
+        let usage = self.usage.get(user)
+            .map(|usage| *usage)
+            .filter(|usage| !self.expired(usage, now))
+            .unwrap_or_default();
+        UsageReport {
+            user: user.to_string(),
+            quota: self.quota(user),
+            overridden: self.overrides.contains_key(user),
+            requests: usage.requests,
+            cost_units: usage.cost_units,
+            resets_at: self.window.map(|window| {
+                if usage.window_start == 0 { now + window } else { usage.window_start + window }
+            }),
+        }
+    }
+
+    /// Count a call by `user` to `agent` estimated at `units`, or fail if it
+    /// would take them over quota
+    pub fn reserve(&self, user: &str, agent: &str, units: f64) -> Result<Reservation<'_>, QuotaExceededError> {
+        self.reserve_at(user, agent, units, unix_now())
+    }
+
+    fn reserve_at(&self, user: &str, agent: &str, units: f64, now: u64) -> Result<Reservation<'_>, QuotaExceededError> {
+        let quota = self.quota(user);
+        let exceeded = |limit| QuotaExceededError { user: user.to_string(), agent: agent.to_string(), limit };
+
+        // Check and reserve under the entry lock so concurrent calls see each other
+        let mut usage = self.usage.entry(user.to_string()).or_default();
+        if self.expired(&usage, now) {
+            *usage = Usage { window_start: now, ..Usage::default() };
+        }
+        if let Some(limit) = quota.max_requests {
+            if usage.requests >= limit {
+                return Err(exceeded(QuotaLimit::Requests { limit }));
+            }
+        }
+        if let Some(max) = quota.max_cost_units {
+            let remaining = (max - usage.cost_units).max(0.0);
+            if units > remaining {
+                return Err(exceeded(QuotaLimit::CostUnits { required: units, remaining }));
+            }
+        }
+        usage.requests += 1;
+        usage.cost_units += units;
+        self.persist(user, &usage);
+
+        Ok(Reservation {
+            ledger: self,
+            user: user.to_string(),
+            window_start: usage.window_start,
+            units,
+            settled: false,
+        })
+    }
+
+    /// Whether `usage` belongs to a window that has ended, or to none yet
+    fn expired(&self, usage: &Usage, now: u64) -> bool {
+        usage.window_start == 0 || self.window.map_or(false, |window| now >= usage.window_start + window)
+    }
+
+    fn persist(&self, user: &str, usage: &Usage) {
+        if let Some(store) = &self.store {
+            UsageStore::save(&store.usage, user, usage);
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// A call counted against a user's quota while it runs. Dropping it without
+/// [`settle`](Reservation::settle) refunds the call.
+#[derive(Debug)]
+pub struct Reservation<'a> {
+    ledger: &'a QuotaLedger,
+    user: String,
+    /// Window the call was counted in; a refund after it ended is moot
+    window_start: u64,
+    units: f64,
+    settled: bool,
+}
+
+impl Reservation<'_> {
+    /// Charge `units` for the completed call in place of its estimate
+    pub fn settle(mut self, units: f64) {
+        self.adjust(0, units - self.units);
+        self.settled = true;
+    }
+
+    fn adjust(&self, requests: i64, units: f64) {
+        let Some(mut usage) = self.ledger.usage.get_mut(&self.user) else {
+            return;
+        };
+        if usage.window_start != self.window_start {
+            return;
+        }
+        usage.requests = usage.requests.saturating_add_signed(requests);
+        usage.cost_units = (usage.cost_units + units).max(0.0);
+        self.ledger.persist(&self.user, &usage);
+    }
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        if !self.settled {
+            self.adjust(-1, -self.units);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ledger() -> QuotaLedger {
+        QuotaLedger::new(HashMap::from([("alice".to_string(), 100.0)]))
+    }
+
+    #[test]
+    fn test_reservations_count_against_budget_until_settled() {
+        let ledger = ledger();
+        let first = ledger.reserve("alice", "llm", 60.0).unwrap();
+        assert_eq!(ledger.remaining("alice"), Some(40.0));
+
+        // A concurrent call cannot spend what the first one holds
+        let err = ledger.reserve("alice", "llm", 60.0).unwrap_err();
+        assert_eq!(err.limit, QuotaLimit::CostUnits { required: 60.0, remaining: 40.0 });
+
+        // Settling charges the actual cost in place of the estimate
+        first.settle(25.0);
+        assert_eq!(ledger.remaining("alice"), Some(75.0));
+
+        // An unsettled reservation is refunded
+        drop(ledger.reserve("alice", "llm", 50.0).unwrap());
+        assert_eq!(ledger.remaining("alice"), Some(75.0));
+        assert_eq!(ledger.report("alice").requests, 1);
+    }
+
+    #[test]
+    fn test_users_without_budget_are_unmetered() {
+        let ledger = ledger();
+        ledger.reserve("bob", "llm", 1e9).unwrap().settle(1e9);
+        assert_eq!(ledger.remaining("bob"), None);
+        assert_eq!(ledger.report("bob").requests, 1);
+    }
+
+    #[test]
+    fn test_request_quota_resets_with_window() {
+        let ledger = QuotaLedger { window: Some(60), ..QuotaLedger::default() };
+        ledger.set_override("alice", Some(Quota { max_requests: Some(2), max_cost_units: None }));
+
+        let start = 1_000;
+        ledger.reserve_at("alice", "echo", 0.0, start).unwrap().settle(0.0);
+        ledger.reserve_at("alice", "echo", 0.0, start + 1).unwrap().settle(0.0);
+        let err = ledger.reserve_at("alice", "echo", 0.0, start + 59).unwrap_err();
+        assert_eq!(err.limit, QuotaLimit::Requests { limit: 2 });
+
+        let report = ledger.report_at("alice", start + 59);
+        assert_eq!((report.requests, report.resets_at, report.overridden), (2, Some(start + 60), true));
+
+        // A new window starts from zero
+        ledger.reserve_at("alice", "echo", 0.0, start + 60).unwrap().settle(0.0);
+        assert_eq!(ledger.report_at("alice", start + 61).requests, 1);
+
+        // Clearing the override returns to the (unlimited) configured quota
+        ledger.set_override("alice", None);
+        assert_eq!(ledger.quota("alice"), Quota::default());
+    }
+
+    #[test]
+    fn test_usage_and_overrides_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut settings = Settings::default();
+        settings.orchestrator.usage_store_path = Some(dir.path().join("usage").to_string_lossy().into_owned());
+
+        {
+            let ledger = QuotaLedger::from_settings(&settings).unwrap();
+            ledger.set_override("alice", Some(Quota { max_requests: Some(5), max_cost_units: None }));
+            ledger.reserve("alice", "echo", 3.0).unwrap().settle(3.0);
+        }
+
+        let ledger = QuotaLedger::from_settings(&settings).unwrap();
+        let report = ledger.report("alice");
+        assert_eq!((report.requests, report.cost_units), (1, 3.0));
+        assert_eq!(report.quota.max_requests, Some(5));
+    }
+}
@@ -0,0 +1,140 @@
+//! Defense-in-depth redaction of secrets echoed back in agent output.
+
+use anyhow::{anyhow, Result};
+use regex::{Regex, RegexBuilder};
+
+use crate::settings::SecurityConfig;
+
+/// Replacement text for redacted matches
+pub const REDACTED: &str = "***REDACTED***";
+
+/// Marker appended when output exceeds the scan budget and is cut short
+pub const TRUNCATED: &str = "...[output truncated for redaction]";
+
+/// Compiled size limit for each redaction pattern, keeps matching cost predictable
+const MAX_PATTERN_SIZE_BYTES: usize = 1 << 20;
+
+/// Fragments marking a field or metadata key whose value is a credential
+pub const SECRET_KEY_FRAGMENTS: &[&str] = &["password", "secret", "token", "auth", "cookie", "key"];
+
+/// Whether `key` names a credential, matched case-insensitively against
+/// [`SECRET_KEY_FRAGMENTS`]
+pub fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SECRET_KEY_FRAGMENTS.iter().any(|fragment| key.contains(fragment))
+}
+
+/// Scans agent output for secret patterns and replaces matches before it leaves the server
+pub struct OutputRedactor {
+    patterns: Vec<Regex>,
+    max_scan_bytes: usize,
+}
+
+impl OutputRedactor {
+    /// Build a redactor from the security config, or `None` when redaction is disabled
+    pub fn from_config(config: &SecurityConfig) -> Result<Option<Self>> {
+        if !config.enable_output_redaction {
+            return Ok(None);
+        }
+        Self::new(config).map(Some)
+    }
+
+    /// Build a redactor from the configured patterns whether or not output
+    /// redaction is enabled, for text that must always be scrubbed
+    pub fn new(config: &SecurityConfig) -> Result<Self> {
+        let mut patterns = Vec::with_capacity(config.output_redaction_patterns.len() + 1);
+        for pattern in &config.output_redaction_patterns {
+            let regex = RegexBuilder::new(pattern)
+                .size_limit(MAX_PATTERN_SIZE_BYTES)
+                .build()
+                .map_err(|e| anyhow!("Invalid output redaction pattern '{}': {}", pattern, e))?;
+            patterns.push(regex);
+        }
+
+        // Always redact the JWT secret itself if one is configured
+        if let Some(secret) = config.jwt_secret.as_deref().filter(|s| !s.is_empty()) {
+            patterns.push(Regex::new(&regex::escape(secret))?);
+        }
+
+        Ok(Self {
+            patterns,
+            max_scan_bytes: config.max_redaction_scan_bytes,
+        })
+    }
+
+    /// Redact all pattern matches in `output`.
+    ///
+    /// Output beyond `max_scan_bytes` is dropped rather than returned unscanned,
+    /// so the cost of a pass is bounded by the budget, not the output size.
+    pub fn redact(&self, output: &str) -> String {
+        let (scanned, truncated) = if output.len() > self.max_scan_bytes {
+            let mut end = self.max_scan_bytes;
+            while !output.is_char_boundary(end) {
+                end -= 1;
+            }
+            (&output[..end], true)
+        } else {
+            (output, false)
+        };
+
+        let mut result = scanned.to_string();
+        for pattern in &self.patterns {
+            if pattern.is_match(&result) {
+                result = pattern.replace_all(&result, REDACTED).into_owned();
+            }
+        }
+
+        if truncated {
+            result.push_str(TRUNCATED);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(patterns: &[&str]) -> SecurityConfig {
+        SecurityConfig {
+            enable_output_redaction: true,
+            output_redaction_patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            jwt_secret: Some("super-secret-jwt-signing-key".to_string()),
+            ..SecurityConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_secret_keys_match_case_insensitively() {
+        for key in ["password", "API_Key", "Authorization", "session_cookie", "refresh_token"] {
+            assert!(is_secret_key(key), "{}", key);
+        }
+        assert!(!is_secret_key("locale"));
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        assert!(OutputRedactor::from_config(&SecurityConfig::default()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_redacts_patterns_and_jwt_secret() {
+        let redactor = OutputRedactor::from_config(&config(&[r"sk-[A-Za-z0-9]{8,}"])).unwrap().unwrap();
+        let output = redactor.redact("key=sk-abcdef123456 secret=super-secret-jwt-signing-key done");
+        assert_eq!(output, format!("key={} secret={} done", REDACTED, REDACTED));
+    }
+
+    #[test]
+    fn test_invalid_pattern_rejected() {
+        assert!(OutputRedactor::from_config(&config(&["("])).is_err());
+    }
+
+    #[test]
+    fn test_scan_budget_truncates_output() {
+        let mut cfg = config(&[]);
+        cfg.max_redaction_scan_bytes = 10;
+        let redactor = OutputRedactor::from_config(&cfg).unwrap().unwrap();
+        let output = redactor.redact("ééééééééééé");
+        assert_eq!(output, format!("ééééé{}", TRUNCATED));
+    }
+}
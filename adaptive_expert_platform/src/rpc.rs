@@ -0,0 +1,296 @@
+//! JSON-RPC 2.0 transport over `POST /rpc`.
+//!
+//! A method is either a platform operation (`agents.list`, `memory.search`,
+//! `memory.add`) or the name of a registered agent, in which case `params` is
+//! passed to it as the task input through the same dispatch path as `/execute`.
+//! Batch requests are executed sequentially and answered in order. Memory
+//! operations honour `memory.tenant_isolation` like the HTTP endpoints.
+
+use axum::{
+    body::Bytes,
+    extract::{Extension, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{error, instrument};
+
+use crate::auth::Claims;
+use crate::error::OrchestratorError;
+use crate::input_guard::InputLimitError;
+use crate::memory::SearchStrategy;
+use crate::mesh::TaskPriority;
+use crate::server::AppState;
+
+/// Invalid JSON was received
+pub const PARSE_ERROR: i64 = -32700;
+/// The JSON sent is not a valid request object
+pub const INVALID_REQUEST: i64 = -32600;
+/// No platform operation or agent with this name
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// Params were rejected by the operation or agent
+pub const INVALID_PARAMS: i64 = -32602;
+/// Internal platform failure
+pub const INTERNAL_ERROR: i64 = -32603;
+/// The agent ran but failed (or timed out)
+pub const AGENT_ERROR: i64 = -32000;
+/// The caller's request or cost quota is used up
+pub const QUOTA_EXCEEDED: i64 = -32001;
+
+/// JSON-RPC request object
+#[derive(Debug, Deserialize)]
+struct RpcRequest {
+    jsonrpc: String,
+    method: String,
+    #[serde(default)]
+    params: Value,
+    /// Absent for notifications, which get no response; an explicit `null` is kept
+    #[serde(default, deserialize_with = "present_id")]
+    id: Option<Value>,
+}
+
+fn present_id<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
+
+/// JSON-RPC error object
+#[derive(Debug, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+/// JSON-RPC response object
+#[derive(Debug, Serialize)]
+pub struct RpcResponse {
+    jsonrpc: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+    id: Value,
+}
+
+impl RpcResponse {
+    fn success(id: Value, result: Value) -> Self {
+        Self { jsonrpc: "2.0", result: Some(result), error: None, id }
+    }
+
+    fn failure(id: Value, error: RpcError) -> Self {
+        Self { jsonrpc: "2.0", result: None, error: Some(error), id }
+    }
+}
+
+/// Handle a single or batch JSON-RPC call. Agent calls count against the
+/// caller's quota like `/execute`; every call in a batch counts.
+#[instrument(skip(state, claims, body))]
+pub async fn handle_rpc(
+    State(state): State<AppState>,
+    claims: Option<Extension<Claims>>,
+    body: Bytes,
+) -> Response {
+    let caller = claims.as_ref().map(|Extension(c)| c);
+    let payload: Value = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => {
+            let error = RpcError::new(PARSE_ERROR, format!("Parse error: {}", e));
+            return Json(RpcResponse::failure(Value::Null, error)).into_response();
+        }
+    };
+
+    match payload {
+        Value::Array(calls) if calls.is_empty() => {
+            let error = RpcError::new(INVALID_REQUEST, "Empty batch");
+            Json(RpcResponse::failure(Value::Null, error)).into_response()
+        }
+        Value::Array(calls) => {
+            let mut responses = Vec::with_capacity(calls.len());
+            for call in calls {
+                if let Some(response) = handle_call(&state, caller, call).await {
+                    responses.push(response);
+                }
+            }
+            if responses.is_empty() {
+                // A batch of notifications gets no response body
+                StatusCode::NO_CONTENT.into_response()
+            } else {
+                Json(responses).into_response()
+            }
+        }
+        call => match handle_call(&state, caller, call).await {
+            Some(response) => Json(response).into_response(),
+            None => StatusCode::NO_CONTENT.into_response(),
+        },
+    }
+}
+
+/// Execute one call; `None` for notifications
+async fn handle_call(state: &AppState, caller: Option<&Claims>, call: Value) -> Option<RpcResponse> {
+    let request: RpcRequest = match serde_json::from_value(call) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = RpcError::new(INVALID_REQUEST, format!("Invalid request: {}", e));
+            return Some(RpcResponse::failure(Value::Null, error));
+        }
+    };
+
+    if request.jsonrpc != "2.0" {
+        let error = RpcError::new(INVALID_REQUEST, "Invalid request: jsonrpc must be \"2.0\"");
+        return Some(RpcResponse::failure(request.id.unwrap_or(Value::Null), error));
+    }
+
+    let result = call_method(state, caller, &request.method, request.params).await;
+    let id = request.id?;
+    Some(match result {
+        Ok(result) => RpcResponse::success(id, result),
+        Err(error) => RpcResponse::failure(id, error),
+    })
+}
+
+async fn call_method(state: &AppState, caller: Option<&Claims>, method: &str, params: Value) -> Result<Value, RpcError> {
+    match method {
+        "agents.list" => {
+            let orchestrator = state.orchestrator.read().await;
+            let agents: Vec<Value> = orchestrator
+                .list_agent_capabilities()
+                .await
+                .into_iter()
+                .map(|(name, agent_type, capabilities)| serde_json::json!({
+                    "status": if orchestrator.is_in_maintenance(&name) { "maintenance" } else { "active" },
+                    "name": name,
+                    "agent_type": agent_type,
+                    "capabilities": capabilities,
+                }))
+                .collect();
+            Ok(Value::Array(agents))
+        }
+        "memory.search" => {
+            let query = string_param(&params, "query")?;
+            let top_k = params.get("top_k").and_then(Value::as_u64).unwrap_or(10) as usize;
+            let tenant = crate::server::memory_tenant(&state.settings, caller);
+            let memory = state.orchestrator.read().await.memory();
+            let results = memory
+                .search_memory_with_tenant(&query, top_k, None, SearchStrategy::Rerank, memory.recency_half_life(), tenant.as_deref())
+                .await
+                .map_err(|e| internal_error("Memory search failed", e))?;
+            Ok(serde_json::json!(results))
+        }
+        "memory.add" => {
+            let content = string_param(&params, "content")?;
+            let tenant = crate::server::memory_tenant(&state.settings, caller);
+            let memory = state.orchestrator.read().await.memory();
+            memory.add_tenant_memory(&content, None, tenant.as_deref(), None).await
+                .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+            Ok(Value::Bool(true))
+        }
+        agent_name => call_agent(state, caller, agent_name, params).await,
+    }
+}
+
+async fn call_agent(state: &AppState, caller: Option<&Claims>, agent_name: &str, input: Value) -> Result<Value, RpcError> {
+    let orchestrator = state.orchestrator.read().await;
+    if !orchestrator.can_dispatch(agent_name).await {
+        return Err(RpcError::new(METHOD_NOT_FOUND, format!("Method not found: {}", agent_name)));
+    }
+
+    let (resp_tx, mut resp_rx) = tokio::sync::mpsc::channel(1);
+    let task = (agent_name.to_string(), input, resp_tx);
+    let caller = crate::server::task_caller(&state.settings, caller);
+    orchestrator.dispatch_as(&caller, task, TaskPriority::Normal, tokio_util::sync::CancellationToken::new()).await
+        .map_err(dispatch_error)?;
+
+    match resp_rx.recv().await {
+        Some(Ok(Value::String(output))) => Ok(Value::String(crate::server::redact_output(state, output))),
+        Some(Ok(output)) => Ok(Value::String(crate::server::redact_output(state, output.to_string()))),
+        Some(Err(e)) => {
+            let code = match e {
+                OrchestratorError::InvalidInput(_) => INVALID_PARAMS,
+                OrchestratorError::QuotaExceeded(_) => QUOTA_EXCEEDED,
+                _ => AGENT_ERROR,
+            };
+            Err(RpcError::new(code, crate::server::redact_output(state, e.to_string())))
+        }
+        None => Err(RpcError::new(INTERNAL_ERROR, "Task response channel closed unexpectedly")),
+    }
+}
+
+fn string_param(params: &Value, name: &str) -> Result<String, RpcError> {
+    params.get(name)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("Missing string param '{}'", name)))
+}
+
+fn internal_error(context: &str, e: anyhow::Error) -> RpcError {
+    error!("{}: {}", context, e);
+    RpcError::new(INTERNAL_ERROR, context)
+}
+
+/// Inputs refused by the input guard are the caller's fault, like a 400 on `/execute`
+fn dispatch_error(e: anyhow::Error) -> RpcError {
+    match e.downcast_ref::<InputLimitError>() {
+        Some(limit) => RpcError::new(INVALID_PARAMS, limit.to_string()),
+        None => internal_error("Failed to dispatch task", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_shape() {
+        let ok = serde_json::to_value(RpcResponse::success(serde_json::json!(1), serde_json::json!("x"))).unwrap();
+        assert_eq!(ok, serde_json::json!({ "jsonrpc": "2.0", "result": "x", "id": 1 }));
+
+        let err = serde_json::to_value(RpcResponse::failure(
+            Value::Null,
+            RpcError::new(METHOD_NOT_FOUND, "Method not found: nope"),
+        )).unwrap();
+        assert_eq!(err, serde_json::json!({
+            "jsonrpc": "2.0",
+            "error": { "code": -32601, "message": "Method not found: nope" },
+            "id": null
+        }));
+    }
+
+    #[test]
+    fn test_notification_has_no_id() {
+        let request: RpcRequest = serde_json::from_value(serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "echo"
+        })).unwrap();
+        assert!(request.id.is_none());
+        assert_eq!(request.params, Value::Null);
+
+        let request: RpcRequest = serde_json::from_value(serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "echo",
+            "id": null
+        })).unwrap();
+        assert_eq!(request.id, Some(Value::Null));
+
+        assert!(string_param(&serde_json::json!({ "query": 3 }), "query").is_err());
+    }
+
+    #[test]
+    fn test_dispatch_error_codes() {
+        let rejected = dispatch_error(InputLimitError {
+            agent: "echo".to_string(),
+            reason: "too deep".to_string(),
+        }.into());
+        assert_eq!(rejected.code, INVALID_PARAMS);
+        assert_eq!(rejected.message, "Input for agent 'echo' rejected: too deep");
+
+        let failed = dispatch_error(anyhow::anyhow!("scheduler closed"));
+        assert_eq!(failed.code, INTERNAL_ERROR);
+        assert_eq!(failed.message, "Failed to dispatch task");
+    }
+}
@@ -0,0 +1,286 @@
+//! Priority-ordered admission of tasks into the orchestrator.
+//!
+//! [`PriorityScheduler`] hands out a fixed number of execution permits. When
+//! all are in use, callers queue by [`TaskPriority`] (first come, first served
+//! within a priority) and a released permit goes straight to the
+//! highest-priority waiter, so bulk work cannot starve interactive requests.
+//! The number of permits can be changed while tasks run with
+//! [`PriorityScheduler::resize`].
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use tokio::sync::oneshot;
+
+use crate::mesh::TaskPriority;
+
+/// Returned when the wait queue is at capacity
+#[derive(Debug)]
+pub struct QueueFullError;
+
+impl fmt::Display for QueueFullError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Task queue full - too many concurrent tasks")
+    }
+}
+
+impl std::error::Error for QueueFullError {}
+
+type WaiterKey = (Reverse<TaskPriority>, u64);
+
+struct State {
+    /// Concurrency limit
+    permits: usize,
+    available: usize,
+    /// Permits still held after the limit was lowered, retired as they return
+    excess: usize,
+    next_seq: u64,
+    waiting: BTreeMap<WaiterKey, oneshot::Sender<()>>,
+}
+
+impl State {
+    /// Give a freed permit to the first live waiter, or return it to the pool
+    fn release(&mut self) {
+        if self.excess > 0 {
+            self.excess -= 1;
+            return;
+        }
+        while let Some((_, waiter)) = self.waiting.pop_first() {
+            if waiter.send(()).is_ok() {
+                return;
+            }
+        }
+        self.available += 1;
+    }
+}
+
+/// Permit pool that serves queued callers in priority order
+pub struct PriorityScheduler {
+    state: Arc<Mutex<State>>,
+    queue_capacity: usize,
+}
+
+/// Held while a task runs; dropping it admits the next queued task
+pub struct SchedulerPermit {
+    state: Arc<Mutex<State>>,
+}
+
+impl Drop for SchedulerPermit {
+    fn drop(&mut self) {
+        lock(&self.state).release();
+    }
+}
+
+/// Removes an abandoned waiter from the queue, passing on a permit it was
+/// granted but never picked up
+struct Waiter {
+    state: Arc<Mutex<State>>,
+    key: WaiterKey,
+    rx: oneshot::Receiver<()>,
+    granted: bool,
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        if self.granted {
+            return;
+        }
+        let mut state = lock(&self.state);
+        if state.waiting.remove(&self.key).is_none() && self.rx.try_recv().is_ok() {
+            state.release();
+        }
+    }
+}
+
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl PriorityScheduler {
+    /// Allow `permits` concurrent tasks and up to `queue_capacity` waiting ones
+    pub fn new(permits: usize, queue_capacity: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                permits,
+                available: permits,
+                excess: 0,
+                next_seq: 0,
+                waiting: BTreeMap::new(),
+            })),
+            queue_capacity,
+        }
+    }
+
+    /// Current concurrency limit
+    pub fn permits(&self) -> usize {
+        lock(&self.state).permits
+    }
+
+    /// Change the concurrency limit. Raising it admits queued tasks at once;
+    /// lowering it lets running tasks finish and retires their permits as
+    /// they are released.
+    pub fn resize(&self, permits: usize) {
+        let mut state = lock(&self.state);
+        if permits >= state.permits {
+            let mut added = permits - state.permits;
+            let retired = added.min(state.excess);
+            state.excess -= retired;
+            added -= retired;
+            state.permits = permits;
+            for _ in 0..added {
+                state.release();
+            }
+        } else {
+            let removed = state.permits - permits;
+            let idle = removed.min(state.available);
+            state.available -= idle;
+            state.excess += removed - idle;
+            state.permits = permits;
+        }
+    }
+
+    /// Wait for a permit, queueing behind higher-priority and earlier
+    /// same-priority callers. Fails with [`QueueFullError`] if the queue is full.
+    pub async fn acquire(&self, priority: TaskPriority) -> Result<SchedulerPermit, QueueFullError> {
+        let mut waiter = {
+            let mut state = lock(&self.state);
+            if state.available > 0 && state.waiting.is_empty() {
+                state.available -= 1;
+                return Ok(SchedulerPermit { state: self.state.clone() });
+            }
+            if state.waiting.len() >= self.queue_capacity {
+                return Err(QueueFullError);
+            }
+
+            let key = (Reverse(priority), state.next_seq);
+            state.next_seq += 1;
+            let (tx, rx) = oneshot::channel();
+            state.waiting.insert(key, tx);
+            Waiter { state: self.state.clone(), key, rx, granted: false }
+        };
+
+        // The sender lives in the queue, which outlives every waiter
+        let _ = (&mut waiter.rx).await;
+        waiter.granted = true;
+        Ok(SchedulerPermit { state: self.state.clone() })
+    }
+
+    /// Take a permit only if one is free and nobody is queued, for optional
+    /// work that should never delay real tasks
+    pub fn try_acquire(&self) -> Option<SchedulerPermit> {
+        let mut state = lock(&self.state);
+        if state.available > 0 && state.waiting.is_empty() {
+            state.available -= 1;
+            Some(SchedulerPermit { state: self.state.clone() })
+        } else {
+            None
+        }
+    }
+
+    /// Whether no task holds a permit or waits for one
+    pub fn is_idle(&self) -> bool {
+        let state = lock(&self.state);
+        state.available == state.permits && state.excess == 0 && state.waiting.is_empty()
+    }
+
+    /// Number of queued tasks per priority, including empty priorities
+    pub fn queue_depths(&self) -> HashMap<TaskPriority, usize> {
+        let state = lock(&self.state);
+        let mut depths: HashMap<TaskPriority, usize> = [
+            TaskPriority::Low,
+            TaskPriority::Normal,
+            TaskPriority::High,
+            TaskPriority::Critical,
+        ]
+        .into_iter()
+        .map(|priority| (priority, 0))
+        .collect();
+        for (Reverse(priority), _) in state.waiting.keys() {
+            *depths.entry(*priority).or_default() += 1;
+        }
+        depths
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_higher_priority_admitted_first() {
+        let scheduler = Arc::new(PriorityScheduler::new(1, 10));
+        let running = scheduler.acquire(TaskPriority::Normal).await.unwrap();
+
+        let (order_tx, mut order_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut handles = Vec::new();
+        for (name, priority) in [("bulk", TaskPriority::Low), ("batch", TaskPriority::Normal), ("interactive", TaskPriority::High)] {
+            let scheduler = scheduler.clone();
+            let order_tx = order_tx.clone();
+            handles.push(tokio::spawn(async move {
+                let _permit = scheduler.acquire(priority).await.unwrap();
+                order_tx.send(name).unwrap();
+            }));
+            // Queue in a fixed order
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let depths = scheduler.queue_depths();
+        assert_eq!((depths[&TaskPriority::Low], depths[&TaskPriority::High], depths[&TaskPriority::Critical]), (1, 1, 0));
+
+        drop(running);
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        let order: Vec<_> = std::iter::from_fn(|| order_rx.try_recv().ok()).collect();
+        assert_eq!(order, vec!["interactive", "batch", "bulk"]);
+    }
+
+    #[tokio::test]
+    async fn test_queue_capacity_and_abandoned_waiters() {
+        let scheduler = PriorityScheduler::new(1, 1);
+        let running = scheduler.acquire(TaskPriority::Normal).await.unwrap();
+
+        // A waiter that gives up leaves the queue
+        let abandoned = tokio::time::timeout(Duration::from_millis(10), scheduler.acquire(TaskPriority::High)).await;
+        assert!(abandoned.is_err());
+        assert_eq!(scheduler.queue_depths()[&TaskPriority::High], 0);
+
+        let queued = scheduler.acquire(TaskPriority::Low);
+        tokio::pin!(queued);
+        assert!(futures::poll!(queued.as_mut()).is_pending());
+        assert!(scheduler.acquire(TaskPriority::Critical).await.is_err());
+
+        drop(running);
+        let permit = queued.await.unwrap();
+        drop(permit);
+        // The permit returns to the pool once nobody is waiting
+        drop(scheduler.acquire(TaskPriority::Low).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_resize_admits_and_retires_permits() {
+        let scheduler = PriorityScheduler::new(1, 10);
+        let first = scheduler.acquire(TaskPriority::Normal).await.unwrap();
+
+        let queued = scheduler.acquire(TaskPriority::Normal);
+        tokio::pin!(queued);
+        assert!(futures::poll!(queued.as_mut()).is_pending());
+
+        // Raising the limit admits the waiter without any permit being released
+        scheduler.resize(2);
+        let second = queued.await.unwrap();
+        assert!(scheduler.try_acquire().is_none());
+
+        // Lowering it below the running count retires permits as they return
+        scheduler.resize(1);
+        drop(first);
+        assert!(scheduler.try_acquire().is_none());
+        drop(second);
+        assert_eq!(scheduler.permits(), 1);
+        assert!(scheduler.is_idle());
+        assert!(scheduler.try_acquire().is_some());
+    }
+}
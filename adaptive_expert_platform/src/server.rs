@@ -2,31 +2,59 @@
 
 use anyhow::Result;
 use axum::{
-    extract::{Path, State},
-    http::{StatusCode, HeaderMap},
+    extract::{ConnectInfo, DefaultBodyLimit, Extension, Multipart, Path, Query, State},
+    http::{header, StatusCode, HeaderMap},
     middleware,
-    response::{Json, IntoResponse},
-    routing::{get, post, delete},
+    response::{sse::{Event, KeepAlive, Sse}, Json, IntoResponse, Response},
+    routing::{get, post, put, delete},
     Router,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{info, warn, error, instrument};
 
 use crate::{
-    agent::{Agent, HashEmbeddingAgent, LengthRerankAgent},
-    auth::{AuthManager, Claims, LoginRequest, LoginResponse, auth_middleware},
+    agent::{Agent, HashEmbeddingAgent, InvalidInputError, LengthRerankAgent, ModelReloadReport, UnknownAgentError},
+    audit::{AuditEntry, AuditEvent, AuditLog, AuditQuery},
+    auth::{
+        AuthManager, AuthUnavailableError, Authorizer, Claims, LoginRequest, LoginResponse,
+        auth_middleware, authorize_middleware, create_authorizer,
+    },
     middleware::{
         create_cors_layer, create_rate_limiter, create_body_limit_layer,
-        rate_limit_middleware, security_headers_middleware, security_logging_middleware
+        rate_limit_middleware, response_envelope_middleware, security_headers_middleware,
+        security_logging_middleware
     },
-    orchestrator::Orchestrator,
-    settings::Settings,
-    memory::{Memory, EmbeddingCache, redis_store::{InMemoryEmbeddingCache}},
-    monitoring::MonitoringSystem,
+    config_reload::{ConfigReloader, ReloadReport},
+    error::OrchestratorError,
+    orchestrator::{AgentReadiness, Caller, Orchestrator, ReadinessReport, WebSocketAgentHandler},
+    settings::{BindTarget, Settings},
+    shutdown::{ShutdownCoordinator, ShutdownPhase},
+    trace::{TraceEntry, TraceQuery},
+    lifecycle::ScalePlan,
+    mesh::TaskPriority,
+    input_guard::InputLimitError,
+    plugin::{PluginInventory, PluginLoadReport, PluginManager},
+    quota::{Quota, UsageReport},
+    memory::{Memory, MemoryFragment, PrefetchReport, RepairReport, SearchExplanation, EmbeddingCache, EmbeddingTimeoutError, SearchStrategy, StreamingChunker, chunker_by_name, redis_store::{InMemoryEmbeddingCache}},
+    monitoring::{MonitoringSystem, SnapshotFormat},
+    redaction::OutputRedactor,
+    idempotency::IdempotencyStore,
+    websocket::{WebSocketConnection, WebSocketServer, WebSocketStats},
 };
 
+/// Header carrying the client-supplied idempotency key for `/execute`
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Output chunks buffered per `/execute` SSE stream before the agent waits
+const EXECUTE_STREAM_BUFFER: usize = 32;
+
+/// Path served by presigned memory export URLs
+const MEMORY_EXPORT_DOWNLOAD_PATH: &str = "/memory/export/download";
+
 #[cfg(feature = "with-redis")]
 use crate::memory::redis_store::RedisCache;
 
@@ -35,10 +63,18 @@ use crate::memory::redis_store::RedisCache;
 pub struct AppState {
     pub orchestrator: Arc<RwLock<Orchestrator>>,
     pub auth_manager: Arc<AuthManager>,
-    pub rate_limiter: Arc<crate::middleware::AppRateLimiter>,
+    pub rate_limiter: Arc<crate::middleware::SharedRateLimiter>,
+    /// Startup settings; the hot-reloadable ones in effect live with `config`
     pub settings: Settings,
+    pub config: Arc<ConfigReloader>,
     pub start_time: std::time::Instant,
     pub monitoring: Arc<MonitoringSystem>,
+    pub output_redactor: Option<Arc<OutputRedactor>>,
+    pub idempotency: Arc<IdempotencyStore>,
+    pub authorizer: Arc<dyn Authorizer>,
+    pub audit: Arc<AuditLog>,
+    pub websocket: Arc<WebSocketServer>,
+    pub plugins: Arc<PluginManager>,
 }
 
 /// Health check response
@@ -56,11 +92,16 @@ struct HealthResponse {
 struct RegisterAgentRequest {
     name: String,
     agent_type: String,
+    /// Partial config merged over the agent type's defaults
+    #[serde(default)]
     config: serde_json::Value,
+    /// Replace an agent already registered under `name` instead of failing
+    #[serde(default)]
+    replace: bool,
 }
 
 /// Task execution request
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 struct ExecuteTaskRequest {
     agent_name: String,
     input: serde_json::Value,
@@ -68,7 +109,7 @@ struct ExecuteTaskRequest {
 }
 
 /// Task execution response
-#[derive(Serialize)]
+#[derive(Clone, Serialize, Deserialize)]
 struct ExecuteTaskResponse {
     success: bool,
     result: Option<String>,
@@ -81,6 +122,7 @@ struct ExecuteTaskResponse {
 struct AgentInfo {
     name: String,
     agent_type: String,
+    capabilities: Vec<String>,
     status: String,
 }
 
@@ -100,28 +142,70 @@ pub fn create_router(state: AppState) -> Router {
     // Create body size limit layer
     let body_limit_layer = create_body_limit_layer(state.settings.security.max_request_size_mb);
 
+    // Uploads are streamed into memory, so they get their own, larger limit
+    let upload_limit_mb = state.settings.memory.max_upload_size_mb;
+    let upload_routes = Router::new()
+        .route("/memory/upload", post(upload_memory))
+        .layer(DefaultBodyLimit::max(upload_limit_mb * 1024 * 1024))
+        .layer(create_body_limit_layer(upload_limit_mb));
+
     // Public routes (no authentication required)
     let public_routes = Router::new()
         .route("/health", get(health_check))
-        .route("/auth/login", post(login));
-
-    // Admin-only routes
+        .route("/readyz", get(readiness_check))
+        .route("/version", get(version_info))
+        .route("/openapi.json", get(openapi_spec))
+        .route("/auth/login", post(login))
+        .route(MEMORY_EXPORT_DOWNLOAD_PATH, get(download_memory_export))
+        .layer(body_limit_layer.clone());
+
+    // Admin-only routes (enforced by the default role policy)
     let admin_routes = Router::new()
         .route("/agents", post(register_agent))
         .route("/agents/:name", delete(remove_agent))
+        .route("/agents/:name/model", post(reload_agent_model))
+        .route("/agents/:name/disable", post(disable_agent))
+        .route("/agents/:name/enable", post(enable_agent))
         .route("/auth/users", post(create_user))
-        .route_layer(middleware::from_fn(crate::auth::require_role("admin")));
+        .route("/auth/users/:name/quota", put(set_user_quota).delete(clear_user_quota))
+        .route("/audit", get(query_audit_log))
+        .route("/trace", get(query_trace_log))
+        .route("/trace/:id/replay", post(replay_trace))
+        .route("/deployments/:name/scale", post(scale_deployment))
+        .route("/ws/connections", get(list_ws_connections))
+        .route("/ws/stats", get(ws_stats))
+        .route("/plugins", get(list_plugins))
+        .route("/plugins/reload", post(reload_plugins))
+        .route("/memory/repair", post(repair_memory))
+        .route("/memory/prefetch", post(prefetch_embeddings))
+        .route("/admin/reload-config", post(reload_config))
+        .route("/admin/metrics/snapshot", post(write_metrics_snapshot));
+    #[cfg(feature = "with-julia")]
+    let admin_routes = admin_routes.route("/admin/julia/gc", post(collect_julia_garbage));
 
     // General protected routes
     let protected_routes = Router::new()
         .route("/agents", get(list_agents))
+        .route("/agents/schema", get(list_agent_schemas))
+        .route("/capabilities", get(list_capabilities))
         .route("/execute", post(execute_task))
+        .route("/rpc", post(crate::rpc::handle_rpc))
         .route("/memory/stats", get(memory_stats))
         .route("/memory/search", post(search_memory))
+        .route("/memory/search/explain", post(explain_memory_search))
         .route("/memory/add", post(add_memory))
+        .route("/memory/export", get(export_memory))
+        .route("/memory/export/url", post(create_memory_export_url))
         .route("/metrics", get(get_metrics))
         .route("/auth/password", post(change_password))
+        .route("/auth/usage", get(get_usage))
         .merge(admin_routes) // Merge admin routes under the main auth middleware
+        .layer(body_limit_layer)
+        .merge(upload_routes)
+        .route_layer(middleware::from_fn_with_state(
+            state.authorizer.clone(),
+            authorize_middleware
+        ))
         .layer(middleware::from_fn_with_state(
             state.auth_manager.clone(),
             auth_middleware
@@ -136,10 +220,13 @@ pub fn create_router(state: AppState) -> Router {
             state.rate_limiter.clone(),
             rate_limit_middleware
         ))
+        .layer(middleware::from_fn_with_state(
+            state.settings.server.response_envelope,
+            response_envelope_middleware
+        ))
         .layer(middleware::from_fn(security_headers_middleware))
         .layer(middleware::from_fn(security_logging_middleware))
-        .layer(cors_layer)
-        .layer(body_limit_layer);
+        .layer(cors_layer);
 
     app
 }
@@ -165,32 +252,173 @@ async fn health_check(
     Ok(Json(response))
 }
 
+/// Readiness probe: 503 until every required agent has warmed up, with the
+/// warm-up state of each agent in the body either way
+async fn readiness_check(State(state): State<AppState>) -> (StatusCode, Json<ReadinessReport>) {
+    let report = state.orchestrator.read().await.readiness();
+    let status = if report.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(report))
+}
+
+/// Build provenance of the running binary
+async fn version_info() -> Json<crate::build_info::BuildInfo> {
+    Json(crate::build_info::BuildInfo::current())
+}
+
 /// List all registered agents
 #[instrument(skip(state))]
 async fn list_agents(
     State(state): State<AppState>,
 ) -> Result<Json<Vec<AgentInfo>>, StatusCode> {
     let orchestrator = state.orchestrator.read().await;
-    let agents = orchestrator.list_agents().await;
+    let agents = orchestrator.list_agent_capabilities().await;
 
     let agent_infos: Vec<AgentInfo> = agents
         .into_iter()
-        .map(|(name, agent_type)| AgentInfo {
+        .map(|(name, agent_type, capabilities)| AgentInfo {
+            status: agent_status(&orchestrator, &name).to_string(),
             name,
             agent_type,
-            status: "active".to_string(),
+            capabilities,
         })
         .collect();
 
     Ok(Json(agent_infos))
 }
 
+/// List capabilities with the agents that provide each one
+#[instrument(skip(state))]
+async fn list_capabilities(
+    State(state): State<AppState>,
+) -> Result<Json<BTreeMap<String, Vec<String>>>, StatusCode> {
+    let orchestrator = state.orchestrator.read().await;
+    Ok(Json(orchestrator.capability_index().await))
+}
+
+/// Describe each agent's capabilities and input schema
+#[instrument(skip(state))]
+async fn list_agent_schemas(
+    State(state): State<AppState>,
+) -> Json<Vec<crate::agent::AgentSchema>> {
+    let orchestrator = state.orchestrator.read().await;
+    Json(orchestrator.agent_schemas().await)
+}
+
+/// List active WebSocket sessions with credentials redacted
+#[instrument(skip(state))]
+async fn list_ws_connections(
+    State(state): State<AppState>,
+) -> Json<Vec<WebSocketConnection>> {
+    let connections = state.websocket.get_connections().await;
+    Json(connections.iter().map(WebSocketConnection::redacted).collect())
+}
+
+/// WebSocket server statistics
+#[instrument(skip(state))]
+async fn ws_stats(
+    State(state): State<AppState>,
+) -> Json<WebSocketStats> {
+    Json(state.websocket.get_stats().await)
+}
+
+/// List loaded native plugins and the ones that failed to load
+async fn list_plugins(State(state): State<AppState>) -> Json<PluginInventory> {
+    Json(state.plugins.inventory())
+}
+
+/// Load any new native plugins from the plugin directory
+#[instrument(skip(state, claims, connect_info))]
+async fn reload_plugins(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+) -> Result<Json<PluginLoadReport>, StatusCode> {
+    let report = state.orchestrator.read().await.reload_plugins().await.map_err(|e| {
+        error!("Plugin reload failed: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    record_audit(&state, &claims.sub, "plugins_reload", None, connect_info);
+    Ok(Json(report))
+}
+
+/// Outcome of `POST /admin/reload-config`
+#[derive(Debug, Serialize)]
+struct ConfigReloadResponse {
+    /// Agents removed from the registry, in name order
+    unregistered: Vec<String>,
+    plugins: PluginLoadReport,
+    /// Changed settings, by whether they are now in effect
+    settings: ReloadReport,
+    /// Steps that failed after the registry had already changed; everything
+    /// reported above stays in effect
+    errors: Vec<String>,
+}
+
+/// Re-read and validate the configuration, apply its hot-reloadable settings
+/// and reload plugin agents from the plugin directory without a restart
+/// (admin only). Agents registered through `POST /agents` are kept.
+///
+/// Settings sizing long-lived subsystems (ports, pools, storage) still take
+/// effect only on restart; see [`crate::config_reload`]. An invalid
+/// configuration leaves the registry and settings as they are. Once plugin
+/// agents have been unregistered the reload is not rolled back: later
+/// failures are listed in `errors` next to what did change.
+#[instrument(skip(state, claims, connect_info))]
+async fn reload_config(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+) -> Result<Json<ConfigReloadResponse>, StatusCode> {
+    let settings = tokio::task::spawn_blocking(Settings::load)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|e| {
+            warn!("Rejected configuration reload: {:#}", e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        })?;
+    let settings_report = state.config.apply(&settings).await.map_err(|e| {
+        error!("Applying reloaded settings failed: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let mut errors = Vec::new();
+    let (unregistered, plugins) = {
+        let orchestrator = state.orchestrator.read().await;
+        let unregistered = orchestrator.unregister_plugin_agents().await;
+        let plugins = orchestrator.reload_plugins().await.unwrap_or_else(|e| {
+            error!("Plugin reload during configuration reload failed: {}", e);
+            errors.push(format!("Plugin reload failed: {}", e));
+            PluginLoadReport::default()
+        });
+        // Reloaded plugin agents were warmed up; critical ones must have succeeded
+        let readiness = orchestrator.readiness();
+        for name in &settings.orchestrator.critical_agents {
+            if let Some(AgentReadiness::Failed { error }) = readiness.agents.get(name) {
+                error!("Configuration reload: critical agent '{}' failed to warm up: {}", name, error);
+                errors.push(format!("Critical agent '{}' failed to warm up: {}", name, error));
+            }
+        }
+        (unregistered, plugins)
+    };
+
+    record_audit(&state, &claims.sub, "config_reload", None, connect_info);
+    Ok(Json(ConfigReloadResponse { unregistered, plugins, settings: settings_report, errors }))
+}
+
+/// OpenAPI 3 document describing the REST API
+async fn openapi_spec() -> Json<serde_json::Value> {
+    Json(crate::openapi::openapi_document())
+}
+
 use crate::agent::AgentFactory;
 
 /// Register a new agent
 #[instrument(skip(state))]
 async fn register_agent(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     Json(request): Json<RegisterAgentRequest>,
 ) -> Result<StatusCode, StatusCode> {
     let agent = AgentFactory::create_agent(&request.agent_type, request.config, &state.settings)
@@ -200,11 +428,28 @@ async fn register_agent(
         })?;
 
     let mut orchestrator = state.orchestrator.write().await;
-    orchestrator.register_agent(request.name.clone(), agent).await.map_err(|e| {
+    let registered = if request.replace {
+        orchestrator.replace_agent(request.name.clone(), agent).await
+    } else {
+        orchestrator.register_agent(request.name.clone(), agent).await
+    };
+    registered.map_err(|e| {
+        if e.downcast_ref::<crate::agent::DuplicateAgentError>().is_some() {
+            warn!("Refused to register agent '{}': {}", request.name, e);
+            return StatusCode::CONFLICT;
+        }
         error!("Failed to register agent '{}': {}", request.name, e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
+    drop(orchestrator);
+
+    match state.orchestrator.read().await.warm_up_agent(&request.name).await {
+        Ok(duration) => info!("Agent '{}' warmed up in {}ms", request.name, duration.as_millis()),
+        Err(e) => warn!("Agent '{}' failed to warm up: {}", request.name, e),
+    }
+
+    record_audit(&state, &claims.sub, "agent_register", Some(&request.name), connect_info);
     info!("Registered agent: {}", request.name);
     Ok(StatusCode::CREATED)
 }
@@ -213,11 +458,14 @@ async fn register_agent(
 #[instrument(skip(state))]
 async fn remove_agent(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     Path(name): Path<String>,
 ) -> Result<StatusCode, StatusCode> {
     let orchestrator = state.orchestrator.read().await;
     match orchestrator.remove_agent(&name).await {
         Ok(_) => {
+            record_audit(&state, &claims.sub, "agent_remove", Some(&name), connect_info);
             info!("Removed agent: {}", name);
             Ok(StatusCode::NO_CONTENT)
         }
@@ -228,42 +476,294 @@ async fn remove_agent(
     }
 }
 
-/// Execute a task with an agent
-#[instrument(skip(state))]
+/// `"maintenance"` for agents taken offline, `"active"` otherwise
+fn agent_status(orchestrator: &Orchestrator, name: &str) -> &'static str {
+    if orchestrator.is_in_maintenance(name) {
+        "maintenance"
+    } else {
+        "active"
+    }
+}
+
+/// Result of `POST /agents/:name/disable` and `/enable`
+#[derive(Serialize)]
+struct AgentStatusResponse {
+    name: String,
+    status: &'static str,
+    /// False when the agent was already in the requested state
+    changed: bool,
+}
+
+/// Put an agent in maintenance: it stays registered and listed, but calls
+/// to it fail with 503 until it is enabled again
+#[instrument(skip(state, claims, connect_info))]
+async fn disable_agent(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Path(name): Path<String>,
+) -> Result<Json<AgentStatusResponse>, StatusCode> {
+    set_agent_maintenance(&state, &claims, connect_info, name, true).await
+}
+
+/// Bring an agent out of maintenance
+#[instrument(skip(state, claims, connect_info))]
+async fn enable_agent(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Path(name): Path<String>,
+) -> Result<Json<AgentStatusResponse>, StatusCode> {
+    set_agent_maintenance(&state, &claims, connect_info, name, false).await
+}
+
+async fn set_agent_maintenance(
+    state: &AppState,
+    claims: &Claims,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    name: String,
+    in_maintenance: bool,
+) -> Result<Json<AgentStatusResponse>, StatusCode> {
+    let orchestrator = state.orchestrator.read().await;
+    let changed = orchestrator.set_agent_maintenance(&name, in_maintenance).await.map_err(|e| {
+        warn!("Cannot change maintenance state of '{}': {}", name, e);
+        StatusCode::NOT_FOUND
+    })?;
+
+    let action = if in_maintenance { "agent_disable" } else { "agent_enable" };
+    record_audit(state, &claims.sub, action, Some(&name), connect_info);
+    Ok(Json(AgentStatusResponse {
+        status: agent_status(&orchestrator, &name),
+        name,
+        changed,
+    }))
+}
+
+/// Body of `POST /agents/:name/model`
+#[derive(Debug, Deserialize)]
+struct ReloadModelRequest {
+    path: std::path::PathBuf,
+}
+
+/// Swap the model file behind an LLM agent without unregistering it
+#[instrument(skip(state, claims, connect_info))]
+async fn reload_agent_model(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Path(name): Path<String>,
+    Json(request): Json<ReloadModelRequest>,
+) -> Result<Json<ModelReloadReport>, StatusCode> {
+    let result = state.orchestrator.read().await.reload_agent_model(&name, &request.path).await;
+    let report = result.map_err(|e| {
+        if e.is::<UnknownAgentError>() {
+            return StatusCode::NOT_FOUND;
+        }
+        if e.is::<InvalidInputError>() {
+            warn!("Rejected model reload for '{}': {}", name, e);
+            return StatusCode::BAD_REQUEST;
+        }
+        // The previous model keeps serving
+        error!("Model reload for '{}' failed: {:#}", name, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let target = format!("{}:{}", name, report.path.display());
+    record_audit(&state, &claims.sub, "agent_model_reload", Some(&target), connect_info);
+    Ok(Json(report))
+}
+
+/// Execute a task with an agent.
+///
+/// If an `Idempotency-Key` header is supplied, the completed response is cached
+/// under that key and replayed for retries instead of running the agent again.
+///
+/// Clients sending `Accept: text/event-stream` get the output as an SSE stream
+/// instead (see [`stream_task`]); streamed responses are never cached.
+#[instrument(skip(state, claims, headers))]
 async fn execute_task(
     State(state): State<AppState>,
+    claims: Option<Extension<Claims>>,
+    headers: HeaderMap,
     Json(request): Json<ExecuteTaskRequest>,
-) -> Result<Json<ExecuteTaskResponse>, StatusCode> {
+) -> Result<Response, StatusCode> {
+    let caller = task_caller(&state.settings, claims.as_ref().map(|Extension(c)| c));
+    if accepts_event_stream(&headers) {
+        return stream_task(&state, request, caller).await;
+    }
+
+    let key = match headers.get(IDEMPOTENCY_KEY_HEADER) {
+        Some(value) => {
+            let key = value.to_str().map_err(|_| StatusCode::BAD_REQUEST)?;
+            IdempotencyStore::validate_key(key).map_err(|e| {
+                warn!("Rejected idempotency key: {}", e);
+                StatusCode::BAD_REQUEST
+            })?;
+            key.to_string()
+        }
+        None => return run_task(&state, request, &caller).await.map(|r| Json(r).into_response()),
+    };
+
+    // Scope keys per user so clients cannot replay each other's results
+    let scoped_key = format!("{}:{}", caller.user.as_deref().unwrap_or_default(), key);
+    let fingerprint = IdempotencyStore::fingerprint(&request)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Concurrent duplicates wait here for the first request to finish
+    let _guard = state.idempotency.lock(&scoped_key).await;
+
+    match state.idempotency.get::<ExecuteTaskResponse>(&scoped_key, &fingerprint).await {
+        Ok(Some(cached)) => {
+            info!("Replaying cached response for idempotency key {}", key);
+            return Ok(Json(cached).into_response());
+        }
+        Ok(None) => {}
+        Err(e) => {
+            warn!("Idempotency key {} conflict: {}", key, e);
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+    }
+
+    let response = run_task(&state, request, &caller).await?;
+    if let Err(e) = state.idempotency.put(&scoped_key, &fingerprint, response.clone()).await {
+        warn!("Failed to store idempotent response for key {}: {}", key, e);
+    }
+
+    Ok(Json(response).into_response())
+}
+
+/// Whether the client asked for a server-sent event stream
+fn accepts_event_stream(headers: &HeaderMap) -> bool {
+    headers.get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |accept| {
+            accept.split(',').any(|media| media.split(';').next().unwrap_or("").trim() == "text/event-stream")
+        })
+}
+
+/// Run a task in streaming mode, sending each output chunk as a `chunk` event
+/// followed by a terminal `done` or `error` event.
+///
+/// Dropping the response stream (the client disconnecting) cancels the agent.
+async fn stream_task(
+    state: &AppState,
+    request: ExecuteTaskRequest,
+    caller: Caller,
+) -> Result<Response, StatusCode> {
+    {
+        let orchestrator = state.orchestrator.read().await;
+        if !orchestrator.can_dispatch(&request.agent_name).await {
+            return Err(StatusCode::NOT_FOUND);
+        }
+        if orchestrator.is_in_maintenance(&request.agent_name) {
+            return Err(StatusCode::SERVICE_UNAVAILABLE);
+        }
+    }
+
+    let start_time = std::time::Instant::now();
+    let (chunk_tx, chunk_rx) = tokio::sync::mpsc::channel::<String>(EXECUTE_STREAM_BUFFER);
+    let cancel = tokio_util::sync::CancellationToken::new();
+
+    let orchestrator = state.orchestrator.clone();
+    let agent_cancel = cancel.clone();
+    // Admitted like `/execute`, ahead of batch work
+    let agent_task = tokio::spawn(async move {
+        orchestrator.read().await
+            .dispatch_stream_as(&caller, &request.agent_name, request.input, chunk_tx, TaskPriority::High, agent_cancel)
+            .await
+    });
+
+    let state = state.clone();
+    let initial = Some((chunk_rx, agent_task, cancel.drop_guard()));
+    let events = futures::stream::unfold(initial, move |stream_state| {
+        let state = state.clone();
+        async move {
+            let (mut chunks, agent_task, cancel_guard) = stream_state?;
+            if let Some(chunk) = chunks.recv().await {
+                let event = Event::default().event("chunk").data(redact_output(&state, chunk));
+                return Some((Ok::<_, std::convert::Infallible>(event), Some((chunks, agent_task, cancel_guard))));
+            }
+
+            let event = match agent_task.await {
+                Ok(Ok(())) => Event::default().event("done").data(serde_json::json!({
+                    "execution_time_ms": start_time.elapsed().as_millis() as u64,
+                }).to_string()),
+                Ok(Err(e)) => {
+                    let e = OrchestratorError::from(e);
+                    error!(kind = e.kind(), "Streaming task execution failed: {:#}", e);
+                    Event::default().event("error").data(serde_json::json!({
+                        "kind": e.kind(),
+                        "error": redact_output(&state, e.to_string()),
+                    }).to_string())
+                }
+                Err(e) => {
+                    error!("Streaming task panicked: {}", e);
+                    Event::default().event("error").data(serde_json::json!({
+                        "kind": "agent",
+                        "error": "Streaming task failed",
+                    }).to_string())
+                }
+            };
+            // The agent already finished; there is nothing left to cancel
+            cancel_guard.disarm();
+            Some((Ok(event), None))
+        }
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()).into_response())
+}
+
+/// Dispatch a task to its agent on behalf of `caller` and wait for the result
+async fn run_task(
+    state: &AppState,
+    request: ExecuteTaskRequest,
+    caller: &Caller,
+) -> Result<ExecuteTaskResponse, StatusCode> {
     let start_time = std::time::Instant::now();
     let orchestrator = state.orchestrator.read().await;
 
     let (resp_tx, mut resp_rx) = tokio::sync::mpsc::channel(1);
 
-    orchestrator.dispatch((
+    // Cancel the agent's work if the client disconnects and this future is dropped
+    let cancel = tokio_util::sync::CancellationToken::new();
+    let _cancel_on_drop = cancel.clone().drop_guard();
+
+    // Interactive requests are admitted ahead of batch work when the orchestrator is saturated
+    orchestrator.dispatch_as(caller, (
         request.agent_name.clone(),
         request.input,
         resp_tx,
-    )).await?;
+    ), TaskPriority::High, cancel).await.map_err(|e| {
+        if e.is::<InputLimitError>() {
+            warn!("{}", e);
+            return StatusCode::BAD_REQUEST;
+        }
+        error!("Failed to dispatch task: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
 
     let execution_time = start_time.elapsed().as_millis() as u64;
 
     match resp_rx.recv().await {
         Some(Ok(result)) => {
-            Ok(Json(ExecuteTaskResponse {
+            Ok(ExecuteTaskResponse {
                 success: true,
-                result: Some(result.to_string()),
+                result: Some(redact_output(state, result.to_string())),
                 error: None,
                 execution_time_ms: execution_time,
-            }))
+            })
         }
         Some(Err(e)) => {
-            error!("Task execution failed: {}", e);
-            Ok(Json(ExecuteTaskResponse {
+            error!(kind = e.kind(), "Task execution failed: {:#}", e);
+            if let Some(status) = task_error_status(&e) {
+                return Err(status);
+            }
+            Ok(ExecuteTaskResponse {
                 success: false,
                 result: None,
-                error: Some(e.to_string()),
+                error: Some(redact_output(state, e.to_string())),
                 execution_time_ms: execution_time,
-            }))
+            })
         }
         None => {
             error!("Task execution response channel closed unexpectedly");
@@ -272,6 +772,26 @@ async fn execute_task(
     }
 }
 
+/// Status for task failures that are not the agent's doing; agent errors
+/// (including rejected input) are reported in the response body instead
+fn task_error_status(e: &OrchestratorError) -> Option<StatusCode> {
+    match e {
+        OrchestratorError::UnknownAgent(_) => Some(StatusCode::NOT_FOUND),
+        OrchestratorError::Maintenance(_) | OrchestratorError::QueueFull(_) => Some(StatusCode::SERVICE_UNAVAILABLE),
+        OrchestratorError::QuotaExceeded(_) => Some(StatusCode::TOO_MANY_REQUESTS),
+        OrchestratorError::Timeout(_) => Some(StatusCode::GATEWAY_TIMEOUT),
+        OrchestratorError::InvalidInput(_) | OrchestratorError::Cancelled(_) | OrchestratorError::Agent(_) => None,
+    }
+}
+
+/// Apply the configured output redaction, if any, to agent output
+pub(crate) fn redact_output(state: &AppState, output: String) -> String {
+    match &state.output_redactor {
+        Some(redactor) => redactor.redact(&output),
+        None => output,
+    }
+}
+
 /// Get memory statistics
 #[instrument(skip(state))]
 async fn memory_stats(
@@ -287,44 +807,665 @@ async fn memory_stats(
     Ok(Json(stats))
 }
 
-/// Search memory
-#[instrument(skip(state))]
+/// Tenant whose memory fragments `claims` may see: the id of a `tenant:<id>`
+/// role, falling back to the username. `None`, meaning every fragment, when
+/// isolation is off or the caller is unauthenticated.
+pub(crate) fn memory_tenant(settings: &Settings, claims: Option<&Claims>) -> Option<String> {
+    if !settings.memory.tenant_isolation {
+        return None;
+    }
+    let claims = claims?;
+    let tenant = claims.roles.iter()
+        .find_map(|role| role.strip_prefix("tenant:"))
+        .unwrap_or(&claims.sub);
+    Some(tenant.to_string())
+}
+
+/// Who an agent call made with `claims` is for
+pub(crate) fn task_caller(settings: &Settings, claims: Option<&Claims>) -> Caller {
+    Caller {
+        user: claims.map(|claims| claims.sub.clone()),
+        tenant: memory_tenant(settings, claims),
+    }
+}
+
+/// Options shared by `/memory/search` and `/memory/search/explain`
+struct SearchParams {
+    query: String,
+    threshold: Option<f32>,
+    strategy: SearchStrategy,
+    half_life: Option<std::time::Duration>,
+    budget: Option<std::time::Duration>,
+}
+
+impl SearchParams {
+    /// Read the query and search options from a request body, rejecting
+    /// invalid values with 400
+    fn from_request(request: &serde_json::Value, memory: &Memory) -> Result<Self, StatusCode> {
+        let query = request.get("query")
+            .and_then(|v| v.as_str())
+            .ok_or(StatusCode::BAD_REQUEST)?
+            .to_string();
+
+        // Optional per-call override of the configured similarity threshold
+        let threshold = match request.get("similarity_threshold") {
+            None | Some(serde_json::Value::Null) => None,
+            Some(value) => {
+                let threshold = value.as_f64().ok_or(StatusCode::BAD_REQUEST)? as f32;
+                crate::memory::validate_similarity_threshold(threshold).map_err(|e| {
+                    warn!("Rejected memory search: {}", e);
+                    StatusCode::BAD_REQUEST
+                })?;
+                Some(threshold)
+            }
+        };
+
+        // Candidate ordering: "rerank" (default), "similarity", "mmr" or "hybrid"
+        let strategy = match request.get("strategy").and_then(|v| v.as_str()) {
+            None | Some("rerank") => SearchStrategy::Rerank,
+            Some("similarity") => SearchStrategy::Similarity,
+            Some("mmr") => {
+                let lambda = match request.get("mmr_lambda") {
+                    None | Some(serde_json::Value::Null) => 0.5,
+                    Some(value) => value.as_f64().ok_or(StatusCode::BAD_REQUEST)? as f32,
+                };
+                crate::memory::validate_mmr_lambda(lambda).map_err(|e| {
+                    warn!("Rejected memory search: {}", e);
+                    StatusCode::BAD_REQUEST
+                })?;
+                SearchStrategy::Mmr { lambda }
+            }
+            Some("hybrid") => {
+                let keyword_weight = match request.get("keyword_weight") {
+                    None | Some(serde_json::Value::Null) => crate::memory::DEFAULT_KEYWORD_WEIGHT,
+                    Some(value) => value.as_f64().ok_or(StatusCode::BAD_REQUEST)? as f32,
+                };
+                crate::memory::validate_keyword_weight(keyword_weight).map_err(|e| {
+                    warn!("Rejected memory search: {}", e);
+                    StatusCode::BAD_REQUEST
+                })?;
+                SearchStrategy::Hybrid { keyword_weight }
+            }
+            Some(other) => {
+                warn!("Rejected memory search: unknown strategy '{}'", other);
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        };
+
+        // Optional per-call time decay half-life; 0 turns off the configured default
+        let half_life = match request.get("recency_half_life_seconds") {
+            None | Some(serde_json::Value::Null) => memory.recency_half_life(),
+            Some(value) => {
+                let seconds = value.as_u64().ok_or(StatusCode::BAD_REQUEST)?;
+                (seconds > 0).then(|| std::time::Duration::from_secs(seconds))
+            }
+        };
+
+        // Optional per-call time limit; 0 turns off the configured default
+        let budget = match request.get("budget_ms") {
+            None | Some(serde_json::Value::Null) => memory.search_budget(),
+            Some(value) => {
+                let millis = value.as_u64().ok_or(StatusCode::BAD_REQUEST)?;
+                (millis > 0).then(|| std::time::Duration::from_millis(millis))
+            }
+        };
+
+        Ok(Self { query, threshold, strategy, half_life, budget })
+    }
+}
+
+/// Results returned by memory search
+const SEARCH_RESULT_LIMIT: usize = 10;
+
+/// Set to "true" on search responses cut short by the time budget
+const SEARCH_TRUNCATED_HEADER: &str = "x-search-truncated";
+
+/// Search memory.
+///
+/// When the search runs out of its time budget (`budget_ms`, or
+/// `memory.search_budget_ms`), the best matches found so far are returned
+/// unreranked, with an `x-search-truncated: true` header and no ETag.
+#[instrument(skip(state, claims))]
 async fn search_memory(
     State(state): State<AppState>,
+    claims: Option<Extension<Claims>>,
+    headers: HeaderMap,
     Json(request): Json<serde_json::Value>,
-) -> Result<Json<Vec<String>>, StatusCode> {
-    let query = request.get("query")
-        .and_then(|v| v.as_str())
-        .ok_or(StatusCode::BAD_REQUEST)?;
-
+) -> Result<axum::response::Response, StatusCode> {
     let memory = state.orchestrator.read().await.memory();
-    let results = memory.search_memory(query, 10).await
+    let SearchParams { query, threshold, strategy, half_life, budget } = SearchParams::from_request(&request, &memory)?;
+
+    // Read the version before searching so a concurrent write can only make
+    // the tag stale, never attach old results to a new version
+    let tenant = memory_tenant(&state.settings, claims.as_ref().map(|Extension(c)| c));
+    let etag = search_etag(&query, threshold, strategy, half_life, tenant.as_deref(), memory.fragment_version());
+    if if_none_match(&headers, &etag) {
+        return Ok((
+            StatusCode::NOT_MODIFIED,
+            [(header::ETAG, etag), (header::CACHE_CONTROL, "no-cache".to_string())],
+        ).into_response());
+    }
+
+    let search = memory.search_memory_with_budget(&query, SEARCH_RESULT_LIMIT, threshold, strategy, half_life, tenant.as_deref(), budget).await
         .map_err(|e| {
             error!("Memory search failed: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            memory_error_status(&e)
+        })?;
+    let results = search.results;
+
+    // Partial results depend on timing, so they are never revalidated
+    if search.truncated {
+        return Ok((
+            [(header::CACHE_CONTROL, "no-cache")],
+            [(SEARCH_TRUNCATED_HEADER, "true")],
+            Json(results),
+        ).into_response());
+    }
+
+    Ok((
+        [(header::ETAG, etag), (header::CACHE_CONTROL, "no-cache".to_string())],
+        Json(results),
+    ).into_response())
+}
+
+/// Characters of each fragment's content returned by `/memory/search/explain`
+/// unless the request asks for fewer
+const EXPLAIN_CONTENT_CHARS: usize = 500;
+
+/// Run a memory search and return, for each result, the fragment's source,
+/// tags and tenant with its similarity, keyword and rerank scores and the
+/// filters it passed. Takes the same options as `/memory/search`, plus
+/// `content_chars` to shorten the content shown (at most 500 characters).
+#[instrument(skip(state, claims))]
+async fn explain_memory_search(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<serde_json::Value>,
+) -> Result<Json<SearchExplanation>, StatusCode> {
+    let memory = state.orchestrator.read().await.memory();
+    let SearchParams { query, threshold, strategy, half_life, budget } = SearchParams::from_request(&request, &memory)?;
+    let content_chars = match request.get("content_chars") {
+        None | Some(serde_json::Value::Null) => EXPLAIN_CONTENT_CHARS,
+        Some(value) => (value.as_u64().ok_or(StatusCode::BAD_REQUEST)? as usize).min(EXPLAIN_CONTENT_CHARS),
+    };
+
+    let tenant = memory_tenant(&state.settings, Some(&claims));
+    let mut explanation = memory
+        .explain_search(&query, SEARCH_RESULT_LIMIT, threshold, strategy, half_life, tenant.as_deref(), budget)
+        .await
+        .map_err(|e| {
+            error!("Memory search explanation failed: {}", e);
+            memory_error_status(&e)
         })?;
+    for result in &mut explanation.results {
+        result.truncate_content(content_chars);
+    }
+    Ok(Json(explanation))
+}
 
-    Ok(Json(results))
+/// 504 when the embedding or reranker agent timed out, 500 otherwise
+fn memory_error_status(e: &anyhow::Error) -> StatusCode {
+    if e.is::<EmbeddingTimeoutError>() {
+        StatusCode::GATEWAY_TIMEOUT
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+/// Random per-process nonce mixed into ETags, since the fragment version
+/// restarts from zero with the process and would let old tags match new content
+fn etag_epoch() -> &'static str {
+    static EPOCH: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+    EPOCH.get_or_init(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Strong ETag for a search, valid until the fragment set changes or the
+/// process restarts
+fn search_etag(
+    query: &str,
+    threshold: Option<f32>,
+    strategy: SearchStrategy,
+    half_life: Option<std::time::Duration>,
+    tenant: Option<&str>,
+    version: u64,
+) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(etag_epoch().as_bytes());
+    hasher.update(query.as_bytes());
+    if let Some(threshold) = threshold {
+        hasher.update(&threshold.to_le_bytes());
+    }
+    hasher.update(format!("{:?}", strategy).as_bytes());
+    if let Some(half_life) = half_life {
+        hasher.update(&half_life.as_secs().to_le_bytes());
+    }
+    if let Some(tenant) = tenant {
+        hasher.update(tenant.as_bytes());
+    }
+    hasher.update(&version.to_le_bytes());
+    format!("\"{}\"", &hasher.finalize().to_hex()[..32])
+}
+
+/// Whether `If-None-Match` lists `etag` (or `*`)
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers.get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag)
 }
 
 /// Add content to memory
-#[instrument(skip(state))]
+#[instrument(skip(state, claims))]
 async fn add_memory(
     State(state): State<AppState>,
+    claims: Option<Extension<Claims>>,
     Json(request): Json<serde_json::Value>,
 ) -> Result<StatusCode, StatusCode> {
     let content = request.get("content")
         .and_then(|v| v.as_str())
         .ok_or(StatusCode::BAD_REQUEST)?;
 
+    // Optional per-call chunker, e.g. "sentence" for prose; sized like the
+    // configured one, or like upload fragments when auto-chunking is off
+    let chunker = match request.get("chunker").and_then(|v| v.as_str()) {
+        None => None,
+        Some(name) => {
+            let memory_settings = &state.settings.memory;
+            let max_chars = memory_settings.max_fragment_chars.unwrap_or(memory_settings.upload_chunk_size);
+            let chunker = chunker_by_name(name, max_chars, memory_settings.fragment_chunk_overlap).map_err(|e| {
+                warn!("Rejected memory add: {}", e);
+                StatusCode::BAD_REQUEST
+            })?;
+            Some(chunker)
+        }
+    };
+
+    let tenant = memory_tenant(&state.settings, claims.as_ref().map(|Extension(c)| c));
     let memory = state.orchestrator.read().await.memory();
-    memory.add_memory(content).await
-        .map_err(|e| {
-            error!("Failed to add to memory: {}", e);
+    memory.add_tenant_memory(content, None, tenant.as_deref(), chunker.as_deref()).await.map_err(|e| {
+        error!("Failed to add to memory: {}", e);
+        memory_error_status(&e)
+    })?;
+
+    Ok(StatusCode::CREATED)
+}
+
+/// Result of a file upload into memory
+#[derive(Serialize)]
+struct UploadResponse {
+    fragments_created: usize,
+}
+
+/// Ingest uploaded text or markdown files into memory.
+///
+/// Each file is read chunk by chunk and every completed fragment is embedded
+/// before more of the body is read, so large files are never buffered whole
+/// and a slow embedding agent applies backpressure to the client. Fragments
+/// are tagged with the file name as their source. The body may be up to
+/// `memory.max_upload_size_mb`; an upload that fails part way removes the
+/// fragments it already created.
+#[instrument(skip(state, claims, multipart))]
+async fn upload_memory(
+    State(state): State<AppState>,
+    claims: Option<Extension<Claims>>,
+    multipart: Multipart,
+) -> Result<(StatusCode, Json<UploadResponse>), StatusCode> {
+    let tenant = memory_tenant(&state.settings, claims.as_ref().map(|Extension(c)| c));
+    let memory = state.orchestrator.read().await.memory();
+    let mut created = Vec::new();
+
+    match ingest_upload(&state.settings, &memory, multipart, tenant.as_deref(), &mut created).await {
+        Ok(()) => Ok((StatusCode::CREATED, Json(UploadResponse { fragments_created: created.len() }))),
+        Err(status) => {
+            if !created.is_empty() {
+                info!("Removing {} fragments of a failed upload", created.len());
+                memory.discard_fragments(&created).await;
+            }
+            Err(status)
+        }
+    }
+}
+
+/// Ingest every file of an upload, recording the ids of created fragments in `created`
+async fn ingest_upload(
+    settings: &Settings,
+    memory: &Memory,
+    mut multipart: Multipart,
+    tenant: Option<&str>,
+    created: &mut Vec<String>,
+) -> Result<(), StatusCode> {
+    while let Some(mut field) = multipart.next_field().await.map_err(|e| {
+        warn!("Malformed multipart upload: {}", e);
+        StatusCode::BAD_REQUEST
+    })? {
+        let Some(file_name) = field.file_name().map(str::to_string) else {
+            continue;
+        };
+        if !is_text_upload(&file_name, field.content_type()) {
+            warn!("Rejected upload of unsupported file type: {}", file_name);
+            return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        }
+
+        let mut chunker = StreamingChunker::new(
+            settings.memory.upload_chunk_size,
+            settings.memory.upload_chunk_overlap,
+        ).map_err(|e| {
+            error!("Invalid upload chunking configuration: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
-    Ok(StatusCode::CREATED)
+        while let Some(bytes) = field.chunk().await.map_err(|e| {
+            warn!("Upload of {} interrupted: {}", file_name, e);
+            StatusCode::BAD_REQUEST
+        })? {
+            let chunks = chunker.push(&bytes).map_err(|e| {
+                warn!("Rejected upload of {}: {}", file_name, e);
+                StatusCode::UNPROCESSABLE_ENTITY
+            })?;
+            for chunk in chunks {
+                created.extend(ingest_chunk(memory, &chunk, &file_name, tenant).await?);
+            }
+        }
+
+        let tail = chunker.finish().map_err(|e| {
+            warn!("Rejected upload of {}: {}", file_name, e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        })?;
+        if let Some(chunk) = tail {
+            created.extend(ingest_chunk(memory, &chunk, &file_name, tenant).await?);
+        }
+        info!("Ingested {} into memory", file_name);
+    }
+
+    Ok(())
+}
+
+/// Only plain text and markdown are extracted for now
+fn is_text_upload(file_name: &str, content_type: Option<&str>) -> bool {
+    let by_type = content_type
+        .map(|ct| ct.starts_with("text/plain") || ct.starts_with("text/markdown"))
+        .unwrap_or(false);
+    let by_extension = std::path::Path::new(file_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| matches!(ext.to_ascii_lowercase().as_str(), "txt" | "md" | "markdown"))
+        .unwrap_or(false);
+    by_type || by_extension
+}
+
+/// Add one uploaded chunk, returning the ids of the fragments created
+async fn ingest_chunk(memory: &Memory, chunk: &str, source: &str, tenant: Option<&str>) -> Result<Vec<String>, StatusCode> {
+    // Whitespace-only windows carry nothing worth embedding
+    if chunk.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    memory.add_tenant_memory(chunk, Some(source), tenant, None).await.map_err(|e| {
+        error!("Failed to add uploaded chunk to memory: {}", e);
+        memory_error_status(&e)
+    })
+}
+
+/// Presigned download URL for a memory export
+#[derive(Serialize)]
+struct SignedUrlResponse {
+    url: String,
+    expires_at: u64,
+}
+
+/// Query parameters carried by a presigned URL
+#[derive(Deserialize)]
+struct SignedUrlQuery {
+    expires: u64,
+    signature: String,
+    /// Tenant the export was scoped to when the URL was created
+    tenant: Option<String>,
+}
+
+/// Export the memory fragments visible to the caller
+#[instrument(skip(state, claims))]
+async fn export_memory(
+    State(state): State<AppState>,
+    claims: Option<Extension<Claims>>,
+) -> Result<Json<Vec<MemoryFragment>>, StatusCode> {
+    let tenant = memory_tenant(&state.settings, claims.as_ref().map(|Extension(c)| c));
+    collect_memory_export(&state, tenant.as_deref()).await.map(Json)
+}
+
+/// Resource a presigned export URL signs, binding it to the creator's tenant
+fn memory_export_resource(tenant: Option<&str>) -> String {
+    match tenant {
+        Some(tenant) => format!("{}?tenant={}", MEMORY_EXPORT_DOWNLOAD_PATH, tenant),
+        None => MEMORY_EXPORT_DOWNLOAD_PATH.to_string(),
+    }
+}
+
+/// Create a time-limited URL for downloading the memory export without a token
+#[instrument(skip(state, claims))]
+async fn create_memory_export_url(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Json<SignedUrlResponse> {
+    let expires_at = chrono::Utc::now().timestamp().max(0) as u64
+        + state.settings.server.signed_url_ttl_seconds;
+    let tenant = memory_tenant(&state.settings, Some(&claims));
+    let signature = state.auth_manager.sign_url(&memory_export_resource(tenant.as_deref()), expires_at);
+    info!("User {} created memory export URL expiring at {}", claims.sub, expires_at);
+
+    let mut url = format!(
+        "{}?expires={}&signature={}",
+        MEMORY_EXPORT_DOWNLOAD_PATH, expires_at, signature
+    );
+    if let Some(tenant) = &tenant {
+        let tenant: String = url::form_urlencoded::byte_serialize(tenant.as_bytes()).collect();
+        url.push_str(&format!("&tenant={}", tenant));
+    }
+    Json(SignedUrlResponse { url, expires_at })
+}
+
+/// Serve a memory export via a presigned URL
+#[instrument(skip(state, query))]
+async fn download_memory_export(
+    State(state): State<AppState>,
+    Query(query): Query<SignedUrlQuery>,
+) -> Result<Json<Vec<MemoryFragment>>, StatusCode> {
+    state.auth_manager
+        .verify_signed_url(&memory_export_resource(query.tenant.as_deref()), query.expires, &query.signature)
+        .map_err(|e| {
+            warn!("Rejected memory export download: {}", e);
+            StatusCode::FORBIDDEN
+        })?;
+
+    collect_memory_export(&state, query.tenant.as_deref()).await.map(Json)
+}
+
+async fn collect_memory_export(state: &AppState, tenant: Option<&str>) -> Result<Vec<MemoryFragment>, StatusCode> {
+    let memory = state.orchestrator.read().await.memory();
+    memory.export_tenant_fragments(tenant).await.map_err(|e| {
+        error!("Memory export failed: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Reclaim memory held by the Julia runtime (admin only)
+#[cfg(feature = "with-julia")]
+#[instrument(skip(state, claims, connect_info))]
+async fn collect_julia_garbage(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+) -> Result<StatusCode, StatusCode> {
+    crate::ffi_julia::collect_julia_garbage().await.map_err(|e| {
+        warn!("Julia garbage collection failed: {}", e);
+        StatusCode::SERVICE_UNAVAILABLE
+    })?;
+
+    record_audit(&state, &claims.sub, "julia_gc", None, connect_info);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Reconcile fragment embeddings with the embedding cache (admin only)
+#[instrument(skip(state, claims, connect_info))]
+async fn repair_memory(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+) -> Result<Json<RepairReport>, StatusCode> {
+    let memory = state.orchestrator.read().await.memory();
+    let report = memory.verify_and_repair().await.map_err(|e| {
+        error!("Memory repair failed: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    record_audit(&state, &claims.sub, "memory_repair", None, connect_info);
+    info!(
+        "Memory repair by {}: {} fragments checked, {} updated, {} orphaned cache entries removed",
+        claims.sub, report.fragments_checked, report.fragments_updated, report.orphaned_cache_entries_removed
+    );
+    Ok(Json(report))
+}
+
+/// Body of `POST /memory/prefetch`
+#[derive(Deserialize)]
+struct PrefetchRequest {
+    texts: Vec<String>,
+}
+
+/// Warm the embedding cache with anticipated queries (admin only)
+#[instrument(skip(state, claims, connect_info, request))]
+async fn prefetch_embeddings(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Json(request): Json<PrefetchRequest>,
+) -> Result<Json<PrefetchReport>, StatusCode> {
+    let memory = state.orchestrator.read().await.memory();
+    let report = memory.prefetch_embeddings(&request.texts).await.map_err(|e| {
+        error!("Embedding prefetch failed: {}", e);
+        memory_error_status(&e)
+    })?;
+
+    record_audit(&state, &claims.sub, "memory_prefetch", None, connect_info);
+    info!(
+        "Embedding prefetch by {}: {} computed, {} already cached",
+        claims.sub, report.computed, report.already_cached
+    );
+    Ok(Json(report))
+}
+
+/// Query the audit log (admin only)
+#[instrument(skip(state))]
+async fn query_audit_log(
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<Vec<AuditEntry>>, StatusCode> {
+    state.audit.query(&query).await.map(Json).map_err(|e| {
+        error!("Audit log query failed: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Deployment scaling request
+#[derive(Deserialize)]
+struct ScaleRequest {
+    replicas: u32,
+}
+
+/// Query parameters for deployment scaling
+#[derive(Deserialize)]
+struct ScaleQuery {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Scale a deployment, or with `?dry_run=true` return the plan without applying it (admin only)
+#[instrument(skip(state, claims, connect_info))]
+async fn scale_deployment(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Path(name): Path<String>,
+    Query(query): Query<ScaleQuery>,
+    Json(request): Json<ScaleRequest>,
+) -> Result<Json<ScalePlan>, StatusCode> {
+    let lifecycle = state.orchestrator.read().await.lifecycle();
+    if lifecycle.get_deployment_status(&name).await.is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let result = if query.dry_run {
+        lifecycle.plan_scale(&name, request.replicas).await
+    } else {
+        record_audit(&state, &claims.sub, "deployment_scale", Some(&name), connect_info);
+        lifecycle.scale_deployment(&name, request.replicas).await
+    };
+
+    result.map(Json).map_err(|e| {
+        warn!("Rejected scaling of deployment '{}': {}", name, e);
+        StatusCode::BAD_REQUEST
+    })
+}
+
+/// Query recorded agent executions (admin only)
+#[instrument(skip(state))]
+async fn query_trace_log(
+    State(state): State<AppState>,
+    Query(query): Query<TraceQuery>,
+) -> Json<Vec<TraceEntry>> {
+    let trace_log = state.orchestrator.read().await.trace_log();
+    Json(trace_log.query(&query).await)
+}
+
+/// Re-dispatch the input of a recorded execution (admin only)
+#[instrument(skip(state, claims, connect_info))]
+async fn replay_trace(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<Json<ExecuteTaskResponse>, StatusCode> {
+    let orchestrator = state.orchestrator.read().await;
+    if orchestrator.trace_log().get(id).await.is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    record_audit(&state, &claims.sub, "trace_replay", Some(&id.to_string()), connect_info);
+
+    let start_time = std::time::Instant::now();
+    let caller = task_caller(&state.settings, Some(&claims));
+    let result = orchestrator.replay_trace(&caller, id).await;
+    let execution_time_ms = start_time.elapsed().as_millis() as u64;
+
+    Ok(Json(match result {
+        Ok(output) => ExecuteTaskResponse {
+            success: true,
+            result: Some(redact_output(&state, output.to_string())),
+            error: None,
+            execution_time_ms,
+        },
+        Err(e) => ExecuteTaskResponse {
+            success: false,
+            result: None,
+            error: Some(redact_output(&state, e.to_string())),
+            execution_time_ms,
+        },
+    }))
+}
+
+/// Queue an audit event; never blocks the calling handler
+fn record_audit(
+    state: &AppState,
+    actor: &str,
+    action: &str,
+    target: Option<&str>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+) {
+    state.audit.record(AuditEvent {
+        actor: actor.to_string(),
+        action: action.to_string(),
+        target: target.map(str::to_string),
+        source_ip: connect_info.map(|ConnectInfo(addr)| addr.ip().to_string()),
+    });
 }
 
 /// Get system metrics
@@ -334,17 +1475,63 @@ async fn get_metrics(
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     let system = state.monitoring.get_system_metrics().await;
     let agents = state.monitoring.get_all_agent_metrics().await;
+    let task_queue: std::collections::HashMap<String, usize> = state.orchestrator.read().await
+        .task_queue_depths()
+        .into_iter()
+        .map(|(priority, depth)| (format!("{:?}", priority).to_lowercase(), depth))
+        .collect();
     let metrics = serde_json::json!({
         "system": system,
         "agents": agents,
+        "auth_user_cache": state.auth_manager.user_cache_stats(),
+        "task_queue": task_queue,
+        "platform": crate::metrics::platform().snapshot(),
     });
     Ok(Json(metrics))
 }
 
+/// Query of `POST /admin/metrics/snapshot`
+#[derive(Deserialize)]
+struct MetricsSnapshotQuery {
+    /// "json" or "csv"; defaults to `observability.metrics_snapshot_format`
+    format: Option<String>,
+}
+
+#[derive(Serialize)]
+struct MetricsSnapshotResponse {
+    path: std::path::PathBuf,
+}
+
+/// Write every metric to a file in `observability.metrics_snapshot_dir` (admin only)
+#[instrument(skip(state, claims, connect_info))]
+async fn write_metrics_snapshot(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Query(query): Query<MetricsSnapshotQuery>,
+) -> Result<Json<MetricsSnapshotResponse>, StatusCode> {
+    let config = &state.settings.observability;
+    let Some(dir) = &config.metrics_snapshot_dir else {
+        warn!("Metrics snapshot requested but observability.metrics_snapshot_dir is not set");
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let format = SnapshotFormat::parse(query.format.as_deref().unwrap_or(&config.metrics_snapshot_format))
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let path = state.monitoring.archive_snapshot(dir, format, config.metrics_snapshot_retention).await.map_err(|e| {
+        error!("Metrics snapshot failed: {:#}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    record_audit(&state, &claims.sub, "metrics_snapshot", path.to_str(), connect_info);
+    Ok(Json(MetricsSnapshotResponse { path }))
+}
+
 /// Login endpoint
 #[instrument(skip(state, request))]
 async fn login(
     State(state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     Json(request): Json<LoginRequest>,
 ) -> Result<Json<LoginResponse>, StatusCode> {
     let auth_manager = state.auth_manager.clone();
@@ -368,10 +1555,16 @@ async fn login(
                 roles: claims.roles,
             };
 
+            record_audit(&state, &request.username, "login", None, connect_info);
             info!("User {} logged in successfully", request.username);
             Ok(Json(response))
         }
+        Err(e) if e.is::<AuthUnavailableError>() => {
+            warn!("Login unavailable for user {}: {}", request.username, e);
+            Err(StatusCode::SERVICE_UNAVAILABLE)
+        }
         Err(e) => {
+            record_audit(&state, &request.username, "login_failed", None, connect_info);
             warn!("Login failed for user {}: {}", request.username, e);
             Err(StatusCode::UNAUTHORIZED)
         }
@@ -382,9 +1575,12 @@ async fn login(
 #[instrument(skip(state, request))]
 async fn create_user(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     Json(request): Json<CreateUserRequest>,
 ) -> Result<StatusCode, StatusCode> {
     let auth_manager = state.auth_manager.clone();
+    let username = request.username.clone();
 
     let result = tokio::task::spawn_blocking(move || {
         auth_manager.add_user(request.username, &request.password, request.roles)
@@ -392,6 +1588,7 @@ async fn create_user(
 
     match result {
         Ok(_) => {
+            record_audit(&state, &claims.sub, "user_create", Some(&username), connect_info);
             info!("User created successfully");
             Ok(StatusCode::CREATED)
         }
@@ -406,9 +1603,12 @@ async fn create_user(
 #[instrument(skip(state, request))]
 async fn change_password(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     Json(request): Json<ChangePasswordRequest>,
 ) -> Result<StatusCode, StatusCode> {
     let auth_manager = state.auth_manager.clone();
+    let username = request.username.clone();
 
     let result = tokio::task::spawn_blocking(move || {
         auth_manager.update_password(&request.username, &request.new_password)
@@ -416,6 +1616,7 @@ async fn change_password(
 
     match result {
         Ok(_) => {
+            record_audit(&state, &claims.sub, "password_change", Some(&username), connect_info);
             info!("Password changed for user {}", request.username);
             Ok(StatusCode::OK)
         }
@@ -426,6 +1627,47 @@ async fn change_password(
     }
 }
 
+/// The caller's quota and usage in the current window
+#[instrument(skip(state, claims))]
+async fn get_usage(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Json<UsageReport> {
+    Json(state.orchestrator.read().await.usage(&claims.sub))
+}
+
+/// Hold a user to the given quota instead of the configured one (admin only)
+#[instrument(skip(state, claims, connect_info))]
+async fn set_user_quota(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Path(name): Path<String>,
+    Json(quota): Json<Quota>,
+) -> Result<Json<UsageReport>, StatusCode> {
+    if quota.max_cost_units.map_or(false, |units| !units.is_finite() || units < 0.0) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let orchestrator = state.orchestrator.read().await;
+    orchestrator.set_user_quota(&name, Some(quota));
+    record_audit(&state, &claims.sub, "quota_set", Some(&name), connect_info);
+    Ok(Json(orchestrator.usage(&name)))
+}
+
+/// Return a user to their configured quota (admin only)
+#[instrument(skip(state, claims, connect_info))]
+async fn clear_user_quota(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Path(name): Path<String>,
+) -> Json<UsageReport> {
+    let orchestrator = state.orchestrator.read().await;
+    orchestrator.set_user_quota(&name, None);
+    record_audit(&state, &claims.sub, "quota_clear", Some(&name), connect_info);
+    Json(orchestrator.usage(&name))
+}
+
 /// Create user request
 #[derive(Deserialize)]
 struct CreateUserRequest {
@@ -454,10 +1696,17 @@ fn create_dummy_memory() -> Memory {
 /// Start the HTTP server and wait for shutdown signal
 pub async fn serve(settings: &Settings) -> Result<()> {
     info!("Starting HTTP server on port {}", settings.server.port);
+    let startup_start = std::time::Instant::now();
 
     // Enforce strict JWT secret validation
     validate_jwt_secret_startup(settings)?;
 
+    // A bad certificate or key should stop startup, not the first handshake
+    let tls = match (&settings.server.tls_cert_path, &settings.server.tls_key_path) {
+        (Some(cert), Some(key)) => Some((crate::tls::load_tls_config(cert, key).await?, cert.clone(), key.clone())),
+        _ => None,
+    };
+
     // Configure memory cache
     let memory_cache: Arc<dyn EmbeddingCache> = if settings.memory.provider == "redis" {
         #[cfg(feature = "with-redis")]
@@ -481,12 +1730,21 @@ pub async fn serve(settings: &Settings) -> Result<()> {
     let embedding_agent = Arc::new(HashEmbeddingAgent::new(settings.memory.embedding_dim));
     let reranker_agent = Arc::new(LengthRerankAgent::new());
 
-    let memory = Arc::new(
-        Memory::new(embedding_agent.clone(), reranker_agent.clone(), memory_cache)
-            .with_max_fragments(settings.memory.max_fragments)
-            .with_embedding_dim(settings.memory.embedding_dim)
-            .with_similarity_threshold(settings.memory.similarity_threshold),
-    );
+    let mut memory = Memory::new(embedding_agent.clone(), reranker_agent.clone(), memory_cache)
+        .with_max_fragments(settings.memory.max_fragments)
+        .with_embedding_dim(settings.memory.embedding_dim)
+        .with_similarity_threshold(settings.memory.similarity_threshold)
+        .with_embedding_timeout(std::time::Duration::from_secs(settings.memory.embedding_timeout_seconds))
+        .with_recency_half_life(settings.memory.recency_half_life_seconds.map(std::time::Duration::from_secs))
+        .with_search_budget(settings.memory.search_budget_ms.map(std::time::Duration::from_millis));
+    if let Some(max_chars) = settings.memory.max_fragment_chars {
+        memory = memory.with_chunker(chunker_by_name(
+            &settings.memory.fragment_chunker,
+            max_chars,
+            settings.memory.fragment_chunk_overlap,
+        )?);
+    }
+    let memory = Arc::new(memory);
 
     let orchestrator = Arc::new(RwLock::new(
         Orchestrator::new(&settings, memory.clone()).await
@@ -496,61 +1754,278 @@ pub async fn serve(settings: &Settings) -> Result<()> {
             })?
     ));
 
+    // Report whether the Julia runtime is usable; agents degrade to unhealthy if not
+    #[cfg(feature = "with-julia")]
+    {
+        let probe_settings = settings.clone();
+        let _ = tokio::task::spawn_blocking(move || {
+            crate::ffi_julia::probe_julia_runtime(&probe_settings)
+        }).await;
+    }
+
+    // Register plugins already in the plugin directory; bad libraries are
+    // reported by the orchestrator and don't block the rest
+    if let Err(e) = orchestrator.read().await.register_plugins().await {
+        warn!("Skipping startup plugin load: {:#}", e);
+    }
+
+    // Preload models and connections so the first request doesn't pay for them
+    if settings.orchestrator.background_warm_up {
+        let orchestrator = orchestrator.clone();
+        let settings = settings.clone();
+        tokio::spawn(async move {
+            if let Err(e) = warm_up_agents(&orchestrator, &settings).await {
+                error!("{}; serving without it", e);
+            }
+        });
+    } else {
+        warm_up_agents(&orchestrator, settings).await?;
+    }
+
     // Initialize authentication manager with validated JWT secret
     let db_path = settings.db_path.clone().unwrap_or_else(|| "./acropolis_db/auth".to_string());
     let jwt_secret = get_jwt_secret_for_server(settings)?;
-    let auth_manager = Arc::new(AuthManager::new(jwt_secret, &db_path)?);
-    
-    // Check admin initialization
-    if settings.security.enable_authentication && !auth_manager.has_admin()? {
+    let user_cache_ttl = std::time::Duration::from_secs(settings.security.auth_user_cache_ttl_seconds);
+    let auth_manager = match AuthManager::new(jwt_secret.clone(), &db_path) {
+        Ok(auth_manager) => Arc::new(
+            auth_manager.with_user_cache(settings.security.auth_user_cache_size, user_cache_ttl),
+        ),
+        Err(e) if settings.security.auth_db_unavailable_mode == "degraded" => {
+            warn!("{}; starting in degraded mode, protected routes return 503 until it is available", e);
+            let auth_manager = Arc::new(
+                AuthManager::new_degraded(jwt_secret, &db_path)
+                    .with_user_cache(settings.security.auth_user_cache_size, user_cache_ttl),
+            );
+            spawn_auth_reconnect(
+                auth_manager.clone(),
+                std::time::Duration::from_secs(settings.security.auth_db_retry_interval_seconds),
+                settings.security.enable_authentication,
+            );
+            auth_manager
+        }
+        Err(e) => return Err(e),
+    };
+
+    // Check admin initialization; a degraded manager is checked once it reconnects
+    if settings.security.enable_authentication
+        && auth_manager.is_available()
+        && !auth_manager.has_admin()?
+    {
         error!("No admin user found. Run 'acropolis-cli init-admin' to create the first admin user.");
         return Err(anyhow::anyhow!("Admin user must be initialized before starting the server"));
     }
 
+    // Audit trail for security-relevant actions
+    let audit = Arc::new(AuditLog::open(&settings.security.audit_log_path).await?);
+
+    // Initialize authorization policy
+    let authorizer = create_authorizer(&settings.security.authorization_policy)?;
+
     // Initialize rate limiter
     let rate_limiter = create_rate_limiter(&settings.security);
 
     let monitoring = orchestrator.read().await.monitoring();
+    let websocket = orchestrator.read().await.websocket();
+    let plugins = orchestrator.read().await.plugin_manager();
+
+    // Settings validation rejects unknown formats
+    if let (Some(dir), interval @ 1..) = (
+        &settings.observability.metrics_snapshot_dir,
+        settings.observability.metrics_snapshot_interval_seconds,
+    ) {
+        let format = SnapshotFormat::parse(&settings.observability.metrics_snapshot_format)?;
+        monitoring.start_snapshot_archival(
+            dir.clone(),
+            format,
+            std::time::Duration::from_secs(interval),
+            settings.observability.metrics_snapshot_retention,
+        );
+    }
+
+    // Initialize output redaction if enabled for this deployment
+    let output_redactor = OutputRedactor::from_config(&settings.security)?.map(Arc::new);
+    websocket.set_agent_handler(Arc::new(WebSocketAgentHandler::new(
+        &orchestrator,
+        auth_manager.clone(),
+        output_redactor.clone(),
+        settings.clone(),
+    )));
+
+    // Completed /execute responses are replayed from the shared cache on retry
+    let idempotency = Arc::new(IdempotencyStore::new(
+        orchestrator.read().await.cache(),
+        std::time::Duration::from_secs(settings.server.idempotency_ttl_seconds),
+    ));
+
+    let coordinator = Arc::new(shutdown_coordinator(settings, &orchestrator, &monitoring, &websocket, &audit));
+
+    // The gRPC API shares the orchestrator, auth and TLS certificate but
+    // listens on its own port; it stops accepting alongside HTTP and its
+    // in-flight calls drain with the orchestrator's tasks
+    #[cfg(feature = "with-grpc")]
+    {
+        let listener = crate::grpc::bind(&settings.server.host, settings.server.grpc_port).await?;
+        let service = crate::grpc::GrpcAgentService::new(
+            orchestrator.clone(),
+            auth_manager.clone(),
+            output_redactor.clone(),
+            settings.clone(),
+        );
+        let stop = tokio_util::sync::CancellationToken::new();
+        let tls_config = tls.as_ref().map(|(config, _, _)| config.clone());
+        let server = tokio::spawn(crate::grpc::serve(listener, service, tls_config, stop.clone()));
+        coordinator.register(ShutdownPhase::StopAccepting, "grpc", move || async move {
+            stop.cancel();
+            Ok(())
+        });
+        coordinator.register(ShutdownPhase::Drain, "grpc_calls", move || async move {
+            server.await.map_err(|e| anyhow::anyhow!("gRPC server task failed: {}", e))?
+        });
+    }
+
+    // Rate limits, concurrency, timeouts and the log level follow SIGHUP reloads
+    let config = Arc::new(ConfigReloader::new(
+        settings.clone(),
+        rate_limiter.clone(),
+        orchestrator.clone(),
+        memory.clone(),
+    ));
+    #[cfg(unix)]
+    crate::config_reload::spawn_sighup_listener(config.clone())?;
 
     let state = AppState {
         orchestrator,
         auth_manager,
         rate_limiter,
         settings: settings.clone(),
+        config,
         start_time: std::time::Instant::now(),
         monitoring,
+        output_redactor,
+        idempotency,
+        authorizer,
+        audit,
+        websocket,
+        plugins,
     };
 
+    info!("Startup completed in {}ms", startup_start.elapsed().as_millis());
+
     // Create router
     let app = create_router(state);
+    let shutdown = wait_for_shutdown(coordinator.clone());
+
+    // Settings validation rejects TLS on Unix sockets
+    match (settings.server.bind_target()?, tls) {
+        (BindTarget::Tcp(addr), Some((config, cert_path, key_path))) => {
+            let _watcher = crate::tls::watch_certificates(config.clone(), cert_path, key_path)
+                .map_err(|e| warn!("TLS certificate changes will need a restart: {}", e))
+                .ok();
+            let drain_timeout = std::time::Duration::from_secs(settings.server.shutdown_timeout_seconds);
+            serve_tls(app, addr, config, shutdown, drain_timeout).await?;
+        }
+        (BindTarget::Tcp(addr), None) => {
+            info!("HTTP server listening on {}", addr);
+
+            // Start server with graceful shutdown
+            let listener = tokio::net::TcpListener::bind(&addr).await
+                .map_err(|e| anyhow::anyhow!("Failed to bind to address: {}", e))?;
+
+            // Connection info provides source IPs for the audit log
+            let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>());
+            let drain_timeout = std::time::Duration::from_secs(settings.server.shutdown_timeout_seconds);
+            let (draining_tx, draining_rx) = tokio::sync::oneshot::channel();
+            let shutdown = async move {
+                shutdown.await;
+                let _ = draining_tx.send(());
+            };
+            // Connections still open once the drain timeout has passed are dropped
+            let drain_deadline = async move {
+                match draining_rx.await {
+                    Ok(()) => tokio::time::sleep(drain_timeout).await,
+                    Err(_) => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                result = server.with_graceful_shutdown(shutdown) => if let Err(e) = result {
+                    error!("HTTP server error: {}", e);
+                },
+                _ = drain_deadline => warn!("HTTP connections still open after {:?}, closing them", drain_timeout),
+            }
+        }
+        #[cfg(unix)]
+        (BindTarget::Unix(path), _) => {
+            let drain_timeout = std::time::Duration::from_secs(settings.server.shutdown_timeout_seconds);
+            serve_unix(app, &path, settings.server.unix_socket_mode, shutdown, drain_timeout).await?;
+        }
+        #[cfg(not(unix))]
+        (BindTarget::Unix(_), _) => {
+            return Err(anyhow::anyhow!("Unix domain sockets are not supported on this platform"));
+        }
+    }
 
-    // Bind to address
-    let addr = format!("{}:{}", settings.server.host, settings.server.port)
-        .parse()
-        .map_err(|e| anyhow::anyhow!("Invalid server address: {}", e))?;
+    info!("HTTP server drained");
+    coordinator.run_from(ShutdownPhase::Drain).await;
+    info!("HTTP server shutdown complete");
+    Ok(())
+}
 
-    info!("HTTP server listening on {}", addr);
+/// Register the subsystems' shutdown hooks in the order they must stop:
+/// WebSocket sessions close first, running tasks finish, background loops
+/// and agent instances stop, then metrics and audit events are written out
+fn shutdown_coordinator(
+    settings: &Settings,
+    orchestrator: &Arc<RwLock<Orchestrator>>,
+    monitoring: &Arc<MonitoringSystem>,
+    websocket: &Arc<WebSocketServer>,
+    audit: &Arc<AuditLog>,
+) -> ShutdownCoordinator {
+    let coordinator = ShutdownCoordinator::new(std::time::Duration::from_secs(settings.server.shutdown_timeout_seconds));
+
+    let websocket = websocket.clone();
+    coordinator.register(ShutdownPhase::StopAccepting, "websocket", move || async move {
+        let closed = websocket.close_all();
+        info!("Closed {} WebSocket connections", closed);
+        Ok(())
+    });
 
-    // Start server with graceful shutdown
-    let listener = tokio::net::TcpListener::bind(&addr).await
-        .map_err(|e| anyhow::anyhow!("Failed to bind to address: {}", e))?;
-    
-    let server = axum::serve(listener, app);
+    let tasks = orchestrator.clone();
+    coordinator.register(ShutdownPhase::Drain, "orchestrator_tasks", move || async move {
+        tasks.read().await.drain(std::time::Duration::from_millis(100)).await;
+        Ok(())
+    });
 
-    // Wait for shutdown signal
-    let orchestrator_for_shutdown = state.orchestrator.clone();
-    let graceful = server.with_graceful_shutdown(wait_for_shutdown(orchestrator_for_shutdown));
+    let agents = orchestrator.clone();
+    coordinator.register(ShutdownPhase::StopBackground, "agent_instances", move || async move {
+        agents.read().await.shutdown().await
+    });
+    let mesh = orchestrator.clone();
+    coordinator.register(ShutdownPhase::StopBackground, "agent_mesh", move || async move {
+        match mesh.read().await.mesh() {
+            Some(mesh) => mesh.shutdown().await,
+            None => Ok(()),
+        }
+    });
+    let background = monitoring.clone();
+    coordinator.register(ShutdownPhase::StopBackground, "monitoring", move || async move {
+        background.stop();
+        Ok(())
+    });
 
-    if let Err(e) = graceful.await {
-        error!("HTTP server error: {}", e);
-    }
+    let metrics = monitoring.clone();
+    coordinator.register(ShutdownPhase::Flush, "metrics", move || async move {
+        metrics.flush().await;
+        Ok(())
+    });
+    let audit = audit.clone();
+    coordinator.register(ShutdownPhase::Flush, "audit_log", move || async move { audit.flush().await });
 
-    info!("HTTP server shutdown complete");
-    Ok(())
+    coordinator
 }
 
-/// Wait for shutdown signal (Ctrl+C)
-async fn wait_for_shutdown(orchestrator: Arc<RwLock<Orchestrator>>) {
+/// Wait for a shutdown signal (SIGTERM or Ctrl+C), then stop accepting new
+/// work; the remaining phases run once HTTP connections have drained
+async fn wait_for_shutdown(coordinator: Arc<ShutdownCoordinator>) {
     #[cfg(unix)]
     {
         use tokio::signal::unix::{signal, SignalKind};
@@ -573,9 +2048,163 @@ async fn wait_for_shutdown(orchestrator: Arc<RwLock<Orchestrator>>) {
         tokio::time::sleep(tokio::time::Duration::from_secs(u64::MAX)).await;
     }
 
-    if let Err(e) = orchestrator.write().await.shutdown().await {
-        error!("Error during orchestrator shutdown: {}", e);
+    coordinator.run_phase(ShutdownPhase::StopAccepting).await;
+}
+
+/// Serve `app` over HTTPS until `shutdown` completes, then give in-flight
+/// connections up to `drain_timeout` to finish
+async fn serve_tls(
+    app: Router,
+    addr: SocketAddr,
+    config: axum_server::tls_rustls::RustlsConfig,
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+    drain_timeout: std::time::Duration,
+) -> Result<()> {
+    let handle = axum_server::Handle::new();
+    let stopper = handle.clone();
+    tokio::spawn(async move {
+        shutdown.await;
+        stopper.graceful_shutdown(Some(drain_timeout));
+    });
+
+    info!("HTTPS server listening on {}", addr);
+    axum_server::bind_rustls(addr, config)
+        .handle(handle)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .map_err(|e| anyhow::anyhow!("HTTPS server error: {}", e))
+}
+
+/// Serve `app` on a Unix domain socket until `shutdown` completes, then wait
+/// for in-flight connections and remove the socket file.
+///
+/// Requests carry no `ConnectInfo`, so audit entries and WebSocket sessions
+/// have no source address.
+#[cfg(unix)]
+async fn serve_unix(
+    app: Router,
+    path: &std::path::Path,
+    mode: u32,
+    shutdown: impl std::future::Future<Output = ()>,
+    drain_timeout: std::time::Duration,
+) -> Result<()> {
+    use hyper_util::{
+        rt::{TokioExecutor, TokioIo},
+        server::{conn::auto::Builder, graceful::GracefulShutdown},
+        service::TowerToHyperService,
+    };
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    prepare_socket_path(path)?;
+    let listener = tokio::net::UnixListener::bind(path)
+        .map_err(|e| anyhow::anyhow!("Failed to bind Unix socket {:?}: {}", path, e))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+        .map_err(|e| anyhow::anyhow!("Failed to set permissions on {:?}: {}", path, e))?;
+    if mode & 0o007 != 0 {
+        warn!("Unix socket {:?} is accessible to all local users (mode {:o})", path, mode);
+    }
+    info!("HTTP server listening on unix:{}", path.display());
+
+    let builder = Builder::new(TokioExecutor::new());
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(shutdown);
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let stream = match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        warn!("Failed to accept Unix socket connection: {}", e);
+                        continue;
+                    }
+                };
+                let service = TowerToHyperService::new(app.clone());
+                let connection = builder
+                    .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                    .into_owned();
+                let connection = graceful.watch(connection);
+                tokio::spawn(async move {
+                    if let Err(e) = connection.await {
+                        warn!("Unix socket connection error: {}", e);
+                    }
+                });
+            }
+            _ = &mut shutdown => break,
+        }
+    }
+
+    drop(listener);
+    if tokio::time::timeout(drain_timeout, graceful.shutdown()).await.is_err() {
+        warn!("Timed out waiting for Unix socket connections to close");
+    }
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => {
+            if let Err(e) = std::fs::remove_file(path) {
+                warn!("Failed to remove Unix socket {:?}: {}", path, e);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Make sure a Unix socket can be created at `path`, removing a stale socket
+/// left behind by an earlier run. Anything other than a socket is left alone.
+#[cfg(unix)]
+fn prepare_socket_path(path: &std::path::Path) -> Result<()> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => std::path::Path::new("."),
+    };
+    let metadata = std::fs::metadata(parent)
+        .map_err(|e| anyhow::anyhow!("Socket directory {:?} is not accessible: {}", parent, e))?;
+    if !metadata.is_dir() || metadata.permissions().readonly() {
+        return Err(anyhow::anyhow!("Socket directory {:?} is not writable", parent));
+    }
+    let dir_mode = metadata.permissions().mode();
+    if dir_mode & 0o002 != 0 && dir_mode & 0o1000 == 0 {
+        warn!("Socket directory {:?} is world-writable; other users could replace the socket", parent);
+    }
+
+    match std::fs::symlink_metadata(path) {
+        Ok(existing) if existing.file_type().is_socket() => {
+            info!("Removing stale Unix socket {:?}", path);
+            std::fs::remove_file(path)?;
+        }
+        Ok(_) => return Err(anyhow::anyhow!("{:?} exists and is not a socket", path)),
+        Err(_) => {}
+    }
+    Ok(())
+}
+
+/// Warm up registered agents, failing if a critical agent cannot warm up
+async fn warm_up_agents(orchestrator: &Arc<RwLock<Orchestrator>>, settings: &Settings) -> Result<()> {
+    let reports = orchestrator.read().await
+        .warm_up_agents(settings.orchestrator.parallel_warm_up)
+        .await;
+
+    for report in reports {
+        match report.result {
+            Ok(()) => info!(
+                "Agent '{}' warmed up in {}ms",
+                report.agent_name, report.duration.as_millis()
+            ),
+            Err(e) if settings.orchestrator.critical_agents.contains(&report.agent_name) => {
+                error!("Critical agent '{}' failed to warm up: {}", report.agent_name, e);
+                return Err(anyhow::anyhow!(
+                    "Critical agent '{}' failed to warm up: {}", report.agent_name, e
+                ));
+            }
+            Err(e) => warn!(
+                "Agent '{}' failed to warm up after {}ms: {}",
+                report.agent_name, report.duration.as_millis(), e
+            ),
+        }
     }
+    Ok(())
 }
 
 /// Validate JWT secret meets security requirements for server startup
@@ -624,3 +2253,146 @@ fn get_jwt_secret_for_server(settings: &Settings) -> Result<String> {
             "JWT secret must be provided via AEP_JWT_SECRET environment variable or config file when authentication is enabled"
         ))
 }
+
+/// Periodically retry opening the auth database until it succeeds
+fn spawn_auth_reconnect(auth_manager: Arc<AuthManager>, interval: std::time::Duration, check_admin: bool) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let manager = auth_manager.clone();
+            match tokio::task::spawn_blocking(move || manager.try_reconnect()).await {
+                Ok(Ok(_)) => {
+                    info!("Authentication database available; leaving degraded mode");
+                    if check_admin && !matches!(auth_manager.has_admin(), Ok(true)) {
+                        error!("No admin user found. Run 'acropolis-cli init-admin' to create the first admin user.");
+                    }
+                    break;
+                }
+                Ok(Err(e)) => warn!("Authentication database still unavailable: {}", e),
+                Err(e) => error!("Auth reconnect task failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    /// Counts its calls and takes a moment, so duplicate requests overlap
+    struct SlowCountingAgent {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for SlowCountingAgent {
+        fn name(&self) -> &str { "slow" }
+        fn agent_type(&self) -> &str { "test" }
+        fn capabilities(&self) -> Vec<String> { vec![] }
+        async fn handle(&self, _input: serde_json::Value, _memory: Arc<Memory>) -> Result<String> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            Ok(format!("call {}", call))
+        }
+        async fn health_check(&self) -> Result<crate::agent::AgentHealth> {
+            Ok(crate::agent::AgentHealth::default())
+        }
+        fn cacheable(&self) -> bool { false }
+    }
+
+    /// The full router over a fresh orchestrator serving `agent` as "slow",
+    /// and a token for a regular user
+    async fn test_app(dir: &std::path::Path, agent: Arc<dyn Agent>) -> (Router, String) {
+        let mut settings = Settings::default();
+        settings.server.response_envelope = false;
+        settings.security.audit_log_path = dir.join("audit.jsonl");
+
+        let memory = Arc::new(Memory::new(
+            Arc::new(HashEmbeddingAgent::new(settings.memory.embedding_dim)),
+            Arc::new(LengthRerankAgent::new()),
+            Arc::new(InMemoryEmbeddingCache::new()),
+        ));
+        let orchestrator = Orchestrator::new(&settings, memory.clone()).await.unwrap();
+        orchestrator.register_agent("slow".to_string(), agent).await.unwrap();
+        let orchestrator = Arc::new(RwLock::new(orchestrator));
+
+        let auth_manager = Arc::new(AuthManager::new("test_secret".to_string(), dir.join("auth").to_str().unwrap()).unwrap());
+        auth_manager.add_user("alice".to_string(), "password", vec!["user".to_string()]).unwrap();
+        let token = auth_manager.authenticate("alice", "password").unwrap();
+
+        let rate_limiter = create_rate_limiter(&settings.security);
+        let (monitoring, websocket, plugins, cache) = {
+            let orchestrator = orchestrator.read().await;
+            (orchestrator.monitoring(), orchestrator.websocket(), orchestrator.plugin_manager(), orchestrator.cache())
+        };
+        let state = AppState {
+            orchestrator: orchestrator.clone(),
+            auth_manager,
+            rate_limiter: rate_limiter.clone(),
+            settings: settings.clone(),
+            config: Arc::new(ConfigReloader::new(settings.clone(), rate_limiter, orchestrator, memory)),
+            start_time: std::time::Instant::now(),
+            monitoring,
+            output_redactor: None,
+            idempotency: Arc::new(IdempotencyStore::new(cache, std::time::Duration::from_secs(60))),
+            authorizer: create_authorizer(&settings.security.authorization_policy).unwrap(),
+            audit: Arc::new(AuditLog::open(&settings.security.audit_log_path).await.unwrap()),
+            websocket,
+            plugins,
+        };
+        (create_router(state), token)
+    }
+
+    fn execute_request(token: &str, key: &str, input: &str) -> axum::http::Request<Body> {
+        axum::http::Request::post("/execute")
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(IDEMPOTENCY_KEY_HEADER, key)
+            .body(Body::from(serde_json::json!({ "agent_name": "slow", "input": input }).to_string()))
+            .unwrap()
+    }
+
+    async fn json_body(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_execute_idempotency_key_replays_response() {
+        let dir = tempfile::tempdir().unwrap();
+        let agent = Arc::new(SlowCountingAgent { calls: AtomicUsize::new(0) });
+        let (app, token) = test_app(dir.path(), agent.clone()).await;
+
+        // Concurrent duplicates wait for the first and get its response
+        let responses = futures::future::join_all(
+            (0..4).map(|_| app.clone().oneshot(execute_request(&token, "key-1", "hi"))),
+        ).await;
+        let mut bodies = Vec::new();
+        for response in responses {
+            let response = response.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            bodies.push(json_body(response).await);
+        }
+        assert_eq!(agent.calls.load(Ordering::SeqCst), 1);
+        // `result` holds the agent output JSON-encoded
+        assert!(bodies.iter().all(|body| body["result"] == "\"call 1\""), "{:?}", bodies);
+
+        // A later retry is replayed too
+        let retry = app.clone().oneshot(execute_request(&token, "key-1", "hi")).await.unwrap();
+        assert_eq!(json_body(retry).await["result"], "\"call 1\"");
+        assert_eq!(agent.calls.load(Ordering::SeqCst), 1);
+
+        // Reusing the key for a different request is refused
+        let reused = app.clone().oneshot(execute_request(&token, "key-1", "other")).await.unwrap();
+        assert_eq!(reused.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let bad_key = app.oneshot(execute_request(&token, "has space", "hi")).await.unwrap();
+        assert_eq!(bad_key.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(agent.calls.load(Ordering::SeqCst), 1);
+    }
+}
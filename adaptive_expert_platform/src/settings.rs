@@ -17,6 +17,48 @@ pub struct ServerConfig {
     pub enable_cors: bool,
     pub cors_origins: Vec<String>,
     pub rate_limit_per_minute: u32,
+    pub idempotency_ttl_seconds: u64,
+    pub signed_url_ttl_seconds: u64,
+    pub grpc_port: u16,
+    /// Overrides host/port; either "host:port" or "unix:/path/to.sock"
+    pub bind: Option<String>,
+    /// Permission bits applied to a Unix domain socket after binding
+    pub unix_socket_mode: u32,
+    /// Total time allowed for every shutdown phase after a stop signal
+    pub shutdown_timeout_seconds: u64,
+    /// Wrap responses in a `data`/`meta` envelope for clients that accept
+    /// `application/vnd.acropolis+json`
+    pub response_envelope: bool,
+    /// PEM certificate chain; with `tls_key_path`, serves HTTPS instead of HTTP
+    pub tls_cert_path: Option<PathBuf>,
+    /// PEM private key matching `tls_cert_path`
+    pub tls_key_path: Option<PathBuf>,
+}
+
+/// Where the HTTP server listens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindTarget {
+    Tcp(std::net::SocketAddr),
+    Unix(PathBuf),
+}
+
+impl ServerConfig {
+    /// Resolve `bind`, falling back to `host`/`port`
+    pub fn bind_target(&self) -> Result<BindTarget> {
+        let bind = match &self.bind {
+            Some(bind) => bind.trim().to_string(),
+            None => format!("{}:{}", self.host, self.port),
+        };
+        if let Some(path) = bind.strip_prefix("unix:") {
+            if path.is_empty() {
+                return Err(anyhow!("Unix socket bind address is missing a path"));
+            }
+            return Ok(BindTarget::Unix(PathBuf::from(path)));
+        }
+        bind.parse()
+            .map(BindTarget::Tcp)
+            .map_err(|e| anyhow!("Invalid server address '{}': {}", bind, e))
+    }
 }
 
 impl Default for ServerConfig {
@@ -29,6 +71,15 @@ impl Default for ServerConfig {
             enable_cors: true,
             cors_origins: vec!["*".to_string()],
             rate_limit_per_minute: 1_000,
+            idempotency_ttl_seconds: 86_400, // 24 hours
+            signed_url_ttl_seconds: 900, // 15 minutes
+            grpc_port: 50051,
+            bind: None,
+            unix_socket_mode: 0o660,
+            shutdown_timeout_seconds: 30,
+            response_envelope: true,
+            tls_cert_path: None,
+            tls_key_path: None,
         }
     }
 }
@@ -36,12 +87,18 @@ impl Default for ServerConfig {
 /// Enhanced logging configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoggingConfig {
+    /// Log filter directive such as "info"; `RUST_LOG` overrides it at
+    /// startup. Hot-reloadable
     pub level: String,
     pub format: String, // "json" or "text"
     pub output: String, // "stdout", "stderr", or file path
     pub enable_timestamps: bool,
     pub enable_thread_ids: bool,
     pub enable_target: bool,
+    /// Fraction of calls per agent whose input and output are logged at DEBUG; 0 disables
+    pub agent_io_sample_rate: f64,
+    /// Characters of each logged input and output, after redaction
+    pub agent_io_preview_chars: usize,
 }
 
 impl Default for LoggingConfig {
@@ -53,6 +110,8 @@ impl Default for LoggingConfig {
             enable_timestamps: true,
             enable_thread_ids: true,
             enable_target: false,
+            agent_io_sample_rate: 0.0,
+            agent_io_preview_chars: 256,
         }
     }
 }
@@ -60,13 +119,70 @@ impl Default for LoggingConfig {
 /// Enhanced orchestrator configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrchestratorConfig {
+    /// Hot-reloadable; running tasks finish when it is lowered
     pub max_concurrent_tasks: usize,
+    /// Seconds an agent call may run before it fails with a timeout.
+    /// Hot-reloadable; calls already running keep their timeout.
     pub task_timeout_seconds: u64,
     pub enable_hot_reload: bool,
     pub plugin_scan_interval_seconds: u64,
     pub max_plugin_size_mb: usize,
     pub enable_agent_health_checks: bool,
     pub health_check_interval_seconds: u64,
+    pub parallel_warm_up: bool,
+    /// Plugin libraries loaded, and agents warmed up, at once during startup
+    pub startup_concurrency: usize,
+    /// Agents whose warm-up failure aborts startup
+    pub critical_agents: Vec<String>,
+    /// Agents that must have warmed up before `/readyz` reports ready
+    pub required_agents: Vec<String>,
+    /// Serve requests while agents warm up instead of waiting for them;
+    /// critical agent failures are then logged rather than aborting startup
+    pub background_warm_up: bool,
+    /// Record agent executions to the in-memory trace log
+    pub enable_trace_log: bool,
+    /// Maximum number of executions kept in the trace log
+    pub trace_log_capacity: usize,
+    /// Cache outputs of agents that report themselves cacheable
+    pub enable_result_cache: bool,
+    pub result_cache_ttl_seconds: u64,
+    /// Per-agent TTL overrides, in seconds
+    pub result_cache_agent_ttls: HashMap<String, u64>,
+    /// Tasks allowed to wait for a free slot once `max_concurrent_tasks` are running
+    pub task_queue_capacity: usize,
+    /// Agents whose traffic is mirrored to a candidate replacement for comparison
+    pub shadow_agents: Vec<ShadowAgentConfig>,
+    /// Compute units each user may spend per quota window on agents that
+    /// estimate their cost, over `default_user_budget`
+    pub user_budgets: HashMap<String, f64>,
+    /// Compute units per window for users not in `user_budgets`; unset is unlimited
+    pub default_user_budget: Option<f64>,
+    /// Requests each user may make per quota window, over `default_request_quota`
+    pub user_request_quotas: HashMap<String, u64>,
+    /// Requests per window for users not in `user_request_quotas`; unset is unlimited
+    pub default_request_quota: Option<u64>,
+    /// Length of the window after which usage resets; 0 never resets it
+    pub quota_window_seconds: u64,
+    /// sled database persisting usage and admin quota overrides; unset keeps them in memory
+    pub usage_store_path: Option<String>,
+    /// Agent handling calls to names with no registered agent, given the
+    /// requested name in its input; unset rejects them as unknown
+    pub default_agent: Option<String>,
+    /// Handling of outputs that don't match the agent's output schema:
+    /// "warn" logs and returns them, "fail" fails the call, "off" skips the check
+    pub output_validation: String,
+    /// Per-agent overrides of `output_validation`
+    pub agent_output_validation: HashMap<String, String>,
+}
+
+/// Mirror a sample of calls to `primary` to `shadow`. Callers only ever see
+/// the primary's output; the shadow's agreement and latency go to metrics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowAgentConfig {
+    pub primary: String,
+    pub shadow: String,
+    /// Fraction of primary calls mirrored, in `[0.0, 1.0]`
+    pub sample_rate: f64,
 }
 
 impl Default for OrchestratorConfig {
@@ -79,6 +195,27 @@ impl Default for OrchestratorConfig {
             max_plugin_size_mb: 50,
             enable_agent_health_checks: true,
             health_check_interval_seconds: 60,
+            parallel_warm_up: true,
+            startup_concurrency: 4,
+            critical_agents: Vec::new(),
+            required_agents: Vec::new(),
+            background_warm_up: false,
+            enable_trace_log: false,
+            trace_log_capacity: 1_000,
+            enable_result_cache: false,
+            result_cache_ttl_seconds: 300,
+            result_cache_agent_ttls: HashMap::new(),
+            task_queue_capacity: 1_000,
+            shadow_agents: Vec::new(),
+            user_budgets: HashMap::new(),
+            default_user_budget: None,
+            user_request_quotas: HashMap::new(),
+            default_request_quota: None,
+            quota_window_seconds: 86_400,
+            usage_store_path: None,
+            default_agent: None,
+            output_validation: "warn".to_string(),
+            agent_output_validation: HashMap::new(),
         }
     }
 }
@@ -116,10 +253,35 @@ pub struct MemoryConfig {
     pub url: Option<String>,
     pub max_fragments: usize,
     pub embedding_dim: usize,
+    /// Default search threshold. Hot-reloadable
     pub similarity_threshold: f32,
     pub cache_size: usize,
     pub enable_persistence: bool,
     pub persistence_path: Option<PathBuf>,
+    /// Characters per fragment when ingesting uploaded files
+    pub upload_chunk_size: usize,
+    /// Characters shared between consecutive upload fragments
+    pub upload_chunk_overlap: usize,
+    /// Largest `/memory/upload` body accepted, in megabytes; applies instead
+    /// of `security.max_request_size_mb` on that route
+    pub max_upload_size_mb: usize,
+    /// Split added content longer than this many characters; `None` disables chunking
+    pub max_fragment_chars: Option<usize>,
+    /// Characters shared between consecutive chunks of one document
+    pub fragment_chunk_overlap: usize,
+    /// How added content is split: "fixed" windows or whole "sentence"s
+    pub fragment_chunker: String,
+    /// Limit on each embedding and rerank call made by memory operations.
+    /// Hot-reloadable
+    pub embedding_timeout_seconds: u64,
+    /// Age at which a fragment's search score is halved; `None` disables time decay
+    pub recency_half_life_seconds: Option<u64>,
+    /// Time limit on a search, after which the best matches found so far are
+    /// returned unreranked; `None` searches exhaustively
+    pub search_budget_ms: Option<u64>,
+    /// Tag fragments with the adding user's tenant and confine searches and
+    /// exports to it; off, every caller sees every fragment
+    pub tenant_isolation: bool,
 }
 
 impl Default for MemoryConfig {
@@ -133,6 +295,16 @@ impl Default for MemoryConfig {
             cache_size: 1_000,
             enable_persistence: false,
             persistence_path: None,
+            upload_chunk_size: 1_000,
+            upload_chunk_overlap: 200,
+            max_upload_size_mb: 50,
+            max_fragment_chars: None,
+            fragment_chunk_overlap: 200,
+            fragment_chunker: "fixed".to_string(),
+            embedding_timeout_seconds: 30,
+            recency_half_life_seconds: None,
+            search_budget_ms: None,
+            tenant_isolation: false,
         }
     }
 }
@@ -146,6 +318,10 @@ pub struct LlmConfig {
     pub max_tokens: usize,
     pub temperature: f32,
     pub enable_streaming: bool,
+    /// Named `{{variable}}` templates LLM requests can select with `"template"`
+    pub prompt_templates: HashMap<String, String>,
+    /// Directory of `<name>.tmpl` template files, overriding inline templates
+    pub prompt_template_dir: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -177,6 +353,11 @@ impl Default for LlmConfig {
             max_tokens: 512,
             temperature: 0.7,
             enable_streaming: false,
+            prompt_templates: HashMap::from([(
+                "rag".to_string(),
+                "Context:\n{{context}}\n\nQuestion: {{prompt}}".to_string(),
+            )]),
+            prompt_template_dir: None,
         }
     }
 }
@@ -190,12 +371,28 @@ pub struct SecurityConfig {
     pub api_key_header: String,
     pub allowed_origins: Vec<String>,
     pub enable_rate_limiting: bool,
+    /// Requests per minute across all clients. Hot-reloadable
     pub rate_limit_per_minute: u32,
     pub enable_cors: bool,
     pub max_request_size_mb: usize,
     pub enable_plugin_signatures: bool,
     pub plugin_allowlist_hashes: Vec<String>,
     pub script_allowlist_hashes: HashMap<String, String>,
+    pub python_script_directories: Vec<PathBuf>,
+    pub python_non_utf8_output: String,
+    /// Child processes agents may run at once across the whole process
+    pub max_subprocesses: usize,
+    /// How long a spawn waits for a free subprocess slot before failing
+    pub subprocess_wait_seconds: u64,
+    pub zig_library_directories: Vec<PathBuf>,
+    /// Zig calls that may occupy blocking threads at once, counting timed-out
+    /// calls that are still running
+    pub zig_max_running_calls: usize,
+    /// Directories LLM model files may be hot-reloaded from
+    pub llm_model_directories: Vec<PathBuf>,
+    pub http_fetch_allowed_hosts: Vec<String>,
+    pub http_fetch_max_response_bytes: usize,
+    pub http_fetch_max_redirects: usize,
     pub max_plugin_size_mb: usize,
     pub enable_resource_limits: bool,
     pub max_execution_time_seconds: u64,
@@ -204,6 +401,21 @@ pub struct SecurityConfig {
     pub session_timeout_minutes: u64,
     pub max_login_attempts: u32,
     pub lockout_duration_minutes: u64,
+    pub authorization_policy: String,
+    pub audit_log_path: PathBuf,
+    pub enable_output_redaction: bool,
+    pub output_redaction_patterns: Vec<String>,
+    pub max_redaction_scan_bytes: usize,
+    /// Encrypt and authenticate inter-node mesh traffic with the cluster secret
+    pub mesh_encryption: bool,
+    pub mesh_cluster_secret: Option<String>,
+    pub auth_db_unavailable_mode: String,
+    pub auth_db_retry_interval_seconds: u64,
+    pub auth_user_cache_size: usize,
+    pub auth_user_cache_ttl_seconds: u64,
+    pub max_input_depth: usize,
+    pub max_input_nodes: usize,
+    pub agent_input_limits: HashMap<String, crate::input_guard::InputLimits>,
 }
 
 impl Default for SecurityConfig {
@@ -221,6 +433,16 @@ impl Default for SecurityConfig {
             enable_plugin_signatures: true, // Always require signatures
             plugin_allowlist_hashes: vec![], // Empty by default - must be configured
             script_allowlist_hashes: HashMap::new(),
+            python_script_directories: vec![PathBuf::from("./python_scripts")], // Dedicated, non-tmp directory
+            python_non_utf8_output: "base64".to_string(),
+            max_subprocesses: 32,
+            subprocess_wait_seconds: 30,
+            zig_library_directories: vec![PathBuf::from("./zig_libs")],
+            zig_max_running_calls: 8,
+            llm_model_directories: vec![PathBuf::from("./models")],
+            http_fetch_allowed_hosts: vec![], // Internal hosts are blocked unless listed
+            http_fetch_max_response_bytes: 10 * 1024 * 1024, // 10MB
+            http_fetch_max_redirects: 5,
             max_plugin_size_mb: 10, // Smaller plugin size limit
             enable_resource_limits: true,
             max_execution_time_seconds: 30,
@@ -229,6 +451,20 @@ impl Default for SecurityConfig {
             session_timeout_minutes: 480, // 8 hours
             max_login_attempts: 5,
             lockout_duration_minutes: 15,
+            authorization_policy: "role".to_string(),
+            audit_log_path: PathBuf::from("./acropolis_db/audit.jsonl"),
+            enable_output_redaction: false, // Opt-in per deployment
+            output_redaction_patterns: vec![],
+            max_redaction_scan_bytes: 1024 * 1024, // 1MB scan budget
+            mesh_encryption: true,
+            mesh_cluster_secret: None, // Required when mesh encryption is enabled
+            auth_db_unavailable_mode: "fail_fast".to_string(),
+            auth_db_retry_interval_seconds: 10,
+            auth_user_cache_size: 1024,
+            auth_user_cache_ttl_seconds: 60,
+            max_input_depth: 64,
+            max_input_nodes: 100_000,
+            agent_input_limits: HashMap::new(),
         }
     }
 }
@@ -244,6 +480,24 @@ pub struct ObservabilityConfig {
     pub profiling_port: u16,
     pub otlp_endpoint: Option<String>,
     pub jaeger_endpoint: Option<String>,
+    /// Directory for metrics snapshot files; unset disables archival and
+    /// `POST /admin/metrics/snapshot`
+    pub metrics_snapshot_dir: Option<PathBuf>,
+    /// Seconds between archived snapshots; 0 writes them only on request
+    pub metrics_snapshot_interval_seconds: u64,
+    /// "json" or "csv"
+    pub metrics_snapshot_format: String,
+    /// Snapshot files kept in `metrics_snapshot_dir`, oldest deleted first;
+    /// 0 keeps every file
+    pub metrics_snapshot_retention: usize,
+    /// Idle connections kept open per host by the HTTP client shared by
+    /// health checks and webhook alerts
+    pub http_pool_max_idle_per_host: usize,
+    /// Seconds an idle pooled connection is kept before it is closed
+    pub http_pool_idle_timeout_seconds: u64,
+    pub http_connect_timeout_seconds: u64,
+    /// Default request timeout; health checks use their own `timeout_secs`
+    pub http_request_timeout_seconds: u64,
 }
 
 impl Default for ObservabilityConfig {
@@ -257,10 +511,101 @@ impl Default for ObservabilityConfig {
             profiling_port: 6060,
             otlp_endpoint: None,
             jaeger_endpoint: None,
+            metrics_snapshot_dir: None,
+            metrics_snapshot_interval_seconds: 0,
+            metrics_snapshot_format: "json".to_string(),
+            metrics_snapshot_retention: 168,
+            http_pool_max_idle_per_host: 16,
+            http_pool_idle_timeout_seconds: 90,
+            http_connect_timeout_seconds: 5,
+            http_request_timeout_seconds: 30,
+        }
+    }
+}
+
+/// Julia runtime configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JuliaConfig {
+    /// Julia worker threads, capped at the available cores. The runtime is
+    /// initialized once per process, so changes require a restart.
+    pub threads: usize,
+    /// Run a full Julia GC once the runtime has been idle this long; 0 disables
+    pub idle_gc_seconds: u64,
+    /// Run a full Julia GC after every task, trading latency for footprint
+    pub gc_after_task: bool,
+    /// Julia calls handed to the runtime at once; later ones queue in order
+    pub max_concurrent: usize,
+    /// Calls allowed to wait for a slot before new ones fail as busy
+    pub max_queued: usize,
+}
+
+impl Default for JuliaConfig {
+    fn default() -> Self {
+        Self {
+            threads: 4,
+            idle_gc_seconds: 300,
+            gc_after_task: false,
+            max_concurrent: 1,
+            max_queued: 32,
         }
     }
 }
 
+/// Agent mesh configuration, used when `enabled` is set. Encryption and
+/// the cluster secret are under `security`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeshNetworkConfig {
+    /// Join an agent mesh to share work with other nodes
+    pub enabled: bool,
+    /// Address the mesh transport listens on, e.g. "0.0.0.0:7001"
+    pub bind_address: String,
+    /// How a node is chosen among those able to run a task
+    pub load_balancing_strategy: crate::mesh::LoadBalancingStrategy,
+    /// Weights of the `Capability` strategy's score
+    #[serde(default)]
+    pub scoring: crate::mesh::ScoringWeights,
+    /// Proficiency this node advertises per capability, as non-negative scores
+    #[serde(default)]
+    pub proficiency: HashMap<String, f64>,
+}
+
+impl Default for MeshNetworkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1:7001".to_string(),
+            load_balancing_strategy: crate::mesh::LoadBalancingStrategy::LeastConnections,
+            scoring: crate::mesh::ScoringWeights::default(),
+            proficiency: HashMap::new(),
+        }
+    }
+}
+
+/// A single configuration problem found by [`Settings::validate`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    /// Dotted path of the offending field, e.g. `server.port`
+    pub field: String,
+    pub message: String,
+    pub suggestion: String,
+}
+
+impl ConfigError {
+    fn new(field: &str, message: impl Into<String>, suggestion: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.into(),
+            suggestion: suggestion.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} ({})", self.field, self.message, self.suggestion)
+    }
+}
+
 /// Main settings structure with all configuration sections
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
@@ -272,6 +617,10 @@ pub struct Settings {
     pub llm: LlmConfig,
     pub security: SecurityConfig,
     pub observability: ObservabilityConfig,
+    pub julia: JuliaConfig,
+    pub mesh: MeshNetworkConfig,
+    #[serde(default)]
+    pub websocket: crate::websocket::WebSocketConfig,
     pub db_path: Option<String>,
 
     // Legacy fields for backward compatibility
@@ -290,6 +639,9 @@ impl Default for Settings {
             llm: LlmConfig::default(),
             security: SecurityConfig::default(),
             observability: ObservabilityConfig::default(),
+            julia: JuliaConfig::default(),
+            mesh: MeshNetworkConfig::default(),
+            websocket: crate::websocket::WebSocketConfig::default(),
             db_path: None,
 
             // Legacy fields
@@ -324,8 +676,11 @@ impl Settings {
         // Apply environment variable overrides for critical settings
         Self::apply_env_overrides(&mut settings)?;
 
-        // Validate settings
-        settings.validate()?;
+        // Validate settings, reporting every problem at once
+        if let Err(errors) = settings.validate() {
+            let report: Vec<String> = errors.iter().map(|e| format!("  - {}", e)).collect();
+            return Err(anyhow!("Invalid configuration:\n{}", report.join("\n")));
+        }
 
         Ok(settings)
     }
@@ -339,6 +694,9 @@ impl Settings {
         if let Ok(port) = std::env::var("AEP_SERVER_PORT") {
             settings.server.port = port.parse()?;
         }
+        if let Ok(grpc_port) = std::env::var("AEP_GRPC_PORT") {
+            settings.server.grpc_port = grpc_port.parse()?;
+        }
 
         // Plugin settings
         if let Ok(plugin_dir) = std::env::var("AEP_PLUGIN_DIR") {
@@ -356,6 +714,9 @@ impl Settings {
         if let Ok(jwt_secret) = std::env::var("AEP_JWT_SECRET") {
             settings.security.jwt_secret = Some(jwt_secret);
         }
+        if let Ok(mesh_secret) = std::env::var("AEP_MESH_CLUSTER_SECRET") {
+            settings.security.mesh_cluster_secret = Some(mesh_secret);
+        }
 
         // Memory settings
         if let Ok(memory_url) = std::env::var("AEP_MEMORY_URL") {
@@ -372,14 +733,137 @@ impl Settings {
         Ok(())
     }
 
-    /// Validate settings for consistency and security
-    pub fn validate(&self) -> Result<()> {
+    /// Validate settings for consistency and security, collecting every
+    /// problem rather than stopping at the first
+    pub fn validate(&self) -> std::result::Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+
         // Server validation
         if self.server.port == 0 {
-            return Err(anyhow!("Server port cannot be 0"));
+            errors.push(ConfigError::new("server.port", "Server port cannot be 0", "Set a port such as 8080 or AEP_SERVER_PORT"));
+        }
+        if cfg!(feature = "with-grpc") && self.server.grpc_port == self.server.port {
+            errors.push(ConfigError::new("server.grpc_port", "gRPC port must differ from the HTTP server port", "Set a separate port such as 50051 or AEP_GRPC_PORT"));
+        }
+        match self.server.bind_target() {
+            Ok(BindTarget::Unix(_)) if cfg!(not(unix)) => {
+                errors.push(ConfigError::new("server.bind", "Unix domain sockets are not supported on this platform", "Bind to host:port instead"));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                errors.push(ConfigError::new("server.bind", e.to_string(), "Use \"127.0.0.1:8080\" or \"unix:/run/acropolis/api.sock\""));
+            }
+        }
+        if self.server.unix_socket_mode > 0o777 {
+            errors.push(ConfigError::new("server.unix_socket_mode", "Socket mode must be a permission mask", "Use an octal mode such as 0o660"));
+        }
+        match (&self.server.tls_cert_path, &self.server.tls_key_path) {
+            (Some(_), None) => errors.push(ConfigError::new("server.tls_key_path", "TLS certificate is set without a private key", "Set tls_key_path to the certificate's PEM key")),
+            (None, Some(_)) => errors.push(ConfigError::new("server.tls_cert_path", "TLS private key is set without a certificate", "Set tls_cert_path to the PEM certificate chain")),
+            (Some(_), Some(_)) if matches!(self.server.bind_target(), Ok(BindTarget::Unix(_))) => {
+                errors.push(ConfigError::new("server.bind", "TLS is not supported on Unix domain sockets", "Bind to host:port, or remove tls_cert_path and tls_key_path"));
+            }
+            _ => {}
+        }
+        if self.server.shutdown_timeout_seconds == 0 {
+            errors.push(ConfigError::new("server.shutdown_timeout_seconds", "Shutdown timeout cannot be 0", "Allow at least a few seconds, e.g. 30"));
         }
         if self.server.max_connections == 0 {
-            return Err(anyhow!("Max connections cannot be 0"));
+            errors.push(ConfigError::new("server.max_connections", "Max connections cannot be 0", "Set at least 1"));
+        }
+
+        // Logging validation
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.logging.level) {
+            errors.push(ConfigError::new("logging.level", format!("Invalid log level: {}", e), "Use a level such as \"info\" or a RUST_LOG-style directive"));
+        }
+        if !(0.0..=1.0).contains(&self.logging.agent_io_sample_rate) {
+            errors.push(ConfigError::new("logging.agent_io_sample_rate", format!("Sample rate {} is outside [0.0, 1.0]", self.logging.agent_io_sample_rate), "Use a fraction such as 0.01, or 0 to disable"));
+        }
+        if self.logging.agent_io_sample_rate > 0.0 && self.logging.agent_io_preview_chars == 0 {
+            errors.push(ConfigError::new("logging.agent_io_preview_chars", "Preview length cannot be 0 when agent I/O logging is enabled", "Set a length such as 256"));
+        }
+
+        // Orchestrator validation
+        if self.orchestrator.max_concurrent_tasks == 0 {
+            errors.push(ConfigError::new("orchestrator.max_concurrent_tasks", "Max concurrent tasks cannot be 0", "Set at least 1"));
+        }
+        if self.orchestrator.task_timeout_seconds == 0 {
+            errors.push(ConfigError::new("orchestrator.task_timeout_seconds", "Task timeout cannot be 0", "Set at least 1"));
+        }
+        if self.orchestrator.startup_concurrency == 0 {
+            errors.push(ConfigError::new("orchestrator.startup_concurrency", "Startup concurrency cannot be 0", "Set at least 1"));
+        }
+        let output_modes = std::iter::once(("orchestrator.output_validation".to_string(), &self.orchestrator.output_validation))
+            .chain(self.orchestrator.agent_output_validation.iter()
+                .map(|(agent, mode)| (format!("orchestrator.agent_output_validation.{}", agent), mode)));
+        for (field, mode) in output_modes {
+            if let Err(e) = crate::output_schema::OutputValidationMode::parse(mode) {
+                errors.push(ConfigError::new(&field, e.to_string(), "Use \"warn\", \"fail\" or \"off\""));
+            }
+        }
+        if self.orchestrator.task_queue_capacity == 0 {
+            errors.push(ConfigError::new("orchestrator.task_queue_capacity", "Task queue capacity cannot be 0", "Set at least 1"));
+        }
+        for (user, budget) in &self.orchestrator.user_budgets {
+            if !budget.is_finite() || *budget < 0.0 {
+                errors.push(ConfigError::new("orchestrator.user_budgets", format!("Budget {} for '{}' is not a non-negative number", budget, user), "Set a budget such as 100000"));
+            }
+        }
+        if self.orchestrator.default_agent.as_deref().map_or(false, |name| name.trim().is_empty()) {
+            errors.push(ConfigError::new("orchestrator.default_agent", "Default agent name cannot be empty", "Name a registered agent, or leave it unset"));
+        }
+        if let Some(budget) = self.orchestrator.default_user_budget {
+            if !budget.is_finite() || budget < 0.0 {
+                errors.push(ConfigError::new("orchestrator.default_user_budget", format!("Budget {} is not a non-negative number", budget), "Set a budget such as 100000, or leave it unset"));
+            }
+        }
+        let mut shadowed = std::collections::HashSet::new();
+        for shadow in &self.orchestrator.shadow_agents {
+            if !(0.0..=1.0).contains(&shadow.sample_rate) {
+                errors.push(ConfigError::new("orchestrator.shadow_agents", format!("Sample rate {} for '{}' is outside [0.0, 1.0]", shadow.sample_rate, shadow.primary), "Use a fraction such as 0.1"));
+            }
+            if shadow.primary == shadow.shadow {
+                errors.push(ConfigError::new("orchestrator.shadow_agents", format!("Agent '{}' cannot shadow itself", shadow.primary), "Name a different shadow agent"));
+            }
+            if !shadowed.insert(&shadow.primary) {
+                errors.push(ConfigError::new("orchestrator.shadow_agents", format!("Agent '{}' has more than one shadow", shadow.primary), "Configure one shadow per primary agent"));
+            }
+        }
+
+        // Julia validation
+        if self.julia.threads == 0 {
+            errors.push(ConfigError::new("julia.threads", "Julia threads must be at least 1", "Set at least 1"));
+        }
+        if self.julia.max_concurrent == 0 {
+            errors.push(ConfigError::new("julia.max_concurrent", "Julia concurrency must be at least 1", "Set at least 1"));
+        }
+
+        // Mesh validation
+        if self.mesh.bind_address.parse::<std::net::SocketAddr>().is_err() {
+            errors.push(ConfigError::new("mesh.bind_address", format!("Invalid mesh bind address: {}", self.mesh.bind_address), "Use host:port, e.g. 0.0.0.0:7001"));
+        }
+        let scoring = &self.mesh.scoring;
+        let weights = [scoring.proficiency_weight, scoring.load_weight, scoring.priority_weight, scoring.default_proficiency];
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            errors.push(ConfigError::new("mesh.scoring", "Scoring weights must be finite and non-negative", "Use values such as the defaults of 1.0, 1.0, 0.5 and 0.5"));
+        }
+        for (capability, score) in &self.mesh.proficiency {
+            if !score.is_finite() || *score < 0.0 {
+                errors.push(ConfigError::new(&format!("mesh.proficiency.{}", capability), "Proficiency must be finite and non-negative", "Use a score such as 0.9"));
+            }
+        }
+
+        // WebSocket validation
+        if self.websocket.max_message_size == 0 {
+            errors.push(ConfigError::new("websocket.max_message_size", "Message size limit must be greater than 0", "Use a limit such as 1048576 (1 MB)"));
+        }
+        if self.websocket.outbound_queue_size == 0 {
+            errors.push(ConfigError::new("websocket.outbound_queue_size", "Outbound queue must hold at least one message", "Use a size such as 1000"));
+        }
+        for header in &self.websocket.trusted_forwarded_headers {
+            if !header.eq_ignore_ascii_case("x-forwarded-for") && !header.eq_ignore_ascii_case("x-real-ip") {
+                errors.push(ConfigError::new("websocket.trusted_forwarded_headers", format!("Unsupported forwarded header: {}", header), "Use X-Forwarded-For or X-Real-IP"));
+            }
         }
 
         // Plugin validation
@@ -389,25 +873,149 @@ impl Settings {
 
         // Memory validation
         if self.memory.provider == "redis" && self.memory.url.is_none() {
-            return Err(anyhow!("Redis provider requires AEP_MEMORY_URL environment variable"));
+            errors.push(ConfigError::new("memory.url", "Redis provider requires a URL", "Set AEP_MEMORY_URL or memory.url, or use provider \"in_memory\""));
+        }
+        if self.memory.embedding_dim == 0 {
+            errors.push(ConfigError::new("memory.embedding_dim", "Embedding dimension cannot be 0", "Match the embedding model's output size, e.g. 384"));
+        }
+        if self.memory.max_fragments == 0 {
+            errors.push(ConfigError::new("memory.max_fragments", "Max fragments cannot be 0", "Set at least 1"));
+        }
+        if let Err(e) = crate::memory::validate_similarity_threshold(self.memory.similarity_threshold) {
+            errors.push(ConfigError::new("memory.similarity_threshold", e.to_string(), "Use a cosine similarity such as 0.1"));
+        }
+        if let Err(e) = crate::memory::StreamingChunker::new(self.memory.upload_chunk_size, self.memory.upload_chunk_overlap) {
+            errors.push(ConfigError::new("memory.upload_chunk_overlap", e.to_string(), "Use a non-zero chunk size larger than the overlap"));
+        }
+        if self.memory.max_upload_size_mb == 0 {
+            errors.push(ConfigError::new("memory.max_upload_size_mb", "Max upload size cannot be 0", "Set a size such as 50 MB"));
+        }
+        if self.memory.embedding_timeout_seconds == 0 {
+            errors.push(ConfigError::new("memory.embedding_timeout_seconds", "Embedding timeout cannot be 0", "Set a timeout such as 30 seconds"));
+        }
+        if self.memory.recency_half_life_seconds == Some(0) {
+            errors.push(ConfigError::new("memory.recency_half_life_seconds", "Recency half-life cannot be 0", "Set a half-life such as 604800 (one week), or remove it to disable decay"));
+        }
+        if self.memory.search_budget_ms == Some(0) {
+            errors.push(ConfigError::new("memory.search_budget_ms", "Search budget cannot be 0", "Set a budget such as 200 milliseconds, or remove it to search exhaustively"));
+        }
+        if let Some(max_chars) = self.memory.max_fragment_chars {
+            if let Err(e) = crate::memory::FixedSizeChunker::new(max_chars, self.memory.fragment_chunk_overlap) {
+                errors.push(ConfigError::new("memory.fragment_chunk_overlap", e.to_string(), "Use a non-zero max_fragment_chars larger than the overlap"));
+            }
+        }
+        if !crate::memory::chunking::CHUNKER_NAMES.contains(&self.memory.fragment_chunker.as_str()) {
+            errors.push(ConfigError::new(
+                "memory.fragment_chunker",
+                format!("Unknown chunker '{}'", self.memory.fragment_chunker),
+                format!("Use one of: {}", crate::memory::chunking::CHUNKER_NAMES.join(", ")),
+            ));
         }
 
         // Security validation
+        if self.security.rate_limit_per_minute == 0 {
+            errors.push(ConfigError::new("security.rate_limit_per_minute", "Rate limit cannot be 0", "Set at least 1, or disable enable_rate_limiting"));
+        }
         if self.security.enable_authentication && self.security.jwt_secret.is_none() {
-            return Err(anyhow!("Authentication enabled but no JWT secret provided"));
+            errors.push(ConfigError::new(
+                "security.jwt_secret",
+                "Authentication enabled but no JWT secret provided",
+                "Set AEP_JWT_SECRET to a random string of at least 32 characters, then run 'init-admin'",
+            ));
+        }
+        if self.mesh.enabled
+            && self.security.mesh_encryption
+            && self.security.mesh_cluster_secret.is_none()
+        {
+            errors.push(ConfigError::new(
+                "security.mesh_cluster_secret",
+                "Mesh encryption enabled but no cluster secret provided",
+                "Set AEP_MESH_CLUSTER_SECRET to the same random string on every node",
+            ));
+        }
+        if let Err(e) = crate::auth::create_authorizer(&self.security.authorization_policy) {
+            errors.push(ConfigError::new("security.authorization_policy", e.to_string(), "Use \"role\" or \"attribute\""));
+        }
+        if !matches!(self.security.auth_db_unavailable_mode.as_str(), "fail_fast" | "degraded") {
+            errors.push(ConfigError::new(
+                "security.auth_db_unavailable_mode",
+                format!("Unknown mode '{}'", self.security.auth_db_unavailable_mode),
+                "Use \"fail_fast\" or \"degraded\"",
+            ));
+        }
+        if self.security.auth_db_retry_interval_seconds == 0 {
+            errors.push(ConfigError::new("security.auth_db_retry_interval_seconds", "Auth database retry interval cannot be 0", "Set at least 1"));
+        }
+        if !matches!(self.security.python_non_utf8_output.as_str(), "base64" | "error") {
+            errors.push(ConfigError::new(
+                "security.python_non_utf8_output",
+                format!("Unknown mode '{}'", self.security.python_non_utf8_output),
+                "Use \"base64\" or \"error\"",
+            ));
+        }
+        if self.security.max_subprocesses == 0 {
+            errors.push(ConfigError::new("security.max_subprocesses", "Subprocess limit cannot be 0", "Allow at least 1, e.g. 32"));
+        }
+        if self.security.zig_max_running_calls == 0 {
+            errors.push(ConfigError::new("security.zig_max_running_calls", "Zig call limit cannot be 0", "Allow at least 1, e.g. 8"));
+        }
+        if self.security.auth_user_cache_size == 0 {
+            errors.push(ConfigError::new("security.auth_user_cache_size", "Auth user cache size cannot be 0", "Set at least 1; use a TTL of 0 to disable caching"));
+        }
+        if self.security.max_input_depth == 0 {
+            errors.push(ConfigError::new("security.max_input_depth", "Input nesting depth limit must be greater than 0", "Use the default of 64"));
+        }
+        if self.security.max_input_nodes == 0 {
+            errors.push(ConfigError::new("security.max_input_nodes", "Input node limit must be greater than 0", "Use the default of 100000"));
+        }
+        if self.security.enable_output_redaction {
+            if self.security.max_redaction_scan_bytes == 0 {
+                errors.push(ConfigError::new("security.max_redaction_scan_bytes", "Output redaction scan budget cannot be 0", "Set a budget such as 1048576"));
+            } else if let Err(e) = crate::redaction::OutputRedactor::from_config(&self.security) {
+                errors.push(ConfigError::new("security.output_redaction_patterns", e.to_string(), "Fix or remove the invalid pattern"));
+            }
+        }
+
+        // Observability validation
+        if !(0.0..=1.0).contains(&self.observability.tracing_sampler) {
+            errors.push(ConfigError::new("observability.tracing_sampler", "Tracing sampler must be between 0.0 and 1.0", "Use a ratio such as 0.1"));
+        }
+        if let Err(e) = crate::monitoring::SnapshotFormat::parse(&self.observability.metrics_snapshot_format) {
+            errors.push(ConfigError::new("observability.metrics_snapshot_format", e.to_string(), "Use \"json\" or \"csv\""));
+        }
+        if self.observability.metrics_snapshot_interval_seconds > 0 && self.observability.metrics_snapshot_dir.is_none() {
+            errors.push(ConfigError::new("observability.metrics_snapshot_interval_seconds", "Periodic metrics snapshots need observability.metrics_snapshot_dir", "Set metrics_snapshot_dir or use an interval of 0"));
+        }
+        if self.observability.http_connect_timeout_seconds == 0 {
+            errors.push(ConfigError::new("observability.http_connect_timeout_seconds", "HTTP connect timeout cannot be 0", "Use a few seconds, such as 5"));
+        }
+        if self.observability.http_request_timeout_seconds == 0 {
+            errors.push(ConfigError::new("observability.http_request_timeout_seconds", "HTTP request timeout cannot be 0", "Use a value such as 30"));
         }
 
         // LLM validation
+        if let Err(e) = crate::prompt::PromptTemplates::from_config(&self.llm) {
+            errors.push(ConfigError::new("llm.prompt_templates", format!("{:#}", e), "Fix the template syntax or the template directory path"));
+        }
         if self.llm.provider == "llama" {
-            let default_model = self.llm.models.get(&self.llm.default_model)
-                .ok_or_else(|| anyhow!("Default LLM model not found"))?;
-
-            if !std::path::Path::new(&default_model.path).exists() {
-                warn!("LLM model file does not exist: {}", default_model.path);
+            match self.llm.models.get(&self.llm.default_model) {
+                Some(default_model) if !std::path::Path::new(&default_model.path).exists() => {
+                    warn!("LLM model file does not exist: {}", default_model.path);
+                }
+                Some(_) => {}
+                None => errors.push(ConfigError::new(
+                    "llm.default_model",
+                    format!("Default LLM model '{}' not found", self.llm.default_model),
+                    "Name one of the models defined under [llm.models]",
+                )),
             }
         }
 
-        Ok(())
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
     }
 
     /// Get a configuration value by path (e.g., "server.port")
@@ -437,3 +1045,113 @@ impl Settings {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid_settings() -> Settings {
+        let mut settings = Settings::default();
+        settings.security.jwt_secret = Some("a".repeat(32));
+        settings
+    }
+
+    fn invalid_fields(settings: &Settings) -> Vec<String> {
+        settings.validate().unwrap_err().into_iter().map(|e| e.field).collect()
+    }
+
+    #[test]
+    fn test_default_settings_with_secret_are_valid() {
+        assert!(valid_settings().validate().is_ok());
+    }
+
+    #[test]
+    fn test_validation_collects_every_error() {
+        let mut settings = valid_settings();
+        settings.server.port = 0;
+        settings.memory.provider = "redis".to_string();
+        settings.memory.embedding_dim = 0;
+        settings.security.jwt_secret = None;
+
+        let fields = invalid_fields(&settings);
+        assert_eq!(fields, vec!["server.port", "memory.url", "memory.embedding_dim", "security.jwt_secret"]);
+    }
+
+    #[test]
+    fn test_validation_reports_invalid_values() {
+        let mut settings = valid_settings();
+        settings.memory.similarity_threshold = 2.0;
+        settings.memory.upload_chunk_overlap = settings.memory.upload_chunk_size;
+        settings.security.auth_db_unavailable_mode = "retry".to_string();
+        settings.observability.tracing_sampler = 1.5;
+        settings.llm.default_model = "missing".to_string();
+
+        let errors = settings.validate().unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec![
+            "memory.similarity_threshold",
+            "memory.upload_chunk_overlap",
+            "security.auth_db_unavailable_mode",
+            "observability.tracing_sampler",
+            "llm.default_model",
+        ]);
+        assert!(errors[2].to_string().contains("Use \"fail_fast\" or \"degraded\""));
+    }
+
+    #[test]
+    fn test_input_limits_are_reported_separately() {
+        let mut settings = valid_settings();
+        settings.security.max_input_nodes = 0;
+        assert_eq!(invalid_fields(&settings), vec!["security.max_input_nodes"]);
+
+        settings.security.max_input_depth = 0;
+        assert_eq!(invalid_fields(&settings), vec!["security.max_input_depth", "security.max_input_nodes"]);
+    }
+
+    #[test]
+    fn test_websocket_proxy_settings_are_validated() {
+        let mut settings = valid_settings();
+        settings.websocket.trusted_proxies = vec!["10.0.0.2".parse().unwrap()];
+        settings.websocket.trusted_forwarded_headers = vec!["X-Real-IP".to_string()];
+        assert!(settings.validate().is_ok());
+
+        settings.websocket.trusted_forwarded_headers.push("Forwarded".to_string());
+        assert_eq!(invalid_fields(&settings), vec!["websocket.trusted_forwarded_headers"]);
+    }
+
+    #[test]
+    fn test_snapshot_interval_requires_dir() {
+        let mut settings = valid_settings();
+        settings.observability.metrics_snapshot_interval_seconds = 3600;
+        assert_eq!(invalid_fields(&settings), vec!["observability.metrics_snapshot_interval_seconds"]);
+
+        settings.observability.metrics_snapshot_dir = Some(PathBuf::from("./metrics"));
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn test_bind_target() {
+        let mut server = ServerConfig::default();
+        assert_eq!(server.bind_target().unwrap(), BindTarget::Tcp("127.0.0.1:8080".parse().unwrap()));
+
+        server.bind = Some("unix:/run/acropolis/api.sock".to_string());
+        assert_eq!(server.bind_target().unwrap(), BindTarget::Unix(PathBuf::from("/run/acropolis/api.sock")));
+
+        server.bind = Some("unix:".to_string());
+        assert!(server.bind_target().is_err());
+        server.bind = Some("localhost".to_string());
+        assert!(server.bind_target().is_err());
+    }
+    #[test]
+    fn test_tls_requires_cert_and_key_on_tcp() {
+        let mut settings = valid_settings();
+        settings.server.tls_cert_path = Some(PathBuf::from("cert.pem"));
+        assert_eq!(invalid_fields(&settings), vec!["server.tls_key_path"]);
+
+        settings.server.tls_key_path = Some(PathBuf::from("key.pem"));
+        assert!(settings.validate().is_ok());
+
+        settings.server.bind = Some("unix:/run/acropolis/api.sock".to_string());
+        assert_eq!(invalid_fields(&settings), vec!["server.bind"]);
+    }
+}
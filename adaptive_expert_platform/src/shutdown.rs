@@ -0,0 +1,217 @@
+//! Ordered, deadline-bounded shutdown of the server's subsystems.
+//!
+//! Subsystems register hooks against a [`ShutdownPhase`]. Phases run in
+//! order, hooks within a phase run concurrently, and the whole sequence
+//! shares one deadline: once it passes, hooks still running are abandoned
+//! and later phases are skipped, so a stuck subsystem cannot hold up exit.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tracing::{error, info, warn};
+
+/// Shutdown stages, run in declaration order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownPhase {
+    /// Refuse new connections and sessions
+    StopAccepting,
+    /// Let in-flight requests and tasks finish
+    Drain,
+    /// Stop background loops and agent instances
+    StopBackground,
+    /// Write out buffered metrics and audit events
+    Flush,
+}
+
+impl ShutdownPhase {
+    pub const ALL: [ShutdownPhase; 4] = [
+        ShutdownPhase::StopAccepting,
+        ShutdownPhase::Drain,
+        ShutdownPhase::StopBackground,
+        ShutdownPhase::Flush,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShutdownPhase::StopAccepting => "stop_accepting",
+            ShutdownPhase::Drain => "drain",
+            ShutdownPhase::StopBackground => "stop_background",
+            ShutdownPhase::Flush => "flush",
+        }
+    }
+}
+
+/// How a single hook ended
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookOutcome {
+    Completed,
+    Failed(String),
+    /// Still running when the deadline passed
+    TimedOut,
+    /// Not started because the deadline had already passed
+    Skipped,
+}
+
+/// Outcome of one registered hook
+#[derive(Debug, Clone, Serialize)]
+pub struct HookReport {
+    pub phase: ShutdownPhase,
+    pub name: String,
+    pub outcome: HookOutcome,
+}
+
+struct Hook {
+    phase: ShutdownPhase,
+    name: String,
+    run: Box<dyn FnOnce() -> BoxFuture<'static, Result<()>> + Send>,
+}
+
+/// Runs registered shutdown hooks phase by phase under a total deadline
+pub struct ShutdownCoordinator {
+    timeout: Duration,
+    hooks: Mutex<Vec<Hook>>,
+    deadline: Mutex<Option<Instant>>,
+}
+
+impl ShutdownCoordinator {
+    /// Allow `timeout` for every phase together, counted from the first phase run
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            hooks: Mutex::new(Vec::new()),
+            deadline: Mutex::new(None),
+        }
+    }
+
+    /// Run `hook` during `phase`. Hooks in the same phase run concurrently.
+    pub fn register<F, Fut>(&self, phase: ShutdownPhase, name: impl Into<String>, hook: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.hooks.lock().push(Hook {
+            phase,
+            name: name.into(),
+            run: Box::new(move || Box::pin(hook())),
+        });
+    }
+
+    /// Time left before the deadline, starting the clock if no phase has run yet
+    pub fn remaining(&self) -> Duration {
+        let deadline = *self.deadline.lock().get_or_insert_with(|| Instant::now() + self.timeout);
+        deadline.saturating_duration_since(Instant::now())
+    }
+
+    /// Run the hooks registered for `phase`. Each hook runs at most once, so
+    /// running a phase again only picks up hooks registered since.
+    pub async fn run_phase(&self, phase: ShutdownPhase) -> Vec<HookReport> {
+        let hooks: Vec<Hook> = {
+            let mut all = self.hooks.lock();
+            let (selected, rest): (Vec<Hook>, Vec<Hook>) = std::mem::take(&mut *all)
+                .into_iter()
+                .partition(|hook| hook.phase == phase);
+            *all = rest;
+            selected
+        };
+        if hooks.is_empty() {
+            return Vec::new();
+        }
+
+        let remaining = self.remaining();
+        if remaining.is_zero() {
+            warn!("Shutdown deadline passed, skipping phase '{}'", phase.as_str());
+            return hooks.into_iter()
+                .map(|hook| HookReport { phase, name: hook.name, outcome: HookOutcome::Skipped })
+                .collect();
+        }
+
+        info!("Shutdown phase '{}': running {} hooks", phase.as_str(), hooks.len());
+        let start = Instant::now();
+        let reports = futures::future::join_all(hooks.into_iter().map(|hook| async move {
+            let outcome = match tokio::time::timeout(remaining, (hook.run)()).await {
+                Ok(Ok(())) => HookOutcome::Completed,
+                Ok(Err(e)) => {
+                    error!("Shutdown hook '{}' failed: {:#}", hook.name, e);
+                    HookOutcome::Failed(format!("{:#}", e))
+                }
+                Err(_) => {
+                    warn!("Shutdown hook '{}' did not finish before the deadline", hook.name);
+                    HookOutcome::TimedOut
+                }
+            };
+            HookReport { phase, name: hook.name, outcome }
+        })).await;
+        info!("Shutdown phase '{}' finished in {}ms", phase.as_str(), start.elapsed().as_millis());
+        reports
+    }
+
+    /// Run the phases from `first` onwards, in order
+    pub async fn run_from(&self, first: ShutdownPhase) -> Vec<HookReport> {
+        let mut reports = Vec::new();
+        for phase in ShutdownPhase::ALL.into_iter().filter(|phase| *phase >= first) {
+            reports.extend(self.run_phase(phase).await);
+        }
+        reports
+    }
+
+    /// Run every phase in order
+    pub async fn run(&self) -> Vec<HookReport> {
+        self.run_from(ShutdownPhase::StopAccepting).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_phases_run_in_order() {
+        let coordinator = ShutdownCoordinator::new(Duration::from_secs(5));
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        // Registered out of order on purpose
+        for (phase, name) in [
+            (ShutdownPhase::Flush, "audit"),
+            (ShutdownPhase::StopAccepting, "websocket"),
+            (ShutdownPhase::StopBackground, "monitoring"),
+            (ShutdownPhase::Drain, "tasks"),
+        ] {
+            let order = order.clone();
+            coordinator.register(phase, name, move || async move {
+                order.lock().push(name);
+                Ok(())
+            });
+        }
+        coordinator.register(ShutdownPhase::Drain, "broken", || async { Err(anyhow::anyhow!("boom")) });
+
+        let reports = coordinator.run().await;
+        assert_eq!(*order.lock(), vec!["websocket", "tasks", "monitoring", "audit"]);
+        assert_eq!(reports.len(), 5);
+        let broken = reports.iter().find(|report| report.name == "broken").unwrap();
+        assert_eq!(broken.outcome, HookOutcome::Failed("boom".to_string()));
+
+        // Hooks run once
+        assert!(coordinator.run().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deadline_abandons_stuck_hooks() {
+        let coordinator = ShutdownCoordinator::new(Duration::from_millis(50));
+        coordinator.register(ShutdownPhase::Drain, "stuck", || async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(())
+        });
+        coordinator.register(ShutdownPhase::Flush, "late", || async { Ok(()) });
+
+        let reports = coordinator.run().await;
+        assert_eq!(reports[0].outcome, HookOutcome::TimedOut);
+        assert_eq!(reports[1].outcome, HookOutcome::Skipped);
+    }
+}
@@ -1,7 +1,9 @@
 //! Logging and telemetry initialization with conditional OpenTelemetry support.
 
-use anyhow::Result;
-use tracing_subscriber::{layer::SubscriberExt, EnvFilter, Registry};
+use std::sync::OnceLock;
+
+use anyhow::{anyhow, Result};
+use tracing_subscriber::{layer::SubscriberExt, reload, EnvFilter, Registry};
 
 #[cfg(feature = "with-observability")]
 use {
@@ -10,10 +12,19 @@ use {
     tracing_opentelemetry,
 };
 
-/// Initialize logging and telemetry based on configuration
-pub fn init(otlp_endpoint: Option<&str>) -> Result<()> {
+type FilterHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Swaps the active log filter; set once logging is initialized
+static LOG_FILTER: OnceLock<FilterHandle> = OnceLock::new();
+
+/// Initialize logging and telemetry based on configuration.
+///
+/// `RUST_LOG` takes precedence over `level` when set.
+pub fn init(level: &str, otlp_endpoint: Option<&str>) -> Result<()> {
     let filter = EnvFilter::try_from_default_env()
-        .or_else(|_| EnvFilter::try_new("info"))?;
+        .or_else(|_| EnvFilter::try_new(level))?;
+    let (filter, handle) = reload::Layer::new(filter);
+    let _ = LOG_FILTER.set(handle);
 
     #[cfg(feature = "with-observability")]
     if let Some(endpoint) = otlp_endpoint {
@@ -27,7 +38,7 @@ pub fn init(otlp_endpoint: Option<&str>) -> Result<()> {
 }
 
 #[cfg(feature = "with-observability")]
-fn init_with_otlp(endpoint: &str, filter: EnvFilter) -> Result<()> {
+fn init_with_otlp(endpoint: &str, filter: reload::Layer<EnvFilter, Registry>) -> Result<()> {
     let tracer = otlp::new_pipeline()
         .tracing()
         .with_exporter(otlp::new_exporter().tonic().with_endpoint(endpoint))
@@ -52,7 +63,7 @@ fn init_with_otlp(endpoint: &str, filter: EnvFilter) -> Result<()> {
     Ok(())
 }
 
-fn init_console_only(filter: EnvFilter) -> Result<()> {
+fn init_console_only(filter: reload::Layer<EnvFilter, Registry>) -> Result<()> {
     let subscriber = Registry::default()
         .with(filter)
         .with(tracing_subscriber::fmt::layer().with_target(false));
@@ -61,3 +72,14 @@ fn init_console_only(filter: EnvFilter) -> Result<()> {
     tracing::info!("Console logging initialized");
     Ok(())
 }
+
+/// Replace the log filter with `level`, an `EnvFilter` directive such as
+/// "debug" or "info,adaptive_expert_platform=trace"
+pub fn set_log_level(level: &str) -> Result<()> {
+    let filter = EnvFilter::try_new(level)
+        .map_err(|e| anyhow!("Invalid log level {:?}: {}", level, e))?;
+    let handle = LOG_FILTER.get().ok_or_else(|| anyhow!("Logging is not initialized"))?;
+    handle.reload(filter)?;
+    tracing::info!("Log level set to {}", level);
+    Ok(())
+}
@@ -0,0 +1,117 @@
+//! Built-in HTTPS for standalone deployments.
+//!
+//! When `server.tls_cert_path` and `server.tls_key_path` are set the HTTP
+//! server terminates TLS itself with rustls instead of relying on a proxy.
+//! The certificate and key are PEM files; both are loaded and checked at
+//! startup, and rewritten files are picked up without a restart.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use axum_server::tls_rustls::RustlsConfig;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tracing::{error, info, warn};
+
+/// Load a PEM certificate chain and private key.
+///
+/// Fails if either file is unreadable or malformed, or if the key does not
+/// belong to the certificate.
+pub async fn load_tls_config(cert_path: &Path, key_path: &Path) -> Result<RustlsConfig> {
+    RustlsConfig::from_pem_file(cert_path, key_path).await.map_err(|e| {
+        anyhow!(
+            "Invalid TLS certificate {:?} or key {:?}: {}",
+            cert_path, key_path, e
+        )
+    })
+}
+
+/// Reload `config` whenever the certificate or key file changes.
+///
+/// The parent directories are watched rather than the files, so rotations
+/// that replace a file by renaming over it are seen too. A pair that fails
+/// to load (e.g. the key was written before the certificate) is logged and
+/// the previous certificate stays in use until a later change fixes it.
+pub fn watch_certificates(config: RustlsConfig, cert_path: PathBuf, key_path: PathBuf) -> Result<RecommendedWatcher> {
+    let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+    let mut watcher = RecommendedWatcher::new(
+        move |res| {
+            if let Err(e) = tx.blocking_send(res) {
+                error!("Failed to send TLS certificate watcher event: {}", e);
+            }
+        },
+        notify::Config::default().with_poll_interval(Duration::from_secs(2)),
+    )?;
+    // Events name paths under the watched directories, so compare against
+    // the files' locations within those
+    let watched = [watched_path(&cert_path)?, watched_path(&key_path)?];
+    let mut dirs: Vec<&Path> = watched.iter().filter_map(|path| path.parent()).collect();
+    dirs.dedup();
+    for dir in dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+
+    tokio::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            match event {
+                Ok(Event { kind: EventKind::Create(_) | EventKind::Modify(_), paths, .. })
+                    if paths.iter().any(|p| watched.contains(p)) =>
+                {
+                    match config.reload_from_pem_file(&cert_path, &key_path).await {
+                        Ok(()) => info!("Reloaded TLS certificate from {:?}", cert_path),
+                        Err(e) => warn!("Keeping the current TLS certificate; reload failed: {}", e),
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => error!("TLS certificate watcher error: {}", e),
+            }
+        }
+    });
+
+    info!("Watching {:?} and {:?} for TLS certificate changes", cert_path, key_path);
+    Ok(watcher)
+}
+
+/// `path` within its canonical parent directory; the file itself is not
+/// resolved, since rotation may point a symlink elsewhere
+fn watched_path(path: &Path) -> Result<PathBuf> {
+    let file_name = path.file_name()
+        .ok_or_else(|| anyhow!("TLS file path {:?} does not name a file", path))?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let parent = std::fs::canonicalize(parent)
+        .map_err(|e| anyhow!("Cannot watch TLS directory {:?}: {}", parent, e))?;
+    Ok(parent.join(file_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_invalid_certificates_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let cert = dir.path().join("cert.pem");
+        let key = dir.path().join("key.pem");
+
+        let err = load_tls_config(&cert, &key).await.unwrap_err();
+        assert!(err.to_string().contains("Invalid TLS certificate"));
+
+        std::fs::write(&cert, "-----BEGIN CERTIFICATE-----\nbm90IGEgY2VydA==\n-----END CERTIFICATE-----\n").unwrap();
+        std::fs::write(&key, "not a key").unwrap();
+        assert!(load_tls_config(&cert, &key).await.is_err());
+    }
+
+    #[test]
+    fn test_watched_path_resolves_parent_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("certs");
+        std::fs::create_dir(&nested).unwrap();
+
+        let path = watched_path(&nested.join("..").join("certs").join("cert.pem")).unwrap();
+        assert_eq!(path, nested.canonicalize().unwrap().join("cert.pem"));
+        assert!(watched_path(Path::new("/")).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! Bounded in-memory log of agent executions for debugging and replay.
+//!
+//! When enabled, the orchestrator records every agent call with its input,
+//! output or error, and timing. The oldest entries are dropped once the
+//! configured capacity is reached. Results served from the result cache are
+//! recorded too, marked `cached`. Inputs and outputs pass through the output
+//! redactor (when configured) before being stored, so a replay re-dispatches
+//! the redacted input.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::redaction::OutputRedactor;
+
+/// Entries returned by a query when no limit is given
+const DEFAULT_QUERY_LIMIT: usize = 100;
+
+/// A recorded agent execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceEntry {
+    pub id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub agent: String,
+    pub input: Value,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    /// The output was served from the result cache without running the agent
+    #[serde(default)]
+    pub cached: bool,
+}
+
+/// Filters for [`TraceLog::query`]
+#[derive(Debug, Default, Deserialize)]
+pub struct TraceQuery {
+    pub agent: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Ring buffer of recent agent executions
+pub struct TraceLog {
+    entries: Mutex<VecDeque<TraceEntry>>,
+    capacity: usize,
+    enabled: AtomicBool,
+    redactor: Option<Arc<OutputRedactor>>,
+}
+
+impl TraceLog {
+    pub fn new(capacity: usize, enabled: bool) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity.min(1024))),
+            capacity,
+            enabled: AtomicBool::new(enabled),
+            redactor: None,
+        }
+    }
+
+    /// Redact secrets from inputs and outputs before they are stored
+    pub fn with_redactor(mut self, redactor: Option<Arc<OutputRedactor>>) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Start or stop recording; existing entries are kept
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Record an execution if recording is enabled
+    pub async fn record(
+        &self,
+        agent: &str,
+        input: &Value,
+        result: &anyhow::Result<Value>,
+        duration: std::time::Duration,
+        cached: bool,
+    ) {
+        if !self.is_enabled() || self.capacity == 0 {
+            return;
+        }
+
+        let (output, error) = match result {
+            Ok(Value::String(output)) => (Some(self.redact(output)), None),
+            Ok(other) => (Some(self.redact(&other.to_string())), None),
+            Err(e) => (None, Some(self.redact(&e.to_string()))),
+        };
+        let entry = TraceEntry {
+            id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            agent: agent.to_string(),
+            input: self.redact_value(input.clone()),
+            output,
+            error,
+            duration_ms: duration.as_millis() as u64,
+            cached,
+        };
+
+        let mut entries = self.entries.lock().await;
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Most recent entries first, optionally filtered by agent
+    pub async fn query(&self, query: &TraceQuery) -> Vec<TraceEntry> {
+        let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+        self.entries.lock().await
+            .iter()
+            .rev()
+            .filter(|entry| query.agent.as_ref().map_or(true, |agent| &entry.agent == agent))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    pub async fn get(&self, id: Uuid) -> Option<TraceEntry> {
+        self.entries.lock().await.iter().find(|entry| entry.id == id).cloned()
+    }
+
+    fn redact(&self, text: &str) -> String {
+        match &self.redactor {
+            Some(redactor) => redactor.redact(text),
+            None => text.to_string(),
+        }
+    }
+
+    /// Redact every string in a JSON value, keeping its structure replayable
+    fn redact_value(&self, value: Value) -> Value {
+        if self.redactor.is_none() {
+            return value;
+        }
+        match value {
+            Value::String(s) => Value::String(self.redact(&s)),
+            Value::Array(items) => Value::Array(items.into_iter().map(|v| self.redact_value(v)).collect()),
+            Value::Object(map) => Value::Object(
+                map.into_iter().map(|(k, v)| (k, self.redact_value(v))).collect(),
+            ),
+            other => other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::SecurityConfig;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_trace_log_is_bounded_and_filtered() {
+        let log = TraceLog::new(2, true);
+        for agent in ["a", "b", "a"] {
+            log.record(agent, &Value::Null, &Ok(Value::String(agent.into())), Duration::ZERO, false).await;
+        }
+
+        let all = log.query(&TraceQuery::default()).await;
+        let agents: Vec<&str> = all.iter().map(|e| e.agent.as_str()).collect();
+        assert_eq!(agents, vec!["a", "b"]);
+
+        let only_a = log.query(&TraceQuery { agent: Some("a".into()), limit: None }).await;
+        assert_eq!(only_a.len(), 1);
+        assert_eq!(log.get(only_a[0].id).await.unwrap().agent, "a");
+
+        log.set_enabled(false);
+        log.record("c", &Value::Null, &Err(anyhow::anyhow!("boom")), Duration::ZERO, false).await;
+        assert!(log.query(&TraceQuery { agent: Some("c".into()), limit: None }).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_trace_log_redacts_inputs_and_outputs() {
+        let config = SecurityConfig {
+            enable_output_redaction: true,
+            output_redaction_patterns: vec!["sk-[a-z0-9]+".to_string()],
+            ..SecurityConfig::default()
+        };
+        let redactor = OutputRedactor::from_config(&config).unwrap().map(Arc::new);
+        let log = TraceLog::new(10, true).with_redactor(redactor);
+
+        let input = serde_json::json!({"key": "sk-abc123", "n": 1, "list": ["sk-def456"]});
+        log.record("a", &input, &Ok(Value::String("used sk-abc123".into())), Duration::ZERO, false).await;
+
+        let entry = &log.query(&TraceQuery::default()).await[0];
+        let stored = entry.input.to_string();
+        assert!(!stored.contains("sk-abc123") && !stored.contains("sk-def456"));
+        assert_eq!(entry.input["n"], 1);
+        assert!(!entry.output.as_ref().unwrap().contains("sk-abc123"));
+    }
+}
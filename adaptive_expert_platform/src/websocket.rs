@@ -2,24 +2,29 @@
 
 use anyhow::{anyhow, Result};
 use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use tokio::sync::{RwLock, mpsc, broadcast};
 use uuid::Uuid;
 use serde::{Deserialize, Serialize};
 use dashmap::DashMap;
+use futures::{SinkExt, StreamExt};
+use tokio_util::sync::CancellationToken;
 use tracing::{info, warn, error, instrument, debug};
 
 use axum::{
     extract::{
-        ws::{Message, WebSocket, WebSocketUpgrade},
-        Query, State, Path,
+        ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, Query, State, Path,
     },
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
 };
 use axum_extra::extract::cookie::{CookieJar, Cookie};
 
+use crate::orchestrator::Caller;
+
 /// WebSocket connection information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebSocketConnection {
@@ -31,6 +36,27 @@ pub struct WebSocketConnection {
     pub client_info: ClientInfo,
     pub subscriptions: Vec<String>,
     pub metadata: HashMap<String, serde_json::Value>,
+    /// Who agent requests on this connection are made for; until the client
+    /// authenticates they are refused
+    #[serde(skip)]
+    pub caller: Option<Caller>,
+}
+
+impl WebSocketConnection {
+    /// Copy safe to show operators: the session id and any metadata that
+    /// looks like a credential are replaced
+    pub fn redacted(&self) -> Self {
+        let mut connection = self.clone();
+        if connection.session_id.is_some() {
+            connection.session_id = Some("[REDACTED]".to_string());
+        }
+        for (key, value) in connection.metadata.iter_mut() {
+            if crate::redaction::is_secret_key(key) {
+                *value = serde_json::Value::String("[REDACTED]".to_string());
+            }
+        }
+        connection
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,6 +85,7 @@ pub enum WebSocketMessage {
     AgentRequest(AgentRequestPayload),
     AgentResponse(AgentResponsePayload),
     AgentStatus(AgentStatusPayload),
+    CancelRequest(CancelRequestPayload),
     
     // Real-time events
     TaskUpdate(TaskUpdatePayload),
@@ -119,6 +146,11 @@ pub struct AgentRequestPayload {
     pub timeout_seconds: Option<u64>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CancelRequestPayload {
+    pub request_id: Uuid,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentResponsePayload {
     pub request_id: Uuid,
@@ -187,17 +219,48 @@ pub struct ErrorPayload {
     pub details: Option<serde_json::Value>,
 }
 
-/// WebSocket server configuration
+/// What to do when a channel broadcast finds a connection's outgoing queue full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackpressurePolicy {
+    /// Keep the newest broadcasts, discarding the oldest still queued
+    DropOldest,
+    /// Discard the broadcast that did not fit
+    DropNewest,
+    /// Discard, and close the connection once its queue has stayed full for
+    /// `slow_consumer_timeout_ms`
+    DisconnectSlow,
+}
+
+/// Error code sent before closing a connection that exceeded `max_message_size`
+pub const MESSAGE_TOO_LARGE: &str = "MESSAGE_TOO_LARGE";
+
+/// WebSocket server configuration, read from the `[websocket]` settings
+/// section; omitted fields keep their defaults
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct WebSocketConfig {
     pub max_connections: usize,
     pub max_message_size: usize,
     pub ping_interval_seconds: u64,
     pub connection_timeout_seconds: u64,
     pub enable_compression: bool,
+    /// Require a valid token before a connection may send agent requests;
+    /// when off, every connection acts as the anonymous caller
     pub enable_authentication: bool,
     pub rate_limit_messages_per_minute: u32,
     pub max_subscriptions_per_connection: usize,
+    /// Proxies whose forwarded headers are believed for the client address
+    pub trusted_proxies: Vec<IpAddr>,
+    /// Forwarded headers consulted, in order, when the peer is a trusted
+    /// proxy; supports `X-Forwarded-For` and `X-Real-IP`
+    pub trusted_forwarded_headers: Vec<String>,
+    /// Messages buffered per connection before backpressure applies
+    pub outbound_queue_size: usize,
+    /// Policy for channels without an entry in `channel_backpressure`
+    pub broadcast_backpressure: BackpressurePolicy,
+    pub channel_backpressure: HashMap<String, BackpressurePolicy>,
+    pub slow_consumer_timeout_ms: u64,
 }
 
 impl Default for WebSocketConfig {
@@ -211,6 +274,12 @@ impl Default for WebSocketConfig {
             enable_authentication: true,
             rate_limit_messages_per_minute: 100,
             max_subscriptions_per_connection: 50,
+            trusted_proxies: Vec::new(),
+            trusted_forwarded_headers: vec!["X-Forwarded-For".to_string()],
+            outbound_queue_size: 1_000,
+            broadcast_backpressure: BackpressurePolicy::DropNewest,
+            channel_backpressure: HashMap::new(),
+            slow_consumer_timeout_ms: 5_000,
         }
     }
 }
@@ -223,9 +292,51 @@ pub struct WebSocketServer {
     subscriptions: Arc<DashMap<String, Vec<Uuid>>>, // channel -> connection_ids
     message_broadcaster: broadcast::Sender<(String, WebSocketMessage)>,
     stats: Arc<RwLock<WebSocketStats>>,
+    in_flight: Arc<DashMap<Uuid, InFlightRequest>>, // request_id -> owner and cancel token
+    /// Runs agent requests; until one is set they are refused
+    agent_handler: parking_lot::RwLock<Option<Arc<dyn AgentRequestHandler>>>,
+    outboxes: Arc<DashMap<Uuid, Outbox>>,
+    /// Set by [`WebSocketServer::close_all`]; new upgrades are refused
+    closing: Arc<std::sync::atomic::AtomicBool>,
 }
 
-#[derive(Debug, Default, Serialize)]
+/// Broadcast delivery state for one connection
+struct Outbox {
+    /// Drop-oldest broadcasts; a lagging receiver skips the oldest entries
+    lossy: broadcast::Sender<WebSocketMessage>,
+    /// When a broadcast first found the queue full, cleared on the next success
+    full_since: Option<std::time::Instant>,
+    dropped: u64,
+    /// Cancelled to close a slow connection
+    disconnect: CancellationToken,
+}
+
+/// Receiving ends of a connection's outgoing queues
+struct OutboxReceivers {
+    messages: mpsc::Receiver<WebSocketMessage>,
+    lossy: broadcast::Receiver<WebSocketMessage>,
+    disconnect: CancellationToken,
+}
+
+/// Agent request still running for a connection. Whoever removes it from
+/// `in_flight` (the finished request or a cancel) sends the final response.
+struct InFlightRequest {
+    connection_id: Uuid,
+    cancel: CancellationToken,
+}
+
+/// Runs the agent requests clients send over their connections
+#[async_trait::async_trait]
+pub trait AgentRequestHandler: Send + Sync {
+    /// Who a client presenting `token` acts as; fails for invalid tokens
+    fn authenticate(&self, token: &str) -> Result<Caller>;
+
+    /// Run `agent` on `input` for `caller`, stopping early when `cancel` is
+    /// triggered. The output is ready to send to the client.
+    async fn run(&self, caller: &Caller, agent: &str, input: serde_json::Value, cancel: CancellationToken) -> Result<serde_json::Value>;
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct WebSocketStats {
     pub total_connections: u64,
     pub active_connections: usize,
@@ -236,6 +347,8 @@ pub struct WebSocketStats {
     pub subscription_count: usize,
     pub error_count: u64,
     pub average_latency_ms: f64,
+    /// Broadcasts not delivered because of backpressure, per connection
+    pub dropped_messages: HashMap<Uuid, u64>,
 }
 
 impl WebSocketServer {
@@ -250,9 +363,18 @@ impl WebSocketServer {
             subscriptions: Arc::new(DashMap::new()),
             message_broadcaster,
             stats: Arc::new(RwLock::new(WebSocketStats::default())),
+            in_flight: Arc::new(DashMap::new()),
+            agent_handler: parking_lot::RwLock::new(None),
+            outboxes: Arc::new(DashMap::new()),
+            closing: Arc::new(std::sync::atomic::AtomicBool::new(false)),
         }
     }
 
+    /// Run agent requests from clients with `handler`
+    pub fn set_agent_handler(&self, handler: Arc<dyn AgentRequestHandler>) {
+        *self.agent_handler.write() = Some(handler);
+    }
+
     /// Start the WebSocket server
     #[instrument(skip(self))]
     pub async fn start(&self) -> Result<()> {
@@ -267,54 +389,135 @@ impl WebSocketServer {
         Ok(())
     }
 
-    /// Handle WebSocket upgrade
-    #[instrument(skip(self, ws))]
+    /// Handle WebSocket upgrade. Frames and messages over `max_message_size`
+    /// are refused by the transport before they are buffered whole.
+    #[instrument(skip(self, ws, headers))]
     pub async fn handle_upgrade(
         &self,
         ws: WebSocketUpgrade,
         query: Query<HashMap<String, String>>,
         cookies: CookieJar,
+        headers: HeaderMap,
+        connect_info: Option<ConnectInfo<SocketAddr>>,
     ) -> impl IntoResponse {
         // Extract authentication info
         let auth_token = query.get("token")
             .or_else(|| cookies.get("auth_token").map(|c| c.value()))
             .map(|s| s.to_string());
 
+        if self.closing.load(std::sync::atomic::Ordering::Relaxed) {
+            return (StatusCode::SERVICE_UNAVAILABLE, "Server is shutting down").into_response();
+        }
+
         // Check connection limits
         if self.connections.len() >= self.config.max_connections {
             return (StatusCode::TOO_MANY_REQUESTS, "Connection limit exceeded").into_response();
         }
 
+        let client_info = self.client_info(connect_info.map(|ConnectInfo(addr)| addr), &headers);
+
         // Upgrade to WebSocket
-        ws.on_upgrade(move |socket| self.handle_connection(socket, auth_token))
+        ws.max_message_size(self.config.max_message_size)
+            .max_frame_size(self.config.max_message_size)
+            .on_upgrade(move |socket| self.handle_connection(socket, auth_token, client_info))
+    }
+
+    /// Describe the client from the peer address and request headers
+    fn client_info(&self, peer: Option<SocketAddr>, headers: &HeaderMap) -> ClientInfo {
+        let ip_address = self.client_ip(peer, headers)
+            .map(|ip| ip.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        ClientInfo {
+            user_agent: headers.get(header::USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string()),
+            ip_address,
+            platform: None,
+            version: None,
+        }
+    }
+
+    /// The peer address, or the address a trusted proxy forwarded for
+    fn client_ip(&self, peer: Option<SocketAddr>, headers: &HeaderMap) -> Option<IpAddr> {
+        let peer = peer.map(|addr| addr.ip());
+        let behind_trusted_proxy = peer.map_or(false, |ip| self.config.trusted_proxies.contains(&ip));
+        if !behind_trusted_proxy {
+            return peer;
+        }
+
+        for name in &self.config.trusted_forwarded_headers {
+            let Some(value) = headers.get(name.as_str()).and_then(|v| v.to_str().ok()) else {
+                continue;
+            };
+            let forwarded = if name.eq_ignore_ascii_case("x-forwarded-for") {
+                // Each proxy appends; the nearest untrusted hop is the client
+                let hops: Vec<IpAddr> = value.split(',')
+                    .filter_map(|hop| hop.trim().parse().ok())
+                    .collect();
+                hops.iter().rev()
+                    .find(|ip| !self.config.trusted_proxies.contains(ip))
+                    .or_else(|| hops.first())
+                    .copied()
+            } else {
+                value.trim().parse().ok()
+            };
+            if forwarded.is_some() {
+                return forwarded;
+            }
+        }
+        peer
     }
 
     /// Handle new WebSocket connection
-    async fn handle_connection(&self, socket: WebSocket, auth_token: Option<String>) {
+    async fn handle_connection(&self, socket: WebSocket, auth_token: Option<String>, client_info: ClientInfo) {
+        let (ws_sender, ws_receiver) = socket.split();
+        self.serve_connection(ws_sender, ws_receiver, auth_token, client_info).await;
+    }
+
+    /// Run one connection until the client leaves or is disconnected.
+    ///
+    /// The transport is any sink of outgoing frames plus stream of incoming
+    /// ones: the upgraded socket's halves in production, in-memory channels
+    /// in tests, so message handling can be exercised without a real socket.
+    async fn serve_connection<S, R, E>(
+        &self,
+        ws_sender: S,
+        mut ws_receiver: R,
+        auth_token: Option<String>,
+        client_info: ClientInfo,
+    )
+    where
+        S: futures::Sink<Message> + Unpin + Send + 'static,
+        S::Error: std::fmt::Display,
+        R: futures::Stream<Item = std::result::Result<Message, E>> + Unpin,
+        E: std::fmt::Display,
+    {
         let connection_id = Uuid::new_v4();
-        let (ws_sender, mut ws_receiver) = socket.split();
-        let (msg_sender, mut msg_receiver) = mpsc::channel::<WebSocketMessage>(1000);
+        let (msg_sender, receivers) = self.open_outbox(connection_id);
+        let OutboxReceivers { messages: mut msg_receiver, lossy: mut lossy_receiver, disconnect } = receivers;
 
-        // Create connection info
+        // Create connection info; without authentication every connection
+        // acts as the anonymous caller
         let connection = WebSocketConnection {
             connection_id,
-            user_id: None, // Would be set after authentication
+            user_id: None,
             session_id: None,
             connected_at: SystemTime::now(),
             last_activity: SystemTime::now(),
-            client_info: ClientInfo {
-                user_agent: None,
-                ip_address: "127.0.0.1".to_string(), // Would be extracted from headers
-                platform: None,
-                version: None,
-            },
+            client_info,
             subscriptions: Vec::new(),
             metadata: HashMap::new(),
+            caller: (!self.config.enable_authentication).then(Caller::default),
         };
 
         // Store connection
         self.connections.insert(connection_id, connection);
-        self.connection_handlers.insert(connection_id, msg_sender.clone());
+        // A token from the upgrade request authenticates the connection up front
+        if let Some(token) = auth_token {
+            if let Err(e) = self.authenticate_connection(connection_id, &token) {
+                warn!("WebSocket connection {} presented an invalid token: {}", connection_id, e);
+            }
+        }
 
         // Update stats
         {
@@ -330,9 +533,26 @@ impl WebSocketServer {
         let sender_task = {
             let ws_sender = ws_sender.clone();
             let stats = self.stats.clone();
+            let outboxes = self.outboxes.clone();
             
             tokio::spawn(async move {
-                while let Some(message) = msg_receiver.recv().await {
+                loop {
+                    let message = tokio::select! {
+                        message = msg_receiver.recv() => match message {
+                            Some(message) => message,
+                            None => break,
+                        },
+                        message = lossy_receiver.recv() => match message {
+                            Ok(message) => message,
+                            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                                if let Some(mut outbox) = outboxes.get_mut(&connection_id) {
+                                    outbox.dropped += skipped;
+                                }
+                                continue;
+                            }
+                            Err(broadcast::error::RecvError::Closed) => break,
+                        },
+                    };
                     let json_message = match serde_json::to_string(&message) {
                         Ok(json) => json,
                         Err(e) => {
@@ -364,7 +584,17 @@ impl WebSocketServer {
         let message_broadcaster = self.message_broadcaster.clone();
         let stats = self.stats.clone();
 
-        while let Some(msg) = ws_receiver.next().await {
+        loop {
+            let msg = tokio::select! {
+                msg = ws_receiver.next() => match msg {
+                    Some(msg) => msg,
+                    None => break,
+                },
+                _ = disconnect.cancelled() => {
+                    info!("Closing WebSocket connection: {}", connection_id);
+                    break;
+                }
+            };
             match msg {
                 Ok(Message::Text(text)) => {
                     // Update activity timestamp
@@ -373,18 +603,16 @@ impl WebSocketServer {
                     }
 
                     // Parse and handle message
-                    match serde_json::from_str::<WebSocketMessage>(&text) {
+                    match self.parse_text_message(&text) {
                         Ok(ws_message) => {
                             self.handle_message(connection_id, ws_message, &msg_sender).await;
                         }
-                        Err(e) => {
-                            error!("Failed to parse WebSocket message: {}", e);
-                            let error_msg = WebSocketMessage::Error(ErrorPayload {
-                                error_code: "PARSE_ERROR".to_string(),
-                                message: "Invalid message format".to_string(),
-                                details: None,
-                            });
-                            let _ = msg_sender.send(error_msg).await;
+                        Err(error) if error.error_code == MESSAGE_TOO_LARGE => {
+                            self.reject_oversized(connection_id, &*ws_sender, error).await;
+                            break;
+                        }
+                        Err(error) => {
+                            let _ = msg_sender.send(WebSocketMessage::Error(error)).await;
                         }
                     }
 
@@ -393,7 +621,11 @@ impl WebSocketServer {
                     stats.messages_received += 1;
                     stats.bytes_received += text.len() as u64;
                 }
-                Ok(Message::Binary(_)) => {
+                Ok(Message::Binary(data)) => {
+                    if let Err(error) = self.check_message_size(data.len()) {
+                        self.reject_oversized(connection_id, &*ws_sender, error).await;
+                        break;
+                    }
                     // Handle binary messages if needed
                 }
                 Ok(Message::Close(_)) => {
@@ -421,6 +653,59 @@ impl WebSocketServer {
         self.cleanup_connection(connection_id).await;
     }
 
+    /// Reject frames over `max_message_size` before they are parsed
+    fn check_message_size(&self, len: usize) -> std::result::Result<(), ErrorPayload> {
+        if len > self.config.max_message_size {
+            return Err(ErrorPayload {
+                error_code: MESSAGE_TOO_LARGE.to_string(),
+                message: format!(
+                    "Message of {} bytes exceeds the {} byte limit",
+                    len, self.config.max_message_size
+                ),
+                details: None,
+            });
+        }
+        Ok(())
+    }
+
+    /// Size-check and parse a text frame
+    fn parse_text_message(&self, text: &str) -> std::result::Result<WebSocketMessage, ErrorPayload> {
+        self.check_message_size(text.len())?;
+        serde_json::from_str::<WebSocketMessage>(text).map_err(|e| {
+            error!("Failed to parse WebSocket message: {}", e);
+            ErrorPayload {
+                error_code: "PARSE_ERROR".to_string(),
+                message: "Invalid message format".to_string(),
+                details: None,
+            }
+        })
+    }
+
+    /// Tell the client why and close the connection. Sent directly rather
+    /// than through the outgoing queue so it is not lost when the sender task
+    /// is aborted.
+    async fn reject_oversized<S>(
+        &self,
+        connection_id: Uuid,
+        ws_sender: &tokio::sync::Mutex<S>,
+        error: ErrorPayload,
+    )
+    where
+        S: futures::Sink<Message> + Unpin,
+    {
+        warn!("Closing WebSocket connection {}: {}", connection_id, error.message);
+        self.stats.write().await.error_count += 1;
+
+        let mut sender = ws_sender.lock().await;
+        if let Ok(json) = serde_json::to_string(&WebSocketMessage::Error(error)) {
+            let _ = sender.send(Message::Text(json)).await;
+        }
+        let _ = sender.send(Message::Close(Some(CloseFrame {
+            code: close_code::SIZE,
+            reason: "message too large".into(),
+        }))).await;
+    }
+
     /// Handle WebSocket message
     async fn handle_message(
         &self,
@@ -441,6 +726,9 @@ impl WebSocketServer {
             WebSocketMessage::AgentRequest(payload) => {
                 self.handle_agent_request(connection_id, payload, sender).await;
             }
+            WebSocketMessage::CancelRequest(payload) => {
+                self.handle_cancel_request(connection_id, payload, sender).await;
+            }
             WebSocketMessage::BroadcastMessage(payload) => {
                 self.handle_broadcast(payload).await;
             }
@@ -463,16 +751,24 @@ impl WebSocketServer {
         payload: ConnectPayload,
         sender: &mpsc::Sender<WebSocketMessage>,
     ) {
-        // Update connection info
+        if let Some(token) = payload.auth_token {
+            if let Err(e) = self.authenticate_connection(connection_id, &token) {
+                warn!("WebSocket connection {} failed to authenticate: {}", connection_id, e);
+                let _ = sender.send(WebSocketMessage::Error(ErrorPayload {
+                    error_code: "AUTH_FAILED".to_string(),
+                    message: "Invalid authentication token".to_string(),
+                    details: None,
+                })).await;
+                return;
+            }
+        }
+
+        // Update connection info. The address and user agent were taken from
+        // the upgrade request; only self-reported details come from the client.
         if let Some(mut conn) = self.connections.get_mut(&connection_id) {
-            conn.client_info = payload.client_info;
+            conn.client_info.platform = payload.client_info.platform;
+            conn.client_info.version = payload.client_info.version;
             conn.session_id = payload.session_id;
-            
-            // Authenticate if token provided
-            if let Some(token) = payload.auth_token {
-                // Implement authentication logic here
-                conn.user_id = Some("authenticated_user".to_string());
-            }
         }
 
         // Send confirmation
@@ -490,6 +786,19 @@ impl WebSocketServer {
         let _ = sender.send(response).await;
     }
 
+    /// Validate `token` with the agent handler and record who the
+    /// connection acts as
+    fn authenticate_connection(&self, connection_id: Uuid, token: &str) -> Result<()> {
+        let handler = self.agent_handler.read().clone()
+            .ok_or_else(|| anyhow!("No agent handler to validate tokens"))?;
+        let caller = handler.authenticate(token)?;
+        if let Some(mut conn) = self.connections.get_mut(&connection_id) {
+            conn.user_id = caller.user.clone();
+            conn.caller = Some(caller);
+        }
+        Ok(())
+    }
+
     /// Handle subscription
     async fn handle_subscribe(
         &self,
@@ -557,39 +866,114 @@ impl WebSocketServer {
     /// Handle agent request
     async fn handle_agent_request(
         &self,
-        _connection_id: Uuid,
+        connection_id: Uuid,
         payload: AgentRequestPayload,
         sender: &mpsc::Sender<WebSocketMessage>,
     ) {
-        // This would integrate with the orchestrator to execute agent tasks
-        // For now, return a mock response
-        
-        let response = WebSocketMessage::AgentResponse(AgentResponsePayload {
+        let Some(handler) = self.agent_handler.read().clone() else {
+            let _ = sender.send(WebSocketMessage::Error(ErrorPayload {
+                error_code: "AGENTS_UNAVAILABLE".to_string(),
+                message: "Agent requests are not served on this connection".to_string(),
+                details: None,
+            })).await;
+            return;
+        };
+        let Some(caller) = self.connections.get(&connection_id).and_then(|conn| conn.caller.clone()) else {
+            let _ = sender.send(WebSocketMessage::Error(ErrorPayload {
+                error_code: "UNAUTHENTICATED".to_string(),
+                message: "Authenticate the connection before sending agent requests".to_string(),
+                details: None,
+            })).await;
+            return;
+        };
+
+        let cancel = CancellationToken::new();
+        match self.in_flight.entry(payload.request_id) {
+            dashmap::mapref::entry::Entry::Occupied(_) => {
+                let _ = sender.send(WebSocketMessage::Error(ErrorPayload {
+                    error_code: "DUPLICATE_REQUEST".to_string(),
+                    message: format!("Request {} is already in flight", payload.request_id),
+                    details: None,
+                })).await;
+                return;
+            }
+            dashmap::mapref::entry::Entry::Vacant(entry) => {
+                entry.insert(InFlightRequest { connection_id, cancel: cancel.clone() });
+            }
+        }
+
+        // Run off the receive loop so a later CancelRequest can be processed
+        let in_flight = self.in_flight.clone();
+        let sender = sender.clone();
+        tokio::spawn(async move {
+            let work = handler.run(&caller, &payload.agent_name, payload.input, cancel.clone());
+            let result = match payload.timeout_seconds {
+                Some(seconds) => tokio::time::timeout(Duration::from_secs(seconds), work)
+                    .await
+                    .unwrap_or_else(|_| {
+                        cancel.cancel();
+                        Err(anyhow!("Request timed out after {}s", seconds))
+                    }),
+                None => work.await,
+            };
+
+            // A cancel that removed the request first has already answered it
+            if in_flight.remove(&payload.request_id).is_none() {
+                return;
+            }
+            let (success, response, error) = match result {
+                Ok(output) => (true, Some(output), None),
+                Err(e) => (false, None, Some(e.to_string())),
+            };
+            let _ = sender.send(WebSocketMessage::AgentResponse(AgentResponsePayload {
+                request_id: payload.request_id,
+                success,
+                response,
+                error,
+                partial: false,
+                final_response: true,
+            })).await;
+        });
+    }
+
+    /// Handle cancellation of an in-flight agent request
+    async fn handle_cancel_request(
+        &self,
+        connection_id: Uuid,
+        payload: CancelRequestPayload,
+        sender: &mpsc::Sender<WebSocketMessage>,
+    ) {
+        // Connections may only cancel their own requests
+        let removed = self.in_flight.remove_if(&payload.request_id, |_, request| {
+            request.connection_id == connection_id
+        });
+
+        let Some((_, request)) = removed else {
+            let _ = sender.send(WebSocketMessage::Error(ErrorPayload {
+                error_code: "UNKNOWN_REQUEST".to_string(),
+                message: format!("No in-flight request {}", payload.request_id),
+                details: None,
+            })).await;
+            return;
+        };
+
+        request.cancel.cancel();
+        info!("Cancelled agent request {} for connection {}", payload.request_id, connection_id);
+
+        let _ = sender.send(WebSocketMessage::AgentResponse(AgentResponsePayload {
             request_id: payload.request_id,
-            success: true,
-            response: Some(serde_json::json!({
-                "message": "Agent request processed",
-                "agent": payload.agent_name
-            })),
-            error: None,
+            success: false,
+            response: None,
+            error: Some("Request cancelled".to_string()),
             partial: false,
             final_response: true,
-        });
-
-        let _ = sender.send(response).await;
+        })).await;
     }
 
     /// Handle broadcast message
     async fn handle_broadcast(&self, payload: BroadcastPayload) {
-        if let Some(subscribers) = self.subscriptions.get(&payload.channel) {
-            let message = WebSocketMessage::BroadcastMessage(payload);
-            
-            for &connection_id in subscribers.iter() {
-                if let Some(sender) = self.connection_handlers.get(&connection_id) {
-                    let _ = sender.send(message.clone()).await;
-                }
-            }
-        }
+        let channel = payload.channel.clone();
+        self.broadcast_to_channel(&channel, WebSocketMessage::BroadcastMessage(payload)).await;
     }
 
     /// Handle direct message
@@ -630,15 +1014,64 @@ impl WebSocketServer {
 
     /// Broadcast message to channel
     pub async fn broadcast_to_channel(&self, channel: &str, message: WebSocketMessage) {
-        if let Some(subscribers) = self.subscriptions.get(channel) {
-            for &connection_id in subscribers.iter() {
-                if let Some(sender) = self.connection_handlers.get(&connection_id) {
-                    let _ = sender.send(message.clone()).await;
+        let subscribers = match self.subscriptions.get(channel) {
+            Some(subscribers) => subscribers.clone(),
+            None => return,
+        };
+        let policy = self.config.channel_backpressure.get(channel)
+            .copied()
+            .unwrap_or(self.config.broadcast_backpressure);
+        for connection_id in subscribers {
+            self.deliver(connection_id, &message, policy);
+        }
+    }
+
+    /// Queue a broadcast for one connection without waiting on a slow client
+    fn deliver(&self, connection_id: Uuid, message: &WebSocketMessage, policy: BackpressurePolicy) {
+        let Some(sender) = self.connection_handlers.get(&connection_id).map(|s| s.clone()) else {
+            return;
+        };
+        let Some(mut outbox) = self.outboxes.get_mut(&connection_id) else {
+            return;
+        };
+
+        if policy == BackpressurePolicy::DropOldest {
+            // Evictions are counted by the connection's sender task
+            let _ = outbox.lossy.send(message.clone());
+            return;
+        }
+
+        match sender.try_send(message.clone()) {
+            Ok(()) => outbox.full_since = None,
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                outbox.dropped += 1;
+                let full_since = *outbox.full_since.get_or_insert_with(std::time::Instant::now);
+                if policy == BackpressurePolicy::DisconnectSlow
+                    && full_since.elapsed() >= Duration::from_millis(self.config.slow_consumer_timeout_ms)
+                {
+                    warn!("Disconnecting slow WebSocket consumer: {}", connection_id);
+                    outbox.disconnect.cancel();
                 }
             }
+            Err(mpsc::error::TrySendError::Closed(_)) => {}
         }
     }
 
+    /// Create the outgoing queues for a new connection
+    fn open_outbox(&self, connection_id: Uuid) -> (mpsc::Sender<WebSocketMessage>, OutboxReceivers) {
+        let (sender, messages) = mpsc::channel(self.config.outbound_queue_size);
+        let (lossy, lossy_receiver) = broadcast::channel(self.config.outbound_queue_size);
+        let disconnect = CancellationToken::new();
+        self.connection_handlers.insert(connection_id, sender.clone());
+        self.outboxes.insert(connection_id, Outbox {
+            lossy,
+            full_since: None,
+            dropped: 0,
+            disconnect: disconnect.clone(),
+        });
+        (sender, OutboxReceivers { messages, lossy: lossy_receiver, disconnect })
+    }
+
     /// Send message to specific connection
     pub async fn send_to_connection(&self, connection_id: Uuid, message: WebSocketMessage) -> Result<()> {
         if let Some(sender) = self.connection_handlers.get(&connection_id) {
@@ -648,11 +1081,28 @@ impl WebSocketServer {
         Ok(())
     }
 
+    /// Refuse new connections, cancel running agent requests and close every
+    /// open connection, returning how many were closed
+    pub fn close_all(&self) -> usize {
+        self.closing.store(true, std::sync::atomic::Ordering::Relaxed);
+        for request in self.in_flight.iter() {
+            request.cancel.cancel();
+        }
+        for outbox in self.outboxes.iter() {
+            outbox.disconnect.cancel();
+        }
+        self.outboxes.len()
+    }
+
     /// Get WebSocket statistics
     pub async fn get_stats(&self) -> WebSocketStats {
         let mut stats = self.stats.read().await.clone();
         stats.active_connections = self.connections.len();
         stats.subscription_count = self.subscriptions.len();
+        stats.dropped_messages = self.outboxes.iter()
+            .filter(|entry| entry.dropped > 0)
+            .map(|entry| (*entry.key(), entry.dropped))
+            .collect();
         stats
     }
 
@@ -678,6 +1128,17 @@ impl WebSocketServer {
 
         // Remove connection handler
         self.connection_handlers.remove(&connection_id);
+        self.outboxes.remove(&connection_id);
+
+        // Abort requests nobody is listening for anymore
+        self.in_flight.retain(|_, request| {
+            if request.connection_id == connection_id {
+                request.cancel.cancel();
+                false
+            } else {
+                true
+            }
+        });
 
         // Update stats
         let mut stats = self.stats.write().await;
@@ -719,6 +1180,7 @@ impl WebSocketServer {
     async fn start_cleanup_task(&self) {
         let connections = self.connections.clone();
         let connection_handlers = self.connection_handlers.clone();
+        let outboxes = self.outboxes.clone();
         let timeout_duration = Duration::from_secs(self.config.connection_timeout_seconds);
 
         tokio::spawn(async move {
@@ -743,6 +1205,7 @@ impl WebSocketServer {
                 for connection_id in inactive_connections {
                     connections.remove(&connection_id);
                     connection_handlers.remove(&connection_id);
+                    outboxes.remove(&connection_id);
                     info!("Removed inactive WebSocket connection: {}", connection_id);
                 }
             }
@@ -770,3 +1233,436 @@ impl WebSocketServer {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oversized_message_rejected_before_parse() {
+        let server = WebSocketServer::new(WebSocketConfig {
+            max_message_size: 16,
+            ..WebSocketConfig::default()
+        });
+
+        // Not valid JSON either, so a parse attempt would report PARSE_ERROR
+        let error = server.parse_text_message(&"x".repeat(17)).unwrap_err();
+        assert_eq!(error.error_code, MESSAGE_TOO_LARGE);
+        assert!(server.check_message_size(17).is_err());
+
+        let error = server.parse_text_message("not json").unwrap_err();
+        assert_eq!(error.error_code, "PARSE_ERROR");
+        assert!(server.check_message_size(16).is_ok());
+    }
+
+    #[test]
+    fn test_client_info_honors_trusted_proxies_only() {
+        let proxy: SocketAddr = "10.0.0.1:4000".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(header::USER_AGENT, "test-client/1.0".parse().unwrap());
+        headers.insert("X-Forwarded-For", "203.0.113.7, 10.0.0.2".parse().unwrap());
+        headers.insert("X-Real-IP", "198.51.100.1".parse().unwrap());
+
+        let untrusted = WebSocketServer::new(WebSocketConfig::default());
+        let info = untrusted.client_info(Some(proxy), &headers);
+        assert_eq!(info.ip_address, "10.0.0.1");
+        assert_eq!(info.user_agent.as_deref(), Some("test-client/1.0"));
+
+        let trusted = WebSocketServer::new(WebSocketConfig {
+            trusted_proxies: vec!["10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()],
+            ..WebSocketConfig::default()
+        });
+        assert_eq!(trusted.client_info(Some(proxy), &headers).ip_address, "203.0.113.7");
+
+        let real_ip = WebSocketServer::new(WebSocketConfig {
+            trusted_proxies: vec!["10.0.0.1".parse().unwrap()],
+            trusted_forwarded_headers: vec!["X-Real-IP".to_string()],
+            ..WebSocketConfig::default()
+        });
+        assert_eq!(real_ip.client_info(Some(proxy), &headers).ip_address, "198.51.100.1");
+        assert_eq!(real_ip.client_info(None, &headers).ip_address, "unknown");
+    }
+
+    fn subscribed_server(policy: BackpressurePolicy, queue_size: usize) -> (WebSocketServer, Uuid, OutboxReceivers) {
+        let server = WebSocketServer::new(WebSocketConfig {
+            outbound_queue_size: queue_size,
+            channel_backpressure: HashMap::from([("news".to_string(), policy)]),
+            slow_consumer_timeout_ms: 0,
+            ..WebSocketConfig::default()
+        });
+        let connection_id = Uuid::new_v4();
+        let (_, receivers) = server.open_outbox(connection_id);
+        server.subscriptions.insert("news".to_string(), vec![connection_id]);
+        (server, connection_id, receivers)
+    }
+
+    fn ping(sequence: u64) -> WebSocketMessage {
+        WebSocketMessage::Ping(PingPayload { timestamp: 0, sequence })
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_backpressure_policies() {
+        let (server, id, mut receivers) = subscribed_server(BackpressurePolicy::DropNewest, 1);
+        for sequence in 1..=3 {
+            server.broadcast_to_channel("news", ping(sequence)).await;
+        }
+        assert!(matches!(receivers.messages.try_recv(), Ok(WebSocketMessage::Ping(p)) if p.sequence == 1));
+        assert!(receivers.messages.try_recv().is_err());
+        assert_eq!(server.get_stats().await.dropped_messages[&id], 2);
+        assert!(!receivers.disconnect.is_cancelled());
+
+        let (server, _, receivers) = subscribed_server(BackpressurePolicy::DisconnectSlow, 1);
+        server.broadcast_to_channel("news", ping(1)).await;
+        server.broadcast_to_channel("news", ping(2)).await;
+        assert!(receivers.disconnect.is_cancelled());
+
+        let (server, _, mut receivers) = subscribed_server(BackpressurePolicy::DropOldest, 2);
+        for sequence in 1..=3 {
+            server.broadcast_to_channel("news", ping(sequence)).await;
+        }
+        assert!(matches!(receivers.lossy.recv().await, Err(broadcast::error::RecvError::Lagged(1))));
+        assert!(matches!(receivers.lossy.recv().await, Ok(WebSocketMessage::Ping(p)) if p.sequence == 2));
+    }
+
+    /// Client end of a connection served over in-memory channels
+    struct TestClient {
+        inbound: futures::channel::mpsc::UnboundedSender<std::result::Result<Message, std::convert::Infallible>>,
+        outbound: futures::channel::mpsc::UnboundedReceiver<Message>,
+        connection: tokio::task::JoinHandle<()>,
+    }
+
+    impl TestClient {
+        fn connect(server: Arc<WebSocketServer>) -> Self {
+            Self::connect_with_token(server, None)
+        }
+
+        /// Connect presenting `token` on the upgrade request
+        fn connect_with_token(server: Arc<WebSocketServer>, token: Option<&str>) -> Self {
+            let (inbound, incoming) = futures::channel::mpsc::unbounded();
+            let (outgoing, outbound) = futures::channel::mpsc::unbounded();
+            let client_info = server.client_info(None, &HeaderMap::new());
+            let token = token.map(str::to_string);
+            let connection = tokio::spawn(async move {
+                server.serve_connection(outgoing, incoming, token, client_info).await;
+            });
+            Self { inbound, outbound, connection }
+        }
+
+        fn send(&self, message: WebSocketMessage) {
+            let text = serde_json::to_string(&message).unwrap();
+            self.inbound.unbounded_send(Ok(Message::Text(text))).unwrap();
+        }
+
+        /// Next message sent to the client, failing the test after a second
+        async fn recv(&mut self) -> WebSocketMessage {
+            loop {
+                let frame = tokio::time::timeout(Duration::from_secs(1), self.outbound.next())
+                    .await
+                    .expect("no message within a second")
+                    .expect("connection closed");
+                if let Message::Text(text) = frame {
+                    return serde_json::from_str(&text).unwrap();
+                }
+            }
+        }
+
+        /// Close the client side and wait for the server to clean up
+        async fn disconnect(self) {
+            drop(self.inbound);
+            self.connection.await.unwrap();
+        }
+    }
+
+    fn subscribe(channels: &[&str]) -> WebSocketMessage {
+        WebSocketMessage::Subscribe(SubscribePayload {
+            channels: channels.iter().map(|c| c.to_string()).collect(),
+            filters: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_subscribe_and_unsubscribe() {
+        let server = Arc::new(WebSocketServer::new(WebSocketConfig {
+            max_subscriptions_per_connection: 2,
+            ..WebSocketConfig::default()
+        }));
+        let mut client = TestClient::connect(server.clone());
+
+        client.send(subscribe(&["news", "alerts", "sports"]));
+        assert!(matches!(client.recv().await,
+            WebSocketMessage::Error(e) if e.error_code == "SUBSCRIPTION_LIMIT"));
+        match client.recv().await {
+            WebSocketMessage::Subscribe(confirmed) => assert_eq!(confirmed.channels, vec!["news", "alerts"]),
+            other => panic!("expected subscription confirmation, got {:?}", other),
+        }
+
+        // Broadcasts reach subscribers through the connection's outbox
+        server.broadcast_to_channel("news", ping(7)).await;
+        assert!(matches!(client.recv().await, WebSocketMessage::Ping(p) if p.sequence == 7));
+
+        client.send(WebSocketMessage::Unsubscribe(UnsubscribePayload { channels: vec!["news".to_string()] }));
+        // Unsubscribe is not acknowledged; a ping round trip orders it
+        client.send(ping(1));
+        assert!(matches!(client.recv().await, WebSocketMessage::Pong(_)));
+        assert!(!server.subscriptions.contains_key("news"));
+        assert_eq!(server.get_connections().await[0].subscriptions, vec!["alerts"]);
+
+        client.disconnect().await;
+        assert!(server.get_connections().await.is_empty());
+        assert!(server.subscriptions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_ping_reports_latency() {
+        let server = Arc::new(WebSocketServer::new(WebSocketConfig::default()));
+        let mut client = TestClient::connect(server);
+
+        let sent_at = SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64 - 250;
+        client.send(WebSocketMessage::Ping(PingPayload { timestamp: sent_at, sequence: 42 }));
+        match client.recv().await {
+            WebSocketMessage::Pong(pong) => {
+                assert_eq!(pong.sequence, 42);
+                assert!(pong.latency_ms >= 250);
+                assert_eq!(pong.timestamp - sent_at, pong.latency_ms);
+            }
+            other => panic!("expected pong, got {:?}", other),
+        }
+        client.disconnect().await;
+    }
+
+    /// Echoes its input, except "slow", which runs until cancelled
+    struct EchoHandler;
+
+    #[async_trait::async_trait]
+    impl AgentRequestHandler for EchoHandler {
+        fn authenticate(&self, token: &str) -> Result<Caller> {
+            match token {
+                "valid" => Ok(Caller { user: Some("alice".to_string()), tenant: Some("acme".to_string()) }),
+                _ => Err(anyhow!("Token validation failed")),
+            }
+        }
+
+        async fn run(&self, caller: &Caller, agent: &str, input: serde_json::Value, cancel: CancellationToken) -> Result<serde_json::Value> {
+            if agent == "slow" {
+                cancel.cancelled().await;
+                return Err(crate::agent::CancelledError.into());
+            }
+            Ok(serde_json::json!({ "agent": agent, "input": input, "user": caller.user, "tenant": caller.tenant }))
+        }
+    }
+
+    fn connect_message(token: &str) -> WebSocketMessage {
+        WebSocketMessage::Connect(ConnectPayload {
+            client_info: ClientInfo {
+                user_agent: None,
+                ip_address: "127.0.0.1".to_string(),
+                platform: None,
+                version: None,
+            },
+            auth_token: Some(token.to_string()),
+            session_id: None,
+        })
+    }
+
+    fn agent_request(request_id: Uuid, agent: &str) -> WebSocketMessage {
+        WebSocketMessage::AgentRequest(AgentRequestPayload {
+            request_id,
+            agent_name: agent.to_string(),
+            input: serde_json::json!("hi"),
+            stream_response: false,
+            timeout_seconds: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_agent_request_and_cancel() {
+        use crate::agent::{EchoAgent, HashEmbeddingAgent, LengthRerankAgent};
+        use crate::memory::{redis_store::InMemoryEmbeddingCache, Memory};
+        use crate::orchestrator::{Orchestrator, WebSocketAgentHandler};
+
+        let memory = Arc::new(Memory::new(
+            Arc::new(HashEmbeddingAgent::new(384)),
+            Arc::new(LengthRerankAgent::new()),
+            Arc::new(InMemoryEmbeddingCache::new()),
+        ));
+        let orchestrator = Arc::new(RwLock::new(
+            Orchestrator::new(&crate::settings::Settings::default(), memory).await.unwrap(),
+        ));
+        orchestrator.read().await.register_agent("echo".to_string(), Arc::new(EchoAgent::new())).await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let auth_manager = Arc::new(crate::auth::AuthManager::new("test_secret".to_string(), dir.path().to_str().unwrap()).unwrap());
+        auth_manager.add_user("alice".to_string(), "password", vec!["user".to_string()]).unwrap();
+        let token = auth_manager.authenticate("alice", "password").unwrap();
+        let server = orchestrator.read().await.websocket();
+        server.set_agent_handler(Arc::new(WebSocketAgentHandler::new(
+            &orchestrator,
+            auth_manager,
+            None,
+            crate::settings::Settings::default(),
+        )));
+        let mut client = TestClient::connect(server.clone());
+
+        // Agent requests wait for the connection to authenticate
+        client.send(agent_request(Uuid::new_v4(), "echo"));
+        assert!(matches!(client.recv().await,
+            WebSocketMessage::Error(e) if e.error_code == "UNAUTHENTICATED"));
+        client.send(connect_message(&token));
+        assert!(matches!(client.recv().await, WebSocketMessage::Connect(_)));
+
+        let request_id = Uuid::new_v4();
+        client.send(agent_request(request_id, "echo"));
+        match client.recv().await {
+            WebSocketMessage::AgentResponse(response) => {
+                assert_eq!(response.request_id, request_id);
+                assert!(response.success && response.final_response);
+                assert_eq!(response.response, Some(serde_json::json!("Echo: \"hi\"")));
+            }
+            other => panic!("expected agent response, got {:?}", other),
+        }
+
+        // Failures of the dispatch come back as unsuccessful responses
+        client.send(agent_request(Uuid::new_v4(), "missing"));
+        match client.recv().await {
+            WebSocketMessage::AgentResponse(response) => {
+                assert!(!response.success && response.final_response);
+                assert!(response.error.is_some());
+            }
+            other => panic!("expected agent response, got {:?}", other),
+        }
+
+        // The request already finished, so there is nothing to cancel
+        client.send(WebSocketMessage::CancelRequest(CancelRequestPayload { request_id }));
+        assert!(matches!(client.recv().await,
+            WebSocketMessage::Error(e) if e.error_code == "UNKNOWN_REQUEST"));
+
+        client.inbound.unbounded_send(Ok(Message::Text("not json".to_string()))).unwrap();
+        assert!(matches!(client.recv().await,
+            WebSocketMessage::Error(e) if e.error_code == "PARSE_ERROR"));
+        client.disconnect().await;
+        assert_eq!(server.get_stats().await.messages_received, 6);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_request_gets_one_final_response() {
+        let server = Arc::new(WebSocketServer::new(WebSocketConfig::default()));
+        server.set_agent_handler(Arc::new(EchoHandler));
+        let mut client = TestClient::connect_with_token(server.clone(), Some("valid"));
+
+        let request_id = Uuid::new_v4();
+        client.send(agent_request(request_id, "slow"));
+        client.send(agent_request(request_id, "echo"));
+        assert!(matches!(client.recv().await,
+            WebSocketMessage::Error(e) if e.error_code == "DUPLICATE_REQUEST"));
+
+        client.send(WebSocketMessage::CancelRequest(CancelRequestPayload { request_id }));
+        match client.recv().await {
+            WebSocketMessage::AgentResponse(response) => {
+                assert_eq!(response.request_id, request_id);
+                assert!(!response.success);
+                assert_eq!(response.error.as_deref(), Some("Request cancelled"));
+            }
+            other => panic!("expected agent response, got {:?}", other),
+        }
+
+        // The cancelled agent's own result is never delivered
+        client.send(WebSocketMessage::Ping(PingPayload { timestamp: 0, sequence: 7 }));
+        assert!(matches!(client.recv().await, WebSocketMessage::Pong(pong) if pong.sequence == 7));
+        client.disconnect().await;
+    }
+
+    #[tokio::test]
+    async fn test_agent_requests_run_as_the_authenticated_caller() {
+        let server = Arc::new(WebSocketServer::new(WebSocketConfig::default()));
+        server.set_agent_handler(Arc::new(EchoHandler));
+        let mut client = TestClient::connect(server.clone());
+
+        client.send(connect_message("forged"));
+        assert!(matches!(client.recv().await,
+            WebSocketMessage::Error(e) if e.error_code == "AUTH_FAILED"));
+        client.send(agent_request(Uuid::new_v4(), "echo"));
+        assert!(matches!(client.recv().await,
+            WebSocketMessage::Error(e) if e.error_code == "UNAUTHENTICATED"));
+
+        client.send(connect_message("valid"));
+        assert!(matches!(client.recv().await, WebSocketMessage::Connect(_)));
+        client.send(agent_request(Uuid::new_v4(), "echo"));
+        match client.recv().await {
+            WebSocketMessage::AgentResponse(response) => {
+                let output = response.response.unwrap();
+                assert_eq!(output["user"], "alice");
+                assert_eq!(output["tenant"], "acme");
+            }
+            other => panic!("expected agent response, got {:?}", other),
+        }
+        let connection = server.get_connections().await.into_iter().next().unwrap();
+        assert_eq!(connection.user_id.as_deref(), Some("alice"));
+        client.disconnect().await;
+    }
+
+    #[tokio::test]
+    async fn test_connect_keeps_server_derived_client_info() {
+        let server = Arc::new(WebSocketServer::new(WebSocketConfig::default()));
+        server.set_agent_handler(Arc::new(EchoHandler));
+        let mut client = TestClient::connect(server.clone());
+
+        client.send(WebSocketMessage::Connect(ConnectPayload {
+            client_info: ClientInfo {
+                user_agent: Some("spoofed-agent".to_string()),
+                ip_address: "10.0.0.1".to_string(),
+                platform: Some("ios".to_string()),
+                version: Some("2.3.0".to_string()),
+            },
+            auth_token: Some("valid".to_string()),
+            session_id: None,
+        }));
+        assert!(matches!(client.recv().await, WebSocketMessage::Connect(_)));
+
+        let info = server.get_connections().await.into_iter().next().unwrap().client_info;
+        assert_eq!(info.ip_address, "unknown");
+        assert_eq!(info.user_agent, None);
+        assert_eq!(info.platform.as_deref(), Some("ios"));
+        assert_eq!(info.version.as_deref(), Some("2.3.0"));
+        client.disconnect().await;
+    }
+
+    #[tokio::test]
+    async fn test_agent_requests_refused_without_handler() {
+        let server = Arc::new(WebSocketServer::new(WebSocketConfig::default()));
+        let mut client = TestClient::connect(server.clone());
+
+        client.send(agent_request(Uuid::new_v4(), "echo"));
+        assert!(matches!(client.recv().await,
+            WebSocketMessage::Error(e) if e.error_code == "AGENTS_UNAVAILABLE"));
+        client.disconnect().await;
+    }
+
+    #[test]
+    fn test_connection_redaction() {
+        let server = WebSocketServer::new(WebSocketConfig::default());
+        let connection = WebSocketConnection {
+            connection_id: Uuid::new_v4(),
+            user_id: Some("alice".to_string()),
+            session_id: Some("session-secret".to_string()),
+            connected_at: SystemTime::now(),
+            last_activity: SystemTime::now(),
+            client_info: server.client_info(None, &HeaderMap::new()),
+            subscriptions: vec!["alerts".to_string()],
+            metadata: HashMap::from([
+                ("auth_token".to_string(), serde_json::json!("abc")),
+                ("API_Key".to_string(), serde_json::json!("xyz")),
+                ("locale".to_string(), serde_json::json!("en")),
+            ]),
+            caller: None,
+        };
+
+        let redacted = connection.redacted();
+        assert_eq!(redacted.session_id.as_deref(), Some("[REDACTED]"));
+        assert_eq!(redacted.metadata["auth_token"], "[REDACTED]");
+        assert_eq!(redacted.metadata["API_Key"], "[REDACTED]");
+        assert_eq!(redacted.metadata["locale"], "en");
+        assert_eq!(redacted.user_id.as_deref(), Some("alice"));
+    }
+}
@@ -6,10 +6,10 @@ use adaptive_expert_platform::memory::Memory;
 use adaptive_expert_platform::plugin::PluginRegistrar;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use dashmap::DashMap;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Instant;
 use tracing::{info, warn, debug};
@@ -56,14 +56,24 @@ impl State {
     }
 }
 
+/// Per-episode progress, updated together under one lock
+#[derive(Debug, Default)]
+struct Episode {
+    /// Previous state and the action taken from it
+    last: Option<(State, usize)>,
+    steps: u64,
+    total_reward: f64,
+}
+
 /// Simple Q-Learning agent implementation.
+///
+/// The Q-table is sharded so concurrent steps only contend on the entries
+/// they touch. Each step snapshots the config once and never holds more than
+/// one lock at a time.
 pub struct QLearningAgent {
-    config: Mutex<QLearningConfig>,
-    q_table: Mutex<HashMap<(State, usize), f64>>,
-    last_state: Mutex<Option<State>>,
-    last_action: Mutex<Option<usize>>,
-    steps: Mutex<u64>,
-    total_reward: Mutex<f64>,
+    config: RwLock<QLearningConfig>,
+    q_table: DashMap<(State, usize), f64>,
+    episode: Mutex<Episode>,
     request_count: AtomicU64,
     error_count: AtomicU64,
     start_time: Instant,
@@ -72,12 +82,9 @@ pub struct QLearningAgent {
 impl QLearningAgent {
     pub fn new() -> Self {
         Self {
-            config: Mutex::new(QLearningConfig::default()),
-            q_table: Mutex::new(HashMap::new()),
-            last_state: Mutex::new(None),
-            last_action: Mutex::new(None),
-            steps: Mutex::new(0),
-            total_reward: Mutex::new(0.0),
+            config: RwLock::new(QLearningConfig::default()),
+            q_table: DashMap::new(),
+            episode: Mutex::new(Episode::default()),
             request_count: AtomicU64::new(0),
             error_count: AtomicU64::new(0),
             start_time: Instant::now(),
@@ -87,26 +94,22 @@ impl QLearningAgent {
     /// Load configuration from JSON string
     fn load_config(&self, config_json: &str) -> Result<()> {
         let config: QLearningConfig = serde_json::from_str(config_json)?;
-        *self.config.lock().unwrap() = config.clone();
+        *self.config.write().unwrap() = config.clone();
         info!(?config, "Q-Learning config loaded");
         Ok(())
     }
 
-    /// Get Q-value for state-action pair
-    fn get_q_value(&self, state: &State, action: usize) -> f64 {
-        let q_table = self.q_table.lock().unwrap();
-        *q_table.get(&(state.clone(), action)).unwrap_or(&0.0)
+    fn config(&self) -> QLearningConfig {
+        self.config.read().unwrap().clone()
     }
 
-    /// Set Q-value for state-action pair
-    fn set_q_value(&self, state: State, action: usize, value: f64) {
-        let mut q_table = self.q_table.lock().unwrap();
-        q_table.insert((state, action), value);
+    /// Get Q-value for state-action pair
+    fn get_q_value(&self, state: &State, action: usize) -> f64 {
+        self.q_table.get(&(state.clone(), action)).map_or(0.0, |q| *q)
     }
 
     /// Choose action using epsilon-greedy strategy
-    fn choose_action(&self, state: &State) -> usize {
-        let config = self.config.lock().unwrap();
+    fn choose_action(&self, config: &QLearningConfig, state: &State) -> usize {
         let mut rng = rand::thread_rng();
 
         // Epsilon-greedy action selection
@@ -126,82 +129,79 @@ impl QLearningAgent {
     }
 
     /// Update Q-value using Q-learning update rule
-    fn update_q_value(&self, state: State, action: usize, reward: f64, next_state: &State) {
-        let config = self.config.lock().unwrap();
-
-        // Find maximum Q-value for next state
+    fn update_q_value(&self, config: &QLearningConfig, state: State, action: usize, reward: f64, next_state: &State) {
+        // Find maximum Q-value for next state before taking the entry, which
+        // may live in the same shard
         let max_next_q = (0..config.action_count)
             .map(|a| self.get_q_value(next_state, a))
             .fold(f64::NEG_INFINITY, f64::max);
 
         // Q-learning update: Q(s,a) = Q(s,a) + α[r + γ max Q(s',a') - Q(s,a)]
-        let current_q = self.get_q_value(&state, action);
+        let values = state.values.clone();
+        let mut q = self.q_table.entry((state, action)).or_insert(0.0);
+        let current_q = *q;
         let target = reward + config.discount_factor * max_next_q;
         let new_q = current_q + config.learning_rate * (target - current_q);
-
-        self.set_q_value(state, action, new_q);
+        *q = new_q;
 
         debug!("Q-update: state={:?}, action={}, reward={:.3}, current_q={:.3}, new_q={:.3}",
-               state.values, action, reward, current_q, new_q);
+               values, action, reward, current_q, new_q);
     }
 
-    /// Decay epsilon for reduced exploration over time
-    fn decay_epsilon(&self) {
-        let mut config = self.config.lock().unwrap();
+    /// Decay epsilon for reduced exploration over time, returning the new value
+    fn decay_epsilon(&self) -> f64 {
+        let mut config = self.config.write().unwrap();
         config.epsilon = (config.epsilon * config.epsilon_decay).max(config.min_epsilon);
+        config.epsilon
     }
 
     /// Process a step in the environment
     fn step(&self, observation: Vec<f64>, reward: f64) -> Result<serde_json::Value> {
+        let config = self.config();
         let state = State::from_observation(&observation);
-        let action = self.choose_action(&state);
+        let action = self.choose_action(&config, &state);
+
+        // Record the transition and update metrics in one critical section
+        let (previous, steps, total_reward) = {
+            let mut episode = self.episode.lock().unwrap();
+            let previous = episode.last.replace((state.clone(), action));
+            episode.steps += 1;
+            episode.total_reward += reward;
+            (previous, episode.steps, episode.total_reward)
+        };
 
         // Update Q-value if we have a previous state-action pair
-        if let (Some(last_state), Some(last_action)) = (
-            self.last_state.lock().unwrap().clone(),
-            *self.last_action.lock().unwrap()
-        ) {
-            self.update_q_value(last_state, last_action, reward, &state);
+        if let Some((last_state, last_action)) = previous {
+            self.update_q_value(&config, last_state, last_action, reward, &state);
         }
 
-        // Update state tracking
-        *self.last_state.lock().unwrap() = Some(state.clone());
-        *self.last_action.lock().unwrap() = Some(action);
-
-        // Update metrics
-        {
-            let mut steps = self.steps.lock().unwrap();
-            *steps += 1;
-
-            let mut total_reward = self.total_reward.lock().unwrap();
-            *total_reward += reward;
-
-            if *steps % 100 == 0 {
-                info!("Step {}: Total reward={:.2}, Epsilon={:.3}",
-                      *steps, *total_reward, self.config.lock().unwrap().epsilon);
-            }
+        if steps % 100 == 0 {
+            info!("Step {}: Total reward={:.2}, Epsilon={:.3}", steps, total_reward, config.epsilon);
         }
 
-        self.decay_epsilon();
+        let epsilon = self.decay_epsilon();
 
         Ok(serde_json::json!({
             "action": action,
             "q_value": self.get_q_value(&state, action),
-            "epsilon": self.config.lock().unwrap().epsilon,
-            "steps": *self.steps.lock().unwrap()
+            "epsilon": epsilon,
+            "steps": steps
         }))
     }
 
     /// Get agent statistics
     fn get_stats(&self) -> serde_json::Value {
-        let q_table = self.q_table.lock().unwrap();
-        let config = self.config.lock().unwrap();
+        let config = self.config();
+        let (steps, total_reward) = {
+            let episode = self.episode.lock().unwrap();
+            (episode.steps, episode.total_reward)
+        };
 
         serde_json::json!({
-            "steps": *self.steps.lock().unwrap(),
-            "total_reward": *self.total_reward.lock().unwrap(),
+            "steps": steps,
+            "total_reward": total_reward,
             "epsilon": config.epsilon,
-            "q_table_size": q_table.len(),
+            "q_table_size": self.q_table.len(),
             "learning_rate": config.learning_rate,
             "discount_factor": config.discount_factor
         })
@@ -254,10 +254,7 @@ impl Agent for QLearningAgent {
                 Ok(serde_json::to_string(&stats)?)
             }
             Some("reset") => {
-                *self.last_state.lock().unwrap() = None;
-                *self.last_action.lock().unwrap() = None;
-                *self.steps.lock().unwrap() = 0;
-                *self.total_reward.lock().unwrap() = 0.0;
+                *self.episode.lock().unwrap() = Episode::default();
                 info!("Agent reset");
                 Ok("Agent reset successfully".to_string())
             }
@@ -340,6 +337,29 @@ mod tests {
         assert!(response.get("epsilon").is_some());
     }
 
+    #[test]
+    fn test_qlearning_concurrent_steps() {
+        let agent = Arc::new(QLearningAgent::new());
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let agent = agent.clone();
+                std::thread::spawn(move || {
+                    for i in 0..50 {
+                        agent.step(vec![t as f64, (i % 5) as f64, 0.0, 0.0], 1.0).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let stats = agent.get_stats();
+        assert_eq!(stats["steps"], 200);
+        assert_eq!(stats["total_reward"], 200.0);
+        assert!(stats["q_table_size"].as_u64().unwrap() > 0);
+    }
+
     fn create_dummy_memory() -> adaptive_expert_platform::memory::Memory {
         use adaptive_expert_platform::memory::redis_store::InMemoryEmbeddingCache;
         use adaptive_expert_platform::agent::EchoAgent;
@@ -7,7 +7,7 @@
 /// ```
 /// Evaluates `code` inside a sandboxed Julia environment and returns its string representation.
 
-use adaptive_expert_platform::agent::{Agent, AgentHealth};
+use adaptive_expert_platform::agent::{extract_text_input, Agent, AgentHealth};
 use adaptive_expert_platform::memory::Memory;
 use adaptive_expert_platform::plugin::PluginRegistrar;
 use anyhow::{anyhow, Result};
@@ -279,15 +279,7 @@ impl Agent for JuliaAgent {
     #[instrument(skip(self, input, _memory), fields(code_length))]
     async fn handle(&self, input: Value, _memory: Arc<Memory>) -> Result<String> {
         // Parse input structure
-        let code = match &input {
-            Value::Object(obj) => {
-                obj.get("code")
-                    .and_then(|v| v.as_str())
-                    .ok_or_else(|| anyhow!("Expected JSON {{\"code\": \"…\"}}"))?
-            }
-            Value::String(s) => s.as_str(),
-            _ => return Err(anyhow!("Expected string or object with 'code' field")),
-        }.to_owned();
+        let code = extract_text_input(&input, "code")?;
 
         tracing::Span::current().record("code_length", code.len());
 
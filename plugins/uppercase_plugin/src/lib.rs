@@ -7,10 +7,10 @@
 //! { "action": "uppercase_many", "texts": ["foo", "bar"] }
 //! ```
 
-use adaptive_expert_platform::agent::{Agent, AgentHealth};
+use adaptive_expert_platform::agent::{extract_text_input, Agent, AgentHealth};
 use adaptive_expert_platform::memory::Memory;
 use adaptive_expert_platform::plugin::PluginRegistrar;
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use async_trait::async_trait;
 use serde::Deserialize;
 use std::sync::Arc;
@@ -57,13 +57,10 @@ impl Agent for UppercaseAgent {
 
     async fn handle(&self, input: serde_json::Value, _memory: Arc<Memory>) -> Result<String> {
         // Handle both structured JSON and simple string inputs
-        let request = if let Ok(req) = serde_json::from_value::<Request>(input.clone()) {
-            req
-        } else if let Some(text) = input.as_str() {
-            // Fallback for simple string input
-            Request::Uppercase { text: text.to_string() }
-        } else {
-            return Err(anyhow!("Invalid input format. Expected JSON with 'action' field or simple string"));
+        let request = match serde_json::from_value::<Request>(input.clone()) {
+            Ok(req) => req,
+            // Fallback for simple string input or an object without an action
+            Err(_) => Request::Uppercase { text: extract_text_input(&input, "text")? },
         };
 
         let result = self.process(request);